@@ -9,7 +9,7 @@ use goose_mcp::{
 
 use crate::commands::acp::run_acp_agent;
 use crate::commands::bench::agent_generator;
-use crate::commands::configure::handle_configure;
+use crate::commands::configure::{handle_config_schema, handle_config_validate, handle_configure};
 use crate::commands::info::handle_info;
 use crate::commands::project::{handle_project_default, handle_projects_interactive};
 use crate::commands::recipe::{handle_deeplink, handle_list, handle_open, handle_validate};
@@ -19,8 +19,8 @@ use crate::commands::term::{
 
 use crate::commands::schedule::{
     handle_schedule_add, handle_schedule_cron_help, handle_schedule_list, handle_schedule_remove,
-    handle_schedule_run_now, handle_schedule_services_status, handle_schedule_services_stop,
-    handle_schedule_sessions,
+    handle_schedule_run_now, handle_schedule_runs, handle_schedule_services_status,
+    handle_schedule_services_stop, handle_schedule_sessions,
 };
 use crate::commands::session::{handle_session_list, handle_session_remove};
 use crate::recipes::extract_from_cli::extract_recipe_info_from_cli;
@@ -263,7 +263,15 @@ enum SchedulerCommand {
             help = "Cron expression for the schedule",
             long_help = "Cron expression for when to run the job. Examples:\n  '0 * * * *'     - Every hour at minute 0\n  '0 */2 * * *'   - Every 2 hours\n  '@hourly'       - Every hour (shorthand)\n  '0 9 * * *'     - Every day at 9:00 AM\n  '0 9 * * 1'     - Every Monday at 9:00 AM\n  '0 0 1 * *'     - First day of every month at midnight"
         )]
-        cron: String,
+        cron: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "cron",
+            required_unless_present = "cron",
+            help = "Run the job once at this time instead of on a cron schedule",
+            long_help = "Run the job once at this time instead of on a cron schedule, as RFC 3339 (2025-06-01T09:00:00Z) or local time (2025-06-01 09:00)"
+        )]
+        at: Option<String>,
         #[arg(
             long,
             help = "Recipe source (path to file, or base64 encoded recipe string)"
@@ -290,6 +298,14 @@ enum SchedulerCommand {
         #[arg(short = 'l', long, help = "Maximum number of sessions to return")]
         limit: Option<usize>,
     },
+    /// List the runs of a schedule and how they went
+    #[command(about = "List the runs of a schedule and how they went")]
+    Runs {
+        #[arg(long = "schedule-id", alias = "id", help = "ID of the schedule")]
+        schedule_id: String,
+        #[arg(short = 'l', long, help = "Maximum number of runs to show")]
+        limit: Option<usize>,
+    },
     #[command(about = "Run a scheduled job immediately")]
     RunNow {
         /// ID of the schedule to run
@@ -361,6 +377,20 @@ pub enum BenchCommand {
     },
 }
 
+#[derive(Subcommand)]
+enum ConfigureCommand {
+    /// Print the JSON Schema of config.yaml
+    #[command(about = "Print the JSON Schema of config.yaml, for editors and setup tools")]
+    Schema {},
+
+    /// Check the configuration for mistakes
+    #[command(about = "Check the configuration for unknown keys, bad values and missing settings")]
+    Validate {
+        #[arg(long, help = "Print the problems as JSON")]
+        json: bool,
+    },
+}
+
 #[derive(Subcommand)]
 enum RecipeCommand {
     /// Validate a recipe file
@@ -431,7 +461,10 @@ enum RecipeCommand {
 enum Command {
     /// Configure goose settings
     #[command(about = "Configure goose settings")]
-    Configure {},
+    Configure {
+        #[command(subcommand)]
+        command: Option<ConfigureCommand>,
+    },
 
     /// Display goose configuration information
     #[command(about = "Display goose information")]
@@ -957,8 +990,10 @@ pub async fn cli() -> anyhow::Result<()> {
         warn!("Warning: Failed to update project tracker: {}", e);
     }
 
+    goose::config::remote::start().await;
+
     let command_name = match &cli.command {
-        Some(Command::Configure {}) => "configure",
+        Some(Command::Configure { .. }) => "configure",
         Some(Command::Info { .. }) => "info",
         Some(Command::Mcp { .. }) => "mcp",
         Some(Command::Acp {}) => "acp",
@@ -982,7 +1017,11 @@ pub async fn cli() -> anyhow::Result<()> {
     );
 
     match cli.command {
-        Some(Command::Configure {}) => handle_configure().await?,
+        Some(Command::Configure { command }) => match command {
+            None => handle_configure().await?,
+            Some(ConfigureCommand::Schema {}) => handle_config_schema().await?,
+            Some(ConfigureCommand::Validate { json }) => handle_config_validate(json).await?,
+        },
         Some(Command::Info { verbose }) => handle_info(verbose)?,
         Some(Command::Mcp { server }) => {
             let name = server.name();
@@ -1402,9 +1441,10 @@ pub async fn cli() -> anyhow::Result<()> {
                 SchedulerCommand::Add {
                     schedule_id,
                     cron,
+                    at,
                     recipe_source,
                 } => {
-                    handle_schedule_add(schedule_id, cron, recipe_source).await?;
+                    handle_schedule_add(schedule_id, cron, at, recipe_source).await?;
                 }
                 SchedulerCommand::List {} => {
                     handle_schedule_list().await?;
@@ -1416,6 +1456,9 @@ pub async fn cli() -> anyhow::Result<()> {
                     // New arm
                     handle_schedule_sessions(schedule_id, limit).await?;
                 }
+                SchedulerCommand::Runs { schedule_id, limit } => {
+                    handle_schedule_runs(schedule_id, limit).await?;
+                }
                 SchedulerCommand::RunNow { schedule_id } => {
                     // New arm
                     handle_schedule_run_now(schedule_id).await?;
@@ -266,6 +266,14 @@ impl GooseAcpAgent {
             toolshim: false,
             toolshim_model: None,
             fast_model: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            reasoning_effort: None,
+            thinking_budget_tokens: None,
+            seed: None,
+            logprobs: None,
+            response_schema: None,
+            additional_request_fields: None,
         };
         let provider = create(&provider_name, model_config).await?;
 
@@ -941,6 +949,14 @@ impl GooseAcpAgent {
             schedule_id: None,
             max_turns: None,
             retry_config: None,
+            moderation: None,
+            context_policy: None,
+            budget: None,
+            budget_session_id: None,
+            model_routing: None,
+            lead_worker: None,
+            reflection: None,
+            final_answer_schema: None,
         };
 
         let mut stream = self
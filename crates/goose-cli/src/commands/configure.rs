@@ -12,6 +12,7 @@ use goose::config::extensions::{
 };
 use goose::config::paths::Paths;
 use goose::config::permission::PermissionLevel;
+use goose::config::schema::{config_schema, validate_config, IssueSeverity};
 use goose::config::signup_tetrate::TetrateAuth;
 use goose::config::{
     configure_tetrate, Config, ConfigError, ExperimentManager, ExtensionEntry, GooseMode,
@@ -1250,6 +1251,11 @@ pub fn configure_goose_mode_dialog() -> anyhow::Result<()> {
             "Chat Mode",
             "Engage with the selected provider without using tools, extensions, or file modification"
         )
+        .item(
+            GooseMode::DryRun,
+            "Dry Run Mode",
+            "Tools that only read run; edits, shell commands and other changes are only reported"
+        )
         .interact()?;
 
     config.set_goose_mode(mode)?;
@@ -1258,6 +1264,7 @@ pub fn configure_goose_mode_dialog() -> anyhow::Result<()> {
         GooseMode::Approve => "Set to Approve Mode - all tools and modifications require approval",
         GooseMode::SmartApprove => "Set to Smart Approve Mode - modifications require approval",
         GooseMode::Chat => "Set to Chat Mode - no tools or modifications enabled",
+        GooseMode::DryRun => "Set to Dry Run Mode - modifications are reported, not made",
     };
     cliclack::outro(msg)?;
     Ok(())
@@ -1958,3 +1965,42 @@ fn print_config_file_saved() -> anyhow::Result<()> {
     ))?;
     Ok(())
 }
+
+pub async fn handle_config_schema() -> anyhow::Result<()> {
+    let schema = config_schema().await;
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+pub async fn handle_config_validate(json: bool) -> anyhow::Result<()> {
+    let config = Config::global();
+    let validation = validate_config(config).await?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&validation)?);
+    } else {
+        for issue in &validation.issues {
+            let marker = match issue.severity {
+                IssueSeverity::Error => style("✗").red().bold(),
+                IssueSeverity::Warning => style("!").yellow().bold(),
+            };
+            println!("{} {}: {}", marker, style(&issue.key).bold(), issue.message);
+        }
+    }
+
+    if !validation.is_valid() {
+        return Err(anyhow::anyhow!(
+            "{} configuration in {} has {} errors",
+            style("✗").red().bold(),
+            config.path(),
+            validation.errors().count()
+        ));
+    }
+    if !json {
+        println!(
+            "{} configuration in {} is valid",
+            style("✓").green().bold(),
+            config.path()
+        );
+    }
+    Ok(())
+}
@@ -1,7 +1,8 @@
 use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
 use goose::scheduler::{
-    get_default_scheduled_recipes_dir, get_default_scheduler_storage_path, ScheduledJob, Scheduler,
-    SchedulerError,
+    get_default_scheduled_recipes_dir, get_default_scheduler_storage_path, RunOutcome,
+    ScheduledJob, Scheduler, SchedulerError,
 };
 use std::path::Path;
 
@@ -63,17 +64,50 @@ fn validate_cron_expression(cron: &str) -> Result<()> {
     Ok(())
 }
 
+/// Parse the time a one-shot job runs at, given as RFC 3339 or as local time
+fn parse_run_at(at: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(at) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(at, format).ok())
+        .and_then(|time| Local.from_local_datetime(&time).single())
+        .map(|time| time.with_timezone(&Utc))
+        .with_context(|| {
+            format!(
+                "Invalid time '{}': use RFC 3339 (2025-06-01T09:00:00Z) or local time (2025-06-01 09:00)",
+                at
+            )
+        })
+}
+
 pub async fn handle_schedule_add(
     schedule_id: String,
-    cron: String,
+    cron: Option<String>,
+    at: Option<String>,
     recipe_source_arg: String, // This is expected to be a file path by the Scheduler
 ) -> Result<()> {
-    println!(
-        "[CLI Debug] Scheduling job ID: {}, Cron: {}, Recipe Source Path: {}",
-        schedule_id, cron, recipe_source_arg
-    );
-
-    validate_cron_expression(&cron)?;
+    let run_at = at.as_deref().map(parse_run_at).transpose()?;
+    let cron = cron.unwrap_or_default();
+    match run_at {
+        Some(run_at) => {
+            if run_at < Utc::now() {
+                println!("⚠️  {} is in the past; the job will run right away", run_at);
+            }
+            println!(
+                "[CLI Debug] Scheduling job ID: {}, Run at: {}, Recipe Source Path: {}",
+                schedule_id, run_at, recipe_source_arg
+            );
+        }
+        None => {
+            println!(
+                "[CLI Debug] Scheduling job ID: {}, Cron: {}, Recipe Source Path: {}",
+                schedule_id, cron, recipe_source_arg
+            );
+            validate_cron_expression(&cron)?;
+        }
+    }
 
     // The Scheduler's add_scheduled_job will handle copying the recipe from recipe_source_arg
     // to its internal storage and validating the path.
@@ -81,6 +115,7 @@ pub async fn handle_schedule_add(
         id: schedule_id.clone(),
         source: recipe_source_arg.clone(), // Pass the original user-provided path
         cron,
+        run_at,
         last_run: None,
         currently_running: false,
         paused: false,
@@ -154,11 +189,15 @@ pub async fn handle_schedule_list() -> Result<()> {
                 "⏹️  IDLE"
             };
 
+            let schedule = match job.run_at {
+                Some(run_at) => format!("Once at {}", run_at.to_rfc3339()),
+                None => format!("Cron: {}", job.cron),
+            };
             println!(
-                "- ID: {}\n  Status: {}\n  Cron: {}\n  Recipe Source (in store): {}\n  Last Run: {}",
+                "- ID: {}\n  Status: {}\n  {}\n  Recipe Source (in store): {}\n  Last Run: {}",
                 job.id,
                 status,
-                job.cron,
+                schedule,
                 job.source, // This source is now the path within scheduled_recipes_dir
                 job.last_run
                     .map_or_else(|| "Never".to_string(), |dt| dt.to_rfc3339())
@@ -231,6 +270,49 @@ pub async fn handle_schedule_sessions(schedule_id: String, limit: Option<usize>)
     Ok(())
 }
 
+pub async fn handle_schedule_runs(schedule_id: String, limit: Option<usize>) -> Result<()> {
+    let scheduler_storage_path =
+        get_default_scheduler_storage_path().context("Failed to get scheduler storage path")?;
+    let scheduler = Scheduler::new(scheduler_storage_path)
+        .await
+        .context("Failed to initialize scheduler")?;
+
+    let runs = match scheduler
+        .run_history(&schedule_id, limit.unwrap_or(20))
+        .await
+    {
+        Ok(runs) => runs,
+        Err(SchedulerError::JobNotFound(job_id)) => {
+            bail!("Error: Job with ID '{}' not found.", job_id);
+        }
+        Err(e) => bail!("Failed to get runs for schedule '{}': {:?}", schedule_id, e),
+    };
+    if runs.is_empty() {
+        println!("Schedule '{}' hasn't run yet.", schedule_id);
+        return Ok(());
+    }
+
+    println!("Runs of schedule '{}', most recent first:", schedule_id);
+    for run in runs {
+        let outcome = match &run.outcome {
+            RunOutcome::Completed => "✅ completed".to_string(),
+            RunOutcome::Failed { error } => format!("❌ failed: {}", error),
+            RunOutcome::Cancelled => "⏹️  cancelled".to_string(),
+        };
+        println!(
+            "  - {} ({:?}, {}s): {}{}",
+            run.started_at.to_rfc3339(),
+            run.trigger,
+            (run.finished_at - run.started_at).num_seconds(),
+            outcome,
+            run.session_id
+                .map(|id| format!(", session {}", id))
+                .unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
 pub async fn handle_schedule_run_now(schedule_id: String) -> Result<()> {
     let scheduler_storage_path =
         get_default_scheduler_storage_path().context("Failed to get scheduler storage path")?;
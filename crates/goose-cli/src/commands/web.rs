@@ -514,6 +514,14 @@ async fn process_message_streaming(
         schedule_id: None,
         max_turns: None,
         retry_config: None,
+        moderation: None,
+        context_policy: None,
+        budget: None,
+        budget_session_id: None,
+        model_routing: None,
+        lead_worker: None,
+        reflection: None,
+        final_answer_schema: None,
     };
 
     match agent.reply(user_message, session_config, None).await {
@@ -613,6 +621,21 @@ async fn process_message_streaming(
                     Ok(AgentEvent::ModelChange { model, mode }) => {
                         tracing::info!("Model changed to {} in {} mode", model, mode);
                     }
+                    Ok(AgentEvent::ToolTimeout {
+                        tool_name, timeout, ..
+                    }) => {
+                        tracing::warn!("Tool {} timed out after {:?}", tool_name, timeout);
+                    }
+                    Ok(AgentEvent::BudgetExceeded(exceeded)) => {
+                        tracing::info!("{}", exceeded);
+                    }
+                    Ok(AgentEvent::PlanUpdated(plan)) => {
+                        tracing::info!("Plan updated:\n{}", plan.checklist());
+                    }
+                    Ok(AgentEvent::FinalAnswer(answer)) => {
+                        tracing::debug!("Final answer: {}", answer);
+                    }
+                    Ok(AgentEvent::InterimUsage(_)) => {}
                     Err(e) => {
                         error!("Error in message stream: {}", e);
                         let mut sender = sender.lock().await;
@@ -8,7 +8,10 @@ use tracing_subscriber::{
     Registry,
 };
 
-use goose::tracing::{langfuse_layer, otlp_layer};
+use goose::redaction::RedactingMakeWriter;
+#[cfg(feature = "otel")]
+use goose::tracing::otlp_layer;
+use goose::tracing::{langfuse_layer, langfuse_middleware};
 use goose_bench::bench_session::BenchAgentError;
 use goose_bench::error_capture::ErrorCaptureLayer;
 
@@ -60,7 +63,7 @@ fn setup_logging_internal(
             let file_layer = fmt::layer()
                 .with_target(true)
                 .with_level(true)
-                .with_writer(file_appender)
+                .with_writer(RedactingMakeWriter::new(file_appender))
                 .with_ansi(false)
                 .json();
 
@@ -89,6 +92,7 @@ fn setup_logging_internal(
                 layers.push(ErrorCaptureLayer::new().boxed());
             }
 
+            #[cfg(feature = "otel")]
             if !force {
                 if let Ok((otlp_tracing_layer, otlp_metrics_layer, otlp_logs_layer)) =
                     otlp_layer::init_otlp()
@@ -111,8 +115,11 @@ fn setup_logging_internal(
                 }
             }
 
-            if let Some(langfuse) = langfuse_layer::create_langfuse_observer() {
-                layers.push(langfuse.with_filter(LevelFilter::DEBUG).boxed());
+            // LANGFUSE_EXPORT=generations reports provider calls instead of spans
+            if !langfuse_middleware::register_langfuse_middleware() {
+                if let Some(langfuse) = langfuse_layer::create_langfuse_observer() {
+                    layers.push(langfuse.with_filter(LevelFilter::DEBUG).boxed());
+                }
             }
 
             // Build the subscriber
@@ -9,6 +9,14 @@ async fn main() -> Result<()> {
 
     let result = cli().await;
 
+    #[cfg(feature = "otel")]
+    flush_otlp().await;
+
+    result
+}
+
+#[cfg(feature = "otel")]
+async fn flush_otlp() {
     // Only wait for telemetry flush if OTLP is configured
     let should_wait = goose::config::Config::global()
         .get_param::<String>("otel_exporter_otlp_endpoint")
@@ -32,6 +40,4 @@ async fn main() -> Result<()> {
 
         goose::tracing::shutdown_otlp();
     }
-
-    result
 }
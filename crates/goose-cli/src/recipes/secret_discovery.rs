@@ -58,6 +58,7 @@ fn extract_secrets_from_extensions(
             ExtensionConfig::Platform { name, .. } => (name, &Vec::new()),
             ExtensionConfig::Frontend { name, .. } => (name, &Vec::new()),
             ExtensionConfig::InlinePython { name, .. } => (name, &Vec::new()),
+            ExtensionConfig::Wasm { name, env_keys, .. } => (name, env_keys),
         };
 
         for key in env_keys {
@@ -172,6 +173,7 @@ mod tests {
             parameters: None,
             response: None,
             sub_recipes: None,
+            steps: None,
             retry: None,
         }
     }
@@ -215,6 +217,7 @@ mod tests {
             parameters: None,
             response: None,
             sub_recipes: None,
+            steps: None,
             retry: None,
         };
 
@@ -259,6 +262,7 @@ mod tests {
             parameters: None,
             response: None,
             sub_recipes: None,
+            steps: None,
             retry: None,
         };
 
@@ -311,6 +315,7 @@ mod tests {
             author: None,
             parameters: None,
             response: None,
+            steps: None,
             retry: None,
         };
 
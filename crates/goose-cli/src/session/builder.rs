@@ -634,6 +634,14 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
         schedule_id: None,
         max_turns: None,
         retry_config: None,
+        moderation: None,
+        context_policy: None,
+        budget: None,
+        budget_session_id: None,
+        model_routing: None,
+        lead_worker: None,
+        reflection: None,
+        final_answer_schema: None,
     };
 
     if let Err(e) = session
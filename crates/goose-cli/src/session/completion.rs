@@ -81,7 +81,7 @@ impl GooseCompleter {
 
     /// Complete flags for the /mode command
     fn complete_mode_flags(&self, line: &str) -> Result<(usize, Vec<Pair>)> {
-        let modes = ["auto", "approve", "smart_approve", "chat"];
+        let modes = ["auto", "approve", "smart_approve", "chat", "dry_run"];
 
         let parts: Vec<&str> = line.split_whitespace().collect();
 
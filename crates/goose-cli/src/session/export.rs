@@ -383,6 +383,16 @@ pub fn message_to_markdown(message: &Message, export_all_content: bool) -> Strin
                     image.data.chars().take(30).collect::<String>()
                 ));
             }
+            MessageContent::Audio(audio) => {
+                md.push_str(&format!("**Audio:** `(type: {})`\n\n", audio.mime_type));
+            }
+            MessageContent::Document(document) => {
+                md.push_str(&format!(
+                    "**Document:** `{}` (type: {})\n\n",
+                    document.name.as_deref().unwrap_or("unnamed"),
+                    document.mime_type
+                ));
+            }
             MessageContent::Thinking(thinking) => {
                 md.push_str("**Thinking:**\n");
                 md.push_str("> ");
@@ -10,6 +10,7 @@ pub enum InputResult {
     Exit,
     AddExtension(String),
     AddBuiltin(String),
+    RemoveExtension(String),
     ToggleTheme,
     SelectTheme(String),
     Retry,
@@ -21,6 +22,8 @@ pub enum InputResult {
     Clear,
     Recipe(Option<String>),
     Compact,
+    AttachResource { extension: String, uri: String },
+    Sandbox(bool),
 }
 
 #[derive(Debug)]
@@ -116,12 +119,15 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
     const CMD_PROMPT_WITH_SPACE: &str = "/prompt ";
     const CMD_EXTENSION: &str = "/extension ";
     const CMD_BUILTIN: &str = "/builtin ";
+    const CMD_REMOVE: &str = "/remove ";
     const CMD_MODE: &str = "/mode ";
     const CMD_PLAN: &str = "/plan";
     const CMD_ENDPLAN: &str = "/endplan";
     const CMD_CLEAR: &str = "/clear";
     const CMD_RECIPE: &str = "/recipe";
     const CMD_COMPACT: &str = "/compact";
+    const CMD_RESOURCE: &str = "/resource ";
+    const CMD_SANDBOX: &str = "/sandbox";
     const CMD_SUMMARIZE_DEPRECATED: &str = "/summarize";
 
     match input {
@@ -175,6 +181,9 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
         s if s.starts_with(CMD_BUILTIN) => Some(InputResult::AddBuiltin(
             s.get(CMD_BUILTIN.len()..).unwrap_or("").to_string(),
         )),
+        s if s.starts_with(CMD_REMOVE) => Some(InputResult::RemoveExtension(
+            s.get(CMD_REMOVE.len()..).unwrap_or("").trim().to_string(),
+        )),
         s if s.starts_with(CMD_MODE) => Some(InputResult::GooseMode(
             s.get(CMD_MODE.len()..).unwrap_or("").to_string(),
         )),
@@ -185,6 +194,29 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
         s if s == CMD_CLEAR => Some(InputResult::Clear),
         s if s.starts_with(CMD_RECIPE) => parse_recipe_command(s),
         s if s == CMD_COMPACT => Some(InputResult::Compact),
+        s if s.starts_with(CMD_RESOURCE) => {
+            let args = s.get(CMD_RESOURCE.len()..).unwrap_or("").trim();
+            match args.split_once(char::is_whitespace) {
+                Some((extension, uri)) if !uri.trim().is_empty() => {
+                    Some(InputResult::AttachResource {
+                        extension: extension.to_string(),
+                        uri: uri.trim().to_string(),
+                    })
+                }
+                _ => {
+                    println!("Usage: /resource <extension> <uri>");
+                    Some(InputResult::Retry)
+                }
+            }
+        }
+        s if s.starts_with(CMD_SANDBOX) => match s.get(CMD_SANDBOX.len()..).unwrap_or("").trim() {
+            "on" => Some(InputResult::Sandbox(true)),
+            "off" => Some(InputResult::Sandbox(false)),
+            _ => {
+                println!("Usage: /sandbox on|off");
+                Some(InputResult::Retry)
+            }
+        },
         s if s == CMD_SUMMARIZE_DEPRECATED => {
             println!("{}", console::style("⚠️  Note: /summarize has been renamed to /compact and will be removed in a future release.").yellow());
             Some(InputResult::Compact)
@@ -302,9 +334,10 @@ fn print_help() {
 /t <name> - Set theme directly (light, dark, ansi)
 /extension <command> - Add a stdio extension (format: ENV1=val1 command args...)
 /builtin <names> - Add builtin extensions by name (comma-separated)
+/remove <name> - Remove an extension from the session
 /prompts [--extension <name>] - List all available prompts, optionally filtered by extension
 /prompt <n> [--info] [key=value...] - Get prompt info or execute a prompt
-/mode <name> - Set the goose mode to use ('auto', 'approve', 'chat', 'smart_approve')
+/mode <name> - Set the goose mode to use ('auto', 'approve', 'chat', 'smart_approve', 'dry_run')
 /plan <message_text> -  Enters 'plan' mode with optional message. Create a plan based on the current messages and asks user if they want to act on it.
                         If user acts on the plan, goose mode is set to 'auto' and returns to 'normal' goose mode.
                         To warm up goose before using '/plan', we recommend setting '/mode approve' & putting appropriate context into goose.
@@ -314,6 +347,8 @@ fn print_help() {
 /recipe [filepath] - Generate a recipe from the current conversation and save it to the specified filepath (must end with .yaml).
                        If no filepath is provided, it will be saved to ./recipe.yaml.
 /compact - Compact the current conversation to reduce context length while preserving key information.
+/resource <extension> <uri> - Attach a resource from an extension to your next message
+/sandbox on|off - Run shell commands in a container, or on the host again
 /? or /help - Display this help message
 /clear - Clears the current chat history
 
@@ -370,6 +405,27 @@ mod tests {
             panic!("Expected AddBuiltin");
         }
 
+        // Test remove command
+        if let Some(InputResult::RemoveExtension(name)) = handle_slash_command("/remove git") {
+            assert_eq!(name, "git");
+        } else {
+            panic!("Expected RemoveExtension");
+        }
+
+        // Test sandbox command
+        assert!(matches!(
+            handle_slash_command("/sandbox on"),
+            Some(InputResult::Sandbox(true))
+        ));
+        assert!(matches!(
+            handle_slash_command("/sandbox off"),
+            Some(InputResult::Sandbox(false))
+        ));
+        assert!(matches!(
+            handle_slash_command("/sandbox"),
+            Some(InputResult::Retry)
+        ));
+
         // Test unknown commands
         assert!(handle_slash_command("/unknown").is_none());
     }
@@ -526,6 +582,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_resource_command() {
+        match handle_slash_command("/resource docs  file:///guide.md") {
+            Some(InputResult::AttachResource { extension, uri }) => {
+                assert_eq!(extension, "docs");
+                assert_eq!(uri, "file:///guide.md");
+            }
+            _ => panic!("Expected AttachResource"),
+        }
+
+        let result = handle_slash_command("/resource docs");
+        assert!(matches!(result, Some(InputResult::Retry)));
+    }
+
     #[test]
     fn test_recipe_command() {
         // Test recipe with no filepath
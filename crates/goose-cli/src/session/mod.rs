@@ -15,6 +15,7 @@ use goose::conversation::Conversation;
 use std::io::Write;
 use std::str::FromStr;
 use tokio::signal::ctrl_c;
+use tokio::sync::broadcast;
 use tokio_util::task::AbortOnDropHandle;
 
 pub use self::export::message_to_markdown;
@@ -29,11 +30,16 @@ use goose::utils::safe_truncate;
 
 use anyhow::{Context, Result};
 use completion::GooseCompleter;
+use goose::agents::budget::BudgetLimit;
 use goose::agents::extension::{Envs, ExtensionConfig, PLATFORM_EXTENSIONS};
+use goose::agents::plan::Plan;
+use goose::agents::shell_sandbox::configured_sandbox;
+use goose::agents::tool_changes::ToolListChange;
 use goose::agents::types::RetryConfig;
 use goose::agents::{Agent, SessionConfig, COMPACT_TRIGGERS};
+use goose::config::watcher::ConfigWatcher;
 use goose::config::{Config, GooseMode};
-use goose::session::SessionManager;
+use goose::session::{ExtensionState, ModelUsageState, SamplingUsageState, SessionManager};
 use input::InputResult;
 use rmcp::model::PromptMessage;
 use rmcp::model::ServerNotification;
@@ -80,6 +86,27 @@ enum StreamEvent {
         model: String,
         mode: String,
     },
+    ToolTimeout {
+        request_id: String,
+        tool_name: String,
+        timeout_secs: f64,
+    },
+    BudgetExceeded {
+        limit: BudgetLimit,
+        summary: String,
+    },
+    PlanUpdated {
+        plan: Plan,
+    },
+    FinalAnswer {
+        answer: Value,
+    },
+    InterimUsage {
+        model: String,
+        input_tokens: Option<i32>,
+        output_tokens: Option<i32>,
+        cost: Option<f64>,
+    },
     Error {
         error: String,
     },
@@ -118,6 +145,8 @@ pub struct CliSession {
     edit_mode: Option<EditMode>,
     retry_config: Option<RetryConfig>,
     output_format: String,
+    /// Resource contents to send along with the next message
+    pending_attachments: Vec<MessageContent>,
 }
 
 // Cache structure for completion data
@@ -226,6 +255,7 @@ impl CliSession {
             edit_mode,
             retry_config,
             output_format,
+            pending_attachments: Vec::new(),
         }
     }
 
@@ -238,7 +268,7 @@ impl CliSession {
     /// # Arguments
     /// * `extension_command` - Full command string including environment variables
     ///   Format: "ENV1=val1 ENV2=val2 command args..."
-    pub async fn add_extension(&mut self, extension_command: String) -> Result<()> {
+    pub async fn add_extension(&mut self, extension_command: String) -> Result<ToolListChange> {
         let mut parts: Vec<&str> = extension_command.split_whitespace().collect();
         let mut envs = HashMap::new();
 
@@ -271,22 +301,23 @@ impl CliSession {
             available_tools: Vec::new(),
         };
 
-        self.agent
-            .add_extension(config)
+        let change = self
+            .agent
+            .attach_extension(config, &self.session_id)
             .await
             .map_err(|e| anyhow::anyhow!("Failed to start extension: {}", e))?;
 
         // Invalidate the completion cache when a new extension is added
         self.invalidate_completion_cache().await;
 
-        Ok(())
+        Ok(change)
     }
 
     /// Add a remote extension to the session
     ///
     /// # Arguments
     /// * `extension_url` - URL of the server
-    pub async fn add_remote_extension(&mut self, extension_url: String) -> Result<()> {
+    pub async fn add_remote_extension(&mut self, extension_url: String) -> Result<ToolListChange> {
         let name = generate_extension_name(&extension_url);
 
         let config = ExtensionConfig::Sse {
@@ -301,22 +332,26 @@ impl CliSession {
             available_tools: Vec::new(),
         };
 
-        self.agent
-            .add_extension(config)
+        let change = self
+            .agent
+            .attach_extension(config, &self.session_id)
             .await
             .map_err(|e| anyhow::anyhow!("Failed to start extension: {}", e))?;
 
         // Invalidate the completion cache when a new extension is added
         self.invalidate_completion_cache().await;
 
-        Ok(())
+        Ok(change)
     }
 
     /// Add a streamable HTTP extension to the session
     ///
     /// # Arguments
     /// * `extension_url` - URL of the server
-    pub async fn add_streamable_http_extension(&mut self, extension_url: String) -> Result<()> {
+    pub async fn add_streamable_http_extension(
+        &mut self,
+        extension_url: String,
+    ) -> Result<ToolListChange> {
         let name = generate_extension_name(&extension_url);
 
         let config = ExtensionConfig::StreamableHttp {
@@ -332,22 +367,24 @@ impl CliSession {
             available_tools: Vec::new(),
         };
 
-        self.agent
-            .add_extension(config)
+        let change = self
+            .agent
+            .attach_extension(config, &self.session_id)
             .await
             .map_err(|e| anyhow::anyhow!("Failed to start extension: {}", e))?;
 
         // Invalidate the completion cache when a new extension is added
         self.invalidate_completion_cache().await;
 
-        Ok(())
+        Ok(change)
     }
 
     /// Add a builtin extension to the session
     ///
     /// # Arguments
     /// * `builtin_name` - Name of the builtin extension(s), comma separated
-    pub async fn add_builtin(&mut self, builtin_name: String) -> Result<()> {
+    pub async fn add_builtin(&mut self, builtin_name: String) -> Result<ToolListChange> {
+        let before = self.agent.list_tools(None).await;
         for name in builtin_name.split(',') {
             let extension_name = name.trim();
 
@@ -369,7 +406,7 @@ impl CliSession {
                 }
             };
             self.agent
-                .add_extension(config)
+                .attach_extension(config, &self.session_id)
                 .await
                 .map_err(|e| anyhow::anyhow!("Failed to start builtin extension: {}", e))?;
         }
@@ -377,7 +414,29 @@ impl CliSession {
         // Invalidate the completion cache when a new extension is added
         self.invalidate_completion_cache().await;
 
-        Ok(())
+        Ok(ToolListChange::between(
+            &before,
+            &self.agent.list_tools(None).await,
+        ))
+    }
+
+    /// Remove an extension from the session
+    ///
+    /// # Arguments
+    /// * `name` - Name of the extension
+    pub async fn remove_extension(&mut self, name: &str) -> Result<ToolListChange> {
+        if !self
+            .agent
+            .list_extensions()
+            .await
+            .iter()
+            .any(|ext| ext == name)
+        {
+            return Err(anyhow::anyhow!("Extension '{}' is not enabled", name));
+        }
+        let change = self.agent.detach_extension(name, &self.session_id).await?;
+        self.invalidate_completion_cache().await;
+        Ok(change)
     }
 
     pub async fn list_prompts(
@@ -498,8 +557,19 @@ impl CliSession {
                 }
             };
 
+        let mut config_changes = ConfigWatcher::new().spawn().subscribe();
+
         output::display_greeting();
         loop {
+            // Report edits to the config file since the last prompt
+            loop {
+                match config_changes.try_recv() {
+                    Ok(event) => output::render_config_changes(&event),
+                    Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                    Err(_) => break,
+                }
+            }
+
             // Display context usage before each prompt
             self.display_context_usage().await?;
 
@@ -509,7 +579,11 @@ impl CliSession {
                         RunMode::Normal => {
                             save_history(&mut editor);
 
-                            self.push_message(Message::user().with_text(&content));
+                            let message = self.pending_attachments.drain(..).fold(
+                                Message::user().with_text(&content),
+                                |message, attachment| message.with_content(attachment),
+                            );
+                            self.push_message(message);
 
                             // Track the current directory and last instruction in projects.json
                             if let Err(e) = crate::project_tracker::update_project_tracker(
@@ -549,7 +623,10 @@ impl CliSession {
                     save_history(&mut editor);
 
                     match self.add_extension(cmd.clone()).await {
-                        Ok(_) => output::render_extension_success(&cmd),
+                        Ok(change) => {
+                            output::render_extension_success(&cmd);
+                            output::render_tool_changes(&change);
+                        }
                         Err(e) => output::render_extension_error(&cmd, &e.to_string()),
                     }
                 }
@@ -557,10 +634,24 @@ impl CliSession {
                     save_history(&mut editor);
 
                     match self.add_builtin(names.clone()).await {
-                        Ok(_) => output::render_builtin_success(&names),
+                        Ok(change) => {
+                            output::render_builtin_success(&names);
+                            output::render_tool_changes(&change);
+                        }
                         Err(e) => output::render_builtin_error(&names, &e.to_string()),
                     }
                 }
+                input::InputResult::RemoveExtension(name) => {
+                    save_history(&mut editor);
+
+                    match self.remove_extension(&name).await {
+                        Ok(change) => {
+                            output::render_extension_removed(&name);
+                            output::render_tool_changes(&change);
+                        }
+                        Err(e) => output::render_extension_remove_error(&name, &e.to_string()),
+                    }
+                }
                 input::InputResult::ToggleTheme => {
                     save_history(&mut editor);
 
@@ -621,7 +712,7 @@ impl CliSession {
                         Ok(mode) => mode,
                         Err(_) => {
                             output::render_error(&format!(
-                                "Invalid mode '{}'. Mode must be one of: auto, approve, chat, smart_approve",
+                                "Invalid mode '{}'. Mode must be one of: auto, approve, chat, smart_approve, dry_run",
                                 mode
                             ));
                             continue;
@@ -646,11 +737,55 @@ impl CliSession {
                     self.plan_with_reasoner_model(plan_messages, reasoner)
                         .await?;
                 }
+                input::InputResult::Sandbox(enabled) => {
+                    save_history(&mut editor);
+
+                    let sandbox = enabled.then(configured_sandbox);
+                    match self
+                        .agent
+                        .set_shell_sandbox(&self.session_id, sandbox.clone())
+                        .await
+                    {
+                        Ok(()) => output::goose_mode_message(&match sandbox {
+                            Some(sandbox) if sandbox.network => {
+                                format!("Shell commands now run in a {} container", sandbox.image)
+                            }
+                            Some(sandbox) => format!(
+                                "Shell commands now run in a {} container without network",
+                                sandbox.image
+                            ),
+                            None => "Shell commands now run on the host".to_string(),
+                        }),
+                        Err(e) => {
+                            output::render_error(&format!("Failed to set the shell sandbox: {}", e))
+                        }
+                    }
+                    continue;
+                }
                 input::InputResult::EndPlan => {
                     self.run_mode = RunMode::Normal;
                     output::render_exit_plan_mode();
                     continue;
                 }
+                input::InputResult::AttachResource { extension, uri } => {
+                    save_history(&mut editor);
+
+                    match self.agent.resource_content(&extension, &uri).await {
+                        Ok(contents) => {
+                            self.pending_attachments.extend(contents);
+                            println!(
+                                "{}",
+                                console::style(format!(
+                                    "Attached {}, it will be sent with your next message.",
+                                    uri
+                                ))
+                                .dim()
+                            );
+                        }
+                        Err(e) => output::render_error(&e.to_string()),
+                    }
+                    continue;
+                }
                 input::InputResult::Clear => {
                     save_history(&mut editor);
 
@@ -863,6 +998,14 @@ impl CliSession {
             schedule_id: self.scheduled_job_id.clone(),
             max_turns: self.max_turns,
             retry_config: self.retry_config.clone(),
+            moderation: None,
+            context_policy: None,
+            budget: None,
+            budget_session_id: None,
+            model_routing: None,
+            lead_worker: None,
+            reflection: None,
+            final_answer_schema: None,
         };
         let user_message = self
             .messages
@@ -1229,6 +1372,53 @@ impl CliSession {
                                 eprintln!("Model changed to {} in {} mode", model, mode);
                             }
                         }
+                        Some(Ok(AgentEvent::BudgetExceeded(exceeded))) => {
+                            // The agent follows this with a message saying the same for display
+                            if is_stream_json_mode {
+                                emit_stream_event(&StreamEvent::BudgetExceeded {
+                                    limit: exceeded.limit,
+                                    summary: exceeded.to_string(),
+                                });
+                            }
+                        }
+                        Some(Ok(AgentEvent::PlanUpdated(plan))) => {
+                            if is_stream_json_mode {
+                                emit_stream_event(&StreamEvent::PlanUpdated { plan });
+                            } else if !is_json_mode {
+                                output::render_text(&plan.checklist(), Some(Color::Cyan), true);
+                            }
+                        }
+                        Some(Ok(AgentEvent::FinalAnswer(answer))) => {
+                            // The answer was already shown as the final output message
+                            if is_stream_json_mode {
+                                emit_stream_event(&StreamEvent::FinalAnswer { answer });
+                            }
+                        }
+                        Some(Ok(AgentEvent::InterimUsage(usage))) => {
+                            if is_stream_json_mode {
+                                emit_stream_event(&StreamEvent::InterimUsage {
+                                    model: usage.model,
+                                    input_tokens: usage.usage.input_tokens,
+                                    output_tokens: usage.usage.output_tokens,
+                                    cost: usage.cost,
+                                });
+                            }
+                        }
+                        Some(Ok(AgentEvent::ToolTimeout { request_id, tool_name, timeout })) => {
+                            if is_stream_json_mode {
+                                emit_stream_event(&StreamEvent::ToolTimeout {
+                                    request_id,
+                                    tool_name,
+                                    timeout_secs: timeout.as_secs_f64(),
+                                });
+                            } else if !is_json_mode {
+                                output::render_text(
+                                    &format!("Tool {} timed out after {:?}", tool_name, timeout),
+                                    Some(Color::Yellow),
+                                    true,
+                                );
+                            }
+                        }
 
                         Some(Err(e)) => {
                             let error_msg = e.to_string();
@@ -1508,8 +1698,20 @@ impl CliSession {
                         &model_config.model_name,
                         input_tokens,
                         output_tokens,
+                        metadata.accumulated_cost,
                     );
                 }
+
+                if let Some(model_usage) =
+                    ModelUsageState::from_extension_data(&metadata.extension_data)
+                {
+                    output::display_model_usage(&model_usage, show_cost);
+                }
+                if let Some(sampling_usage) =
+                    SamplingUsageState::from_extension_data(&metadata.extension_data)
+                {
+                    output::display_sampling_usage(&sampling_usage, show_cost);
+                }
             }
             Err(_) => {
                 output::display_context_usage(0, context_limit);
@@ -1,11 +1,15 @@
 use anstream::println;
 use bat::WrappingMode;
 use console::{measure_text_width, style, Color, Term};
+use goose::agents::tool_changes::ToolListChange;
+use goose::config::watcher::ConfigChangeEvent;
 use goose::config::Config;
 use goose::conversation::message::{
     ActionRequiredData, Message, MessageContent, ToolRequest, ToolResponse,
 };
-use goose::providers::canonical::maybe_get_canonical_model;
+use goose::providers::base::Usage;
+use goose::providers::pricing::estimate_cost;
+use goose::session::{ModelUsageState, SamplingUsageState};
 use goose::utils::safe_truncate;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use rmcp::model::{CallToolRequestParam, JsonObject, PromptArgument};
@@ -183,6 +187,16 @@ pub fn render_message(message: &Message, debug: bool) {
             MessageContent::Image(image) => {
                 println!("Image: [data: {}, type: {}]", image.data, image.mime_type);
             }
+            MessageContent::Audio(audio) => {
+                println!("Audio: [type: {}]", audio.mime_type);
+            }
+            MessageContent::Document(document) => {
+                println!(
+                    "Document: [name: {}, type: {}]",
+                    document.name.as_deref().unwrap_or("unnamed"),
+                    document.mime_type
+                );
+            }
             MessageContent::Thinking(thinking) => {
                 if std::env::var("GOOSE_CLI_SHOW_THINKING").is_ok()
                     && std::io::stdout().is_terminal()
@@ -387,6 +401,69 @@ pub fn render_extension_error(name: &str, error: &str) {
     println!();
 }
 
+pub fn render_extension_removed(name: &str) {
+    println!();
+    println!(
+        "  {} extension `{}`",
+        style("removed").green(),
+        style(name).cyan(),
+    );
+    println!();
+}
+
+pub fn render_extension_remove_error(name: &str, error: &str) {
+    println!();
+    println!(
+        "  {} to remove extension {}",
+        style("failed").red(),
+        style(name).red()
+    );
+    println!();
+    println!("{}", style(error).dim());
+    println!();
+}
+
+/// Show which tools the model gained and lost
+pub fn render_tool_changes(change: &ToolListChange) {
+    if change.is_empty() {
+        return;
+    }
+    for tool in &change.added {
+        println!("  {} {}", style("+").green(), tool);
+    }
+    for tool in &change.removed {
+        println!("  {} {}", style("-").red(), tool);
+    }
+    println!();
+}
+
+/// Show which settings changed in the config file, and which of them need a restart
+pub fn render_config_changes(event: &ConfigChangeEvent) {
+    let restart = event.requires_restart();
+    let live: Vec<&str> = event
+        .changes
+        .iter()
+        .map(|change| change.key.as_str())
+        .filter(|key| !restart.contains(key))
+        .collect();
+    if !live.is_empty() {
+        println!(
+            "{}",
+            style(format!("Config changed: {}", live.join(", "))).dim()
+        );
+    }
+    if !restart.is_empty() {
+        println!(
+            "{}",
+            style(format!(
+                "Config changed; restart goose for these to take effect: {}",
+                restart.join(", ")
+            ))
+            .yellow()
+        );
+    }
+}
+
 pub fn render_builtin_success(names: &str) {
     println!();
     println!(
@@ -855,18 +932,18 @@ fn estimate_cost_usd(
     input_tokens: usize,
     output_tokens: usize,
 ) -> Option<f64> {
-    let canonical_model = maybe_get_canonical_model(provider, model)?;
-
-    let input_cost_per_token = canonical_model.pricing.prompt?;
-    let output_cost_per_token = canonical_model.pricing.completion?;
-
-    let input_cost = input_cost_per_token * input_tokens as f64;
-    let output_cost = output_cost_per_token * output_tokens as f64;
-    Some(input_cost + output_cost)
+    let usage = Usage::new(Some(input_tokens as i32), Some(output_tokens as i32), None);
+    estimate_cost(provider, model, &usage)
 }
 
 /// Display cost information, if price data is available.
-pub fn display_cost_usage(provider: &str, model: &str, input_tokens: usize, output_tokens: usize) {
+pub fn display_cost_usage(
+    provider: &str,
+    model: &str,
+    input_tokens: usize,
+    output_tokens: usize,
+    session_cost: Option<f64>,
+) {
     if let Some(cost) = estimate_cost_usd(provider, model, input_tokens, output_tokens) {
         use console::style;
         eprintln!(
@@ -877,6 +954,48 @@ pub fn display_cost_usage(provider: &str, model: &str, input_tokens: usize, outp
             output_tokens
         );
     }
+    if let Some(session_cost) = session_cost {
+        eprintln!(
+            "Session cost: {} USD",
+            style(format!("${:.4}", session_cost)).cyan()
+        );
+    }
+}
+
+/// Display the session's usage per model, if more than one model served it.
+pub fn display_model_usage(model_usage: &ModelUsageState, show_cost: bool) {
+    if model_usage.models.len() < 2 {
+        return;
+    }
+    for (model, usage) in &model_usage.models {
+        let cost = match usage.cost {
+            Some(cost) if show_cost => format!(", {}", style(format!("${:.4}", cost)).cyan()),
+            _ => String::new(),
+        };
+        eprintln!(
+            "  {}: {} requests, {} tokens (in {}, out {}){}",
+            model,
+            usage.requests,
+            usage.total_tokens,
+            usage.input_tokens,
+            usage.output_tokens,
+            cost
+        );
+    }
+}
+
+/// Display what extensions spent on completions they requested through MCP sampling.
+pub fn display_sampling_usage(sampling_usage: &SamplingUsageState, show_cost: bool) {
+    for (extension, usage) in &sampling_usage.extensions {
+        let cost = match usage.cost {
+            Some(cost) if show_cost => format!(", {}", style(format!("${:.4}", cost)).cyan()),
+            _ => String::new(),
+        };
+        eprintln!(
+            "  {} (sampling): {} requests, {} tokens{}",
+            extension, usage.requests, usage.total_tokens, cost
+        );
+    }
 }
 
 pub struct McpSpinners {
@@ -2,6 +2,7 @@ pub mod analyze;
 mod editor_models;
 mod lang;
 pub mod paths;
+pub mod sandbox;
 mod shell;
 mod text_editor;
 
@@ -29,6 +29,7 @@ use xcap::{Monitor, Window};
 
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
+    process::Child,
     sync::RwLock,
 };
 use tokio_stream::{wrappers::SplitStream, StreamExt as _};
@@ -38,6 +39,7 @@ use crate::developer::{paths::get_shell_path_dirs, shell::ShellConfig};
 
 use super::analyze::{types::AnalyzeParams, CodeAnalyzer};
 use super::editor_models::{create_editor_model, EditorModel};
+use super::sandbox::{shell_tool_meta, ContainerSandbox};
 use super::shell::{configure_shell_command, expand_path, is_absolute_path, kill_process_group};
 use super::text_editor::{
     text_editor_insert, text_editor_replace, text_editor_undo, text_editor_view, text_editor_write,
@@ -851,7 +853,8 @@ impl DeveloperServer {
     /// this tool does not run indefinitely.
     #[tool(
         name = "shell",
-        description = "Execute a command in the shell.This will return the output and error concatenated into a single string, as you would see from running on the command line. There will also be an indication of if the command succeeded or failed. Avoid commands that produce a large amount of output, and consider piping those outputs to files. If you need to run a long lived command, background it - e.g. `uvicorn main:app &` so that this tool does not run indefinitely."
+        description = "Execute a command in the shell.This will return the output and error concatenated into a single string, as you would see from running on the command line. There will also be an indication of if the command succeeded or failed. Avoid commands that produce a large amount of output, and consider piping those outputs to files. If you need to run a long lived command, background it - e.g. `uvicorn main:app &` so that this tool does not run indefinitely.",
+        meta = shell_tool_meta()
     )]
     pub async fn shell(
         &self,
@@ -865,6 +868,7 @@ impl DeveloperServer {
 
         // Validate the shell command
         self.validate_shell_command(command)?;
+        let sandbox = ContainerSandbox::from_meta(&context.meta.0)?;
 
         let cancellation_token = CancellationToken::new();
         // Track the process using the request ID
@@ -876,7 +880,7 @@ impl DeveloperServer {
 
         // Execute the command and capture output
         let output_result = self
-            .execute_shell_command(command, &peer, cancellation_token.clone())
+            .execute_shell_command(command, sandbox, &peer, cancellation_token.clone())
             .await;
 
         // Clean up the process from tracking
@@ -954,43 +958,25 @@ impl DeveloperServer {
 
     /// Execute a shell command and return the combined output.
     ///
-    /// Streams output in real-time to the client using logging notifications.
+    /// Streams output in real-time to the client using logging notifications. With a sandbox,
+    /// the command runs in a container instead of on the host.
     async fn execute_shell_command(
         &self,
         command: &str,
+        sandbox: Option<ContainerSandbox>,
         peer: &rmcp::service::Peer<RoleServer>,
         cancellation_token: CancellationToken,
     ) -> Result<String, ErrorData> {
-        let mut shell_config = ShellConfig::default();
-        let shell_name = std::path::Path::new(&shell_config.executable)
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or("bash");
-
-        if let Some(ref env_file) = self.bash_env_file {
-            if shell_name == "bash" {
-                shell_config.envs.push((
-                    OsString::from("BASH_ENV"),
-                    env_file.clone().into_os_string(),
-                ))
-            }
-        }
-
-        let mut command = configure_shell_command(&shell_config, command);
-
-        if self.extend_path_with_shell {
-            if let Err(e) = get_shell_path_dirs()
-                .await
-                .and_then(|dirs| join_paths(dirs).map_err(|e| anyhow!(e)))
-                .map(|path| command.env("PATH", path))
-            {
-                tracing::error!("Failed to extend PATH with shell directories: {}", e)
+        let (mut child, container) = match &sandbox {
+            Some(sandbox) => {
+                let (mut command, name) = sandbox.command(command)?;
+                let child = command
+                    .spawn()
+                    .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+                (child, Some(name))
             }
-        }
-
-        let mut child = command
-            .spawn()
-            .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+            None => (self.spawn_shell_command(command).await?, None),
+        };
 
         let pid = child.id();
         if let Some(pid) = pid {
@@ -1024,6 +1010,10 @@ impl DeveloperServer {
                         tracing::error!("Failed to kill shell process and child processes: {}", e);
                     }
                 }
+                // Killing the container runtime's client can leave the container running
+                if let (Some(sandbox), Some(name)) = (&sandbox, &container) {
+                    sandbox.remove(name).await;
+                }
 
                 Err(ErrorData::new(
                     ErrorCode::INTERNAL_ERROR,
@@ -1034,6 +1024,40 @@ impl DeveloperServer {
         }
     }
 
+    /// Spawn a shell command on the host
+    async fn spawn_shell_command(&self, command: &str) -> Result<Child, ErrorData> {
+        let mut shell_config = ShellConfig::default();
+        let shell_name = std::path::Path::new(&shell_config.executable)
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("bash");
+
+        if let Some(ref env_file) = self.bash_env_file {
+            if shell_name == "bash" {
+                shell_config.envs.push((
+                    OsString::from("BASH_ENV"),
+                    env_file.clone().into_os_string(),
+                ))
+            }
+        }
+
+        let mut command = configure_shell_command(&shell_config, command);
+
+        if self.extend_path_with_shell {
+            if let Err(e) = get_shell_path_dirs()
+                .await
+                .and_then(|dirs| join_paths(dirs).map_err(|e| anyhow!(e)))
+                .map(|path| command.env("PATH", path))
+            {
+                tracing::error!("Failed to extend PATH with shell directories: {}", e)
+            }
+        }
+
+        command
+            .spawn()
+            .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))
+    }
+
     /// Stream shell output in real-time and return the combined output.
     ///
     /// Merges stdout and stderr streams and sends each line as a logging notification.
@@ -0,0 +1,199 @@
+//! Running shell commands in throwaway containers.
+//!
+//! The shell tool says in its `_meta` that it honors [`ShellSandbox`], and when a call carries
+//! one the command runs in a new Docker or Podman container that is removed when it exits. The
+//! sandbox's working directory, the session's, is mounted read-write under the same path, and the
+//! container has no network unless the sandbox allows it. Only the shell tool is covered; the
+//! text editor and the other tools still work on the host.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use goose_sandbox::ShellSandbox;
+use rmcp::model::{ErrorCode, ErrorData, JsonObject, Meta};
+
+use super::shell::NON_INTERACTIVE_ENVS;
+
+const CONTAINER_RUNTIMES: &[&str] = &["docker", "podman"];
+
+static CONTAINER_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// The `_meta` of the shell tool, which runs its commands in the sandbox a call asks for
+pub fn shell_tool_meta() -> Meta {
+    Meta(ShellSandbox::support_meta())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerSandbox {
+    sandbox: ShellSandbox,
+    /// The directory mounted into the container, which the command runs in
+    working_dir: PathBuf,
+}
+
+impl ContainerSandbox {
+    /// The sandbox a tool call asks for in its `_meta`, if any
+    pub fn from_meta(meta: &JsonObject) -> Result<Option<Self>, ErrorData> {
+        let invalid = |message: String| ErrorData::new(ErrorCode::INVALID_PARAMS, message, None);
+        let Some(sandbox) = ShellSandbox::from_meta(meta)
+            .map_err(|e| invalid(format!("Invalid shell sandbox settings: {}", e)))?
+        else {
+            return Ok(None);
+        };
+        let working_dir = sandbox.working_dir.clone().ok_or_else(|| {
+            invalid("The shell sandbox settings need the working directory".to_string())
+        })?;
+        Ok(Some(Self {
+            sandbox,
+            working_dir,
+        }))
+    }
+
+    fn runtime(&self) -> Result<String, ErrorData> {
+        if let Some(runtime) = &self.sandbox.runtime {
+            return Ok(runtime.clone());
+        }
+        CONTAINER_RUNTIMES
+            .iter()
+            .find(|runtime| which::which(runtime).is_ok())
+            .map(|runtime| runtime.to_string())
+            .ok_or_else(|| {
+                ErrorData::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    "The shell sandbox needs Docker or Podman, and neither is installed"
+                        .to_string(),
+                    None,
+                )
+            })
+    }
+
+    /// The arguments to `{runtime} run` a container called `name` running `command` in
+    /// `working_dir`
+    fn run_args(
+        &self,
+        runtime: &str,
+        name: &str,
+        working_dir: &Path,
+        command: &str,
+    ) -> Vec<String> {
+        let working_dir = working_dir.to_string_lossy();
+        let mut args = vec![
+            "run".to_string(),
+            "--rm".to_string(),
+            "--name".to_string(),
+            name.to_string(),
+            "--volume".to_string(),
+            format!("{}:{}", working_dir, working_dir),
+            "--workdir".to_string(),
+            working_dir.to_string(),
+        ];
+        if !self.sandbox.network {
+            args.extend(["--network".to_string(), "none".to_string()]);
+        }
+        // Keep files the command writes owned by the user rather than by root
+        if runtime.ends_with("podman") {
+            args.push("--userns=keep-id".to_string());
+        } else {
+            #[cfg(unix)]
+            args.extend([
+                "--user".to_string(),
+                format!("{}:{}", unsafe { libc::getuid() }, unsafe {
+                    libc::getgid()
+                }),
+            ]);
+        }
+        for (key, value) in NON_INTERACTIVE_ENVS {
+            args.extend(["--env".to_string(), format!("{}={}", key, value)]);
+        }
+        args.extend([
+            self.sandbox.image.clone(),
+            "sh".to_string(),
+            "-c".to_string(),
+            command.to_string(),
+        ]);
+        args
+    }
+
+    /// A command running `command` in a new container, and the container's name
+    pub fn command(&self, command: &str) -> Result<(tokio::process::Command, String), ErrorData> {
+        let runtime = self.runtime()?;
+        let name = format!(
+            "goose-shell-{}-{}",
+            std::process::id(),
+            CONTAINER_COUNT.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let mut container = tokio::process::Command::new(&runtime);
+        container
+            .args(self.run_args(&runtime, &name, &self.working_dir, command))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        container.process_group(0);
+
+        Ok((container, name))
+    }
+
+    /// Stop and remove the container called `name`, for commands that were cancelled
+    pub async fn remove(&self, name: &str) {
+        let Ok(runtime) = self.runtime() else {
+            return;
+        };
+        if let Err(e) = tokio::process::Command::new(runtime)
+            .args(["rm", "--force", name])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+        {
+            tracing::warn!("Failed to remove sandbox container {}: {}", name, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goose_sandbox::SHELL_SANDBOX_META_KEY;
+    use serde_json::json;
+
+    #[test]
+    fn test_sandbox_from_meta() {
+        let meta = |value: serde_json::Value| value.as_object().unwrap().clone();
+
+        assert_eq!(ContainerSandbox::from_meta(&meta(json!({}))).unwrap(), None);
+        assert!(ContainerSandbox::from_meta(&meta(
+            json!({SHELL_SANDBOX_META_KEY: {"network": true}})
+        ))
+        .is_err());
+        assert!(ContainerSandbox::from_meta(&meta(
+            json!({SHELL_SANDBOX_META_KEY: {"image": "ubuntu:24.04"}})
+        ))
+        .is_err());
+
+        let sandbox = ContainerSandbox::from_meta(&meta(json!({
+            SHELL_SANDBOX_META_KEY: {
+                "runtime": "podman",
+                "image": "ubuntu:24.04",
+                "working_dir": "/work",
+            }
+        })))
+        .unwrap()
+        .unwrap();
+        assert_eq!(sandbox.working_dir, Path::new("/work"));
+        let args = sandbox.run_args("podman", "goose-shell-1", &sandbox.working_dir, "ls");
+        assert!(args.windows(2).any(|pair| pair == ["--network", "none"]));
+        assert!(args
+            .windows(2)
+            .any(|pair| pair == ["--volume", "/work:/work"]));
+        assert!(args.contains(&"--userns=keep-id".to_string()));
+        assert!(args.ends_with(&[
+            "ubuntu:24.04".to_string(),
+            "sh".to_string(),
+            "-c".to_string(),
+            "ls".to_string(),
+        ]));
+    }
+}
@@ -102,6 +102,17 @@ pub fn normalize_line_endings(text: &str) -> String {
     }
 }
 
+/// Environment for shell commands that keeps tools from waiting for input that never comes
+pub const NON_INTERACTIVE_ENVS: &[(&str, &str)] = &[
+    ("GOOSE_TERMINAL", "1"),
+    ("GIT_EDITOR", "sh -c 'echo \"Interactive Git commands are not supported in this environment.\" >&2; exit 1'"),
+    ("GIT_SEQUENCE_EDITOR", "sh -c 'echo \"Interactive Git commands are not supported in this environment.\" >&2; exit 1'"),
+    ("VISUAL", "sh -c 'echo \"Interactive editor not available in this environment.\" >&2; exit 1'"),
+    ("EDITOR", "sh -c 'echo \"Interactive editor not available in this environment.\" >&2; exit 1'"),
+    ("GIT_TERMINAL_PROMPT", "0"),
+    ("GIT_PAGER", "cat"),
+];
+
 /// Configure a shell command with process group support for proper child process tracking.
 ///
 /// On Unix systems, creates a new process group so child processes can be killed together.
@@ -116,13 +127,7 @@ pub fn configure_shell_command(
         .stderr(Stdio::piped())
         .stdin(Stdio::null())
         .kill_on_drop(true)
-        .env("GOOSE_TERMINAL", "1")
-        .env("GIT_EDITOR", "sh -c 'echo \"Interactive Git commands are not supported in this environment.\" >&2; exit 1'")
-        .env("GIT_SEQUENCE_EDITOR", "sh -c 'echo \"Interactive Git commands are not supported in this environment.\" >&2; exit 1'")
-        .env("VISUAL", "sh -c 'echo \"Interactive editor not available in this environment.\" >&2; exit 1'")
-        .env("EDITOR", "sh -c 'echo \"Interactive editor not available in this environment.\" >&2; exit 1'")
-        .env("GIT_TERMINAL_PROMPT", "0")
-        .env("GIT_PAGER", "cat")
+        .envs(NON_INTERACTIVE_ENVS.iter().copied())
         .args(&shell_config.args)
         .arg(command);
 
@@ -0,0 +1,100 @@
+//! The shell sandbox as goose and its extensions agree on it.
+//!
+//! goose asks an extension to run a tool call's shell commands in a throwaway container by
+//! putting a [`ShellSandbox`] in the call's `_meta` under [`SHELL_SANDBOX_META_KEY`]. It only does
+//! so for tools that list the same key in their own `_meta`, which is how a tool says it honors
+//! the sandbox. Nothing else is covered: tools that don't list it, like the developer extension's
+//! text editor or another extension's shell, run on the host as they otherwise would.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Key of the tool call `_meta` entry asking an extension to run commands in a container, and of
+/// the tool `_meta` entry saying the tool will
+pub const SHELL_SANDBOX_META_KEY: &str = "goose-shell-sandbox";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShellSandbox {
+    /// docker or podman, whichever is installed if not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<String>,
+    pub image: String,
+    #[serde(default)]
+    pub network: bool,
+    /// The directory the commands run in and the only one the container sees, the session's
+    /// working directory. Set when the sandbox is attached to a tool call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<PathBuf>,
+}
+
+impl ShellSandbox {
+    /// The sandbox for commands run in `working_dir`
+    pub fn in_working_dir(self, working_dir: &Path) -> Self {
+        Self {
+            working_dir: Some(working_dir.to_path_buf()),
+            ..self
+        }
+    }
+
+    /// The `_meta` entries of tool calls made in the sandbox
+    pub fn to_meta(&self) -> Map<String, Value> {
+        let mut meta = Map::new();
+        meta.insert(
+            SHELL_SANDBOX_META_KEY.to_string(),
+            serde_json::to_value(self).unwrap_or_default(),
+        );
+        meta
+    }
+
+    /// The sandbox a tool call asks for in its `_meta`, if any
+    pub fn from_meta(meta: &Map<String, Value>) -> Result<Option<Self>, serde_json::Error> {
+        meta.get(SHELL_SANDBOX_META_KEY)
+            .map(|value| serde_json::from_value(value.clone()))
+            .transpose()
+    }
+
+    /// The `_meta` entries of a tool that runs its commands in the sandbox a call asks for
+    pub fn support_meta() -> Map<String, Value> {
+        let mut meta = Map::new();
+        meta.insert(SHELL_SANDBOX_META_KEY.to_string(), Value::Bool(true));
+        meta
+    }
+
+    /// Whether a tool with `_meta` entries `tool_meta` runs its commands in the sandbox
+    pub fn is_supported_by(tool_meta: &Map<String, Value>) -> bool {
+        tool_meta
+            .get(SHELL_SANDBOX_META_KEY)
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_meta_round_trip() {
+        let sandbox = ShellSandbox {
+            runtime: None,
+            image: "alpine".to_string(),
+            network: false,
+            working_dir: None,
+        }
+        .in_working_dir(Path::new("/work"));
+        let meta = sandbox.to_meta();
+        assert_eq!(
+            Value::Object(meta.clone()),
+            json!({SHELL_SANDBOX_META_KEY: {"image": "alpine", "network": false, "working_dir": "/work"}})
+        );
+        assert_eq!(ShellSandbox::from_meta(&meta).unwrap(), Some(sandbox));
+        assert_eq!(ShellSandbox::from_meta(&Map::new()).unwrap(), None);
+
+        assert!(ShellSandbox::is_supported_by(&ShellSandbox::support_meta()));
+        assert!(!ShellSandbox::is_supported_by(&meta));
+        assert!(!ShellSandbox::is_supported_by(&Map::new()));
+    }
+}
@@ -35,6 +35,8 @@ pub async fn run() -> Result<()> {
 
     let app_state = state::AppState::new().await?;
 
+    goose::config::remote::start().await;
+
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
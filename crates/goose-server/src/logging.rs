@@ -1,11 +1,33 @@
 use anyhow::Result;
 use tracing_appender::rolling::Rotation;
 use tracing_subscriber::{
-    filter::LevelFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer,
-    Registry,
+    filter::LevelFilter, fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter,
+    Layer, Registry,
 };
 
-use goose::tracing::{langfuse_layer, otlp_layer};
+use goose::redaction::RedactingMakeWriter;
+#[cfg(feature = "otel")]
+use goose::tracing::otlp_layer;
+use goose::tracing::{langfuse_layer, langfuse_middleware};
+
+/// The filter for the file and console logs: RUST_LOG if set, then `level`, then the defaults
+fn env_filter(level: Option<&str>) -> EnvFilter {
+    if let Ok(filter) = EnvFilter::try_from_default_env() {
+        return filter;
+    }
+    if let Some(level) = level {
+        match EnvFilter::try_new(level) {
+            Ok(filter) => return filter,
+            Err(e) => eprintln!("Ignoring invalid GOOSE_LOG_LEVEL '{}': {}", level, e),
+        }
+    }
+    EnvFilter::new("")
+        .add_directive("mcp_client=info".parse().unwrap())
+        .add_directive("goose=debug".parse().unwrap())
+        .add_directive("goose_server=info".parse().unwrap())
+        .add_directive("tower_http=info".parse().unwrap())
+        .add_directive(LevelFilter::WARN.into())
+}
 
 /// Sets up the logging infrastructure for the application.
 /// This includes:
@@ -27,21 +49,17 @@ pub fn setup_logging(name: Option<&str>) -> Result<()> {
     let file_layer = fmt::layer()
         .with_target(true)
         .with_level(true)
-        .with_writer(file_appender)
+        .with_writer(RedactingMakeWriter::new(file_appender))
         .with_ansi(false)
         .with_file(true);
 
-    let base_env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-        EnvFilter::new("")
-            .add_directive("mcp_client=info".parse().unwrap())
-            .add_directive("goose=debug".parse().unwrap())
-            .add_directive("goose_server=info".parse().unwrap())
-            .add_directive("tower_http=info".parse().unwrap())
-            .add_directive(LevelFilter::WARN.into())
-    });
+    // Reloadable so that a change to GOOSE_LOG_LEVEL applies without a restart
+    let level = goose::logging::configured_log_level();
+    let (file_filter, file_filter_handle) = reload::Layer::new(env_filter(level.as_deref()));
+    let (console_filter, console_filter_handle) = reload::Layer::new(env_filter(level.as_deref()));
 
     let console_layer = fmt::layer()
-        .with_writer(std::io::stderr)
+        .with_writer(RedactingMakeWriter::new(std::io::stderr))
         .with_target(true)
         .with_level(true)
         .with_file(true)
@@ -50,10 +68,11 @@ pub fn setup_logging(name: Option<&str>) -> Result<()> {
         .pretty();
 
     let mut layers = vec![
-        file_layer.with_filter(base_env_filter.clone()).boxed(),
-        console_layer.with_filter(base_env_filter).boxed(),
+        file_layer.with_filter(file_filter).boxed(),
+        console_layer.with_filter(console_filter).boxed(),
     ];
 
+    #[cfg(feature = "otel")]
     if let Ok((otlp_tracing_layer, otlp_metrics_layer, otlp_logs_layer)) = otlp_layer::init_otlp() {
         layers.push(
             otlp_tracing_layer
@@ -72,13 +91,22 @@ pub fn setup_logging(name: Option<&str>) -> Result<()> {
         );
     }
 
-    if let Some(langfuse) = langfuse_layer::create_langfuse_observer() {
-        layers.push(langfuse.with_filter(LevelFilter::DEBUG).boxed());
+    // LANGFUSE_EXPORT=generations reports provider calls instead of spans
+    if !langfuse_middleware::register_langfuse_middleware() {
+        if let Some(langfuse) = langfuse_layer::create_langfuse_observer() {
+            layers.push(langfuse.with_filter(LevelFilter::DEBUG).boxed());
+        }
     }
 
     let subscriber = Registry::default().with(layers);
 
     subscriber.try_init()?;
 
+    goose::logging::register_log_level_reloader(Box::new(move |level| {
+        file_filter_handle.reload(env_filter(level))?;
+        console_filter_handle.reload(env_filter(level))?;
+        Ok(())
+    }));
+
     Ok(())
 }
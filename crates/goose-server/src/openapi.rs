@@ -1,12 +1,16 @@
-use goose::agents::extension::Envs;
+use goose::agents::budget::{BudgetLimit, BudgetSpend};
 use goose::agents::extension::ToolInfo;
-use goose::agents::ExtensionConfig;
+use goose::agents::extension::{Envs, WasmCapabilities};
+use goose::agents::plan::{Plan, PlanStep, PlanStepKind, PlanStepStatus};
+use goose::agents::{ExtensionConfig, SessionBudget};
 use goose::config::permission::PermissionLevel;
 use goose::config::ExtensionEntry;
 use goose::conversation::Conversation;
-use goose::model::ModelConfig;
+use goose::model::{ModelConfig, ReasoningEffort, ToolChoice};
 use goose::permission::permission_confirmation::PrincipalType;
-use goose::providers::base::{ConfigKey, ModelInfo, ProviderMetadata, ProviderType};
+use goose::providers::base::{
+    ConfigKey, ModelInfo, ProviderMetadata, ProviderType, ProviderVerification,
+};
 use goose::session::{Session, SessionInsights, SessionType};
 use rmcp::model::{
     Annotations, Content, EmbeddedResource, Icon, ImageContent, JsonObject, RawAudioContent,
@@ -19,9 +23,10 @@ use goose::config::declarative_providers::{
     DeclarativeProviderConfig, LoadedProvider, ProviderEngine,
 };
 use goose::conversation::message::{
-    ActionRequired, ActionRequiredData, FrontendToolRequest, Message, MessageContent,
-    MessageMetadata, RedactedThinkingContent, SystemNotificationContent, SystemNotificationType,
-    ThinkingContent, TokenState, ToolConfirmationRequest, ToolRequest, ToolResponse,
+    ActionRequired, ActionRequiredData, AudioContent, DocumentContent, FrontendToolRequest,
+    Message, MessageContent, MessageImportance, MessageMetadata, RedactedThinkingContent,
+    SystemNotificationContent, SystemNotificationType, ThinkingContent, TokenLogprob, TokenState,
+    ToolConfirmationRequest, ToolRequest, ToolResponse, TopLogprob,
 };
 
 use crate::routes::recipe_utils::RecipeManifest;
@@ -333,6 +338,9 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::config_management::detect_provider,
         super::routes::config_management::recover_config,
         super::routes::config_management::validate_config,
+        super::routes::config_management::get_config_schema,
+        super::routes::config_management::get_config_validation,
+        super::routes::config_management::config_changes,
         super::routes::config_management::init_config,
         super::routes::config_management::upsert_config,
         super::routes::config_management::remove_config,
@@ -343,6 +351,7 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::config_management::read_all_config,
         super::routes::config_management::providers,
         super::routes::config_management::get_provider_models,
+        super::routes::config_management::get_provider_model_info,
         super::routes::config_management::get_slash_commands,
         super::routes::config_management::upsert_permissions,
         super::routes::config_management::create_custom_provider,
@@ -350,6 +359,7 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::config_management::update_custom_provider,
         super::routes::config_management::remove_custom_provider,
         super::routes::config_management::check_provider,
+        super::routes::config_management::verify_provider,
         super::routes::config_management::set_config_provider,
         super::routes::config_management::get_pricing,
         super::routes::agent::start_agent,
@@ -382,6 +392,7 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::schedule::kill_running_job,
         super::routes::schedule::inspect_running_job,
         super::routes::schedule::sessions_handler,
+        super::routes::schedule::runs_handler,
         super::routes::recipe::create_recipe,
         super::routes::recipe::encode_recipe,
         super::routes::recipe::decode_recipe,
@@ -420,6 +431,12 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::config_management::PricingQuery,
         super::routes::config_management::PricingResponse,
         super::routes::config_management::PricingData,
+        goose::config::schema::ConfigValidation,
+        goose::config::schema::ConfigIssue,
+        goose::config::schema::IssueSeverity,
+        goose::config::watcher::ConfigChangeEvent,
+        goose::config::watcher::ConfigChange,
+        goose::config::watcher::ReloadBehavior,
         super::routes::action_required::ConfirmToolActionRequest,
         super::routes::reply::ChatRequest,
         super::routes::session::ImportSessionRequest,
@@ -433,6 +450,9 @@ derive_utoipa!(Icon as IconSchema);
         Message,
         MessageContent,
         MessageMetadata,
+        MessageImportance,
+        TokenLogprob,
+        TopLogprob,
         TokenState,
         ContentSchema,
         EmbeddedResourceSchema,
@@ -447,6 +467,8 @@ derive_utoipa!(Icon as IconSchema);
         ToolResponse,
         ToolRequest,
         ToolConfirmationRequest,
+        DocumentContent,
+        AudioContent,
         ActionRequired,
         ActionRequiredData,
         ThinkingContent,
@@ -456,6 +478,13 @@ derive_utoipa!(Icon as IconSchema);
         SystemNotificationType,
         SystemNotificationContent,
         MessageEvent,
+        SessionBudget,
+        BudgetLimit,
+        BudgetSpend,
+        Plan,
+        PlanStep,
+        PlanStepKind,
+        PlanStepStatus,
         JsonObjectSchema,
         RoleSchema,
         ProviderMetadata,
@@ -467,6 +496,7 @@ derive_utoipa!(Icon as IconSchema);
         ExtensionConfig,
         ConfigKey,
         Envs,
+        WasmCapabilities,
         RecipeManifest,
         ToolSchema,
         ToolAnnotationsSchema,
@@ -474,7 +504,10 @@ derive_utoipa!(Icon as IconSchema);
         PermissionLevel,
         PrincipalType,
         ModelInfo,
+        ProviderVerification,
         ModelConfig,
+        ToolChoice,
+        ReasoningEffort,
         Session,
         SessionInsights,
         SessionType,
@@ -486,6 +519,9 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::schedule::KillJobResponse,
         super::routes::schedule::InspectJobResponse,
         goose::scheduler::ScheduledJob,
+        goose::scheduler::ScheduledRun,
+        goose::scheduler::RunTrigger,
+        goose::scheduler::RunOutcome,
         super::routes::schedule::RunNowResponse,
         super::routes::schedule::ListSchedulesResponse,
         super::routes::schedule::SessionsQuery,
@@ -516,6 +552,7 @@ derive_utoipa!(Icon as IconSchema);
         goose::recipe::RecipeParameterRequirement,
         goose::recipe::Response,
         goose::recipe::SubRecipe,
+        goose::recipe::RecipeStep,
         goose::agents::types::RetryConfig,
         goose::agents::types::SuccessCheck,
         super::routes::agent::UpdateProviderRequest,
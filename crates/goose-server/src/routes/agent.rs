@@ -512,14 +512,17 @@ async fn agent_add_extension(
     Json(request): Json<AddExtensionRequest>,
 ) -> Result<StatusCode, ErrorResponse> {
     let extension_name = request.config.name();
-    let agent = state.get_agent(request.session_id).await?;
-    agent.add_extension(request.config).await.map_err(|e| {
-        goose::posthog::emit_error(
-            "extension_add_failed",
-            &format!("{}: {}", extension_name, e),
-        );
-        ErrorResponse::internal(format!("Failed to add extension: {}", e))
-    })?;
+    let agent = state.get_agent(request.session_id.clone()).await?;
+    agent
+        .attach_extension(request.config, &request.session_id)
+        .await
+        .map_err(|e| {
+            goose::posthog::emit_error(
+                "extension_add_failed",
+                &format!("{}: {}", extension_name, e),
+            );
+            ErrorResponse::internal(format!("Failed to add extension: {}", e))
+        })?;
     Ok(StatusCode::OK)
 }
 
@@ -538,8 +541,10 @@ async fn agent_remove_extension(
     State(state): State<Arc<AppState>>,
     Json(request): Json<RemoveExtensionRequest>,
 ) -> Result<StatusCode, ErrorResponse> {
-    let agent = state.get_agent(request.session_id).await?;
-    agent.remove_extension(&request.name).await?;
+    let agent = state.get_agent(request.session_id.clone()).await?;
+    agent
+        .detach_extension(&request.name, &request.session_id)
+        .await?;
     Ok(StatusCode::OK)
 }
 
@@ -1,20 +1,25 @@
+use crate::routes::reply::SseResponse;
 use crate::routes::utils::check_provider_configured;
 use crate::state::AppState;
 use axum::routing::put;
 use axum::{
-    extract::Path,
+    extract::{Path, State},
     routing::{delete, get, post},
     Json, Router,
 };
 use goose::config::declarative_providers::LoadedProvider;
 use goose::config::paths::Paths;
+use goose::config::schema::{config_schema, validate_config as check_config, ConfigValidation};
+use goose::config::watcher::ConfigChangeEvent;
 use goose::config::ExtensionEntry;
 use goose::config::{Config, ConfigError};
 use goose::model::ModelConfig;
 use goose::providers::auto_detect::detect_provider_from_api_key;
-use goose::providers::base::{ProviderMetadata, ProviderType};
+use goose::providers::base::{ModelInfo, ProviderMetadata, ProviderType, ProviderVerification};
 use goose::providers::canonical::maybe_get_canonical_model;
 use goose::providers::create_with_default_model;
+use goose::providers::errors::ProviderError;
+use goose::providers::pricing::get_model_pricing;
 use goose::providers::providers as get_providers;
 use goose::{
     agents::execute_commands, agents::ExtensionConfig, config::permission::PermissionLevel,
@@ -25,6 +30,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_yaml;
 use std::{collections::HashMap, sync::Arc};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
 use utoipa::ToSchema;
 
 #[derive(Serialize, ToSchema)]
@@ -399,25 +406,74 @@ pub async fn get_provider_models(
         Ok(Some(models)) => Ok(Json(models)),
         Ok(None) => Ok(Json(Vec::new())),
         Err(provider_error) => {
-            use goose::providers::errors::ProviderError;
-            let status_code = match provider_error {
-                // Permanent misconfigurations - client should fix configuration
-                ProviderError::Authentication(_) => StatusCode::BAD_REQUEST,
-                ProviderError::UsageError(_) => StatusCode::BAD_REQUEST,
+            tracing::warn!(
+                "Provider {} failed to fetch models: {}",
+                name,
+                provider_error
+            );
+            Err(provider_error_status(&provider_error))
+        }
+    }
+}
 
-                // Transient errors - client should retry later
-                ProviderError::RateLimitExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+fn provider_error_status(provider_error: &ProviderError) -> StatusCode {
+    match provider_error {
+        // Permanent misconfigurations - client should fix configuration
+        ProviderError::Authentication(_) => StatusCode::BAD_REQUEST,
+        ProviderError::UsageError(_) => StatusCode::BAD_REQUEST,
 
-                // All other errors - internal server error
-                _ => StatusCode::INTERNAL_SERVER_ERROR,
-            };
+        // Transient errors - client should retry later
+        ProviderError::RateLimitExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+
+        // All other errors - internal server error
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/config/providers/{name}/model_info",
+    params(
+        ("name" = String, Path, description = "Provider name (e.g., openai)")
+    ),
+    responses(
+        (status = 200, description = "Models with context limits and capabilities", body = [ModelInfo]),
+        (status = 400, description = "Unknown provider, provider not configured, or authentication error"),
+        (status = 429, description = "Rate limit exceeded"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_provider_model_info(
+    Path(name): Path<String>,
+) -> Result<Json<Vec<ModelInfo>>, StatusCode> {
+    let Some((metadata, provider_type)) = get_providers()
+        .await
+        .into_iter()
+        .find(|(m, _)| m.name == name)
+    else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+    if !check_provider_configured(&metadata, provider_type) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let model_config =
+        ModelConfig::new(&metadata.default_model).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let provider = goose::providers::create(&name, model_config)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    match provider.list_models().await {
+        Ok(models) => Ok(Json(models)),
+        // Providers that can't list models fall back to their known model list
+        Err(ProviderError::NotImplemented(_)) => Ok(Json(metadata.known_models)),
+        Err(provider_error) => {
             tracing::warn!(
-                "Provider {} failed to fetch models: {}",
+                "Provider {} failed to list models: {}",
                 name,
                 provider_error
             );
-            Err(status_code)
+            Err(provider_error_status(&provider_error))
         }
     }
 }
@@ -456,6 +512,7 @@ pub struct PricingData {
     pub model: String,
     pub input_token_cost: f64,
     pub output_token_cost: f64,
+    pub cached_input_token_cost: Option<f64>,
     pub currency: String,
     pub context_length: Option<u32>,
 }
@@ -483,22 +540,21 @@ pub struct PricingQuery {
 pub async fn get_pricing(
     Json(query): Json<PricingQuery>,
 ) -> Result<Json<PricingResponse>, StatusCode> {
-    let canonical_model =
-        maybe_get_canonical_model(&query.provider, &query.model).ok_or(StatusCode::NOT_FOUND)?;
+    let pricing = get_model_pricing(&query.provider, &query.model).ok_or(StatusCode::NOT_FOUND)?;
+    let context_length = maybe_get_canonical_model(&query.provider, &query.model)
+        .map(|canonical_model| canonical_model.context_length as u32);
 
     let mut pricing_data = Vec::new();
 
-    if let (Some(input_cost), Some(output_cost)) = (
-        canonical_model.pricing.prompt,
-        canonical_model.pricing.completion,
-    ) {
+    if let (Some(input_cost), Some(output_cost)) = (pricing.prompt, pricing.completion) {
         pricing_data.push(PricingData {
             provider: query.provider.clone(),
             model: query.model.clone(),
             input_token_cost: input_cost,
             output_token_cost: output_cost,
+            cached_input_token_cost: pricing.input_cache_read,
             currency: "$".to_string(),
-            context_length: Some(canonical_model.context_length as u32),
+            context_length,
         });
     }
 
@@ -672,6 +728,65 @@ pub async fn validate_config() -> Result<Json<String>, StatusCode> {
         }
     }
 }
+#[utoipa::path(
+    get,
+    path = "/config/schema",
+    responses(
+        (status = 200, description = "JSON Schema of config.yaml", body = Value)
+    )
+)]
+pub async fn get_config_schema() -> Json<Value> {
+    Json(config_schema().await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/config/validation",
+    responses(
+        (status = 200, description = "Problems found in the configuration", body = ConfigValidation),
+        (status = 422, description = "Config file is corrupted")
+    )
+)]
+pub async fn get_config_validation() -> Result<Json<ConfigValidation>, StatusCode> {
+    check_config(Config::global()).await.map(Json).map_err(|e| {
+        tracing::warn!("Config validation failed: {}", e);
+        StatusCode::UNPROCESSABLE_ENTITY
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/config/changes",
+    responses(
+        (status = 200, description = "An event for each edit of the config file, flagging keys that need a restart",
+         body = ConfigChangeEvent,
+         content_type = "text/event-stream")
+    )
+)]
+pub async fn config_changes(State(state): State<Arc<AppState>>) -> SseResponse {
+    let mut changes = state.config_changes.subscribe();
+    let (tx, rx) = mpsc::channel(16);
+    tokio::spawn(async move {
+        loop {
+            let event = match changes.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Dropped {} config change events for a slow client", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let Ok(json) = serde_json::to_string(&event) else {
+                continue;
+            };
+            if tx.send(format!("data: {}\n\n", json)).await.is_err() {
+                break;
+            }
+        }
+    });
+    SseResponse::new(ReceiverStream::new(rx))
+}
+
 #[utoipa::path(
     post,
     path = "/config/custom-providers",
@@ -787,6 +902,24 @@ pub async fn check_provider(
     Ok(())
 }
 
+#[utoipa::path(
+    post,
+    path = "/config/verify_provider",
+    request_body = CheckProviderRequest,
+    responses(
+        (status = 200, description = "Result of the provider health check", body = ProviderVerification),
+        (status = 400, description = "Provider could not be created from its configuration")
+    )
+)]
+pub async fn verify_provider(
+    Json(CheckProviderRequest { provider }): Json<CheckProviderRequest>,
+) -> Result<Json<ProviderVerification>, (StatusCode, String)> {
+    let provider = create_with_default_model(&provider)
+        .await
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+    Ok(Json(provider.verify().await))
+}
+
 #[utoipa::path(
     post,
     path = "/config/set_provider",
@@ -819,6 +952,10 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/config/extensions/{name}", delete(remove_extension))
         .route("/config/providers", get(providers))
         .route("/config/providers/{name}/models", get(get_provider_models))
+        .route(
+            "/config/providers/{name}/model_info",
+            get(get_provider_model_info),
+        )
         .route("/config/detect-provider", post(detect_provider))
         .route("/config/slash_commands", get(get_slash_commands))
         .route("/config/pricing", post(get_pricing))
@@ -826,6 +963,9 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/config/backup", post(backup_config))
         .route("/config/recover", post(recover_config))
         .route("/config/validate", get(validate_config))
+        .route("/config/schema", get(get_config_schema))
+        .route("/config/validation", get(get_config_validation))
+        .route("/config/changes", get(config_changes))
         .route("/config/permissions", post(upsert_permissions))
         .route("/config/custom-providers", post(create_custom_provider))
         .route(
@@ -835,6 +975,7 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/config/custom-providers/{id}", put(update_custom_provider))
         .route("/config/custom-providers/{id}", get(get_custom_provider))
         .route("/config/check_provider", post(check_provider))
+        .route("/config/verify_provider", post(verify_provider))
         .route("/config/set_provider", post(set_config_provider))
         .with_state(state)
 }
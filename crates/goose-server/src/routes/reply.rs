@@ -8,7 +8,9 @@ use axum::{
 };
 use bytes::Bytes;
 use futures::{stream::StreamExt, Stream};
-use goose::agents::{AgentEvent, SessionConfig};
+use goose::agents::budget::{BudgetLimit, BudgetSpend};
+use goose::agents::plan::Plan;
+use goose::agents::{AgentEvent, SessionBudget, SessionConfig};
 use goose::conversation::message::{Message, MessageContent, TokenState};
 use goose::conversation::Conversation;
 use goose::session::SessionManager;
@@ -88,7 +90,7 @@ pub struct SseResponse {
 }
 
 impl SseResponse {
-    fn new(rx: ReceiverStream<String>) -> Self {
+    pub fn new(rx: ReceiverStream<String>) -> Self {
         Self { rx }
     }
 }
@@ -143,6 +145,31 @@ pub enum MessageEvent {
     UpdateConversation {
         conversation: Conversation,
     },
+    ToolTimeout {
+        request_id: String,
+        tool_name: String,
+        timeout_secs: f64,
+    },
+    BudgetExceeded {
+        limit: BudgetLimit,
+        budget: SessionBudget,
+        spend: BudgetSpend,
+        summary: String,
+    },
+    PlanUpdated {
+        plan: Plan,
+    },
+    FinalAnswer {
+        #[schema(value_type = Object)]
+        answer: serde_json::Value,
+    },
+    /// Tokens the response has used so far, while it streams in
+    InterimUsage {
+        model: String,
+        input_tokens: Option<i32>,
+        output_tokens: Option<i32>,
+        cost: Option<f64>,
+    },
     Ping,
 }
 
@@ -156,6 +183,7 @@ async fn get_token_state(session_id: &str) -> TokenState {
             accumulated_input_tokens: session.accumulated_input_tokens.unwrap_or(0),
             accumulated_output_tokens: session.accumulated_output_tokens.unwrap_or(0),
             accumulated_total_tokens: session.accumulated_total_tokens.unwrap_or(0),
+            accumulated_cost: session.accumulated_cost,
         })
         .inspect_err(|e| {
             tracing::warn!(
@@ -279,6 +307,14 @@ pub async fn reply(
             schedule_id: session.schedule_id.clone(),
             max_turns: None,
             retry_config: None,
+            moderation: None,
+            context_policy: None,
+            budget: None,
+            budget_session_id: None,
+            model_routing: None,
+            lead_worker: None,
+            reflection: None,
+            final_answer_schema: None,
         };
 
         let mut all_messages = match conversation_so_far {
@@ -351,6 +387,35 @@ pub async fn reply(
                         Ok(Some(Ok(AgentEvent::ModelChange { model, mode }))) => {
                             stream_event(MessageEvent::ModelChange { model, mode }, &tx, &cancel_token).await;
                         }
+                        Ok(Some(Ok(AgentEvent::ToolTimeout { request_id, tool_name, timeout }))) => {
+                            stream_event(MessageEvent::ToolTimeout {
+                                request_id,
+                                tool_name,
+                                timeout_secs: timeout.as_secs_f64(),
+                            }, &tx, &cancel_token).await;
+                        }
+                        Ok(Some(Ok(AgentEvent::BudgetExceeded(exceeded)))) => {
+                            stream_event(MessageEvent::BudgetExceeded {
+                                limit: exceeded.limit,
+                                budget: exceeded.budget,
+                                spend: exceeded.spend,
+                                summary: exceeded.to_string(),
+                            }, &tx, &cancel_token).await;
+                        }
+                        Ok(Some(Ok(AgentEvent::PlanUpdated(plan)))) => {
+                            stream_event(MessageEvent::PlanUpdated { plan }, &tx, &cancel_token).await;
+                        }
+                        Ok(Some(Ok(AgentEvent::FinalAnswer(answer)))) => {
+                            stream_event(MessageEvent::FinalAnswer { answer }, &tx, &cancel_token).await;
+                        }
+                        Ok(Some(Ok(AgentEvent::InterimUsage(usage)))) => {
+                            stream_event(MessageEvent::InterimUsage {
+                                model: usage.model,
+                                input_tokens: usage.usage.input_tokens,
+                                output_tokens: usage.usage.output_tokens,
+                                cost: usage.cost,
+                            }, &tx, &cancel_token).await;
+                        }
                         Ok(Some(Ok(AgentEvent::McpNotification((request_id, n))))) => {
                             stream_event(MessageEvent::Notification{
                                 request_id: request_id.clone(),
@@ -9,13 +9,18 @@ use axum::{
 use serde::{Deserialize, Serialize};
 
 use crate::state::AppState;
-use goose::scheduler::ScheduledJob;
+use chrono::{DateTime, Utc};
+use goose::scheduler::{ScheduledJob, ScheduledRun};
 
 #[derive(Deserialize, Serialize, utoipa::ToSchema)]
 pub struct CreateScheduleRequest {
     id: String,
     recipe_source: String,
+    #[serde(default)]
     cron: String,
+    /// Run the job once at this time instead of on `cron`
+    #[serde(default)]
+    run_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Deserialize, Serialize, utoipa::ToSchema)]
@@ -98,6 +103,7 @@ async fn create_schedule(
         id: req.id,
         source: req.recipe_source,
         cron: req.cron,
+        run_at: req.run_at,
         last_run: None,
         currently_running: false,
         paused: false,
@@ -318,6 +324,40 @@ async fn sessions_handler(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/schedule/{id}/runs",
+    params(
+        ("id" = String, Path, description = "ID of the schedule"),
+        SessionsQuery
+    ),
+    responses(
+        (status = 200, description = "The schedule's runs, most recent first", body = Vec<ScheduledRun>),
+        (status = 404, description = "Scheduled job not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "schedule"
+)]
+#[axum::debug_handler]
+async fn runs_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query_params): Query<SessionsQuery>,
+) -> Result<Json<Vec<ScheduledRun>>, StatusCode> {
+    let scheduler = state.scheduler();
+
+    match scheduler.run_history(&id, query_params.limit).await {
+        Ok(runs) => Ok(Json(runs)),
+        Err(e) => {
+            tracing::error!("Error fetching runs for schedule '{}': {:?}", id, e);
+            match e {
+                goose::scheduler::SchedulerError::JobNotFound(_) => Err(StatusCode::NOT_FOUND),
+                _ => Err(StatusCode::INTERNAL_SERVER_ERROR),
+            }
+        }
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/schedule/{id}/pause",
@@ -513,5 +553,6 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/schedule/{id}/kill", post(kill_running_job))
         .route("/schedule/{id}/inspect", get(inspect_running_job))
         .route("/schedule/{id}/sessions", get(sessions_handler)) // Corrected
+        .route("/schedule/{id}/runs", get(runs_handler))
         .with_state(state)
 }
@@ -1,11 +1,12 @@
 use axum::http::StatusCode;
+use goose::config::watcher::{ConfigChangeEvent, ConfigWatcher};
 use goose::execution::manager::AgentManager;
 use goose::scheduler_trait::SchedulerTrait;
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
 
 use crate::tunnel::TunnelManager;
 
@@ -17,6 +18,8 @@ pub struct AppState {
     /// Tracks sessions that have already emitted recipe telemetry to prevent double counting.
     recipe_session_tracker: Arc<Mutex<HashSet<String>>>,
     pub tunnel_manager: Arc<TunnelManager>,
+    /// Changes to the config file, as the watcher notices them
+    pub config_changes: broadcast::Sender<ConfigChangeEvent>,
 }
 
 impl AppState {
@@ -30,6 +33,7 @@ impl AppState {
             session_counter: Arc::new(AtomicUsize::new(0)),
             recipe_session_tracker: Arc::new(Mutex::new(HashSet::new())),
             tunnel_manager,
+            config_changes: ConfigWatcher::new().spawn(),
         }))
     }
 
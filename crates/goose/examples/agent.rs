@@ -45,6 +45,14 @@ async fn main() -> anyhow::Result<()> {
         schedule_id: None,
         max_turns: None,
         retry_config: None,
+        moderation: None,
+        context_policy: None,
+        budget: None,
+        budget_session_id: None,
+        model_routing: None,
+        lead_worker: None,
+        reflection: None,
+        final_answer_schema: None,
     };
 
     let user_message = Message::user()
@@ -0,0 +1,135 @@
+//! A typed feed of what an agent is doing, for observers.
+//!
+//! The stream returned by [`Agent::reply`](crate::agents::Agent::reply) belongs to whoever
+//! started the reply. Anything else that wants to follow along, such as a status bar, a log
+//! shipper or a second UI on the same session, subscribes to the agent's activity instead with
+//! [`Agent::subscribe_activity`](crate::agents::Agent::subscribe_activity). Each subscriber gets
+//! every [`AgentActivity`] from then on, across all of the agent's sessions. A subscriber that
+//! falls more than [`ACTIVITY_CHANNEL_CAPACITY`] events behind misses the oldest ones.
+
+use rmcp::model::JsonObject;
+use serde::{Deserialize, Serialize};
+
+use crate::conversation::message::Message;
+use crate::providers::base::ProviderUsage;
+
+pub const ACTIVITY_CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ActivityEvent {
+    /// The agent is about to send a request to the model, the `turn`th of this reply
+    #[serde(rename_all = "camelCase")]
+    TurnStarted {
+        turn: u32,
+    },
+    /// Part of the model's response, as it streams in
+    ModelDelta {
+        message: Message,
+    },
+    /// The model asked for a tool call
+    #[serde(rename_all = "camelCase")]
+    ToolRequested {
+        request_id: String,
+        tool_name: String,
+        arguments: JsonObject,
+    },
+    /// A tool call was cleared to run, by the user or by the permission settings
+    #[serde(rename_all = "camelCase")]
+    ToolApproved {
+        request_id: String,
+        tool_name: String,
+        by_user: bool,
+    },
+    /// A tool call finished, failed or timed out
+    #[serde(rename_all = "camelCase")]
+    ToolCompleted {
+        request_id: String,
+        tool_name: String,
+        is_error: bool,
+    },
+    /// The session's token use grew by `usage`
+    UsageUpdated {
+        usage: ProviderUsage,
+    },
+    /// The model's response and any tool calls it made are done
+    #[serde(rename_all = "camelCase")]
+    TurnCompleted {
+        turn: u32,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// An [`ActivityEvent`] and where it happened
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentActivity {
+    pub session_id: String,
+    /// When the event happened, in milliseconds since the Unix epoch
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub event: ActivityEvent,
+}
+
+impl AgentActivity {
+    pub fn new(session_id: impl Into<String>, event: ActivityEvent) -> Self {
+        Self {
+            session_id: session_id.into(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            event,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::Agent;
+
+    #[tokio::test]
+    async fn test_subscribers_receive_activity() {
+        let agent = Agent::new();
+        let mut first = agent.subscribe_activity();
+        let mut second = agent.subscribe_activity();
+
+        agent.emit_activity("session-1", ActivityEvent::TurnStarted { turn: 1 });
+        agent.emit_activity(
+            "session-1",
+            ActivityEvent::ToolCompleted {
+                request_id: "call_1".to_string(),
+                tool_name: "developer__shell".to_string(),
+                is_error: false,
+            },
+        );
+
+        for receiver in [&mut first, &mut second] {
+            let started = receiver.recv().await.unwrap();
+            assert_eq!(started.session_id, "session-1");
+            assert!(matches!(
+                started.event,
+                ActivityEvent::TurnStarted { turn: 1 }
+            ));
+            let completed = receiver.recv().await.unwrap();
+            assert!(matches!(
+                completed.event,
+                ActivityEvent::ToolCompleted {
+                    is_error: false,
+                    ..
+                }
+            ));
+        }
+
+        let json = serde_json::to_value(AgentActivity {
+            session_id: "session-1".to_string(),
+            timestamp: 0,
+            event: ActivityEvent::TurnCompleted { turn: 2 },
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"sessionId": "session-1", "timestamp": 0, "type": "turnCompleted", "turn": 2})
+        );
+    }
+}
@@ -1,49 +1,81 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
 use futures::stream::BoxStream;
 use futures::{stream, FutureExt, Stream, StreamExt, TryStreamExt};
 use uuid::Uuid;
 
+use super::activity::{ActivityEvent, AgentActivity, ACTIVITY_CHANNEL_CAPACITY};
+use super::budget::{charge_turn, BudgetExceeded, SessionBudget};
+use super::checkpoint;
+use super::dry_run;
 use super::final_output_tool::FinalOutputTool;
+use super::handoff_tool::{Handoff, HandoffTarget, HandoffTool, HANDOFF_TOOL_NAME};
+use super::hooks::{AgentHook, AgentHooks, HookContext, ModelCall};
+use super::plan::{create_plan, Plan, PlanTracker, PLAN_UPDATE_TOOL_NAME};
 use super::platform_tools;
-use super::tool_execution::{ToolCallResult, CHAT_MODE_TOOL_SKIPPED_RESPONSE, DECLINED_RESPONSE};
+use super::reflection::{Reflection, ReflectionSettings, MAX_CORRECTIONS, REFLECTION_NOTICE_TEXT};
+use super::tool_cache::{tool_cache_enabled, ToolResultCache};
+use super::tool_execution::{
+    apply_tool_timeouts, max_parallel_tool_calls, notification_text, requested_tool_name,
+    run_tool_calls_concurrently, timed_out_after, tool_timeout_result, with_abort_grace,
+    ToolCallResult, ToolProgressMessages, ToolTimeouts, CANCELLED_RESPONSE,
+    CHAT_MODE_TOOL_SKIPPED_RESPONSE, DECLINED_RESPONSE,
+};
+use super::tool_retry::ToolRetryTracker;
 use crate::action_required_manager::ActionRequiredManager;
 use crate::agents::extension::{ExtensionConfig, ExtensionResult, ToolInfo};
+use crate::agents::extension_health;
 use crate::agents::extension_manager::{get_parameter_names, ExtensionManager};
 use crate::agents::extension_manager_extension::MANAGE_EXTENSIONS_TOOL_NAME_COMPLETE;
-use crate::agents::final_output_tool::{FINAL_OUTPUT_CONTINUATION_MESSAGE, FINAL_OUTPUT_TOOL_NAME};
-use crate::agents::platform_tools::PLATFORM_MANAGE_SCHEDULE_TOOL_NAME;
+use crate::agents::final_output_tool::{
+    FINAL_OUTPUT_CONTINUATION_MESSAGE, FINAL_OUTPUT_PROMPT_KEY, FINAL_OUTPUT_RESPONSE_MESSAGE,
+    FINAL_OUTPUT_TOOL_NAME,
+};
+use crate::agents::platform_tools::{
+    PLATFORM_MANAGE_SCHEDULE_TOOL_NAME, PLATFORM_READ_MORE_TOOL_NAME,
+};
 use crate::agents::prompt_manager::PromptManager;
 use crate::agents::retry::{RetryManager, RetryResult};
+use crate::agents::shell_sandbox::{session_sandbox, ShellSandbox};
 use crate::agents::subagent_task_config::TaskConfig;
 use crate::agents::subagent_tool::{
     create_subagent_tool, handle_subagent_tool, SUBAGENT_TOOL_NAME,
 };
+use crate::agents::tool_changes::ToolListChange;
 use crate::agents::types::SessionConfig;
 use crate::agents::types::{FrontendTool, SharedProvider, ToolResultReceiver};
+use crate::agents::workspace_roots::session_roots;
 use crate::config::{get_enabled_extensions, Config, GooseMode};
 use crate::context_mgmt::{
-    check_if_compaction_needed, compact_messages, DEFAULT_COMPACTION_THRESHOLD,
+    check_if_compaction_needed, compact_messages, reduce_context, CompactionConfig,
 };
 use crate::conversation::message::{
     ActionRequiredData, Message, MessageContent, ProviderMetadata, SystemNotificationType,
     ToolRequest,
 };
+use crate::conversation::reencode::reencode_for_provider_switch;
 use crate::conversation::{debug_conversation_fix, fix_conversation, Conversation};
 use crate::mcp_utils::ToolResult;
 use crate::permission::permission_inspector::PermissionInspector;
 use crate::permission::permission_judge::PermissionCheckResult;
 use crate::permission::PermissionConfirmation;
-use crate::providers::base::Provider;
+use crate::providers::base::{Provider, ProviderUsage};
+use crate::providers::context_policy::ContextPolicy;
 use crate::providers::errors::ProviderError;
+use crate::providers::lead_worker::LeadWorkerSettings;
+use crate::providers::model_router::ModelRouting;
+use crate::providers::moderation::{Moderation, ModerationSettings};
 use crate::recipe::{Author, Recipe, Response, Settings, SubRecipe};
 use crate::scheduler_trait::SchedulerTrait;
 use crate::security::security_inspector::SecurityInspector;
-use crate::session::extension_data::{EnabledExtensionsState, ExtensionState};
+use crate::security::tool_output::ToolOutputGuard;
+use crate::session::extension_data::{EnabledExtensionsState, ExtensionState, ShellSandboxState};
 use crate::session::{Session, SessionManager, SessionType};
 use crate::tool_inspection::ToolInspectionManager;
 use crate::tool_monitor::RepetitionInspector;
@@ -54,12 +86,15 @@ use rmcp::model::{
     ServerNotification, Tool,
 };
 use serde_json::Value;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{broadcast, mpsc, Mutex};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, instrument, warn};
 
 const DEFAULT_MAX_TURNS: u32 = 1000;
 const COMPACTION_THINKING_TEXT: &str = "goose is compacting the conversation...";
+const TURN_CANCELLED_TEXT: &str = "Cancelled by the user";
+/// Tag on the metadata of an assistant message that was cut off by cancellation
+pub const PARTIAL_RESPONSE_TAG: &str = "partial";
 
 /// Context needed for the reply function
 pub struct ReplyContext {
@@ -84,6 +119,10 @@ pub struct Agent {
     pub extension_manager: Arc<ExtensionManager>,
     pub(super) sub_recipes: Mutex<HashMap<String, SubRecipe>>,
     pub(super) final_output_tool: Arc<Mutex<Option<FinalOutputTool>>>,
+    /// The final answer schema the last reply required, so the next can withdraw it
+    pub(super) final_answer_schema: Mutex<Option<Value>>,
+    pub(super) handoff_tool: Mutex<Option<HandoffTool>>,
+    pub(super) plan_tracker: Mutex<Option<PlanTracker>>,
     pub(super) frontend_tools: Mutex<HashMap<String, FrontendTool>>,
     pub(super) frontend_instructions: Mutex<Option<String>>,
     pub(super) prompt_manager: Mutex<PromptManager>,
@@ -95,14 +134,36 @@ pub struct Agent {
     pub(super) scheduler_service: Mutex<Option<Arc<dyn SchedulerTrait>>>,
     pub(super) retry_manager: RetryManager,
     pub(super) tool_inspection_manager: ToolInspectionManager,
+    tool_cache: Arc<ToolResultCache>,
+    activity_tx: broadcast::Sender<AgentActivity>,
+    hooks: Mutex<AgentHooks>,
+    /// Set when extensions were added or removed, so a reply under way refreshes its tools
+    extensions_changed: AtomicBool,
 }
 
 #[derive(Clone, Debug)]
 pub enum AgentEvent {
     Message(Message),
     McpNotification((String, ServerNotification)),
-    ModelChange { model: String, mode: String },
+    ModelChange {
+        model: String,
+        mode: String,
+    },
     HistoryReplaced(Conversation),
+    /// A tool call ran out of time and the model was given an error in place of its result
+    ToolTimeout {
+        request_id: String,
+        tool_name: String,
+        timeout: Duration,
+    },
+    /// The session reached a limit of its budget and the agent stopped
+    BudgetExceeded(BudgetExceeded),
+    /// The plan the agent is carrying out changed, such as a step being marked completed
+    PlanUpdated(Plan),
+    /// The reply's final answer, matching the schema it was required to follow
+    FinalAnswer(Value),
+    /// Tokens the model's response has used so far, while it streams in
+    InterimUsage(ProviderUsage),
 }
 
 impl Default for Agent {
@@ -151,6 +212,7 @@ impl Agent {
         // Create channels with buffer size 32 (adjust if needed)
         let (confirm_tx, confirm_rx) = mpsc::channel(32);
         let (tool_tx, tool_rx) = mpsc::channel(32);
+        let (activity_tx, _) = broadcast::channel(ACTIVITY_CHANNEL_CAPACITY);
         let provider = Arc::new(Mutex::new(None));
 
         Self {
@@ -158,6 +220,9 @@ impl Agent {
             extension_manager: Arc::new(ExtensionManager::new(provider.clone())),
             sub_recipes: Mutex::new(HashMap::new()),
             final_output_tool: Arc::new(Mutex::new(None)),
+            final_answer_schema: Mutex::new(None),
+            handoff_tool: Mutex::new(None),
+            plan_tracker: Mutex::new(None),
             frontend_tools: Mutex::new(HashMap::new()),
             frontend_instructions: Mutex::new(None),
             prompt_manager: Mutex::new(PromptManager::new()),
@@ -168,9 +233,36 @@ impl Agent {
             scheduler_service: Mutex::new(None),
             retry_manager: RetryManager::new(),
             tool_inspection_manager: Self::create_default_tool_inspection_manager(),
+            tool_cache: Arc::new(ToolResultCache::default()),
+            activity_tx,
+            hooks: Mutex::new(Self::create_default_hooks()),
+            extensions_changed: AtomicBool::new(false),
         }
     }
 
+    /// Follow what the agent does from now on, in every session it replies in
+    pub fn subscribe_activity(&self) -> broadcast::Receiver<AgentActivity> {
+        self.activity_tx.subscribe()
+    }
+
+    /// Add a hook to the agent loop, after the ones already added
+    pub async fn add_hook(&self, hook: Arc<dyn AgentHook>) {
+        self.hooks.lock().await.add(hook);
+    }
+
+    pub(crate) fn emit_activity(&self, session_id: &str, event: ActivityEvent) {
+        // Nobody listening is fine
+        let _ = self.activity_tx.send(AgentActivity::new(session_id, event));
+    }
+
+    /// Hooks every agent starts with
+    fn create_default_hooks() -> AgentHooks {
+        let mut hooks = AgentHooks::default();
+        // Scans tool results for prompt injection, once enabled in the config
+        hooks.add(Arc::new(ToolOutputGuard::new()));
+        hooks
+    }
+
     /// Create a tool inspection manager with default inspectors
     fn create_default_tool_inspection_manager() -> ToolInspectionManager {
         let mut tool_inspection_manager = ToolInspectionManager::new();
@@ -308,6 +400,14 @@ impl Agent {
         // Handle pre-approved and read-only tools
         for request in &permission_check_result.approved {
             if let Ok(tool_call) = request.tool_call.clone() {
+                self.emit_activity(
+                    &session.id,
+                    ActivityEvent::ToolApproved {
+                        request_id: request.id.clone(),
+                        tool_name: tool_call.name.to_string(),
+                        by_user: false,
+                    },
+                );
                 let (req_id, tool_result) = self
                     .dispatch_tool_call(
                         tool_call,
@@ -387,7 +487,147 @@ impl Agent {
         let created_final_output_tool = FinalOutputTool::new(response);
         let final_output_system_prompt = created_final_output_tool.system_prompt();
         *final_output_tool = Some(created_final_output_tool);
-        self.extend_system_prompt(final_output_system_prompt).await;
+        self.prompt_manager
+            .lock()
+            .await
+            .set_system_prompt_extra(FINAL_OUTPUT_PROMPT_KEY, Some(final_output_system_prompt));
+    }
+
+    async fn remove_final_output_tool(&self) {
+        *self.final_output_tool.lock().await = None;
+        self.prompt_manager
+            .lock()
+            .await
+            .set_system_prompt_extra(FINAL_OUTPUT_PROMPT_KEY, None);
+    }
+
+    /// Require the reply to end with a final answer matching `schema`, installing the final
+    /// output tool for it unless it already has that schema. Any earlier answer is cleared, so
+    /// each reply gives its own. Without a schema, the final output tool the last reply
+    /// required is removed; one set up by a recipe stays.
+    async fn require_final_answer(&self, schema: Option<&Value>) -> Result<()> {
+        let mut required = self.final_answer_schema.lock().await;
+        let Some(schema) = schema else {
+            if required.take().is_some() {
+                self.remove_final_output_tool().await;
+            }
+            return Ok(());
+        };
+        {
+            let mut final_output_tool = self.final_output_tool.lock().await;
+            if let Some(tool) = final_output_tool
+                .as_mut()
+                .filter(|tool| tool.response.json_schema.as_ref() == Some(schema))
+            {
+                tool.final_output = None;
+                *required = Some(schema.clone());
+                return Ok(());
+            }
+        }
+
+        if schema.as_object().is_none_or(|schema| schema.is_empty()) {
+            return Err(anyhow!(
+                "The final answer schema must be a non-empty JSON object"
+            ));
+        }
+        jsonschema::meta::validate(schema)
+            .map_err(|e| anyhow!("Invalid final answer schema: {}", e))?;
+        self.add_final_output_tool(Response {
+            json_schema: Some(schema.clone()),
+        })
+        .await;
+        *required = Some(schema.clone());
+        Ok(())
+    }
+
+    /// Ask a provider that supports structured output for the final answer directly, holding
+    /// its response to the final output schema. Returns whether an answer was collected.
+    async fn complete_final_answer(
+        &self,
+        session_config: &SessionConfig,
+        system_prompt: &str,
+        messages: &[Message],
+    ) -> bool {
+        let Ok(provider) = self.provider().await else {
+            return false;
+        };
+        if !provider.supports_structured_output() {
+            return false;
+        }
+        let schema = self
+            .final_output_tool
+            .lock()
+            .await
+            .as_ref()
+            .filter(|tool| tool.final_output.is_none())
+            .and_then(|tool| tool.response.json_schema.clone());
+        let Some(schema) = schema else {
+            return false;
+        };
+
+        let model_config = provider
+            .get_model_config()
+            .with_response_schema(Some(schema));
+        let mut messages = messages.to_vec();
+        messages.push(Message::user().with_text(FINAL_OUTPUT_RESPONSE_MESSAGE));
+        let output = match provider
+            .complete_with_model(&model_config, system_prompt, &messages, &[])
+            .await
+        {
+            Ok((message, usage)) => {
+                if let Err(e) = Self::update_session_metrics(session_config, &usage, false).await {
+                    warn!(
+                        "Failed to record the structured final answer's usage: {}",
+                        e
+                    );
+                }
+                self.emit_activity(&session_config.id, ActivityEvent::UsageUpdated { usage });
+                serde_json::from_str::<Value>(&message.as_concat_text())
+            }
+            Err(e) => {
+                warn!("Structured final answer request failed: {}", e);
+                return false;
+            }
+        };
+        let collected = match (output, self.final_output_tool.lock().await.as_mut()) {
+            (Ok(output), Some(tool)) => tool.collect(output).await.map_err(|e| e.to_string()),
+            (Ok(_), None) => Err("the final output tool was removed".to_string()),
+            (Err(e), _) => Err(e.to_string()),
+        };
+        collected
+            .inspect_err(|e| warn!("No structured final answer: {}", e))
+            .is_ok()
+    }
+
+    /// Offer the model a `handoff` tool for passing the conversation to one of `targets`,
+    /// or withdraw it if there are none
+    pub async fn set_handoff_targets(&self, targets: Vec<HandoffTarget>) {
+        let mut handoff_tool = self.handoff_tool.lock().await;
+        *handoff_tool = (!targets.is_empty()).then(|| HandoffTool::new(targets));
+    }
+
+    /// The handoff the model asked for in the last reply, if any
+    pub async fn take_handoff(&self) -> Option<Handoff> {
+        self.handoff_tool
+            .lock()
+            .await
+            .as_mut()
+            .and_then(|handoff_tool| handoff_tool.pending.take())
+    }
+
+    /// Have the agent carry out `plan`, tracking its progress with the `plan__update_step`
+    /// tool, or stop tracking a plan if `None`
+    pub async fn set_plan(&self, plan: Option<Plan>) {
+        *self.plan_tracker.lock().await = plan.map(PlanTracker::new);
+    }
+
+    /// The plan the agent is carrying out, with the status of each step
+    pub async fn plan(&self) -> Option<Plan> {
+        self.plan_tracker
+            .lock()
+            .await
+            .as_ref()
+            .map(|tracker| tracker.plan.clone())
     }
 
     pub async fn add_sub_recipes(&self, sub_recipes_to_add: Vec<SubRecipe>) {
@@ -415,14 +655,35 @@ impl Agent {
     }
 
     /// Dispatch a single tool call to the appropriate client
-    #[instrument(skip(self, tool_call, request_id), fields(input, output))]
+    #[instrument(
+        skip(self, tool_call, request_id),
+        fields(
+            input,
+            output,
+            otel.name = %format!("execute_tool {}", tool_call.name),
+            gen_ai.operation.name = "execute_tool",
+            gen_ai.tool.name = %tool_call.name,
+            gen_ai.tool.call.id = %request_id,
+        )
+    )]
     pub async fn dispatch_tool_call(
         &self,
-        tool_call: CallToolRequestParam,
+        mut tool_call: CallToolRequestParam,
         request_id: String,
         cancellation_token: Option<CancellationToken>,
         session: &Session,
     ) -> (String, Result<ToolCallResult, ErrorData>) {
+        let hooks = self.hooks.lock().await.clone();
+        let hook_context = HookContext {
+            session_id: session.id.clone(),
+        };
+        if let Some(veto) = hooks.before_tool(&hook_context, &mut tool_call).await {
+            return (
+                request_id,
+                Err(ErrorData::new(ErrorCode::INVALID_REQUEST, veto, None)),
+            );
+        }
+
         // Prevent subagents from creating other subagents
         if session.session_type == SessionType::SubAgent && tool_call.name == SUBAGENT_TOOL_NAME {
             return (
@@ -452,6 +713,33 @@ impl Agent {
             return (request_id, Ok(ToolCallResult::from(wrapped_result)));
         }
 
+        if tool_call.name == PLATFORM_READ_MORE_TOOL_NAME {
+            let arguments = tool_call
+                .arguments
+                .map(Value::Object)
+                .unwrap_or(Value::Object(serde_json::Map::new()));
+            let result =
+                super::large_response_handler::read_more(arguments).map(|content| CallToolResult {
+                    content,
+                    structured_content: None,
+                    is_error: Some(false),
+                    meta: None,
+                });
+            return (request_id, Ok(ToolCallResult::from(result)));
+        }
+
+        if tool_call.name == HANDOFF_TOOL_NAME {
+            if let Some(handoff_tool) = self.handoff_tool.lock().await.as_mut() {
+                return (request_id, Ok(handoff_tool.execute_tool_call(tool_call)));
+            }
+        }
+
+        if tool_call.name == PLAN_UPDATE_TOOL_NAME {
+            if let Some(plan_tracker) = self.plan_tracker.lock().await.as_mut() {
+                return (request_id, Ok(plan_tracker.execute_tool_call(tool_call)));
+            }
+        }
+
         if tool_call.name == FINAL_OUTPUT_TOOL_NAME {
             return if let Some(final_output_tool) = self.final_output_tool.lock().await.as_mut() {
                 let result = final_output_tool.execute_tool_call(tool_call.clone()).await;
@@ -468,8 +756,17 @@ impl Agent {
             };
         }
 
+        let cache_enabled = tool_cache_enabled();
+        let cache_key = cache_enabled
+            .then(|| self.tool_cache_key(&tool_call, &session.id))
+            .flatten();
+        let cached = cache_key.as_ref().and_then(|key| self.tool_cache.get(key));
+
         debug!("WAITING_TOOL_START: {}", tool_call.name);
-        let result: ToolCallResult = if tool_call.name == SUBAGENT_TOOL_NAME {
+        let result: ToolCallResult = if let Some(cached) = cached {
+            debug!("Serving {} from the tool cache", tool_call.name);
+            ToolCallResult::from(Ok(cached))
+        } else if tool_call.name == SUBAGENT_TOOL_NAME {
             let provider = match self.provider().await {
                 Ok(p) => p,
                 Err(_) => {
@@ -514,7 +811,11 @@ impl Agent {
             let result = self
                 .extension_manager
                 .dispatch_tool_call(tool_call.clone(), cancellation_token.unwrap_or_default())
-                .await;
+                .await
+                .map(|result| match cache_key {
+                    Some(key) => self.tool_cache.caching(&session.id, key, result),
+                    None => result,
+                });
             result.unwrap_or_else(|e| {
                 crate::posthog::emit_error(
                     "tool_execution_failed",
@@ -528,36 +829,80 @@ impl Agent {
             })
         };
 
+        // Calls that can't be cached might change what cached calls would return
+        let result = if cache_enabled && cache_key.is_none() {
+            self.tool_cache.writing(&session.id, result)
+        } else {
+            result
+        };
+
         debug!("WAITING_TOOL_END: {}", tool_call.name);
 
+        let tool_name = tool_call.name.to_string();
+        let audited = crate::audit::enabled().then(|| {
+            (
+                session.id.clone(),
+                request_id.clone(),
+                Value::Object(tool_call.arguments.clone().unwrap_or_default()),
+            )
+        });
         (
             request_id,
             Ok(ToolCallResult {
                 notification_stream: result.notification_stream,
-                result: Box::new(
-                    result
-                        .result
-                        .map(super::large_response_handler::process_tool_response),
-                ),
+                result: Box::new(result.result.map(move |response| {
+                    let success = response
+                        .as_ref()
+                        .is_ok_and(|result| result.is_error != Some(true));
+                    crate::metrics::record_tool_call(&tool_name, success);
+                    if let Some((session_id, request_id, arguments)) = audited {
+                        let result = match &response {
+                            Ok(result) => serde_json::to_value(result).unwrap_or_default(),
+                            Err(e) => serde_json::to_value(e).unwrap_or_default(),
+                        };
+                        crate::audit::record(
+                            Some(session_id),
+                            crate::audit::AuditEvent::ToolCall {
+                                request_id,
+                                tool: tool_name,
+                                arguments,
+                                success,
+                                result,
+                            },
+                        );
+                    }
+                    super::large_response_handler::process_tool_response(response)
+                })),
             }),
         )
     }
 
+    /// The key to cache `tool_call` under, if its result can be cached
+    fn tool_cache_key(&self, tool_call: &CallToolRequestParam, session_id: &str) -> Option<String> {
+        self.extension_manager
+            .is_cacheable_tool(&tool_call.name)
+            .then(|| ToolResultCache::key(session_id, tool_call))
+    }
+
     /// Save current extension state to session metadata
     /// Should be called after any extension add/remove operation
     pub async fn save_extension_state(&self, session: &SessionConfig) -> Result<()> {
+        self.save_session_extensions(&session.id).await
+    }
+
+    async fn save_session_extensions(&self, session_id: &str) -> Result<()> {
         let extension_configs = self.extension_manager.get_extension_configs().await;
 
         let extensions_state = EnabledExtensionsState::new(extension_configs);
 
-        let mut session_data = SessionManager::get_session(&session.id, false).await?;
+        let mut session_data = SessionManager::get_session(session_id, false).await?;
 
         if let Err(e) = extensions_state.to_extension_data(&mut session_data.extension_data) {
             warn!("Failed to serialize extension state: {}", e);
             return Err(anyhow!("Extension state serialization failed: {}", e));
         }
 
-        SessionManager::update_session(&session.id)
+        SessionManager::update_session(session_id)
             .extension_data(session_data.extension_data)
             .apply()
             .await?;
@@ -565,6 +910,54 @@ impl Agent {
         Ok(())
     }
 
+    /// Add an extension to a running session, keeping it for when the session is resumed, and
+    /// report how the tools changed
+    pub async fn attach_extension(
+        &self,
+        extension: ExtensionConfig,
+        session_id: &str,
+    ) -> Result<ToolListChange> {
+        let before = self.list_tools(None).await;
+        self.add_extension(extension).await?;
+        self.save_session_extensions(session_id).await?;
+        Ok(ToolListChange::between(
+            &before,
+            &self.list_tools(None).await,
+        ))
+    }
+
+    /// Remove an extension from a running session, and report how the tools changed
+    pub async fn detach_extension(&self, name: &str, session_id: &str) -> Result<ToolListChange> {
+        let before = self.list_tools(None).await;
+        self.remove_extension(name).await?;
+        self.save_session_extensions(session_id).await?;
+        Ok(ToolListChange::between(
+            &before,
+            &self.list_tools(None).await,
+        ))
+    }
+
+    /// Run the shell commands of a session in `sandbox`, or on the host if None, remembering
+    /// the choice for when the session is resumed
+    pub async fn set_shell_sandbox(
+        &self,
+        session_id: &str,
+        sandbox: Option<ShellSandbox>,
+    ) -> Result<()> {
+        let mut session = SessionManager::get_session(session_id, false).await?;
+        ShellSandboxState {
+            sandbox: sandbox.clone(),
+        }
+        .to_extension_data(&mut session.extension_data)?;
+        SessionManager::update_session(session_id)
+            .extension_data(session.extension_data)
+            .apply()
+            .await?;
+        self.extension_manager
+            .set_shell_sandbox(sandbox.map(|sandbox| sandbox.in_working_dir(&session.working_dir)));
+        Ok(())
+    }
+
     pub async fn add_extension(&self, extension: ExtensionConfig) -> ExtensionResult<()> {
         match &extension {
             ExtensionConfig::Frontend {
@@ -596,8 +989,10 @@ impl Agent {
                 self.extension_manager
                     .add_extension(extension.clone())
                     .await?;
+                self.extension_manager.start_health_monitor();
             }
         }
+        self.extensions_changed.store(true, Ordering::SeqCst);
 
         Ok(())
     }
@@ -645,6 +1040,7 @@ impl Agent {
         let subagents_enabled = self.subagents_enabled().await;
         if extension_name.is_none() || extension_name.as_deref() == Some("platform") {
             prefixed_tools.push(platform_tools::manage_schedule_tool());
+            prefixed_tools.push(platform_tools::read_more_tool());
         }
 
         if extension_name.is_none() {
@@ -652,6 +1048,14 @@ impl Agent {
                 prefixed_tools.push(final_output_tool.tool());
             }
 
+            if let Some(handoff_tool) = self.handoff_tool.lock().await.as_ref() {
+                prefixed_tools.push(handoff_tool.tool());
+            }
+
+            if let Some(plan_tracker) = self.plan_tracker.lock().await.as_ref() {
+                prefixed_tools.push(plan_tracker.tool());
+            }
+
             if subagents_enabled {
                 let sub_recipes = self.sub_recipes.lock().await;
                 let sub_recipes_vec: Vec<_> = sub_recipes.values().cloned().collect();
@@ -664,6 +1068,7 @@ impl Agent {
 
     pub async fn remove_extension(&self, name: &str) -> Result<()> {
         self.extension_manager.remove_extension(name).await?;
+        self.extensions_changed.store(true, Ordering::SeqCst);
         Ok(())
     }
 
@@ -689,13 +1094,30 @@ impl Agent {
         }
     }
 
-    #[instrument(skip(self, user_message, session_config), fields(user_message))]
+    #[instrument(
+        skip(self, user_message, session_config),
+        fields(
+            user_message,
+            otel.name = "invoke_agent goose",
+            gen_ai.operation.name = "invoke_agent",
+            gen_ai.agent.name = "goose",
+            gen_ai.conversation.id = %session_config.id,
+        )
+    )]
     pub async fn reply(
         &self,
         user_message: Message,
         session_config: SessionConfig,
         cancel_token: Option<CancellationToken>,
     ) -> Result<BoxStream<'_, Result<AgentEvent>>> {
+        // Results of tool calls from a turn cut short by a crash go in before the new message
+        if let Err(e) = checkpoint::recover(&session_config.id).await {
+            warn!(
+                "Failed to recover the checkpoint of session {}: {}",
+                session_config.id, e
+            );
+        }
+
         for content in &user_message.content {
             if let MessageContent::ActionRequired(action_required) = content {
                 if let ActionRequiredData::ElicitationResponse { id, user_data } =
@@ -813,10 +1235,7 @@ impl Agent {
             let final_conversation = if !needs_auto_compact {
                 conversation
             } else {
-                let config = Config::global();
-                let threshold = config
-                    .get_param::<f64>("GOOSE_AUTO_COMPACT_THRESHOLD")
-                    .unwrap_or(DEFAULT_COMPACTION_THRESHOLD);
+                let threshold = CompactionConfig::from_config().threshold;
                 let threshold_percentage = (threshold * 100.0) as u32;
 
                 let inline_msg = format!(
@@ -872,6 +1291,37 @@ impl Agent {
         }))
     }
 
+    /// Carry on with the session where its last run stopped, such as after a crash. Tool calls
+    /// that finished by then keep their results, unfinished ones are reported to the model as
+    /// interrupted, and the plan that was being carried out is restored.
+    pub async fn resume(
+        &self,
+        session_config: SessionConfig,
+        cancel_token: Option<CancellationToken>,
+    ) -> Result<BoxStream<'_, Result<AgentEvent>>> {
+        let checkpoint = checkpoint::recover(&session_config.id).await?;
+        if let Some(plan) = checkpoint.plan.filter(|plan| !plan.is_finished()) {
+            self.set_plan(Some(plan)).await;
+        }
+
+        let session = SessionManager::get_session(&session_config.id, true).await?;
+        let conversation = session
+            .conversation
+            .clone()
+            .ok_or_else(|| anyhow!("Session {} has no conversation", session_config.id))?;
+        // Once the model has had the last word there is nothing left to do
+        let waiting_on_model = conversation
+            .agent_visible_messages()
+            .last()
+            .is_some_and(|message| message.role == rmcp::model::Role::User);
+        if !waiting_on_model {
+            return Ok(Box::pin(stream::empty()));
+        }
+
+        self.reply_internal(conversation, session_config, session, cancel_token)
+            .await
+    }
+
     async fn reply_internal(
         &self,
         conversation: Conversation,
@@ -879,6 +1329,19 @@ impl Agent {
         session: Session,
         cancel_token: Option<CancellationToken>,
     ) -> Result<BoxStream<'_, Result<AgentEvent>>> {
+        self.require_final_answer(session_config.final_answer_schema.as_ref())
+            .await?;
+
+        self.extension_manager
+            .set_workspace_roots(session_roots(&session.working_dir))
+            .await;
+        self.extension_manager.set_shell_sandbox(
+            session_sandbox(&session.extension_data)
+                .map(|sandbox| sandbox.in_working_dir(&session.working_dir)),
+        );
+        // The tools prepared below already reflect any extensions changed before this reply
+        self.extensions_changed.store(false, Ordering::SeqCst);
+
         let context = self
             .prepare_reply_context(conversation, &session.working_dir)
             .await?;
@@ -893,6 +1356,41 @@ impl Agent {
         let reply_span = tracing::Span::current();
         self.reset_retry_attempts().await;
 
+        let moderation = match session_config
+            .moderation
+            .clone()
+            .or_else(ModerationSettings::from_config)
+        {
+            Some(settings) => Some(Moderation::from_settings(&settings).await?),
+            None => None,
+        };
+
+        let context_policy = session_config
+            .context_policy
+            .or_else(ContextPolicy::from_config);
+
+        let model_routing = session_config
+            .model_routing
+            .clone()
+            .or_else(ModelRouting::from_config);
+
+        if let Some(settings) = &session_config.lead_worker {
+            self.apply_lead_worker(settings).await?;
+        }
+
+        let budget = session_config.budget.or_else(SessionBudget::from_config);
+
+        let reflection = match session_config
+            .reflection
+            .clone()
+            .or_else(ReflectionSettings::from_config)
+        {
+            Some(settings) => {
+                Some(Reflection::from_settings(&settings, self.provider().await?).await?)
+            }
+            None => None,
+        };
+
         let provider = self.provider().await?;
         let session_id = session_config.id.clone();
         let working_dir = session.working_dir.clone();
@@ -907,22 +1405,73 @@ impl Agent {
             let mut turns_taken = 0u32;
             let max_turns = session_config.max_turns.unwrap_or(DEFAULT_MAX_TURNS);
             let mut compaction_attempts = 0;
+            let mut reduction_attempts = 0;
+            let mut corrections_made = 0u32;
+            let mut tool_retries = ToolRetryTracker::from_config();
+            let hooks = self.hooks.lock().await.clone();
+            let hook_context = HookContext { session_id: session_config.id.clone() };
+            let turn_cancel = cancel_token.clone().unwrap_or_default();
+            let mut health_events = self.extension_manager.subscribe_health_events();
 
             loop {
                 if is_token_cancelled(&cancel_token) {
                     break;
                 }
 
+                let health_changes = extension_health::drain_events(&mut health_events);
+                let health_changed = !health_changes.is_empty();
+                for event in health_changes {
+                    yield AgentEvent::Message(Message::assistant().with_system_notification(
+                        SystemNotificationType::InlineMessage,
+                        event.message,
+                    ));
+                }
+                if self.extensions_changed.swap(false, Ordering::SeqCst) || health_changed {
+                    let (new_tools, new_toolshim_tools, new_system_prompt) =
+                        self.prepare_tools_and_prompt(&working_dir).await?;
+                    let change = ToolListChange::between(&tools, &new_tools);
+                    if !change.is_empty() {
+                        yield AgentEvent::Message(Message::assistant().with_system_notification(
+                            SystemNotificationType::InlineMessage,
+                            change.summary(),
+                        ));
+                    }
+                    (tools, toolshim_tools, system_prompt) =
+                        (new_tools, new_toolshim_tools, new_system_prompt);
+                }
+                for collision in self.extension_manager.take_new_tool_collisions() {
+                    yield AgentEvent::Message(Message::assistant().with_system_notification(
+                        SystemNotificationType::InlineMessage,
+                        collision.message(),
+                    ));
+                }
+
+                let plan_update = self.plan_tracker.lock().await.as_mut().and_then(PlanTracker::take_update);
+                if let Some(plan) = plan_update {
+                    if let Err(e) = checkpoint::save_plan(&session_config.id, Some(plan.clone())).await {
+                        warn!("Failed to checkpoint the plan: {}", e);
+                    }
+                    yield AgentEvent::PlanUpdated(plan);
+                }
+
                 if let Some(final_output_tool) = self.final_output_tool.lock().await.as_ref() {
                     if final_output_tool.final_output.is_some() {
                         let final_event = AgentEvent::Message(
                             Message::assistant().with_text(final_output_tool.final_output.clone().unwrap())
                         );
                         yield final_event;
+                        if let Some(answer) = final_output_tool.final_value() {
+                            yield AgentEvent::FinalAnswer(answer);
+                        }
                         break;
                     }
                 }
 
+                // A handoff ends this agent's part of the conversation once its tools have run
+                if self.handoff_tool.lock().await.as_ref().is_some_and(|tool| tool.pending.is_some()) {
+                    break;
+                }
+
                 turns_taken += 1;
                 if turns_taken > max_turns {
                     yield AgentEvent::Message(
@@ -933,32 +1482,97 @@ impl Agent {
                     break;
                 }
 
+                if let Some(budget) = &budget {
+                    let budget_session_id = session_config
+                        .budget_session_id
+                        .as_deref()
+                        .unwrap_or(&session_config.id);
+                    if let Some(exceeded) = charge_turn(budget_session_id, budget).await? {
+                        info!("Session {} stopped: {:?}", session_config.id, exceeded);
+                        yield AgentEvent::BudgetExceeded(exceeded);
+                        let marker = Message::assistant().with_system_notification(
+                            SystemNotificationType::InlineMessage,
+                            exceeded.to_string(),
+                        );
+                        yield AgentEvent::Message(marker.clone());
+                        SessionManager::add_message(&session_config.id, &marker).await?;
+                        break;
+                    }
+                }
+
+                self.emit_activity(&session_config.id, ActivityEvent::TurnStarted { turn: turns_taken });
+
                 let conversation_with_moim = super::moim::inject_moim(
                     conversation.clone(),
                     &self.extension_manager,
                 ).await;
 
-                let mut stream = Self::stream_response_from_provider(
-                    self.provider().await?,
-                    &system_prompt,
-                    conversation_with_moim.messages(),
+                let mut model_call = ModelCall {
+                    system_prompt: system_prompt.clone(),
+                    messages: conversation_with_moim.messages().clone(),
+                    tool_choice: None,
+                };
+                if let Some(veto) = hooks.before_model_call(&hook_context, &mut model_call).await {
+                    let marker = Message::assistant().with_system_notification(
+                        SystemNotificationType::InlineMessage,
+                        veto,
+                    );
+                    yield AgentEvent::Message(marker.clone());
+                    SessionManager::add_message(&session_config.id, &marker).await?;
+                    break;
+                }
+
+                let provider = self.provider().await?;
+                let router = model_routing.as_ref().map(|routing| routing.wrap(provider.clone()));
+                let provider: Arc<dyn Provider> = match &router {
+                    Some(router) => router.clone(),
+                    None => provider,
+                };
+                let provider = match context_policy {
+                    Some(policy) => policy.wrap(provider),
+                    None => provider,
+                };
+                let provider = match &moderation {
+                    Some(moderation) => moderation.wrap(provider),
+                    None => provider,
+                };
+                // Dropping the provider stream on cancellation aborts the request mid-response
+                let mut stream = Box::pin(Self::stream_response_from_provider(
+                    provider,
+                    &model_call.system_prompt,
+                    &model_call.messages,
                     &tools,
                     &toolshim_tools,
-                ).await?;
+                    model_call.tool_choice.clone(),
+                ).await?.take_until(turn_cancel.clone().cancelled_owned()));
+
+                // Responses the router replaced were still paid for
+                if let Some(router) = &router {
+                    for usage in router.take_discarded_usage() {
+                        Self::update_session_metrics(&session_config, &usage, false).await?;
+                    }
+                }
 
                 let mut no_tools_called = true;
+                let mut tool_calls_checkpointed = false;
+                let mut response_veto = None;
                 let mut messages_to_add = Conversation::default();
                 let mut tools_updated = false;
                 let mut did_recovery_compact_this_iteration = false;
 
                 while let Some(next) = stream.next().await {
-                    if is_token_cancelled(&cancel_token) {
-                        break;
-                    }
-
                     match next {
                         Ok((response, usage)) => {
                             compaction_attempts = 0;
+                            reduction_attempts = 0;
+
+                            let usage = match usage {
+                                Some(usage) if usage.interim => {
+                                    yield AgentEvent::InterimUsage(usage);
+                                    None
+                                }
+                                usage => usage,
+                            };
 
                             // Emit model change event if provider is lead-worker
                             let provider = self.provider().await?;
@@ -981,20 +1595,46 @@ impl Agent {
                                 }
                             }
 
+                            if let (Some(router), Some(usage)) = (&router, &usage) {
+                                if let Some(turn_type) = router.turn_type_of_model(&usage.model) {
+                                    yield AgentEvent::ModelChange {
+                                        model: usage.model.clone(),
+                                        mode: turn_type.as_str().to_string(),
+                                    };
+                                }
+                            }
+
                             if let Some(ref usage) = usage {
                                 Self::update_session_metrics(&session_config, usage, false).await?;
+                                self.emit_activity(&session_config.id, ActivityEvent::UsageUpdated { usage: usage.clone() });
                             }
 
-                            if let Some(response) = response {
+                            if let Some(mut response) = response {
+                                if let Some(veto) = hooks.after_model_call(&hook_context, &mut response).await {
+                                    response_veto = Some(veto);
+                                    break;
+                                }
+
                                 let ToolCategorizeResult {
                                     frontend_requests,
                                     remaining_requests,
                                     filtered_response,
                                 } = self.categorize_tools(&response, &tools).await;
 
+                                self.emit_activity(&session_config.id, ActivityEvent::ModelDelta { message: filtered_response.clone() });
                                 yield AgentEvent::Message(filtered_response.clone());
                                 tokio::task::yield_now().await;
 
+                                for request in frontend_requests.iter().chain(remaining_requests.iter()) {
+                                    if let Ok(tool_call) = &request.tool_call {
+                                        self.emit_activity(&session_config.id, ActivityEvent::ToolRequested {
+                                            request_id: request.id.clone(),
+                                            tool_name: tool_call.name.to_string(),
+                                            arguments: tool_call.arguments.clone().unwrap_or_default(),
+                                        });
+                                    }
+                                }
+
                                 let num_tool_requests = frontend_requests.len() + remaining_requests.len();
                                 if num_tool_requests == 0 {
                                     messages_to_add.push(response.clone());
@@ -1014,11 +1654,54 @@ impl Agent {
                                     request_metadata.insert(request.id.clone(), request.metadata.clone());
                                 }
 
-                                for (idx, request) in frontend_requests.iter().enumerate() {
-                                    let mut frontend_tool_stream = self.handle_frontend_tool_request(
+                                // Saved so the calls' results survive a crash before the turn ends
+                                let calls_to_checkpoint = frontend_requests.iter().chain(remaining_requests.iter())
+                                    .filter(|request| request.tool_call.is_ok())
+                                    .map(|request| {
+                                        let request_msg = Message::assistant()
+                                            .with_id(format!("msg_{}", Uuid::new_v4()))
+                                            .with_tool_request_with_metadata(
+                                                request.id.clone(),
+                                                request.tool_call.clone(),
+                                                request.metadata.as_ref(),
+                                            );
+                                        (request.id.clone(), request_msg)
+                                    });
+                                match checkpoint::save_tool_calls(&session_config.id, calls_to_checkpoint).await {
+                                    Ok(()) => tool_calls_checkpointed = true,
+                                    Err(e) => warn!("Failed to checkpoint tool calls: {}", e),
+                                }
+
+                                // In a dry run, report calls that may change something instead of running them
+                                let (frontend_to_run, remaining_to_run) = if goose_mode == GooseMode::DryRun {
+                                    let (frontend_read_only, frontend_mutating) = dry_run::split_requests(&frontend_requests, &tools);
+                                    let (read_only, mutating) = dry_run::split_requests(&remaining_requests, &tools);
+                                    for request in frontend_mutating.iter().chain(mutating.iter()) {
+                                        let (Ok(tool_call), Some(response_msg)) = (&request.tool_call, request_to_response_map.get(&request.id)) else {
+                                            continue;
+                                        };
+                                        let mut response = response_msg.lock().await;
+                                        *response = response.clone().with_tool_response_with_metadata(
+                                            request.id.clone(),
+                                            Ok(CallToolResult {
+                                                content: vec![Content::text(dry_run::dry_run_report(tool_call))],
+                                                structured_content: None,
+                                                is_error: Some(false),
+                                                meta: None,
+                                            }),
+                                            request.metadata.as_ref(),
+                                        );
+                                    }
+                                    (frontend_read_only, read_only)
+                                } else {
+                                    (frontend_requests.clone(), remaining_requests.clone())
+                                };
+
+                                for request in frontend_to_run.iter() {
+                                    let mut frontend_tool_stream = Box::pin(self.handle_frontend_tool_request(
                                         request,
-                                        tool_response_messages[idx].clone(),
-                                    );
+                                        request_to_response_map[&request.id].clone(),
+                                    ).take_until(turn_cancel.clone().cancelled_owned()));
 
                                     while let Some(msg) = frontend_tool_stream.try_next().await? {
                                         yield AgentEvent::Message(msg);
@@ -1042,6 +1725,8 @@ impl Agent {
                                         }
                                     }
                                 } else {
+                                    let remaining_requests = remaining_to_run;
+
                                     // Run all tool inspectors
                                     let inspection_results = self.tool_inspection_manager
                                         .inspect_tools(
@@ -1084,14 +1769,14 @@ impl Agent {
 
                                     let tool_futures_arc = Arc::new(Mutex::new(tool_futures));
 
-                                    let mut tool_approval_stream = self.handle_approval_tool_requests(
+                                    let mut tool_approval_stream = Box::pin(self.handle_approval_tool_requests(
                                         &permission_check_result.needs_approval,
                                         tool_futures_arc.clone(),
                                         &request_to_response_map,
                                         cancel_token.clone(),
                                         &session,
                                         &inspection_results,
-                                    );
+                                    ).take_until(turn_cancel.clone().cancelled_owned()));
 
                                     while let Some(msg) = tool_approval_stream.try_next().await? {
                                         yield AgentEvent::Message(msg);
@@ -1102,27 +1787,90 @@ impl Agent {
                                         futures_lock.drain(..).collect::<Vec<_>>()
                                     };
 
-                                    let with_id = tool_futures
-                                        .into_iter()
-                                        .map(|(request_id, stream)| {
-                                            stream.map(move |item| (request_id.clone(), item))
-                                        })
-                                        .collect::<Vec<_>>();
-
-                                    let mut combined = stream::select_all(with_id);
+                                    // Tool calls run concurrently unless the model config asks for them
+                                    // to be executed one at a time, in the order they were requested.
+                                    // Either way their responses follow in request order.
+                                    let tool_timeouts = ToolTimeouts::from_config();
+                                    let tool_futures = apply_tool_timeouts(tool_futures, &remaining_requests, &tool_timeouts);
+                                    let parallel_tool_calls = self
+                                        .provider()
+                                        .await?
+                                        .get_model_config()
+                                        .allows_parallel_tool_calls();
+                                    let combined: BoxStream<'_, (String, ToolStreamItem<ToolResult<CallToolResult>>)> =
+                                        if parallel_tool_calls {
+                                            run_tool_calls_concurrently(
+                                                tool_futures,
+                                                &remaining_requests,
+                                                &tools,
+                                                max_parallel_tool_calls(),
+                                            )
+                                        } else {
+                                            let with_id = tool_futures
+                                                .into_iter()
+                                                .map(|(request_id, stream)| {
+                                                    stream.map(move |item| (request_id.clone(), item))
+                                                })
+                                                .collect::<Vec<_>>();
+                                            Box::pin(stream::iter(with_id).flatten())
+                                        };
+                                    let mut combined = with_abort_grace(combined, turn_cancel.clone());
                                     let mut all_install_successful = true;
 
-                                    while let Some((request_id, item)) = combined.next().await {
-                                        if is_token_cancelled(&cancel_token) {
-                                            break;
-                                        }
-
+                                    // The turn's budget covers all of the calls together; what the
+                                    // unfinished ones reported so far goes back to the model
+                                    let turn_deadline = tool_timeouts.turn.map(|timeout| tokio::time::Instant::now() + timeout);
+                                    let mut partial_output: HashMap<String, Vec<String>> = HashMap::new();
+                                    let mut turn_timed_out = false;
+                                    let mut progress_messages = ToolProgressMessages::from_config();
+
+                                    loop {
+                                        let next = match turn_deadline {
+                                            Some(deadline) => match tokio::time::timeout_at(deadline, combined.next()).await {
+                                                Ok(next) => next,
+                                                Err(_) => {
+                                                    turn_timed_out = true;
+                                                    break;
+                                                }
+                                            },
+                                            None => combined.next().await,
+                                        };
+                                        let (request_id, item) = match next {
+                                            Some(next) => next,
+                                            None => break,
+                                        };
                                         for msg in Self::drain_elicitation_messages(&session_config.id).await {
                                             yield AgentEvent::Message(msg);
                                         }
 
                                         match item {
-                                            ToolStreamItem::Result(output) => {
+                                            ToolStreamItem::Result(mut output) => {
+                                                if let Some(progress_messages) = progress_messages.as_mut() {
+                                                    progress_messages.finish(&request_id);
+                                                }
+                                                let tool_call = remaining_requests.iter()
+                                                    .find(|request| request.id == request_id)
+                                                    .and_then(|request| request.tool_call.as_ref().ok());
+                                                if let Some(tool_call) = tool_call {
+                                                    hooks.after_tool(&hook_context, tool_call, &mut output).await;
+                                                    tool_retries.record(&tool_call.name, &mut output);
+                                                }
+                                                if let Some(timeout) = output.as_ref().ok().and_then(timed_out_after) {
+                                                    yield AgentEvent::ToolTimeout {
+                                                        tool_name: requested_tool_name(&remaining_requests, &request_id)
+                                                            .unwrap_or_default()
+                                                            .to_string(),
+                                                        request_id: request_id.clone(),
+                                                        timeout,
+                                                    };
+                                                }
+                                                self.emit_activity(&session_config.id, ActivityEvent::ToolCompleted {
+                                                    request_id: request_id.clone(),
+                                                    tool_name: requested_tool_name(&remaining_requests, &request_id)
+                                                        .unwrap_or_default()
+                                                        .to_string(),
+                                                    is_error: matches!(&output, Err(_) | Ok(CallToolResult { is_error: Some(true), .. })),
+                                                });
                                                 if enable_extension_request_ids.contains(&request_id)
                                                     && output.is_err()
                                                 {
@@ -1131,11 +1879,30 @@ impl Agent {
                                                 if let Some(response_msg) = request_to_response_map.get(&request_id) {
                                                     let metadata = request_metadata.get(&request_id).and_then(|m| m.as_ref());
                                                     let mut response = response_msg.lock().await;
-                                                    *response = response.clone().with_tool_response_with_metadata(request_id, output, metadata);
+                                                    *response = response.clone().with_tool_response_with_metadata(request_id.clone(), output, metadata);
+                                                    if let Err(e) = checkpoint::save_tool_result(&session_config.id, &request_id, &response).await {
+                                                        warn!("Failed to checkpoint the result of tool call {}: {}", request_id, e);
+                                                    }
                                                 }
                                             }
                                             ToolStreamItem::Message(msg) => {
+                                                if turn_deadline.is_some() {
+                                                    partial_output
+                                                        .entry(request_id.clone())
+                                                        .or_default()
+                                                        .extend(notification_text(&msg));
+                                                }
+                                                let progress_message = progress_messages.as_mut().and_then(|progress_messages| {
+                                                    let tool_name = requested_tool_name(&remaining_requests, &request_id).unwrap_or_default();
+                                                    progress_messages.message(&request_id, tool_name, &msg)
+                                                });
                                                 yield AgentEvent::McpNotification((request_id, msg));
+                                                if let Some(text) = progress_message {
+                                                    yield AgentEvent::Message(Message::assistant().with_system_notification(
+                                                        SystemNotificationType::InlineMessage,
+                                                        text,
+                                                    ));
+                                                }
                                             }
                                         }
                                     }
@@ -1145,6 +1912,54 @@ impl Agent {
                                         yield AgentEvent::Message(msg);
                                     }
 
+                                    if let Some(timeout) = tool_timeouts.turn.filter(|_| turn_timed_out) {
+                                        drop(combined);
+                                        let mut timed_out = Vec::new();
+                                        for request in &remaining_requests {
+                                            if let Some(response_msg) = request_to_response_map.get(&request.id) {
+                                                let mut response = response_msg.lock().await;
+                                                if response.content.is_empty() {
+                                                    let tool_name = requested_tool_name(&remaining_requests, &request.id)
+                                                        .unwrap_or_default()
+                                                        .to_string();
+                                                    let partial = partial_output.remove(&request.id).unwrap_or_default();
+                                                    *response = response.clone().with_tool_response_with_metadata(
+                                                        request.id.clone(),
+                                                        Ok(tool_timeout_result(&tool_name, timeout, &partial)),
+                                                        request.metadata.as_ref(),
+                                                    );
+                                                    timed_out.push((request.id.clone(), tool_name));
+                                                }
+                                            }
+                                        }
+                                        for (request_id, tool_name) in timed_out {
+                                            warn!("Tool {} timed out when the turn ran out of {:?}", tool_name, timeout);
+                                            self.emit_activity(&session_config.id, ActivityEvent::ToolCompleted {
+                                                request_id: request_id.clone(),
+                                                tool_name: tool_name.clone(),
+                                                is_error: true,
+                                            });
+                                            yield AgentEvent::ToolTimeout { request_id, tool_name, timeout };
+                                        }
+                                    }
+
+                                    // Tools cut off by cancellation, or never approved, still need a
+                                    // response for the conversation to stay valid
+                                    if is_token_cancelled(&cancel_token) {
+                                        for request in &remaining_requests {
+                                            if let Some(response_msg) = request_to_response_map.get(&request.id) {
+                                                let mut response = response_msg.lock().await;
+                                                if response.content.is_empty() {
+                                                    *response = response.clone().with_tool_response_with_metadata(
+                                                        request.id.clone(),
+                                                        Err(ErrorData::new(ErrorCode::INTERNAL_ERROR, CANCELLED_RESPONSE, None)),
+                                                        request.metadata.as_ref(),
+                                                    );
+                                                }
+                                            }
+                                        }
+                                    }
+
                                     if all_install_successful && !enable_extension_request_ids.is_empty() {
                                         if let Err(e) = self.save_extension_state(&session_config).await {
                                             warn!("Failed to save extension state after runtime changes: {}", e);
@@ -1184,12 +1999,41 @@ impl Agent {
                                         messages_to_add.push(final_response);
                                     }
                                 }
+                                for notice in tool_retries.take_notices() {
+                                    let marker = Message::assistant().with_system_notification(
+                                        SystemNotificationType::InlineMessage,
+                                        notice,
+                                    );
+                                    yield AgentEvent::Message(marker.clone());
+                                    messages_to_add.push(marker);
+                                }
 
                                 no_tools_called = false;
                             }
                         }
                         Err(ref provider_err @ ProviderError::ContextLengthExceeded(_)) => {
                             crate::posthog::emit_error(provider_err.telemetry_type(), &provider_err.to_string());
+
+                            // Dropping old tool outputs and turns is cheaper than summarizing, so try it first
+                            let reduced = (reduction_attempts < CompactionConfig::from_config().reduction_attempts)
+                                .then(|| reduce_context(&conversation))
+                                .flatten();
+                            if let Some((reduced_conversation, reduction)) = reduced {
+                                reduction_attempts += 1;
+                                info!("Context limit exceeded, retrying with reduced context: {}", reduction);
+                                SessionManager::replace_conversation(&session_config.id, &reduced_conversation).await?;
+                                conversation = reduced_conversation;
+                                did_recovery_compact_this_iteration = true;
+                                yield AgentEvent::Message(
+                                    Message::assistant().with_system_notification(
+                                        SystemNotificationType::InlineMessage,
+                                        format!("Context limit reached. {} and retrying...", reduction),
+                                    )
+                                );
+                                yield AgentEvent::HistoryReplaced(conversation.clone());
+                                break;
+                            }
+
                             compaction_attempts += 1;
 
                             if compaction_attempts >= 2 {
@@ -1235,6 +2079,7 @@ impl Agent {
                         Err(ref provider_err) => {
                             crate::posthog::emit_error(provider_err.telemetry_type(), &provider_err.to_string());
                             error!("Error: {}", provider_err);
+                            self.emit_activity(&session_config.id, ActivityEvent::Error { message: provider_err.to_string() });
                             yield AgentEvent::Message(
                                 Message::assistant().with_text(
                                     format!("Ran into this error: {provider_err}.\n\nPlease retry if you think this is a transient or recoverable error.")
@@ -1244,12 +2089,63 @@ impl Agent {
                         }
                     }
                 }
+                self.emit_activity(&session_config.id, ActivityEvent::TurnCompleted { turn: turns_taken });
+                if let Some(veto) = response_veto {
+                    let marker = Message::assistant().with_system_notification(
+                        SystemNotificationType::InlineMessage,
+                        veto,
+                    );
+                    yield AgentEvent::Message(marker.clone());
+                    messages_to_add.push(marker);
+                    for msg in &messages_to_add {
+                        SessionManager::add_message(&session_config.id, msg).await?;
+                    }
+                    if tool_calls_checkpointed {
+                        checkpoint::clear_tool_calls(&session_config.id).await?;
+                    }
+                    break;
+                }
+                if is_token_cancelled(&cancel_token) {
+                    // Keep what was said so far, marking a response cut off mid-stream
+                    if stream.is_stopped() {
+                        if let Some(partial) = messages_to_add.pop() {
+                            let partial = if partial.role == rmcp::model::Role::Assistant {
+                                let metadata = partial.metadata.clone().with_tag(PARTIAL_RESPONSE_TAG);
+                                partial.with_metadata(metadata)
+                            } else {
+                                partial
+                            };
+                            messages_to_add.push(partial);
+                        }
+                    }
+                    // A call cut off before its result came back can't go to the model again
+                    messages_to_add = drop_unanswered_tool_requests(messages_to_add);
+                    let marker = Message::assistant().with_system_notification(
+                        SystemNotificationType::InlineMessage,
+                        TURN_CANCELLED_TEXT,
+                    );
+                    yield AgentEvent::Message(marker.clone());
+                    messages_to_add.push(marker);
+                    for msg in &messages_to_add {
+                        SessionManager::add_message(&session_config.id, msg).await?;
+                    }
+                    if tool_calls_checkpointed {
+                        checkpoint::clear_tool_calls(&session_config.id).await?;
+                    }
+                    break;
+                }
                 if tools_updated {
+                    self.extensions_changed.store(false, Ordering::SeqCst);
                     (tools, toolshim_tools, system_prompt) =
                         self.prepare_tools_and_prompt(&working_dir).await?;
                 }
                 let mut exit_chat = false;
                 if no_tools_called {
+                    if self.final_output_tool.lock().await.is_some() {
+                        let mut messages = conversation.messages().clone();
+                        messages.extend(messages_to_add.iter().cloned());
+                        self.complete_final_answer(&session_config, &system_prompt, &messages).await;
+                    }
                     if let Some(final_output_tool) = self.final_output_tool.lock().await.as_ref() {
                         if final_output_tool.final_output.is_none() {
                             warn!("Final output tool has not been called yet. Continuing agent loop.");
@@ -1260,6 +2156,9 @@ impl Agent {
                             let message = Message::assistant().with_text(final_output_tool.final_output.clone().unwrap());
                             messages_to_add.push(message.clone());
                             yield AgentEvent::Message(message);
+                            if let Some(answer) = final_output_tool.final_value() {
+                                yield AgentEvent::FinalAnswer(answer);
+                            }
                             exit_chat = true;
                         }
                     } else if did_recovery_compact_this_iteration {
@@ -1269,6 +2168,34 @@ impl Agent {
                             Ok(should_retry) => {
                                 if should_retry {
                                     info!("Retry logic triggered, restarting agent loop");
+                                } else if let Some(reflection) = reflection.as_ref().filter(|_| corrections_made < MAX_CORRECTIONS) {
+                                    let mut reviewed = conversation.messages().clone();
+                                    reviewed.extend(messages_to_add.iter().cloned());
+                                    let review = reflection.review(&reviewed).await;
+                                    // The review is paid for like any other request of the session
+                                    if let Ok((_, Some(usage))) = &review {
+                                        Self::update_session_metrics(&session_config, usage, false).await?;
+                                    }
+                                    match review.map(|(critique, _)| critique) {
+                                        Ok(critique) if !critique.satisfied => {
+                                            corrections_made += 1;
+                                            info!("Reflection asked for a correction: {:?}", critique.issues);
+                                            let notice = Message::assistant().with_system_notification(
+                                                SystemNotificationType::InlineMessage,
+                                                REFLECTION_NOTICE_TEXT,
+                                            );
+                                            yield AgentEvent::Message(notice.clone());
+                                            messages_to_add.push(notice);
+                                            messages_to_add.push(
+                                                Message::user().with_text(critique.correction_prompt()).agent_only()
+                                            );
+                                        }
+                                        Ok(_) => exit_chat = true,
+                                        Err(e) => {
+                                            warn!("Reflection failed, keeping the answer: {}", e);
+                                            exit_chat = true;
+                                        }
+                                    }
                                 } else {
                                     exit_chat = true;
                                 }
@@ -1286,9 +2213,22 @@ impl Agent {
                     }
                 }
 
+                if let Some(veto) = hooks.on_turn_end(&hook_context, messages_to_add.messages()).await {
+                    let marker = Message::assistant().with_system_notification(
+                        SystemNotificationType::InlineMessage,
+                        veto,
+                    );
+                    yield AgentEvent::Message(marker.clone());
+                    messages_to_add.push(marker);
+                    exit_chat = true;
+                }
+
                 for msg in &messages_to_add {
                     SessionManager::add_message(&session_config.id, msg).await?;
                 }
+                if tool_calls_checkpointed {
+                    checkpoint::clear_tool_calls(&session_config.id).await?;
+                }
                 conversation.extend(messages_to_add);
                 if exit_chat {
                     break;
@@ -1304,6 +2244,8 @@ impl Agent {
         prompt_manager.add_system_prompt_extra(instruction);
     }
 
+    /// Switch the agent to `provider`. If the session was persisted with a different provider
+    /// or model, its history is re-encoded so the new one accepts it.
     pub async fn update_provider(
         &self,
         provider: Arc<dyn Provider>,
@@ -1312,6 +2254,30 @@ impl Agent {
         let mut current_provider = self.provider.lock().await;
         *current_provider = Some(provider.clone());
 
+        // A session that doesn't exist yet has no history to re-encode
+        if let Ok(session) = SessionManager::get_session(session_id, true).await {
+            let switched = session
+                .provider_name
+                .as_deref()
+                .is_some_and(|name| name != provider.get_name())
+                || session.model_config.as_ref().is_some_and(|config| {
+                    config.model_name != provider.get_model_config().model_name
+                });
+            let reencoded = session
+                .conversation
+                .as_ref()
+                .filter(|_| switched)
+                .and_then(reencode_for_provider_switch);
+            if let Some(conversation) = reencoded {
+                info!(
+                    "Re-encoded session {} history for {}",
+                    session_id,
+                    provider.get_name()
+                );
+                SessionManager::replace_conversation(session_id, &conversation).await?;
+            }
+        }
+
         SessionManager::update_session(session_id)
             .provider_name(provider.get_name())
             .model_config(provider.get_model_config())
@@ -1320,6 +2286,30 @@ impl Agent {
             .context("Failed to persist provider config to session")
     }
 
+    /// Put the session's lead model in front of the provider, which keeps serving as the worker.
+    /// Done once, so the turn count carries over between replies.
+    async fn apply_lead_worker(&self, settings: &LeadWorkerSettings) -> Result<()> {
+        let mut current_provider = self.provider.lock().await;
+        let provider = current_provider
+            .clone()
+            .ok_or_else(|| anyhow!("Provider not set"))?;
+        if settings.is_applied_to(provider.as_ref()) {
+            return Ok(());
+        }
+        if provider.as_lead_worker().is_some() {
+            warn!("Provider already has a lead model; ignoring the session's lead/worker settings");
+            return Ok(());
+        }
+
+        let provider = crate::providers::create_lead_worker(provider, settings).await?;
+        info!(
+            "Using lead model {} for the first {} turns",
+            settings.lead_model, settings.lead_turns
+        );
+        *current_provider = Some(provider);
+        Ok(())
+    }
+
     /// Override the system prompt with a custom template
     pub async fn override_system_prompt(&self, template: String) {
         let mut prompt_manager = self.prompt_manager.lock().await;
@@ -1356,6 +2346,19 @@ impl Agent {
         Err(anyhow!("Prompt '{}' not found", name))
     }
 
+    /// The contents of resource `uri` of extension `extension_name`, ready to attach to a
+    /// user message
+    pub async fn resource_content(
+        &self,
+        extension_name: &str,
+        uri: &str,
+    ) -> Result<Vec<MessageContent>> {
+        self.extension_manager
+            .resource_message_content(extension_name, uri, CancellationToken::default())
+            .await
+            .map_err(|e| anyhow!("Failed to read resource: {}", e.message))
+    }
+
     pub async fn get_plan_prompt(&self) -> Result<String> {
         let tools = self.extension_manager.get_prefixed_tools(None).await?;
         let tools_info = tools
@@ -1378,6 +2381,43 @@ impl Agent {
         Ok(plan_prompt)
     }
 
+    /// Reply to `user_message` in two phases: `planner` first writes a structured plan, which
+    /// this agent then carries out, reporting each step's progress as [`AgentEvent::PlanUpdated`].
+    /// If the planner answers with something other than a plan, such as a clarifying question,
+    /// that answer is the whole reply.
+    pub async fn plan_and_execute(
+        &self,
+        user_message: Message,
+        planner: Arc<dyn Provider>,
+        session_config: SessionConfig,
+        cancel_token: Option<CancellationToken>,
+    ) -> Result<BoxStream<'_, Result<AgentEvent>>> {
+        let session = SessionManager::get_session(&session_config.id, true).await?;
+        let mut messages = session
+            .conversation
+            .map(|conversation| conversation.agent_visible_messages())
+            .unwrap_or_default();
+        messages.push(user_message.clone());
+
+        let plan_prompt = self.get_plan_prompt().await?;
+        let (plan, _) = create_plan(planner.as_ref(), &plan_prompt, &messages).await?;
+        SessionManager::add_message(&session_config.id, &user_message).await?;
+
+        match plan {
+            Ok(plan) => {
+                let instructions = Message::user().with_text(plan.instructions()).agent_only();
+                self.set_plan(Some(plan)).await;
+                self.reply(instructions, session_config, cancel_token).await
+            }
+            Err(answer) => {
+                SessionManager::add_message(&session_config.id, &answer).await?;
+                Ok(Box::pin(stream::once(async move {
+                    Ok(AgentEvent::Message(answer))
+                })))
+            }
+        }
+    }
+
     pub async fn handle_tool_result(&self, id: String, result: ToolResult<CallToolResult>) {
         if let Err(e) = self.tool_result_tx.send((id, result)).await {
             error!("Failed to send tool result: {}", e);
@@ -1592,11 +2632,69 @@ impl Agent {
     }
 }
 
+/// Drops the tool requests that no response came back for, as when a turn is cancelled while
+/// they stream in or run, along with any messages that leaves empty
+fn drop_unanswered_tool_requests(messages: Conversation) -> Conversation {
+    let answered: HashSet<String> = messages
+        .iter()
+        .flat_map(|message| message.get_tool_response_ids())
+        .map(str::to_string)
+        .collect();
+    Conversation::new_unvalidated(messages.into_iter().filter_map(|mut message| {
+        message.content.retain(|content| match content {
+            MessageContent::ToolRequest(request) => answered.contains(&request.id),
+            _ => true,
+        });
+        (!message.content.is_empty()).then_some(message)
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::recipe::Response;
 
+    #[tokio::test]
+    async fn test_final_answer_schema_changes_between_replies() -> Result<()> {
+        let agent = Agent::new();
+        let schema = |field: &str| {
+            serde_json::json!({
+                "type": "object",
+                "properties": {field: {"type": "string"}}
+            })
+        };
+        let final_output_tools = |tools: &[Tool]| {
+            tools
+                .iter()
+                .filter(|tool| tool.name == FINAL_OUTPUT_TOOL_NAME)
+                .map(|tool| Value::Object(tool.input_schema.as_ref().clone()))
+                .collect::<Vec<_>>()
+        };
+
+        agent
+            .require_final_answer(Some(&schema("first_field")))
+            .await?;
+        agent
+            .require_final_answer(Some(&schema("second_field")))
+            .await?;
+        let tools = agent.list_tools(None).await;
+        assert_eq!(final_output_tools(&tools), vec![schema("second_field")]);
+        let system_prompt = agent.prompt_manager.lock().await.builder().build();
+        assert!(system_prompt.contains("second_field"));
+        assert!(!system_prompt.contains("first_field"));
+        assert_eq!(
+            system_prompt.matches("# Final Output Instructions").count(),
+            1
+        );
+
+        agent.require_final_answer(None).await?;
+        let tools = agent.list_tools(None).await;
+        assert!(final_output_tools(&tools).is_empty());
+        let system_prompt = agent.prompt_manager.lock().await.builder().build();
+        assert!(!system_prompt.contains("Final Output Instructions"));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_add_final_output_tool() -> Result<()> {
         let agent = Agent::new();
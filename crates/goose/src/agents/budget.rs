@@ -0,0 +1,253 @@
+//! Spending limits for a session.
+//!
+//! A [`SessionBudget`] caps what a session may cost in USD, priced with the model pricing
+//! table, how many tokens it may use and how many turns the agent may take in it, across all
+//! of its replies. Defaults come from GOOSE_SESSION_MAX_COST, GOOSE_SESSION_MAX_TOKENS and
+//! GOOSE_SESSION_MAX_TURNS, and a session can set its own through
+//! [`SessionConfig::budget`](crate::agents::types::SessionConfig). The agent checks the budget
+//! before each turn and stops with [`AgentEvent::BudgetExceeded`](crate::agents::AgentEvent)
+//! once a limit is reached.
+//!
+//! Subagents spend the budget of the session that started them: their turns and usage are
+//! charged to it as well as to their own session.
+
+use std::fmt;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::providers::base::ProviderUsage;
+use crate::session::extension_data::{BudgetState, ExtensionState};
+use crate::session::{Session, SessionManager};
+
+pub const MAX_COST_CONFIG_KEY: &str = "GOOSE_SESSION_MAX_COST";
+pub const MAX_TOKENS_CONFIG_KEY: &str = "GOOSE_SESSION_MAX_TOKENS";
+pub const MAX_TURNS_CONFIG_KEY: &str = "GOOSE_SESSION_MAX_TURNS";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SessionBudget {
+    /// Most the session may cost, in USD. Only models with known pricing are held to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cost: Option<f64>,
+    /// Most tokens the session may use, input and output together
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<i64>,
+    /// Most turns the agent may take in the session, each one a request to the model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_turns: Option<u32>,
+}
+
+impl SessionBudget {
+    /// The budget set in the config, if any limit is
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        let budget = Self {
+            max_cost: config.get_param(MAX_COST_CONFIG_KEY).ok(),
+            max_tokens: config.get_param(MAX_TOKENS_CONFIG_KEY).ok(),
+            max_turns: config.get_param(MAX_TURNS_CONFIG_KEY).ok(),
+        };
+        (!budget.is_unlimited()).then_some(budget)
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.max_cost.is_none() && self.max_tokens.is_none() && self.max_turns.is_none()
+    }
+
+    /// The first limit `spend` has reached, if any
+    pub fn exceeded_by(&self, spend: &BudgetSpend) -> Option<BudgetLimit> {
+        if let (Some(max_cost), Some(cost)) = (self.max_cost, spend.cost) {
+            if cost >= max_cost {
+                return Some(BudgetLimit::Cost);
+            }
+        }
+        if self.max_tokens.is_some_and(|max| spend.tokens >= max) {
+            return Some(BudgetLimit::Tokens);
+        }
+        if self.max_turns.is_some_and(|max| spend.turns >= max) {
+            return Some(BudgetLimit::Turns);
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetLimit {
+    Cost,
+    Tokens,
+    Turns,
+}
+
+/// What a session has used so far
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BudgetSpend {
+    /// Cost in USD, if the pricing of the models used is known
+    pub cost: Option<f64>,
+    pub tokens: i64,
+    pub turns: u32,
+}
+
+impl BudgetSpend {
+    pub fn new(session: &Session, state: &BudgetState) -> Self {
+        Self {
+            cost: session.accumulated_cost,
+            tokens: session.accumulated_total_tokens.unwrap_or(0).into(),
+            turns: state.turns_taken,
+        }
+    }
+}
+
+impl fmt::Display for BudgetSpend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.cost {
+            Some(cost) => write!(f, "${:.4}", cost)?,
+            None => write!(f, "unknown cost")?,
+        }
+        write!(f, ", {} tokens, {} turns", self.tokens, self.turns)
+    }
+}
+
+/// Why the agent stopped: the limit that was reached, and what had been spent by then
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BudgetExceeded {
+    pub limit: BudgetLimit,
+    pub budget: SessionBudget,
+    pub spend: BudgetSpend,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.limit {
+            BudgetLimit::Cost => write!(
+                f,
+                "Stopped: this session reached its cost limit of ${:.2}",
+                self.budget.max_cost.unwrap_or_default()
+            )?,
+            BudgetLimit::Tokens => write!(
+                f,
+                "Stopped: this session reached its limit of {} tokens",
+                self.budget.max_tokens.unwrap_or_default()
+            )?,
+            BudgetLimit::Turns => write!(
+                f,
+                "Stopped: this session reached its limit of {} turns",
+                self.budget.max_turns.unwrap_or_default()
+            )?,
+        }
+        write!(f, ". Spent {}.", self.spend)
+    }
+}
+
+/// Count a turn of session `session_id` against `budget`, or say which limit keeps it from
+/// being taken. Turns are only counted while a budget is set.
+pub(crate) async fn charge_turn(
+    session_id: &str,
+    budget: &SessionBudget,
+) -> Result<Option<BudgetExceeded>> {
+    let mut session = SessionManager::get_session(session_id, false).await?;
+    let mut state = BudgetState::from_extension_data(&session.extension_data).unwrap_or_default();
+
+    let spend = BudgetSpend::new(&session, &state);
+    if let Some(limit) = budget.exceeded_by(&spend) {
+        return Ok(Some(BudgetExceeded {
+            limit,
+            budget: *budget,
+            spend,
+        }));
+    }
+
+    state.turns_taken += 1;
+    state.budget = Some(*budget);
+    state.to_extension_data(&mut session.extension_data)?;
+    SessionManager::update_session(session_id)
+        .extension_data(session.extension_data)
+        .apply()
+        .await?;
+    Ok(None)
+}
+
+/// The budget session `session_id` last ran under, if it ran under one
+pub(crate) async fn budget_of(session_id: &str) -> Result<Option<SessionBudget>> {
+    let session = SessionManager::get_session(session_id, false).await?;
+    Ok(BudgetState::from_extension_data(&session.extension_data).and_then(|state| state.budget))
+}
+
+/// Add `usage` to what session `session_id` has spent, for usage that was recorded on one of
+/// its subagents
+pub(crate) async fn charge_usage(session_id: &str, usage: &ProviderUsage) -> Result<()> {
+    let session = SessionManager::get_session(session_id, false).await?;
+    let add = |a: Option<i32>, b: Option<i32>| match (a, b) {
+        (Some(x), Some(y)) => Some(x + y),
+        _ => a.or(b),
+    };
+    let accumulated_cost = match (session.accumulated_cost, usage.cost) {
+        (Some(x), Some(y)) => Some(x + y),
+        (a, b) => a.or(b),
+    };
+    SessionManager::update_session(session_id)
+        .accumulated_total_tokens(add(
+            session.accumulated_total_tokens,
+            usage.usage.total_tokens,
+        ))
+        .accumulated_input_tokens(add(
+            session.accumulated_input_tokens,
+            usage.usage.input_tokens,
+        ))
+        .accumulated_output_tokens(add(
+            session.accumulated_output_tokens,
+            usage.usage.output_tokens,
+        ))
+        .accumulated_cost(accumulated_cost)
+        .apply()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_limits() {
+        let budget = SessionBudget {
+            max_cost: Some(1.0),
+            max_tokens: Some(10_000),
+            max_turns: Some(20),
+        };
+        let spend = BudgetSpend {
+            cost: Some(0.25),
+            tokens: 4_000,
+            turns: 5,
+        };
+        assert_eq!(budget.exceeded_by(&spend), None);
+
+        let over_tokens = BudgetSpend {
+            tokens: 10_000,
+            ..spend
+        };
+        assert_eq!(budget.exceeded_by(&over_tokens), Some(BudgetLimit::Tokens));
+
+        // Without pricing the cost limit can't be checked, but the others still are
+        let unpriced = BudgetSpend {
+            cost: None,
+            turns: 20,
+            ..spend
+        };
+        assert_eq!(budget.exceeded_by(&unpriced), Some(BudgetLimit::Turns));
+
+        let exceeded = BudgetExceeded {
+            limit: BudgetLimit::Cost,
+            budget,
+            spend: BudgetSpend {
+                cost: Some(1.5),
+                ..spend
+            },
+        };
+        assert_eq!(
+            exceeded.to_string(),
+            "Stopped: this session reached its cost limit of $1.00. \
+             Spent $1.5000, 4000 tokens, 5 turns."
+        );
+    }
+}
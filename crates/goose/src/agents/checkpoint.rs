@@ -0,0 +1,198 @@
+//! Crash-safe checkpoints of agent runs.
+//!
+//! The conversation of a session is saved once each turn is over. What happens during a turn,
+//! such as the tool calls the model made and the results of those that finished, is kept in a
+//! [`RunCheckpoint`] in the session's extension data, along with the plan the agent is carrying
+//! out. Budgets need nothing more, since [`BudgetState`](crate::session::BudgetState) is already
+//! saved as each turn starts. If the process dies mid-turn,
+//! [`Agent::resume`](crate::agents::Agent::resume) puts the finished results into the
+//! conversation, reports the unfinished calls as interrupted, and carries on from there.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use rmcp::model::{ErrorCode, ErrorData};
+
+use crate::agents::plan::Plan;
+use crate::conversation::message::{Message, MessageContent};
+use crate::session::extension_data::{ExtensionState, PendingToolCall, RunCheckpoint};
+use crate::session::SessionManager;
+
+pub const INTERRUPTED_RESPONSE: &str =
+    "This tool call was interrupted when goose stopped before it finished, so its outcome is \
+     unknown. Check its effects or run it again if it's still needed.";
+
+pub async fn load(session_id: &str) -> Result<RunCheckpoint> {
+    let session = SessionManager::get_session(session_id, false).await?;
+    Ok(RunCheckpoint::from_extension_data(&session.extension_data).unwrap_or_default())
+}
+
+async fn update(session_id: &str, change: impl FnOnce(&mut RunCheckpoint)) -> Result<()> {
+    let mut session = SessionManager::get_session(session_id, false).await?;
+    let mut checkpoint =
+        RunCheckpoint::from_extension_data(&session.extension_data).unwrap_or_default();
+    change(&mut checkpoint);
+    checkpoint.to_extension_data(&mut session.extension_data)?;
+    SessionManager::update_session(session_id)
+        .extension_data(session.extension_data)
+        .apply()
+        .await
+}
+
+/// Record the tool calls the model just made, before any of them runs. `requests` holds the
+/// request id and the assistant message making the call for each.
+pub(crate) async fn save_tool_calls(
+    session_id: &str,
+    requests: impl IntoIterator<Item = (String, Message)>,
+) -> Result<()> {
+    let pending_tool_calls = requests
+        .into_iter()
+        .map(|(request_id, request)| PendingToolCall {
+            request_id,
+            request,
+            response: None,
+        })
+        .collect();
+    update(session_id, |checkpoint| {
+        checkpoint.pending_tool_calls = pending_tool_calls
+    })
+    .await
+}
+
+/// Record the finished result of tool call `request_id`
+pub(crate) async fn save_tool_result(
+    session_id: &str,
+    request_id: &str,
+    response: &Message,
+) -> Result<()> {
+    update(session_id, |checkpoint| {
+        if let Some(call) = checkpoint
+            .pending_tool_calls
+            .iter_mut()
+            .find(|call| call.request_id == request_id)
+        {
+            call.response = Some(response.clone());
+        }
+    })
+    .await
+}
+
+/// Forget the turn's tool calls, once the conversation has them
+pub(crate) async fn clear_tool_calls(session_id: &str) -> Result<()> {
+    update(session_id, |checkpoint| {
+        checkpoint.pending_tool_calls.clear()
+    })
+    .await
+}
+
+pub(crate) async fn save_plan(session_id: &str, plan: Option<Plan>) -> Result<()> {
+    update(session_id, |checkpoint| checkpoint.plan = plan).await
+}
+
+/// The messages that bring a conversation up to date with the tool calls of an interrupted
+/// turn: each call followed by its result, or by an error for calls that never finished
+pub fn recovered_messages(pending_tool_calls: &[PendingToolCall]) -> Vec<Message> {
+    pending_tool_calls
+        .iter()
+        .flat_map(|call| {
+            let response = call.response.clone().unwrap_or_else(|| {
+                Message::user().with_tool_response(
+                    call.request_id.clone(),
+                    Err(ErrorData::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        INTERRUPTED_RESPONSE,
+                        None,
+                    )),
+                )
+            });
+            [call.request.clone(), response]
+        })
+        .collect()
+}
+
+/// Put the tool calls of an interrupted turn of session `session_id` into its conversation.
+/// Returns the checkpoint as it was, so the caller can restore the rest of the run.
+pub(crate) async fn recover(session_id: &str) -> Result<RunCheckpoint> {
+    let session = SessionManager::get_session(session_id, true).await?;
+    let checkpoint =
+        RunCheckpoint::from_extension_data(&session.extension_data).unwrap_or_default();
+    if checkpoint.pending_tool_calls.is_empty() {
+        return Ok(checkpoint);
+    }
+
+    // The turn may have been saved after all, if the process stopped right before the
+    // checkpoint was cleared
+    let answered: HashSet<&str> = session
+        .conversation
+        .iter()
+        .flat_map(|conversation| conversation.iter())
+        .flat_map(|message| &message.content)
+        .filter_map(|content| match content {
+            MessageContent::ToolResponse(response) => Some(response.id.as_str()),
+            _ => None,
+        })
+        .collect();
+    let unanswered: Vec<PendingToolCall> = checkpoint
+        .pending_tool_calls
+        .iter()
+        .filter(|call| !answered.contains(call.request_id.as_str()))
+        .cloned()
+        .collect();
+
+    tracing::info!(
+        "Recovering {} tool calls of an interrupted turn in session {}",
+        unanswered.len(),
+        session_id
+    );
+    for message in recovered_messages(&unanswered) {
+        SessionManager::add_message(session_id, &message).await?;
+    }
+    clear_tool_calls(session_id).await?;
+    Ok(checkpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::{CallToolRequestParam, CallToolResult, Content};
+
+    fn call(id: &str, finished: bool) -> PendingToolCall {
+        PendingToolCall {
+            request_id: id.to_string(),
+            request: Message::assistant().with_tool_request(
+                id,
+                Ok(CallToolRequestParam {
+                    name: "developer__shell".into(),
+                    arguments: None,
+                }),
+            ),
+            response: finished.then(|| {
+                Message::user()
+                    .with_tool_response(id, Ok(CallToolResult::success(vec![Content::text("ok")])))
+            }),
+        }
+    }
+
+    #[test]
+    fn test_recovered_messages_reissue_finished_results() {
+        let checkpoint = RunCheckpoint {
+            plan: None,
+            pending_tool_calls: vec![call("call_1", true), call("call_2", false)],
+        };
+        let mut extension_data = Default::default();
+        checkpoint.to_extension_data(&mut extension_data).unwrap();
+        let checkpoint = RunCheckpoint::from_extension_data(&extension_data).unwrap();
+
+        let messages = recovered_messages(&checkpoint.pending_tool_calls);
+        assert_eq!(messages.len(), 4);
+        let result = |message: &Message| match &message.content[0] {
+            MessageContent::ToolResponse(response) => response.tool_result.clone(),
+            other => panic!("expected a tool response, got {:?}", other),
+        };
+        assert!(result(&messages[1]).is_ok());
+        assert_eq!(
+            result(&messages[3]).unwrap_err().message,
+            INTERRUPTED_RESPONSE
+        );
+    }
+}
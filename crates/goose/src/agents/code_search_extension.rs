@@ -0,0 +1,303 @@
+use crate::agents::extension::PlatformExtensionContext;
+use crate::agents::mcp_client::{Error, McpClientTrait};
+use crate::agents::workspace_roots::session_roots;
+use crate::code_search::{search, SearchOptions};
+use anyhow::Result;
+use async_trait::async_trait;
+use indoc::indoc;
+use rmcp::model::{
+    CallToolResult, Content, GetPromptResult, Implementation, InitializeResult, JsonObject,
+    ListPromptsResult, ListResourcesResult, ListToolsResult, ProtocolVersion, ReadResourceResult,
+    ServerCapabilities, ServerNotification, Tool, ToolAnnotations, ToolsCapability,
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+pub static EXTENSION_NAME: &str = "code_search";
+
+const DEFAULT_CONTEXT_LINES: usize = 2;
+const MAX_CONTEXT_LINES: usize = 10;
+const DEFAULT_MAX_RESULTS: usize = 50;
+const MAX_MAX_RESULTS: usize = 500;
+
+/// Parameters for the search tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct SearchParams {
+    /// Regex to search for, or plain text if literal is set
+    pattern: String,
+    /// Directory to search, relative to the working directory (default: the working directory).
+    /// It must be inside the workspace roots.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    /// Match the pattern as plain text rather than as a regex
+    #[serde(default)]
+    literal: bool,
+    /// Whether case matters (default: only if the pattern has capitals)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    case_sensitive: Option<bool>,
+    /// Globs of files to search, such as '*.rs' or 'src/**'; prefix with '!' to exclude
+    #[serde(default)]
+    globs: Vec<String>,
+    /// Lines of context around each match (default: 2, max: 10)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    context_lines: Option<usize>,
+    /// Max matching lines to show (default: 50, max: 500)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_results: Option<usize>,
+}
+
+pub struct CodeSearchClient {
+    info: InitializeResult,
+    context: PlatformExtensionContext,
+}
+
+impl CodeSearchClient {
+    pub fn new(context: PlatformExtensionContext) -> Result<Self> {
+        let info = InitializeResult {
+            protocol_version: ProtocolVersion::V_2025_03_26,
+            capabilities: ServerCapabilities {
+                tools: Some(ToolsCapability {
+                    list_changed: Some(false),
+                }),
+                resources: None,
+                prompts: None,
+                completions: None,
+                experimental: None,
+                logging: None,
+            },
+            server_info: Implementation {
+                name: EXTENSION_NAME.to_string(),
+                title: Some("Code Search".to_string()),
+                version: "1.0.0".to_string(),
+                icons: None,
+                website_url: None,
+            },
+            instructions: Some(
+                indoc! {r#"
+                Code Search
+
+                Use search to find code in the workspace, rather than running grep or find in a
+                shell. It skips ignored, hidden and binary files and ranks the files most
+                likely to matter first. Narrow large searches with globs.
+            "#}
+                .to_string(),
+            ),
+        };
+
+        Ok(Self { info, context })
+    }
+
+    /// The workspace roots of the session, the first being its working directory
+    fn workspace_roots(&self) -> Result<Vec<PathBuf>, String> {
+        let roots = self
+            .context
+            .extension_manager
+            .as_ref()
+            .and_then(|weak| weak.upgrade())
+            .map(|extension_manager| extension_manager.workspace_roots())
+            .unwrap_or_default();
+        if !roots.is_empty() {
+            return Ok(roots);
+        }
+        let working_dir = std::env::current_dir().map_err(|e| e.to_string())?;
+        Ok(session_roots(&working_dir))
+    }
+
+    async fn handle_search(&self, arguments: Option<JsonObject>) -> Result<Vec<Content>, String> {
+        let arguments = arguments.ok_or("Missing arguments")?;
+        let params: SearchParams =
+            serde_json::from_value(Value::Object(arguments)).map_err(|e| e.to_string())?;
+
+        let roots = self.workspace_roots()?;
+        let working_dir = &roots[0];
+        let requested = match &params.path {
+            Some(path) => working_dir.join(shellexpand::tilde(path).as_ref()),
+            None => working_dir.clone(),
+        };
+        let root = requested
+            .canonicalize()
+            .map_err(|_| format!("{} is not a directory", requested.display()))?;
+        if !roots
+            .iter()
+            .any(|workspace_root| root.starts_with(workspace_root))
+        {
+            return Err(format!(
+                "{} is outside the workspace roots: {}",
+                root.display(),
+                roots
+                    .iter()
+                    .map(|workspace_root| workspace_root.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if !root.is_dir() {
+            return Err(format!("{} is not a directory", root.display()));
+        }
+        let options = SearchOptions {
+            literal: params.literal,
+            case_sensitive: params.case_sensitive,
+            globs: params.globs,
+            context_lines: params
+                .context_lines
+                .unwrap_or(DEFAULT_CONTEXT_LINES)
+                .min(MAX_CONTEXT_LINES),
+            max_results: params
+                .max_results
+                .unwrap_or(DEFAULT_MAX_RESULTS)
+                .clamp(1, MAX_MAX_RESULTS),
+            ..SearchOptions::new(params.pattern)
+        };
+
+        let (root, output) =
+            tokio::task::spawn_blocking(move || -> anyhow::Result<(PathBuf, String)> {
+                let results = search(&root, &options)?;
+                Ok((root, results.format(&options)))
+            })
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+        Ok(vec![Content::text(format!(
+            "Searched {}\n\n{}",
+            root.display(),
+            output
+        ))])
+    }
+
+    fn get_tools() -> Vec<Tool> {
+        let schema = schema_for!(SearchParams);
+        let schema_value =
+            serde_json::to_value(schema).expect("Failed to serialize SearchParams schema");
+
+        vec![Tool::new(
+            "search".to_string(),
+            indoc! {r#"
+                Search the contents of files in the workspace for a regex or plain text.
+
+                Results list the matching lines of each file with their line numbers, most
+                relevant files first. Matching lines are marked with ':' and context lines
+                with '-'. Ignored, hidden and binary files are skipped.
+            "#}
+            .to_string(),
+            schema_value.as_object().unwrap().clone(),
+        )
+        .annotate(ToolAnnotations {
+            title: Some("Search code".to_string()),
+            read_only_hint: Some(true),
+            destructive_hint: Some(false),
+            idempotent_hint: Some(true),
+            open_world_hint: Some(false),
+        })]
+    }
+}
+
+#[async_trait]
+impl McpClientTrait for CodeSearchClient {
+    async fn list_resources(
+        &self,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListResourcesResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn read_resource(
+        &self,
+        _uri: &str,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ReadResourceResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn list_tools(
+        &self,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListToolsResult, Error> {
+        Ok(ListToolsResult {
+            tools: Self::get_tools(),
+            next_cursor: None,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        name: &str,
+        arguments: Option<JsonObject>,
+        cancellation_token: CancellationToken,
+    ) -> Result<CallToolResult, Error> {
+        let content = match name {
+            "search" => tokio::select! {
+                content = self.handle_search(arguments) => content,
+                _ = cancellation_token.cancelled() => Err("Search cancelled".to_string()),
+            },
+            _ => Err(format!("Unknown tool: {}", name)),
+        };
+
+        match content {
+            Ok(content) => Ok(CallToolResult::success(content)),
+            Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Error: {}",
+                error
+            ))])),
+        }
+    }
+
+    async fn list_prompts(
+        &self,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListPromptsResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn get_prompt(
+        &self,
+        _name: &str,
+        _arguments: Value,
+        _cancellation_token: CancellationToken,
+    ) -> Result<GetPromptResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn subscribe(&self) -> mpsc::Receiver<ServerNotification> {
+        mpsc::channel(1).1
+    }
+
+    fn get_info(&self) -> Option<&InitializeResult> {
+        Some(&self.info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn arguments(path: &str) -> Option<JsonObject> {
+        json!({"pattern": "fn", "path": path}).as_object().cloned()
+    }
+
+    #[tokio::test]
+    async fn test_search_stays_within_the_workspace_roots() {
+        let client = CodeSearchClient::new(PlatformExtensionContext {
+            session_id: None,
+            extension_manager: None,
+        })
+        .unwrap();
+
+        for path in ["..", "/"] {
+            let error = client.handle_search(arguments(path)).await.unwrap_err();
+            assert!(error.contains("outside the workspace roots"), "{}", error);
+        }
+        assert!(client
+            .handle_search(arguments("src/missing"))
+            .await
+            .is_err());
+        assert!(client.handle_search(arguments("src")).await.is_ok());
+    }
+}
@@ -0,0 +1,144 @@
+//! Previewing what an agent would do.
+//!
+//! In dry-run mode (GOOSE_MODE `dry_run`) tools that only read run as usual, so the agent can
+//! still look around, but calls that may change something are reported back as what would have
+//! run instead of running. A call only runs if its tool is annotated read-only, since neither a
+//! tool's name nor its arguments say what it will do: `postgres__query` can delete rows. Calls
+//! with a `method` argument other than GET, HEAD or OPTIONS, such as HTTP POSTs, are reported
+//! even for read-only tools.
+
+use rmcp::model::{CallToolRequestParam, Tool};
+use serde_json::Value;
+
+use crate::conversation::message::ToolRequest;
+
+const READ_METHODS: &[&str] = &["GET", "HEAD", "OPTIONS"];
+
+pub const DRY_RUN_RESPONSE: &str = "This is a dry run, so the tool call was not executed. \
+    Continue with the plan as if it had succeeded where you can, and tell the user what you \
+    would have done.";
+
+/// Whether `tool_call` may change something, and so isn't run in a dry run
+pub fn is_mutating(tool_call: &CallToolRequestParam, tools: &[Tool]) -> bool {
+    let method = tool_call
+        .arguments
+        .as_ref()
+        .and_then(|arguments| arguments.get("method"))
+        .and_then(Value::as_str);
+    if let Some(method) = method {
+        if !READ_METHODS.contains(&method.to_uppercase().as_str()) {
+            return true;
+        }
+    }
+
+    let read_only = tools
+        .iter()
+        .find(|tool| tool.name == tool_call.name)
+        .and_then(|tool| tool.annotations.as_ref())
+        .and_then(|annotations| annotations.read_only_hint);
+    read_only != Some(true)
+}
+
+/// The requests to run and those only to report, in that order. Requests that failed to parse
+/// are run, so the model gets the error.
+pub fn split_requests(
+    requests: &[ToolRequest],
+    tools: &[Tool],
+) -> (Vec<ToolRequest>, Vec<ToolRequest>) {
+    requests.iter().cloned().partition(|request| {
+        request
+            .tool_call
+            .as_ref()
+            .map_or(true, |tool_call| !is_mutating(tool_call, tools))
+    })
+}
+
+/// What the call would have done, for the model and the user
+pub fn dry_run_report(tool_call: &CallToolRequestParam) -> String {
+    let arguments = tool_call
+        .arguments
+        .as_ref()
+        .map(|arguments| serde_json::to_string(arguments).unwrap_or_default())
+        .unwrap_or_else(|| "{}".to_string());
+    format!(
+        "Would execute {} with {}\n\n{}",
+        tool_call.name, arguments, DRY_RUN_RESPONSE
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::{object, ToolAnnotations};
+    use serde_json::json;
+
+    fn call(name: &str, arguments: Value) -> CallToolRequestParam {
+        CallToolRequestParam {
+            name: name.to_string().into(),
+            arguments: Some(object(arguments)),
+        }
+    }
+
+    fn tool(name: &str, read_only: bool) -> Tool {
+        Tool::new(name.to_string(), "", rmcp::object!({"type": "object"})).annotate(
+            ToolAnnotations {
+                title: None,
+                read_only_hint: Some(read_only),
+                destructive_hint: None,
+                idempotent_hint: None,
+                open_world_hint: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_unannotated_tools_are_mutating() {
+        assert!(is_mutating(
+            &call("developer__shell", json!({"command": "ls"})),
+            &[]
+        ));
+        assert!(is_mutating(&call("files__list_files", json!({})), &[]));
+        assert!(is_mutating(
+            &call("postgres__query", json!({"sql": "DELETE FROM users"})),
+            &[]
+        ));
+        assert!(is_mutating(
+            &call(
+                "developer__text_editor",
+                json!({"command": "view", "path": "a.rs"})
+            ),
+            &[]
+        ));
+    }
+
+    #[test]
+    fn test_annotations_and_http_methods() {
+        let tools = [tool("notes__save", true), tool("notes__get", false)];
+        assert!(!is_mutating(&call("notes__save", json!({})), &tools));
+        assert!(is_mutating(&call("notes__get", json!({})), &tools));
+        assert!(is_mutating(
+            &call(
+                "notes__save",
+                json!({"method": "post", "url": "https://example.com"})
+            ),
+            &tools
+        ));
+        assert!(!is_mutating(
+            &call(
+                "notes__save",
+                json!({"method": "GET", "url": "https://example.com"})
+            ),
+            &tools
+        ));
+    }
+
+    #[test]
+    fn test_dry_run_report() {
+        let report = dry_run_report(&call(
+            "developer__shell",
+            json!({"command": "rm -rf build"}),
+        ));
+        assert!(report
+            .starts_with("Would execute developer__shell with {\"command\":\"rm -rf build\"}"));
+    }
+}
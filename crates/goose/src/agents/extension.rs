@@ -1,6 +1,9 @@
 use crate::agents::chatrecall_extension;
 use crate::agents::code_execution_extension;
+use crate::agents::code_search_extension;
 use crate::agents::extension_manager_extension;
+use crate::agents::fetch_extension;
+use crate::agents::memory_extension;
 use crate::agents::skills_extension;
 use crate::agents::todo_extension;
 use std::collections::HashMap;
@@ -67,6 +70,17 @@ pub static PLATFORM_EXTENSIONS: Lazy<HashMap<&'static str, PlatformExtensionDef>
             },
         );
 
+        map.insert(
+            memory_extension::EXTENSION_NAME,
+            PlatformExtensionDef {
+                name: memory_extension::EXTENSION_NAME,
+                description:
+                    "Remember facts and preferences across sessions, recalled by relevance using embeddings",
+                default_enabled: false,
+                client_factory: |ctx| Box::new(memory_extension::MemoryClient::new(ctx).unwrap()),
+            },
+        );
+
         map.insert(
             "extensionmanager",
             PlatformExtensionDef {
@@ -88,6 +102,28 @@ pub static PLATFORM_EXTENSIONS: Lazy<HashMap<&'static str, PlatformExtensionDef>
             },
         );
 
+        map.insert(
+            code_search_extension::EXTENSION_NAME,
+            PlatformExtensionDef {
+                name: code_search_extension::EXTENSION_NAME,
+                description: "Search the files of the workspace, ranking the most relevant first",
+                default_enabled: false,
+                client_factory: |ctx| {
+                    Box::new(code_search_extension::CodeSearchClient::new(ctx).unwrap())
+                },
+            },
+        );
+
+        map.insert(
+            fetch_extension::EXTENSION_NAME,
+            PlatformExtensionDef {
+                name: fetch_extension::EXTENSION_NAME,
+                description: "Fetch web pages and read their main content as Markdown",
+                default_enabled: false,
+                client_factory: |ctx| Box::new(fetch_extension::FetchClient::new(ctx).unwrap()),
+            },
+        );
+
         map.insert(
             code_execution_extension::EXTENSION_NAME,
             PlatformExtensionDef {
@@ -229,6 +265,20 @@ impl Envs {
     }
 }
 
+/// What a WASM extension may access. Without grants it can only compute and talk MCP.
+#[derive(Debug, Clone, Deserialize, Serialize, Default, ToSchema, PartialEq)]
+pub struct WasmCapabilities {
+    /// Directories the extension may read, under the same paths as on the host
+    #[serde(default)]
+    pub read_dirs: Vec<String>,
+    /// Directories the extension may read and write, under the same paths as on the host
+    #[serde(default)]
+    pub write_dirs: Vec<String>,
+    /// Whether the extension may open network connections and look up host names
+    #[serde(default)]
+    pub network: bool,
+}
+
 /// Represents the different types of MCP extensions that can be added to the manager
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema, PartialEq)]
 #[serde(tag = "type")]
@@ -363,6 +413,28 @@ pub enum ExtensionConfig {
         #[serde(default)]
         available_tools: Vec<String>,
     },
+    /// A WASI component speaking MCP over stdio, run sandboxed inside goose
+    #[serde(rename = "wasm")]
+    Wasm {
+        /// The name used to identify this extension
+        name: String,
+        #[serde(deserialize_with = "deserialize_null_with_default")]
+        #[schema(required)]
+        description: String,
+        /// Path to the compiled component
+        path: String,
+        #[serde(default)]
+        capabilities: WasmCapabilities,
+        #[serde(default)]
+        envs: Envs,
+        #[serde(default)]
+        env_keys: Vec<String>,
+        timeout: Option<u64>,
+        #[serde(default)]
+        bundled: Option<bool>,
+        #[serde(default)]
+        available_tools: Vec<String>,
+    },
 }
 
 impl Default for ExtensionConfig {
@@ -446,6 +518,25 @@ impl ExtensionConfig {
         }
     }
 
+    pub fn wasm<S: Into<String>, T: Into<u64>>(
+        name: S,
+        path: S,
+        description: S,
+        timeout: T,
+    ) -> Self {
+        Self::Wasm {
+            name: name.into(),
+            path: path.into(),
+            capabilities: WasmCapabilities::default(),
+            envs: Envs::default(),
+            env_keys: Vec::new(),
+            description: description.into(),
+            timeout: Some(timeout.into()),
+            bundled: None,
+            available_tools: Vec::new(),
+        }
+    }
+
     pub fn with_args<I, S>(self, args: I) -> Self
     where
         I: IntoIterator<Item = S>,
@@ -492,6 +583,7 @@ impl ExtensionConfig {
             Self::Platform { name, .. } => name,
             Self::Frontend { name, .. } => name,
             Self::InlinePython { name, .. } => name,
+            Self::Wasm { name, .. } => name,
         }
         .to_string()
     }
@@ -517,6 +609,9 @@ impl ExtensionConfig {
             | Self::InlinePython {
                 available_tools, ..
             }
+            | Self::Wasm {
+                available_tools, ..
+            }
             | Self::Frontend {
                 available_tools, ..
             } => available_tools,
@@ -526,6 +621,47 @@ impl ExtensionConfig {
         // If tools are specified, only those tools are available
         available_tools.is_empty() || available_tools.contains(&tool_name.to_string())
     }
+
+    /// Limit the tools offered to the LLM to those of `tool_names` this extension already
+    /// makes available. Returns false, leaving the extension as it was, if there are none:
+    /// an empty list would make all of its tools available, so it should not be enabled.
+    pub fn restrict_tools(&mut self, tool_names: &[String]) -> bool {
+        let allowed: Vec<String> = tool_names
+            .iter()
+            .filter(|tool_name| self.is_tool_available(tool_name))
+            .cloned()
+            .collect();
+        if allowed.is_empty() {
+            return false;
+        }
+        match self {
+            Self::Sse {
+                available_tools, ..
+            }
+            | Self::StreamableHttp {
+                available_tools, ..
+            }
+            | Self::Stdio {
+                available_tools, ..
+            }
+            | Self::Builtin {
+                available_tools, ..
+            }
+            | Self::Platform {
+                available_tools, ..
+            }
+            | Self::InlinePython {
+                available_tools, ..
+            }
+            | Self::Wasm {
+                available_tools, ..
+            }
+            | Self::Frontend {
+                available_tools, ..
+            } => *available_tools = allowed,
+        }
+        true
+    }
 }
 
 impl std::fmt::Display for ExtensionConfig {
@@ -548,6 +684,7 @@ impl std::fmt::Display for ExtensionConfig {
             ExtensionConfig::InlinePython { name, code, .. } => {
                 write!(f, "InlinePython({}: {} chars)", name, code.len())
             }
+            ExtensionConfig::Wasm { name, path, .. } => write!(f, "Wasm({}: {})", name, path),
         }
     }
 }
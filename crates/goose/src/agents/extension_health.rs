@@ -0,0 +1,146 @@
+//! Health of running extensions.
+//!
+//! While an agent has extensions, a monitor pings each of them every
+//! GOOSE_EXTENSION_HEALTH_CHECK_INTERVAL seconds (30 by default, 0 turns it off). An extension
+//! busy with a request counts as alive. Tools of an extension that doesn't answer are hidden
+//! from the model until it answers again. Extensions that goose runs itself, as child processes
+//! or WASM components, are restarted instead, with exponential backoff between restarts, until
+//! they have been restarted GOOSE_EXTENSION_MAX_RESTARTS times. Every change of health is broadcast as an
+//! [`ExtensionHealthEvent`].
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::TryRecvError;
+
+use crate::agents::extension::ExtensionConfig;
+use crate::config::Config;
+
+pub const HEALTH_CHECK_INTERVAL_CONFIG_KEY: &str = "GOOSE_EXTENSION_HEALTH_CHECK_INTERVAL";
+pub const MAX_RESTARTS_CONFIG_KEY: &str = "GOOSE_EXTENSION_MAX_RESTARTS";
+pub const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+pub const DEFAULT_MAX_RESTARTS: u32 = 5;
+/// How long an extension has to answer a ping
+pub const PING_TIMEOUT: Duration = Duration::from_secs(10);
+const MIN_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+
+/// How often extensions are checked, None if they aren't
+pub fn health_check_interval() -> Option<Duration> {
+    let seconds: u64 = Config::global()
+        .get_param(HEALTH_CHECK_INTERVAL_CONFIG_KEY)
+        .unwrap_or(DEFAULT_HEALTH_CHECK_INTERVAL.as_secs());
+    (seconds > 0).then(|| Duration::from_secs(seconds))
+}
+
+pub fn max_restarts() -> u32 {
+    Config::global()
+        .get_param(MAX_RESTARTS_CONFIG_KEY)
+        .unwrap_or(DEFAULT_MAX_RESTARTS)
+}
+
+/// How long to wait after restart number `restarts` before the next one
+pub fn restart_delay(restarts: u32) -> Duration {
+    MIN_RESTART_DELAY
+        .saturating_mul(2u32.saturating_pow(restarts.saturating_sub(1)))
+        .min(MAX_RESTART_DELAY)
+}
+
+/// Whether goose can restart the extension, which it can for the ones it runs itself
+pub fn is_restartable(config: &ExtensionConfig) -> bool {
+    matches!(
+        config,
+        ExtensionConfig::Stdio { .. }
+            | ExtensionConfig::Builtin { .. }
+            | ExtensionConfig::InlinePython { .. }
+            | ExtensionConfig::Wasm { .. }
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "status")]
+pub enum ExtensionHealth {
+    Healthy,
+    /// Not answering, and not something goose can restart
+    Unresponsive,
+    Restarting {
+        attempt: u32,
+    },
+    /// Restarted as many times as allowed and still failing
+    Dead,
+}
+
+impl ExtensionHealth {
+    /// Whether the model may use the extension's tools
+    pub fn is_available(&self) -> bool {
+        *self == Self::Healthy
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionHealthEvent {
+    pub extension: String,
+    pub health: ExtensionHealth,
+    pub message: String,
+}
+
+/// What the monitor knows about one extension
+#[derive(Debug, Clone)]
+pub(crate) struct HealthRecord {
+    pub health: ExtensionHealth,
+    pub restarts: u32,
+    pub last_restart: Option<Instant>,
+}
+
+impl Default for HealthRecord {
+    fn default() -> Self {
+        Self {
+            health: ExtensionHealth::Healthy,
+            restarts: 0,
+            last_restart: None,
+        }
+    }
+}
+
+impl HealthRecord {
+    /// Whether the backoff since the last restart is over
+    pub fn may_restart(&self) -> bool {
+        self.last_restart
+            .is_none_or(|last| last.elapsed() >= restart_delay(self.restarts))
+    }
+}
+
+/// The events received so far. Events missed because the receiver fell behind are skipped.
+pub fn drain_events(
+    receiver: &mut broadcast::Receiver<ExtensionHealthEvent>,
+) -> Vec<ExtensionHealthEvent> {
+    let mut events = Vec::new();
+    loop {
+        match receiver.try_recv() {
+            Ok(event) => events.push(event),
+            Err(TryRecvError::Lagged(_)) => continue,
+            Err(_) => break,
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_backoff() {
+        assert_eq!(restart_delay(0), Duration::from_secs(1));
+        assert_eq!(restart_delay(1), Duration::from_secs(1));
+        assert_eq!(restart_delay(3), Duration::from_secs(4));
+        assert_eq!(restart_delay(40), MAX_RESTART_DELAY);
+
+        let mut record = HealthRecord::default();
+        assert!(record.may_restart());
+        record.restarts = 3;
+        record.last_restart = Some(Instant::now());
+        assert!(!record.may_restart());
+    }
+}
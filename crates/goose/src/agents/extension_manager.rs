@@ -11,16 +11,17 @@ use rmcp::transport::{
     ConfigureCommandExt, DynamicTransportError, SseClientTransport, StreamableHttpClientTransport,
     TokioChildProcess,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::option::Option;
 use std::path::PathBuf;
 use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tempfile::{tempdir, TempDir};
 use tokio::io::AsyncReadExt;
 use tokio::process::Command;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
 use tokio::task;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_util::sync::CancellationToken;
@@ -30,19 +31,34 @@ use super::extension::{
     ExtensionConfig, ExtensionError, ExtensionInfo, ExtensionResult, PlatformExtensionContext,
     ToolInfo, PLATFORM_EXTENSIONS,
 };
-use super::tool_execution::ToolCallResult;
+use super::tool_execution::{
+    is_for_tool_call, new_progress_token, ToolCallResult, PROGRESS_TOKEN_META_KEY,
+};
 use super::types::SharedProvider;
 use crate::agents::extension::{Envs, ProcessExit};
+use crate::agents::extension_health::{
+    health_check_interval, is_restartable, max_restarts, ExtensionHealth, ExtensionHealthEvent,
+    HealthRecord, PING_TIMEOUT,
+};
 use crate::agents::extension_malware_check;
 use crate::agents::mcp_client::{McpClient, McpClientTrait};
+use crate::agents::sampling::SamplingHandler;
+use crate::agents::shell_sandbox::ShellSandbox;
+use crate::agents::tool_cache::is_cacheable;
+use crate::agents::tool_namespace::{ToolCollision, ToolIndex, ToolNamespace, ToolRoute};
+use crate::agents::tool_policy::ToolPolicies;
+#[cfg(feature = "wasm-extensions")]
+use crate::agents::wasm_extension;
+use crate::agents::workspace_roots::{enforce_workspace_roots, WorkspaceRoots};
 use crate::config::search_path::SearchPaths;
 use crate::config::{get_all_extensions, Config};
-use crate::oauth::oauth_flow;
+use crate::conversation::message::MessageContent;
+use crate::oauth::{self, oauth_flow};
 use crate::prompt_template;
 use crate::subprocess::configure_command_no_window;
 use rmcp::model::{
     CallToolRequestParam, Content, ErrorCode, ErrorData, GetPromptResult, Prompt, RawContent,
-    Resource, ResourceContents, ServerInfo, Tool,
+    Resource, ResourceContents, ServerInfo, ServerNotification, Tool,
 };
 use rmcp::transport::auth::AuthClient;
 use schemars::_private::NoSerialize;
@@ -50,6 +66,9 @@ use serde_json::Value;
 
 type McpClientBox = Arc<Mutex<Box<dyn McpClientTrait>>>;
 
+/// Health changes kept for subscribers that haven't received them yet
+const HEALTH_EVENT_CAPACITY: usize = 64;
+
 struct Extension {
     pub config: ExtensionConfig,
 
@@ -80,6 +99,14 @@ impl Extension {
             .is_some()
     }
 
+    fn supports_resource_subscriptions(&self) -> bool {
+        self.server_info
+            .as_ref()
+            .and_then(|info| info.capabilities.resources.as_ref())
+            .and_then(|resources| resources.subscribe)
+            .unwrap_or(false)
+    }
+
     fn get_instructions(&self) -> Option<String> {
         self.server_info
             .as_ref()
@@ -91,11 +118,27 @@ impl Extension {
     }
 }
 
+/// Subscribed resources by extension and URI, each with whether it changed since it was last read
+type ResourceSubscriptions = Arc<std::sync::Mutex<HashMap<(String, String), bool>>>;
+
 /// Manages goose extensions / MCP clients and their interactions
 pub struct ExtensionManager {
     extensions: Mutex<HashMap<String, Extension>>,
     context: Mutex<PlatformExtensionContext>,
     provider: SharedProvider,
+    resource_subscriptions: ResourceSubscriptions,
+    workspace_roots: WorkspaceRoots,
+    health: std::sync::Mutex<HashMap<String, HealthRecord>>,
+    health_events: broadcast::Sender<ExtensionHealthEvent>,
+    health_monitor_started: AtomicBool,
+    tool_index: std::sync::Mutex<ToolIndex>,
+    /// The tool policies read when tools were last listed, which the agent does every turn
+    tool_policies: std::sync::Mutex<Option<Arc<ToolPolicies>>>,
+    shell_sandbox: std::sync::Mutex<Option<ShellSandbox>>,
+    /// The tools that run their commands in the shell sandbox, as of the last full listing
+    sandboxed_tools: std::sync::Mutex<HashSet<String>>,
+    /// The tools whose results the tool cache may keep, as of the last full listing
+    cacheable_tools: std::sync::Mutex<HashSet<String>>,
 }
 
 /// A flattened representation of a resource used by the agent to prepare inference
@@ -193,7 +236,8 @@ impl Default for ExtensionManager {
 async fn child_process_client(
     mut command: Command,
     timeout: &Option<u64>,
-    provider: SharedProvider,
+    sampling: SamplingHandler,
+    roots: WorkspaceRoots,
 ) -> ExtensionResult<McpClient> {
     #[cfg(unix)]
     command.process_group(0);
@@ -219,7 +263,8 @@ async fn child_process_client(
     let client_result = McpClient::connect(
         transport,
         Duration::from_secs(timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT)),
-        provider,
+        sampling,
+        roots,
     )
     .await;
 
@@ -266,6 +311,16 @@ impl ExtensionManager {
                 extension_manager: None,
             }),
             provider,
+            resource_subscriptions: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            workspace_roots: WorkspaceRoots::default(),
+            health: std::sync::Mutex::new(HashMap::new()),
+            health_events: broadcast::channel(HEALTH_EVENT_CAPACITY).0,
+            health_monitor_started: AtomicBool::new(false),
+            tool_index: std::sync::Mutex::new(ToolIndex::default()),
+            tool_policies: std::sync::Mutex::new(None),
+            shell_sandbox: std::sync::Mutex::new(None),
+            sandboxed_tools: std::sync::Mutex::new(HashSet::new()),
+            cacheable_tools: std::sync::Mutex::new(HashSet::new()),
         }
     }
 
@@ -282,6 +337,182 @@ impl ExtensionManager {
         self.context.lock().await.clone()
     }
 
+    fn tool_policies(&self) -> Arc<ToolPolicies> {
+        self.tool_policies
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| Arc::new(ToolPolicies::from_config()))
+            .clone()
+    }
+
+    pub fn shell_sandbox(&self) -> Option<ShellSandbox> {
+        self.shell_sandbox.lock().unwrap().clone()
+    }
+
+    /// Set the container that shell commands run in, None to run them on the host
+    pub fn set_shell_sandbox(&self, sandbox: Option<ShellSandbox>) {
+        *self.shell_sandbox.lock().unwrap() = sandbox;
+    }
+
+    pub fn workspace_roots(&self) -> Vec<PathBuf> {
+        self.workspace_roots.get()
+    }
+
+    /// Set the workspace roots, telling the extensions if they changed
+    pub async fn set_workspace_roots(&self, roots: Vec<PathBuf>) {
+        if !self.workspace_roots.set(roots) {
+            return;
+        }
+        let clients: Vec<(String, McpClientBox)> = self
+            .extensions
+            .lock()
+            .await
+            .iter()
+            .map(|(name, ext)| (name.clone(), ext.get_client()))
+            .collect();
+        for (name, client) in clients {
+            if let Err(e) = client.lock().await.notify_roots_list_changed().await {
+                warn!("Failed to notify {} of the workspace roots: {}", name, e);
+            }
+        }
+    }
+
+    pub fn subscribe_health_events(&self) -> broadcast::Receiver<ExtensionHealthEvent> {
+        self.health_events.subscribe()
+    }
+
+    pub fn extension_health(&self, name: &str) -> ExtensionHealth {
+        self.health
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|record| record.health)
+            .unwrap_or(ExtensionHealth::Healthy)
+    }
+
+    fn set_health(&self, name: &str, health: ExtensionHealth, message: String) {
+        let changed = {
+            let mut records = self.health.lock().unwrap();
+            let record = records.entry(name.to_string()).or_default();
+            let changed = record.health != health;
+            record.health = health;
+            changed
+        };
+        if changed {
+            let _ = self.health_events.send(ExtensionHealthEvent {
+                extension: name.to_string(),
+                health,
+                message,
+            });
+        }
+    }
+
+    /// Start checking the health of the extensions in the background, once
+    pub fn start_health_monitor(self: &Arc<Self>) {
+        let Some(interval) = health_check_interval() else {
+            return;
+        };
+        if self.health_monitor_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let manager = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                manager.check_health().await;
+            }
+        });
+    }
+
+    /// Ping every extension, restarting the ones that goose runs and that stopped answering
+    pub async fn check_health(&self) {
+        let extensions: Vec<(String, ExtensionConfig, McpClientBox)> = self
+            .extensions
+            .lock()
+            .await
+            .iter()
+            .map(|(name, ext)| (name.clone(), ext.config.clone(), ext.get_client()))
+            .collect();
+
+        for (name, config, client) in extensions {
+            let alive = match client.try_lock() {
+                // Busy serving a request
+                Err(_) => true,
+                Ok(client) => matches!(
+                    tokio::time::timeout(PING_TIMEOUT, client.ping(CancellationToken::default()))
+                        .await,
+                    Ok(Ok(()))
+                ),
+            };
+            if alive {
+                self.set_health(
+                    &name,
+                    ExtensionHealth::Healthy,
+                    format!("Extension {} is available again", name),
+                );
+                continue;
+            }
+
+            if !is_restartable(&config) {
+                self.set_health(
+                    &name,
+                    ExtensionHealth::Unresponsive,
+                    format!(
+                        "Extension {} is not responding; its tools are unavailable until it does",
+                        name
+                    ),
+                );
+                continue;
+            }
+
+            let record = self
+                .health
+                .lock()
+                .unwrap()
+                .get(&name)
+                .cloned()
+                .unwrap_or_default();
+            if record.restarts >= max_restarts() {
+                self.set_health(
+                    &name,
+                    ExtensionHealth::Dead,
+                    format!(
+                        "Extension {} stopped working and was restarted {} times; its tools are unavailable",
+                        name, record.restarts
+                    ),
+                );
+                continue;
+            }
+            if !record.may_restart() {
+                continue;
+            }
+
+            let attempt = record.restarts + 1;
+            if let Some(record) = self.health.lock().unwrap().get_mut(&name) {
+                record.restarts = attempt;
+                record.last_restart = Some(std::time::Instant::now());
+            }
+            self.set_health(
+                &name,
+                ExtensionHealth::Restarting { attempt },
+                format!("Extension {} stopped working, restarting it", name),
+            );
+            match self.add_extension(config).await {
+                Ok(()) => self.set_health(
+                    &name,
+                    ExtensionHealth::Healthy,
+                    format!("Extension {} was restarted", name),
+                ),
+                Err(e) => warn!("Failed to restart extension {}: {}", name, e),
+            }
+        }
+    }
+
     pub async fn supports_resources(&self) -> bool {
         self.extensions
             .lock()
@@ -292,8 +523,17 @@ impl ExtensionManager {
 
     pub async fn add_extension(&self, config: ExtensionConfig) -> ExtensionResult<()> {
         let config_name = config.key().to_string();
+        if let Some(remote) = crate::config::remote::active() {
+            if !remote.allows_extension(&config_name) {
+                return Err(ExtensionError::ConfigError(format!(
+                    "Extension '{}' is not allowed by the remote config",
+                    config_name
+                )));
+            }
+        }
         let sanitized_name = normalize(config_name.clone());
         let mut temp_dir = None;
+        let sampling = || SamplingHandler::new(self.provider.clone(), sanitized_name.clone());
 
         /// Helper function to merge environment variables from direct envs and keychain-stored env_keys
         async fn merge_environments(
@@ -368,7 +608,8 @@ impl ExtensionManager {
                         Duration::from_secs(
                             timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
                         ),
-                        self.provider.clone(),
+                        sampling(),
+                        self.workspace_roots.clone(),
                     )
                     .await?,
                 )
@@ -438,41 +679,71 @@ impl ExtensionManager {
                     .map_err(|_| {
                         ExtensionError::ConfigError("could not construct http client".to_string())
                     })?;
-                let transport = StreamableHttpClientTransport::with_client(
-                    client,
-                    StreamableHttpClientTransportConfig {
-                        uri: uri.clone().into(),
-                        ..Default::default()
-                    },
+                let timeout = Duration::from_secs(
+                    timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
                 );
-                let client_res = McpClient::connect(
-                    transport,
-                    Duration::from_secs(
-                        timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
-                    ),
-                    self.provider.clone(),
-                )
-                .await;
-                let client = if let Some(_auth_error) = extract_auth_error(&client_res) {
-                    let am = oauth_flow(uri, name)
+                let transport_config = || StreamableHttpClientTransportConfig {
+                    uri: uri.clone().into(),
+                    ..Default::default()
+                };
+
+                // Servers authorized before get their saved token from the start
+                let stored_auth =
+                    oauth::stored_authorization(uri, name)
+                        .await
+                        .unwrap_or_else(|e| {
+                            warn!("Failed to load OAuth credentials for {}: {}", name, e);
+                            None
+                        });
+                let used_stored_auth = stored_auth.is_some();
+                let client_res = match stored_auth {
+                    Some(am) => {
+                        let transport = StreamableHttpClientTransport::with_client(
+                            AuthClient::new(client.clone(), am),
+                            transport_config(),
+                        );
+                        McpClient::connect(
+                            transport,
+                            timeout,
+                            sampling(),
+                            self.workspace_roots.clone(),
+                        )
+                        .await
+                    }
+                    None => {
+                        let transport = StreamableHttpClientTransport::with_client(
+                            client.clone(),
+                            transport_config(),
+                        );
+                        McpClient::connect(
+                            transport,
+                            timeout,
+                            sampling(),
+                            self.workspace_roots.clone(),
+                        )
                         .await
-                        .map_err(|_| ExtensionError::SetupError("auth error".to_string()))?;
-                    let client = AuthClient::new(reqwest::Client::default(), am);
+                    }
+                };
+                let client = if let Some(_auth_error) = extract_auth_error(&client_res) {
+                    if used_stored_auth {
+                        // The server no longer accepts the saved token
+                        if let Err(e) = oauth::clear_credentials(name) {
+                            warn!("Failed to clear OAuth credentials for {}: {}", name, e);
+                        }
+                    }
+                    let am = oauth_flow(uri, name).await.map_err(|e| {
+                        ExtensionError::SetupError(format!(
+                            "OAuth authorization for {} failed: {}",
+                            name, e
+                        ))
+                    })?;
+                    // Keep the configured headers on authorized requests
                     let transport = StreamableHttpClientTransport::with_client(
-                        client,
-                        StreamableHttpClientTransportConfig {
-                            uri: uri.clone().into(),
-                            ..Default::default()
-                        },
+                        AuthClient::new(client, am),
+                        transport_config(),
                     );
-                    McpClient::connect(
-                        transport,
-                        Duration::from_secs(
-                            timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
-                        ),
-                        self.provider.clone(),
-                    )
-                    .await?
+                    McpClient::connect(transport, timeout, sampling(), self.workspace_roots.clone())
+                        .await?
                 } else {
                     client_res?
                 };
@@ -497,7 +768,13 @@ impl ExtensionManager {
                     command.args(args).envs(all_envs);
                 });
 
-                let client = child_process_client(command, timeout, self.provider.clone()).await?;
+                let client = child_process_client(
+                    command,
+                    timeout,
+                    sampling(),
+                    self.workspace_roots.clone(),
+                )
+                .await?;
                 Box::new(client)
             }
             ExtensionConfig::Builtin {
@@ -526,7 +803,13 @@ impl ExtensionManager {
                 let command = Command::new(cmd).configure(|command| {
                     command.arg("mcp").arg(name);
                 });
-                let client = child_process_client(command, timeout, self.provider.clone()).await?;
+                let client = child_process_client(
+                    command,
+                    timeout,
+                    sampling(),
+                    self.workspace_roots.clone(),
+                )
+                .await?;
                 Box::new(client)
             }
             ExtensionConfig::Platform { name, .. } => {
@@ -562,10 +845,43 @@ impl ExtensionManager {
                     command.arg("python").arg(file_path.to_str().unwrap());
                 });
 
-                let client = child_process_client(command, timeout, self.provider.clone()).await?;
+                let client = child_process_client(
+                    command,
+                    timeout,
+                    sampling(),
+                    self.workspace_roots.clone(),
+                )
+                .await?;
 
                 Box::new(client)
             }
+            #[cfg(feature = "wasm-extensions")]
+            ExtensionConfig::Wasm {
+                path,
+                capabilities,
+                envs,
+                env_keys,
+                timeout,
+                ..
+            } => {
+                let all_envs = merge_environments(envs, env_keys, &sanitized_name).await?;
+                let client = wasm_extension::wasm_client(
+                    path,
+                    capabilities,
+                    all_envs,
+                    timeout,
+                    sampling(),
+                    self.workspace_roots.clone(),
+                )
+                .await?;
+                Box::new(client)
+            }
+            #[cfg(not(feature = "wasm-extensions"))]
+            ExtensionConfig::Wasm { .. } => {
+                return Err(ExtensionError::ConfigError(
+                    "WASM extensions need goose built with the wasm-extensions feature".to_string(),
+                ));
+            }
             ExtensionConfig::Frontend { .. } => {
                 return Err(ExtensionError::ConfigError(
                     "Invalid extension type: Frontend extensions cannot be added as server extensions".to_string()
@@ -620,6 +936,11 @@ impl ExtensionManager {
     pub async fn remove_extension(&self, name: &str) -> ExtensionResult<()> {
         let sanitized_name = normalize(name.to_string());
         self.extensions.lock().await.remove(&sanitized_name);
+        self.resource_subscriptions
+            .lock()
+            .unwrap()
+            .retain(|(extension, _), _| *extension != sanitized_name);
+        self.health.lock().unwrap().remove(&sanitized_name);
         Ok(())
     }
 
@@ -678,6 +999,10 @@ impl ExtensionManager {
                     }
                 }
 
+                if !self.extension_health(name).is_available() {
+                    return false;
+                }
+
                 if let Some(ref name_filter) = extension_name {
                     *name == name_filter
                 } else {
@@ -688,8 +1013,11 @@ impl ExtensionManager {
             .collect();
 
         let cancel_token = CancellationToken::default();
+        let policies = Arc::new(ToolPolicies::from_config());
+        *self.tool_policies.lock().unwrap() = Some(policies.clone());
         let client_futures = filtered_clients.into_iter().map(|(name, config, client)| {
             let cancel_token = cancel_token.clone();
+            let policies = policies.clone();
             task::spawn(async move {
                 let mut tools = Vec::new();
                 let client_guard = client.lock().await;
@@ -697,19 +1025,23 @@ impl ExtensionManager {
 
                 loop {
                     for tool in client_tools.tools {
-                        let is_available = config.is_tool_available(&tool.name);
+                        let is_available = config.is_tool_available(&tool.name)
+                            && policies.is_allowed(&name, &tool.name);
 
                         if is_available {
-                            tools.push(Tool {
-                                name: format!("{}__{}", name, tool.name).into(),
-                                description: tool.description,
-                                input_schema: tool.input_schema,
-                                annotations: tool.annotations,
-                                output_schema: tool.output_schema,
-                                icons: None,
-                                title: None,
-                                meta: None,
-                            });
+                            tools.push((
+                                name.clone(),
+                                Tool {
+                                    name: tool.name,
+                                    description: tool.description,
+                                    input_schema: tool.input_schema,
+                                    annotations: tool.annotations,
+                                    output_schema: tool.output_schema,
+                                    icons: None,
+                                    title: None,
+                                    meta: tool.meta,
+                                },
+                            ));
                         }
                     }
 
@@ -723,7 +1055,7 @@ impl ExtensionManager {
                         .await?;
                 }
 
-                Ok::<Vec<Tool>, ExtensionError>(tools)
+                Ok::<Vec<(String, Tool)>, ExtensionError>(tools)
             })
         });
 
@@ -740,7 +1072,47 @@ impl ExtensionManager {
             }
         }
 
-        Ok(tools)
+        let resolved = ToolNamespace::from_config().resolve(tools);
+        if extension_name.is_none() && exclude.is_none() {
+            for collision in &resolved.collisions {
+                warn!("{}", collision.message());
+            }
+            self.tool_index.lock().unwrap().update(&resolved);
+            *self.sandboxed_tools.lock().unwrap() = resolved
+                .tools
+                .iter()
+                .filter(|tool| {
+                    tool.meta
+                        .as_ref()
+                        .is_some_and(|meta| ShellSandbox::is_supported_by(&meta.0))
+                })
+                .map(|tool| tool.name.to_string())
+                .collect();
+            *self.cacheable_tools.lock().unwrap() = resolved
+                .tools
+                .iter()
+                .filter(|tool| is_cacheable(tool))
+                .map(|tool| tool.name.to_string())
+                .collect();
+        }
+
+        Ok(resolved.tools)
+    }
+
+    /// Whether results of `tool_name` can be cached, going by the annotations of the last full
+    /// tool listing
+    pub fn is_cacheable_tool(&self, tool_name: &str) -> bool {
+        self.cacheable_tools.lock().unwrap().contains(tool_name)
+    }
+
+    /// Tool names offered by more than one extension in the last full tool listing
+    pub fn tool_collisions(&self) -> Vec<ToolCollision> {
+        self.tool_index.lock().unwrap().collisions()
+    }
+
+    /// Tool name collisions found since this was last called
+    pub fn take_new_tool_collisions(&self) -> Vec<ToolCollision> {
+        self.tool_index.lock().unwrap().take_unreported()
     }
 
     pub async fn get_prefixed_tools_excluding(&self, exclude: &str) -> ExtensionResult<Vec<Tool>> {
@@ -755,16 +1127,45 @@ impl ExtensionManager {
         prompt_template::render_global_file("plan.md", &context).expect("Prompt should render")
     }
 
-    /// Find and return a reference to the appropriate client for a tool call
+    /// Find and return a reference to the appropriate client for a tool call, going by the
+    /// longest extension name the tool name is prefixed with
     async fn get_client_for_tool(&self, prefixed_name: &str) -> Option<(String, McpClientBox)> {
         self.extensions
             .lock()
             .await
             .iter()
-            .find(|(key, _)| prefixed_name.starts_with(*key))
+            .filter(|(key, _)| {
+                prefixed_name
+                    .strip_prefix(key.as_str())
+                    .is_some_and(|rest| rest.starts_with("__"))
+            })
+            .max_by_key(|(key, _)| key.len())
             .map(|(name, extension)| (name.clone(), extension.get_client()))
     }
 
+    /// Find the extension tool a tool name stands for, using the routes of the last full tool
+    /// listing and otherwise the aliases and prefixes
+    async fn route_tool(&self, name: &str) -> Option<(ToolRoute, McpClientBox)> {
+        let known = self.tool_index.lock().unwrap().route(name);
+        let route = match known {
+            Some(route) => route,
+            None => {
+                let namespace = ToolNamespace::from_config();
+                let name = namespace.alias_target(name).unwrap_or(name);
+                let (extension, _) = self.get_client_for_tool(name).await?;
+                let tool = name.get(extension.len() + 2..)?.to_string();
+                ToolRoute { extension, tool }
+            }
+        };
+        let client = self
+            .extensions
+            .lock()
+            .await
+            .get(&route.extension)?
+            .get_client();
+        Some((route, client))
+    }
+
     // Function that gets executed for read_resource tool
     pub async fn read_resource(
         &self,
@@ -869,21 +1270,201 @@ impl ExtensionManager {
                 )
             })?;
 
+        if let Some(changed) = self
+            .resource_subscriptions
+            .lock()
+            .unwrap()
+            .get_mut(&(extension_name.to_string(), uri.to_string()))
+        {
+            *changed = false;
+        }
+
         let mut result = Vec::new();
         for content in read_result.contents {
-            if let ResourceContents::TextResourceContents { text, .. } = content {
-                let content_str = if format_with_uri {
-                    format!("{}\n\n{}", uri, text)
-                } else {
-                    text
-                };
-                result.push(Content::text(content_str));
+            match content {
+                ResourceContents::TextResourceContents { text, .. } => {
+                    let content_str = if format_with_uri {
+                        format!("{}\n\n{}", uri, text)
+                    } else {
+                        text
+                    };
+                    result.push(Content::text(content_str));
+                }
+                ResourceContents::BlobResourceContents {
+                    mime_type, blob, ..
+                } => match mime_type {
+                    Some(mime_type) if mime_type.starts_with("image/") => {
+                        result.push(Content::image(blob, mime_type));
+                    }
+                    mime_type => result.push(Content::text(format!(
+                        "[Binary resource {} ({}), {} bytes of base64]",
+                        uri,
+                        mime_type.as_deref().unwrap_or("unknown type"),
+                        blob.len()
+                    ))),
+                },
             }
         }
 
         Ok(result)
     }
 
+    /// The contents of resource `uri` of extension `extension_name` as message content, for
+    /// attaching it to a user message
+    pub async fn resource_message_content(
+        &self,
+        extension_name: &str,
+        uri: &str,
+        cancellation_token: CancellationToken,
+    ) -> Result<Vec<MessageContent>, ErrorData> {
+        let client = self
+            .get_server_client(extension_name)
+            .await
+            .ok_or_else(|| {
+                ErrorData::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!("Extension {} is not valid", extension_name),
+                    None,
+                )
+            })?;
+        let read_result = client
+            .lock()
+            .await
+            .read_resource(uri, cancellation_token)
+            .await
+            .map_err(|e| {
+                ErrorData::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("Could not read resource with uri {}: {}", uri, e),
+                    None,
+                )
+            })?;
+        Ok(read_result
+            .contents
+            .iter()
+            .map(MessageContent::resource)
+            .collect())
+    }
+
+    /// Get notified when resource `uri` of extension `extension_name` changes. Changed
+    /// resources are listed at the start of each turn until they're read again.
+    pub async fn subscribe_resource(
+        &self,
+        extension_name: &str,
+        uri: &str,
+        cancellation_token: CancellationToken,
+    ) -> Result<(), ErrorData> {
+        let (client, supported) = {
+            let extensions = self.extensions.lock().await;
+            let extension = extensions.get(extension_name).ok_or_else(|| {
+                ErrorData::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!("Extension {} is not valid", extension_name),
+                    None,
+                )
+            })?;
+            (
+                extension.get_client(),
+                extension.supports_resource_subscriptions(),
+            )
+        };
+        if !supported {
+            return Err(ErrorData::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Extension {} doesn't support resource subscriptions",
+                    extension_name
+                ),
+                None,
+            ));
+        }
+
+        let client_guard = client.lock().await;
+        client_guard
+            .subscribe_resource(uri, cancellation_token)
+            .await
+            .map_err(|e| {
+                ErrorData::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("Could not subscribe to resource {}: {}", uri, e),
+                    None,
+                )
+            })?;
+
+        let first_for_extension = {
+            let mut subscriptions = self.resource_subscriptions.lock().unwrap();
+            let first = !subscriptions
+                .keys()
+                .any(|(extension, _)| extension == extension_name);
+            subscriptions.insert((extension_name.to_string(), uri.to_string()), false);
+            first
+        };
+        if first_for_extension {
+            let mut notifications = client_guard.subscribe().await;
+            let subscriptions = self.resource_subscriptions.clone();
+            let extension_name = extension_name.to_string();
+            // Ends when the extension's client is dropped
+            tokio::spawn(async move {
+                while let Some(notification) = notifications.recv().await {
+                    if let ServerNotification::ResourceUpdatedNotification(updated) = notification {
+                        let key = (extension_name.clone(), updated.params.uri);
+                        if let Some(changed) = subscriptions.lock().unwrap().get_mut(&key) {
+                            *changed = true;
+                        }
+                    }
+                }
+            });
+        }
+        Ok(())
+    }
+
+    pub async fn unsubscribe_resource(
+        &self,
+        extension_name: &str,
+        uri: &str,
+        cancellation_token: CancellationToken,
+    ) -> Result<(), ErrorData> {
+        let key = (extension_name.to_string(), uri.to_string());
+        if self
+            .resource_subscriptions
+            .lock()
+            .unwrap()
+            .remove(&key)
+            .is_none()
+        {
+            return Err(ErrorData::new(
+                ErrorCode::INVALID_PARAMS,
+                format!("Not subscribed to resource {} of {}", uri, extension_name),
+                None,
+            ));
+        }
+        if let Some(client) = self.get_server_client(extension_name).await {
+            if let Err(e) = client
+                .lock()
+                .await
+                .unsubscribe_resource(uri, cancellation_token)
+                .await
+            {
+                warn!("Failed to unsubscribe from resource {}: {}", uri, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Subscribed resources, as (extension, URI), that changed since they were last read
+    pub fn changed_resources(&self) -> Vec<(String, String)> {
+        let mut changed: Vec<(String, String)> = self
+            .resource_subscriptions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, changed)| **changed)
+            .map(|(key, _)| key.clone())
+            .collect();
+        changed.sort();
+        changed
+    }
+
     pub async fn get_ui_resources(&self) -> Result<Vec<(String, Resource)>, ErrorData> {
         let mut ui_resources = Vec::new();
 
@@ -960,26 +1541,42 @@ impl ExtensionManager {
             })?;
 
         let client_guard = client.lock().await;
-        client_guard
-            .list_resources(None, cancellation_token)
-            .await
-            .map_err(|e| {
-                ErrorData::new(
-                    ErrorCode::INTERNAL_ERROR,
-                    format!("Unable to list resources for {}, {:?}", extension_name, e),
-                    None,
-                )
-            })
-            .map(|lr| {
-                let resource_list = lr
-                    .resources
-                    .into_iter()
-                    .map(|r| format!("{} - {}, uri: ({})", extension_name, r.name, r.uri))
-                    .collect::<Vec<String>>()
-                    .join("\n");
+        let mut resources = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = client_guard
+                .list_resources(cursor, cancellation_token.clone())
+                .await
+                .map_err(|e| {
+                    ErrorData::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!("Unable to list resources for {}, {:?}", extension_name, e),
+                        None,
+                    )
+                })?;
+            resources.extend(page.resources);
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
 
-                vec![Content::text(resource_list)]
+        let resource_list = resources
+            .into_iter()
+            .map(|r| {
+                let mut line = format!("{} - {}, uri: ({})", extension_name, r.name, r.uri);
+                if let Some(mime_type) = &r.mime_type {
+                    line.push_str(&format!(", type: {}", mime_type));
+                }
+                if let Some(description) = &r.description {
+                    line.push_str(&format!(" - {}", description));
+                }
+                line
             })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        Ok(vec![Content::text(resource_list)])
     }
 
     pub async fn list_resources(
@@ -987,7 +1584,10 @@ impl ExtensionManager {
         params: Value,
         cancellation_token: CancellationToken,
     ) -> Result<Vec<Content>, ErrorData> {
-        let extension = params.get("extension").and_then(|v| v.as_str());
+        let extension = params
+            .get("extension_name")
+            .or_else(|| params.get("extension"))
+            .and_then(|v| v.as_str());
 
         match extension {
             Some(extension_name) => {
@@ -1049,26 +1649,21 @@ impl ExtensionManager {
         tool_call: CallToolRequestParam,
         cancellation_token: CancellationToken,
     ) -> Result<ToolCallResult> {
-        // Dispatch tool call based on the prefix naming convention
-        let (client_name, client) =
-            self.get_client_for_tool(&tool_call.name)
-                .await
-                .ok_or_else(|| {
-                    ErrorData::new(ErrorCode::RESOURCE_NOT_FOUND, tool_call.name.clone(), None)
-                })?;
-
-        // rsplit returns the iterator in reverse, tool_name is then at 0
-        let tool_name = tool_call
-            .name
-            .strip_prefix(client_name.as_str())
-            .and_then(|s| s.strip_prefix("__"))
-            .ok_or_else(|| {
-                ErrorData::new(ErrorCode::RESOURCE_NOT_FOUND, tool_call.name.clone(), None)
-            })?
-            .to_string();
+        let (
+            ToolRoute {
+                extension: client_name,
+                tool: tool_name,
+            },
+            client,
+        ) = self.route_tool(&tool_call.name).await.ok_or_else(|| {
+            ErrorData::new(ErrorCode::RESOURCE_NOT_FOUND, tool_call.name.clone(), None)
+        })?;
 
+        let policies = self.tool_policies();
         if let Some(extension) = self.extensions.lock().await.get(&client_name) {
-            if !extension.config.is_tool_available(&tool_name) {
+            if !extension.config.is_tool_available(&tool_name)
+                || !policies.is_allowed(&client_name, &tool_name)
+            {
                 return Err(ErrorData::new(
                     ErrorCode::RESOURCE_NOT_FOUND,
                     format!(
@@ -1081,14 +1676,89 @@ impl ExtensionManager {
             }
         }
 
+        if let Some(violation) =
+            policies.violation(&client_name, &tool_name, tool_call.arguments.as_ref())
+        {
+            return Err(ErrorData::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "The tool policy for '{}' doesn't allow this call: {}",
+                    client_name, violation
+                ),
+                None,
+            )
+            .into());
+        }
+
+        let health = self.extension_health(&client_name);
+        if !health.is_available() {
+            return Err(ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "Extension '{}' is unavailable ({})",
+                    client_name,
+                    match health {
+                        ExtensionHealth::Restarting { .. } => "restarting",
+                        ExtensionHealth::Dead => "stopped after repeated failures",
+                        _ => "not responding",
+                    }
+                ),
+                None,
+            )
+            .into());
+        }
+
+        if enforce_workspace_roots() {
+            if let Some(path) = tool_call
+                .arguments
+                .as_ref()
+                .and_then(|arguments| self.workspace_roots.path_outside(arguments))
+            {
+                return Err(ErrorData::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!(
+                        "{} is outside the workspace roots: {}",
+                        path.display(),
+                        self.workspace_roots
+                            .get()
+                            .iter()
+                            .map(|root| root.display().to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    None,
+                )
+                .into());
+            }
+        }
+
         let arguments = tool_call.arguments.clone();
+        let mut meta = self
+            .shell_sandbox()
+            .filter(|_| {
+                self.sandboxed_tools
+                    .lock()
+                    .unwrap()
+                    .contains(tool_call.name.as_ref())
+            })
+            .map(|sandbox| sandbox.to_meta())
+            .unwrap_or_default();
+        let progress_token = new_progress_token();
+        meta.insert(
+            PROGRESS_TOKEN_META_KEY.to_string(),
+            Value::String(progress_token.clone()),
+        );
         let client = client.clone();
         let notifications_receiver = client.lock().await.subscribe().await;
+        let notifications =
+            ReceiverStream::new(notifications_receiver).filter(move |notification| {
+                future::ready(is_for_tool_call(notification, &progress_token))
+            });
 
         let fut = async move {
             let client_guard = client.lock().await;
             client_guard
-                .call_tool(&tool_name, arguments, cancellation_token)
+                .call_tool_with_meta(&tool_name, arguments, meta, cancellation_token)
                 .await
                 .map_err(|e| match e {
                     ServiceError::McpError(error_data) => error_data,
@@ -1100,7 +1770,7 @@ impl ExtensionManager {
 
         Ok(ToolCallResult {
             result: Box::new(fut.boxed()),
-            notification_stream: Some(Box::new(ReceiverStream::new(notifications_receiver))),
+            notification_stream: Some(Box::new(notifications)),
         })
     }
 
@@ -1225,7 +1895,8 @@ impl ExtensionManager {
                     | ExtensionConfig::StreamableHttp { description, .. }
                     | ExtensionConfig::Stdio { description, .. }
                     | ExtensionConfig::Frontend { description, .. }
-                    | ExtensionConfig::InlinePython { description, .. } => description,
+                    | ExtensionConfig::InlinePython { description, .. }
+                    | ExtensionConfig::Wasm { description, .. } => description,
                 };
                 disabled_extensions.push(format!("- {} - {}", config.name(), description));
             }
@@ -1296,6 +1967,16 @@ impl ExtensionManager {
             }
         }
 
+        let changed_resources = self.changed_resources();
+        if !changed_resources.is_empty() {
+            content.push_str(
+                "\nSubscribed resources changed since they were last read (use read_resource to see them):\n",
+            );
+            for (extension, uri) in changed_resources {
+                content.push_str(&format!("- {}: {}\n", extension, uri));
+            }
+        }
+
         content.push_str("\n</info-msg>");
 
         Some(content)
@@ -1305,6 +1986,7 @@ impl ExtensionManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agents::extension_health;
     use rmcp::model::CallToolResult;
     use rmcp::model::{InitializeResult, JsonObject};
     use rmcp::{object, ServiceError as Error};
@@ -1471,12 +2153,33 @@ mod tests {
             )
             .await;
 
+        extension_manager
+            .add_mock_extension(
+                "test".to_string(),
+                Arc::new(Mutex::new(Box::new(MockClient {}))),
+            )
+            .await;
+
         // Test basic case
         assert!(extension_manager
             .get_client_for_tool("test_client__tool")
             .await
             .is_some());
 
+        // Test that an extension whose name starts another's doesn't take its tools
+        assert_eq!(
+            extension_manager
+                .get_client_for_tool("test_client__tool")
+                .await
+                .map(|(name, _)| name)
+                .as_deref(),
+            Some("test_client")
+        );
+        assert!(extension_manager
+            .get_client_for_tool("testing__tool")
+            .await
+            .is_none());
+
         // Test leading underscores
         assert!(extension_manager
             .get_client_for_tool("__client__tool")
@@ -1668,6 +2371,58 @@ mod tests {
         assert!(tool_names.len() == 3);
     }
 
+    #[tokio::test]
+    async fn test_unhealthy_extension_tools_are_unavailable() {
+        let extension_manager = ExtensionManager::new_without_provider();
+        extension_manager
+            .add_mock_extension(
+                "test_client".to_string(),
+                Arc::new(Mutex::new(Box::new(MockClient {}))),
+            )
+            .await;
+        let mut events = extension_manager.subscribe_health_events();
+
+        extension_manager.set_health(
+            "test_client",
+            ExtensionHealth::Restarting { attempt: 1 },
+            "restarting".to_string(),
+        );
+        let tool_call = CallToolRequestParam {
+            name: "test_client__tool".to_string().into(),
+            arguments: Some(object!({})),
+        };
+        let err = extension_manager
+            .dispatch_tool_call(tool_call.clone(), CancellationToken::default())
+            .await
+            .err()
+            .unwrap();
+        let err = err.downcast_ref::<ErrorData>().expect("Expected ErrorData");
+        assert!(err.message.contains("restarting"));
+        assert!(extension_manager
+            .get_prefixed_tools(None)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // The mock answers pings, so the next check finds it healthy
+        extension_manager.check_health().await;
+        assert!(extension_manager
+            .dispatch_tool_call(tool_call, CancellationToken::default())
+            .await
+            .is_ok());
+        let health: Vec<ExtensionHealth> = extension_health::drain_events(&mut events)
+            .into_iter()
+            .map(|event| event.health)
+            .collect();
+        assert_eq!(
+            health,
+            vec![
+                ExtensionHealth::Restarting { attempt: 1 },
+                ExtensionHealth::Healthy
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_dispatch_unavailable_tool_returns_error() {
         let extension_manager = ExtensionManager::new_without_provider();
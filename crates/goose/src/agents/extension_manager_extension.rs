@@ -68,7 +68,17 @@ pub struct ListResourcesParams {
     pub extension_name: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SubscribeResourceParams {
+    pub uri: String,
+    pub extension_name: String,
+    /// Stop following the resource instead
+    #[serde(default)]
+    pub unsubscribe: bool,
+}
+
 pub const READ_RESOURCE_TOOL_NAME: &str = "read_resource";
+pub const SUBSCRIBE_RESOURCE_TOOL_NAME: &str = "subscribe_resource";
 pub const LIST_RESOURCES_TOOL_NAME: &str = "list_resources";
 pub const SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME: &str = "search_available_extensions";
 pub const MANAGE_EXTENSIONS_TOOL_NAME: &str = "manage_extensions";
@@ -111,6 +121,7 @@ impl ExtensionManagerClient {
                 - manage_extensions: Enable or disable extensions
                 - list_resources: List resources from extensions
                 - read_resource: Read specific resources from extensions
+                - subscribe_resource: Follow changes to a resource
 
                 Use search_available_extensions when you need to find what extensions are available.
                 Use manage_extensions to enable or disable specific extensions by name.
@@ -273,6 +284,48 @@ impl ExtensionManagerClient {
         }
     }
 
+    async fn handle_subscribe_resource(
+        &self,
+        arguments: Option<JsonObject>,
+    ) -> Result<Vec<Content>, ExtensionManagerToolError> {
+        let arguments = arguments.ok_or(ExtensionManagerToolError::MissingParameter {
+            param_name: "arguments".to_string(),
+        })?;
+        let params: SubscribeResourceParams =
+            serde_json::from_value(serde_json::Value::Object(arguments))?;
+
+        let extension_manager = self
+            .context
+            .extension_manager
+            .as_ref()
+            .and_then(|weak| weak.upgrade())
+            .ok_or(ExtensionManagerToolError::ManagerUnavailable)?;
+
+        let token = CancellationToken::default();
+        let (result, message) = if params.unsubscribe {
+            (
+                extension_manager
+                    .unsubscribe_resource(&params.extension_name, &params.uri, token)
+                    .await,
+                format!("Unsubscribed from {}", params.uri),
+            )
+        } else {
+            (
+                extension_manager
+                    .subscribe_resource(&params.extension_name, &params.uri, token)
+                    .await,
+                format!(
+                    "Subscribed to {}. You'll be told at the start of a turn when it changes.",
+                    params.uri
+                ),
+            )
+        };
+        result.map_err(|e| ExtensionManagerToolError::OperationFailed {
+            message: e.message.to_string(),
+        })?;
+        Ok(vec![Content::text(message)])
+    }
+
     #[allow(clippy::too_many_lines)]
     async fn get_tools(&self) -> Vec<Tool> {
         let mut tools = vec![
@@ -374,6 +427,29 @@ impl ExtensionManagerClient {
                             idempotent_hint: Some(false),
                             open_world_hint: Some(false),
                         }),
+                        Tool::new(
+                            SUBSCRIBE_RESOURCE_TOOL_NAME.to_string(),
+                            indoc! {r#"
+            Subscribe to changes of a resource, or unsubscribe from them.
+
+            While subscribed, resources that changed since you last read them are listed at the
+            start of each turn. Use this for resources you need to keep up to date with, such as
+            a log or a document someone else is editing. Not every extension supports it.
+        "#}.to_string(),
+                            Arc::new(
+                                serde_json::to_value(schema_for!(SubscribeResourceParams))
+                                    .expect("Failed to serialize schema")
+                                    .as_object()
+                                    .expect("Schema must be an object")
+                                    .clone()
+                            ),
+                        ).annotate(ToolAnnotations {
+                            title: Some("Subscribe to a resource".to_string()),
+                            read_only_hint: Some(false),
+                            destructive_hint: Some(false),
+                            idempotent_hint: Some(true),
+                            open_world_hint: Some(false),
+                        }),
                     ]);
                 }
             }
@@ -426,6 +502,7 @@ impl McpClientTrait for ExtensionManagerClient {
             MANAGE_EXTENSIONS_TOOL_NAME => self.handle_manage_extensions(arguments).await,
             LIST_RESOURCES_TOOL_NAME => self.handle_list_resources(arguments).await,
             READ_RESOURCE_TOOL_NAME => self.handle_read_resource(arguments).await,
+            SUBSCRIBE_RESOURCE_TOOL_NAME => self.handle_subscribe_resource(arguments).await,
             _ => Err(ExtensionManagerToolError::UnknownTool {
                 tool_name: name.to_string(),
             }),
@@ -0,0 +1,260 @@
+use crate::agents::extension::PlatformExtensionContext;
+use crate::agents::mcp_client::{Error, McpClientTrait};
+use crate::web_fetch::{FetchedPage, WebFetcher};
+use anyhow::Result;
+use async_trait::async_trait;
+use indoc::indoc;
+use rmcp::model::{
+    CallToolResult, Content, GetPromptResult, Implementation, InitializeResult, JsonObject,
+    ListPromptsResult, ListResourcesResult, ListToolsResult, ProtocolVersion, ReadResourceResult,
+    ServerCapabilities, ServerNotification, Tool, ToolAnnotations, ToolsCapability,
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{mpsc, OnceCell};
+use tokio_util::sync::CancellationToken;
+
+pub static EXTENSION_NAME: &str = "fetch";
+
+const DEFAULT_MAX_LENGTH: usize = 20_000;
+
+/// Parameters for the fetch_url tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct FetchUrlParams {
+    /// The http or https URL to fetch
+    url: String,
+    /// Max characters to return (default: 20000)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_length: Option<usize>,
+    /// Character to start from, to continue reading a page that was cut off (default: 0)
+    #[serde(default)]
+    start_index: usize,
+    /// Return the page's HTML as is instead of its main content as Markdown
+    #[serde(default)]
+    raw: bool,
+}
+
+/// The part of `page` from `start_index`, at most `max_length` characters of it
+fn format_page(page: &FetchedPage, start_index: usize, max_length: usize) -> String {
+    let total = page.content.chars().count();
+    let mut output = String::new();
+    if let Some(title) = &page.title {
+        output.push_str(&format!("# {}\n\n", title));
+    }
+    output.push_str(&format!("Source: {}\n\n", page.url));
+    if start_index >= total && total > 0 {
+        output.push_str(&format!(
+            "No more content: the page has {} characters.",
+            total
+        ));
+        return output;
+    }
+
+    output.extend(page.content.chars().skip(start_index).take(max_length));
+    let end = (start_index + max_length).min(total);
+    if end < total {
+        output.push_str(&format!(
+            "\n\n[Showing characters {} to {} of {}. Call fetch_url again with start_index={} to read more.]",
+            start_index, end, total, end
+        ));
+    }
+    if page.truncated {
+        output.push_str("\n\n[The page was cut off at the fetch size limit.]");
+    }
+    output
+}
+
+pub struct FetchClient {
+    info: InitializeResult,
+    /// Set up on first use, so the fetch settings are read when they are needed
+    fetcher: OnceCell<WebFetcher>,
+}
+
+impl FetchClient {
+    pub fn new(_context: PlatformExtensionContext) -> Result<Self> {
+        let info = InitializeResult {
+            protocol_version: ProtocolVersion::V_2025_03_26,
+            capabilities: ServerCapabilities {
+                tools: Some(ToolsCapability {
+                    list_changed: Some(false),
+                }),
+                resources: None,
+                prompts: None,
+                completions: None,
+                experimental: None,
+                logging: None,
+            },
+            server_info: Implementation {
+                name: EXTENSION_NAME.to_string(),
+                title: Some("Fetch".to_string()),
+                version: "1.0.0".to_string(),
+                icons: None,
+                website_url: None,
+            },
+            instructions: Some(
+                indoc! {r#"
+                Fetch
+
+                Use fetch_url to read web pages, such as documentation, articles and API
+                references. Pages come back as Markdown of their main content; long pages are
+                cut off and can be read further with start_index. Some sites may be off limits
+                because of the user's settings or the site's robots.txt.
+            "#}
+                .to_string(),
+            ),
+        };
+
+        Ok(Self {
+            info,
+            fetcher: OnceCell::new(),
+        })
+    }
+
+    async fn fetcher(&self) -> Result<&WebFetcher, String> {
+        self.fetcher
+            .get_or_try_init(|| async { WebFetcher::from_config() })
+            .await
+            .map_err(|e| format!("Fetching is unavailable: {}", e))
+    }
+
+    async fn handle_fetch_url(
+        &self,
+        arguments: Option<JsonObject>,
+    ) -> Result<Vec<Content>, String> {
+        let arguments = arguments.ok_or("Missing arguments")?;
+        let params: FetchUrlParams =
+            serde_json::from_value(Value::Object(arguments)).map_err(|e| e.to_string())?;
+        let page = self.fetcher().await?.fetch(&params.url, params.raw).await?;
+        Ok(vec![Content::text(format_page(
+            &page,
+            params.start_index,
+            params.max_length.unwrap_or(DEFAULT_MAX_LENGTH),
+        ))])
+    }
+
+    fn get_tools() -> Vec<Tool> {
+        let schema = schema_for!(FetchUrlParams);
+        let schema_value =
+            serde_json::to_value(schema).expect("Failed to serialize FetchUrlParams schema");
+
+        vec![Tool::new(
+            "fetch_url".to_string(),
+            indoc! {r#"
+                Fetch a web page and return its main content as Markdown.
+
+                Navigation, ads and scripts are left out. Plain text and JSON are returned as
+                they are. Use start_index to continue reading a page that was cut off.
+            "#}
+            .to_string(),
+            schema_value.as_object().unwrap().clone(),
+        )
+        .annotate(ToolAnnotations {
+            title: Some("Fetch URL".to_string()),
+            read_only_hint: Some(true),
+            destructive_hint: Some(false),
+            idempotent_hint: Some(true),
+            open_world_hint: Some(true),
+        })]
+    }
+}
+
+#[async_trait]
+impl McpClientTrait for FetchClient {
+    async fn list_resources(
+        &self,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListResourcesResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn read_resource(
+        &self,
+        _uri: &str,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ReadResourceResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn list_tools(
+        &self,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListToolsResult, Error> {
+        Ok(ListToolsResult {
+            tools: Self::get_tools(),
+            next_cursor: None,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        name: &str,
+        arguments: Option<JsonObject>,
+        cancellation_token: CancellationToken,
+    ) -> Result<CallToolResult, Error> {
+        let content = match name {
+            "fetch_url" => tokio::select! {
+                content = self.handle_fetch_url(arguments) => content,
+                _ = cancellation_token.cancelled() => Err("Fetch cancelled".to_string()),
+            },
+            _ => Err(format!("Unknown tool: {}", name)),
+        };
+
+        match content {
+            Ok(content) => Ok(CallToolResult::success(content)),
+            Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Error: {}",
+                error
+            ))])),
+        }
+    }
+
+    async fn list_prompts(
+        &self,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListPromptsResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn get_prompt(
+        &self,
+        _name: &str,
+        _arguments: Value,
+        _cancellation_token: CancellationToken,
+    ) -> Result<GetPromptResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn subscribe(&self) -> mpsc::Receiver<ServerNotification> {
+        mpsc::channel(1).1
+    }
+
+    fn get_info(&self) -> Option<&InitializeResult> {
+        Some(&self.info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use url::Url;
+
+    #[test]
+    fn test_format_page_pages_through_content() {
+        let page = FetchedPage {
+            url: Url::parse("https://example.com/").unwrap(),
+            title: Some("Example".to_string()),
+            content: "abcdefghij".to_string(),
+            truncated: false,
+        };
+
+        let first = format_page(&page, 0, 4);
+        assert!(first.starts_with("# Example\n\nSource: https://example.com/\n\nabcd\n\n"));
+        assert!(first.contains("start_index=4"));
+        assert!(format_page(&page, 8, 4).ends_with("ij"));
+        assert!(format_page(&page, 10, 4).contains("No more content"));
+    }
+}
@@ -8,6 +8,11 @@ use std::borrow::Cow;
 pub const FINAL_OUTPUT_TOOL_NAME: &str = "recipe__final_output";
 pub const FINAL_OUTPUT_CONTINUATION_MESSAGE: &str =
     "You MUST call the `final_output` tool NOW with the final output for the user.";
+/// Asks for the final output as the response itself, from providers with structured output
+pub const FINAL_OUTPUT_RESPONSE_MESSAGE: &str =
+    "Respond NOW with the final output for the user as JSON matching the schema.";
+/// Key of the final output instructions among the system prompt extras
+pub const FINAL_OUTPUT_PROMPT_KEY: &str = "final_output";
 
 pub struct FinalOutputTool {
     pub response: Response,
@@ -118,27 +123,21 @@ impl FinalOutputTool {
 
     pub async fn execute_tool_call(&mut self, tool_call: CallToolRequestParam) -> ToolCallResult {
         match tool_call.name.to_string().as_str() {
-            FINAL_OUTPUT_TOOL_NAME => {
-                let result = self.validate_json_output(&tool_call.arguments.into()).await;
-                match result {
-                    Ok(parsed_value) => {
-                        self.final_output = Some(Self::parsed_final_output_string(parsed_value));
-                        ToolCallResult::from(Ok(rmcp::model::CallToolResult {
-                            content: vec![Content::text(
-                                "Final output successfully collected.".to_string(),
-                            )],
-                            structured_content: None,
-                            is_error: Some(false),
-                            meta: None,
-                        }))
-                    }
-                    Err(error) => ToolCallResult::from(Err(ErrorData {
-                        code: ErrorCode::INVALID_PARAMS,
-                        message: Cow::from(error),
-                        data: None,
-                    })),
-                }
-            }
+            FINAL_OUTPUT_TOOL_NAME => match self.collect(tool_call.arguments.into()).await {
+                Ok(()) => ToolCallResult::from(Ok(rmcp::model::CallToolResult {
+                    content: vec![Content::text(
+                        "Final output successfully collected.".to_string(),
+                    )],
+                    structured_content: None,
+                    is_error: Some(false),
+                    meta: None,
+                })),
+                Err(error) => ToolCallResult::from(Err(ErrorData {
+                    code: ErrorCode::INVALID_PARAMS,
+                    message: Cow::from(error),
+                    data: None,
+                })),
+            },
             _ => ToolCallResult::from(Err(ErrorData {
                 code: ErrorCode::INVALID_REQUEST,
                 message: Cow::from(format!("Unknown tool: {}", tool_call.name)),
@@ -147,6 +146,20 @@ impl FinalOutputTool {
         }
     }
 
+    /// Keep `output` as the final output if it matches the schema
+    pub async fn collect(&mut self, output: Value) -> Result<(), String> {
+        let parsed_value = self.validate_json_output(&output).await?;
+        self.final_output = Some(Self::parsed_final_output_string(parsed_value));
+        Ok(())
+    }
+
+    /// The collected final output as JSON
+    pub fn final_value(&self) -> Option<Value> {
+        self.final_output
+            .as_deref()
+            .and_then(|output| serde_json::from_str(output).ok())
+    }
+
     // Formats the parsed JSON as a single line string so its easy to extract from the output
     fn parsed_final_output_string(parsed_json: Value) -> String {
         serde_json::to_string(&parsed_json).unwrap()
@@ -269,6 +282,10 @@ mod tests {
         let tool_result = result.result.await;
         assert!(tool_result.is_ok());
         assert!(tool.final_output.is_some());
+        assert_eq!(
+            tool.final_value(),
+            Some(json!({"user": {"name": "John", "age": 30}, "tags": ["developer", "rust"]}))
+        );
 
         let final_output = tool.final_output.unwrap();
         assert!(serde_json::from_str::<Value>(&final_output).is_ok());
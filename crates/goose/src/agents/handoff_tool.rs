@@ -0,0 +1,179 @@
+use std::borrow::Cow;
+
+use rmcp::model::{CallToolRequestParam, Content, ErrorCode, ErrorData, Tool, ToolAnnotations};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::agents::tool_execution::ToolCallResult;
+
+pub const HANDOFF_TOOL_NAME: &str = "handoff";
+
+/// An agent the conversation can be handed to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HandoffTarget {
+    pub name: String,
+    pub description: String,
+}
+
+/// A handoff requested by the model, to be carried out once its turn ends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Handoff {
+    pub to: String,
+    pub reason: String,
+}
+
+#[derive(Deserialize)]
+struct HandoffParams {
+    to: String,
+    #[serde(default)]
+    reason: String,
+}
+
+pub struct HandoffTool {
+    pub targets: Vec<HandoffTarget>,
+    /// The handoff requested this turn, if any
+    pub pending: Option<Handoff>,
+}
+
+impl HandoffTool {
+    pub fn new(targets: Vec<HandoffTarget>) -> Self {
+        Self {
+            targets,
+            pending: None,
+        }
+    }
+
+    pub fn tool(&self) -> Tool {
+        let mut description = String::from(
+            "Hand the conversation over to another agent that is better suited to continue it. \
+             The other agent sees the whole conversation so far and takes over once your turn \
+             ends, so finish what you are doing before handing off and explain why in `reason`.\n\n\
+             Agents you can hand off to:",
+        );
+        for target in &self.targets {
+            description.push_str(&format!("\n• {} - {}", target.name, target.description));
+        }
+        let names: Vec<&str> = self
+            .targets
+            .iter()
+            .map(|target| target.name.as_str())
+            .collect();
+
+        let schema = json!({
+            "type": "object",
+            "required": ["to", "reason"],
+            "properties": {
+                "to": {
+                    "type": "string",
+                    "enum": names,
+                    "description": "Name of the agent to hand the conversation to"
+                },
+                "reason": {
+                    "type": "string",
+                    "description": "What the other agent should do next, and why it is the one to do it"
+                }
+            }
+        });
+
+        Tool::new(
+            HANDOFF_TOOL_NAME,
+            description,
+            schema.as_object().unwrap().clone(),
+        )
+        .annotate(ToolAnnotations {
+            title: Some("Handoff".to_string()),
+            read_only_hint: Some(true),
+            destructive_hint: Some(false),
+            idempotent_hint: Some(true),
+            open_world_hint: Some(false),
+        })
+    }
+
+    pub fn execute_tool_call(&mut self, tool_call: CallToolRequestParam) -> ToolCallResult {
+        let params: HandoffParams =
+            match serde_json::from_value(tool_call.arguments.unwrap_or_default().into()) {
+                Ok(params) => params,
+                Err(e) => {
+                    return ToolCallResult::from(Err(ErrorData {
+                        code: ErrorCode::INVALID_PARAMS,
+                        message: Cow::from(format!("Invalid parameters: {}", e)),
+                        data: None,
+                    }))
+                }
+            };
+
+        if !self.targets.iter().any(|target| target.name == params.to) {
+            let available: Vec<&str> = self
+                .targets
+                .iter()
+                .map(|target| target.name.as_str())
+                .collect();
+            return ToolCallResult::from(Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: Cow::from(format!(
+                    "Can't hand off to '{}'. Available: {}",
+                    params.to,
+                    available.join(", ")
+                )),
+                data: None,
+            }));
+        }
+        if let Some(pending) = &self.pending {
+            return ToolCallResult::from(Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: Cow::from(format!("Already handing off to '{}' this turn", pending.to)),
+                data: None,
+            }));
+        }
+
+        let text = format!("Handing the conversation off to {}.", params.to);
+        self.pending = Some(Handoff {
+            to: params.to,
+            reason: params.reason,
+        });
+        ToolCallResult::from(Ok(rmcp::model::CallToolResult {
+            content: vec![Content::text(text)],
+            structured_content: None,
+            is_error: Some(false),
+            meta: None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(arguments: serde_json::Value) -> CallToolRequestParam {
+        CallToolRequestParam {
+            name: HANDOFF_TOOL_NAME.into(),
+            arguments: arguments.as_object().cloned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handoff_records_one_target_per_turn() {
+        let mut tool = HandoffTool::new(vec![HandoffTarget {
+            name: "reviewer".to_string(),
+            description: "Reviews code changes".to_string(),
+        }]);
+        assert!(tool.tool().description.unwrap().contains("reviewer"));
+
+        let unknown = tool.execute_tool_call(call(json!({"to": "deployer", "reason": "ship"})));
+        assert!(unknown.result.await.is_err());
+        assert!(tool.pending.is_none());
+
+        let handed = tool.execute_tool_call(call(json!({"to": "reviewer", "reason": "done"})));
+        assert!(handed.result.await.is_ok());
+        assert_eq!(
+            tool.pending,
+            Some(Handoff {
+                to: "reviewer".to_string(),
+                reason: "done".to_string(),
+            })
+        );
+
+        let again = tool.execute_tool_call(call(json!({"to": "reviewer", "reason": "again"})));
+        assert!(again.result.await.is_err());
+    }
+}
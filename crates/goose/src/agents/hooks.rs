@@ -0,0 +1,291 @@
+//! Hooks into the agent loop.
+//!
+//! An [`AgentHook`] is called at fixed points of every reply: before and after each request to
+//! the model, before and after each tool call, and at the end of each turn. Hooks can change
+//! what passes through those points or veto it, which makes room for guardrails, prompt
+//! injection scanning and telemetry without touching the loop itself. Hooks run in the order
+//! they were added with [`Agent::add_hook`](crate::agents::Agent::add_hook), and the first veto
+//! wins. A hook that fails is logged and skipped, so a broken hook doesn't stop the agent.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use rmcp::model::{CallToolRequestParam, CallToolResult};
+use tracing::warn;
+
+use crate::conversation::message::Message;
+use crate::mcp_utils::ToolResult;
+use crate::model::ToolChoice;
+
+/// Whether the agent may go ahead
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookDecision {
+    Continue,
+    /// Stop, for the given reason
+    Veto(String),
+}
+
+/// Where in the agent's run a hook is being called
+#[derive(Debug, Clone)]
+pub struct HookContext {
+    pub session_id: String,
+}
+
+/// A request about to be sent to the model
+#[derive(Debug, Clone)]
+pub struct ModelCall {
+    pub system_prompt: String,
+    pub messages: Vec<Message>,
+    /// Forces or disables tool use for this request, overriding the model's tool choice
+    pub tool_choice: Option<ToolChoice>,
+}
+
+/// Callbacks into the agent loop. Each does nothing by default, so a hook only implements the
+/// ones it needs.
+#[async_trait]
+pub trait AgentHook: Send + Sync {
+    /// Name of this hook, for logs and veto messages
+    fn name(&self) -> &str;
+
+    /// Before each request to the model. A veto ends the reply without making the request.
+    async fn before_model_call(
+        &self,
+        _context: &HookContext,
+        _call: &mut ModelCall,
+    ) -> Result<HookDecision> {
+        Ok(HookDecision::Continue)
+    }
+
+    /// After each message of the model's response, as it streams in. A veto drops the message
+    /// and ends the reply.
+    async fn after_model_call(
+        &self,
+        _context: &HookContext,
+        _response: &mut Message,
+    ) -> Result<HookDecision> {
+        Ok(HookDecision::Continue)
+    }
+
+    /// Before a tool call runs. A veto gives the model an error in place of the tool's result.
+    async fn before_tool(
+        &self,
+        _context: &HookContext,
+        _tool_call: &mut CallToolRequestParam,
+    ) -> Result<HookDecision> {
+        Ok(HookDecision::Continue)
+    }
+
+    /// After a tool call finished, before the model sees its result
+    async fn after_tool(
+        &self,
+        _context: &HookContext,
+        _tool_call: &CallToolRequestParam,
+        _result: &mut ToolResult<CallToolResult>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Once a turn is over, with the messages it added to the conversation. A veto stops the
+    /// agent instead of taking another turn.
+    async fn on_turn_end(
+        &self,
+        _context: &HookContext,
+        _messages: &[Message],
+    ) -> Result<HookDecision> {
+        Ok(HookDecision::Continue)
+    }
+}
+
+/// The hooks added to an agent, run one after another
+#[derive(Clone, Default)]
+pub struct AgentHooks {
+    hooks: Vec<Arc<dyn AgentHook>>,
+}
+
+/// The reason to give for `decision`, if it's a veto. A hook that failed is logged and counts
+/// as letting the agent go ahead.
+fn veto_reason(name: &str, point: &str, decision: Result<HookDecision>) -> Option<String> {
+    match decision {
+        Ok(HookDecision::Continue) => None,
+        Ok(HookDecision::Veto(reason)) => Some(format!("Stopped by the {} hook: {}", name, reason)),
+        Err(e) => {
+            warn!("Hook {} failed in {}: {}", name, point, e);
+            None
+        }
+    }
+}
+
+impl AgentHooks {
+    pub fn add(&mut self, hook: Arc<dyn AgentHook>) {
+        self.hooks.push(hook);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Run `before_model_call` on each hook, returning the reason of the first veto
+    pub async fn before_model_call(
+        &self,
+        context: &HookContext,
+        call: &mut ModelCall,
+    ) -> Option<String> {
+        for hook in &self.hooks {
+            let decision = hook.before_model_call(context, call).await;
+            if let Some(veto) = veto_reason(hook.name(), "before_model_call", decision) {
+                return Some(veto);
+            }
+        }
+        None
+    }
+
+    pub async fn after_model_call(
+        &self,
+        context: &HookContext,
+        response: &mut Message,
+    ) -> Option<String> {
+        for hook in &self.hooks {
+            let decision = hook.after_model_call(context, response).await;
+            if let Some(veto) = veto_reason(hook.name(), "after_model_call", decision) {
+                return Some(veto);
+            }
+        }
+        None
+    }
+
+    pub async fn before_tool(
+        &self,
+        context: &HookContext,
+        tool_call: &mut CallToolRequestParam,
+    ) -> Option<String> {
+        for hook in &self.hooks {
+            let decision = hook.before_tool(context, tool_call).await;
+            if let Some(veto) = veto_reason(hook.name(), "before_tool", decision) {
+                return Some(veto);
+            }
+        }
+        None
+    }
+
+    pub async fn after_tool(
+        &self,
+        context: &HookContext,
+        tool_call: &CallToolRequestParam,
+        result: &mut ToolResult<CallToolResult>,
+    ) {
+        for hook in &self.hooks {
+            if let Err(e) = hook.after_tool(context, tool_call, result).await {
+                warn!("Hook {} failed in after_tool: {}", hook.name(), e);
+            }
+        }
+    }
+
+    pub async fn on_turn_end(&self, context: &HookContext, messages: &[Message]) -> Option<String> {
+        for hook in &self.hooks {
+            let decision = hook.on_turn_end(context, messages).await;
+            if let Some(veto) = veto_reason(hook.name(), "on_turn_end", decision) {
+                return Some(veto);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use rmcp::model::Content;
+
+    struct Redactor;
+
+    #[async_trait]
+    impl AgentHook for Redactor {
+        fn name(&self) -> &str {
+            "redactor"
+        }
+
+        async fn after_tool(
+            &self,
+            _context: &HookContext,
+            _tool_call: &CallToolRequestParam,
+            result: &mut ToolResult<CallToolResult>,
+        ) -> Result<()> {
+            if let Ok(result) = result {
+                result.content = vec![Content::text("[redacted]")];
+            }
+            Ok(())
+        }
+    }
+
+    struct NoShell;
+
+    #[async_trait]
+    impl AgentHook for NoShell {
+        fn name(&self) -> &str {
+            "no-shell"
+        }
+
+        async fn before_tool(
+            &self,
+            _context: &HookContext,
+            tool_call: &mut CallToolRequestParam,
+        ) -> Result<HookDecision> {
+            if tool_call.name == "developer__shell" {
+                return Ok(HookDecision::Veto("shell is off limits".to_string()));
+            }
+            Ok(HookDecision::Continue)
+        }
+    }
+
+    struct Broken;
+
+    #[async_trait]
+    impl AgentHook for Broken {
+        fn name(&self) -> &str {
+            "broken"
+        }
+
+        async fn before_tool(
+            &self,
+            _context: &HookContext,
+            _tool_call: &mut CallToolRequestParam,
+        ) -> Result<HookDecision> {
+            Err(anyhow!("oops"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks_run_in_order_and_veto() {
+        let mut hooks = AgentHooks::default();
+        hooks.add(Arc::new(Broken));
+        hooks.add(Arc::new(NoShell));
+        hooks.add(Arc::new(Redactor));
+        let context = HookContext {
+            session_id: "session-1".to_string(),
+        };
+
+        let mut shell = CallToolRequestParam {
+            name: "developer__shell".into(),
+            arguments: None,
+        };
+        assert_eq!(
+            hooks.before_tool(&context, &mut shell).await.as_deref(),
+            Some("Stopped by the no-shell hook: shell is off limits")
+        );
+
+        let mut editor = CallToolRequestParam {
+            name: "developer__text_editor".into(),
+            arguments: None,
+        };
+        assert_eq!(hooks.before_tool(&context, &mut editor).await, None);
+
+        let mut result = Ok(CallToolResult::success(vec![Content::text("secret")]));
+        hooks.after_tool(&context, &editor, &mut result).await;
+        assert_eq!(
+            result.unwrap().content[0].as_text().unwrap().text,
+            "[redacted]"
+        );
+    }
+}
@@ -1,13 +1,43 @@
-use chrono::Utc;
-use rmcp::model::{CallToolResult, Content, ErrorData};
-use std::fs::File;
+//! Capping of large tool outputs.
+//!
+//! Text content longer than the output limit (GOOSE_TOOL_OUTPUT_LIMIT characters, default
+//! 200,000) is written to an overflow store on disk and only its first page enters the
+//! conversation, followed by a note on how to page through the rest with the
+//! [`platform__read_more`](PLATFORM_READ_MORE_TOOL_NAME) tool.
+
+use crate::agents::platform_tools::PLATFORM_READ_MORE_TOOL_NAME;
+use crate::config::Config;
+use rmcp::model::{CallToolResult, Content, ErrorCode, ErrorData};
+use serde_json::Value;
+use std::fs::{self, File};
 use std::io::Write;
+use std::path::PathBuf;
+use uuid::Uuid;
 
 const LARGE_TEXT_THRESHOLD: usize = 200_000;
 
+/// Largest text, in characters, that a tool output may put into the conversation at once
+fn output_limit() -> usize {
+    Config::global()
+        .get_param("GOOSE_TOOL_OUTPUT_LIMIT")
+        .unwrap_or(LARGE_TEXT_THRESHOLD)
+        .max(1)
+}
+
+fn overflow_dir() -> PathBuf {
+    std::env::temp_dir().join("goose_mcp_responses")
+}
+
 /// Process tool response and handle large text content
 pub fn process_tool_response(
     response: Result<CallToolResult, ErrorData>,
+) -> Result<CallToolResult, ErrorData> {
+    cap_tool_response(response, output_limit())
+}
+
+fn cap_tool_response(
+    response: Result<CallToolResult, ErrorData>,
+    limit: usize,
 ) -> Result<CallToolResult, ErrorData> {
     match response {
         Ok(mut result) => {
@@ -15,39 +45,29 @@ pub fn process_tool_response(
 
             for content in result.content {
                 match content.as_text() {
-                    Some(text_content) => {
-                        // Check if text exceeds threshold
-                        if text_content.text.chars().count() > LARGE_TEXT_THRESHOLD {
-                            // Write to temp file
-                            match write_large_text_to_file(&text_content.text) {
-                                Ok(file_path) => {
-                                    // Create a new text content with reference to the file
-                                    let message = format!(
-                                        "The response returned from the tool call was larger ({} characters) and is stored in the file which you can use other tools to examine or search in: {}",
-                                        text_content.text.chars().count(),
-                                        file_path
-                                    );
-                                    processed_contents.push(Content::text(message));
-                                }
-                                Err(e) => {
-                                    // If file writing fails, include original content with warning
-                                    let warning = format!(
-                                        "Warning: Failed to write large response to file: {}. Showing full content instead.\n\n{}",
-                                        e,
-                                        text_content.text
-                                    );
-                                    processed_contents.push(Content::text(warning));
-                                }
+                    Some(text_content) if text_content.text.chars().count() > limit => {
+                        match store_overflow(&text_content.text) {
+                            Ok(id) => {
+                                processed_contents.push(Content::text(page(
+                                    &id,
+                                    &text_content.text,
+                                    0,
+                                    limit,
+                                )));
+                            }
+                            Err(e) => {
+                                // If the store fails, include original content with warning
+                                let warning = format!(
+                                    "Warning: Failed to store large response: {}. Showing full content instead.\n\n{}",
+                                    e,
+                                    text_content.text
+                                );
+                                processed_contents.push(Content::text(warning));
                             }
-                        } else {
-                            // Keep original content for smaller texts
-                            processed_contents.push(content);
                         }
                     }
-                    None => {
-                        // Pass through other content types unchanged
-                        processed_contents.push(content);
-                    }
+                    // Pass through smaller texts and other content types unchanged
+                    _ => processed_contents.push(content),
                 }
             }
 
@@ -58,31 +78,69 @@ pub fn process_tool_response(
     }
 }
 
-/// Write large text content to a temporary file
-fn write_large_text_to_file(content: &str) -> Result<String, std::io::Error> {
-    // Create temp directory if it doesn't exist
-    let temp_dir = std::env::temp_dir().join("goose_mcp_responses");
-    std::fs::create_dir_all(&temp_dir)?;
+/// Write the full text to the overflow store, returning the id to read it back with
+fn store_overflow(content: &str) -> Result<String, std::io::Error> {
+    let dir = overflow_dir();
+    fs::create_dir_all(&dir)?;
 
-    // Generate a unique filename with timestamp
-    let timestamp = Utc::now().format("%Y%m%d_%H%M%S%.6f");
-    let filename = format!("mcp_response_{}.txt", timestamp);
-    let file_path = temp_dir.join(&filename);
-
-    // Write content to file
-    let mut file = File::create(&file_path)?;
+    let id = Uuid::new_v4().simple().to_string();
+    let mut file = File::create(dir.join(format!("{}.txt", id)))?;
     file.write_all(content.as_bytes())?;
 
-    Ok(file_path.to_string_lossy().to_string())
+    Ok(id)
+}
+
+/// At most `limit` characters of `text` from `offset`, with a note on where the page ends
+fn page(id: &str, text: &str, offset: usize, limit: usize) -> String {
+    let total = text.chars().count();
+    let chunk: String = text.chars().skip(offset).take(limit).collect();
+    let end = (offset + limit).min(total);
+    if end < total {
+        format!(
+            "{}\n\n[Output truncated: showing characters {}-{} of {}. Call {} with id \"{}\" and offset {} to read more.]",
+            chunk, offset, end, total, PLATFORM_READ_MORE_TOOL_NAME, id, end
+        )
+    } else {
+        format!(
+            "{}\n\n[Showing characters {}-{} of {}, the end of the output.]",
+            chunk, offset, end, total
+        )
+    }
+}
+
+/// Handle a call to the read more tool
+pub fn read_more(arguments: Value) -> Result<Vec<Content>, ErrorData> {
+    let invalid = |message: String| ErrorData::new(ErrorCode::INVALID_PARAMS, message, None);
+
+    let id = arguments
+        .get("id")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid("Missing 'id' parameter".to_string()))?;
+    let offset = arguments.get("offset").and_then(Value::as_u64).unwrap_or(0) as usize;
+    // Ids are generated by us, so anything else can't name a stored output
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(invalid(format!("Invalid output id '{}'", id)));
+    }
+
+    let text = fs::read_to_string(overflow_dir().join(format!("{}.txt", id)))
+        .map_err(|_| invalid(format!("No stored output with id '{}'", id)))?;
+    let total = text.chars().count();
+    if offset >= total {
+        return Err(invalid(format!(
+            "Offset {} is past the end of the output ({} characters)",
+            offset, total
+        )));
+    }
+
+    Ok(vec![Content::text(page(id, &text, offset, output_limit()))])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use rmcp::model::{Content, ErrorCode, ErrorData};
+    use serde_json::json;
     use std::borrow::Cow;
-    use std::fs;
-    use std::path::Path;
 
     #[test]
     fn test_small_text_response_passes_through() {
@@ -109,47 +167,44 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_large_text_response_redirected_to_file() {
-        // Create a text larger than the threshold
-        let large_text = "a".repeat(LARGE_TEXT_THRESHOLD + 1000);
-        let content = Content::text(large_text.clone());
+    /// The overflow id named in a truncation note
+    fn overflow_id(text: &str) -> String {
+        let id = text.split("with id \"").nth(1).expect("truncation note");
+        id.split('"').next().unwrap().to_string()
+    }
 
+    #[test]
+    fn test_large_text_response_is_paged() {
+        let large_text = "0123456789".repeat(25);
+        let (first_page, rest_of_text) = large_text.split_at(100);
         let response = Ok(CallToolResult {
-            content: vec![content],
+            content: vec![Content::text(large_text.clone())],
             structured_content: None,
             is_error: Some(false),
             meta: None,
         });
 
-        // Process the response
-        let processed = process_tool_response(response).unwrap();
+        let processed = cap_tool_response(response, 100).unwrap();
 
-        // Verify the response contains a message about the file
         assert_eq!(processed.content.len(), 1);
-        if let Some(text_content) = processed.content[0].as_text() {
-            assert!(text_content
-                .text
-                .contains("The response returned from the tool call was larger"));
-            assert!(text_content.text.contains("characters"));
-
-            // Extract the file path from the message
-            if let Some(file_path) = text_content.text.split("stored in the file: ").nth(1) {
-                // Verify the file exists and contains the original text
-                let path = Path::new(file_path.trim());
-                if path.exists() {
-                    // Only check content if file exists (may not exist in CI environments)
-                    if let Ok(file_content) = fs::read_to_string(path) {
-                        assert_eq!(file_content, large_text);
-                    }
-
-                    // Clean up the file
-                    let _ = fs::remove_file(path); // Ignore errors on cleanup
-                }
-            }
-        } else {
-            panic!("Expected text content");
-        }
+        let text = &processed.content[0].as_text().expect("text content").text;
+        assert!(text.starts_with(first_page));
+        assert!(!text.contains(&format!("{}0", first_page)));
+        assert!(text.contains("showing characters 0-100 of 250"));
+        assert!(text.contains("and offset 100"));
+
+        let id = overflow_id(text);
+        let stored = overflow_dir().join(format!("{}.txt", id));
+        assert_eq!(fs::read_to_string(&stored).unwrap(), large_text);
+
+        let rest = read_more(json!({"id": id, "offset": 100})).unwrap();
+        let rest = &rest[0].as_text().expect("text content").text;
+        assert!(rest.starts_with(rest_of_text));
+        assert!(rest.contains("the end of the output"));
+
+        assert!(read_more(json!({"id": id, "offset": 250})).is_err());
+        assert!(read_more(json!({"id": "../secrets", "offset": 0})).is_err());
+        let _ = fs::remove_file(stored);
     }
 
     #[test]
@@ -192,7 +247,7 @@ mod tests {
         });
 
         // Process the response
-        let processed = process_tool_response(response).unwrap();
+        let processed = cap_tool_response(response, LARGE_TEXT_THRESHOLD).unwrap();
 
         // Verify each item is handled correctly
         assert_eq!(processed.content.len(), 3);
@@ -204,19 +259,11 @@ mod tests {
             panic!("Expected text content");
         }
 
-        // Second item should be a message about the file
+        // Second item should be the first page with a truncation note
         if let Some(text_content) = processed.content[1].as_text() {
-            assert!(text_content
-                .text
-                .contains("The response returned from the tool call was larger"));
-
-            // Extract the file path and clean up
-            if let Some(file_path) = text_content.text.split("stored in the file: ").nth(1) {
-                let path = Path::new(file_path.trim());
-                if path.exists() {
-                    let _ = fs::remove_file(path); // Ignore errors on cleanup
-                }
-            }
+            assert!(text_content.text.contains("Output truncated"));
+            let id = overflow_id(&text_content.text);
+            let _ = fs::remove_file(overflow_dir().join(format!("{}.txt", id)));
         } else {
             panic!("Expected text content");
         }
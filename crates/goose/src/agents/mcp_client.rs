@@ -1,8 +1,9 @@
 use crate::action_required_manager::ActionRequiredManager;
-use crate::agents::types::SharedProvider;
+use crate::agents::sampling::SamplingHandler;
+use crate::agents::workspace_roots::WorkspaceRoots;
 use crate::session_context::SESSION_ID_HEADER;
 use rmcp::model::{
-    Content, CreateElicitationRequestParam, CreateElicitationResult, ElicitationAction, ErrorCode,
+    CreateElicitationRequestParam, CreateElicitationResult, ElicitationAction, ErrorCode,
     JsonObject,
 };
 /// MCP client implementation for Goose
@@ -13,10 +14,13 @@ use rmcp::{
         ClientRequest, CreateMessageRequestParam, CreateMessageResult, GetPromptRequest,
         GetPromptRequestParam, GetPromptResult, Implementation, InitializeResult,
         ListPromptsRequest, ListPromptsResult, ListResourcesRequest, ListResourcesResult,
-        ListToolsRequest, ListToolsResult, LoggingMessageNotification,
-        LoggingMessageNotificationMethod, PaginatedRequestParam, ProgressNotification,
-        ProgressNotificationMethod, ProtocolVersion, ReadResourceRequest, ReadResourceRequestParam,
-        ReadResourceResult, RequestId, Role, SamplingMessage, ServerNotification, ServerResult,
+        ListRootsResult, ListToolsRequest, ListToolsResult, LoggingMessageNotification,
+        LoggingMessageNotificationMethod, PaginatedRequestParam, PingRequest, PingRequestMethod,
+        ProgressNotification, ProgressNotificationMethod, ProtocolVersion, ReadResourceRequest,
+        ReadResourceRequestParam, ReadResourceResult, RequestId, ResourceUpdatedNotification,
+        ResourceUpdatedNotificationMethod, ResourceUpdatedNotificationParam, ServerNotification,
+        ServerResult, SubscribeRequest, SubscribeRequestParam, UnsubscribeRequest,
+        UnsubscribeRequestParam,
     },
     service::{
         ClientInitializeError, PeerRequestOptions, RequestContext, RequestHandle, RunningService,
@@ -64,6 +68,18 @@ pub trait McpClientTrait: Send + Sync {
         cancel_token: CancellationToken,
     ) -> Result<CallToolResult, Error>;
 
+    /// Call a tool, passing `meta` to the server in the request's `_meta`. Clients that can't
+    /// pass it call the tool without it.
+    async fn call_tool_with_meta(
+        &self,
+        name: &str,
+        arguments: Option<JsonObject>,
+        _meta: JsonObject,
+        cancel_token: CancellationToken,
+    ) -> Result<CallToolResult, Error> {
+        self.call_tool(name, arguments, cancel_token).await
+    }
+
     async fn list_prompts(
         &self,
         next_cursor: Option<String>,
@@ -77,6 +93,33 @@ pub trait McpClientTrait: Send + Sync {
         cancel_token: CancellationToken,
     ) -> Result<GetPromptResult, Error>;
 
+    /// Ask the server to notify us when resource `uri` changes
+    async fn subscribe_resource(
+        &self,
+        _uri: &str,
+        _cancel_token: CancellationToken,
+    ) -> Result<(), Error> {
+        Err(Error::UnexpectedResponse)
+    }
+
+    async fn unsubscribe_resource(
+        &self,
+        _uri: &str,
+        _cancel_token: CancellationToken,
+    ) -> Result<(), Error> {
+        Err(Error::UnexpectedResponse)
+    }
+
+    /// Check that the server is alive
+    async fn ping(&self, _cancel_token: CancellationToken) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Tell the server that the workspace roots changed
+    async fn notify_roots_list_changed(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
     async fn subscribe(&self) -> mpsc::Receiver<ServerNotification>;
 
     fn get_info(&self) -> Option<&InitializeResult>;
@@ -88,17 +131,20 @@ pub trait McpClientTrait: Send + Sync {
 
 pub struct GooseClient {
     notification_handlers: Arc<Mutex<Vec<Sender<ServerNotification>>>>,
-    provider: SharedProvider,
+    sampling: SamplingHandler,
+    roots: WorkspaceRoots,
 }
 
 impl GooseClient {
     pub fn new(
         handlers: Arc<Mutex<Vec<Sender<ServerNotification>>>>,
-        provider: SharedProvider,
+        sampling: SamplingHandler,
+        roots: WorkspaceRoots,
     ) -> Self {
         GooseClient {
             notification_handlers: handlers,
-            provider,
+            sampling,
+            roots,
         }
     }
 }
@@ -144,81 +190,40 @@ impl ClientHandler for GooseClient {
             });
     }
 
-    async fn create_message(
+    async fn on_resource_updated(
         &self,
-        params: CreateMessageRequestParam,
-        _context: RequestContext<RoleClient>,
-    ) -> Result<CreateMessageResult, ErrorData> {
-        let provider = self
-            .provider
+        params: ResourceUpdatedNotificationParam,
+        context: rmcp::service::NotificationContext<rmcp::RoleClient>,
+    ) {
+        self.notification_handlers
             .lock()
             .await
-            .as_ref()
-            .ok_or(ErrorData::new(
-                ErrorCode::INTERNAL_ERROR,
-                "Could not use provider",
-                None,
-            ))?
-            .clone();
-
-        let provider_ready_messages: Vec<crate::conversation::message::Message> = params
-            .messages
             .iter()
-            .map(|msg| {
-                let base = match msg.role {
-                    Role::User => crate::conversation::message::Message::user(),
-                    Role::Assistant => crate::conversation::message::Message::assistant(),
-                };
-
-                match msg.content.as_text() {
-                    Some(text) => base.with_text(&text.text),
-                    None => base.with_content(msg.content.clone().into()),
-                }
-            })
-            .collect();
+            .for_each(|handler| {
+                let _ = handler.try_send(ServerNotification::ResourceUpdatedNotification(
+                    ResourceUpdatedNotification {
+                        params: params.clone(),
+                        method: ResourceUpdatedNotificationMethod,
+                        extensions: context.extensions.clone(),
+                    },
+                ));
+            });
+    }
 
-        let system_prompt = params
-            .system_prompt
-            .as_deref()
-            .unwrap_or("You are a general-purpose AI agent called goose");
+    async fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<CreateMessageResult, ErrorData> {
+        self.sampling.create_message(params).await
+    }
 
-        let (response, usage) = provider
-            .complete(system_prompt, &provider_ready_messages, &[])
-            .await
-            .map_err(|e| {
-                ErrorData::new(
-                    ErrorCode::INTERNAL_ERROR,
-                    "Unexpected error while completing the prompt",
-                    Some(Value::from(e.to_string())),
-                )
-            })?;
-
-        Ok(CreateMessageResult {
-            model: usage.model,
-            stop_reason: Some(CreateMessageResult::STOP_REASON_END_TURN.to_string()),
-            message: SamplingMessage {
-                role: Role::Assistant,
-                // TODO(alexhancock): MCP sampling currently only supports one content on each SamplingMessage
-                // https://modelcontextprotocol.io/specification/draft/client/sampling#messages
-                // This doesn't mesh well with goose's approach which has Vec<MessageContent>
-                // There is a proposal to MCP which is agreed to go in the next version to have SamplingMessages support multiple content parts
-                // https://github.com/modelcontextprotocol/modelcontextprotocol/pull/198
-                // Until that is formalized, we can take the first message content from the provider and use it
-                content: if let Some(content) = response.content.first() {
-                    match content {
-                        crate::conversation::message::MessageContent::Text(text) => {
-                            Content::text(&text.text)
-                        }
-                        crate::conversation::message::MessageContent::Image(img) => {
-                            Content::image(&img.data, &img.mime_type)
-                        }
-                        // TODO(alexhancock) - Content::Audio? goose's messages don't currently have it
-                        _ => Content::text(""),
-                    }
-                } else {
-                    Content::text("")
-                },
-            },
+    async fn list_roots(
+        &self,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<ListRootsResult, ErrorData> {
+        Ok(ListRootsResult {
+            roots: self.roots.to_mcp_roots(),
         })
     }
 
@@ -259,6 +264,8 @@ impl ClientHandler for GooseClient {
         ClientInfo {
             protocol_version: ProtocolVersion::V_2025_03_26,
             capabilities: ClientCapabilities::builder()
+                .enable_roots()
+                .enable_roots_list_changed()
                 .enable_sampling()
                 .enable_elicitation()
                 .build(),
@@ -280,13 +287,15 @@ pub struct McpClient {
     notification_subscribers: Arc<Mutex<Vec<mpsc::Sender<ServerNotification>>>>,
     server_info: Option<InitializeResult>,
     timeout: std::time::Duration,
+    sampling: SamplingHandler,
 }
 
 impl McpClient {
     pub async fn connect<T, E, A>(
         transport: T,
         timeout: std::time::Duration,
-        provider: SharedProvider,
+        sampling: SamplingHandler,
+        roots: WorkspaceRoots,
     ) -> Result<Self, ClientInitializeError>
     where
         T: IntoTransport<RoleClient, E, A>,
@@ -295,7 +304,7 @@ impl McpClient {
         let notification_subscribers =
             Arc::new(Mutex::new(Vec::<mpsc::Sender<ServerNotification>>::new()));
 
-        let client = GooseClient::new(notification_subscribers.clone(), provider);
+        let client = GooseClient::new(notification_subscribers.clone(), sampling.clone(), roots);
         let client: rmcp::service::RunningService<rmcp::RoleClient, GooseClient> =
             client.serve(transport).await?;
         let server_info = client.peer_info().cloned();
@@ -305,6 +314,7 @@ impl McpClient {
             notification_subscribers,
             server_info,
             timeout,
+            sampling,
         })
     }
 
@@ -313,6 +323,7 @@ impl McpClient {
         request: ClientRequest,
         cancel_token: CancellationToken,
     ) -> Result<ServerResult, Error> {
+        self.sampling.note_session();
         let handle = self
             .client
             .lock()
@@ -443,6 +454,21 @@ impl McpClientTrait for McpClient {
         arguments: Option<JsonObject>,
         cancel_token: CancellationToken,
     ) -> Result<CallToolResult, Error> {
+        self.call_tool_with_meta(name, arguments, JsonObject::new(), cancel_token)
+            .await
+    }
+
+    async fn call_tool_with_meta(
+        &self,
+        name: &str,
+        arguments: Option<JsonObject>,
+        meta: JsonObject,
+        cancel_token: CancellationToken,
+    ) -> Result<CallToolResult, Error> {
+        let mut extensions = rmcp::model::Extensions::default();
+        if !meta.is_empty() {
+            extensions.insert(rmcp::model::Meta(meta));
+        }
         let res = self
             .send_request(
                 ClientRequest::CallToolRequest(CallToolRequest {
@@ -451,7 +477,7 @@ impl McpClientTrait for McpClient {
                         arguments,
                     },
                     method: Default::default(),
-                    extensions: inject_session_into_extensions(Default::default()),
+                    extensions: inject_session_into_extensions(extensions),
                 }),
                 cancel_token,
             )
@@ -515,6 +541,75 @@ impl McpClientTrait for McpClient {
         }
     }
 
+    async fn subscribe_resource(
+        &self,
+        uri: &str,
+        cancel_token: CancellationToken,
+    ) -> Result<(), Error> {
+        let res = self
+            .send_request(
+                ClientRequest::SubscribeRequest(SubscribeRequest {
+                    params: SubscribeRequestParam {
+                        uri: uri.to_string(),
+                    },
+                    method: Default::default(),
+                    extensions: inject_session_into_extensions(Default::default()),
+                }),
+                cancel_token,
+            )
+            .await?;
+
+        match res {
+            ServerResult::EmptyResult(_) => Ok(()),
+            _ => Err(ServiceError::UnexpectedResponse),
+        }
+    }
+
+    async fn unsubscribe_resource(
+        &self,
+        uri: &str,
+        cancel_token: CancellationToken,
+    ) -> Result<(), Error> {
+        let res = self
+            .send_request(
+                ClientRequest::UnsubscribeRequest(UnsubscribeRequest {
+                    params: UnsubscribeRequestParam {
+                        uri: uri.to_string(),
+                    },
+                    method: Default::default(),
+                    extensions: inject_session_into_extensions(Default::default()),
+                }),
+                cancel_token,
+            )
+            .await?;
+
+        match res {
+            ServerResult::EmptyResult(_) => Ok(()),
+            _ => Err(ServiceError::UnexpectedResponse),
+        }
+    }
+
+    async fn ping(&self, cancel_token: CancellationToken) -> Result<(), Error> {
+        let res = self
+            .send_request(
+                ClientRequest::PingRequest(PingRequest {
+                    method: PingRequestMethod,
+                    extensions: Default::default(),
+                }),
+                cancel_token,
+            )
+            .await?;
+
+        match res {
+            ServerResult::EmptyResult(_) => Ok(()),
+            _ => Err(ServiceError::UnexpectedResponse),
+        }
+    }
+
+    async fn notify_roots_list_changed(&self) -> Result<(), Error> {
+        self.client.lock().await.notify_roots_list_changed().await
+    }
+
     async fn subscribe(&self) -> mpsc::Receiver<ServerNotification> {
         let (tx, rx) = mpsc::channel(16);
         self.notification_subscribers.lock().await.push(tx);
@@ -0,0 +1,356 @@
+use crate::agents::extension::PlatformExtensionContext;
+use crate::agents::mcp_client::{Error, McpClientTrait};
+use crate::memory::{MemoryStore, RecalledMemory};
+use crate::session::SessionManager;
+use anyhow::Result;
+use async_trait::async_trait;
+use indoc::indoc;
+use rmcp::model::{
+    CallToolResult, Content, GetPromptResult, Implementation, InitializeResult, JsonObject,
+    ListPromptsResult, ListResourcesResult, ListToolsResult, ProtocolVersion, ReadResourceResult,
+    Role, ServerCapabilities, ServerNotification, Tool, ToolAnnotations, ToolsCapability,
+};
+use schemars::{schema_for, JsonSchema};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Write as _;
+use tokio::sync::{mpsc, Mutex, OnceCell};
+use tokio_util::sync::CancellationToken;
+
+pub static EXTENSION_NAME: &str = "longterm_memory";
+
+/// How many memories to show at the start of each turn
+const TURN_START_RECALL_LIMIT: usize = 3;
+
+/// Parameters for the remember tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct RememberParams {
+    /// The fact or note to keep, self-contained so it makes sense in a later session
+    text: String,
+    /// Optional labels, such as 'preference' or a project name
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Parameters for the recall tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct RecallParams {
+    /// What to look for, in natural language
+    query: String,
+    /// Max results (default: 5, max: 20)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<usize>,
+}
+
+/// Parameters for the forget tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct ForgetParams {
+    /// ID of the memory to delete, as shown by recall
+    id: String,
+}
+
+fn parse_params<T: DeserializeOwned>(arguments: Option<JsonObject>) -> Result<T, String> {
+    let arguments = arguments.ok_or("Missing arguments")?;
+    serde_json::from_value(Value::Object(arguments)).map_err(|e| e.to_string())
+}
+
+fn format_memories(recalled: &[RecalledMemory]) -> String {
+    let mut output = String::new();
+    for RecalledMemory { memory, relevance } in recalled {
+        let _ = write!(output, "- [{}] {}", memory.id, memory.text);
+        if !memory.tags.is_empty() {
+            let _ = write!(output, " (tags: {})", memory.tags.join(", "));
+        }
+        let _ = writeln!(
+            output,
+            " (saved {}, relevance {:.2})",
+            memory.created_at.format("%Y-%m-%d"),
+            relevance
+        );
+    }
+    output
+}
+
+pub struct MemoryClient {
+    info: InitializeResult,
+    context: PlatformExtensionContext,
+    /// Set up on first use, since creating the embedding provider is async
+    store: OnceCell<MemoryStore>,
+    /// The latest user request and what it recalled, so the memories shown at the start of
+    /// each turn are only looked up again when the request changes
+    last_recall: Mutex<Option<(String, Option<String>)>>,
+}
+
+impl MemoryClient {
+    pub fn new(context: PlatformExtensionContext) -> Result<Self> {
+        let info = InitializeResult {
+            protocol_version: ProtocolVersion::V_2025_03_26,
+            capabilities: ServerCapabilities {
+                tools: Some(ToolsCapability {
+                    list_changed: Some(false),
+                }),
+                resources: None,
+                prompts: None,
+                completions: None,
+                experimental: None,
+                logging: None,
+            },
+            server_info: Implementation {
+                name: EXTENSION_NAME.to_string(),
+                title: Some("Long-term Memory".to_string()),
+                version: "1.0.0".to_string(),
+                icons: None,
+                website_url: None,
+            },
+            instructions: Some(
+                indoc! {r#"
+                Long-term Memory
+
+                Memories persist across all sessions with this user. Memories relevant to the
+                current request are automatically available in your context.
+
+                - remember: save durable facts worth knowing next time, such as the user's
+                  preferences, project conventions and decisions. Don't save secrets or
+                  details that only matter to the current task.
+                - recall: search memories when you need more than what is in your context
+                - forget: delete a memory that is wrong or outdated, or that the user asks you
+                  to forget
+            "#}
+                .to_string(),
+            ),
+        };
+
+        Ok(Self {
+            info,
+            context,
+            store: OnceCell::new(),
+            last_recall: Mutex::new(None),
+        })
+    }
+
+    async fn store(&self) -> Result<&MemoryStore, String> {
+        self.store
+            .get_or_try_init(MemoryStore::from_config)
+            .await
+            .map_err(|e| format!("Memory is unavailable: {}", e))
+    }
+
+    async fn handle_remember(&self, arguments: Option<JsonObject>) -> Result<Vec<Content>, String> {
+        let params: RememberParams = parse_params(arguments)?;
+        let memory = self
+            .store()
+            .await?
+            .remember(&params.text, params.tags)
+            .await
+            .map_err(|e| format!("Failed to save memory: {}", e))?;
+        *self.last_recall.lock().await = None;
+        Ok(vec![Content::text(format!(
+            "Remembered (ID: {})",
+            memory.id
+        ))])
+    }
+
+    async fn handle_recall(&self, arguments: Option<JsonObject>) -> Result<Vec<Content>, String> {
+        let params: RecallParams = parse_params(arguments)?;
+        let limit = params.limit.unwrap_or(5).min(20);
+        let recalled = self
+            .store()
+            .await?
+            .recall(&params.query, limit)
+            .await
+            .map_err(|e| format!("Recall failed: {}", e))?;
+        if recalled.is_empty() {
+            return Ok(vec![Content::text(format!(
+                "No memories found for query: '{}'",
+                params.query
+            ))]);
+        }
+        Ok(vec![Content::text(format_memories(&recalled))])
+    }
+
+    async fn handle_forget(&self, arguments: Option<JsonObject>) -> Result<Vec<Content>, String> {
+        let params: ForgetParams = parse_params(arguments)?;
+        let forgotten = self
+            .store()
+            .await?
+            .forget(&params.id)
+            .await
+            .map_err(|e| format!("Failed to delete memory: {}", e))?;
+        match forgotten {
+            Some(memory) => {
+                *self.last_recall.lock().await = None;
+                Ok(vec![Content::text(format!("Forgot: {}", memory.text))])
+            }
+            None => Err(format!("No memory with ID {}", params.id)),
+        }
+    }
+
+    /// Text of the latest request the user typed in this session
+    async fn latest_request(&self) -> Option<String> {
+        let session_id = self.context.session_id.as_ref()?;
+        let session = SessionManager::get_session(session_id, true).await.ok()?;
+        session
+            .conversation?
+            .messages()
+            .iter()
+            .rev()
+            .filter(|message| message.role == Role::User && message.is_agent_visible())
+            .map(|message| message.as_concat_text())
+            .find(|text| !text.trim().is_empty())
+    }
+
+    fn get_tools() -> Vec<Tool> {
+        fn input_schema<T: JsonSchema>() -> JsonObject {
+            serde_json::to_value(schema_for!(T))
+                .expect("Failed to serialize memory tool schema")
+                .as_object()
+                .expect("Schema should be an object")
+                .clone()
+        }
+
+        vec![
+            Tool::new(
+                "remember".to_string(),
+                "Save a fact or note to long-term memory, kept across sessions.".to_string(),
+                input_schema::<RememberParams>(),
+            )
+            .annotate(ToolAnnotations {
+                title: Some("Remember".to_string()),
+                read_only_hint: Some(false),
+                destructive_hint: Some(false),
+                idempotent_hint: Some(false),
+                open_world_hint: Some(false),
+            }),
+            Tool::new(
+                "recall".to_string(),
+                "Search long-term memory for facts and notes relevant to a query.".to_string(),
+                input_schema::<RecallParams>(),
+            )
+            .annotate(ToolAnnotations {
+                title: Some("Recall".to_string()),
+                read_only_hint: Some(true),
+                destructive_hint: Some(false),
+                idempotent_hint: Some(true),
+                open_world_hint: Some(false),
+            }),
+            Tool::new(
+                "forget".to_string(),
+                "Delete a memory from long-term memory by its ID.".to_string(),
+                input_schema::<ForgetParams>(),
+            )
+            .annotate(ToolAnnotations {
+                title: Some("Forget".to_string()),
+                read_only_hint: Some(false),
+                destructive_hint: Some(true),
+                idempotent_hint: Some(true),
+                open_world_hint: Some(false),
+            }),
+        ]
+    }
+}
+
+#[async_trait]
+impl McpClientTrait for MemoryClient {
+    async fn list_resources(
+        &self,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListResourcesResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn read_resource(
+        &self,
+        _uri: &str,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ReadResourceResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn list_tools(
+        &self,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListToolsResult, Error> {
+        Ok(ListToolsResult {
+            tools: Self::get_tools(),
+            next_cursor: None,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        name: &str,
+        arguments: Option<JsonObject>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<CallToolResult, Error> {
+        let content = match name {
+            "remember" => self.handle_remember(arguments).await,
+            "recall" => self.handle_recall(arguments).await,
+            "forget" => self.handle_forget(arguments).await,
+            _ => Err(format!("Unknown tool: {}", name)),
+        };
+
+        match content {
+            Ok(content) => Ok(CallToolResult::success(content)),
+            Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Error: {}",
+                error
+            ))])),
+        }
+    }
+
+    async fn list_prompts(
+        &self,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListPromptsResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn get_prompt(
+        &self,
+        _name: &str,
+        _arguments: Value,
+        _cancellation_token: CancellationToken,
+    ) -> Result<GetPromptResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn subscribe(&self) -> mpsc::Receiver<ServerNotification> {
+        mpsc::channel(1).1
+    }
+
+    fn get_info(&self) -> Option<&InitializeResult> {
+        Some(&self.info)
+    }
+
+    async fn get_moim(&self) -> Option<String> {
+        let request = self.latest_request().await?;
+        let mut last_recall = self.last_recall.lock().await;
+        if let Some((last_request, memories)) = last_recall.as_ref() {
+            if *last_request == request {
+                return memories.clone();
+            }
+        }
+
+        let recalled = match self.store().await {
+            Ok(store) => store.recall(&request, TURN_START_RECALL_LIMIT).await,
+            Err(e) => Err(anyhow::anyhow!(e)),
+        };
+        let memories = match recalled {
+            Ok(recalled) if !recalled.is_empty() => Some(format!(
+                "Memories that may be relevant:\n{}",
+                format_memories(&recalled)
+            )),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::debug!("Failed to recall memories: {}", e);
+                None
+            }
+        };
+        *last_recall = Some((request, memories.clone()));
+        memories
+    }
+}
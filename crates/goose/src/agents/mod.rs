@@ -1,30 +1,54 @@
+pub mod activity;
 mod agent;
+pub mod budget;
 pub(crate) mod chatrecall_extension;
+pub mod checkpoint;
 pub(crate) mod code_execution_extension;
+pub(crate) mod code_search_extension;
+pub mod dry_run;
 pub mod execute_commands;
 pub mod extension;
+pub mod extension_health;
 pub mod extension_malware_check;
 pub mod extension_manager;
 pub mod extension_manager_extension;
+pub(crate) mod fetch_extension;
 pub mod final_output_tool;
+pub mod handoff_tool;
+pub mod hooks;
 mod large_response_handler;
 pub mod mcp_client;
+pub(crate) mod memory_extension;
 pub mod moim;
+pub mod orchestrator;
+pub mod plan;
 pub mod platform_tools;
 pub mod prompt_manager;
+pub mod reflection;
 mod reply_parts;
 pub mod retry;
+pub mod sampling;
 mod schedule_tool;
+pub mod shell_sandbox;
 pub(crate) mod skills_extension;
 pub mod subagent_execution_tool;
 pub mod subagent_handler;
 mod subagent_task_config;
 pub mod subagent_tool;
 pub(crate) mod todo_extension;
+pub mod tool_cache;
+pub mod tool_changes;
 mod tool_execution;
+pub mod tool_namespace;
+pub mod tool_policy;
+pub mod tool_retry;
 pub mod types;
+#[cfg(feature = "wasm-extensions")]
+pub mod wasm_extension;
+pub mod workspace_roots;
 
 pub use agent::{Agent, AgentEvent};
+pub use budget::SessionBudget;
 pub use execute_commands::COMPACT_TRIGGERS;
 pub use extension::ExtensionConfig;
 pub use extension_manager::ExtensionManager;
@@ -0,0 +1,295 @@
+//! Several agents taking turns in one conversation.
+//!
+//! Each [`AgentProfile`] gets its own [`Agent`], with its own provider, model, system prompt
+//! and extensions. They all reply in the same session, so they share one transcript. An agent
+//! passes the conversation on by calling the `handoff` tool; once its reply ends the
+//! [`Orchestrator`] continues with the agent it named.
+//!
+//! Handoffs can't go around in circles forever: an agent can't hand off to itself, a reply
+//! allows at most GOOSE_MAX_HANDOFFS of them, and an agent that has already been handed the
+//! conversation [`MAX_VISITS_PER_REPLY`] times in a reply is no longer offered as a target.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use super::handoff_tool::HandoffTarget;
+use super::{Agent, AgentEvent, ExtensionConfig, SessionConfig};
+use crate::config::Config;
+use crate::conversation::message::Message;
+use crate::providers;
+use crate::providers::base::Provider;
+use crate::utils::is_token_cancelled;
+
+pub const MAX_HANDOFFS_CONFIG_KEY: &str = "GOOSE_MAX_HANDOFFS";
+pub const DEFAULT_MAX_HANDOFFS: usize = 5;
+/// How many times one agent may be handed the conversation in a single reply
+pub const MAX_VISITS_PER_REPLY: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentProfile {
+    pub name: String,
+    /// What the agent is for, shown to the others when they pick whom to hand off to
+    pub description: String,
+    /// Provider to use in place of the orchestrator's default one
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Model to use in place of the provider's default one
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Replaces the default system prompt
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Names of the extensions the agent may use, all of them if unset
+    #[serde(default)]
+    pub extensions: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum OrchestratorEvent {
+    /// An event from the agent holding the conversation
+    Agent { profile: String, event: AgentEvent },
+    /// The conversation passed from one agent to another
+    Handoff {
+        from: String,
+        to: String,
+        reason: String,
+    },
+}
+
+pub struct Orchestrator {
+    members: Vec<(AgentProfile, Agent)>,
+    max_handoffs: usize,
+}
+
+impl Orchestrator {
+    /// Set up an agent for each profile in session `session_id`. Profiles that don't name a
+    /// provider or model use `default_provider`'s, and each gets those of `extensions` it
+    /// allows.
+    pub async fn new(
+        profiles: Vec<AgentProfile>,
+        default_provider: Arc<dyn Provider>,
+        extensions: &[ExtensionConfig],
+        session_id: &str,
+    ) -> Result<Self> {
+        if profiles.is_empty() {
+            bail!("An orchestrator needs at least one agent profile");
+        }
+
+        let mut members: Vec<(AgentProfile, Agent)> = Vec::with_capacity(profiles.len());
+        for profile in profiles {
+            if members.iter().any(|(other, _)| other.name == profile.name) {
+                bail!("More than one agent profile is named '{}'", profile.name);
+            }
+
+            let provider = if profile.provider.is_none() && profile.model.is_none() {
+                default_provider.clone()
+            } else {
+                let provider_name = profile
+                    .provider
+                    .clone()
+                    .unwrap_or_else(|| default_provider.get_name().to_string());
+                let mut model_config = default_provider.get_model_config();
+                if let Some(model) = &profile.model {
+                    model_config.model_name = model.clone();
+                }
+                providers::create(&provider_name, model_config)
+                    .await
+                    .map_err(|e| {
+                        anyhow!(
+                            "Failed to create provider '{}' for agent '{}': {}",
+                            provider_name,
+                            profile.name,
+                            e
+                        )
+                    })?
+            };
+
+            let agent = Agent::new();
+            agent.update_provider(provider, session_id).await?;
+            let allowed = extensions.iter().filter(|extension| {
+                profile
+                    .extensions
+                    .as_ref()
+                    .is_none_or(|names| names.contains(&extension.name()))
+            });
+            for extension in allowed {
+                if let Err(e) = agent.add_extension(extension.clone()).await {
+                    warn!(
+                        "Failed to add extension '{}' to agent '{}': {}",
+                        extension.name(),
+                        profile.name,
+                        e
+                    );
+                }
+            }
+            if let Some(system_prompt) = &profile.system_prompt {
+                agent.override_system_prompt(system_prompt.clone()).await;
+            }
+
+            members.push((profile, agent));
+        }
+
+        Ok(Self {
+            members,
+            max_handoffs: Config::global()
+                .get_param(MAX_HANDOFFS_CONFIG_KEY)
+                .unwrap_or(DEFAULT_MAX_HANDOFFS),
+        })
+    }
+
+    pub fn with_max_handoffs(mut self, max_handoffs: usize) -> Self {
+        self.max_handoffs = max_handoffs;
+        self
+    }
+
+    pub fn agent(&self, name: &str) -> Option<&Agent> {
+        self.position(name).map(|idx| &self.members[idx].1)
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.members
+            .iter()
+            .position(|(profile, _)| profile.name == name)
+    }
+
+    /// Who the agent at `current` may hand off to, given the handoffs so far this reply
+    fn handoff_targets(
+        &self,
+        current: usize,
+        visits: &HashMap<usize, usize>,
+        handoffs: usize,
+    ) -> Vec<HandoffTarget> {
+        if handoffs >= self.max_handoffs {
+            return Vec::new();
+        }
+        self.members
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| {
+                *idx != current && visits.get(idx).copied().unwrap_or(0) < MAX_VISITS_PER_REPLY
+            })
+            .map(|(_, (profile, _))| HandoffTarget {
+                name: profile.name.clone(),
+                description: profile.description.clone(),
+            })
+            .collect()
+    }
+
+    /// Reply to `user_message`, starting with the agent named `start` and following its
+    /// handoffs until an agent finishes without one
+    pub fn reply<'a>(
+        &'a self,
+        start: &str,
+        user_message: Message,
+        session_config: SessionConfig,
+        cancel_token: Option<CancellationToken>,
+    ) -> BoxStream<'a, Result<OrchestratorEvent>> {
+        let start = start.to_string();
+        Box::pin(async_stream::try_stream! {
+            let mut current = self
+                .position(&start)
+                .ok_or_else(|| anyhow!("No agent profile named '{}'", start))?;
+            let mut visits = HashMap::from([(current, 1)]);
+            let mut handoffs = 0;
+            let mut message = user_message;
+
+            loop {
+                let (profile, agent) = &self.members[current];
+                agent
+                    .set_handoff_targets(self.handoff_targets(current, &visits, handoffs))
+                    .await;
+
+                let mut stream = agent
+                    .reply(message, session_config.clone(), cancel_token.clone())
+                    .await?;
+                while let Some(event) = stream.next().await {
+                    yield OrchestratorEvent::Agent {
+                        profile: profile.name.clone(),
+                        event: event?,
+                    };
+                }
+                drop(stream);
+
+                if is_token_cancelled(&cancel_token) {
+                    break;
+                }
+                let handoff = match agent.take_handoff().await {
+                    Some(handoff) => handoff,
+                    None => break,
+                };
+                let next = self
+                    .position(&handoff.to)
+                    .ok_or_else(|| anyhow!("No agent profile named '{}'", handoff.to))?;
+
+                handoffs += 1;
+                *visits.entry(next).or_default() += 1;
+                yield OrchestratorEvent::Handoff {
+                    from: profile.name.clone(),
+                    to: handoff.to.clone(),
+                    reason: handoff.reason.clone(),
+                };
+
+                // Tells the next agent why it has the conversation, without showing the user
+                message = Message::user()
+                    .with_text(format!(
+                        "{} handed the conversation to you, {}: {}",
+                        profile.name, handoff.to, handoff.reason
+                    ))
+                    .agent_only();
+                current = next;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str) -> AgentProfile {
+        AgentProfile {
+            name: name.to_string(),
+            description: format!("The {}", name),
+            provider: None,
+            model: None,
+            system_prompt: None,
+            extensions: None,
+        }
+    }
+
+    #[test]
+    fn test_handoff_targets_prevent_loops() {
+        let orchestrator = Orchestrator {
+            members: ["planner", "coder", "reviewer"]
+                .into_iter()
+                .map(|name| (profile(name), Agent::new()))
+                .collect(),
+            max_handoffs: 3,
+        };
+        let names = |targets: Vec<HandoffTarget>| -> Vec<String> {
+            targets.into_iter().map(|target| target.name).collect()
+        };
+
+        let visits = HashMap::from([(0, 1)]);
+        assert_eq!(
+            names(orchestrator.handoff_targets(0, &visits, 0)),
+            ["coder", "reviewer"]
+        );
+
+        // The coder has had the conversation twice already
+        let visits = HashMap::from([(0, 1), (1, 2)]);
+        assert_eq!(
+            names(orchestrator.handoff_targets(2, &visits, 2)),
+            ["planner"]
+        );
+
+        assert!(orchestrator.handoff_targets(2, &visits, 3).is_empty());
+    }
+}
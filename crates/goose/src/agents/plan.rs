@@ -0,0 +1,429 @@
+//! Structured plans for the planner/executor mode.
+//!
+//! A planning model turns the request into a [`Plan`] of typed steps by calling a
+//! `submit_plan` tool. The executor agent then works through it, reporting progress with the
+//! `plan__update_step` tool, and every change is sent out as
+//! [`AgentEvent::PlanUpdated`](crate::agents::AgentEvent) so UIs can show a checklist.
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+
+use anyhow::{anyhow, Result};
+use rmcp::model::{CallToolRequestParam, Content, ErrorCode, ErrorData, Tool, ToolAnnotations};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::agents::tool_execution::ToolCallResult;
+use crate::conversation::message::{Message, MessageContent};
+use crate::providers::base::{Provider, ProviderUsage};
+use crate::providers::utils::safely_parse_json;
+
+pub const SUBMIT_PLAN_TOOL_NAME: &str = "submit_plan";
+pub const PLAN_UPDATE_TOOL_NAME: &str = "plan__update_step";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanStepKind {
+    /// Reading code, docs or other sources to learn what's needed
+    Research,
+    /// Changing files
+    Edit,
+    /// Running a command
+    Command,
+    /// Checking that earlier steps worked, such as by running tests
+    Verify,
+    /// Asking the user for something
+    AskUser,
+    #[default]
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanStepStatus {
+    #[default]
+    Pending,
+    InProgress,
+    Completed,
+    Failed,
+    Skipped,
+}
+
+impl PlanStepStatus {
+    fn is_finished(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Skipped)
+    }
+
+    fn checkbox(self) -> &'static str {
+        match self {
+            Self::Pending => "[ ]",
+            Self::InProgress => "[~]",
+            Self::Completed => "[x]",
+            Self::Failed => "[!]",
+            Self::Skipped => "[-]",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanStep {
+    pub id: u32,
+    pub title: String,
+    #[serde(default)]
+    pub kind: PlanStepKind,
+    /// What to do, in enough detail for the executor to do it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    /// Steps that have to be finished first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<u32>,
+    #[serde(default)]
+    pub status: PlanStepStatus,
+    /// What the executor reported when it last updated the step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Plan {
+    pub goal: String,
+    pub steps: Vec<PlanStep>,
+}
+
+impl Plan {
+    pub fn is_finished(&self) -> bool {
+        self.steps.iter().all(|step| step.status.is_finished())
+    }
+
+    pub fn step(&self, id: u32) -> Option<&PlanStep> {
+        self.steps.iter().find(|step| step.id == id)
+    }
+
+    /// The plan as a markdown checklist
+    pub fn checklist(&self) -> String {
+        let mut checklist = format!("Goal: {}\n", self.goal);
+        for step in &self.steps {
+            let _ = write!(
+                checklist,
+                "\n{} {}. {}",
+                step.status.checkbox(),
+                step.id,
+                step.title
+            );
+            if let Some(note) = &step.note {
+                let _ = write!(checklist, " ({})", note);
+            }
+        }
+        checklist
+    }
+
+    /// The message that starts the executor on the plan
+    pub fn instructions(&self) -> String {
+        let mut instructions = format!(
+            "Carry out this plan for the goal: {}\n\n\
+             Work through the steps in order, respecting their dependencies. Before starting a \
+             step, mark it in_progress with the `{}` tool, and when it's done mark it \
+             completed, failed or skipped with a short note on the outcome.\n",
+            self.goal, PLAN_UPDATE_TOOL_NAME
+        );
+        for step in &self.steps {
+            let _ = write!(
+                instructions,
+                "\n{}. [{}] {}",
+                step.id,
+                serde_json::to_value(step.kind)
+                    .ok()
+                    .and_then(|kind| kind.as_str().map(str::to_string))
+                    .unwrap_or_default(),
+                step.title
+            );
+            if !step.depends_on.is_empty() {
+                let depends_on: Vec<String> =
+                    step.depends_on.iter().map(|id| id.to_string()).collect();
+                let _ = write!(instructions, " (after {})", depends_on.join(", "));
+            }
+            if let Some(details) = &step.details {
+                let _ = write!(instructions, "\n   {}", details);
+            }
+        }
+        instructions
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.steps.is_empty() {
+            return Err(anyhow!("The plan has no steps"));
+        }
+        for (idx, step) in self.steps.iter().enumerate() {
+            if self.steps[..idx].iter().any(|other| other.id == step.id) {
+                return Err(anyhow!("More than one step has id {}", step.id));
+            }
+            if let Some(missing) = step.depends_on.iter().find(|id| self.step(**id).is_none()) {
+                return Err(anyhow!(
+                    "Step {} depends on step {}, which doesn't exist",
+                    step.id,
+                    missing
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn submit_plan_tool() -> Tool {
+    let schema = json!({
+        "type": "object",
+        "required": ["goal", "steps"],
+        "properties": {
+            "goal": {"type": "string", "description": "What the plan achieves"},
+            "steps": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["id", "title", "kind"],
+                    "properties": {
+                        "id": {"type": "integer", "description": "Step number, starting at 1"},
+                        "title": {"type": "string", "description": "Short summary of the step"},
+                        "kind": {
+                            "type": "string",
+                            "enum": ["research", "edit", "command", "verify", "ask_user", "other"]
+                        },
+                        "details": {"type": "string", "description": "What exactly to do"},
+                        "dependsOn": {
+                            "type": "array",
+                            "items": {"type": "integer"},
+                            "description": "Ids of steps that must be finished first"
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    Tool::new(
+        SUBMIT_PLAN_TOOL_NAME,
+        "Submit the step-by-step plan for the executor agent to follow.",
+        schema.as_object().unwrap().clone(),
+    )
+}
+
+/// Have `planner` write a plan for the conversation in `messages`, with `system` as its
+/// prompt. Returns the planner's own message instead when it answers without a plan, such as
+/// to ask clarifying questions.
+pub async fn create_plan(
+    planner: &dyn Provider,
+    system: &str,
+    messages: &[Message],
+) -> Result<(std::result::Result<Plan, Message>, ProviderUsage)> {
+    let system = format!(
+        "{}\n\nWhen you have a plan, submit it by calling the `{}` tool.",
+        system, SUBMIT_PLAN_TOOL_NAME
+    );
+    let (response, usage) = planner
+        .complete(&system, messages, &[submit_plan_tool()])
+        .await?;
+
+    let submitted = response.content.iter().find_map(|content| match content {
+        MessageContent::ToolRequest(request) => request
+            .tool_call
+            .as_ref()
+            .ok()
+            .filter(|call| call.name == SUBMIT_PLAN_TOOL_NAME)
+            .map(|call| Value::Object(call.arguments.clone().unwrap_or_default())),
+        _ => None,
+    });
+    // Models that can't call tools may write the plan out as JSON instead
+    let submitted = submitted.or_else(|| {
+        let text = response.as_concat_text();
+        let text = text
+            .trim()
+            .trim_start_matches("```json")
+            .trim_start_matches("```")
+            .trim_end_matches("```");
+        safely_parse_json(text).ok().filter(Value::is_object)
+    });
+
+    let Some(submitted) = submitted else {
+        return Ok((Err(response), usage));
+    };
+    let mut plan: Plan = serde_json::from_value(submitted)
+        .map_err(|e| anyhow!("The planner submitted an invalid plan: {}", e))?;
+    for step in &mut plan.steps {
+        step.status = PlanStepStatus::Pending;
+        step.note = None;
+    }
+    plan.validate()?;
+    Ok((Ok(plan), usage))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateStepParams {
+    step_id: u32,
+    status: PlanStepStatus,
+    #[serde(default)]
+    note: Option<String>,
+}
+
+/// The plan an executor agent is working through
+pub struct PlanTracker {
+    pub plan: Plan,
+    /// Whether the plan changed since it was last reported
+    pub changed: bool,
+}
+
+impl PlanTracker {
+    pub fn new(plan: Plan) -> Self {
+        Self {
+            plan,
+            changed: true,
+        }
+    }
+
+    /// The plan, if it changed since the last call
+    pub fn take_update(&mut self) -> Option<Plan> {
+        std::mem::take(&mut self.changed).then(|| self.plan.clone())
+    }
+
+    pub fn tool(&self) -> Tool {
+        let ids: Vec<u32> = self.plan.steps.iter().map(|step| step.id).collect();
+        let schema = json!({
+            "type": "object",
+            "required": ["stepId", "status"],
+            "properties": {
+                "stepId": {"type": "integer", "enum": ids},
+                "status": {
+                    "type": "string",
+                    "enum": ["pending", "in_progress", "completed", "failed", "skipped"]
+                },
+                "note": {
+                    "type": "string",
+                    "description": "Short note on the outcome, or why the step failed or was skipped"
+                }
+            }
+        });
+
+        Tool::new(
+            PLAN_UPDATE_TOOL_NAME,
+            format!(
+                "Update the status of a step of the plan you are carrying out.\n\n{}",
+                self.plan.checklist()
+            ),
+            schema.as_object().unwrap().clone(),
+        )
+        .annotate(ToolAnnotations {
+            title: Some("Update plan step".to_string()),
+            read_only_hint: Some(true),
+            destructive_hint: Some(false),
+            idempotent_hint: Some(true),
+            open_world_hint: Some(false),
+        })
+    }
+
+    pub fn execute_tool_call(&mut self, tool_call: CallToolRequestParam) -> ToolCallResult {
+        let params: UpdateStepParams =
+            match serde_json::from_value(Value::Object(tool_call.arguments.unwrap_or_default())) {
+                Ok(params) => params,
+                Err(e) => {
+                    return ToolCallResult::from(Err(ErrorData {
+                        code: ErrorCode::INVALID_PARAMS,
+                        message: Cow::from(format!("Invalid parameters: {}", e)),
+                        data: None,
+                    }))
+                }
+            };
+
+        let Some(step) = self
+            .plan
+            .steps
+            .iter_mut()
+            .find(|step| step.id == params.step_id)
+        else {
+            return ToolCallResult::from(Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: Cow::from(format!("The plan has no step {}", params.step_id)),
+                data: None,
+            }));
+        };
+        step.status = params.status;
+        step.note = params.note;
+        self.changed = true;
+
+        ToolCallResult::from(Ok(rmcp::model::CallToolResult {
+            content: vec![Content::text(self.plan.checklist())],
+            structured_content: None,
+            is_error: Some(false),
+            meta: None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockProvider, MockResponse};
+
+    fn submitted_plan() -> Value {
+        json!({
+            "goal": "Fix the flaky test",
+            "steps": [
+                {"id": 1, "title": "Find the flaky test", "kind": "research"},
+                {"id": 2, "title": "Add a retry", "kind": "edit", "dependsOn": [1]},
+                {"id": 3, "title": "Run the tests", "kind": "verify", "dependsOn": [2]}
+            ]
+        })
+    }
+
+    #[tokio::test]
+    async fn test_create_plan_from_tool_call() {
+        let planner = MockProvider::new().with_response(MockResponse::tool_call(
+            SUBMIT_PLAN_TOOL_NAME,
+            submitted_plan(),
+        ));
+
+        let (plan, _) = create_plan(&planner, "plan", &[Message::user().with_text("fix it")])
+            .await
+            .unwrap();
+        let plan = plan.unwrap();
+        assert_eq!(plan.steps.len(), 3);
+        assert_eq!(plan.steps[1].kind, PlanStepKind::Edit);
+        assert_eq!(plan.steps[2].depends_on, [2]);
+        assert!(plan
+            .instructions()
+            .contains("2. [edit] Add a retry (after 1)"));
+
+        // A question instead of a plan comes back as is
+        let planner = MockProvider::new().with_response(MockResponse::text("Which test is flaky?"));
+        let (plan, _) = create_plan(&planner, "plan", &[Message::user().with_text("fix it")])
+            .await
+            .unwrap();
+        assert_eq!(plan.unwrap_err().as_concat_text(), "Which test is flaky?");
+    }
+
+    #[tokio::test]
+    async fn test_plan_tracker_updates_steps() {
+        let plan: Plan = serde_json::from_value(submitted_plan()).unwrap();
+        let mut tracker = PlanTracker::new(plan);
+        assert!(tracker.take_update().is_some());
+        assert!(tracker.take_update().is_none());
+
+        let update = |step_id: u32, status: &str| CallToolRequestParam {
+            name: PLAN_UPDATE_TOOL_NAME.into(),
+            arguments: json!({"stepId": step_id, "status": status, "note": "done"})
+                .as_object()
+                .cloned(),
+        };
+        for step_id in 1..=3 {
+            let result = tracker.execute_tool_call(update(step_id, "completed"));
+            assert!(result.result.await.is_ok());
+        }
+        let plan = tracker.take_update().unwrap();
+        assert!(plan.is_finished());
+        assert!(plan.checklist().contains("[x] 3. Run the tests (done)"));
+
+        let missing = tracker.execute_tool_call(update(9, "completed"));
+        assert!(missing.result.await.is_err());
+    }
+}
@@ -2,6 +2,7 @@ use indoc::indoc;
 use rmcp::model::{Tool, ToolAnnotations};
 use rmcp::object;
 pub const PLATFORM_MANAGE_SCHEDULE_TOOL_NAME: &str = "platform__manage_schedule";
+pub const PLATFORM_READ_MORE_TOOL_NAME: &str = "platform__read_more";
 
 pub fn manage_schedule_tool() -> Tool {
     Tool::new(
@@ -45,3 +46,30 @@ pub fn manage_schedule_tool() -> Tool {
         open_world_hint: Some(false),
     })
 }
+
+pub fn read_more_tool() -> Tool {
+    Tool::new(
+        PLATFORM_READ_MORE_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Read more of a tool output that was too large to show in full.
+
+            Truncated outputs end with a note giving the id of the stored output and the
+            character offset to continue from. Call this tool with both to get the next page.
+        "#}
+        .to_string(),
+        object!({
+            "type": "object",
+            "required": ["id", "offset"],
+            "properties": {
+                "id": {"type": "string", "description": "Id of the stored output, from the truncation note"},
+                "offset": {"type": "integer", "description": "Character offset to start reading from", "minimum": 0}
+            }
+        }),
+    ).annotate(ToolAnnotations {
+        title: Some("Read more tool output".to_string()),
+        read_only_hint: Some(true),
+        destructive_hint: Some(false),
+        idempotent_hint: Some(true),
+        open_world_hint: Some(false),
+    })
+}
@@ -20,6 +20,8 @@ const MAX_TOOLS: usize = 50;
 pub struct PromptManager {
     system_prompt_override: Option<String>,
     system_prompt_extras: Vec<String>,
+    /// Extras that are replaced rather than added to, by key
+    keyed_system_prompt_extras: Vec<(String, String)>,
     current_date_timestamp: String,
 }
 
@@ -170,6 +172,12 @@ impl<'a> SystemPromptBuilder<'a, PromptManager> {
         });
 
         let mut system_prompt_extras = self.manager.system_prompt_extras.clone();
+        system_prompt_extras.extend(
+            self.manager
+                .keyed_system_prompt_extras
+                .iter()
+                .map(|(_, extra)| extra.clone()),
+        );
 
         // Add hints if provided
         if let Some(hints) = self.hints {
@@ -183,6 +191,14 @@ impl<'a> SystemPromptBuilder<'a, PromptManager> {
             );
         }
 
+        if goose_mode == GooseMode::DryRun {
+            system_prompt_extras.push(
+                "Right now you are in dry-run mode: tools that only read run, but other tool calls \
+                 are reported back instead of executed. Use them to show the user what you would do."
+                    .to_string(),
+            );
+        }
+
         let sanitized_system_prompt_extras: Vec<String> = system_prompt_extras
             .into_iter()
             .map(|extra| sanitize_unicode_tags(&extra))
@@ -205,6 +221,7 @@ impl PromptManager {
         PromptManager {
             system_prompt_override: None,
             system_prompt_extras: Vec::new(),
+            keyed_system_prompt_extras: Vec::new(),
             // Use the fixed current date time so that prompt cache can be used.
             // Filtering to an hour to balance user time accuracy and multi session prompt cache hits.
             current_date_timestamp: Utc::now().format("%Y-%m-%d %H:00").to_string(),
@@ -216,6 +233,7 @@ impl PromptManager {
         PromptManager {
             system_prompt_override: None,
             system_prompt_extras: Vec::new(),
+            keyed_system_prompt_extras: Vec::new(),
             current_date_timestamp: dt.format("%Y-%m-%d %H:%M:%S").to_string(),
         }
     }
@@ -225,6 +243,19 @@ impl PromptManager {
         self.system_prompt_extras.push(instruction);
     }
 
+    /// Set the instruction kept under `key`, replacing any set before, or remove it if `None`
+    pub fn set_system_prompt_extra(&mut self, key: &str, instruction: Option<String>) {
+        let extras = &mut self.keyed_system_prompt_extras;
+        match (extras.iter().position(|(k, _)| k == key), instruction) {
+            (Some(index), Some(instruction)) => extras[index].1 = instruction,
+            (Some(index), None) => {
+                extras.remove(index);
+            }
+            (None, Some(instruction)) => extras.push((key.to_string(), instruction)),
+            (None, None) => {}
+        }
+    }
+
     /// Override the system prompt with custom text
     pub fn set_system_prompt_override(&mut self, template: String) {
         self.system_prompt_override = Some(template);
@@ -0,0 +1,349 @@
+//! Self-critique after a reply.
+//!
+//! With reflection on, a reviewing model, which can be a cheaper one than the session's, reads
+//! what the agent did to answer the latest request: its tool calls, their results and the final
+//! answer. It then judges whether the answer meets the user's goal. If it doesn't, the agent gets
+//! the critique and one more iteration to correct itself. Reflection is enabled with
+//! GOOSE_REFLECTION, with GOOSE_REFLECTION_PROVIDER and GOOSE_REFLECTION_MODEL picking the
+//! reviewer, or per session through
+//! [`SessionConfig::reflection`](crate::agents::types::SessionConfig).
+
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use rmcp::model::{Role, Tool};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::config::Config;
+use crate::conversation::message::{Message, MessageContent};
+use crate::providers;
+use crate::providers::base::{Provider, ProviderUsage};
+use crate::providers::utils::safely_parse_json;
+use crate::utils::safe_truncate;
+
+pub const REFLECTION_CONFIG_KEY: &str = "GOOSE_REFLECTION";
+pub const REFLECTION_PROVIDER_CONFIG_KEY: &str = "GOOSE_REFLECTION_PROVIDER";
+pub const REFLECTION_MODEL_CONFIG_KEY: &str = "GOOSE_REFLECTION_MODEL";
+pub const SUBMIT_CRITIQUE_TOOL_NAME: &str = "submit_critique";
+/// How many corrective iterations the reviewer may ask for in one reply
+pub const MAX_CORRECTIONS: u32 = 1;
+
+pub const REFLECTION_NOTICE_TEXT: &str = "A review found problems with this answer. Revising...";
+
+/// Longest tool argument or result text shown to the reviewer, in characters
+const MAX_TRANSCRIPT_ITEM_CHARS: usize = 1000;
+
+const REVIEWER_SYSTEM_PROMPT: &str = "\
+You review the work of an AI agent. You are given the user's request and a transcript of \
+what the agent did to answer it: its tool calls, their results and its final answer.
+
+Judge whether the final answer accomplishes what the user asked for. Look for claims the tool \
+results don't support, errors the agent ignored, steps it skipped and parts of the request it \
+left unanswered. Don't ask for changes of style, or for work beyond the request.
+
+Submit your verdict with the `submit_critique` tool.";
+
+/// Reflection settings for a session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReflectionSettings {
+    /// Provider of the reviewing model, the session's own if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Reviewing model, the session's own if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl ReflectionSettings {
+    /// Read settings from GOOSE_REFLECTION, GOOSE_REFLECTION_PROVIDER and
+    /// GOOSE_REFLECTION_MODEL. Returns None when reflection isn't turned on.
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        if !config.get_param(REFLECTION_CONFIG_KEY).unwrap_or(false) {
+            return None;
+        }
+        Some(Self {
+            provider: config.get_param(REFLECTION_PROVIDER_CONFIG_KEY).ok(),
+            model: config.get_param(REFLECTION_MODEL_CONFIG_KEY).ok(),
+        })
+    }
+}
+
+/// The reviewer's verdict on a reply
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Critique {
+    /// Whether the answer accomplishes the user's goal
+    pub satisfied: bool,
+    /// What is wrong with the answer, if anything
+    #[serde(default)]
+    pub issues: Vec<String>,
+    /// What the agent should do to fix it
+    #[serde(default)]
+    pub suggestion: Option<String>,
+}
+
+impl Critique {
+    /// The message that sends the agent back to correct its answer
+    pub fn correction_prompt(&self) -> String {
+        let mut prompt =
+            String::from("A reviewer checked your answer and found these problems with it:\n");
+        for issue in &self.issues {
+            let _ = write!(prompt, "\n- {}", issue);
+        }
+        if let Some(suggestion) = &self.suggestion {
+            let _ = write!(prompt, "\n\nSuggested fix: {}", suggestion);
+        }
+        prompt.push_str(
+            "\n\nFix what the reviewer is right about, using tools if needed, then give your \
+             corrected final answer. If the reviewer is wrong, explain why briefly instead.",
+        );
+        prompt
+    }
+}
+
+fn submit_critique_tool() -> Tool {
+    let schema = json!({
+        "type": "object",
+        "required": ["satisfied"],
+        "properties": {
+            "satisfied": {
+                "type": "boolean",
+                "description": "Whether the final answer accomplishes the user's request"
+            },
+            "issues": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Concrete problems with the answer, empty if satisfied"
+            },
+            "suggestion": {
+                "type": "string",
+                "description": "What the agent should do to fix the problems"
+            }
+        }
+    });
+
+    Tool::new(
+        SUBMIT_CRITIQUE_TOOL_NAME,
+        "Submit your verdict on the agent's answer.",
+        schema.as_object().unwrap().clone(),
+    )
+}
+
+/// The agent's work on the latest request in `messages`, written out for the reviewer. The
+/// request is the last user message with text that isn't a tool result.
+fn transcript(messages: &[Message]) -> Option<String> {
+    let messages: Vec<&Message> = messages.iter().filter(|m| m.is_agent_visible()).collect();
+    let start = messages.iter().rposition(|message| {
+        message.role == Role::User
+            && message.content.iter().any(|c| c.as_text().is_some())
+            && !message
+                .content
+                .iter()
+                .any(|c| matches!(c, MessageContent::ToolResponse(_)))
+    })?;
+
+    let mut transcript = format!("User request:\n{}\n", messages[start].as_concat_text());
+    for message in &messages[start + 1..] {
+        for content in &message.content {
+            match content {
+                MessageContent::Text(text) if message.role == Role::Assistant => {
+                    let _ = write!(transcript, "\nAgent:\n{}\n", text.text);
+                }
+                MessageContent::Text(text) => {
+                    let _ = write!(transcript, "\nUser:\n{}\n", text.text);
+                }
+                MessageContent::ToolRequest(request) => match &request.tool_call {
+                    Ok(call) => {
+                        let arguments = Value::Object(call.arguments.clone().unwrap_or_default());
+                        let _ = write!(
+                            transcript,
+                            "\nTool call {}: {}\n",
+                            call.name,
+                            safe_truncate(&arguments.to_string(), MAX_TRANSCRIPT_ITEM_CHARS)
+                        );
+                    }
+                    Err(e) => {
+                        let _ = write!(transcript, "\nInvalid tool call: {}\n", e.message);
+                    }
+                },
+                MessageContent::ToolResponse(response) => {
+                    let (label, text) = match &response.tool_result {
+                        Ok(result) if result.is_error == Some(true) => {
+                            ("Tool error", content_text(&result.content))
+                        }
+                        Ok(result) => ("Tool result", content_text(&result.content)),
+                        Err(e) => ("Tool error", e.message.to_string()),
+                    };
+                    let _ = write!(
+                        transcript,
+                        "\n{}:\n{}\n",
+                        label,
+                        safe_truncate(&text, MAX_TRANSCRIPT_ITEM_CHARS)
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+    Some(transcript)
+}
+
+fn content_text(content: &[rmcp::model::Content]) -> String {
+    content
+        .iter()
+        .filter_map(|c| c.as_text().map(|text| text.text.as_str()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A reviewing model, set up from [`ReflectionSettings`]
+#[derive(Clone)]
+pub struct Reflection {
+    reviewer: Arc<dyn Provider>,
+}
+
+impl Reflection {
+    pub fn new(reviewer: Arc<dyn Provider>) -> Self {
+        Self { reviewer }
+    }
+
+    /// Set up the reviewer `settings` ask for. `provider` is the session's, used as is when
+    /// the settings name neither a provider nor a model.
+    pub async fn from_settings(
+        settings: &ReflectionSettings,
+        provider: Arc<dyn Provider>,
+    ) -> Result<Self> {
+        if settings.provider.is_none() && settings.model.is_none() {
+            return Ok(Self::new(provider));
+        }
+        let provider_name = settings
+            .provider
+            .clone()
+            .unwrap_or_else(|| provider.get_name().to_string());
+        let mut model_config = provider.get_model_config();
+        if let Some(model) = &settings.model {
+            model_config.model_name = model.clone();
+        }
+        let reviewer = providers::create(&provider_name, model_config)
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "Failed to create reflection provider '{}': {}",
+                    provider_name,
+                    e
+                )
+            })?;
+        Ok(Self::new(reviewer))
+    }
+
+    /// Have the reviewer judge the agent's answer to the latest request in `messages`. An
+    /// answer the reviewer gives no usable verdict on counts as satisfactory. Also returns the
+    /// usage of the review, if the reviewer was asked.
+    pub async fn review(&self, messages: &[Message]) -> Result<(Critique, Option<ProviderUsage>)> {
+        let Some(transcript) = transcript(messages) else {
+            let critique = Critique {
+                satisfied: true,
+                ..Default::default()
+            };
+            return Ok((critique, None));
+        };
+
+        let (response, usage) = self
+            .reviewer
+            .complete(
+                REVIEWER_SYSTEM_PROMPT,
+                &[Message::user().with_text(transcript)],
+                &[submit_critique_tool()],
+            )
+            .await?;
+
+        let submitted = response.content.iter().find_map(|content| match content {
+            MessageContent::ToolRequest(request) => request
+                .tool_call
+                .as_ref()
+                .ok()
+                .filter(|call| call.name == SUBMIT_CRITIQUE_TOOL_NAME)
+                .map(|call| Value::Object(call.arguments.clone().unwrap_or_default())),
+            _ => None,
+        });
+        let submitted =
+            submitted.or_else(|| safely_parse_json(response.as_concat_text().trim()).ok());
+
+        let critique = match submitted.map(serde_json::from_value::<Critique>) {
+            Some(Ok(critique)) => critique,
+            _ => {
+                warn!("Reflection reviewer gave no usable verdict, accepting the answer");
+                Critique {
+                    satisfied: true,
+                    ..Default::default()
+                }
+            }
+        };
+        Ok((critique, Some(usage)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use rmcp::model::{CallToolRequestParam, CallToolResult, Content};
+
+    fn reply() -> Vec<Message> {
+        vec![
+            Message::user().with_text("earlier request"),
+            Message::assistant().with_text("earlier answer"),
+            Message::user().with_text("how many tests are there?"),
+            Message::assistant().with_tool_request(
+                "call_1",
+                Ok(CallToolRequestParam {
+                    name: "developer__shell".into(),
+                    arguments: json!({"command": "cargo test"}).as_object().cloned(),
+                }),
+            ),
+            Message::user().with_tool_response(
+                "call_1",
+                Ok(CallToolResult::error(vec![Content::text("build failed")])),
+            ),
+            Message::assistant().with_text("There are 42 tests."),
+        ]
+    }
+
+    #[test]
+    fn test_transcript_covers_latest_request() {
+        let transcript = transcript(&reply()).unwrap();
+        assert!(transcript.starts_with("User request:\nhow many tests are there?"));
+        assert!(!transcript.contains("earlier"));
+        assert!(transcript.contains("Tool call developer__shell: {\"command\":\"cargo test\"}"));
+        assert!(transcript.contains("Tool error:\nbuild failed"));
+        assert!(transcript.ends_with("Agent:\nThere are 42 tests.\n"));
+    }
+
+    #[tokio::test]
+    async fn test_review_returns_critique() {
+        let reviewer = MockProvider::new()
+            .with_response(MockResponse::tool_call(
+                SUBMIT_CRITIQUE_TOOL_NAME,
+                json!({
+                    "satisfied": false,
+                    "issues": ["The build failed, so no tests were counted"],
+                    "suggestion": "Fix the build or say the count is unknown"
+                }),
+            ))
+            .with_response(MockResponse::text("Looks fine to me"));
+        let reflection = Reflection::new(Arc::new(reviewer));
+
+        let (critique, usage) = reflection.review(&reply()).await.unwrap();
+        assert!(!critique.satisfied);
+        assert!(usage.is_some());
+        let prompt = critique.correction_prompt();
+        assert!(prompt.contains("- The build failed, so no tests were counted"));
+        assert!(prompt.contains("Suggested fix: Fix the build"));
+
+        // No verdict counts as satisfied
+        assert!(reflection.review(&reply()).await.unwrap().0.satisfied);
+    }
+}
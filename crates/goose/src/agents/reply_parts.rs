@@ -4,22 +4,29 @@ use std::sync::Arc;
 use async_stream::try_stream;
 use futures::stream::StreamExt;
 use serde_json::{json, Value};
-use tracing::debug;
+use tracing::{debug, warn};
 
 use super::super::agents::Agent;
 use crate::conversation::message::{Message, MessageContent, ToolRequest};
 use crate::conversation::Conversation;
+use crate::model::{with_tool_choice_override, ToolChoice};
 use crate::providers::base::{stream_from_single_message, MessageStream, Provider, ProviderUsage};
 use crate::providers::errors::ProviderError;
+use crate::providers::stream_event::into_message_stream;
+use crate::providers::stream_limits::StreamLimits;
 use crate::providers::toolshim::{
     augment_message_with_tool_calls, convert_tool_messages_to_text,
     modify_system_prompt_for_tool_json, OllamaInterpreter,
 };
+use crate::providers::transcription::transcribe_audio_input;
+use crate::tracing::genai::{chat_span, instrument_stream, record_error};
+use tracing::Instrument;
 
+use crate::agents::budget::charge_usage;
 use crate::agents::code_execution_extension::EXTENSION_NAME as CODE_EXECUTION_EXTENSION;
-use crate::session::SessionManager;
 #[cfg(test)]
 use crate::session::SessionType;
+use crate::session::{ExtensionState, ModelUsageState, SessionManager};
 use rmcp::model::Tool;
 
 fn coerce_value(s: &str, schema: &Value) -> Value {
@@ -116,8 +123,18 @@ impl Agent {
         // Get tools from extension manager
         let mut tools = self.list_tools(None).await;
 
-        // Add frontend tools
+        // Add frontend tools, which take the calls for their names from any other tool
         let frontend_tools = self.frontend_tools.lock().await;
+        tools.retain(|tool| {
+            let shadowed = frontend_tools.contains_key(tool.name.as_ref());
+            if shadowed {
+                warn!(
+                    "Frontend tool {} replaces the tool of the same name",
+                    tool.name
+                );
+            }
+            !shadowed
+        });
         for frontend_tool in frontend_tools.values() {
             tools.push(frontend_tool.tool.clone());
         }
@@ -169,13 +186,14 @@ impl Agent {
     }
 
     /// Stream a response from the LLM provider.
-    /// Handles toolshim transformations if needed
+    /// Handles toolshim transformations if needed, and applies `tool_choice` to the request
     pub(crate) async fn stream_response_from_provider(
         provider: Arc<dyn Provider>,
         system_prompt: &str,
         messages: &[Message],
         tools: &[Tool],
         toolshim_tools: &[Tool],
+        tool_choice: Option<ToolChoice>,
     ) -> Result<MessageStream, ProviderError> {
         let config = provider.get_model_config();
 
@@ -186,34 +204,51 @@ impl Agent {
             Conversation::new_unvalidated(messages.to_vec())
         };
 
+        // Audio is transcribed for models that can't take it directly
+        let messages_for_provider = if provider.supports_audio_input() {
+            messages_for_provider
+        } else {
+            Conversation::new_unvalidated(
+                transcribe_audio_input(messages_for_provider.messages()).await,
+            )
+        };
+
         // Clone owned data to move into the async stream
         let system_prompt = system_prompt.to_owned();
         let tools = tools.to_owned();
         let toolshim_tools = toolshim_tools.to_owned();
         let provider = provider.clone();
+        let span = chat_span(provider.get_name(), &config.model_name);
 
         // Capture errors during stream creation and return them as part of the stream
         // so they can be handled by the existing error handling logic in the agent
         let stream_result = if provider.supports_streaming() {
             debug!("WAITING_LLM_STREAM_START");
-            let result = provider
-                .stream(
+            let result = with_tool_choice_override(
+                tool_choice,
+                provider.stream_events(
                     system_prompt.as_str(),
                     messages_for_provider.messages(),
                     &tools,
-                )
-                .await;
+                ),
+            )
+            .instrument(span.clone())
+            .await
+            .map(into_message_stream);
             debug!("WAITING_LLM_STREAM_END");
             result
         } else {
             debug!("WAITING_LLM_START");
-            let complete_result = provider
-                .complete(
+            let complete_result = with_tool_choice_override(
+                tool_choice,
+                provider.complete(
                     system_prompt.as_str(),
                     messages_for_provider.messages(),
                     &tools,
-                )
-                .await;
+                ),
+            )
+            .instrument(span.clone())
+            .await;
             debug!("WAITING_LLM_END");
 
             match complete_result {
@@ -224,8 +259,12 @@ impl Agent {
 
         // If there was an error creating the stream, return a stream that yields that error
         let mut stream = match stream_result {
-            Ok(s) => s,
+            Ok(s) => instrument_stream(
+                StreamLimits::from_config().apply(s, &config.model_name),
+                span,
+            ),
             Err(e) => {
+                record_error(&span, e.telemetry_type());
                 // Return a stream that immediately yields the error
                 // This allows the error to be caught by existing error handling in agent.rs
                 return Ok(Box::pin(try_stream! {
@@ -240,6 +279,7 @@ impl Agent {
                 if let Some(usage) = usage.as_ref() {
                     crate::providers::base::set_current_model(&usage.model);
                 }
+                let usage = usage.map(|usage| usage.with_estimated_cost(provider.get_name()));
 
                 // Post-process / structure the response only if tool interpretation is enabled
                 if message.is_some() && config.toolshim {
@@ -339,7 +379,7 @@ impl Agent {
         is_compaction_usage: bool,
     ) -> Result<()> {
         let session_id = session_config.id.as_str();
-        let session = SessionManager::get_session(session_id, false).await?;
+        let mut session = SessionManager::get_session(session_id, false).await?;
 
         let accumulate = |a: Option<i32>, b: Option<i32>| -> Option<i32> {
             match (a, b) {
@@ -354,6 +394,10 @@ impl Agent {
             accumulate(session.accumulated_input_tokens, usage.usage.input_tokens);
         let accumulated_output =
             accumulate(session.accumulated_output_tokens, usage.usage.output_tokens);
+        let accumulated_cost = match (session.accumulated_cost, usage.cost) {
+            (Some(x), Some(y)) => Some(x + y),
+            (a, b) => a.or(b),
+        };
 
         let (current_total, current_input, current_output) = if is_compaction_usage {
             // After compaction: summary output becomes new input context
@@ -367,6 +411,11 @@ impl Agent {
             )
         };
 
+        let mut model_usage =
+            ModelUsageState::from_extension_data(&session.extension_data).unwrap_or_default();
+        model_usage.record(usage);
+        model_usage.to_extension_data(&mut session.extension_data)?;
+
         SessionManager::update_session(session_id)
             .schedule_id(session_config.schedule_id.clone())
             .total_tokens(current_total)
@@ -375,9 +424,19 @@ impl Agent {
             .accumulated_total_tokens(accumulated_total)
             .accumulated_input_tokens(accumulated_input)
             .accumulated_output_tokens(accumulated_output)
+            .accumulated_cost(accumulated_cost)
+            .extension_data(session.extension_data)
             .apply()
             .await?;
 
+        if let Some(budget_session_id) = session_config
+            .budget_session_id
+            .as_deref()
+            .filter(|id| *id != session_id)
+        {
+            charge_usage(budget_session_id, usage).await?;
+        }
+
         Ok(())
     }
 }
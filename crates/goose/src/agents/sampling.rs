@@ -0,0 +1,418 @@
+//! Serving MCP sampling requests.
+//!
+//! Extensions can ask goose for a completion with an MCP sampling request. GOOSE_MCP_SAMPLING
+//! decides whether they get one: `allow` serves requests, `ask` asks the user about each of
+//! them and `deny` refuses them. It defaults to `allow` in auto mode and to `ask` in the other
+//! modes. GOOSE_MCP_SAMPLING_PER_EXTENSION sets the policy for particular extensions by name.
+//!
+//! Requests are served by the configured provider. The model hints of a request are looked up
+//! in GOOSE_MCP_SAMPLING_MODELS, which maps hints to model names, and otherwise matched against
+//! the names of the configured model and fast model. A request without a matching hint that
+//! prefers speed or cost over intelligence gets the fast model. Usage is attributed to the
+//! extension that made the request, in the session whose tool call it was serving.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use rmcp::model::{
+    Content, CreateMessageRequestParam, CreateMessageResult, ErrorCode, ErrorData,
+    ModelPreferences, Role, SamplingMessage,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::action_required_manager::ActionRequiredManager;
+use crate::agents::types::SharedProvider;
+use crate::config::{Config, GooseMode};
+use crate::conversation::message::{Message, MessageContent};
+use crate::model::ModelConfig;
+use crate::providers::base::ProviderUsage;
+use crate::session::{ExtensionState, SamplingUsageState, SessionManager};
+use crate::utils::safe_truncate;
+
+pub const SAMPLING_CONFIG_KEY: &str = "GOOSE_MCP_SAMPLING";
+pub const SAMPLING_PER_EXTENSION_CONFIG_KEY: &str = "GOOSE_MCP_SAMPLING_PER_EXTENSION";
+pub const SAMPLING_MODELS_CONFIG_KEY: &str = "GOOSE_MCP_SAMPLING_MODELS";
+/// How long to wait for the user to answer an approval request
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+/// Characters of the request shown to the user when asking for approval
+const APPROVAL_PREVIEW_CHARS: usize = 500;
+const DEFAULT_SYSTEM_PROMPT: &str = "You are a general-purpose AI agent called goose";
+
+/// Whether an extension's sampling requests are served
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplingApproval {
+    Allow,
+    Ask,
+    Deny,
+}
+
+#[derive(Debug, Clone)]
+pub struct SamplingPolicy {
+    pub default: SamplingApproval,
+    pub per_extension: HashMap<String, SamplingApproval>,
+    /// Model names to use for model hints, by hint
+    pub models: HashMap<String, String>,
+}
+
+impl Default for SamplingPolicy {
+    fn default() -> Self {
+        Self {
+            default: SamplingApproval::Allow,
+            per_extension: HashMap::new(),
+            models: HashMap::new(),
+        }
+    }
+}
+
+impl SamplingPolicy {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let default = config.get_param(SAMPLING_CONFIG_KEY).unwrap_or_else(|_| {
+            match config.get_goose_mode().unwrap_or(GooseMode::Auto) {
+                GooseMode::Auto => SamplingApproval::Allow,
+                _ => SamplingApproval::Ask,
+            }
+        });
+        Self {
+            default,
+            per_extension: config
+                .get_param(SAMPLING_PER_EXTENSION_CONFIG_KEY)
+                .unwrap_or_default(),
+            models: config
+                .get_param(SAMPLING_MODELS_CONFIG_KEY)
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn approval_for(&self, extension_name: &str) -> SamplingApproval {
+        self.per_extension
+            .get(extension_name)
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// The model to serve a request with `preferences` with, starting from the configured one
+    pub fn model_for(
+        &self,
+        base: &ModelConfig,
+        preferences: Option<&ModelPreferences>,
+    ) -> ModelConfig {
+        let Some(preferences) = preferences else {
+            return base.clone();
+        };
+
+        let hints = preferences
+            .hints
+            .iter()
+            .flatten()
+            .filter_map(|hint| hint.name.as_deref());
+        for hint in hints {
+            if let Some(model_name) = self.models.get(hint) {
+                let mut config = base.clone();
+                config.model_name = model_name.clone();
+                return config;
+            }
+            if base.model_name.contains(hint) {
+                return base.clone();
+            }
+            if base
+                .fast_model
+                .as_ref()
+                .is_some_and(|fast_model| fast_model.contains(hint))
+            {
+                return base.use_fast_model();
+            }
+        }
+
+        let priority = |priority: Option<f32>| priority.unwrap_or(0.0);
+        let cheap_or_fast =
+            priority(preferences.speed_priority).max(priority(preferences.cost_priority));
+        if cheap_or_fast > priority(preferences.intelligence_priority) {
+            base.use_fast_model()
+        } else {
+            base.clone()
+        }
+    }
+}
+
+/// Serves the sampling requests of one extension
+#[derive(Clone)]
+pub struct SamplingHandler {
+    provider: SharedProvider,
+    extension_name: String,
+    /// The session that last made a request to the extension, which the extension's sampling
+    /// requests are attributed to
+    session_id: Arc<Mutex<Option<String>>>,
+}
+
+impl SamplingHandler {
+    pub fn new(provider: SharedProvider, extension_name: impl Into<String>) -> Self {
+        Self {
+            provider,
+            extension_name: extension_name.into(),
+            session_id: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Note the current session as the one making requests to the extension
+    pub fn note_session(&self) {
+        if let Some(session_id) = crate::session_context::current_session_id() {
+            *self.session_id.lock().unwrap() = Some(session_id);
+        }
+    }
+
+    pub async fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+    ) -> Result<CreateMessageResult, ErrorData> {
+        let policy = SamplingPolicy::from_config();
+        match policy.approval_for(&self.extension_name) {
+            SamplingApproval::Allow => {}
+            SamplingApproval::Deny => {
+                return Err(ErrorData::new(
+                    ErrorCode::INVALID_REQUEST,
+                    format!("Sampling is not allowed for {}", self.extension_name),
+                    None,
+                ));
+            }
+            SamplingApproval::Ask => {
+                if !self.approved(&params).await {
+                    return Err(ErrorData::new(
+                        ErrorCode::INVALID_REQUEST,
+                        "The user declined the sampling request",
+                        None,
+                    ));
+                }
+            }
+        }
+
+        let provider = self
+            .provider
+            .lock()
+            .await
+            .as_ref()
+            .ok_or(ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                "Could not use provider",
+                None,
+            ))?
+            .clone();
+
+        let provider_ready_messages: Vec<Message> = params
+            .messages
+            .iter()
+            .map(|msg| {
+                let base = match msg.role {
+                    Role::User => Message::user(),
+                    Role::Assistant => Message::assistant(),
+                };
+
+                match msg.content.as_text() {
+                    Some(text) => base.with_text(&text.text),
+                    None => base.with_content(msg.content.clone().into()),
+                }
+            })
+            .collect();
+
+        let system_prompt = params
+            .system_prompt
+            .as_deref()
+            .unwrap_or(DEFAULT_SYSTEM_PROMPT);
+
+        let model_config = policy.model_for(
+            &provider.get_model_config(),
+            params.model_preferences.as_ref(),
+        );
+        let temperature = params.temperature.or(model_config.temperature);
+        let model_config = model_config
+            .with_temperature(temperature)
+            .with_max_tokens(i32::try_from(params.max_tokens).ok());
+
+        let (response, usage) = provider
+            .complete_with_model(&model_config, system_prompt, &provider_ready_messages, &[])
+            .await
+            .map_err(|e| {
+                ErrorData::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    "Unexpected error while completing the prompt",
+                    Some(Value::from(e.to_string())),
+                )
+            })?;
+        let usage = usage.with_estimated_cost(provider.get_name());
+        self.record_usage(&usage).await;
+
+        Ok(CreateMessageResult {
+            model: usage.model,
+            stop_reason: Some(CreateMessageResult::STOP_REASON_END_TURN.to_string()),
+            message: SamplingMessage {
+                role: Role::Assistant,
+                // TODO(alexhancock): MCP sampling currently only supports one content on each SamplingMessage
+                // https://modelcontextprotocol.io/specification/draft/client/sampling#messages
+                // This doesn't mesh well with goose's approach which has Vec<MessageContent>
+                // There is a proposal to MCP which is agreed to go in the next version to have SamplingMessages support multiple content parts
+                // https://github.com/modelcontextprotocol/modelcontextprotocol/pull/198
+                // Until that is formalized, we can take the first message content from the provider and use it
+                content: if let Some(content) = response.content.first() {
+                    match content {
+                        MessageContent::Text(text) => Content::text(&text.text),
+                        MessageContent::Image(img) => Content::image(&img.data, &img.mime_type),
+                        // TODO(alexhancock) - Content::Audio? goose's messages don't currently have it
+                        _ => Content::text(""),
+                    }
+                } else {
+                    Content::text("")
+                },
+            },
+        })
+    }
+
+    /// Ask the user whether to serve `params`
+    async fn approved(&self, params: &CreateMessageRequestParam) -> bool {
+        let request = params
+            .messages
+            .iter()
+            .filter_map(|msg| msg.content.as_text().map(|text| text.text.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let message = format!(
+            "The {} extension wants to use your model for this request:\n\n{}",
+            self.extension_name,
+            safe_truncate(&request, APPROVAL_PREVIEW_CHARS)
+        );
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "approve": {
+                    "type": "boolean",
+                    "title": "Allow",
+                    "description": "Send the request to the model"
+                }
+            },
+            "required": ["approve"]
+        });
+
+        match ActionRequiredManager::global()
+            .request_and_wait(message, schema, APPROVAL_TIMEOUT)
+            .await
+        {
+            Ok(user_data) => user_data
+                .get("approve")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            Err(e) => {
+                warn!(
+                    "No answer to the sampling request of {}: {}",
+                    self.extension_name, e
+                );
+                false
+            }
+        }
+    }
+
+    async fn record_usage(&self, usage: &ProviderUsage) {
+        let session_id = self.session_id.lock().unwrap().clone();
+        let Some(session_id) = session_id else {
+            return;
+        };
+        if let Err(e) = record_sampling_usage(&session_id, &self.extension_name, usage).await {
+            warn!(
+                "Failed to record the sampling usage of {}: {}",
+                self.extension_name, e
+            );
+        }
+    }
+}
+
+/// Count `usage` towards session `session_id`, attributed to `extension_name`
+async fn record_sampling_usage(
+    session_id: &str,
+    extension_name: &str,
+    usage: &ProviderUsage,
+) -> Result<()> {
+    let mut session = SessionManager::get_session(session_id, false).await?;
+
+    let mut sampling_usage =
+        SamplingUsageState::from_extension_data(&session.extension_data).unwrap_or_default();
+    sampling_usage.record(extension_name, usage);
+    sampling_usage.to_extension_data(&mut session.extension_data)?;
+
+    let accumulate = |a: Option<i32>, b: Option<i32>| match (a, b) {
+        (Some(x), Some(y)) => Some(x + y),
+        _ => a.or(b),
+    };
+    let accumulated_cost = match (session.accumulated_cost, usage.cost) {
+        (Some(x), Some(y)) => Some(x + y),
+        (a, b) => a.or(b),
+    };
+
+    SessionManager::update_session(session_id)
+        .accumulated_total_tokens(accumulate(
+            session.accumulated_total_tokens,
+            usage.usage.total_tokens,
+        ))
+        .accumulated_input_tokens(accumulate(
+            session.accumulated_input_tokens,
+            usage.usage.input_tokens,
+        ))
+        .accumulated_output_tokens(accumulate(
+            session.accumulated_output_tokens,
+            usage.usage.output_tokens,
+        ))
+        .accumulated_cost(accumulated_cost)
+        .extension_data(session.extension_data)
+        .apply()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::ModelHint;
+
+    fn preferences(hints: &[&str], speed: Option<f32>) -> ModelPreferences {
+        ModelPreferences {
+            hints: Some(
+                hints
+                    .iter()
+                    .map(|hint| ModelHint {
+                        name: Some(hint.to_string()),
+                    })
+                    .collect(),
+            ),
+            cost_priority: None,
+            speed_priority: speed,
+            intelligence_priority: Some(0.5),
+        }
+    }
+
+    #[test]
+    fn test_model_for_preferences() {
+        let policy = SamplingPolicy {
+            default: SamplingApproval::Ask,
+            per_extension: HashMap::from([("memory".to_string(), SamplingApproval::Deny)]),
+            models: HashMap::from([("claude-3-haiku".to_string(), "gpt-4o-mini".to_string())]),
+        };
+        assert_eq!(policy.approval_for("memory"), SamplingApproval::Deny);
+        assert_eq!(policy.approval_for("developer"), SamplingApproval::Ask);
+
+        let base = ModelConfig::new_or_fail("gpt-4o").with_fast("gpt-4o-mini".to_string());
+        let model = |preferences: Option<ModelPreferences>| {
+            policy.model_for(&base, preferences.as_ref()).model_name
+        };
+
+        assert_eq!(model(None), "gpt-4o");
+        assert_eq!(
+            model(Some(preferences(&["claude-3-haiku"], None))),
+            "gpt-4o-mini"
+        );
+        assert_eq!(model(Some(preferences(&["o1", "4o"], Some(0.9)))), "gpt-4o");
+        assert_eq!(model(Some(preferences(&["mini"], None))), "gpt-4o-mini");
+        assert_eq!(model(Some(preferences(&["o1"], Some(0.9)))), "gpt-4o-mini");
+        assert_eq!(model(Some(preferences(&["o1"], Some(0.1)))), "gpt-4o");
+    }
+}
@@ -157,6 +157,7 @@ impl Agent {
             id: job_id.clone(),
             source: recipe_path.to_string(),
             cron: cron_expression.to_string(),
+            run_at: None,
             last_run: None,
             currently_running: false,
             paused: false,
@@ -0,0 +1,83 @@
+//! Running the shell commands of a session in containers.
+//!
+//! A session can have its shell commands run in throwaway Docker or Podman containers, for tasks
+//! that shouldn't touch the host beyond the working directory. Calls of tools that support it,
+//! like the developer extension's shell, carry the sandbox in their `_meta`; see [`goose_sandbox`]
+//! for what that covers. The container sees the session's working directory read-write and has
+//! no network unless the sandbox allows it.
+//!
+//! Sessions start with the sandbox set by GOOSE_SHELL_SANDBOX and can turn it on or off for
+//! themselves. GOOSE_SHELL_SANDBOX_IMAGE, GOOSE_SHELL_SANDBOX_RUNTIME and
+//! GOOSE_SHELL_SANDBOX_NETWORK pick the image, the runtime and whether there is network.
+
+pub use goose_sandbox::{ShellSandbox, SHELL_SANDBOX_META_KEY};
+
+use crate::config::Config;
+use crate::session::{ExtensionData, ExtensionState, ShellSandboxState};
+
+pub const SHELL_SANDBOX_CONFIG_KEY: &str = "GOOSE_SHELL_SANDBOX";
+pub const SHELL_SANDBOX_IMAGE_CONFIG_KEY: &str = "GOOSE_SHELL_SANDBOX_IMAGE";
+pub const SHELL_SANDBOX_RUNTIME_CONFIG_KEY: &str = "GOOSE_SHELL_SANDBOX_RUNTIME";
+pub const SHELL_SANDBOX_NETWORK_CONFIG_KEY: &str = "GOOSE_SHELL_SANDBOX_NETWORK";
+pub const DEFAULT_SHELL_SANDBOX_IMAGE: &str = "ubuntu:24.04";
+
+/// The sandbox configured for new sessions' commands to run in
+pub fn sandbox_from_config() -> Option<ShellSandbox> {
+    let config = Config::global();
+    if !config.get_param(SHELL_SANDBOX_CONFIG_KEY).unwrap_or(false) {
+        return None;
+    }
+    Some(configured_sandbox())
+}
+
+/// A sandbox with the configured image, runtime and network, whether or not new sessions use one
+pub fn configured_sandbox() -> ShellSandbox {
+    let config = Config::global();
+    ShellSandbox {
+        runtime: config.get_param(SHELL_SANDBOX_RUNTIME_CONFIG_KEY).ok(),
+        image: config
+            .get_param(SHELL_SANDBOX_IMAGE_CONFIG_KEY)
+            .unwrap_or_else(|_| DEFAULT_SHELL_SANDBOX_IMAGE.to_string()),
+        network: config
+            .get_param(SHELL_SANDBOX_NETWORK_CONFIG_KEY)
+            .unwrap_or(false),
+        working_dir: None,
+    }
+}
+
+/// The sandbox a session's commands run in, which is the configured one unless the session
+/// chose otherwise
+pub fn session_sandbox(extension_data: &ExtensionData) -> Option<ShellSandbox> {
+    match ShellSandboxState::from_extension_data(extension_data) {
+        Some(state) => state.sandbox,
+        None => sandbox_from_config(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_choice_overrides_config() {
+        let sandbox = ShellSandbox {
+            runtime: None,
+            image: "alpine".to_string(),
+            network: false,
+            working_dir: None,
+        };
+
+        let mut extension_data = ExtensionData::default();
+        ShellSandboxState {
+            sandbox: Some(sandbox.clone()),
+        }
+        .to_extension_data(&mut extension_data)
+        .unwrap();
+        assert_eq!(session_sandbox(&extension_data), Some(sandbox));
+
+        ShellSandboxState { sandbox: None }
+            .to_extension_data(&mut extension_data)
+            .unwrap();
+        assert_eq!(session_sandbox(&extension_data), None);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    agents::{subagent_task_config::TaskConfig, AgentEvent, SessionConfig},
+    agents::{budget::budget_of, subagent_task_config::TaskConfig, AgentEvent, SessionConfig},
     conversation::{message::Message, Conversation},
     execution::manager::AgentManager,
     prompt_template::render_global_file,
@@ -153,23 +153,26 @@ fn get_agent_messages(
             .await;
 
         let tools = agent.list_tools(None).await;
-        let subagent_prompt = render_global_file(
-            "subagent_system.md",
-            &SubagentPromptContext {
-                max_turns: task_config
-                    .max_turns
-                    .expect("TaskConfig always sets max_turns"),
-                subagent_id: session_id.clone(),
-                task_instructions: system_instructions,
-                tool_count: tools.len(),
-                available_tools: tools
-                    .iter()
-                    .map(|t| t.name.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", "),
-            },
-        )
-        .map_err(|e| anyhow!("Failed to render subagent system prompt: {}", e))?;
+        let subagent_prompt = match task_config.system_prompt {
+            Some(system_prompt) => format!("{}\n\n{}", system_prompt, system_instructions),
+            None => render_global_file(
+                "subagent_system.md",
+                &SubagentPromptContext {
+                    max_turns: task_config
+                        .max_turns
+                        .expect("TaskConfig always sets max_turns"),
+                    subagent_id: session_id.clone(),
+                    task_instructions: system_instructions,
+                    tool_count: tools.len(),
+                    available_tools: tools
+                        .iter()
+                        .map(|t| t.name.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                },
+            )
+            .map_err(|e| anyhow!("Failed to render subagent system prompt: {}", e))?,
+        };
         agent.override_system_prompt(subagent_prompt).await;
 
         let user_message = Message::user().with_text(user_task);
@@ -180,11 +183,21 @@ fn get_agent_messages(
                 info!("Recipe activity: {}", activity);
             }
         }
+        // The subagent spends its parent's budget, so it can't be used to get around it
+        let budget = budget_of(&task_config.parent_session_id).await?;
         let session_config = SessionConfig {
             id: session_id.clone(),
             schedule_id: None,
             max_turns: task_config.max_turns.map(|v| v as u32),
             retry_config: recipe.retry,
+            moderation: None,
+            context_policy: None,
+            budget,
+            budget_session_id: Some(task_config.parent_session_id.clone()),
+            model_routing: None,
+            lead_worker: None,
+            reflection: None,
+            final_answer_schema: None,
         };
 
         let mut stream = crate::session_context::with_session_id(Some(session_id.clone()), async {
@@ -197,7 +210,13 @@ fn get_agent_messages(
         while let Some(message_result) = stream.next().await {
             match message_result {
                 Ok(AgentEvent::Message(msg)) => conversation.push(msg),
-                Ok(AgentEvent::McpNotification(_)) | Ok(AgentEvent::ModelChange { .. }) => {}
+                Ok(AgentEvent::McpNotification(_))
+                | Ok(AgentEvent::ModelChange { .. })
+                | Ok(AgentEvent::ToolTimeout { .. })
+                | Ok(AgentEvent::BudgetExceeded(_))
+                | Ok(AgentEvent::PlanUpdated(_))
+                | Ok(AgentEvent::FinalAnswer(_))
+                | Ok(AgentEvent::InterimUsage(_)) => {}
                 Ok(AgentEvent::HistoryReplaced(updated_conversation)) => {
                     conversation = updated_conversation;
                 }
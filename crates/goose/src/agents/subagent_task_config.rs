@@ -19,6 +19,8 @@ pub struct TaskConfig {
     pub parent_working_dir: PathBuf,
     pub extensions: Vec<ExtensionConfig>,
     pub max_turns: Option<usize>,
+    /// Replaces the default subagent system prompt; the task instructions follow it
+    pub system_prompt: Option<String>,
 }
 
 impl fmt::Debug for TaskConfig {
@@ -28,6 +30,7 @@ impl fmt::Debug for TaskConfig {
             .field("parent_session_id", &self.parent_session_id)
             .field("parent_working_dir", &self.parent_working_dir)
             .field("max_turns", &self.max_turns)
+            .field("system_prompt", &self.system_prompt)
             .field("extensions", &self.extensions)
             .finish()
     }
@@ -51,6 +54,7 @@ impl TaskConfig {
                     .and_then(|val| val.parse::<usize>().ok())
                     .unwrap_or(DEFAULT_SUBAGENT_MAX_TURNS),
             ),
+            system_prompt: None,
         }
     }
 }
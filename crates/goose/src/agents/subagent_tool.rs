@@ -37,6 +37,8 @@ pub struct SubagentParams {
     pub subrecipe: Option<String>,
     pub parameters: Option<HashMap<String, Value>>,
     pub extensions: Option<Vec<String>>,
+    pub tools: Option<Vec<String>>,
+    pub system_prompt: Option<String>,
     pub settings: Option<SubagentSettings>,
     #[serde(default = "default_summary")]
     pub summary: bool,
@@ -77,6 +79,15 @@ pub fn create_subagent_tool(sub_recipes: &[SubRecipe]) -> Tool {
                 "items": {"type": "string"},
                 "description": "Extensions to enable. Omit to inherit all, empty array for none."
             },
+            "tools": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Full names of the only tools the subagent may use, such as 'developer__shell'. Narrows 'extensions' further."
+            },
+            "system_prompt": {
+                "type": "string",
+                "description": "System prompt for the subagent in place of the default one. The task instructions are added after it."
+            },
             "settings": {
                 "type": "object",
                 "properties": {
@@ -109,7 +120,10 @@ fn build_tool_description(sub_recipes: &[SubRecipe]) -> String {
          2. Predefined: Provide `subrecipe` name to run a predefined task\n\
          3. Augmented: Provide both `subrecipe` and `instructions` to add context\n\n\
          The subagent has access to the same tools as you by default. \
-         Use `extensions` to limit which extensions the subagent can use.\n\n\
+         Use `extensions` to limit which extensions the subagent can use, \
+         or `tools` to name the only tools it can use.\n\n\
+         The subagent works in a separate conversation: only its result comes back to you, \
+         so delegating large research or refactoring tasks keeps your own context small.\n\n\
          For parallel execution, make multiple `subagent` tool calls in the same message.",
     );
 
@@ -418,12 +432,51 @@ async fn apply_settings_overrides(
         }
     }
 
+    if let Some(tool_names) = &params.tools {
+        let mut by_extension: HashMap<&str, Vec<String>> = HashMap::new();
+        for tool_name in tool_names {
+            let (extension, tool) = tool_name.split_once("__").ok_or_else(|| {
+                anyhow!(
+                    "Tool '{}' should be named with its extension, as in 'developer__shell'",
+                    tool_name
+                )
+            })?;
+            by_extension
+                .entry(extension)
+                .or_default()
+                .push(tool.to_string());
+        }
+
+        if let Some(missing) = by_extension.keys().find(|extension| {
+            !task_config
+                .extensions
+                .iter()
+                .any(|ext| ext.key() == **extension)
+        }) {
+            return Err(anyhow!(
+                "No extension '{}' is available to the subagent",
+                missing
+            ));
+        }
+        task_config
+            .extensions
+            .retain_mut(|ext| match by_extension.get(ext.key().as_str()) {
+                Some(tools) => ext.restrict_tools(tools),
+                None => false,
+            });
+    }
+
+    task_config.system_prompt = params.system_prompt.clone();
+
     Ok(task_config)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agents::ExtensionConfig;
+    use std::path::Path;
+    use std::sync::Arc;
 
     #[test]
     fn test_tool_name() {
@@ -496,6 +549,8 @@ mod tests {
             "subrecipe": "my_recipe",
             "parameters": {"key": "value"},
             "extensions": ["developer"],
+            "tools": ["developer__shell"],
+            "system_prompt": "You are a code reviewer.",
             "settings": {"model": "gpt-4"},
             "summary": false
         }))
@@ -505,6 +560,51 @@ mod tests {
         assert_eq!(params.subrecipe, Some("my_recipe".to_string()));
         assert!(params.parameters.is_some());
         assert_eq!(params.extensions, Some(vec!["developer".to_string()]));
+        assert_eq!(params.tools, Some(vec!["developer__shell".to_string()]));
+        assert_eq!(
+            params.system_prompt,
+            Some("You are a code reviewer.".to_string())
+        );
         assert!(!params.summary);
     }
+
+    #[tokio::test]
+    async fn test_tools_restrict_subagent_extensions() {
+        let task_config = TaskConfig::new(
+            Arc::new(crate::providers::mock::MockProvider::new()),
+            "parent",
+            Path::new("."),
+            vec![
+                ExtensionConfig::stdio("developer", "dev", "Developer", 300u64),
+                ExtensionConfig::stdio("memory", "mem", "Memory", 300u64),
+            ],
+        );
+        let params: SubagentParams = serde_json::from_value(json!({
+            "instructions": "Review the diff",
+            "tools": ["developer__shell", "developer__text_editor"],
+            "system_prompt": "You are a code reviewer."
+        }))
+        .unwrap();
+
+        let task_config = apply_settings_overrides(task_config, &params)
+            .await
+            .unwrap();
+        assert_eq!(task_config.extensions.len(), 1);
+        let developer = &task_config.extensions[0];
+        assert!(developer.is_tool_available("shell"));
+        assert!(!developer.is_tool_available("list_windows"));
+        assert_eq!(
+            task_config.system_prompt.as_deref(),
+            Some("You are a code reviewer.")
+        );
+
+        let params: SubagentParams = serde_json::from_value(json!({
+            "instructions": "Review the diff",
+            "tools": ["github__create_issue"]
+        }))
+        .unwrap();
+        assert!(apply_settings_overrides(task_config, &params)
+            .await
+            .is_err());
+    }
 }
@@ -0,0 +1,403 @@
+//! Caching of idempotent tool calls.
+//!
+//! When GOOSE_TOOL_CACHE is enabled, the results of calls to tools annotated read-only are
+//! cached by a hash of the tool name and arguments, separately for each session. A model that
+//! reads the same file or runs the same search again then gets the earlier result without the
+//! call running a second time. Entries expire after GOOSE_TOOL_CACHE_TTL seconds. A call to any
+//! other tool may change what the cached calls would return, so it empties the session's cache
+//! when it starts and again when it finishes, and read-only calls running alongside it keep their
+//! results out of the cache.
+//! Tools annotated open-world, whose results depend on the outside world, and calls that failed
+//! are never cached.
+
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::FutureExt;
+use lru::LruCache;
+use rmcp::model::{CallToolRequestParam, CallToolResult, Tool};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::agents::tool_execution::ToolCallResult;
+use crate::config::Config;
+
+pub const TOOL_CACHE_CONFIG_KEY: &str = "GOOSE_TOOL_CACHE";
+pub const TOOL_CACHE_TTL_CONFIG_KEY: &str = "GOOSE_TOOL_CACHE_TTL";
+pub const DEFAULT_TOOL_CACHE_TTL: Duration = Duration::from_secs(300);
+/// Entries kept across all sessions
+pub const TOOL_CACHE_CAPACITY: usize = 512;
+
+/// Whether tool results are cached, set with GOOSE_TOOL_CACHE
+pub fn tool_cache_enabled() -> bool {
+    Config::global()
+        .get_param(TOOL_CACHE_CONFIG_KEY)
+        .unwrap_or(false)
+}
+
+fn tool_cache_ttl() -> Duration {
+    Config::global()
+        .get_param::<u64>(TOOL_CACHE_TTL_CONFIG_KEY)
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_TOOL_CACHE_TTL)
+}
+
+/// Whether calls to `tool` can be served from the cache
+pub fn is_cacheable(tool: &Tool) -> bool {
+    tool.annotations.as_ref().is_some_and(|annotations| {
+        annotations.read_only_hint == Some(true) && annotations.open_world_hint != Some(true)
+    })
+}
+
+/// `value` as JSON with object keys sorted, so equal arguments hash the same whatever order
+/// the model wrote them in
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(object) => {
+            let mut entries: Vec<_> = object.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            let fields: Vec<String> = entries
+                .into_iter()
+                .map(|(key, value)| {
+                    format!("{}:{}", Value::from(key.as_str()), canonical_json(value))
+                })
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+struct CachedResult {
+    session_id: String,
+    created_at: Instant,
+    result: CallToolResult,
+}
+
+/// The mutating calls of a session
+#[derive(Default)]
+struct SessionWrites {
+    /// Calls still running
+    pending: usize,
+    /// Calls started or finished so far, so a read can tell whether one overlapped it
+    generation: u64,
+}
+
+/// Results of read-only tool calls, per session
+pub struct ToolResultCache {
+    entries: Mutex<LruCache<String, CachedResult>>,
+    writes: Mutex<HashMap<String, SessionWrites>>,
+}
+
+impl ToolResultCache {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+            writes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn write_generation(&self, session_id: &str) -> u64 {
+        self.writes
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .map_or(0, |writes| writes.generation)
+    }
+
+    /// Cache key for a tool call in session `session_id`
+    pub fn key(session_id: &str, tool_call: &CallToolRequestParam) -> String {
+        let arguments = Value::Object(tool_call.arguments.clone().unwrap_or_default());
+        let mut hasher = Sha256::new();
+        hasher.update(tool_call.name.as_bytes());
+        hasher.update([0]);
+        hasher.update(canonical_json(&arguments).as_bytes());
+        format!("{}:{:x}", session_id, hasher.finalize())
+    }
+
+    /// The cached result for `key`, if there is one younger than GOOSE_TOOL_CACHE_TTL
+    pub fn get(&self, key: &str) -> Option<CallToolResult> {
+        self.get_within(key, tool_cache_ttl())
+    }
+
+    fn get_within(&self, key: &str, ttl: Duration) -> Option<CallToolResult> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some(entry) if entry.created_at.elapsed() < ttl => Some(entry.result.clone()),
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, session_id: &str, key: String, result: CallToolResult) {
+        self.entries.lock().unwrap().put(
+            key,
+            CachedResult {
+                session_id: session_id.to_string(),
+                created_at: Instant::now(),
+                result,
+            },
+        );
+    }
+
+    /// Cache `result` unless a mutating call of the session is running, or started or finished
+    /// since the read that produced it began at `generation`
+    fn insert_unless_written(
+        &self,
+        session_id: &str,
+        key: String,
+        result: CallToolResult,
+        generation: u64,
+    ) {
+        // Held while inserting, so a write can't start between the check and the insert
+        let writes = self.writes.lock().unwrap();
+        let written = writes
+            .get(session_id)
+            .is_some_and(|writes| writes.pending > 0 || writes.generation != generation);
+        if !written {
+            self.insert(session_id, key, result);
+        }
+    }
+
+    fn update_writes(&self, session_id: &str, update: impl FnOnce(&mut SessionWrites)) {
+        let mut writes = self.writes.lock().unwrap();
+        let session_writes = writes.entry(session_id.to_string()).or_default();
+        update(session_writes);
+        session_writes.generation += 1;
+        self.invalidate(session_id);
+    }
+
+    /// Forget every result cached for session `session_id`
+    pub fn invalidate(&self, session_id: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        let stale: Vec<String> = entries
+            .iter()
+            .filter(|(_, entry)| entry.session_id == session_id)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in stale {
+            entries.pop(&key);
+        }
+    }
+
+    /// `result`, caching it under `key` once it turns out to have succeeded
+    pub fn caching(
+        self: &Arc<Self>,
+        session_id: &str,
+        key: String,
+        result: ToolCallResult,
+    ) -> ToolCallResult {
+        let cache = self.clone();
+        let session_id = session_id.to_string();
+        let generation = self.write_generation(&session_id);
+        ToolCallResult {
+            notification_stream: result.notification_stream,
+            result: Box::new(
+                async move {
+                    let response = result.result.await;
+                    if let Ok(result) = &response {
+                        if result.is_error != Some(true) {
+                            cache.insert_unless_written(
+                                &session_id,
+                                key,
+                                result.clone(),
+                                generation,
+                            );
+                        }
+                    }
+                    response
+                }
+                .boxed(),
+            ),
+        }
+    }
+
+    /// `result` of a call that may change what cached calls return. The session's cache is
+    /// emptied now and again once the call is done, and stays empty while it runs.
+    pub fn writing(self: &Arc<Self>, session_id: &str, result: ToolCallResult) -> ToolCallResult {
+        let write = PendingWrite::start(self.clone(), session_id);
+        ToolCallResult {
+            notification_stream: result.notification_stream,
+            result: Box::new(
+                async move {
+                    let response = result.result.await;
+                    drop(write);
+                    response
+                }
+                .boxed(),
+            ),
+        }
+    }
+}
+
+/// A running mutating call, finished when dropped so a cancelled call doesn't block the cache
+struct PendingWrite {
+    cache: Arc<ToolResultCache>,
+    session_id: String,
+}
+
+impl PendingWrite {
+    fn start(cache: Arc<ToolResultCache>, session_id: &str) -> Self {
+        cache.update_writes(session_id, |writes| writes.pending += 1);
+        Self {
+            cache,
+            session_id: session_id.to_string(),
+        }
+    }
+}
+
+impl Drop for PendingWrite {
+    fn drop(&mut self) {
+        self.cache
+            .update_writes(&self.session_id, |writes| writes.pending -= 1);
+    }
+}
+
+impl Default for ToolResultCache {
+    fn default() -> Self {
+        Self::new(TOOL_CACHE_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::{Content, ErrorCode, ErrorData, ToolAnnotations};
+    use rmcp::object;
+
+    fn read_file(path: &str, limit: i64) -> CallToolRequestParam {
+        CallToolRequestParam {
+            name: "files__read_file".into(),
+            arguments: Some(object!({"path": path, "limit": limit})),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_serves_repeated_calls() {
+        let cache = Arc::new(ToolResultCache::new(8));
+
+        let key = ToolResultCache::key("session-1", &read_file("a.txt", 10));
+        let reordered = CallToolRequestParam {
+            name: "files__read_file".into(),
+            arguments: Some(object!({"limit": 10, "path": "a.txt"})),
+        };
+        assert_eq!(key, ToolResultCache::key("session-1", &reordered));
+        assert_ne!(key, ToolResultCache::key("session-2", &reordered));
+        assert_ne!(
+            key,
+            ToolResultCache::key("session-1", &read_file("b.txt", 10))
+        );
+
+        let call = cache.caching(
+            "session-1",
+            key.clone(),
+            ToolCallResult::from(Ok(CallToolResult::success(vec![Content::text("hello")]))),
+        );
+        assert!(cache.get(&key).is_none());
+        call.result.await.unwrap();
+        assert_eq!(
+            cache.get(&key).unwrap().content[0].as_text().unwrap().text,
+            "hello"
+        );
+        assert!(cache.get_within(&key, Duration::ZERO).is_none());
+        assert!(cache.get(&key).is_none());
+
+        // Failures aren't cached
+        let failed = cache.caching(
+            "session-1",
+            key.clone(),
+            ToolCallResult::from(Err(ErrorData::new(ErrorCode::INTERNAL_ERROR, "oops", None))),
+        );
+        assert!(failed.result.await.is_err());
+        assert!(cache.get(&key).is_none());
+
+        let other_key = ToolResultCache::key("session-2", &read_file("a.txt", 10));
+        cache.insert("session-1", key.clone(), CallToolResult::success(vec![]));
+        cache.insert(
+            "session-2",
+            other_key.clone(),
+            CallToolResult::success(vec![]),
+        );
+        cache.invalidate("session-1");
+        assert!(cache.get(&key).is_none());
+        assert!(cache.get(&other_key).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_reads_during_a_write_are_not_cached() {
+        let cache = Arc::new(ToolResultCache::new(8));
+        let key = ToolResultCache::key("session-1", &read_file("a.txt", 10));
+        cache.insert("session-1", key.clone(), CallToolResult::success(vec![]));
+
+        let (finish_write, write_done) = tokio::sync::oneshot::channel();
+        let write = cache.writing(
+            "session-1",
+            ToolCallResult {
+                result: Box::new(
+                    async move {
+                        write_done.await.ok();
+                        Ok(CallToolResult::success(vec![]))
+                    }
+                    .boxed(),
+                ),
+                notification_stream: None,
+            },
+        );
+        assert!(cache.get(&key).is_none());
+
+        // A read that finishes while the write is running isn't cached
+        let read = cache.caching(
+            "session-1",
+            key.clone(),
+            ToolCallResult::from(Ok(CallToolResult::success(vec![Content::text("old")]))),
+        );
+        read.result.await.unwrap();
+        assert!(cache.get(&key).is_none());
+
+        // Nor is one that started before the write finished
+        let read = cache.caching(
+            "session-1",
+            key.clone(),
+            ToolCallResult::from(Ok(CallToolResult::success(vec![Content::text("old")]))),
+        );
+        finish_write.send(()).unwrap();
+        write.result.await.unwrap();
+        read.result.await.unwrap();
+        assert!(cache.get(&key).is_none());
+
+        let read = cache.caching(
+            "session-1",
+            key.clone(),
+            ToolCallResult::from(Ok(CallToolResult::success(vec![Content::text("new")]))),
+        );
+        read.result.await.unwrap();
+        assert!(cache.get(&key).is_some());
+    }
+
+    #[test]
+    fn test_is_cacheable() {
+        let tool = |read_only, open_world| {
+            Tool::new("read_file", "", object!({"type": "object"})).annotate(ToolAnnotations {
+                title: None,
+                read_only_hint: read_only,
+                destructive_hint: None,
+                idempotent_hint: None,
+                open_world_hint: open_world,
+            })
+        };
+        assert!(is_cacheable(&tool(Some(true), None)));
+        assert!(!is_cacheable(&tool(Some(true), Some(true))));
+        assert!(!is_cacheable(&tool(None, None)));
+        assert!(!is_cacheable(&tool(Some(false), Some(false))));
+    }
+}
@@ -0,0 +1,73 @@
+//! Changes to the tools offered to the model as extensions are attached to and detached from a
+//! running session.
+
+use std::collections::BTreeSet;
+
+use rmcp::model::Tool;
+use serde::{Deserialize, Serialize};
+
+/// Tools that appeared and disappeared between two tool lists, by name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolListChange {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl ToolListChange {
+    pub fn between(before: &[Tool], after: &[Tool]) -> Self {
+        let names = |tools: &[Tool]| -> BTreeSet<String> {
+            tools.iter().map(|tool| tool.name.to_string()).collect()
+        };
+        let (before, after) = (names(before), names(after));
+        Self {
+            added: after.difference(&before).cloned().collect(),
+            removed: before.difference(&after).cloned().collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// A one-line summary for the user
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.added.is_empty() {
+            parts.push(format!("Tools added: {}.", self.added.join(", ")));
+        }
+        if !self.removed.is_empty() {
+            parts.push(format!("Tools removed: {}.", self.removed.join(", ")));
+        }
+        if parts.is_empty() {
+            "No tools changed.".to_string()
+        } else {
+            parts.join(" ")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::object;
+
+    #[test]
+    fn test_tool_list_change() {
+        let tool = |name: &str| Tool::new(name.to_string(), "", object!({"type": "object"}));
+        let before = vec![tool("developer__shell"), tool("git__status")];
+        let after = vec![
+            tool("developer__shell"),
+            tool("jira__search"),
+            tool("jira__create"),
+        ];
+
+        let change = ToolListChange::between(&before, &after);
+        assert_eq!(change.added, vec!["jira__create", "jira__search"]);
+        assert_eq!(change.removed, vec!["git__status"]);
+        assert_eq!(
+            change.summary(),
+            "Tools added: jira__create, jira__search. Tools removed: git__status."
+        );
+        assert!(ToolListChange::between(&after, &after).is_empty());
+    }
+}
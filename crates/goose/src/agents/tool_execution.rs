@@ -1,6 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use async_stream::try_stream;
 use futures::stream::{self, BoxStream};
@@ -9,9 +11,11 @@ use tokio::sync::Mutex;
 use tokio_util::sync::CancellationToken;
 
 use crate::config::permission::PermissionLevel;
+use crate::config::Config;
 use crate::mcp_utils::ToolResult;
 use crate::permission::Permission;
-use rmcp::model::{Content, ServerNotification};
+use rmcp::model::{CallToolResult, Content, NumberOrString, ServerNotification, Tool};
+use serde_json::{json, Value};
 
 // ToolCallResult combines the result of a tool call with an optional notification stream that
 // can be used to receive notifications from the tool.
@@ -29,7 +33,8 @@ impl From<ToolResult<rmcp::model::CallToolResult>> for ToolCallResult {
     }
 }
 
-use super::agent::{tool_stream, ToolStream};
+use super::activity::ActivityEvent;
+use super::agent::{tool_stream, ToolStream, ToolStreamItem};
 use crate::agents::Agent;
 use crate::conversation::message::{Message, ToolRequest};
 use crate::session::Session;
@@ -39,6 +44,29 @@ pub const DECLINED_RESPONSE: &str = "The user has declined to run this tool. \
     DO NOT attempt to call this tool again. \
     If there are no alternative methods to proceed, clearly explain the situation and STOP.";
 
+pub const CANCELLED_RESPONSE: &str = "The user cancelled this tool call before it finished.";
+
+/// How long tools may keep running after a turn is cancelled, to tell their servers and
+/// report what they did
+pub const TOOL_ABORT_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+pub const MAX_PARALLEL_TOOL_CALLS_CONFIG_KEY: &str = "GOOSE_MAX_PARALLEL_TOOL_CALLS";
+pub const DEFAULT_MAX_PARALLEL_TOOL_CALLS: usize = 8;
+
+pub const TOOL_TIMEOUT_CONFIG_KEY: &str = "GOOSE_TOOL_TIMEOUT";
+pub const TOOL_TIMEOUTS_CONFIG_KEY: &str = "GOOSE_TOOL_TIMEOUTS";
+pub const TURN_TOOL_TIMEOUT_CONFIG_KEY: &str = "GOOSE_TURN_TOOL_TIMEOUT";
+/// The `error` of the structured content of a tool call that ran out of time
+pub const TOOL_TIMEOUT_ERROR: &str = "timeout";
+
+pub const TOOL_PROGRESS_MESSAGES_CONFIG_KEY: &str = "GOOSE_TOOL_PROGRESS_MESSAGES";
+/// How often a tool call's progress is shown as an inline message
+pub const TOOL_PROGRESS_MESSAGE_INTERVAL: Duration = Duration::from_secs(15);
+/// Key of the tool call `_meta` entry asking the server to report its progress
+pub const PROGRESS_TOKEN_META_KEY: &str = "progressToken";
+
+static PROGRESS_TOKEN_COUNT: AtomicU64 = AtomicU64::new(0);
+
 pub const CHAT_MODE_TOOL_SKIPPED_RESPONSE: &str = "Let the user know the tool call was skipped in goose chat mode. \
                                         DO NOT apologize for skipping the tool call. DO NOT say sorry. \
                                         Provide an explanation of what the tool call would do, structured as a \
@@ -48,6 +76,359 @@ pub const CHAT_MODE_TOOL_SKIPPED_RESPONSE: &str = "Let the user know the tool ca
                                         2. **Outline Steps** - Break down the steps.\n \
                                         If needed, adjust the explanation based on user preferences or questions.";
 
+/// How many tool calls from one response may run at once, set with
+/// GOOSE_MAX_PARALLEL_TOOL_CALLS
+pub fn max_parallel_tool_calls() -> usize {
+    Config::global()
+        .get_param::<usize>(MAX_PARALLEL_TOOL_CALLS_CONFIG_KEY)
+        .unwrap_or(DEFAULT_MAX_PARALLEL_TOOL_CALLS)
+        .max(1)
+}
+
+/// Run the tool calls of one response concurrently, at most `max_parallel` at a time, with
+/// items tagged by request id.
+///
+/// Calls that aren't to a tool annotated read-only may change their extension's state, so
+/// those going to the same extension run one after another in the order given; a file
+/// written by one edit is then there for the next. Everything else runs independently.
+pub(crate) fn run_tool_calls_concurrently(
+    tool_streams: Vec<(String, ToolStream)>,
+    requests: &[ToolRequest],
+    tools: &[Tool],
+    max_parallel: usize,
+) -> BoxStream<'static, (String, ToolStreamItem<ToolResult<CallToolResult>>)> {
+    let read_only: HashSet<&str> = tools
+        .iter()
+        .filter(|tool| {
+            tool.annotations
+                .as_ref()
+                .and_then(|annotations| annotations.read_only_hint)
+                .unwrap_or(false)
+        })
+        .map(|tool| tool.name.as_ref())
+        .collect();
+    let tool_names: HashMap<&str, &str> = requests
+        .iter()
+        .filter_map(|request| {
+            let tool_call = request.tool_call.as_ref().ok()?;
+            Some((request.id.as_str(), tool_call.name.as_ref()))
+        })
+        .collect();
+
+    let mut lanes: Vec<Vec<_>> = Vec::new();
+    let mut extension_lanes: HashMap<&str, usize> = HashMap::new();
+    for (request_id, stream) in tool_streams {
+        let extension = tool_names
+            .get(request_id.as_str())
+            .filter(|name| !read_only.contains(*name))
+            .map(|name| {
+                name.split_once("__")
+                    .map_or(*name, |(extension, _)| extension)
+            });
+        let tagged = stream
+            .map({
+                let request_id = request_id.clone();
+                move |item| (request_id.clone(), item)
+            })
+            .boxed();
+        match extension {
+            Some(extension) => {
+                let lane = *extension_lanes.entry(extension).or_insert_with(|| {
+                    lanes.push(Vec::new());
+                    lanes.len() - 1
+                });
+                lanes[lane].push(tagged);
+            }
+            None => lanes.push(vec![tagged]),
+        }
+    }
+
+    stream::iter(lanes.into_iter().map(|lane| stream::iter(lane).flatten()))
+        .flatten_unordered(max_parallel)
+        .boxed()
+}
+
+/// `stream` until `cancel_token` is cancelled, and then for up to [`TOOL_ABORT_GRACE_PERIOD`]
+/// more. Tools see the same token, so MCP calls use that time to send the server a
+/// cancellation and return.
+pub(crate) fn with_abort_grace<'a, T: Send + 'a>(
+    mut stream: BoxStream<'a, T>,
+    cancel_token: CancellationToken,
+) -> BoxStream<'a, T> {
+    async_stream::stream! {
+        loop {
+            let next = tokio::select! {
+                next = stream.next() => next,
+                _ = cancel_token.cancelled() => break,
+            };
+            match next {
+                Some(item) => yield item,
+                None => return,
+            }
+        }
+
+        let deadline = tokio::time::Instant::now() + TOOL_ABORT_GRACE_PERIOD;
+        while let Ok(Some(item)) = tokio::time::timeout_at(deadline, stream.next()).await {
+            yield item;
+        }
+    }
+    .boxed()
+}
+
+/// Limits on how long tool calls may run, configured in seconds: GOOSE_TOOL_TIMEOUT for any
+/// tool, GOOSE_TOOL_TIMEOUTS for particular tools by name, and GOOSE_TURN_TOOL_TIMEOUT for
+/// all the calls of one response together. Unset means no limit beyond the extension's own.
+#[derive(Debug, Clone, Default)]
+pub struct ToolTimeouts {
+    pub default: Option<Duration>,
+    pub per_tool: HashMap<String, Duration>,
+    pub turn: Option<Duration>,
+}
+
+impl ToolTimeouts {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let secs = |key: &str| {
+            config
+                .get_param::<u64>(key)
+                .ok()
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+        };
+        let per_tool = config
+            .get_param::<HashMap<String, u64>>(TOOL_TIMEOUTS_CONFIG_KEY)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, secs)| *secs > 0)
+            .map(|(tool_name, secs)| (tool_name, Duration::from_secs(secs)))
+            .collect();
+        Self {
+            default: secs(TOOL_TIMEOUT_CONFIG_KEY),
+            per_tool,
+            turn: secs(TURN_TOOL_TIMEOUT_CONFIG_KEY),
+        }
+    }
+
+    pub fn for_tool(&self, tool_name: &str) -> Option<Duration> {
+        self.per_tool.get(tool_name).copied().or(self.default)
+    }
+}
+
+/// What the model gets for a tool call that ran out of time: an error naming the tool and
+/// the limit, with whatever the tool reported before it was stopped
+pub fn tool_timeout_result(
+    tool_name: &str,
+    timeout: Duration,
+    partial: &[String],
+) -> CallToolResult {
+    let mut text = format!(
+        "Tool {} timed out after {:?} and was stopped.",
+        tool_name, timeout
+    );
+    if !partial.is_empty() {
+        text.push_str(" Output before it was stopped:\n");
+        text.push_str(&partial.join("\n"));
+    }
+    CallToolResult {
+        content: vec![Content::text(text)],
+        structured_content: Some(json!({
+            "error": TOOL_TIMEOUT_ERROR,
+            "tool": tool_name,
+            "timeoutMs": timeout.as_millis() as u64,
+            "partialOutput": partial,
+        })),
+        is_error: Some(true),
+        meta: None,
+    }
+}
+
+/// The limit a tool call ran out of, if `result` came from [`tool_timeout_result`]
+pub fn timed_out_after(result: &CallToolResult) -> Option<Duration> {
+    if result.is_error != Some(true) {
+        return None;
+    }
+    let structured = result.structured_content.as_ref()?;
+    if structured.get("error")?.as_str()? != TOOL_TIMEOUT_ERROR {
+        return None;
+    }
+    structured
+        .get("timeoutMs")?
+        .as_u64()
+        .map(Duration::from_millis)
+}
+
+/// The text of a log or progress notification, kept to report if its tool times out
+pub(crate) fn notification_text(notification: &ServerNotification) -> Option<String> {
+    match notification {
+        ServerNotification::LoggingMessageNotification(notification) => {
+            Some(match &notification.params.data {
+                Value::String(text) => text.clone(),
+                data => data.to_string(),
+            })
+        }
+        ServerNotification::ProgressNotification(notification) => {
+            notification.params.message.clone()
+        }
+        _ => None,
+    }
+}
+
+/// A progress token for a new tool call, unique within this process
+pub(crate) fn new_progress_token() -> String {
+    format!(
+        "goose-tool-call-{}",
+        PROGRESS_TOKEN_COUNT.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Whether `notification` belongs to the tool call with progress token `token`. Servers share
+/// one notification channel between their calls, so progress of other calls is left out, while
+/// log messages, which carry no token, are kept.
+pub(crate) fn is_for_tool_call(notification: &ServerNotification, token: &str) -> bool {
+    match notification {
+        ServerNotification::ProgressNotification(notification) => matches!(
+            &notification.params.progress_token.0,
+            NumberOrString::String(progress_token) if progress_token.as_ref() == token
+        ),
+        _ => true,
+    }
+}
+
+/// Shows the progress of long-running tool calls as inline messages, when
+/// GOOSE_TOOL_PROGRESS_MESSAGES is set, so a multi-minute call doesn't look stuck. Each call
+/// gets at most one message per [`TOOL_PROGRESS_MESSAGE_INTERVAL`].
+pub(crate) struct ToolProgressMessages {
+    interval: Duration,
+    last_shown: HashMap<String, Instant>,
+}
+
+impl ToolProgressMessages {
+    pub fn from_config() -> Option<Self> {
+        Config::global()
+            .get_param(TOOL_PROGRESS_MESSAGES_CONFIG_KEY)
+            .unwrap_or(false)
+            .then(|| Self::new(TOOL_PROGRESS_MESSAGE_INTERVAL))
+    }
+
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_shown: HashMap::new(),
+        }
+    }
+
+    /// The message to show for `notification` from the call `request_id` of `tool_name`, if
+    /// it reports progress and the call's last message is old enough
+    pub fn message(
+        &mut self,
+        request_id: &str,
+        tool_name: &str,
+        notification: &ServerNotification,
+    ) -> Option<String> {
+        self.message_at(request_id, tool_name, notification, Instant::now())
+    }
+
+    fn message_at(
+        &mut self,
+        request_id: &str,
+        tool_name: &str,
+        notification: &ServerNotification,
+        now: Instant,
+    ) -> Option<String> {
+        let ServerNotification::ProgressNotification(notification) = notification else {
+            return None;
+        };
+        if self
+            .last_shown
+            .get(request_id)
+            .is_some_and(|shown| now.duration_since(*shown) < self.interval)
+        {
+            return None;
+        }
+        self.last_shown.insert(request_id.to_string(), now);
+
+        let params = &notification.params;
+        let amount = match params.total {
+            Some(total) if total > 0.0 => {
+                format!("{:.0}%", (params.progress / total * 100.0).min(100.0))
+            }
+            _ => format!("{}", params.progress),
+        };
+        Some(match &params.message {
+            Some(message) => format!("{} is still running ({}): {}", tool_name, amount, message),
+            None => format!("{} is still running ({})", tool_name, amount),
+        })
+    }
+
+    /// Forget the call `request_id`, once it has finished
+    pub fn finish(&mut self, request_id: &str) {
+        self.last_shown.remove(request_id);
+    }
+}
+
+pub(crate) fn requested_tool_name<'a>(
+    requests: &'a [ToolRequest],
+    request_id: &str,
+) -> Option<&'a str> {
+    requests
+        .iter()
+        .find(|request| request.id == request_id)
+        .and_then(|request| request.tool_call.as_ref().ok())
+        .map(|tool_call| tool_call.name.as_ref())
+}
+
+/// `stream` cut off after `timeout`, ending with a [`tool_timeout_result`] if it hadn't
+/// produced its result by then. The clock starts when the call is first polled, so a call
+/// waiting behind others to the same extension isn't charged for the wait.
+pub(crate) fn with_tool_timeout(
+    mut stream: ToolStream,
+    tool_name: String,
+    timeout: Duration,
+) -> ToolStream {
+    Box::pin(async_stream::stream! {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut partial = Vec::new();
+        loop {
+            match tokio::time::timeout_at(deadline, stream.next()).await {
+                Ok(Some(item)) => {
+                    if let ToolStreamItem::Message(notification) = &item {
+                        partial.extend(notification_text(notification));
+                    }
+                    yield item;
+                }
+                Ok(None) => return,
+                Err(_) => {
+                    tracing::warn!("Tool {} timed out after {:?}", tool_name, timeout);
+                    yield ToolStreamItem::Result(Ok(tool_timeout_result(&tool_name, timeout, &partial)));
+                    return;
+                }
+            }
+        }
+    })
+}
+
+/// `tool_streams` with the timeouts their tools have in `timeouts`
+pub(crate) fn apply_tool_timeouts(
+    tool_streams: Vec<(String, ToolStream)>,
+    requests: &[ToolRequest],
+    timeouts: &ToolTimeouts,
+) -> Vec<(String, ToolStream)> {
+    tool_streams
+        .into_iter()
+        .map(|(request_id, stream)| {
+            let timeout = requested_tool_name(requests, &request_id)
+                .and_then(|tool_name| Some((tool_name, timeouts.for_tool(tool_name)?)));
+            match timeout {
+                Some((tool_name, timeout)) => {
+                    let stream = with_tool_timeout(stream, tool_name.to_string(), timeout);
+                    (request_id, stream)
+                }
+                None => (request_id, stream),
+            }
+        })
+        .collect()
+}
+
 impl Agent {
     pub(crate) fn handle_approval_tool_requests<'a>(
         &'a self,
@@ -82,6 +463,18 @@ impl Agent {
                     .user_only();
                 yield confirmation;
 
+                let mut arguments = Value::Object(tool_call.arguments.clone().unwrap_or_default());
+                crate::redaction::redact_json(&mut arguments);
+                crate::webhooks::notify(
+                    crate::webhooks::WebhookEvent::ApprovalNeeded,
+                    Some(&session.id),
+                    json!({
+                        "request_id": request.id,
+                        "tool_name": tool_call.name,
+                        "arguments": arguments,
+                    }),
+                );
+
                 let mut rx = self.confirmation_rx.lock().await;
                 while let Some((req_id, confirmation)) = rx.recv().await {
                     if req_id == request.id {
@@ -96,6 +489,11 @@ impl Agent {
                         }
 
                         if confirmation.permission == Permission::AllowOnce || confirmation.permission == Permission::AlwaysAllow {
+                            self.emit_activity(&session.id, ActivityEvent::ToolApproved {
+                                request_id: request.id.clone(),
+                                tool_name: tool_call.name.to_string(),
+                                by_user: true,
+                            });
                             let (req_id, tool_result) = self.dispatch_tool_call(tool_call.clone(), request.id.clone(), cancellation_token.clone(), session).await;
                             let mut futures = tool_futures.lock().await;
 
@@ -168,3 +566,164 @@ impl Agent {
         .boxed()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delayed_call(delay_ms: u64) -> ToolStream {
+        tool_stream(stream::empty(), async move {
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+            Ok(CallToolResult::success(vec![]))
+        })
+    }
+
+    fn request(id: &str, tool_name: &str) -> ToolRequest {
+        ToolRequest {
+            id: id.to_string(),
+            tool_call: Ok(rmcp::model::CallToolRequestParam {
+                name: tool_name.to_string().into(),
+                arguments: None,
+            }),
+            metadata: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_writes_to_one_extension_run_in_order() {
+        let schema = rmcp::object!({"type": "object"});
+        let tools = vec![
+            Tool::new("dev__write", "Write a file", schema.clone()),
+            Tool::new("dev__read", "Read a file", schema).annotate(rmcp::model::ToolAnnotations {
+                title: None,
+                read_only_hint: Some(true),
+                destructive_hint: Some(false),
+                idempotent_hint: Some(true),
+                open_world_hint: Some(false),
+            }),
+        ];
+        let requests = [
+            request("first", "dev__write"),
+            request("second", "dev__write"),
+            request("read", "dev__read"),
+        ];
+        let streams = vec![
+            ("first".to_string(), delayed_call(50)),
+            ("second".to_string(), delayed_call(0)),
+            ("read".to_string(), delayed_call(10)),
+        ];
+
+        let finished: Vec<String> = run_tool_calls_concurrently(streams, &requests, &tools, 8)
+            .map(|(request_id, _)| request_id)
+            .collect()
+            .await;
+        assert_eq!(finished, ["read", "first", "second"]);
+    }
+
+    #[tokio::test]
+    async fn test_with_abort_grace_lets_tools_finish() {
+        let cancel_token = CancellationToken::new();
+        let slow = async_stream::stream! {
+            yield 1;
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            yield 2;
+        }
+        .boxed();
+        let mut stream = with_abort_grace(slow, cancel_token.clone());
+
+        assert_eq!(stream.next().await, Some(1));
+        cancel_token.cancel();
+        assert_eq!(stream.next().await, Some(2));
+        assert_eq!(stream.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_tool_timeout_reports_partial_output() {
+        let progress = ServerNotification::LoggingMessageNotification(
+            rmcp::model::LoggingMessageNotification {
+                method: rmcp::model::LoggingMessageNotificationMethod,
+                params: rmcp::model::LoggingMessageNotificationParam {
+                    level: rmcp::model::LoggingLevel::Info,
+                    logger: None,
+                    data: Value::String("compiled 3 of 10 crates".into()),
+                },
+                extensions: Default::default(),
+            },
+        );
+        let slow = tool_stream(stream::iter([progress]), async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(CallToolResult::success(vec![]))
+        });
+
+        let items: Vec<_> =
+            with_tool_timeout(slow, "dev__build".to_string(), Duration::from_millis(20))
+                .collect()
+                .await;
+        assert_eq!(items.len(), 2);
+        let ToolStreamItem::Result(Ok(result)) = &items[1] else {
+            panic!("expected the tool call to end with a result");
+        };
+        assert_eq!(timed_out_after(result), Some(Duration::from_millis(20)));
+        assert!(result.content[0]
+            .as_text()
+            .unwrap()
+            .text
+            .contains("compiled 3 of 10 crates"));
+
+        let quick = with_tool_timeout(
+            delayed_call(0),
+            "dev__read".to_string(),
+            Duration::from_secs(5),
+        );
+        match quick.collect::<Vec<_>>().await.as_slice() {
+            [ToolStreamItem::Result(Ok(result))] => assert_eq!(timed_out_after(result), None),
+            _ => panic!("expected the tool's own result"),
+        }
+    }
+
+    fn progress(token: &str, progress: f64, message: Option<&str>) -> ServerNotification {
+        ServerNotification::ProgressNotification(rmcp::model::ProgressNotification {
+            method: rmcp::model::ProgressNotificationMethod,
+            params: rmcp::model::ProgressNotificationParam {
+                progress_token: rmcp::model::ProgressToken(NumberOrString::String(token.into())),
+                progress,
+                total: Some(10.0),
+                message: message.map(String::from),
+            },
+            extensions: Default::default(),
+        })
+    }
+
+    #[test]
+    fn test_progress_messages_are_scoped_and_throttled() {
+        let token = new_progress_token();
+        assert_ne!(token, new_progress_token());
+        assert!(is_for_tool_call(&progress(&token, 1.0, None), &token));
+        assert!(!is_for_tool_call(&progress("other", 1.0, None), &token));
+
+        let mut messages = ToolProgressMessages::new(Duration::from_secs(15));
+        let start = Instant::now();
+        assert_eq!(
+            messages.message_at(
+                "call-1",
+                "dev__build",
+                &progress(&token, 3.0, Some("linking")),
+                start
+            ),
+            Some("dev__build is still running (30%): linking".to_string())
+        );
+        let soon = start + Duration::from_secs(5);
+        assert_eq!(
+            messages.message_at("call-1", "dev__build", &progress(&token, 4.0, None), soon),
+            None
+        );
+        assert!(messages
+            .message_at("call-2", "dev__test", &progress("t", 1.0, None), soon)
+            .is_some());
+        let later = start + Duration::from_secs(20);
+        assert_eq!(
+            messages.message_at("call-1", "dev__build", &progress(&token, 6.0, None), later),
+            Some("dev__build is still running (60%)".to_string())
+        );
+    }
+}
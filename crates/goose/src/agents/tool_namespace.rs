@@ -0,0 +1,269 @@
+//! Names under which extension tools are offered to the model.
+//!
+//! Every extension tool is offered as `extension__tool`. Names can still collide, as with an
+//! extension called `git` offering `hub__status` and one called `git__hub` offering `status`, or
+//! an extension listing a tool twice. The tool offered under a contested name is the one from the
+//! extension that comes first in GOOSE_TOOL_PRECEDENCE, a list of extension names, with
+//! extensions not listed coming after in alphabetical order. Collisions are reported rather than
+//! resolved silently. GOOSE_TOOL_ALIASES maps extra names to tools, such as `shell` to
+//! `developer__shell`. An alias never replaces a tool of the same name.
+
+use std::collections::{BTreeMap, HashMap};
+
+use rmcp::model::Tool;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::config::Config;
+
+pub const TOOL_PRECEDENCE_CONFIG_KEY: &str = "GOOSE_TOOL_PRECEDENCE";
+pub const TOOL_ALIASES_CONFIG_KEY: &str = "GOOSE_TOOL_ALIASES";
+
+pub fn prefixed_name(extension: &str, tool: &str) -> String {
+    format!("{}__{}", extension, tool)
+}
+
+/// The tool of an extension that a name offered to the model stands for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolRoute {
+    pub extension: String,
+    pub tool: String,
+}
+
+/// Extensions offering tools under the same name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolCollision {
+    pub name: String,
+    /// The extensions offering the tool, the one whose tool is used first
+    pub extensions: Vec<String>,
+}
+
+impl ToolCollision {
+    pub fn message(&self) -> String {
+        format!(
+            "Extensions {} all offer a tool called {}, using the one from {}. Set {} to choose another.",
+            self.extensions.join(", "),
+            self.name,
+            self.extensions[0],
+            TOOL_PRECEDENCE_CONFIG_KEY
+        )
+    }
+}
+
+/// The tools of a set of extensions as offered to the model
+#[derive(Debug, Default)]
+pub struct ResolvedTools {
+    pub tools: Vec<Tool>,
+    pub routes: HashMap<String, ToolRoute>,
+    pub collisions: Vec<ToolCollision>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ToolNamespace {
+    precedence: Vec<String>,
+    aliases: BTreeMap<String, String>,
+}
+
+impl ToolNamespace {
+    pub fn new(precedence: Vec<String>, aliases: BTreeMap<String, String>) -> Self {
+        Self {
+            precedence,
+            aliases,
+        }
+    }
+
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        Self::new(
+            config
+                .get_param(TOOL_PRECEDENCE_CONFIG_KEY)
+                .unwrap_or_default(),
+            config
+                .get_param(TOOL_ALIASES_CONFIG_KEY)
+                .unwrap_or_default(),
+        )
+    }
+
+    /// The tool name an alias stands for
+    pub fn alias_target(&self, alias: &str) -> Option<&str> {
+        self.aliases.get(alias).map(String::as_str)
+    }
+
+    fn rank<'a>(&self, extension: &'a str) -> (usize, &'a str) {
+        let position = self
+            .precedence
+            .iter()
+            .position(|name| name == extension)
+            .unwrap_or(self.precedence.len());
+        (position, extension)
+    }
+
+    /// Name the tools listed by each extension, given unprefixed with the extension's name
+    pub fn resolve(&self, mut listed: Vec<(String, Tool)>) -> ResolvedTools {
+        listed.sort_by(|(a, _), (b, _)| self.rank(a).cmp(&self.rank(b)));
+
+        let mut resolved = ResolvedTools::default();
+        let mut contenders: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (extension, mut tool) in listed {
+            let name = prefixed_name(&extension, &tool.name);
+            let offering = contenders.entry(name.clone()).or_default();
+            if !offering.contains(&extension) {
+                offering.push(extension.clone());
+            }
+            if resolved.routes.contains_key(&name) {
+                continue;
+            }
+            resolved.routes.insert(
+                name.clone(),
+                ToolRoute {
+                    extension,
+                    tool: tool.name.to_string(),
+                },
+            );
+            tool.name = name.into();
+            resolved.tools.push(tool);
+        }
+        resolved.collisions = contenders
+            .into_iter()
+            .filter(|(_, extensions)| extensions.len() > 1)
+            .map(|(name, extensions)| ToolCollision { name, extensions })
+            .collect();
+
+        for (alias, target) in &self.aliases {
+            if resolved.routes.contains_key(alias) {
+                warn!(
+                    "Ignoring tool alias {}: a tool is already called that",
+                    alias
+                );
+                continue;
+            }
+            let Some(tool) = resolved.tools.iter().find(|tool| tool.name == *target) else {
+                continue;
+            };
+            let mut tool = tool.clone();
+            tool.description = Some(
+                match &tool.description {
+                    Some(description) => format!("{} (alias for {})", description, target),
+                    None => format!("Alias for {}", target),
+                }
+                .into(),
+            );
+            tool.name = alias.clone().into();
+            let route = resolved.routes[target].clone();
+            resolved.routes.insert(alias.clone(), route);
+            resolved.tools.push(tool);
+        }
+
+        resolved
+    }
+}
+
+/// The routes of the tools last offered to the model, and the collisions found among them
+#[derive(Debug, Default)]
+pub(crate) struct ToolIndex {
+    routes: HashMap<String, ToolRoute>,
+    collisions: Vec<ToolCollision>,
+    reported: Vec<ToolCollision>,
+}
+
+impl ToolIndex {
+    pub fn update(&mut self, resolved: &ResolvedTools) {
+        self.routes = resolved.routes.clone();
+        self.collisions = resolved.collisions.clone();
+    }
+
+    pub fn route(&self, name: &str) -> Option<ToolRoute> {
+        self.routes.get(name).cloned()
+    }
+
+    pub fn collisions(&self) -> Vec<ToolCollision> {
+        self.collisions.clone()
+    }
+
+    /// The collisions not reported yet, which count as reported from now on
+    pub fn take_unreported(&mut self) -> Vec<ToolCollision> {
+        let unreported = self
+            .collisions
+            .iter()
+            .filter(|collision| !self.reported.contains(collision))
+            .cloned()
+            .collect();
+        self.reported = self.collisions.clone();
+        unreported
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::object;
+
+    fn tool(name: &str, description: &str) -> Tool {
+        Tool::new(
+            name.to_string(),
+            description.to_string(),
+            object!({"type": "object"}),
+        )
+    }
+
+    fn listed() -> Vec<(String, Tool)> {
+        vec![
+            ("git__hub".to_string(), tool("status", "from git__hub")),
+            ("developer".to_string(), tool("shell", "Run a command")),
+            ("git".to_string(), tool("hub__status", "from git")),
+        ]
+    }
+
+    #[test]
+    fn test_collisions_follow_precedence() {
+        let resolved = ToolNamespace::default().resolve(listed());
+        let names: Vec<_> = resolved.tools.iter().map(|t| t.name.to_string()).collect();
+        assert_eq!(names, vec!["developer__shell", "git__hub__status"]);
+        assert_eq!(
+            resolved.routes["git__hub__status"],
+            ToolRoute {
+                extension: "git".to_string(),
+                tool: "hub__status".to_string(),
+            }
+        );
+        assert_eq!(
+            resolved.collisions,
+            vec![ToolCollision {
+                name: "git__hub__status".to_string(),
+                extensions: vec!["git".to_string(), "git__hub".to_string()],
+            }]
+        );
+
+        let preferred = ToolNamespace::new(vec!["git__hub".to_string()], BTreeMap::new());
+        let resolved = preferred.resolve(listed());
+        assert_eq!(resolved.routes["git__hub__status"].extension, "git__hub");
+        assert_eq!(resolved.collisions[0].extensions[0], "git__hub");
+
+        let mut index = ToolIndex::default();
+        index.update(&resolved);
+        assert_eq!(index.take_unreported().len(), 1);
+        assert!(index.take_unreported().is_empty());
+    }
+
+    #[test]
+    fn test_aliases() {
+        let aliases = BTreeMap::from([
+            ("shell".to_string(), "developer__shell".to_string()),
+            (
+                "developer__shell".to_string(),
+                "git__hub__status".to_string(),
+            ),
+            ("missing".to_string(), "nowhere__tool".to_string()),
+        ]);
+        let resolved = ToolNamespace::new(Vec::new(), aliases).resolve(listed());
+
+        let alias = resolved.tools.iter().find(|t| t.name == "shell").unwrap();
+        assert_eq!(
+            alias.description.as_deref(),
+            Some("Run a command (alias for developer__shell)")
+        );
+        assert_eq!(resolved.routes["shell"].tool, "shell");
+        assert_eq!(resolved.routes["developer__shell"].extension, "developer");
+        assert!(!resolved.routes.contains_key("missing"));
+    }
+}
@@ -0,0 +1,326 @@
+//! Per-extension tool policies.
+//!
+//! GOOSE_TOOL_POLICIES maps extension names, as they prefix their tools, to policies that narrow
+//! down what the model can do with the extension:
+//!
+//! ```yaml
+//! GOOSE_TOOL_POLICIES:
+//!   developer:
+//!     deny: ["screen_*"]
+//!     constraints:
+//!       shell:
+//!         command:
+//!           commands: ["git", "ls", "cargo test"]
+//! ```
+//!
+//! `allow` and `deny` list tool names, where `*` matches any run of characters. Tools that aren't
+//! allowed are neither offered to the model nor run when it asks for them anyway. `constraints`
+//! limit the values of tool arguments and are checked before every call.
+//!
+//! The `commands` constraint only looks at the command line as text, so it keeps a model on
+//! track rather than containing one that is trying to get out. Shells have more ways to run
+//! other programs than a text check can rule out; run the shell in a sandbox where that matters.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+use rmcp::model::JsonObject;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::Config;
+
+pub const TOOL_POLICIES_CONFIG_KEY: &str = "GOOSE_TOOL_POLICIES";
+
+/// Whether `name` matches `pattern`, in which `*` matches any run of characters
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.split_once(part) {
+            Some((_, after)) => rest = after,
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Shell syntax that would let a command run commands other than the ones it starts with.
+/// `$` covers variable expansion as well as substitution, since expanding something like `$IFS`
+/// changes where the words of the command line split.
+const SHELL_ESCAPES: &[&str] = &["`", "$", "<(", ">(", ">", "<"];
+
+/// Options through which an allowed command can be told to run something else, like
+/// `git -c core.pager=sh` or `cargo --config`
+const COMMAND_INJECTION_OPTIONS: &[&str] = &[
+    "-c",
+    "--config",
+    "--exec",
+    "--upload-pack",
+    "--receive-pack",
+];
+
+/// The option of `command` that could make it run something else, if it has one
+fn injection_option(command: &str) -> Option<&str> {
+    command.split_whitespace().skip(1).find(|word| {
+        let word = word.trim_matches(['"', '\'']);
+        COMMAND_INJECTION_OPTIONS.iter().any(|option| {
+            word.strip_prefix(option)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('='))
+        })
+    })
+}
+
+/// Limits on the value of one tool argument. An argument that isn't given is not checked.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArgumentConstraint {
+    /// The only values the argument may take
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub one_of: Option<Vec<Value>>,
+    /// A regular expression the whole of a string argument must match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// For shell command arguments, the commands each part of the command line has to start
+    /// with. Redirection, variable expansion, command substitution and options that make a
+    /// command run something else aren't allowed. This is a check on the text of the command
+    /// line, not a security boundary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commands: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+}
+
+impl ArgumentConstraint {
+    /// Why `value` breaks the constraint, if it does
+    pub fn violation(&self, value: &Value) -> Option<String> {
+        if let Some(values) = &self.one_of {
+            if !values.contains(value) {
+                return Some(format!(
+                    "must be one of {}",
+                    values
+                        .iter()
+                        .map(Value::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+        }
+
+        if let Some(pattern) = &self.pattern {
+            let Some(text) = value.as_str() else {
+                return Some("must be a string".to_string());
+            };
+            match Regex::new(&format!("^(?:{})$", pattern)) {
+                Ok(regex) if regex.is_match(text) => {}
+                Ok(_) => return Some(format!("must match {}", pattern)),
+                Err(e) => return Some(format!("can't be checked against {}: {}", pattern, e)),
+            }
+        }
+
+        if let Some(commands) = &self.commands {
+            let Some(command_line) = value.as_str() else {
+                return Some("must be a string".to_string());
+            };
+            if let Some(escape) = SHELL_ESCAPES.iter().find(|s| command_line.contains(**s)) {
+                return Some(format!("may not contain {}", escape));
+            }
+            let allowed = |command: &str| {
+                commands.iter().any(|allowed| {
+                    command.strip_prefix(allowed.as_str()).is_some_and(|rest| {
+                        rest.is_empty() || rest.starts_with(char::is_whitespace)
+                    })
+                })
+            };
+            if let Some(command) = command_line
+                .split([';', '&', '|', '\n'])
+                .map(str::trim)
+                .find(|command| !command.is_empty() && !allowed(command))
+            {
+                return Some(format!(
+                    "may only run {}, not `{}`",
+                    commands.join(", "),
+                    command
+                ));
+            }
+            if let Some(option) = command_line
+                .split([';', '&', '|', '\n'])
+                .find_map(injection_option)
+            {
+                return Some(format!("may not pass {}", option));
+            }
+        }
+
+        if self.min.is_some() || self.max.is_some() {
+            let Some(number) = value.as_f64() else {
+                return Some("must be a number".to_string());
+            };
+            if let Some(min) = self.min.filter(|min| number < *min) {
+                return Some(format!("must be at least {}", min));
+            }
+            if let Some(max) = self.max.filter(|max| number > *max) {
+                return Some(format!("must be at most {}", max));
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExtensionToolPolicy {
+    /// Tools the model may use, all of them if empty
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Tools the model may not use, even if allowed
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Constraints on arguments, by tool and argument name
+    #[serde(default)]
+    pub constraints: HashMap<String, HashMap<String, ArgumentConstraint>>,
+}
+
+impl ExtensionToolPolicy {
+    pub fn is_allowed(&self, tool_name: &str) -> bool {
+        (self.allow.is_empty()
+            || self
+                .allow
+                .iter()
+                .any(|pattern| matches_pattern(pattern, tool_name)))
+            && !self
+                .deny
+                .iter()
+                .any(|pattern| matches_pattern(pattern, tool_name))
+    }
+
+    /// Why a call of `tool_name` with `arguments` breaks the constraints, if it does
+    pub fn violation(&self, tool_name: &str, arguments: Option<&JsonObject>) -> Option<String> {
+        let constraints = self.constraints.get(tool_name)?;
+        let mut names: Vec<&String> = constraints.keys().collect();
+        names.sort();
+        names.into_iter().find_map(|name| {
+            let value = arguments?.get(name)?;
+            constraints[name]
+                .violation(value)
+                .map(|reason| format!("argument '{}' {}", name, reason))
+        })
+    }
+}
+
+/// The tool policies of all extensions
+#[derive(Debug, Clone, Default)]
+pub struct ToolPolicies(HashMap<String, ExtensionToolPolicy>);
+
+impl ToolPolicies {
+    pub fn new(policies: HashMap<String, ExtensionToolPolicy>) -> Self {
+        Self(policies)
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(
+            Config::global()
+                .get_param(TOOL_POLICIES_CONFIG_KEY)
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn is_allowed(&self, extension: &str, tool_name: &str) -> bool {
+        self.0
+            .get(extension)
+            .is_none_or(|policy| policy.is_allowed(tool_name))
+    }
+
+    pub fn violation(
+        &self,
+        extension: &str,
+        tool_name: &str,
+        arguments: Option<&JsonObject>,
+    ) -> Option<String> {
+        self.0.get(extension)?.violation(tool_name, arguments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::object;
+    use serde_json::json;
+
+    #[test]
+    fn test_allow_and_deny() {
+        assert!(matches_pattern("screen_*", "screen_capture"));
+        assert!(matches_pattern("*_file*", "read_file_range"));
+        assert!(!matches_pattern("screen_*", "shell"));
+        assert!(!matches_pattern("shell", "shell_exec"));
+
+        let policies = ToolPolicies::new(HashMap::from([(
+            "developer".to_string(),
+            ExtensionToolPolicy {
+                allow: vec!["shell".to_string(), "screen_*".to_string()],
+                deny: vec!["screen_capture".to_string()],
+                ..Default::default()
+            },
+        )]));
+        assert!(policies.is_allowed("developer", "shell"));
+        assert!(policies.is_allowed("developer", "screen_list"));
+        assert!(!policies.is_allowed("developer", "screen_capture"));
+        assert!(!policies.is_allowed("developer", "text_editor"));
+        assert!(policies.is_allowed("memory", "remember"));
+    }
+
+    #[test]
+    fn test_argument_constraints() {
+        let policy: ExtensionToolPolicy = serde_json::from_value(json!({
+            "constraints": {
+                "shell": {"command": {"commands": ["git", "ls", "cargo test"]}},
+                "text_editor": {
+                    "command": {"one_of": ["view"]},
+                    "path": {"pattern": "src/.*"},
+                },
+                "sleep": {"seconds": {"min": 1, "max": 60}},
+            }
+        }))
+        .unwrap();
+        let violation =
+            |tool: &str, arguments: JsonObject| policy.violation(tool, Some(&arguments));
+
+        assert!(violation("shell", object!({"command": "git status && ls -la"})).is_none());
+        assert!(violation("shell", object!({"command": "cargo test -p goose"})).is_none());
+        assert_eq!(
+            violation("shell", object!({"command": "git log; rm -rf /"})).unwrap(),
+            "argument 'command' may only run git, ls, cargo test, not `rm -rf /`"
+        );
+        assert!(violation("shell", object!({"command": "gitk"})).is_some());
+        assert!(violation("shell", object!({"command": "ls $(rm -rf /)"})).is_some());
+        assert!(violation("shell", object!({"command": "ls > ~/.bashrc"})).is_some());
+        assert!(violation("shell", object!({"command": "git log $IFS--output=/tmp/x"})).is_some());
+        assert_eq!(
+            violation("shell", object!({"command": "git -c core.pager=sh log"})).unwrap(),
+            "argument 'command' may not pass -c"
+        );
+        assert!(violation("shell", object!({"command": "cargo test --config=x.toml"})).is_some());
+        assert!(violation("shell", object!({"command": "ls && git '--exec=sh' x"})).is_some());
+        assert!(violation("shell", object!({"command": "ls -cl"})).is_none());
+
+        assert!(violation(
+            "text_editor",
+            object!({"command": "view", "path": "src/lib.rs"})
+        )
+        .is_none());
+        assert!(violation("text_editor", object!({"command": "write"})).is_some());
+        assert!(violation("text_editor", object!({"path": "/etc/passwd"})).is_some());
+
+        assert!(violation("sleep", object!({"seconds": 30})).is_none());
+        assert!(violation("sleep", object!({"seconds": 3600})).is_some());
+        assert!(violation("sleep", object!({})).is_none());
+        assert!(violation("other", object!({"seconds": 3600})).is_none());
+    }
+}
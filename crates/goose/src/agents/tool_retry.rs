@@ -0,0 +1,266 @@
+//! Guided retries of failed tool calls.
+//!
+//! When GOOSE_TOOL_RETRIES is set, a tool call that fails gets a hint appended to its result:
+//! what kind of error it looks like, what usually fixes it, and how many retries the model has
+//! left for that tool in the current reply. Once a tool has failed more times than it may be
+//! retried, the hint tells the model to stop calling it and explain the failure instead, and the
+//! user sees a notice. GOOSE_TOOL_RETRIES_PER_TOOL sets the number of retries for particular
+//! tools by name, with 0 turning the hints off for a tool.
+
+use std::collections::{HashMap, HashSet};
+
+use rmcp::model::{CallToolResult, Content, ErrorCode};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::mcp_utils::ToolResult;
+
+pub const TOOL_RETRIES_CONFIG_KEY: &str = "GOOSE_TOOL_RETRIES";
+pub const TOOL_RETRIES_PER_TOOL_CONFIG_KEY: &str = "GOOSE_TOOL_RETRIES_PER_TOOL";
+
+/// What kind of failure a tool error looks like
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    InvalidArguments,
+    NotFound,
+    PermissionDenied,
+    Timeout,
+    Network,
+    Other,
+}
+
+impl ErrorCategory {
+    /// Guess the category from an error's code and text
+    pub fn classify(code: Option<ErrorCode>, text: &str) -> Self {
+        if code == Some(ErrorCode::INVALID_PARAMS) {
+            return Self::InvalidArguments;
+        }
+        let text = text.to_lowercase();
+        let mentions = |needles: &[&str]| needles.iter().any(|needle| text.contains(needle));
+        if mentions(&[
+            "invalid argument",
+            "invalid param",
+            "missing required",
+            "missing argument",
+            "unexpected argument",
+            "unknown field",
+            "invalid type",
+        ]) {
+            Self::InvalidArguments
+        } else if mentions(&[
+            "not found",
+            "no such file",
+            "does not exist",
+            "doesn't exist",
+        ]) {
+            Self::NotFound
+        } else if mentions(&[
+            "permission denied",
+            "access denied",
+            "not permitted",
+            "forbidden",
+            "unauthorized",
+        ]) {
+            Self::PermissionDenied
+        } else if mentions(&["timed out", "timeout", "deadline exceeded"]) {
+            Self::Timeout
+        } else if mentions(&[
+            "connection",
+            "network",
+            "dns",
+            "unreachable",
+            "rate limit",
+            "503",
+            "502",
+        ]) {
+            Self::Network
+        } else {
+            Self::Other
+        }
+    }
+
+    pub fn suggestion(&self) -> &'static str {
+        match self {
+            Self::InvalidArguments => {
+                "Check the tool's input schema and call it again with corrected arguments."
+            }
+            Self::NotFound => {
+                "Check the name or path; list or search for the right one before retrying."
+            }
+            Self::PermissionDenied => {
+                "Don't retry the same call; choose a location or approach you have access to, or \
+                 ask the user."
+            }
+            Self::Timeout => "Retry with a smaller or faster operation, such as a narrower query.",
+            Self::Network => "The failure may be temporary; retry once, then try another approach.",
+            Self::Other => "Read the error, change what caused it, and try again.",
+        }
+    }
+}
+
+/// How many times failed calls may be retried: GOOSE_TOOL_RETRIES for any tool and
+/// GOOSE_TOOL_RETRIES_PER_TOOL for particular tools by name
+#[derive(Debug, Clone, Default)]
+pub struct ToolRetryPolicy {
+    pub default: u32,
+    pub per_tool: HashMap<String, u32>,
+}
+
+impl ToolRetryPolicy {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        Self {
+            default: config.get_param(TOOL_RETRIES_CONFIG_KEY).unwrap_or(0),
+            per_tool: config
+                .get_param(TOOL_RETRIES_PER_TOOL_CONFIG_KEY)
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn for_tool(&self, tool_name: &str) -> u32 {
+        self.per_tool
+            .get(tool_name)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// Counts the failures of each tool over one reply and adds retry hints to failed results
+#[derive(Debug, Default)]
+pub struct ToolRetryTracker {
+    policy: ToolRetryPolicy,
+    failures: HashMap<String, u32>,
+    given_up: HashSet<String>,
+    notices: Vec<String>,
+}
+
+impl ToolRetryTracker {
+    pub fn new(policy: ToolRetryPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(ToolRetryPolicy::from_config())
+    }
+
+    /// Count `result` against `tool_name`, appending a retry hint to it if it failed
+    pub fn record(&mut self, tool_name: &str, result: &mut ToolResult<CallToolResult>) {
+        let (code, text) = match &*result {
+            Ok(result) if result.is_error == Some(true) => (
+                None,
+                result
+                    .content
+                    .iter()
+                    .filter_map(|content| content.as_text().map(|text| text.text.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            Err(e) => (Some(e.code), e.message.to_string()),
+            Ok(_) => return,
+        };
+        let retries = self.policy.for_tool(tool_name);
+        if retries == 0 {
+            return;
+        }
+
+        let failures = self.failures.entry(tool_name.to_string()).or_default();
+        *failures += 1;
+        let failures = *failures;
+        let category = ErrorCategory::classify(code, &text);
+        let category_name = serde_json::to_value(category)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default();
+
+        let next_step = if failures <= retries {
+            format!(
+                "Retry {} of {}: fix the cause and call the tool again.",
+                failures, retries
+            )
+        } else {
+            if self.given_up.insert(tool_name.to_string()) {
+                self.notices.push(format!(
+                    "{} failed {} times, so goose stopped retrying it.",
+                    tool_name, failures
+                ));
+            }
+            format!(
+                "No retries left: {} has failed {} times in this reply. Don't call it again; \
+                 tell the user what failed and why.",
+                tool_name, failures
+            )
+        };
+        let hint = format!(
+            "[tool error]\ncategory: {}\nsuggestion: {}\n{}",
+            category_name,
+            category.suggestion(),
+            next_step
+        );
+
+        match result {
+            Ok(result) => result.content.push(Content::text(hint)),
+            Err(e) => e.message = format!("{}\n\n{}", e.message, hint).into(),
+        }
+    }
+
+    /// Notices for the user about tools the model was told to give up on, since last taken
+    pub fn take_notices(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.notices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::ErrorData;
+
+    fn hint(result: &ToolResult<CallToolResult>) -> String {
+        match result {
+            Ok(result) => result
+                .content
+                .last()
+                .unwrap()
+                .as_text()
+                .unwrap()
+                .text
+                .clone(),
+            Err(e) => e.message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_retry_hints_until_exhausted() {
+        let mut tracker = ToolRetryTracker::new(ToolRetryPolicy {
+            default: 1,
+            per_tool: HashMap::from([("developer__shell".to_string(), 0)]),
+        });
+
+        let mut missing = Ok(CallToolResult::error(vec![Content::text(
+            "No such file or directory: a.txt",
+        )]));
+        tracker.record("developer__text_editor", &mut missing);
+        let first = hint(&missing);
+        assert!(first.contains("category: not_found"));
+        assert!(first.contains("Retry 1 of 1"));
+        assert!(tracker.take_notices().is_empty());
+
+        let mut invalid = Err(ErrorData::new(ErrorCode::INVALID_PARAMS, "bad path", None));
+        tracker.record("developer__text_editor", &mut invalid);
+        let second = hint(&invalid);
+        assert!(second.starts_with("bad path\n\n[tool error]\ncategory: invalid_arguments"));
+        assert!(second.contains("No retries left"));
+        assert_eq!(tracker.take_notices().len(), 1);
+
+        let mut shell = Ok(CallToolResult::error(vec![Content::text("exit code 1")]));
+        tracker.record("developer__shell", &mut shell);
+        assert_eq!(shell.unwrap().content.len(), 1);
+
+        let mut success = Ok(CallToolResult::success(vec![Content::text("ok")]));
+        tracker.record("developer__text_editor", &mut success);
+        assert_eq!(success.unwrap().content.len(), 1);
+    }
+}
@@ -1,5 +1,11 @@
+use crate::agents::budget::SessionBudget;
+use crate::agents::reflection::ReflectionSettings;
 use crate::mcp_utils::ToolResult;
 use crate::providers::base::Provider;
+use crate::providers::context_policy::ContextPolicy;
+use crate::providers::lead_worker::LeadWorkerSettings;
+use crate::providers::model_router::ModelRouting;
+use crate::providers::moderation::ModerationSettings;
 use rmcp::model::{CallToolResult, Tool};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -92,4 +98,30 @@ pub struct SessionConfig {
     /// Retry configuration for automated validation and recovery
     #[serde(skip_serializing_if = "Option::is_none")]
     pub retry_config: Option<RetryConfig>,
+    /// Content moderation for this session, overriding GOOSE_MODERATION_PROVIDER
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moderation: Option<ModerationSettings>,
+    /// How requests that exceed the context window are handled, overriding GOOSE_CONTEXT_POLICY
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_policy: Option<ContextPolicy>,
+    /// Spending limits for this session, overriding the GOOSE_SESSION_MAX_* settings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<SessionBudget>,
+    /// Session whose budget this one spends, when it isn't this session, as for a subagent
+    /// spending its parent's. Its turns and usage are charged to that session too.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_session_id: Option<String>,
+    /// Models for tool-call turns and for answers, overriding GOOSE_ROUTER_TOOL_MODEL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_routing: Option<ModelRouting>,
+    /// A lead model for the first turns and after worker failures, overriding GOOSE_LEAD_MODEL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lead_worker: Option<LeadWorkerSettings>,
+    /// Post-reply critique by a reviewing model, overriding GOOSE_REFLECTION
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reflection: Option<ReflectionSettings>,
+    /// JSON schema the final answer of each reply must match. The agent collects the answer
+    /// with the final output tool and reports it as [`AgentEvent::FinalAnswer`](crate::agents::AgentEvent).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_answer_schema: Option<serde_json::Value>,
 }
@@ -0,0 +1,350 @@
+//! Extensions compiled to WebAssembly.
+//!
+//! A WASM extension is a WASI 0.2 command component, such as an MCP server built for the
+//! wasm32-wasip2 target, that speaks MCP over its stdin and stdout. Rather than spawning it as a
+//! process, goose runs it in-process with wasmtime, where it can reach nothing on the host that
+//! its [`WasmCapabilities`] don't grant: no files outside the granted directories, no network
+//! unless granted, and no environment variables beyond the extension's own.
+//!
+//! A component that computes doesn't hold up the tasks sharing its thread: it yields at every
+//! tick of the engine's epoch. One that computes for longer than the extension's timeout without
+//! stopping to wait for input is interrupted, since it couldn't answer a request in time anyway.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use tokio::io::{duplex, DuplexStream};
+use tracing::{debug, warn};
+use wasmtime::component::{Component, Linker};
+use wasmtime::{Config as EngineConfig, Engine, Store, UpdateDeadline};
+use wasmtime_wasi::bindings::Command;
+use wasmtime_wasi::pipe::{AsyncReadStream, AsyncWriteStream};
+use wasmtime_wasi::{
+    AsyncStdinStream, AsyncStdoutStream, DirPerms, FilePerms, ResourceTable, WasiCtx,
+    WasiCtxBuilder, WasiView,
+};
+
+use crate::agents::extension::{ExtensionError, ExtensionResult, WasmCapabilities};
+use crate::agents::mcp_client::McpClient;
+use crate::agents::sampling::SamplingHandler;
+use crate::agents::workspace_roots::WorkspaceRoots;
+
+/// Bytes buffered between goose and a component in each direction
+const PIPE_CAPACITY: usize = 64 * 1024;
+/// How often the engine's epoch advances
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+struct WasmState {
+    ctx: WasiCtx,
+    table: ResourceTable,
+}
+
+impl WasiView for WasmState {
+    fn table(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+
+    fn ctx(&mut self) -> &mut WasiCtx {
+        &mut self.ctx
+    }
+}
+
+fn setup_error(path: &str, error: impl std::fmt::Display) -> ExtensionError {
+    ExtensionError::SetupError(format!("failed to load WASM extension {}: {}", path, error))
+}
+
+/// The sandbox a component runs in, with `stdin` and `stdout` as its standard streams
+fn wasi_context(
+    capabilities: &WasmCapabilities,
+    envs: HashMap<String, String>,
+    stdin: DuplexStream,
+    stdout: DuplexStream,
+) -> anyhow::Result<WasiCtx> {
+    let mut builder = WasiCtxBuilder::new();
+    builder
+        .stdin(AsyncStdinStream::new(AsyncReadStream::new(stdin)))
+        .stdout(AsyncStdoutStream::new(AsyncWriteStream::new(
+            PIPE_CAPACITY,
+            stdout,
+        )))
+        .envs(&envs.into_iter().collect::<Vec<_>>());
+
+    for dir in &capabilities.read_dirs {
+        let dir = shellexpand::tilde(dir);
+        builder.preopened_dir(dir.as_ref(), dir.as_ref(), DirPerms::READ, FilePerms::READ)?;
+    }
+    for dir in &capabilities.write_dirs {
+        let dir = shellexpand::tilde(dir);
+        builder.preopened_dir(
+            dir.as_ref(),
+            dir.as_ref(),
+            DirPerms::all(),
+            FilePerms::all(),
+        )?;
+    }
+    if capabilities.network {
+        builder.inherit_network().allow_ip_name_lookup(true);
+    }
+
+    Ok(builder.build())
+}
+
+/// How long a component has been computing without stopping to wait for input. It is told about
+/// each epoch tick the component runs into; ticks that follow one another closely mean the
+/// component never stopped in between.
+struct BusyTimer {
+    limit: Duration,
+    last_tick: Option<Instant>,
+    busy: Duration,
+}
+
+impl BusyTimer {
+    fn new(limit: Duration) -> Self {
+        Self {
+            limit,
+            last_tick: None,
+            busy: Duration::ZERO,
+        }
+    }
+
+    /// Whether the component has now been busy for longer than the limit
+    fn tick(&mut self, now: Instant) -> bool {
+        match self.last_tick {
+            Some(last) if now.duration_since(last) <= EPOCH_TICK * 3 => {
+                self.busy += now.duration_since(last);
+            }
+            _ => self.busy = Duration::ZERO,
+        }
+        self.last_tick = Some(now);
+        self.busy > self.limit
+    }
+}
+
+/// A component instantiated in its sandbox, ready to run
+struct WasmComponent {
+    engine: Engine,
+    store: Store<WasmState>,
+    command: Command,
+}
+
+impl WasmComponent {
+    /// Instantiate the component at `path`, to be interrupted if it computes for longer than
+    /// `busy_limit` without waiting
+    async fn load(path: &str, ctx: WasiCtx, busy_limit: Duration) -> ExtensionResult<Self> {
+        let mut engine_config = EngineConfig::new();
+        engine_config
+            .async_support(true)
+            .wasm_component_model(true)
+            .epoch_interruption(true);
+        let engine = Engine::new(&engine_config).map_err(|e| setup_error(path, e))?;
+
+        let component_path = shellexpand::tilde(path).to_string();
+        let component = tokio::task::spawn_blocking({
+            let engine = engine.clone();
+            move || Component::from_file(&engine, component_path)
+        })
+        .await?
+        .map_err(|e| setup_error(path, e))?;
+
+        let mut linker = Linker::<WasmState>::new(&engine);
+        wasmtime_wasi::add_to_linker_async(&mut linker).map_err(|e| setup_error(path, e))?;
+        let mut store = Store::new(
+            &engine,
+            WasmState {
+                ctx,
+                table: ResourceTable::new(),
+            },
+        );
+        let mut timer = BusyTimer::new(busy_limit);
+        store.set_epoch_deadline(1);
+        store.epoch_deadline_callback(move |_| {
+            if timer.tick(Instant::now()) {
+                return Err(anyhow!(
+                    "computed for more than {:?} without waiting for input",
+                    busy_limit
+                ));
+            }
+            Ok(UpdateDeadline::Yield(1))
+        });
+        let command = Command::instantiate_async(&mut store, &component, &linker)
+            .await
+            .map_err(|e| setup_error(path, e))?;
+
+        Ok(Self {
+            engine,
+            store,
+            command,
+        })
+    }
+
+    /// Run the component until it exits, advancing the epoch as it goes
+    async fn run(self) -> anyhow::Result<Result<(), ()>> {
+        let Self {
+            engine,
+            mut store,
+            command,
+        } = self;
+        let ticker = async move {
+            let mut interval = tokio::time::interval(EPOCH_TICK);
+            loop {
+                interval.tick().await;
+                engine.increment_epoch();
+            }
+        };
+        tokio::select! {
+            result = command.wasi_cli_run().call_run(&mut store) => result,
+            _ = ticker => unreachable!("the epoch ticker runs until the component exits"),
+        }
+    }
+}
+
+/// Start the component at `path` and connect to it
+pub async fn wasm_client(
+    path: &str,
+    capabilities: &WasmCapabilities,
+    envs: HashMap<String, String>,
+    timeout: &Option<u64>,
+    sampling: SamplingHandler,
+    roots: WorkspaceRoots,
+) -> ExtensionResult<McpClient> {
+    let timeout = Duration::from_secs(timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT));
+    let (to_component, component_stdin) = duplex(PIPE_CAPACITY);
+    let (component_stdout, from_component) = duplex(PIPE_CAPACITY);
+    let ctx = wasi_context(capabilities, envs, component_stdin, component_stdout)
+        .map_err(|e| setup_error(path, e))?;
+    let component = WasmComponent::load(path, ctx, timeout).await?;
+
+    // The component runs until its stdin closes, which happens when the client is dropped
+    let name = path.to_string();
+    tokio::spawn(async move {
+        match component.run().await {
+            Ok(Ok(())) => debug!("WASM extension {} exited", name),
+            Ok(Err(())) => warn!("WASM extension {} exited with an error", name),
+            Err(e) => warn!("WASM extension {} trapped: {}", name, e),
+        }
+    });
+
+    Ok(McpClient::connect((from_component, to_component), timeout, sampling, roots).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A command component whose `run` is `body`, a core function returning 0 for success
+    fn command_wat(body: &str) -> String {
+        format!(
+            r#"(component
+                (core module $main
+                    (func (export "run") (result i32) {body}))
+                (core instance $main (instantiate $main))
+                (alias core export $main "run" (core func $run-core))
+                (func $run (result (result)) (canon lift (core func $run-core)))
+                (instance $run-instance (export "run" (func $run)))
+                (export "wasi:cli/run@0.2.0" (instance $run-instance)))"#
+        )
+    }
+
+    /// Succeeds only if the component sees exactly one environment variable
+    const ONE_VARIABLE_WAT: &str = r#"(component
+        (import "wasi:cli/environment@0.2.0" (instance $environment
+            (export "get-environment" (func (result (list (tuple string string)))))))
+        (alias export $environment "get-environment" (func $get-environment))
+        (core module $libc
+            (memory (export "memory") 1)
+            (global $heap (mut i32) (i32.const 1024))
+            (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr
+                    (i32.and
+                        (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+                        (i32.sub (i32.const 0) (local.get 2))))
+                (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+                (local.get $ptr)))
+        (core instance $libc (instantiate $libc))
+        (alias core export $libc "memory" (core memory $memory))
+        (alias core export $libc "realloc" (core func $realloc))
+        (core func $get-environment-core
+            (canon lower (func $get-environment) (memory $memory) (realloc $realloc)))
+        (core instance $environment-core (export "get-environment" (func $get-environment-core)))
+        (core module $main
+            (import "libc" "memory" (memory 1))
+            (import "environment" "get-environment" (func $get-environment (param i32)))
+            (func (export "run") (result i32)
+                (call $get-environment (i32.const 0))
+                (i32.ne (i32.load offset=4 (i32.const 0)) (i32.const 1))))
+        (core instance $main (instantiate $main
+            (with "libc" (instance $libc))
+            (with "environment" (instance $environment-core))))
+        (alias core export $main "run" (core func $run-core))
+        (func $run (result (result)) (canon lift (core func $run-core)))
+        (instance $run-instance (export "run" (func $run)))
+        (export "wasi:cli/run@0.2.0" (instance $run-instance)))"#;
+
+    async fn run_component(
+        wat: &str,
+        envs: HashMap<String, String>,
+        busy_limit: Duration,
+    ) -> anyhow::Result<Result<(), ()>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("component.wat");
+        std::fs::write(&path, wat)?;
+        let (_to_component, component_stdin) = duplex(PIPE_CAPACITY);
+        let (component_stdout, _from_component) = duplex(PIPE_CAPACITY);
+        let ctx = wasi_context(
+            &WasmCapabilities::default(),
+            envs,
+            component_stdin,
+            component_stdout,
+        )?;
+        let component = WasmComponent::load(path.to_str().unwrap(), ctx, busy_limit).await?;
+        component.run().await
+    }
+
+    #[tokio::test]
+    async fn test_component_loads_and_runs() {
+        let result = run_component(
+            &command_wat("(i32.const 0)"),
+            HashMap::new(),
+            Duration::from_secs(5),
+        )
+        .await;
+        assert_eq!(result.unwrap(), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_component_only_sees_its_own_environment() {
+        // The host has variables of its own, like PATH, that the component isn't granted
+        assert!(std::env::vars().count() > 0);
+        let envs = HashMap::from([("GRANTED".to_string(), "yes".to_string())]);
+        let result = run_component(ONE_VARIABLE_WAT, envs, Duration::from_secs(5)).await;
+        assert_eq!(result.unwrap(), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_looping_component_is_interrupted() {
+        let looping = command_wat("(loop $spin (br $spin)) unreachable");
+        let result = tokio::time::timeout(
+            Duration::from_secs(10),
+            run_component(&looping, HashMap::new(), Duration::from_millis(200)),
+        )
+        .await
+        .expect("the component should have been interrupted");
+        let error = result.unwrap_err();
+        assert!(format!("{:?}", error).contains("without waiting for input"));
+    }
+
+    #[test]
+    fn test_busy_timer_resets_after_waiting() {
+        let mut timer = BusyTimer::new(Duration::from_millis(25));
+        let start = Instant::now();
+        assert!(!timer.tick(start));
+        assert!(!timer.tick(start + EPOCH_TICK));
+        assert!(!timer.tick(start + EPOCH_TICK * 2));
+        // A long gap means the component waited for input in between
+        assert!(!timer.tick(start + Duration::from_secs(1)));
+        assert!(!timer.tick(start + Duration::from_secs(1) + EPOCH_TICK * 2));
+        assert!(timer.tick(start + Duration::from_secs(1) + EPOCH_TICK * 3));
+    }
+}
@@ -0,0 +1,191 @@
+//! Workspace roots of a session.
+//!
+//! The roots are the directories a session works in: its working directory and any listed in
+//! GOOSE_WORKSPACE_ROOTS. Extensions learn them through the MCP roots capability and are
+//! notified when they change. With GOOSE_ENFORCE_WORKSPACE_ROOTS enabled, a tool call is
+//! rejected when one of its path arguments points outside the roots. Path arguments are found
+//! by name, so paths inside free-form arguments such as shell commands aren't checked.
+
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use rmcp::model::{JsonObject, Root};
+use serde_json::Value;
+
+use crate::config::Config;
+
+pub const WORKSPACE_ROOTS_CONFIG_KEY: &str = "GOOSE_WORKSPACE_ROOTS";
+pub const ENFORCE_WORKSPACE_ROOTS_CONFIG_KEY: &str = "GOOSE_ENFORCE_WORKSPACE_ROOTS";
+
+/// Whether tool calls are kept within the workspace roots, set with GOOSE_ENFORCE_WORKSPACE_ROOTS
+pub fn enforce_workspace_roots() -> bool {
+    Config::global()
+        .get_param(ENFORCE_WORKSPACE_ROOTS_CONFIG_KEY)
+        .unwrap_or(false)
+}
+
+/// The roots of a session working in `working_dir`
+pub fn session_roots(working_dir: &Path) -> Vec<PathBuf> {
+    let configured: Vec<String> = Config::global()
+        .get_param(WORKSPACE_ROOTS_CONFIG_KEY)
+        .unwrap_or_default();
+    let mut roots = vec![normalize_path(working_dir)];
+    for root in configured {
+        let root = normalize_path(&working_dir.join(shellexpand::tilde(&root).as_ref()));
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+/// Whether a tool argument called `name` holds paths
+fn is_path_argument(name: &str) -> bool {
+    let name = name.to_lowercase();
+    matches!(
+        name.as_str(),
+        "file" | "files" | "filename" | "dir" | "directory" | "cwd" | "source" | "destination"
+    ) || ["path", "paths", "_dir", "_directory", "_file"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+}
+
+/// `path` with `.` and `..` resolved and, as far as it exists, symlinks followed
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut lexical = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                lexical.pop();
+            }
+            other => lexical.push(other),
+        }
+    }
+
+    let mut existing = lexical.as_path();
+    let mut rest: Vec<OsString> = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return rest
+                .iter()
+                .rev()
+                .fold(canonical, |path, name| path.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => break,
+        }
+    }
+    lexical
+}
+
+/// The directories a session works in, shared with the clients of its extensions
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceRoots(Arc<RwLock<Vec<PathBuf>>>);
+
+impl WorkspaceRoots {
+    pub fn get(&self) -> Vec<PathBuf> {
+        self.0.read().unwrap().clone()
+    }
+
+    /// Replace the roots, returning whether they changed
+    pub fn set(&self, roots: Vec<PathBuf>) -> bool {
+        let mut current = self.0.write().unwrap();
+        if *current == roots {
+            return false;
+        }
+        *current = roots;
+        true
+    }
+
+    /// The roots as MCP roots
+    pub fn to_mcp_roots(&self) -> Vec<Root> {
+        self.get()
+            .into_iter()
+            .filter_map(|path| {
+                let uri = url::Url::from_file_path(&path).ok()?;
+                Some(Root {
+                    uri: uri.to_string(),
+                    name: path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string()),
+                })
+            })
+            .collect()
+    }
+
+    /// The first path in `arguments` that is outside the roots. Relative paths are resolved
+    /// against the first root. Without roots, nothing is outside them.
+    pub fn path_outside(&self, arguments: &JsonObject) -> Option<PathBuf> {
+        let roots = self.get();
+        let base = roots.first()?;
+        let mut paths = Vec::new();
+        collect_paths(arguments, &mut paths);
+        paths
+            .into_iter()
+            .map(|value| {
+                let path = match url::Url::parse(value) {
+                    Ok(url) if url.scheme() == "file" => url.to_file_path().unwrap_or_default(),
+                    _ => PathBuf::from(shellexpand::tilde(value).as_ref()),
+                };
+                normalize_path(&base.join(path))
+            })
+            .find(|path| !roots.iter().any(|root| path.starts_with(root)))
+    }
+}
+
+/// Collect the values of the path arguments in `arguments`, looking into nested ones
+fn collect_paths<'a>(arguments: &'a JsonObject, paths: &mut Vec<&'a str>) {
+    for (name, value) in arguments {
+        let is_path = is_path_argument(name);
+        let mut visit = |value: &'a Value| match value {
+            Value::String(path) if is_path && !path.is_empty() => paths.push(path),
+            Value::Object(object) => collect_paths(object, paths),
+            _ => {}
+        };
+        match value {
+            Value::Array(items) => items.iter().for_each(&mut visit),
+            value => visit(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::object;
+
+    #[test]
+    fn test_path_outside_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("workspace");
+        let shared = dir.path().join("shared");
+        std::fs::create_dir_all(workspace.join("src")).unwrap();
+        std::fs::create_dir_all(&shared).unwrap();
+
+        let roots = WorkspaceRoots::default();
+        assert!(roots
+            .path_outside(&object!({"path": "/etc/passwd"}))
+            .is_none());
+        assert!(roots.set(vec![normalize_path(&workspace), normalize_path(&shared)]));
+        assert!(!roots.set(roots.get()));
+        assert_eq!(roots.to_mcp_roots()[0].name.as_deref(), Some("workspace"));
+
+        let outside = |arguments: JsonObject| roots.path_outside(&arguments);
+        assert!(outside(object!({"path": "src/main.rs"})).is_none());
+        assert!(outside(object!({"path": "src/new/file.rs"})).is_none());
+        assert!(outside(object!({"path": shared.join("notes.md").to_str().unwrap()})).is_none());
+        assert!(outside(object!({"command": "cat /etc/passwd"})).is_none());
+
+        assert!(outside(object!({"path": "../elsewhere"})).is_some());
+        assert!(outside(object!({"file_path": "/etc/passwd"})).is_some());
+        assert!(outside(object!({"paths": ["src", "/etc"]})).is_some());
+        assert!(outside(object!({"edits": [{"path": "/etc/hosts"}]})).is_some());
+        assert!(outside(object!({"path": "file:///etc/hosts"})).is_some());
+    }
+}
@@ -0,0 +1,287 @@
+//! Append-only audit log of provider requests and tool calls.
+//!
+//! When GOOSE_AUDIT_LOG is true, every provider request with its response, and every tool call
+//! with its arguments and result, is appended as one JSON line to `audit.jsonl`. Each record
+//! carries a timestamp and the session id, and is passed through
+//! [`redact_json`](crate::redaction::redact_json) before writing.
+//!
+//! - GOOSE_AUDIT_LOG_DIR: directory for the log (default `<state dir>/audit`)
+//! - GOOSE_AUDIT_LOG_MAX_BYTES: size at which `audit.jsonl` is rotated to
+//!   `audit.<timestamp>.jsonl` (default 50 MiB)
+//! - GOOSE_AUDIT_LOG_RETENTION_DAYS: days rotated files are kept, 0 to keep them forever
+//!   (default 90)
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::paths::Paths;
+use crate::config::Config;
+use crate::providers::base::Usage;
+use crate::redaction::redact_json;
+
+const AUDIT_FILE_NAME: &str = "audit.jsonl";
+const DEFAULT_MAX_BYTES: u64 = 50 * 1024 * 1024;
+const DEFAULT_RETENTION_DAYS: u64 = 90;
+
+static AUDIT_LOG: Lazy<Option<AuditLog>> = Lazy::new(|| {
+    AuditLogConfig::from_config().and_then(|config| match AuditLog::open(config) {
+        Ok(log) => Some(log),
+        Err(e) => {
+            tracing::error!("Failed to open audit log: {}", e);
+            None
+        }
+    })
+});
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditLogConfig {
+    pub directory: PathBuf,
+    pub max_bytes: u64,
+    pub retention: Option<Duration>,
+}
+
+impl AuditLogConfig {
+    /// The configured audit log, or None if GOOSE_AUDIT_LOG isn't enabled
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        if !config.get_param::<bool>("GOOSE_AUDIT_LOG").unwrap_or(false) {
+            return None;
+        }
+        let retention_days = config
+            .get_param::<u64>("GOOSE_AUDIT_LOG_RETENTION_DAYS")
+            .unwrap_or(DEFAULT_RETENTION_DAYS);
+        Some(Self {
+            directory: config
+                .get_param::<String>("GOOSE_AUDIT_LOG_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| Paths::in_state_dir("audit")),
+            max_bytes: config
+                .get_param("GOOSE_AUDIT_LOG_MAX_BYTES")
+                .unwrap_or(DEFAULT_MAX_BYTES),
+            retention: (retention_days > 0)
+                .then(|| Duration::from_secs(retention_days * 24 * 60 * 60)),
+        })
+    }
+}
+
+/// What happened, in an [`AuditRecord`]
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditEvent {
+    ProviderRequest {
+        request_id: String,
+        model: String,
+        started_at: DateTime<Utc>,
+        request: Value,
+        response: Vec<Value>,
+        usage: Option<Usage>,
+        error: Option<String>,
+    },
+    ToolCall {
+        request_id: String,
+        tool: String,
+        arguments: Value,
+        success: bool,
+        result: Value,
+    },
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditRecord {
+    pub timestamp: DateTime<Utc>,
+    pub session_id: Option<String>,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+impl AuditRecord {
+    /// A record of `event` in `session_id`, timestamped now
+    pub fn new(session_id: Option<String>, event: AuditEvent) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            session_id,
+            event,
+        }
+    }
+}
+
+pub struct AuditLog {
+    config: AuditLogConfig,
+    file: Mutex<Option<File>>,
+}
+
+impl AuditLog {
+    pub fn open(config: AuditLogConfig) -> Result<Self> {
+        fs::create_dir_all(&config.directory)?;
+        let log = Self {
+            config,
+            file: Mutex::new(None),
+        };
+        log.prune()?;
+        Ok(log)
+    }
+
+    fn current_path(&self) -> PathBuf {
+        self.config.directory.join(AUDIT_FILE_NAME)
+    }
+
+    /// Append `record`, rotating the file first if it would grow past the size limit
+    pub fn append(&self, record: &AuditRecord) -> Result<()> {
+        let mut line = serde_json::to_value(record)?;
+        redact_json(&mut line);
+        let mut line = serde_json::to_string(&line)?;
+        line.push('\n');
+
+        let mut file = self.file.lock().unwrap();
+        let path = self.current_path();
+        let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if size > 0 && size + line.len() as u64 > self.config.max_bytes {
+            *file = None;
+            let rotated = self.config.directory.join(format!(
+                "audit.{}.jsonl",
+                Utc::now().format("%Y%m%dT%H%M%S%.6fZ")
+            ));
+            fs::rename(&path, rotated)?;
+            self.prune()?;
+        }
+
+        if file.is_none() {
+            *file = Some(File::options().create(true).append(true).open(&path)?);
+        }
+        let writer = file.as_mut().expect("audit log file is open");
+        writer.write_all(line.as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Delete rotated files older than the retention period
+    fn prune(&self) -> Result<()> {
+        let Some(retention) = self.config.retention else {
+            return Ok(());
+        };
+        let Some(cutoff) = SystemTime::now().checked_sub(retention) else {
+            return Ok(());
+        };
+        for entry in fs::read_dir(&self.config.directory)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name == AUDIT_FILE_NAME || !name.starts_with("audit.") || !name.ends_with(".jsonl") {
+                continue;
+            }
+            let modified = entry.metadata()?.modified()?;
+            if modified < cutoff {
+                fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether audit logging is enabled, so callers can skip building records
+pub fn enabled() -> bool {
+    AUDIT_LOG.is_some()
+}
+
+/// Append a record to the configured audit log, if any. Failures are logged, not returned,
+/// so auditing never interrupts a request.
+pub fn record(session_id: Option<String>, event: AuditEvent) {
+    if let Some(log) = AUDIT_LOG.as_ref() {
+        if let Err(e) = log.append(&AuditRecord::new(session_id, event)) {
+            tracing::error!("Failed to write audit record: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redaction::REDACTED;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn tool_call_event() -> AuditEvent {
+        AuditEvent::ToolCall {
+            request_id: "call_1".to_string(),
+            tool: "developer__shell".to_string(),
+            arguments: json!({"command": "ls", "env": {"GITHUB_TOKEN": "ghp_secret"}}),
+            success: true,
+            result: json!([{"type": "text", "text": "README.md"}]),
+        }
+    }
+
+    #[test]
+    fn test_append_redacts_secrets() {
+        let dir = TempDir::new().unwrap();
+        let log = AuditLog::open(AuditLogConfig {
+            directory: dir.path().to_path_buf(),
+            max_bytes: DEFAULT_MAX_BYTES,
+            retention: None,
+        })
+        .unwrap();
+        log.append(&AuditRecord::new(Some("s1".to_string()), tool_call_event()))
+            .unwrap();
+        log.append(&AuditRecord::new(
+            None,
+            AuditEvent::ProviderRequest {
+                request_id: "req_1".to_string(),
+                model: "gpt-4o".to_string(),
+                started_at: Utc::now(),
+                request: json!({"model": "gpt-4o", "api_key": "sk-secret"}),
+                response: vec![json!({"content": "hi"})],
+                usage: Some(Usage::new(Some(1), Some(2), Some(3))),
+                error: None,
+            },
+        ))
+        .unwrap();
+
+        let contents = fs::read_to_string(dir.path().join(AUDIT_FILE_NAME)).unwrap();
+        let lines: Vec<Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["type"], "tool_call");
+        assert_eq!(lines[0]["session_id"], "s1");
+        assert_eq!(lines[0]["arguments"]["command"], "ls");
+        assert_eq!(lines[0]["arguments"]["env"]["GITHUB_TOKEN"], REDACTED);
+        assert_eq!(lines[1]["type"], "provider_request");
+        assert_eq!(lines[1]["request"]["api_key"], REDACTED);
+        assert_eq!(lines[1]["usage"]["total_tokens"], 3);
+        assert!(!contents.contains("secret"));
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = TempDir::new().unwrap();
+        let log = AuditLog::open(AuditLogConfig {
+            directory: dir.path().to_path_buf(),
+            max_bytes: 200,
+            retention: Some(Duration::from_secs(3600)),
+        })
+        .unwrap();
+        for _ in 0..3 {
+            log.append(&AuditRecord::new(Some("s1".to_string()), tool_call_event()))
+                .unwrap();
+        }
+
+        let rotated = fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name() != AUDIT_FILE_NAME)
+            .count();
+        assert_eq!(rotated, 2);
+        let current = fs::read_to_string(dir.path().join(AUDIT_FILE_NAME)).unwrap();
+        assert_eq!(current.lines().count(), 1);
+    }
+}
@@ -0,0 +1,377 @@
+//! Searching the files of a workspace.
+//!
+//! Searches run in-process, so they behave the same everywhere without depending on grep or
+//! ripgrep being installed. Like ripgrep, they skip hidden files, binary files and whatever
+//! .gitignore and .ignore files rule out. Files are ranked so the most relevant come first:
+//! those whose path matches the pattern, then those with the most whole-word matches, then
+//! those closest to the root.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use regex::{Regex, RegexBuilder};
+
+/// Files larger than this are skipped, as they are rarely source code
+pub const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024;
+/// Most matches collected before the search stops looking
+pub const MAX_COLLECTED_MATCHES: usize = 5_000;
+/// Longest line shown in results, in characters
+const MAX_LINE_LENGTH: usize = 300;
+/// How much of a file is checked for NUL bytes to tell whether it is binary
+const BINARY_CHECK_LENGTH: usize = 8 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub pattern: String,
+    /// Match the pattern as plain text rather than as a regex
+    pub literal: bool,
+    /// Case sensitivity; if not given, the search ignores case unless the pattern has capitals
+    pub case_sensitive: Option<bool>,
+    /// Globs files must match, such as `*.rs`; those starting with `!` exclude files instead
+    pub globs: Vec<String>,
+    pub context_lines: usize,
+    /// Most matching lines to show
+    pub max_results: usize,
+    /// Most bytes of results to show
+    pub max_output_bytes: usize,
+}
+
+impl SearchOptions {
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            literal: false,
+            case_sensitive: None,
+            globs: Vec::new(),
+            context_lines: 0,
+            max_results: 50,
+            max_output_bytes: 20_000,
+        }
+    }
+
+    fn regex(&self) -> Result<Regex> {
+        let pattern = if self.literal {
+            regex::escape(&self.pattern)
+        } else {
+            self.pattern.clone()
+        };
+        let case_sensitive = self
+            .case_sensitive
+            .unwrap_or_else(|| self.pattern.chars().any(char::is_uppercase));
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map_err(|e| anyhow!("Invalid pattern: {}", e))
+    }
+}
+
+/// The matches in one file
+#[derive(Debug, Clone)]
+pub struct FileMatches {
+    /// Path of the file, relative to the search root
+    pub path: PathBuf,
+    /// 1-based numbers of the matching lines
+    pub line_numbers: Vec<usize>,
+    lines: Vec<String>,
+    score: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchResults {
+    /// Files with matches, most relevant first
+    pub files: Vec<FileMatches>,
+    pub total_matches: usize,
+    /// Whether the search stopped at [`MAX_COLLECTED_MATCHES`]
+    pub incomplete: bool,
+}
+
+fn is_binary(content: &[u8]) -> bool {
+    content[..content.len().min(BINARY_CHECK_LENGTH)].contains(&0)
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+fn is_whole_word(line: &str, start: usize, end: usize) -> bool {
+    let bytes = line.as_bytes();
+    (start == 0 || !is_word_byte(bytes[start - 1]))
+        && (end == bytes.len() || !is_word_byte(bytes[end]))
+}
+
+/// Search the files under `root` for `options.pattern`
+pub fn search(root: &Path, options: &SearchOptions) -> Result<SearchResults> {
+    let regex = options.regex()?;
+    let mut overrides = OverrideBuilder::new(root);
+    for glob in &options.globs {
+        overrides
+            .add(glob)
+            .map_err(|e| anyhow!("Invalid glob {}: {}", glob, e))?;
+    }
+    let walker = WalkBuilder::new(root)
+        .require_git(false)
+        .overrides(overrides.build()?)
+        .build();
+
+    let mut results = SearchResults::default();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                tracing::debug!("Skipping a path while searching: {}", e);
+                continue;
+            }
+        };
+        if !entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file())
+            || entry
+                .metadata()
+                .is_ok_and(|metadata| metadata.len() > MAX_FILE_SIZE)
+        {
+            continue;
+        }
+        let Ok(content) = std::fs::read(entry.path()) else {
+            continue;
+        };
+        if is_binary(&content) {
+            continue;
+        }
+        let content = String::from_utf8_lossy(&content);
+        let relative = entry
+            .path()
+            .strip_prefix(root)
+            .unwrap_or(entry.path())
+            .to_path_buf();
+
+        let mut line_numbers = Vec::new();
+        let mut whole_word_matches = 0;
+        for (i, line) in content.lines().enumerate() {
+            let mut matched = false;
+            for found in regex.find_iter(line) {
+                matched = true;
+                if is_whole_word(line, found.start(), found.end()) {
+                    whole_word_matches += 1;
+                }
+            }
+            if matched {
+                line_numbers.push(i + 1);
+            }
+        }
+        if line_numbers.is_empty() {
+            continue;
+        }
+
+        let path_match = regex.is_match(&relative.to_string_lossy());
+        let depth = relative.components().count();
+        let score = usize::from(path_match) * 1_000_000
+            + whole_word_matches * 1_000
+            + line_numbers.len() * 10
+            + 10usize.saturating_sub(depth);
+
+        results.total_matches += line_numbers.len();
+        results.files.push(FileMatches {
+            path: relative,
+            line_numbers,
+            lines: content.lines().map(String::from).collect(),
+            score,
+        });
+        if results.total_matches >= MAX_COLLECTED_MATCHES {
+            results.incomplete = true;
+            break;
+        }
+    }
+
+    results
+        .files
+        .sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    Ok(results)
+}
+
+fn shorten(line: &str) -> String {
+    if line.chars().count() <= MAX_LINE_LENGTH {
+        return line.to_string();
+    }
+    let mut short: String = line.chars().take(MAX_LINE_LENGTH).collect();
+    short.push_str(" …");
+    short
+}
+
+impl SearchResults {
+    /// The results as text, like ripgrep's: matching lines are marked with `:` and context
+    /// lines with `-`, with `--` between separate parts of a file
+    pub fn format(&self, options: &SearchOptions) -> String {
+        if self.files.is_empty() {
+            return format!("No matches for {}", options.pattern);
+        }
+
+        let mut output = String::new();
+        let mut shown = 0;
+        let mut files_shown = 0;
+        let mut cut_off = false;
+        for file in &self.files {
+            let mut file_output = format!("{}\n", file.path.display());
+            let mut last_shown: Option<usize> = None;
+            for &line_number in &file.line_numbers {
+                if shown >= options.max_results {
+                    break;
+                }
+                let first = line_number
+                    .saturating_sub(options.context_lines)
+                    .max(1)
+                    .max(last_shown.map_or(1, |last| last + 1));
+                let last = (line_number + options.context_lines).min(file.lines.len());
+                if last_shown.is_some_and(|shown| first > shown + 1) {
+                    file_output.push_str("--\n");
+                }
+                for number in first..=last {
+                    let separator = if file.line_numbers.binary_search(&number).is_ok() {
+                        ':'
+                    } else {
+                        '-'
+                    };
+                    let _ = writeln!(
+                        file_output,
+                        "{}{}{}",
+                        number,
+                        separator,
+                        shorten(&file.lines[number - 1])
+                    );
+                }
+                last_shown = Some(last.max(last_shown.unwrap_or(0)));
+                shown += 1;
+            }
+
+            if output.len() + file_output.len() > options.max_output_bytes {
+                // Keep what fits of the file, so one large file doesn't hide everything
+                for line in file_output.lines() {
+                    if output.len() + line.len() + 1 > options.max_output_bytes {
+                        break;
+                    }
+                    output.push_str(line);
+                    output.push('\n');
+                }
+                output.push('\n');
+                files_shown += 1;
+                cut_off = true;
+                break;
+            }
+            output.push_str(&file_output);
+            output.push('\n');
+            files_shown += 1;
+            if shown >= options.max_results {
+                cut_off = shown < self.total_matches;
+                break;
+            }
+        }
+
+        let _ = write!(
+            output,
+            "{} matching lines in {} files{}",
+            self.total_matches,
+            self.files.len(),
+            if self.incomplete {
+                " (the search stopped early; narrow it with a more specific pattern or globs)"
+            } else {
+                ""
+            }
+        );
+        if cut_off {
+            let _ = write!(
+                output,
+                "\nShowing {} of the files; narrow the search or raise max_results to see more",
+                files_shown
+            );
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn workspace() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        fs::create_dir_all(dir.path().join("target")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        fs::write(
+            dir.path().join("src/config.rs"),
+            "use std::fs;\n\nfn load_config() {}\nfn config() {\n    load_config();\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("src/nested/reconfigure.rs"),
+            "// reconfigured\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("src/notes.md"), "config notes\n").unwrap();
+        fs::write(dir.path().join("target/config.rs"), "fn config() {}\n").unwrap();
+        fs::write(dir.path().join("src/data.bin"), b"config\0\x01").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_search_ranks_and_filters() {
+        let dir = workspace();
+        let results = search(dir.path(), &SearchOptions::new("config")).unwrap();
+        let paths: Vec<_> = results.files.iter().map(|file| file.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("src/config.rs"),
+                PathBuf::from("src/nested/reconfigure.rs"),
+                PathBuf::from("src/notes.md"),
+            ]
+        );
+        assert_eq!(results.files[0].line_numbers, vec![3, 4, 5]);
+
+        let options = SearchOptions {
+            globs: vec!["*.rs".to_string(), "!**/nested/**".to_string()],
+            ..SearchOptions::new("config")
+        };
+        let results = search(dir.path(), &options).unwrap();
+        assert_eq!(results.files.len(), 1);
+        assert_eq!(results.files[0].path, PathBuf::from("src/config.rs"));
+
+        assert_eq!(
+            search(dir.path(), &SearchOptions::new("LOAD_config"))
+                .unwrap()
+                .total_matches,
+            0
+        );
+        let options = SearchOptions {
+            case_sensitive: Some(false),
+            ..SearchOptions::new("LOAD_config")
+        };
+        assert_eq!(search(dir.path(), &options).unwrap().total_matches, 2);
+
+        let options = SearchOptions {
+            literal: true,
+            ..SearchOptions::new("load_config()")
+        };
+        assert_eq!(search(dir.path(), &options).unwrap().total_matches, 2);
+        assert!(search(dir.path(), &SearchOptions::new("(")).is_err());
+    }
+
+    #[test]
+    fn test_format_shows_context() {
+        let dir = workspace();
+        let options = SearchOptions {
+            context_lines: 1,
+            globs: vec!["src/config.rs".to_string()],
+            ..SearchOptions::new(r"load_config\(")
+        };
+        let results = search(dir.path(), &options).unwrap();
+        assert_eq!(
+            results.format(&options),
+            "src/config.rs\n2-\n3:fn load_config() {}\n4-fn config() {\n5:    load_config();\n6-}\n\n2 matching lines in 1 files"
+        );
+    }
+}
@@ -1,4 +1,8 @@
+use crate::config::interpolation;
+use crate::config::migrations;
 use crate::config::paths::Paths;
+use crate::config::remote;
+use crate::config::secret_backends::{backend_from_config, SecretBackend};
 use crate::config::GooseMode;
 use fs2::FileExt;
 use keyring::Entry;
@@ -33,6 +37,10 @@ pub enum ConfigError {
     KeyringError(String),
     #[error("Failed to lock config file: {0}")]
     LockError(String),
+    #[error("Failed to resolve config value: {0}")]
+    InterpolationError(String),
+    #[error("Secret backend error: {0}")]
+    SecretBackendError(String),
 }
 
 impl From<serde_json::Error> for ConfigError {
@@ -62,10 +70,18 @@ impl From<keyring::Error> for ConfigError {
 /// - YAML-based configuration file storage
 /// - Hot reloading of configuration changes
 /// - Secure secret storage in system keyring
+/// - `${ENV_VAR}` references and `cmd:` values in the config file resolved when read (see
+///   [`interpolation`])
+///
+/// [`interpolation`]: crate::config::interpolation
 ///
 /// Configuration values are loaded with the following precedence:
-/// 1. Environment variables (exact key match)
-/// 2. Configuration file (~/.config/goose/config.yaml by default)
+/// 1. Settings enforced by the remote config (see [`remote`])
+/// 2. Environment variables (exact key match)
+/// 3. Configuration file (~/.config/goose/config.yaml by default)
+/// 4. Defaults from the remote config
+///
+/// [`remote`]: crate::config::remote
 ///
 /// Secrets are loaded with the following precedence:
 /// 1. Environment variables (exact key match)
@@ -73,6 +89,11 @@ impl From<keyring::Error> for ConfigError {
 /// 3. If the keyring is disabled, secrets are stored in a secrets file
 ///    (~/.config/goose/secrets.yaml by default)
 ///
+/// GOOSE_SECRET_BACKEND replaces the keyring or secrets file with another store, such as
+/// Vault (see [`secret_backends`]).
+///
+/// [`secret_backends`]: crate::config::secret_backends
+///
 /// # Examples
 ///
 /// ```no_run
@@ -106,8 +127,15 @@ pub struct Config {
 }
 
 enum SecretStorage {
-    Keyring { service: String },
-    File { path: PathBuf },
+    Keyring {
+        service: String,
+    },
+    File {
+        path: PathBuf,
+    },
+    Backend(Box<dyn SecretBackend>),
+    /// The configured backend couldn't be set up; using secrets fails with this error
+    Unavailable(String),
 }
 
 // Global instance
@@ -127,11 +155,20 @@ impl Default for Config {
                 service: KEYRING_SERVICE.to_string(),
             },
         };
-        Config {
+        let mut config = Config {
             config_path,
             secrets,
             guard: Mutex::new(()),
+        };
+        match backend_from_config(&config) {
+            Ok(Some(backend)) => config.secrets = SecretStorage::Backend(backend),
+            Ok(None) => {}
+            Err(e) => {
+                tracing::error!("Failed to set up the secret backend: {}", e);
+                config.secrets = SecretStorage::Unavailable(e.to_string());
+            }
         }
+        config
     }
 }
 
@@ -254,6 +291,18 @@ impl Config {
         })
     }
 
+    /// Create a new configuration instance that keeps its secrets in `backend`
+    pub fn new_with_secret_backend<P: AsRef<Path>>(
+        config_path: P,
+        backend: Box<dyn SecretBackend>,
+    ) -> Result<Self, ConfigError> {
+        Ok(Config {
+            config_path: config_path.as_ref().to_path_buf(),
+            secrets: SecretStorage::Backend(backend),
+            guard: Mutex::new(()),
+        })
+    }
+
     pub fn exists(&self) -> bool {
         self.config_path.exists()
     }
@@ -267,6 +316,10 @@ impl Config {
     }
 
     fn load(&self) -> Result<Mapping, ConfigError> {
+        self.load_unmigrated().map(|values| self.migrate(values))
+    }
+
+    fn load_unmigrated(&self) -> Result<Mapping, ConfigError> {
         if self.config_path.exists() {
             self.load_values_with_recovery()
         } else {
@@ -288,6 +341,37 @@ impl Config {
         }
     }
 
+    // Upgrade values in an old layout, keeping a copy of the file they came from
+    fn migrate(&self, values: Mapping) -> Mapping {
+        let Some(migrated) = migrations::migrate(&values) else {
+            return values;
+        };
+
+        if let Some(file_name) = self.config_path.file_name() {
+            let mut backup_name = file_name.to_os_string();
+            backup_name.push(format!(".v{}.bak", migrated.from));
+            let backup_path = self.config_path.with_file_name(backup_name);
+            if self.config_path.exists() && !backup_path.exists() {
+                if let Err(e) = std::fs::copy(&self.config_path, &backup_path) {
+                    // Don't replace the file without a copy to go back to
+                    tracing::warn!("Not migrating config as it couldn't be backed up: {}", e);
+                    return migrated.values;
+                }
+            }
+        }
+
+        match self.save_values(migrated.values.clone()) {
+            Ok(_) => tracing::info!(
+                "Migrated config from version {} to {}: {}",
+                migrated.from,
+                migrations::CURRENT_CONFIG_VERSION,
+                migrated.applied.join("; ")
+            ),
+            Err(e) => tracing::warn!("Failed to save migrated config: {}", e),
+        }
+        migrated.values
+    }
+
     pub fn all_values(&self) -> Result<HashMap<String, Value>, ConfigError> {
         self.load().map(|m| {
             HashMap::from_iter(m.into_iter().filter_map(|(k, v)| {
@@ -564,6 +648,10 @@ impl Config {
                     Ok(HashMap::new())
                 }
             }
+            SecretStorage::Backend(backend) => backend.load(),
+            SecretStorage::Unavailable(error) => {
+                Err(ConfigError::SecretBackendError(error.clone()))
+            }
         }
     }
 
@@ -624,8 +712,10 @@ impl Config {
     /// Get a configuration value (non-secret).
     ///
     /// This will attempt to get the value from:
-    /// 1. Environment variable with the exact key name
-    /// 2. Configuration file
+    /// 1. Value enforced by the remote config
+    /// 2. Environment variable with the exact key name
+    /// 3. Configuration file
+    /// 4. Default from the remote config
     ///
     /// The value will be deserialized into the requested type. This works with
     /// both simple types (String, i32, etc.) and complex types that implement
@@ -634,10 +724,14 @@ impl Config {
     /// # Errors
     ///
     /// Returns a ConfigError if:
-    /// - The key doesn't exist in either environment or config file
+    /// - The key isn't set in any of those places
     /// - The value cannot be deserialized into the requested type
     /// - There is an error reading the config file
     pub fn get_param<T: for<'de> Deserialize<'de>>(&self, key: &str) -> Result<T, ConfigError> {
+        if let Some(value) = remote::enforced_value(key) {
+            return Ok(serde_json::from_value(value)?);
+        }
+
         let env_key = key.to_uppercase();
         if let Ok(val) = env::var(&env_key) {
             let value = Self::parse_env_value(&val)?;
@@ -645,10 +739,13 @@ impl Config {
         }
 
         let values = self.load()?;
-        values
-            .get(key)
-            .ok_or_else(|| ConfigError::NotFound(key.to_string()))
-            .and_then(|v| Ok(serde_yaml::from_value(v.clone())?))
+        let Some(value) = values.get(key) else {
+            let value =
+                remote::default_value(key).ok_or_else(|| ConfigError::NotFound(key.to_string()))?;
+            return Ok(serde_json::from_value(value)?);
+        };
+        let value: Value = serde_yaml::from_value(value.clone())?;
+        Ok(serde_json::from_value(interpolation::resolve(value)?)?)
     }
 
     /// Set a configuration value in the config file (non-secret).
@@ -715,15 +812,17 @@ impl Config {
         let env_key = key.to_uppercase();
         if let Ok(val) = env::var(&env_key) {
             let value = Self::parse_env_value(&val)?;
+            crate::redaction::register_secret_value(&value);
             return Ok(serde_json::from_value(value)?);
         }
 
         // Then check keyring
         let values = self.all_secrets()?;
-        values
+        let value = values
             .get(key)
-            .ok_or_else(|| ConfigError::NotFound(key.to_string()))
-            .and_then(|v| Ok(serde_json::from_value(v.clone())?))
+            .ok_or_else(|| ConfigError::NotFound(key.to_string()))?;
+        crate::redaction::register_secret_value(value);
+        Ok(serde_json::from_value(value.clone())?)
     }
 
     /// Get secrets. If primary is in env, use env for all keys. Otherwise use secret storage.
@@ -735,7 +834,10 @@ impl Config {
         let use_env = env::var(primary.to_uppercase()).is_ok();
         let get_value = |key: &str| -> Result<String, ConfigError> {
             if use_env {
-                env::var(key.to_uppercase()).map_err(|_| ConfigError::NotFound(key.to_string()))
+                let value = env::var(key.to_uppercase())
+                    .map_err(|_| ConfigError::NotFound(key.to_string()))?;
+                crate::redaction::register_secret(&value);
+                Ok(value)
             } else {
                 self.get_secret(key)
             }
@@ -785,6 +887,10 @@ impl Config {
                 let yaml_value = serde_yaml::to_string(&values)?;
                 std::fs::write(path, yaml_value)?;
             }
+            SecretStorage::Backend(backend) => backend.save(&values)?,
+            SecretStorage::Unavailable(error) => {
+                return Err(ConfigError::SecretBackendError(error.clone()))
+            }
         };
         Ok(())
     }
@@ -816,6 +922,10 @@ impl Config {
                 let yaml_value = serde_yaml::to_string(&values)?;
                 std::fs::write(path, yaml_value)?;
             }
+            SecretStorage::Backend(backend) => backend.save(&values)?,
+            SecretStorage::Unavailable(error) => {
+                return Err(ConfigError::SecretBackendError(error.clone()))
+            }
         };
         Ok(())
     }
@@ -1585,6 +1695,76 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_values_are_resolved_when_read() -> Result<(), ConfigError> {
+        let config = new_test_config();
+        config.set_param("test_endpoint", "https://${TEST_ENDPOINT_HOST}/v1")?;
+        config.set_secret("test_interpolated_secret", &"${TEST_SECRET_SOURCE}")?;
+
+        temp_env::with_vars(
+            [
+                ("TEST_ENDPOINT_HOST", Some("api.example.com")),
+                ("TEST_SECRET_SOURCE", Some("from-env")),
+                ("TEST_ENDPOINT", None),
+                ("TEST_INTERPOLATED_SECRET", None),
+            ],
+            || {
+                let endpoint: String = config.get_param("test_endpoint").unwrap();
+                assert_eq!(endpoint, "https://api.example.com/v1");
+                // Secrets are taken as they are
+                let secret: String = config.get_secret("test_interpolated_secret").unwrap();
+                assert_eq!(secret, "${TEST_SECRET_SOURCE}");
+            },
+        );
+
+        // The stored value keeps the reference
+        let stored = config.all_values()?;
+        assert_eq!(
+            stored["test_endpoint"],
+            Value::String("https://${TEST_ENDPOINT_HOST}/v1".to_string())
+        );
+
+        // Values from the environment are taken as they are
+        temp_env::with_vars(
+            [
+                ("TEST_ENDPOINT", Some("literal ${TEST_ENDPOINT_HOST}")),
+                ("TEST_ENDPOINT_HOST", Some("api.example.com")),
+            ],
+            || {
+                let endpoint: String = config.get_param("test_endpoint").unwrap();
+                assert_eq!(endpoint, "literal ${TEST_ENDPOINT_HOST}");
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_old_config_is_migrated_with_backup() -> Result<(), ConfigError> {
+        let config_file = NamedTempFile::new().unwrap();
+        let secrets_file = NamedTempFile::new().unwrap();
+        let old_content = "GOOSE_PROVIDER__TYPE: ollama\nGOOSE_PROVIDER__HOST: http://gpu:11434\n";
+        std::fs::write(config_file.path(), old_content)?;
+        let config = Config::new_with_file_secrets(config_file.path(), secrets_file.path())?;
+
+        let host: String = config.get_param("OLLAMA_HOST")?;
+        assert_eq!(host, "http://gpu:11434");
+
+        let mut backup_name = config_file.path().file_name().unwrap().to_os_string();
+        backup_name.push(".v0.bak");
+        let backup_path = config_file.path().with_file_name(backup_name);
+        assert_eq!(std::fs::read_to_string(&backup_path)?, old_content);
+
+        let migrated: Mapping =
+            serde_yaml::from_str(&std::fs::read_to_string(config_file.path())?)?;
+        assert_eq!(
+            migrations::config_version(&migrated),
+            migrations::CURRENT_CONFIG_VERSION
+        );
+        assert!(!migrated.contains_key("GOOSE_PROVIDER__TYPE"));
+        std::fs::remove_file(backup_path)?;
+        Ok(())
+    }
+
     fn new_test_config() -> Config {
         let config_file = NamedTempFile::new().unwrap();
         let secrets_file = NamedTempFile::new().unwrap();
@@ -1,6 +1,7 @@
 use crate::config::paths::Paths;
 use crate::config::Config;
 use crate::providers::anthropic::AnthropicProvider;
+use crate::providers::api_client::TlsConfig;
 use crate::providers::base::{ModelInfo, ProviderType};
 use crate::providers::ollama::OllamaProvider;
 use crate::providers::openai::OpenAiProvider;
@@ -39,6 +40,14 @@ pub struct DeclarativeProviderConfig {
     pub headers: Option<HashMap<String, String>>,
     pub timeout_seconds: Option<u64>,
     pub supports_streaming: Option<bool>,
+    /// Client certificate and key presented to the provider, for gateways that require mutual TLS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key_path: Option<String>,
+    /// Extra CA certificates to trust for this provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert_path: Option<String>,
 }
 
 impl DeclarativeProviderConfig {
@@ -53,6 +62,15 @@ impl DeclarativeProviderConfig {
     pub fn models(&self) -> &[ModelInfo] {
         &self.models
     }
+
+    pub fn tls_config(&self) -> Result<Option<TlsConfig>> {
+        TlsConfig::from_paths(
+            self.client_cert_path.clone(),
+            self.client_key_path.clone(),
+            self.ca_cert_path.clone(),
+            ("client_cert_path", "client_key_path"),
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -121,6 +139,9 @@ pub fn create_custom_provider(
         headers,
         timeout_seconds: None,
         supports_streaming,
+        client_cert_path: None,
+        client_key_path: None,
+        ca_cert_path: None,
     };
 
     let custom_providers_dir = custom_providers_dir();
@@ -173,6 +194,9 @@ pub fn update_custom_provider(
             headers: existing_config.headers,
             timeout_seconds: existing_config.timeout_seconds,
             supports_streaming,
+            client_cert_path: existing_config.client_cert_path,
+            client_key_path: existing_config.client_key_path,
+            ca_cert_path: existing_config.ca_cert_path,
         };
 
         let file_path = custom_providers_dir().join(format!("{}.json", id));
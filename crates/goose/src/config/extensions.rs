@@ -100,8 +100,19 @@ pub fn set_extension(entry: ExtensionEntry) {
 
 pub fn remove_extension(key: &str) {
     let mut extensions = get_extensions_map();
-    extensions.shift_remove(key);
+    let removed = extensions.shift_remove(key);
     save_extensions_map(extensions);
+
+    // Don't leave the tokens of a removed remote server behind in the secret store
+    if let Some(ExtensionEntry {
+        config: ExtensionConfig::StreamableHttp { name, .. },
+        ..
+    }) = removed
+    {
+        if let Err(e) = crate::oauth::clear_credentials(&name) {
+            warn!("Failed to clear OAuth credentials of {}: {}", name, e);
+        }
+    }
 }
 
 pub fn set_extension_enabled(key: &str, enabled: bool) {
@@ -9,6 +9,8 @@ pub enum GooseMode {
     Approve,
     SmartApprove,
     Chat,
+    /// Tools that only read run; other calls are reported instead of run
+    DryRun,
 }
 
 impl FromStr for GooseMode {
@@ -20,6 +22,7 @@ impl FromStr for GooseMode {
             "approve" => Ok(GooseMode::Approve),
             "smart_approve" => Ok(GooseMode::SmartApprove),
             "chat" => Ok(GooseMode::Chat),
+            "dry_run" => Ok(GooseMode::DryRun),
             _ => Err(format!("invalid mode: {}", s)),
         }
     }
@@ -0,0 +1,273 @@
+//! Config values that are worked out when they are read.
+//!
+//! A string value in the config file can refer to environment variables as `${NAME}`, or
+//! `${NAME:-default}` to fall back when the variable isn't set; write `$${` for a literal `${`.
+//! A reference to a variable that isn't set and has no default, or a `${` that is never closed,
+//! is left as it is, with a warning, so values written before references were resolved still
+//! read the same. Values set through environment variables, and secrets, are taken as they are.
+//!
+//! With GOOSE_CONFIG_COMMANDS=true in the environment, a value starting with `cmd:` is a shell
+//! command whose output becomes the value. That way a secret can live in 1Password, Vault or
+//! SSM rather than on disk. Without it, such a value is taken as it is, with a warning:
+//!
+//! ```yaml
+//! OPENAI_API_KEY: "cmd:op read op://Private/OpenAI/credential"
+//! DATABRICKS_HOST: "https://${DATABRICKS_WORKSPACE}.cloud.databricks.com"
+//! ```
+//!
+//! Commands are off unless enabled because anything that can write the config could otherwise
+//! run them. The switch is only read from the environment for the same reason.
+//!
+//! Values are only resolved when read, so a command only runs once something needs its value.
+//! Its output is kept for the rest of the process so that the command isn't run on every read.
+//! Nothing clears it: a rotated secret, or a command changed in the config, only takes effect
+//! once goose restarts.
+//! Command output is treated as a secret and masked in logs.
+
+use std::collections::HashMap;
+use std::env;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde_json::Value;
+
+use crate::config::ConfigError;
+
+pub const COMMAND_PREFIX: &str = "cmd:";
+pub const COMMANDS_ENV_VAR: &str = "GOOSE_CONFIG_COMMANDS";
+
+/// Output of each command run, by command. Never invalidated, see the module docs.
+static COMMAND_OUTPUTS: Lazy<Mutex<HashMap<String, String>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn interpolate_env(text: &str) -> String {
+    let mut resolved = String::new();
+    let mut rest = text;
+    while let Some((before, after)) = rest.split_once("${") {
+        if let Some(before) = before.strip_suffix('$') {
+            resolved.push_str(before);
+            resolved.push_str("${");
+            rest = after;
+            continue;
+        }
+        resolved.push_str(before);
+        let Some((reference, after)) = after.split_once('}') else {
+            tracing::warn!(
+                "Unclosed ${{ in a config value, leaving it as it is; write $${{ for a literal ${{"
+            );
+            resolved.push_str("${");
+            rest = after;
+            break;
+        };
+        let (name, default) = match reference.split_once(":-") {
+            Some((name, default)) => (name.trim(), Some(default)),
+            None => (reference.trim(), None),
+        };
+        match (env::var(name), default) {
+            (Ok(value), _) => resolved.push_str(&value),
+            (Err(_), Some(default)) => resolved.push_str(default),
+            (Err(_), None) => {
+                tracing::warn!(
+                    "Environment variable {} is not set, leaving ${{{}}} in the config value",
+                    name,
+                    reference
+                );
+                resolved.push_str(&format!("${{{}}}", reference));
+            }
+        }
+        rest = after;
+    }
+    resolved.push_str(rest);
+    resolved
+}
+
+fn run_command(command: &str) -> Result<String, ConfigError> {
+    if let Some(output) = COMMAND_OUTPUTS.lock().unwrap().get(command) {
+        return Ok(output.clone());
+    }
+
+    let mut process = if cfg!(target_os = "windows") {
+        let mut process = Command::new("cmd");
+        process.args(["/C", command]);
+        process
+    } else {
+        let mut process = Command::new("sh");
+        process.args(["-c", command]);
+        process
+    };
+    let output = process
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| {
+            ConfigError::InterpolationError(format!("Failed to run '{}': {}", command, e))
+        })?;
+    if !output.status.success() {
+        return Err(ConfigError::InterpolationError(format!(
+            "'{}' failed with {}: {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let value = String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches(['\r', '\n'])
+        .to_string();
+    crate::redaction::register_secret(&value);
+    COMMAND_OUTPUTS
+        .lock()
+        .unwrap()
+        .insert(command.to_string(), value.clone());
+    Ok(value)
+}
+
+fn commands_enabled() -> bool {
+    env::var(COMMANDS_ENV_VAR).is_ok_and(|value| value.eq_ignore_ascii_case("true"))
+}
+
+fn resolve_string(text: &str) -> Result<String, ConfigError> {
+    match text.strip_prefix(COMMAND_PREFIX) {
+        Some(command) if commands_enabled() => run_command(interpolate_env(command).trim()),
+        Some(_) => {
+            tracing::warn!(
+                "A config value starts with {} but commands only run with {}=true, so it is taken as it is",
+                COMMAND_PREFIX,
+                COMMANDS_ENV_VAR
+            );
+            Ok(text.to_string())
+        }
+        None => Ok(interpolate_env(text)),
+    }
+}
+
+/// `value`, as read from the config file, with environment variables filled into its strings
+/// and commands replaced by their output, all the way down
+pub fn resolve(value: Value) -> Result<Value, ConfigError> {
+    Ok(match value {
+        Value::String(text) if text.contains("${") || text.starts_with(COMMAND_PREFIX) => {
+            Value::String(resolve_string(&text)?)
+        }
+        Value::Array(items) => {
+            Value::Array(items.into_iter().map(resolve).collect::<Result<_, _>>()?)
+        }
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| Ok((key, resolve(value)?)))
+                .collect::<Result<_, ConfigError>>()?,
+        ),
+        value => value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_interpolate_env() {
+        temp_env::with_vars(
+            [
+                ("GOOSE_TEST_WORKSPACE", Some("acme")),
+                ("GOOSE_TEST_UNSET", None),
+            ],
+            || {
+                assert_eq!(
+                    interpolate_env("https://${GOOSE_TEST_WORKSPACE}.example.com"),
+                    "https://acme.example.com"
+                );
+                assert_eq!(
+                    interpolate_env("${GOOSE_TEST_UNSET:-fallback}/${ GOOSE_TEST_WORKSPACE }"),
+                    "fallback/acme"
+                );
+                assert_eq!(
+                    interpolate_env("literal $${GOOSE_TEST_WORKSPACE}"),
+                    "literal ${GOOSE_TEST_WORKSPACE}"
+                );
+                assert_eq!(
+                    interpolate_env("${GOOSE_TEST_WORKSPACE}: ${GOOSE_TEST_WORKSPACE"),
+                    "acme: ${GOOSE_TEST_WORKSPACE"
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_unset_variables_stay_literal() {
+        temp_env::with_vars(
+            [
+                ("GOOSE_TEST_WORKSPACE", Some("acme")),
+                ("GOOSE_TEST_UNSET", None),
+            ],
+            || {
+                assert_eq!(
+                    interpolate_env("${GOOSE_TEST_UNSET}"),
+                    "${GOOSE_TEST_UNSET}"
+                );
+                assert_eq!(
+                    resolve(json!("${GOOSE_TEST_WORKSPACE}/${ GOOSE_TEST_UNSET }")).unwrap(),
+                    json!("acme/${ GOOSE_TEST_UNSET }")
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_escaped_references_stay_literal() {
+        temp_env::with_var("GOOSE_TEST_WORKSPACE", Some("acme"), || {
+            let resolved = resolve(json!({
+                "template": "echo $${HOME} in ${GOOSE_TEST_WORKSPACE}",
+                "dollars": "$$${GOOSE_TEST_WORKSPACE}",
+                "plain": "costs $5 {not a reference}",
+            }))
+            .unwrap();
+            assert_eq!(
+                resolved,
+                json!({
+                    "template": "echo ${HOME} in acme",
+                    "dollars": "$${GOOSE_TEST_WORKSPACE}",
+                    "plain": "costs $5 {not a reference}",
+                })
+            );
+        });
+    }
+
+    #[test]
+    fn test_commands_need_opt_in() {
+        temp_env::with_var(COMMANDS_ENV_VAR, None::<&str>, || {
+            assert_eq!(
+                resolve(json!("cmd:echo secret")).unwrap(),
+                json!("cmd:echo secret")
+            );
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_runs_commands() {
+        temp_env::with_vars(
+            [
+                ("GOOSE_TEST_SECRET_NAME", Some("api-key")),
+                (COMMANDS_ENV_VAR, Some("true")),
+            ],
+            || {
+                let resolved = resolve(json!({
+                    "key": "cmd:echo secret-${GOOSE_TEST_SECRET_NAME}",
+                    "plain": ["no references", 3],
+                }))
+                .unwrap();
+                assert_eq!(
+                    resolved,
+                    json!({"key": "secret-api-key", "plain": ["no references", 3]})
+                );
+                assert!(matches!(
+                    resolve(json!("cmd:exit 3")),
+                    Err(ConfigError::InterpolationError(_))
+                ));
+            },
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Upgrading config files written by older versions of goose.
+//!
+//! The layout of a config file is versioned by its `config_version` key; files from before
+//! versioning have none and count as version 0. When a config is loaded, the migrations from its
+//! version up to [`CURRENT_CONFIG_VERSION`] are applied. If that changes anything, the old file is
+//! kept next to the config as `config.yaml.v<version>.bak` and the upgraded one is written in its
+//! place.
+//!
+//! Because unversioned files can already be in the newer layout, each migration must leave a
+//! config it doesn't apply to alone.
+
+use std::sync::Once;
+
+use serde_yaml::{Mapping, Value};
+
+pub const CONFIG_VERSION_KEY: &str = "config_version";
+pub const CURRENT_CONFIG_VERSION: u32 = 1;
+
+pub struct Migration {
+    /// The version this migration upgrades from, to the next one
+    pub from: u32,
+    pub description: &'static str,
+    pub apply: fn(&mut Mapping),
+}
+
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "Move the GOOSE_PROVIDER__* settings to GOOSE_PROVIDER, GOOSE_MODEL and the provider's own keys",
+    apply: split_provider_settings,
+}];
+
+/// A config brought up to the current version
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigratedConfig {
+    pub from: u32,
+    pub values: Mapping,
+    pub applied: Vec<&'static str>,
+}
+
+static NEWER_VERSION_WARNING: Once = Once::new();
+
+/// Move `from` to `to`, unless `to` is set already, in which case `from` is dropped
+fn rename_key(values: &mut Mapping, from: &str, to: &str) {
+    if let Some(value) = values.remove(from) {
+        if !values.contains_key(to) {
+            values.insert(Value::from(to), value);
+        }
+    }
+}
+
+fn split_provider_settings(values: &mut Mapping) {
+    rename_key(values, "GOOSE_PROVIDER__TYPE", "GOOSE_PROVIDER");
+    rename_key(values, "GOOSE_PROVIDER__MODEL", "GOOSE_MODEL");
+
+    let prefix = values
+        .get("GOOSE_PROVIDER")
+        .and_then(Value::as_str)
+        .map(|provider| provider.to_uppercase().replace('-', "_"));
+    match prefix {
+        Some(prefix) => rename_key(values, "GOOSE_PROVIDER__HOST", &format!("{}_HOST", prefix)),
+        None => {
+            if values.contains_key("GOOSE_PROVIDER__HOST") {
+                tracing::warn!("Leaving GOOSE_PROVIDER__HOST alone as no provider is set");
+            }
+        }
+    }
+}
+
+/// The layout version of `values`
+pub fn config_version(values: &Mapping) -> u32 {
+    values
+        .get(CONFIG_VERSION_KEY)
+        .and_then(Value::as_u64)
+        .and_then(|version| u32::try_from(version).ok())
+        .unwrap_or(0)
+}
+
+/// `values` upgraded to the current layout, or `None` if there is nothing to change
+pub fn migrate(values: &Mapping) -> Option<MigratedConfig> {
+    let from = config_version(values);
+    if from > CURRENT_CONFIG_VERSION {
+        NEWER_VERSION_WARNING.call_once(|| {
+            tracing::warn!(
+                "Config is version {}, newer than the {} this goose understands; some settings may be ignored",
+                from,
+                CURRENT_CONFIG_VERSION
+            )
+        });
+        return None;
+    }
+
+    let mut migrated = values.clone();
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= from) {
+        (migration.apply)(&mut migrated);
+        applied.push(migration.description);
+    }
+    if migrated == *values {
+        return None;
+    }
+
+    migrated.insert(
+        Value::from(CONFIG_VERSION_KEY),
+        Value::from(CURRENT_CONFIG_VERSION),
+    );
+    Some(MigratedConfig {
+        from,
+        values: migrated,
+        applied,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(yaml: &str) -> Mapping {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_migrations_are_in_order() {
+        for (version, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.from, version as u32);
+        }
+        assert_eq!(MIGRATIONS.len() as u32, CURRENT_CONFIG_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_provider_settings() {
+        let migrated = migrate(&mapping(
+            "GOOSE_PROVIDER__TYPE: openai\nGOOSE_PROVIDER__MODEL: gpt-4o\nGOOSE_PROVIDER__HOST: https://proxy.example.com\nGOOSE_MODE: auto\n",
+        ))
+        .unwrap();
+        assert_eq!(migrated.from, 0);
+        assert_eq!(migrated.applied.len(), 1);
+        assert_eq!(
+            migrated.values,
+            mapping(
+                "GOOSE_PROVIDER: openai\nGOOSE_MODEL: gpt-4o\nOPENAI_HOST: https://proxy.example.com\nGOOSE_MODE: auto\nconfig_version: 1\n"
+            )
+        );
+
+        // Settings in the new layout win over old ones
+        let migrated = migrate(&mapping(
+            "GOOSE_PROVIDER: anthropic\nGOOSE_PROVIDER__TYPE: openai\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            migrated.values,
+            mapping("GOOSE_PROVIDER: anthropic\nconfig_version: 1\n")
+        );
+    }
+
+    #[test]
+    fn test_migrate_leaves_current_configs_alone() {
+        assert_eq!(migrate(&mapping("GOOSE_PROVIDER: openai\n")), None);
+        assert_eq!(migrate(&mapping("config_version: 1\n")), None);
+        assert_eq!(
+            migrate(&mapping(
+                "config_version: 99\nGOOSE_PROVIDER__TYPE: openai\n"
+            )),
+            None
+        );
+    }
+}
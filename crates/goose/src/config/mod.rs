@@ -3,11 +3,17 @@ pub mod declarative_providers;
 mod experiments;
 pub mod extensions;
 pub mod goose_mode;
+pub mod interpolation;
+pub mod migrations;
 pub mod paths;
 pub mod permission;
+pub mod remote;
+pub mod schema;
 pub mod search_path;
+pub mod secret_backends;
 pub mod signup_openrouter;
 pub mod signup_tetrate;
+pub mod watcher;
 
 pub use crate::agents::ExtensionConfig;
 pub use base::{Config, ConfigError};
@@ -0,0 +1,734 @@
+//! A JSON Schema of config.yaml, and checks of a config against it.
+//!
+//! [`config_schema`] describes the settings goose knows of: the agent settings listed in
+//! [`AGENT_SETTINGS`], the keys each provider asks for, and extensions. Editors can use it to
+//! complete and check config.yaml, and setup UIs to build forms. [`validate_config`] reports
+//! problems with a config: unknown keys (often typos), values of the wrong type, extensions
+//! that can't be read, and values the configured provider needs but doesn't have.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use utoipa::ToSchema;
+
+use crate::config::extensions::ExtensionEntry;
+use crate::config::interpolation::COMMAND_PREFIX;
+use crate::config::{Config, ConfigError};
+use crate::providers::base::ProviderMetadata;
+use crate::providers::providers;
+
+const EXTENSIONS_KEY: &str = "extensions";
+/// Largest edit distance at which an unknown key is taken for a typo of a known one
+const MAX_TYPO_DISTANCE: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    StringList,
+    List,
+    Object,
+    /// One of these strings
+    Choice(&'static [&'static str]),
+}
+
+impl SettingType {
+    fn schema(self) -> Value {
+        match self {
+            SettingType::String => json!({"type": "string"}),
+            SettingType::Integer => json!({"type": "integer", "minimum": 0}),
+            SettingType::Number => json!({"type": "number", "minimum": 0}),
+            SettingType::Boolean => json!({"type": "boolean"}),
+            SettingType::StringList => json!({"type": "array", "items": {"type": "string"}}),
+            SettingType::List => json!({"type": "array"}),
+            SettingType::Object => json!({"type": "object"}),
+            SettingType::Choice(choices) => json!({"type": "string", "enum": choices}),
+        }
+    }
+
+    fn accepts(self, value: &Value) -> bool {
+        match self {
+            SettingType::String => value.is_string(),
+            SettingType::Integer => value.is_u64(),
+            SettingType::Number => value.as_f64().is_some_and(|number| number >= 0.0),
+            SettingType::Boolean => value.is_boolean(),
+            SettingType::StringList => value
+                .as_array()
+                .is_some_and(|items| items.iter().all(Value::is_string)),
+            SettingType::List => value.is_array(),
+            SettingType::Object => value.is_object(),
+            SettingType::Choice(choices) => value
+                .as_str()
+                .is_some_and(|choice| choices.contains(&choice)),
+        }
+    }
+
+    fn describe(self) -> String {
+        match self {
+            SettingType::String => "a string".to_string(),
+            SettingType::Integer => "a whole number of at least 0".to_string(),
+            SettingType::Number => "a number of at least 0".to_string(),
+            SettingType::Boolean => "true or false".to_string(),
+            SettingType::StringList => "a list of strings".to_string(),
+            SettingType::List => "a list".to_string(),
+            SettingType::Object => "a mapping".to_string(),
+            SettingType::Choice(choices) => format!("one of {}", choices.join(", ")),
+        }
+    }
+}
+
+/// A setting of the agent, as opposed to one of a provider
+#[derive(Debug, Clone, Copy)]
+pub struct Setting {
+    pub key: &'static str,
+    pub setting_type: SettingType,
+    pub description: &'static str,
+}
+
+const fn setting(
+    key: &'static str,
+    setting_type: SettingType,
+    description: &'static str,
+) -> Setting {
+    Setting {
+        key,
+        setting_type,
+        description,
+    }
+}
+
+pub const AGENT_SETTINGS: &[Setting] = &[
+    setting(
+        "config_version",
+        SettingType::Integer,
+        "Layout version of this file, set when it is migrated",
+    ),
+    setting("GOOSE_PROVIDER", SettingType::String, "The provider to use"),
+    setting("GOOSE_MODEL", SettingType::String, "The model to use"),
+    setting(
+        "GOOSE_MODE",
+        SettingType::Choice(&["auto", "approve", "smart_approve", "chat", "dry_run"]),
+        "Which tool calls need approval",
+    ),
+    setting(
+        "GOOSE_MAX_TURNS",
+        SettingType::Integer,
+        "Turns the agent takes before asking whether to go on",
+    ),
+    setting(
+        "GOOSE_AUTO_COMPACT_THRESHOLD",
+        SettingType::Number,
+        "Share of the context window at which the conversation is compacted",
+    ),
+    setting(
+        "GOOSE_LEAD_PROVIDER",
+        SettingType::String,
+        "Provider of the model that leads the first turns",
+    ),
+    setting(
+        "GOOSE_LEAD_MODEL",
+        SettingType::String,
+        "Model that leads the first turns",
+    ),
+    setting(
+        "GOOSE_LEAD_TURNS",
+        SettingType::Integer,
+        "Turns the lead model takes before the main model",
+    ),
+    setting(
+        "GOOSE_LEAD_FAILURE_THRESHOLD",
+        SettingType::Integer,
+        "Consecutive failed turns of the main model that hand back to the lead model",
+    ),
+    setting(
+        "GOOSE_LEAD_FALLBACK_TURNS",
+        SettingType::Integer,
+        "Turns the lead model takes after the main model fails",
+    ),
+    setting(
+        "GOOSE_PLANNER_PROVIDER",
+        SettingType::String,
+        "Provider of the model that makes plans",
+    ),
+    setting(
+        "GOOSE_PLANNER_MODEL",
+        SettingType::String,
+        "Model that makes plans",
+    ),
+    setting(
+        "GOOSE_MODEL_CATALOG",
+        SettingType::Object,
+        "Context windows, output limits, capabilities and pricing of models, by model",
+    ),
+    setting(
+        "GOOSE_MODEL_PRESETS",
+        SettingType::List,
+        "Default request parameters for models matching a pattern",
+    ),
+    setting(
+        "GOOSE_SENTENCEPIECE_VOCABS",
+        SettingType::Object,
+        "SentencePiece vocab files to count tokens with, by model name pattern",
+    ),
+    setting(
+        "GOOSE_SEARCH_PATHS",
+        SettingType::StringList,
+        "Extra directories to look for extension commands in",
+    ),
+    setting(
+        "GOOSE_MAX_ACTIVE_AGENTS",
+        SettingType::Integer,
+        "Most agents the server keeps running",
+    ),
+    setting(
+        "GOOSE_STOP_SEQUENCES",
+        SettingType::StringList,
+        "Text that ends a response where it appears",
+    ),
+    setting(
+        "GOOSE_MAX_OUTPUT_TOKENS",
+        SettingType::Integer,
+        "Most tokens to take from one response before ending it",
+    ),
+    setting(
+        "GOOSE_SESSION_MAX_COST",
+        SettingType::Number,
+        "Most a session may spend, in dollars",
+    ),
+    setting(
+        "GOOSE_SESSION_MAX_TOKENS",
+        SettingType::Integer,
+        "Most tokens a session may use",
+    ),
+    setting(
+        "GOOSE_SESSION_MAX_TURNS",
+        SettingType::Integer,
+        "Most turns a session may take",
+    ),
+    setting(
+        "GOOSE_MAX_PARALLEL_TOOL_CALLS",
+        SettingType::Integer,
+        "Most tool calls run at once",
+    ),
+    setting(
+        "GOOSE_TOOL_CACHE",
+        SettingType::Boolean,
+        "Reuse the results of identical read-only tool calls",
+    ),
+    setting(
+        "GOOSE_TOOL_CACHE_TTL",
+        SettingType::Integer,
+        "Seconds cached tool results are kept",
+    ),
+    setting(
+        "GOOSE_WORKSPACE_ROOTS",
+        SettingType::StringList,
+        "Directories tools may work in, besides the session's",
+    ),
+    setting(
+        "GOOSE_ENFORCE_WORKSPACE_ROOTS",
+        SettingType::Boolean,
+        "Refuse tool calls that reach outside the workspace roots",
+    ),
+    setting(
+        "GOOSE_FETCH_ALLOWED_DOMAINS",
+        SettingType::StringList,
+        "Domains web fetches are limited to",
+    ),
+    setting(
+        "GOOSE_FETCH_BLOCKED_DOMAINS",
+        SettingType::StringList,
+        "Domains web fetches may not reach",
+    ),
+    setting(
+        "GOOSE_FETCH_RESPECT_ROBOTS",
+        SettingType::Boolean,
+        "Follow robots.txt when fetching pages",
+    ),
+    setting(
+        "GOOSE_FETCH_MAX_BYTES",
+        SettingType::Integer,
+        "Largest page web fetches download",
+    ),
+    setting(
+        "GOOSE_FETCH_ALLOW_PRIVATE_ADDRESSES",
+        SettingType::Boolean,
+        "Let web fetches reach loopback and private network addresses",
+    ),
+    setting(
+        "GOOSE_PII_SCRUBBING",
+        SettingType::Boolean,
+        "Mask personal information before it is sent to the model",
+    ),
+    setting(
+        "GOOSE_TELEMETRY_ENABLED",
+        SettingType::Boolean,
+        "Send anonymous usage data",
+    ),
+    setting(
+        "GOOSE_LOG_LEVEL",
+        SettingType::String,
+        "Log filter, such as info or goose=debug,warn; RUST_LOG wins if set",
+    ),
+    setting(
+        "GOOSE_AUDIT_LOG",
+        SettingType::Boolean,
+        "Record tool calls in the audit log",
+    ),
+    setting(
+        "GOOSE_AUDIT_LOG_DIR",
+        SettingType::String,
+        "Directory of the audit log",
+    ),
+    setting(
+        "GOOSE_AUDIT_LOG_RETENTION_DAYS",
+        SettingType::Integer,
+        "Days audit logs are kept",
+    ),
+    setting(
+        "GOOSE_WEBHOOKS",
+        SettingType::List,
+        "Webhooks told about run lifecycle events",
+    ),
+    setting(
+        "GOOSE_SECRET_BACKEND",
+        SettingType::Choice(&["keyring", "file", "vault", "aws_secrets_manager"]),
+        "Where secrets are kept",
+    ),
+    setting(
+        "GOOSE_SECRETS_CACHE_TTL",
+        SettingType::Integer,
+        "Seconds secrets from a secret backend are cached",
+    ),
+    setting(
+        "GOOSE_REMOTE_CONFIG_URL",
+        SettingType::String,
+        "HTTPS endpoint of a signed base config shared by many installs",
+    ),
+    setting(
+        "GOOSE_REMOTE_CONFIG_PUBLIC_KEY",
+        SettingType::String,
+        "PEM public key the remote config must be signed with",
+    ),
+    setting(
+        "GOOSE_REMOTE_CONFIG_REFRESH_SECS",
+        SettingType::Integer,
+        "Seconds between fetches of the remote config",
+    ),
+    setting(
+        "GOOSE_CLI_THEME",
+        SettingType::Choice(&["light", "dark", "ansi"]),
+        "Colours of the CLI",
+    ),
+    setting(
+        "GOOSE_CLI_SHOW_COST",
+        SettingType::Boolean,
+        "Show the cost of each session in the CLI",
+    ),
+    setting(
+        "GOOSE_DISABLE_KEYRING",
+        SettingType::String,
+        "Keep secrets in secrets.yaml instead of the keyring",
+    ),
+];
+
+fn provider_key_schema(
+    metadata: &ProviderMetadata,
+    key: &crate::providers::base::ConfigKey,
+) -> Value {
+    let mut description = format!(
+        "{}: {}",
+        metadata.display_name,
+        if key.required { "required" } else { "optional" }
+    );
+    if key.secret {
+        description.push_str(", secret; usually kept in the keyring");
+    }
+    let mut schema = json!({"type": "string", "description": description});
+    if let Some(default) = &key.default {
+        schema["default"] = json!(default);
+    }
+    if key.secret {
+        schema["writeOnly"] = json!(true);
+    }
+    schema
+}
+
+fn extension_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "Extensions, by key",
+        "additionalProperties": {
+            "type": "object",
+            "required": ["enabled", "type", "name"],
+            "properties": {
+                "enabled": {"type": "boolean"},
+                "type": {
+                    "type": "string",
+                    "enum": [
+                        "builtin",
+                        "platform",
+                        "stdio",
+                        "sse",
+                        "streamable_http",
+                        "frontend",
+                        "inline_python",
+                        "wasm"
+                    ]
+                },
+                "name": {"type": "string"},
+                "description": {"type": "string"},
+                "timeout": {"type": "integer", "minimum": 0},
+                "bundled": {"type": "boolean"}
+            }
+        }
+    })
+}
+
+/// The JSON Schema of a config with `providers`
+pub fn schema_for_providers(providers: &[ProviderMetadata]) -> Value {
+    let mut properties = Map::new();
+    for setting in AGENT_SETTINGS {
+        let mut schema = setting.setting_type.schema();
+        schema["description"] = json!(setting.description);
+        properties.insert(setting.key.to_string(), schema);
+    }
+    for metadata in providers {
+        for key in &metadata.config_keys {
+            properties
+                .entry(key.name.clone())
+                .or_insert_with(|| provider_key_schema(metadata, key));
+        }
+    }
+    properties.insert(EXTENSIONS_KEY.to_string(), extension_schema());
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "goose configuration",
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": true,
+    })
+}
+
+/// The JSON Schema of config.yaml, with the keys of every known provider
+pub async fn config_schema() -> Value {
+    let providers: Vec<ProviderMetadata> = providers()
+        .await
+        .into_iter()
+        .map(|(metadata, _)| metadata)
+        .collect();
+    schema_for_providers(&providers)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    /// goose won't work as configured
+    Error,
+    /// Probably a mistake, such as a misspelt key
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct ConfigIssue {
+    pub severity: IssueSeverity,
+    pub key: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, ToSchema)]
+pub struct ConfigValidation {
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl ConfigValidation {
+    /// Whether there are no errors; there may be warnings
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ConfigIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == IssueSeverity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ConfigIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == IssueSeverity::Warning)
+    }
+
+    fn push(
+        &mut self,
+        severity: IssueSeverity,
+        key: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        self.issues.push(ConfigIssue {
+            severity,
+            key: key.into(),
+            message: message.into(),
+        });
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn closest_key<'a>(key: &str, known: &HashSet<&'a str>) -> Option<&'a str> {
+    let key = key.to_uppercase();
+    known
+        .iter()
+        .map(|known_key| (edit_distance(&key, &known_key.to_uppercase()), *known_key))
+        .filter(|(distance, _)| *distance <= MAX_TYPO_DISTANCE)
+        .min()
+        .map(|(_, known_key)| known_key)
+}
+
+/// Values that are resolved when read can't be checked without resolving them
+fn is_deferred(value: &Value) -> bool {
+    value
+        .as_str()
+        .is_some_and(|text| text.contains("${") || text.starts_with(COMMAND_PREFIX))
+}
+
+/// Check `values`, as read from config.yaml, against the settings and `providers`. A key
+/// counts as set if it is in `values`, `secrets` or the environment.
+pub fn validate_values(
+    values: &HashMap<String, Value>,
+    secrets: &HashMap<String, Value>,
+    providers: &[ProviderMetadata],
+) -> ConfigValidation {
+    let mut validation = ConfigValidation::default();
+    let settings: HashMap<&str, &Setting> = AGENT_SETTINGS
+        .iter()
+        .map(|setting| (setting.key, setting))
+        .collect();
+    let mut known: HashSet<&str> = settings.keys().copied().collect();
+    known.insert(EXTENSIONS_KEY);
+    known.extend(
+        providers
+            .iter()
+            .flat_map(|metadata| metadata.config_keys.iter())
+            .map(|key| key.name.as_str()),
+    );
+
+    let mut keys: Vec<&String> = values.keys().collect();
+    keys.sort();
+    for key in keys {
+        let value = &values[key];
+        if let Some(setting) = settings.get(key.as_str()) {
+            if !is_deferred(value) && !setting.setting_type.accepts(value) {
+                validation.push(
+                    IssueSeverity::Error,
+                    key,
+                    format!(
+                        "Should be {}, not {}",
+                        setting.setting_type.describe(),
+                        value
+                    ),
+                );
+            }
+        } else if !known.contains(key.as_str()) {
+            let message = match closest_key(key, &known) {
+                Some(closest) => format!("Unknown setting; did you mean {}?", closest),
+                None => "Unknown setting".to_string(),
+            };
+            validation.push(IssueSeverity::Warning, key, message);
+        }
+    }
+
+    if let Some(extensions) = values.get(EXTENSIONS_KEY) {
+        match extensions.as_object() {
+            Some(extensions) => {
+                for (name, extension) in extensions {
+                    if let Err(e) = serde_json::from_value::<ExtensionEntry>(extension.clone()) {
+                        validation.push(
+                            IssueSeverity::Error,
+                            format!("{}.{}", EXTENSIONS_KEY, name),
+                            format!("Invalid extension: {}", e),
+                        );
+                    }
+                }
+            }
+            None => validation.push(
+                IssueSeverity::Error,
+                EXTENSIONS_KEY,
+                "Should be a mapping of extensions",
+            ),
+        }
+    }
+
+    let is_set = |key: &str| {
+        std::env::var(key.to_uppercase()).is_ok()
+            || values.contains_key(key)
+            || secrets.contains_key(key)
+    };
+    let provider_name = std::env::var("GOOSE_PROVIDER").ok().or_else(|| {
+        values
+            .get("GOOSE_PROVIDER")
+            .and_then(Value::as_str)
+            .map(String::from)
+    });
+    let Some(provider_name) = provider_name else {
+        validation.push(
+            IssueSeverity::Error,
+            "GOOSE_PROVIDER",
+            "No provider is configured; run goose configure",
+        );
+        return validation;
+    };
+    if !is_set("GOOSE_MODEL") {
+        validation.push(
+            IssueSeverity::Error,
+            "GOOSE_MODEL",
+            "No model is configured",
+        );
+    }
+    match providers
+        .iter()
+        .find(|metadata| metadata.name == provider_name)
+    {
+        Some(metadata) => {
+            for key in &metadata.config_keys {
+                if key.required && key.default.is_none() && !key.oauth_flow && !is_set(&key.name) {
+                    validation.push(
+                        IssueSeverity::Error,
+                        &key.name,
+                        format!("{} needs this to be set", metadata.display_name),
+                    );
+                }
+            }
+        }
+        None => validation.push(
+            IssueSeverity::Error,
+            "GOOSE_PROVIDER",
+            format!("Unknown provider {}", provider_name),
+        ),
+    }
+    validation
+}
+
+/// Check `config` against the settings and the keys of every known provider
+pub async fn validate_config(config: &Config) -> Result<ConfigValidation, ConfigError> {
+    let values = config.all_values()?;
+    let secrets = config.all_secrets().unwrap_or_else(|e| {
+        tracing::warn!("Failed to read secrets while validating the config: {}", e);
+        HashMap::new()
+    });
+    let providers: Vec<ProviderMetadata> = providers()
+        .await
+        .into_iter()
+        .map(|(metadata, _)| metadata)
+        .collect();
+    Ok(validate_values(&values, &secrets, &providers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::ConfigKey;
+
+    fn provider() -> ProviderMetadata {
+        ProviderMetadata::new(
+            "acme",
+            "Acme",
+            "Acme models",
+            "acme-large",
+            vec!["acme-large"],
+            "https://acme.example.com/models",
+            vec![
+                ConfigKey::new("ACME_API_KEY", true, true, None),
+                ConfigKey::new(
+                    "ACME_HOST",
+                    true,
+                    false,
+                    Some("https://api.acme.example.com"),
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_schema_includes_settings_providers_and_extensions() {
+        let schema = schema_for_providers(&[provider()]);
+        let properties = &schema["properties"];
+        assert_eq!(
+            properties["GOOSE_MODE"]["enum"],
+            json!(["auto", "approve", "smart_approve", "chat", "dry_run"])
+        );
+        assert_eq!(properties["ACME_API_KEY"]["writeOnly"], json!(true));
+        assert_eq!(
+            properties["ACME_HOST"]["default"],
+            json!("https://api.acme.example.com")
+        );
+        assert_eq!(properties["extensions"]["type"], json!("object"));
+    }
+
+    #[test]
+    fn test_validate_values() {
+        temp_env::with_vars(
+            [
+                ("GOOSE_PROVIDER", None::<&str>),
+                ("GOOSE_MODEL", None),
+                ("ACME_API_KEY", None),
+            ],
+            || {
+                let values: HashMap<String, Value> = serde_json::from_value(json!({
+                    "GOOSE_PROVIDER": "acme",
+                    "GOOSE_MODE": "yolo",
+                    "GOOSE_TOOL_CAHCE": true,
+                    "GOOSE_MAX_TURNS": "${MAX_TURNS}",
+                    "extensions": {
+                        "developer": {"enabled": true, "type": "builtin", "name": "developer"},
+                        "broken": {"enabled": true}
+                    }
+                }))
+                .unwrap();
+                let validation = validate_values(&values, &HashMap::new(), &[provider()]);
+                let issues: Vec<(IssueSeverity, &str)> = validation
+                    .issues
+                    .iter()
+                    .map(|issue| (issue.severity, issue.key.as_str()))
+                    .collect();
+                assert_eq!(
+                    issues,
+                    vec![
+                        (IssueSeverity::Error, "GOOSE_MODE"),
+                        (IssueSeverity::Warning, "GOOSE_TOOL_CAHCE"),
+                        (IssueSeverity::Error, "extensions.broken"),
+                        (IssueSeverity::Error, "GOOSE_MODEL"),
+                        (IssueSeverity::Error, "ACME_API_KEY"),
+                    ]
+                );
+                assert!(validation.issues[1].message.contains("GOOSE_TOOL_CACHE?"));
+                assert!(!validation.is_valid());
+
+                let secrets = HashMap::from([("ACME_API_KEY".to_string(), json!("key"))]);
+                let mut values = values;
+                values.insert("GOOSE_MODEL".to_string(), json!("acme-large"));
+                values.insert("GOOSE_MODE".to_string(), json!("auto"));
+                values.remove("extensions");
+                let validation = validate_values(&values, &secrets, &[provider()]);
+                assert!(validation.is_valid());
+                assert_eq!(validation.warnings().count(), 1);
+            },
+        );
+    }
+}
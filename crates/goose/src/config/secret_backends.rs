@@ -0,0 +1,559 @@
+//! Secret stores other than the keyring and the secrets file.
+//!
+//! Setting GOOSE_SECRET_BACKEND in the config or environment keeps goose's secrets somewhere
+//! else:
+//!
+//! - `vault`: a HashiCorp Vault KV v2 secret, at GOOSE_VAULT_PATH (default `goose`) under the
+//!   GOOSE_VAULT_MOUNT mount (default `secret`) of the server at VAULT_ADDR. goose logs in with
+//!   VAULT_TOKEN, a token read from GOOSE_VAULT_TOKEN_FILE on each request (as written by a
+//!   Vault agent), or the AppRole GOOSE_VAULT_ROLE_ID and GOOSE_VAULT_SECRET_ID. AppRole tokens
+//!   are renewed by logging in again shortly before they expire.
+//! - `aws_secrets_manager`: the AWS Secrets Manager secret GOOSE_AWS_SECRET_ID (default
+//!   `goose`), using credentials from the standard AWS sources. This needs the `aws-secrets`
+//!   feature.
+//!
+//! Either way the secrets are one JSON object, as they are in the keyring. Reads are cached for
+//! GOOSE_SECRETS_CACHE_TTL seconds (default 300), so the store isn't asked on every lookup.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use crate::config::{Config, ConfigError};
+
+pub const SECRET_BACKEND_CONFIG_KEY: &str = "GOOSE_SECRET_BACKEND";
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Log in again when the token has less than this left
+const TOKEN_RENEWAL_MARGIN: Duration = Duration::from_secs(60);
+
+/// Somewhere to keep the secrets, as one map of keys to values
+pub trait SecretBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn load(&self) -> Result<HashMap<String, Value>, ConfigError>;
+    fn save(&self, values: &HashMap<String, Value>) -> Result<(), ConfigError>;
+}
+
+fn backend_error(backend: &str, message: impl std::fmt::Display) -> ConfigError {
+    ConfigError::SecretBackendError(format!("{}: {}", backend, message))
+}
+
+/// Run `future` to completion from sync code, whether or not a runtime is already running
+fn block_on<F, T>(future: F) -> Result<T, ConfigError>
+where
+    F: Future<Output = Result<T, ConfigError>> + Send,
+    T: Send,
+{
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()?
+                    .block_on(future)
+            })
+            .join()
+            .map_err(|_| {
+                ConfigError::SecretBackendError("The secret backend thread panicked".to_string())
+            })?
+    })
+}
+
+/// Keeps what `inner` loads for `ttl`
+pub struct CachedSecretBackend {
+    inner: Box<dyn SecretBackend>,
+    ttl: Duration,
+    cache: Mutex<Option<(Instant, HashMap<String, Value>)>>,
+}
+
+impl CachedSecretBackend {
+    pub fn new(inner: Box<dyn SecretBackend>, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            cache: Mutex::new(None),
+        }
+    }
+}
+
+impl SecretBackend for CachedSecretBackend {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn load(&self) -> Result<HashMap<String, Value>, ConfigError> {
+        if let Some((loaded_at, values)) = self.cache.lock().unwrap().as_ref() {
+            if loaded_at.elapsed() < self.ttl {
+                return Ok(values.clone());
+            }
+        }
+        let values = self.inner.load()?;
+        *self.cache.lock().unwrap() = Some((Instant::now(), values.clone()));
+        Ok(values)
+    }
+
+    fn save(&self, values: &HashMap<String, Value>) -> Result<(), ConfigError> {
+        let saved = self.inner.save(values);
+        *self.cache.lock().unwrap() = match &saved {
+            Ok(()) => Some((Instant::now(), values.clone())),
+            Err(_) => None,
+        };
+        saved
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum VaultAuth {
+    Token(String),
+    /// A file holding the token, read again on each request so rotated tokens are picked up
+    TokenFile(PathBuf),
+    AppRole {
+        role_id: String,
+        secret_id: String,
+    },
+}
+
+struct VaultToken {
+    token: String,
+    expires_at: Option<Instant>,
+}
+
+/// A HashiCorp Vault KV v2 secret
+pub struct VaultBackend {
+    client: reqwest::Client,
+    addr: String,
+    mount: String,
+    path: String,
+    namespace: Option<String>,
+    auth: VaultAuth,
+    token: Mutex<Option<VaultToken>>,
+}
+
+impl VaultBackend {
+    pub fn new(
+        addr: impl Into<String>,
+        mount: impl Into<String>,
+        path: impl Into<String>,
+        namespace: Option<String>,
+        auth: VaultAuth,
+    ) -> Self {
+        // Each call runs on a runtime of its own (see `block_on`), and a pooled connection can't
+        // be used once the runtime that opened it is gone
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .pool_max_idle_per_host(0)
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            addr: addr.into().trim_end_matches('/').to_string(),
+            mount: mount.into().trim_matches('/').to_string(),
+            path: path.into().trim_matches('/').to_string(),
+            namespace,
+            auth,
+            token: Mutex::new(None),
+        }
+    }
+
+    fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        let auth = if let Ok(token) = config.get_param::<String>("VAULT_TOKEN") {
+            VaultAuth::Token(token)
+        } else if let Ok(path) = config.get_param::<PathBuf>("GOOSE_VAULT_TOKEN_FILE") {
+            VaultAuth::TokenFile(path)
+        } else if let (Ok(role_id), Ok(secret_id)) = (
+            config.get_param::<String>("GOOSE_VAULT_ROLE_ID"),
+            config.get_param::<String>("GOOSE_VAULT_SECRET_ID"),
+        ) {
+            VaultAuth::AppRole { role_id, secret_id }
+        } else {
+            return Err(backend_error(
+                "vault",
+                "set VAULT_TOKEN, GOOSE_VAULT_TOKEN_FILE, or GOOSE_VAULT_ROLE_ID and GOOSE_VAULT_SECRET_ID",
+            ));
+        };
+        let addr: String = config
+            .get_param("VAULT_ADDR")
+            .map_err(|_| backend_error("vault", "VAULT_ADDR is not set"))?;
+        Ok(Self::new(
+            addr,
+            config
+                .get_param::<String>("GOOSE_VAULT_MOUNT")
+                .unwrap_or_else(|_| "secret".to_string()),
+            config
+                .get_param::<String>("GOOSE_VAULT_PATH")
+                .unwrap_or_else(|_| "goose".to_string()),
+            config.get_param("VAULT_NAMESPACE").ok(),
+            auth,
+        ))
+    }
+
+    fn secret_url(&self) -> String {
+        format!("{}/v1/{}/data/{}", self.addr, self.mount, self.path)
+    }
+
+    fn request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, url);
+        match &self.namespace {
+            Some(namespace) => request.header("X-Vault-Namespace", namespace),
+            None => request,
+        }
+    }
+
+    async fn token(&self) -> Result<String, ConfigError> {
+        let (role_id, secret_id) = match &self.auth {
+            VaultAuth::Token(token) => return Ok(token.clone()),
+            VaultAuth::TokenFile(path) => {
+                return Ok(std::fs::read_to_string(path)?.trim().to_string());
+            }
+            VaultAuth::AppRole { role_id, secret_id } => (role_id, secret_id),
+        };
+        if let Some(token) = self.token.lock().unwrap().as_ref() {
+            if token
+                .expires_at
+                .is_none_or(|expires_at| Instant::now() + TOKEN_RENEWAL_MARGIN < expires_at)
+            {
+                return Ok(token.token.clone());
+            }
+        }
+
+        let response = self
+            .request(
+                reqwest::Method::POST,
+                &format!("{}/v1/auth/approle/login", self.addr),
+            )
+            .json(&json!({"role_id": role_id, "secret_id": secret_id}))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| backend_error("vault", format!("AppRole login failed: {}", e)))?;
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| backend_error("vault", e))?;
+        let token = body["auth"]["client_token"]
+            .as_str()
+            .ok_or_else(|| backend_error("vault", "AppRole login returned no token"))?
+            .to_string();
+        let expires_at = body["auth"]["lease_duration"]
+            .as_u64()
+            .filter(|seconds| *seconds > 0)
+            .map(|seconds| Instant::now() + Duration::from_secs(seconds));
+        *self.token.lock().unwrap() = Some(VaultToken {
+            token: token.clone(),
+            expires_at,
+        });
+        Ok(token)
+    }
+
+    /// Send `request`, logging in again once if Vault says the token is no longer good
+    async fn send(
+        &self,
+        request: impl Fn(String) -> reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, ConfigError> {
+        let mut retried = false;
+        loop {
+            let response = request(self.token().await?)
+                .send()
+                .await
+                .map_err(|e| backend_error("vault", e))?;
+            if response.status() == reqwest::StatusCode::FORBIDDEN
+                && matches!(self.auth, VaultAuth::AppRole { .. })
+                && !retried
+            {
+                *self.token.lock().unwrap() = None;
+                retried = true;
+                continue;
+            }
+            return Ok(response);
+        }
+    }
+}
+
+impl SecretBackend for VaultBackend {
+    fn name(&self) -> &'static str {
+        "vault"
+    }
+
+    fn load(&self) -> Result<HashMap<String, Value>, ConfigError> {
+        block_on(async {
+            let url = self.secret_url();
+            let response = self
+                .send(|token| {
+                    self.request(reqwest::Method::GET, &url)
+                        .header("X-Vault-Token", token)
+                })
+                .await?;
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(HashMap::new());
+            }
+            let body: Value = response
+                .error_for_status()
+                .map_err(|e| backend_error("vault", e))?
+                .json()
+                .await
+                .map_err(|e| backend_error("vault", e))?;
+            match &body["data"]["data"] {
+                Value::Object(values) => Ok(values.clone().into_iter().collect()),
+                _ => Ok(HashMap::new()),
+            }
+        })
+    }
+
+    fn save(&self, values: &HashMap<String, Value>) -> Result<(), ConfigError> {
+        block_on(async {
+            let url = self.secret_url();
+            let body = json!({ "data": values });
+            self.send(|token| {
+                self.request(reqwest::Method::POST, &url)
+                    .header("X-Vault-Token", token)
+                    .json(&body)
+            })
+            .await?
+            .error_for_status()
+            .map_err(|e| backend_error("vault", e))?;
+            Ok(())
+        })
+    }
+}
+
+/// An AWS Secrets Manager secret holding the secrets as a JSON object
+#[cfg(feature = "aws-secrets")]
+pub struct AwsSecretsManagerBackend {
+    secret_id: String,
+    region: Option<String>,
+}
+
+#[cfg(feature = "aws-secrets")]
+impl AwsSecretsManagerBackend {
+    pub fn new(secret_id: impl Into<String>, region: Option<String>) -> Self {
+        Self {
+            secret_id: secret_id.into(),
+            region,
+        }
+    }
+
+    // The client is made for each request because it belongs to the runtime that made it;
+    // the SDK fetches fresh credentials when the old ones expire
+    async fn client(&self) -> aws_sdk_secretsmanager::Client {
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest());
+        if let Some(region) = &self.region {
+            loader = loader.region(aws_config::Region::new(region.clone()));
+        }
+        aws_sdk_secretsmanager::Client::new(&loader.load().await)
+    }
+}
+
+#[cfg(feature = "aws-secrets")]
+impl SecretBackend for AwsSecretsManagerBackend {
+    fn name(&self) -> &'static str {
+        "aws_secrets_manager"
+    }
+
+    fn load(&self) -> Result<HashMap<String, Value>, ConfigError> {
+        block_on(async {
+            let result = self
+                .client()
+                .await
+                .get_secret_value()
+                .secret_id(&self.secret_id)
+                .send()
+                .await;
+            let output = match result {
+                Ok(output) => output,
+                Err(e)
+                    if e.as_service_error()
+                        .is_some_and(|e| e.is_resource_not_found_exception()) =>
+                {
+                    return Ok(HashMap::new());
+                }
+                Err(e) => return Err(backend_error(self.name(), e)),
+            };
+            match output.secret_string() {
+                Some(content) => Ok(serde_json::from_str(content)?),
+                None => Ok(HashMap::new()),
+            }
+        })
+    }
+
+    fn save(&self, values: &HashMap<String, Value>) -> Result<(), ConfigError> {
+        let content = serde_json::to_string(values)?;
+        block_on(async {
+            let client = self.client().await;
+            let result = client
+                .put_secret_value()
+                .secret_id(&self.secret_id)
+                .secret_string(&content)
+                .send()
+                .await;
+            match result {
+                Ok(_) => Ok(()),
+                Err(e)
+                    if e.as_service_error()
+                        .is_some_and(|e| e.is_resource_not_found_exception()) =>
+                {
+                    client
+                        .create_secret()
+                        .name(&self.secret_id)
+                        .secret_string(&content)
+                        .send()
+                        .await
+                        .map_err(|e| backend_error(self.name(), e))?;
+                    Ok(())
+                }
+                Err(e) => Err(backend_error(self.name(), e)),
+            }
+        })
+    }
+}
+
+/// The secret backend `config` asks for, if it asks for one other than the keyring or the
+/// secrets file
+pub fn backend_from_config(config: &Config) -> Result<Option<Box<dyn SecretBackend>>, ConfigError> {
+    let Ok(name) = config.get_param::<String>(SECRET_BACKEND_CONFIG_KEY) else {
+        return Ok(None);
+    };
+    let backend: Box<dyn SecretBackend> = match name.to_lowercase().as_str() {
+        "keyring" | "file" | "" => return Ok(None),
+        "vault" => Box::new(VaultBackend::from_config(config)?),
+        #[cfg(feature = "aws-secrets")]
+        "aws_secrets_manager" => Box::new(AwsSecretsManagerBackend::new(
+            config
+                .get_param::<String>("GOOSE_AWS_SECRET_ID")
+                .unwrap_or_else(|_| "goose".to_string()),
+            config.get_param("GOOSE_AWS_SECRETS_REGION").ok(),
+        )),
+        #[cfg(not(feature = "aws-secrets"))]
+        "aws_secrets_manager" => {
+            return Err(backend_error(
+                "aws_secrets_manager",
+                "this build of goose doesn't include the aws-secrets feature",
+            ))
+        }
+        other => {
+            return Err(ConfigError::SecretBackendError(format!(
+                "Unknown secret backend '{}'; use keyring, file, vault or aws_secrets_manager",
+                other
+            )))
+        }
+    };
+    let ttl = config
+        .get_param::<u64>("GOOSE_SECRETS_CACHE_TTL")
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_CACHE_TTL);
+    Ok(Some(Box::new(CachedSecretBackend::new(backend, ttl))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    struct CountingBackend {
+        loads: Arc<AtomicUsize>,
+    }
+
+    impl SecretBackend for CountingBackend {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        fn load(&self) -> Result<HashMap<String, Value>, ConfigError> {
+            self.loads.fetch_add(1, Ordering::SeqCst);
+            Ok(HashMap::from([("KEY".to_string(), json!("value"))]))
+        }
+
+        fn save(&self, _values: &HashMap<String, Value>) -> Result<(), ConfigError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_cached_backend_reloads_after_ttl() {
+        let loads = Arc::new(AtomicUsize::new(0));
+        let cached = CachedSecretBackend::new(
+            Box::new(CountingBackend {
+                loads: loads.clone(),
+            }),
+            Duration::from_secs(60),
+        );
+        cached.load().unwrap();
+        cached.load().unwrap();
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+        cached
+            .save(&HashMap::from([("KEY".to_string(), json!("new"))]))
+            .unwrap();
+        assert_eq!(cached.load().unwrap()["KEY"], json!("new"));
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+        let uncached = CachedSecretBackend::new(
+            Box::new(CountingBackend {
+                loads: loads.clone(),
+            }),
+            Duration::ZERO,
+        );
+        uncached.load().unwrap();
+        uncached.load().unwrap();
+        assert_eq!(loads.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_vault_backend_logs_in_with_approle() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/auth/approle/login"))
+            .and(body_json(json!({"role_id": "role", "secret_id": "secret"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "auth": {"client_token": "s.short-lived", "lease_duration": 3600}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/secret/data/goose"))
+            .and(header("X-Vault-Token", "s.short-lived"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": {"data": {"OPENAI_API_KEY": "sk-test"}}
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/secret/data/goose"))
+            .and(body_json(json!({"data": {"OPENAI_API_KEY": "sk-new"}})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let backend = VaultBackend::new(
+            server.uri(),
+            "secret",
+            "goose",
+            None,
+            VaultAuth::AppRole {
+                role_id: "role".to_string(),
+                secret_id: "secret".to_string(),
+            },
+        );
+        let values = tokio::task::spawn_blocking(move || {
+            let values = backend.load().unwrap();
+            backend
+                .save(&HashMap::from([(
+                    "OPENAI_API_KEY".to_string(),
+                    json!("sk-new"),
+                )]))
+                .unwrap();
+            values
+        })
+        .await
+        .unwrap();
+        assert_eq!(values["OPENAI_API_KEY"], json!("sk-test"));
+    }
+}
@@ -0,0 +1,272 @@
+//! Picking up config changes while goose runs.
+//!
+//! Most settings are read from the config file each time they are used, so editing the file
+//! already changes what running sessions do: tool policies, pricing, budgets and so on. The
+//! watcher notices the edit, applies the settings that live in memory (the log filter and rate
+//! limits), and sends out a [`ConfigChangeEvent`] listing what changed. Settings that are only
+//! read when a session or provider is created, like the model or extensions, are flagged as
+//! needing a restart instead.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+
+use crate::config::migrations::CONFIG_VERSION_KEY;
+use crate::config::Config;
+use crate::logging::LOG_LEVEL_CONFIG_KEY;
+use crate::providers::rate_limit::reload_rate_limits;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const EVENT_CHANNEL_CAPACITY: usize = 16;
+
+/// Settings read when a session, provider or extension starts
+const RESTART_KEYS: &[&str] = &[
+    "GOOSE_PROVIDER",
+    "GOOSE_MODEL",
+    "GOOSE_LEAD_PROVIDER",
+    "GOOSE_LEAD_MODEL",
+    "GOOSE_LEAD_TURNS",
+    "GOOSE_LEAD_FAILURE_THRESHOLD",
+    "GOOSE_LEAD_FALLBACK_TURNS",
+    "GOOSE_PLANNER_PROVIDER",
+    "GOOSE_PLANNER_MODEL",
+    "GOOSE_SEARCH_PATHS",
+    "GOOSE_MAX_ACTIVE_AGENTS",
+    "GOOSE_MODEL_CATALOG",
+    "GOOSE_MODEL_PRESETS",
+    "GOOSE_SECRET_BACKEND",
+    "GOOSE_DISABLE_KEYRING",
+    "extensions",
+];
+const RESTART_PREFIXES: &[&str] = &[
+    "GOOSE_VAULT_",
+    "GOOSE_AWS_SECRET",
+    "GOOSE_REMOTE_CONFIG_",
+    "OTEL_",
+    "LANGFUSE_",
+];
+const RATE_LIMIT_SUFFIXES: &[&str] = &["_REQUESTS_PER_MINUTE", "_TOKENS_PER_MINUTE"];
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReloadBehavior {
+    /// Running sessions use the new value
+    Live,
+    /// Only sessions started after a restart use the new value
+    RequiresRestart,
+}
+
+/// How a change to `key` reaches running sessions
+pub fn reload_behavior(key: &str) -> ReloadBehavior {
+    let is_rate_limit = RATE_LIMIT_SUFFIXES.iter().any(|s| key.ends_with(s));
+    if RESTART_KEYS.contains(&key) || RESTART_PREFIXES.iter().any(|p| key.starts_with(p)) {
+        ReloadBehavior::RequiresRestart
+    } else if key.starts_with("GOOSE_") || key == CONFIG_VERSION_KEY || is_rate_limit {
+        ReloadBehavior::Live
+    } else {
+        // Provider settings such as hosts are read when the provider is created
+        ReloadBehavior::RequiresRestart
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct ConfigChange {
+    pub key: String,
+    pub behavior: ReloadBehavior,
+}
+
+/// The settings that changed in one edit of the config file. Only keys are included, as values
+/// may be sensitive.
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct ConfigChangeEvent {
+    pub changes: Vec<ConfigChange>,
+}
+
+impl ConfigChangeEvent {
+    pub fn from_keys(keys: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            changes: keys
+                .into_iter()
+                .map(|key| ConfigChange {
+                    behavior: reload_behavior(&key),
+                    key,
+                })
+                .collect(),
+        }
+    }
+
+    /// Keys whose new values only apply after a restart
+    pub fn requires_restart(&self) -> Vec<&str> {
+        self.changes
+            .iter()
+            .filter(|change| change.behavior == ReloadBehavior::RequiresRestart)
+            .map(|change| change.key.as_str())
+            .collect()
+    }
+
+    fn touches(&self, predicate: impl Fn(&str) -> bool) -> bool {
+        self.changes.iter().any(|change| predicate(&change.key))
+    }
+}
+
+/// Keys that were added, removed or given a different value, in order
+pub fn changed_keys(old: &HashMap<String, Value>, new: &HashMap<String, Value>) -> Vec<String> {
+    old.keys()
+        .chain(new.keys())
+        .filter(|key| old.get(*key) != new.get(*key))
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+fn apply(event: &ConfigChangeEvent) {
+    if event.touches(|key| key == LOG_LEVEL_CONFIG_KEY) {
+        if let Err(e) = crate::logging::reload_log_level() {
+            tracing::warn!("Failed to apply the new log level: {}", e);
+        }
+    }
+    if event.touches(|key| RATE_LIMIT_SUFFIXES.iter().any(|s| key.ends_with(s))) {
+        reload_rate_limits();
+    }
+}
+
+pub struct ConfigWatcher {
+    path: PathBuf,
+    sender: broadcast::Sender<ConfigChangeEvent>,
+}
+
+impl ConfigWatcher {
+    /// Watch the file of the global config
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            path: PathBuf::from(Config::global().path()),
+            sender,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ConfigChangeEvent> {
+        self.sender.subscribe()
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(&self.path)
+            .and_then(|m| m.modified())
+            .ok()
+    }
+
+    /// Check the config file for changes in the background. Subscribe to the returned sender
+    /// for change events.
+    pub fn spawn(self) -> broadcast::Sender<ConfigChangeEvent> {
+        let sender = self.sender.clone();
+        tokio::spawn(async move { self.run().await });
+        sender
+    }
+
+    async fn run(self) {
+        let config = Config::global();
+        let mut modified = self.modified();
+        let mut values = config.all_values().unwrap_or_default();
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let now_modified = self.modified();
+            if now_modified == modified {
+                continue;
+            }
+            modified = now_modified;
+
+            let new_values = match config.all_values() {
+                Ok(new_values) => new_values,
+                Err(e) => {
+                    tracing::warn!("Ignoring config change that can't be read: {}", e);
+                    continue;
+                }
+            };
+            let keys = changed_keys(&values, &new_values);
+            values = new_values;
+            if keys.is_empty() {
+                continue;
+            }
+
+            let event = ConfigChangeEvent::from_keys(keys);
+            apply(&event);
+            let restart = event.requires_restart();
+            if restart.is_empty() {
+                tracing::info!("Applied config changes: {:?}", event.changes);
+            } else {
+                tracing::warn!(
+                    "Config changed; restart for these to take effect: {}",
+                    restart.join(", ")
+                );
+            }
+            // Nobody listening is fine
+            let _ = self.sender.send(event);
+        }
+    }
+}
+
+impl Default for ConfigWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_changed_keys() {
+        let old = HashMap::from([
+            ("GOOSE_MODEL".to_string(), json!("gpt-4o")),
+            ("GOOSE_LOG_LEVEL".to_string(), json!("info")),
+            ("GOOSE_MAX_TURNS".to_string(), json!(100)),
+        ]);
+        let new = HashMap::from([
+            ("GOOSE_MODEL".to_string(), json!("gpt-4o")),
+            ("GOOSE_LOG_LEVEL".to_string(), json!("debug")),
+            ("ANTHROPIC_TOKENS_PER_MINUTE".to_string(), json!(40000)),
+        ]);
+        assert_eq!(
+            changed_keys(&old, &new),
+            vec![
+                "ANTHROPIC_TOKENS_PER_MINUTE",
+                "GOOSE_LOG_LEVEL",
+                "GOOSE_MAX_TURNS"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_change_event_flags_restart() {
+        let event = ConfigChangeEvent::from_keys(
+            [
+                "GOOSE_TOOL_POLICIES",
+                "ANTHROPIC_TOKENS_PER_MINUTE",
+                "GOOSE_MODEL",
+                "OPENAI_HOST",
+                "GOOSE_VAULT_ADDR",
+                "extensions",
+            ]
+            .map(String::from),
+        );
+        assert_eq!(
+            event.requires_restart(),
+            vec![
+                "GOOSE_MODEL",
+                "OPENAI_HOST",
+                "GOOSE_VAULT_ADDR",
+                "extensions"
+            ]
+        );
+        assert_eq!(reload_behavior("GOOSE_LOG_LEVEL"), ReloadBehavior::Live);
+    }
+}
@@ -1,16 +1,21 @@
+use crate::config::Config;
 use crate::conversation::message::{ActionRequiredData, MessageMetadata};
 use crate::conversation::message::{Message, MessageContent};
 use crate::conversation::{merge_consecutive_messages, Conversation};
 use crate::prompt_template::render_global_file;
 use crate::providers::base::{Provider, ProviderUsage};
 use crate::providers::errors::ProviderError;
-use crate::{config::Config, token_counter::create_token_counter};
 use anyhow::Result;
-use rmcp::model::Role;
+use rmcp::model::{AnnotateAble, RawContent, Role};
 use serde::Serialize;
+use std::fmt;
 use tracing::{debug, info};
 
 pub const DEFAULT_COMPACTION_THRESHOLD: f64 = 0.8;
+pub const DEFAULT_KEEP_RECENT_TURNS: usize = 2;
+pub const DEFAULT_REDUCTION_ATTEMPTS: usize = 3;
+
+const ELIDED_TOOL_OUTPUT_TEXT: &str = "[Tool output removed to fit the context window]";
 
 const CONVERSATION_CONTINUATION_TEXT: &str =
     "The previous message contains a summary that was prepared because a context limit was reached.
@@ -22,6 +27,12 @@ const TOOL_LOOP_CONTINUATION_TEXT: &str =
 Do not mention that you read a summary or that conversation summarization occurred.
 Continue calling tools as necessary to complete the task.";
 
+const RECENT_TURNS_CONTINUATION_TEXT: &str =
+    "The previous message contains a summary of the earlier conversation that was prepared because a context limit was reached.
+The most recent messages follow unchanged.
+Do not mention that you read a summary or that conversation summarization occurred.
+Just continue the conversation naturally based on the summarized context and the recent messages";
+
 const MANUAL_COMPACT_CONTINUATION_TEXT: &str =
     "The previous message contains a summary that was prepared at the user's request.
 Do not mention that you read a summary or that conversation summarization occurred.
@@ -32,12 +43,238 @@ struct SummarizeContext {
     messages: String,
 }
 
+/// How automatic compaction decides when to run and what it keeps
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactionConfig {
+    /// Fraction of the context limit at which compaction runs; 0 or 1 and above disable it
+    pub threshold: f64,
+    /// Number of most recent turns kept verbatim rather than summarized
+    pub keep_recent_turns: usize,
+    /// Model used to write the summary instead of the provider's fast model
+    pub summarizer_model: Option<String>,
+    /// Times [`reduce_context`] is tried after a context length error before compacting
+    pub reduction_attempts: usize,
+}
+
+impl Default for CompactionConfig {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_COMPACTION_THRESHOLD,
+            keep_recent_turns: DEFAULT_KEEP_RECENT_TURNS,
+            summarizer_model: None,
+            reduction_attempts: DEFAULT_REDUCTION_ATTEMPTS,
+        }
+    }
+}
+
+impl CompactionConfig {
+    /// Read GOOSE_AUTO_COMPACT_THRESHOLD, GOOSE_COMPACTION_KEEP_TURNS, GOOSE_COMPACTION_MODEL and
+    /// GOOSE_CONTEXT_REDUCTION_ATTEMPTS
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        Self {
+            threshold: config
+                .get_param("GOOSE_AUTO_COMPACT_THRESHOLD")
+                .unwrap_or(DEFAULT_COMPACTION_THRESHOLD),
+            keep_recent_turns: config
+                .get_param("GOOSE_COMPACTION_KEEP_TURNS")
+                .unwrap_or(DEFAULT_KEEP_RECENT_TURNS),
+            summarizer_model: config.get_param("GOOSE_COMPACTION_MODEL").ok(),
+            reduction_attempts: config
+                .get_param("GOOSE_CONTEXT_REDUCTION_ATTEMPTS")
+                .unwrap_or(DEFAULT_REDUCTION_ATTEMPTS),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.threshold > 0.0 && self.threshold < 1.0
+    }
+}
+
+/// A user message with text and no tool results starts a new turn
+pub(crate) fn is_turn_start(msg: &Message) -> bool {
+    let has_text = msg
+        .content
+        .iter()
+        .any(|c| matches!(c, MessageContent::Text(_)));
+    let has_tool_content = msg.content.iter().any(|c| {
+        matches!(
+            c,
+            MessageContent::ToolRequest(_) | MessageContent::ToolResponse(_)
+        )
+    });
+    msg.is_agent_visible() && msg.role == Role::User && has_text && !has_tool_content
+}
+
+/// What a [`reduce_context`] step removed from what the model sees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextReduction {
+    /// The outputs of this many older tool calls were replaced with a placeholder
+    ToolOutputs(usize),
+    /// The oldest turn, made up of this many messages, was hidden
+    OldestTurn(usize),
+}
+
+impl fmt::Display for ContextReduction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContextReduction::ToolOutputs(1) => {
+                write!(f, "Removed the output of 1 older tool call")
+            }
+            ContextReduction::ToolOutputs(count) => {
+                write!(f, "Removed the output of {} older tool calls", count)
+            }
+            ContextReduction::OldestTurn(messages) => {
+                write!(f, "Removed the oldest turn ({} messages)", messages)
+            }
+        }
+    }
+}
+
+fn has_tool_output(msg: &Message) -> bool {
+    msg.content.iter().any(
+        |c| matches!(c, MessageContent::ToolResponse(response) if response.tool_result.is_ok()),
+    )
+}
+
+fn is_elided(msg: &Message) -> bool {
+    msg.content.iter().any(|c| match c {
+        MessageContent::ToolResponse(response) => {
+            response.tool_result.as_ref().is_ok_and(|result| {
+                result.content.iter().any(|content| {
+                    content
+                        .as_text()
+                        .is_some_and(|text| text.text == ELIDED_TOOL_OUTPUT_TEXT)
+                })
+            })
+        }
+        _ => false,
+    })
+}
+
+/// An agent-only copy of `msg` with its tool outputs replaced by a placeholder
+fn elide_tool_outputs(msg: &Message) -> Message {
+    let mut elided = msg.clone().with_metadata(MessageMetadata::agent_only());
+    for content in &mut elided.content {
+        if let MessageContent::ToolResponse(response) = content {
+            if let Ok(result) = &mut response.tool_result {
+                result.content = vec![RawContent::text(ELIDED_TOOL_OUTPUT_TEXT).no_annotation()];
+                result.structured_content = None;
+            }
+        }
+    }
+    elided
+}
+
+/// Shrink what the model sees without summarizing: first replace the outputs of the older
+/// half of the tool calls with a placeholder, keeping the latest output, and once only that is
+/// left, hide the oldest turn. Hidden messages stay visible to the user. Returns None when
+/// nothing is left to remove.
+pub fn reduce_context(conversation: &Conversation) -> Option<(Conversation, ContextReduction)> {
+    let messages = conversation.messages();
+
+    let mut outputs: Vec<usize> = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, msg)| msg.is_agent_visible() && has_tool_output(msg) && !is_elided(msg))
+        .map(|(idx, _)| idx)
+        .collect();
+    outputs.pop();
+    if !outputs.is_empty() {
+        let to_elide = &outputs[..outputs.len().div_ceil(2)];
+        let mut reduced = Vec::with_capacity(messages.len() + to_elide.len());
+        for (idx, msg) in messages.iter().enumerate() {
+            if to_elide.contains(&idx) {
+                let metadata = msg.metadata.clone().with_agent_invisible();
+                reduced.push(msg.clone().with_metadata(metadata));
+                reduced.push(elide_tool_outputs(msg));
+            } else {
+                reduced.push(msg.clone());
+            }
+        }
+        return Some((
+            Conversation::new_unvalidated(reduced),
+            ContextReduction::ToolOutputs(to_elide.len()),
+        ));
+    }
+
+    let second_turn = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, msg)| is_turn_start(msg))
+        .map(|(idx, _)| idx)
+        .nth(1)?;
+    let mut hidden = 0;
+    let reduced = messages
+        .iter()
+        .enumerate()
+        .map(|(idx, msg)| {
+            if idx < second_turn && msg.is_agent_visible() {
+                hidden += 1;
+                let metadata = msg.metadata.clone().with_agent_invisible();
+                msg.clone().with_metadata(metadata)
+            } else {
+                msg.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+    Some((
+        Conversation::new_unvalidated(reduced),
+        ContextReduction::OldestTurn(hidden),
+    ))
+}
+
+/// Index of the first message to keep verbatim: the start of the oldest of the last
+/// `keep_recent_turns` turns whose messages fit in half the compaction threshold, so the
+/// compacted conversation stays well below it. Returns `messages.len()` when no recent turn fits.
+async fn recent_turns_start(
+    provider: &dyn Provider,
+    messages: &[Message],
+    config: &CompactionConfig,
+) -> usize {
+    let threshold = if config.is_enabled() {
+        config.threshold
+    } else {
+        DEFAULT_COMPACTION_THRESHOLD
+    };
+    let budget = (provider.get_model_config().context_limit() as f64 * threshold / 2.0) as usize;
+
+    let turn_starts: Vec<usize> = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, msg)| is_turn_start(msg))
+        .map(|(idx, _)| idx)
+        .collect();
+    let candidates = &turn_starts[turn_starts.len().saturating_sub(config.keep_recent_turns)..];
+
+    for &start in candidates {
+        let recent: Vec<Message> = messages[start..]
+            .iter()
+            .filter(|msg| msg.is_agent_visible())
+            .cloned()
+            .collect();
+        match provider.count_tokens("", &recent, &[]).await {
+            Ok(tokens) if tokens <= budget => return start,
+            Ok(_) => continue,
+            Err(e) => {
+                debug!("Could not count tokens of recent turns: {}", e);
+                break;
+            }
+        }
+    }
+    messages.len()
+}
+
 /// Compact messages by summarizing them
 ///
 /// This function performs the actual compaction by summarizing messages and updating
 /// their visibility metadata. It does not check thresholds - use `check_if_compaction_needed`
 /// first to determine if compaction is necessary.
 ///
+/// Automatic compactions keep the most recent turns verbatim, as configured by
+/// [`CompactionConfig`], and summarize only the turns before them. When no recent turn fits,
+/// everything is summarized and only the latest user message is repeated.
+///
 /// # Arguments
 /// * `provider` - The provider to use for summarization
 /// * `conversation` - The current conversation history
@@ -55,20 +292,15 @@ pub async fn compact_messages(
     info!("Performing message compaction");
 
     let messages = conversation.messages();
+    let compaction = CompactionConfig::from_config();
+    let summarizer_model = compaction.summarizer_model.as_deref();
 
-    let has_text_only = |msg: &Message| {
-        let has_text = msg
-            .content
-            .iter()
-            .any(|c| matches!(c, MessageContent::Text(_)));
-        let has_tool_content = msg.content.iter().any(|c| {
-            matches!(
-                c,
-                MessageContent::ToolRequest(_) | MessageContent::ToolResponse(_)
-            )
-        });
-        has_text && !has_tool_content
-    };
+    if !manual_compact {
+        let split = recent_turns_start(provider, messages, &compaction).await;
+        if split > 0 && split < messages.len() {
+            return compact_older_turns(provider, messages, split, summarizer_model).await;
+        }
+    }
 
     let extract_text = |msg: &Message| -> Option<String> {
         let text_parts: Vec<String> = msg
@@ -92,11 +324,11 @@ pub async fn compact_messages(
 
     // Find and preserve the most recent user message for non-manual compacts
     let (preserved_user_message, is_most_recent) = if !manual_compact {
-        let found_msg = messages.iter().enumerate().rev().find(|(_, msg)| {
-            msg.is_agent_visible()
-                && matches!(msg.role, rmcp::model::Role::User)
-                && has_text_only(msg)
-        });
+        let found_msg = messages
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, msg)| is_turn_start(msg));
 
         if let Some((idx, msg)) = found_msg {
             let is_last = idx == messages.len() - 1;
@@ -110,7 +342,8 @@ pub async fn compact_messages(
 
     let messages_to_compact = messages.as_slice();
 
-    let (summary_message, summarization_usage) = do_compact(provider, messages_to_compact).await?;
+    let (summary_message, summarization_usage) =
+        do_compact(provider, messages_to_compact, summarizer_model).await?;
 
     // Create the final message list with updated visibility metadata:
     // 1. Original messages become user_visible but not agent_visible
@@ -126,7 +359,7 @@ pub async fn compact_messages(
             // This is the most recent message and we're preserving it by adding a fresh copy
             MessageMetadata::invisible()
         } else {
-            msg.metadata.with_agent_invisible()
+            msg.metadata.clone().with_agent_invisible()
         };
         let updated_msg = msg.clone().with_metadata(updated_metadata);
         final_messages.push(updated_msg);
@@ -164,6 +397,41 @@ pub async fn compact_messages(
     ))
 }
 
+/// Summarize the messages before `split` and keep the rest verbatim after the summary
+async fn compact_older_turns(
+    provider: &dyn Provider,
+    messages: &[Message],
+    split: usize,
+    summarizer_model: Option<&str>,
+) -> Result<(Conversation, ProviderUsage)> {
+    let (older, recent) = messages.split_at(split);
+    let (summary_message, summarization_usage) =
+        do_compact(provider, older, summarizer_model).await?;
+
+    let mut final_messages: Vec<Message> = older
+        .iter()
+        .map(|msg| {
+            let metadata = msg.metadata.clone().with_agent_invisible();
+            msg.clone().with_metadata(metadata)
+        })
+        .collect();
+
+    let continuation_messages = vec![
+        summary_message.with_metadata(MessageMetadata::agent_only()),
+        Message::assistant()
+            .with_text(RECENT_TURNS_CONTINUATION_TEXT)
+            .with_metadata(MessageMetadata::agent_only()),
+    ];
+    let (merged_continuation, _issues) = merge_consecutive_messages(continuation_messages);
+    final_messages.extend(merged_continuation);
+    final_messages.extend(recent.iter().cloned());
+
+    Ok((
+        Conversation::new_unvalidated(final_messages),
+        summarization_usage,
+    ))
+}
+
 /// Check if messages exceed the auto-compaction threshold
 pub async fn check_if_compaction_needed(
     provider: &dyn Provider,
@@ -172,29 +440,24 @@ pub async fn check_if_compaction_needed(
     session: &crate::session::Session,
 ) -> Result<bool> {
     let messages = conversation.messages();
-    let config = Config::global();
-    let threshold = threshold_override.unwrap_or_else(|| {
-        config
-            .get_param::<f64>("GOOSE_AUTO_COMPACT_THRESHOLD")
-            .unwrap_or(DEFAULT_COMPACTION_THRESHOLD)
-    });
+    let threshold = threshold_override.unwrap_or_else(|| CompactionConfig::from_config().threshold);
 
     let context_limit = provider.get_model_config().context_limit();
 
     let (current_tokens, token_source) = match session.total_tokens {
         Some(tokens) => (tokens as usize, "session metadata"),
         None => {
-            let token_counter = create_token_counter()
-                .await
-                .map_err(|e| anyhow::anyhow!("Failed to create token counter: {}", e))?;
-
-            let token_counts: Vec<_> = messages
+            let visible: Vec<Message> = messages
                 .iter()
                 .filter(|m| m.is_agent_visible())
-                .map(|msg| token_counter.count_chat_tokens("", std::slice::from_ref(msg), &[]))
+                .cloned()
                 .collect();
+            let tokens = provider
+                .count_tokens("", &visible, &[])
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to count tokens: {}", e))?;
 
-            (token_counts.iter().sum(), "estimated")
+            (tokens, "counted")
         }
     };
 
@@ -269,9 +532,29 @@ fn filter_tool_responses<'a>(messages: &[&'a Message], remove_percent: u32) -> V
         .collect()
 }
 
+/// Complete with `summarizer_model` if one is configured and the fast model otherwise
+async fn complete_summary(
+    provider: &dyn Provider,
+    summarizer_model: Option<&str>,
+    system: &str,
+    messages: &[Message],
+) -> Result<(Message, ProviderUsage), ProviderError> {
+    match summarizer_model {
+        Some(model) => {
+            let mut model_config = provider.get_model_config();
+            model_config.model_name = model.to_string();
+            provider
+                .complete_with_model(&model_config, system, messages, &[])
+                .await
+        }
+        None => provider.complete_fast(system, messages, &[]).await,
+    }
+}
+
 async fn do_compact(
     provider: &dyn Provider,
     messages: &[Message],
+    summarizer_model: Option<&str>,
 ) -> Result<(Message, ProviderUsage), anyhow::Error> {
     let agent_visible_messages: Vec<&Message> = messages
         .iter()
@@ -300,9 +583,13 @@ async fn do_compact(
             .with_text("Please summarize the conversation history provided in the system prompt.");
         let summarization_request = vec![user_message];
 
-        match provider
-            .complete_fast(&system_prompt, &summarization_request, &[])
-            .await
+        match complete_summary(
+            provider,
+            summarizer_model,
+            &system_prompt,
+            &summarization_request,
+        )
+        .await
         {
             Ok((mut response, mut provider_usage)) => {
                 response.role = Role::User;
@@ -341,6 +628,12 @@ fn format_message_for_compacting(msg: &Message) -> String {
         .map(|content| match content {
             MessageContent::Text(text) => text.text.clone(),
             MessageContent::Image(img) => format!("[image: {}]", img.mime_type),
+            MessageContent::Audio(audio) => format!("[audio: {}]", audio.mime_type),
+            MessageContent::Document(doc) => format!(
+                "[document: {} ({})]",
+                doc.name.as_deref().unwrap_or("unnamed"),
+                doc.mime_type
+            ),
             MessageContent::ToolRequest(req) => {
                 if let Ok(call) = &req.tool_call {
                     format!(
@@ -444,6 +737,14 @@ mod tests {
                     toolshim: false,
                     toolshim_model: None,
                     fast_model: None,
+                    tool_choice: None,
+                    parallel_tool_calls: None,
+                    reasoning_effort: None,
+                    thinking_budget_tokens: None,
+                    seed: None,
+                    logprobs: None,
+                    response_schema: None,
+                    additional_request_fields: None,
                 },
                 max_tool_responses: None,
             }
@@ -537,6 +838,91 @@ mod tests {
             .expect("compaction should produce a valid conversation");
     }
 
+    #[tokio::test]
+    async fn test_keeps_recent_turns_verbatim() {
+        let response_message = Message::assistant().with_text("<mock summary>");
+        let provider = MockProvider::new(response_message, 100_000);
+        let mut messages = Vec::new();
+        for turn in ["first", "second"] {
+            messages.push(Message::user().with_text(format!("{} question", turn)));
+            messages.push(Message::assistant().with_text(format!("{} answer", turn)));
+        }
+        messages.push(Message::user().with_text("third question"));
+
+        let conversation = Conversation::new_unvalidated(messages);
+        let (compacted_conversation, _usage) = compact_messages(&provider, &conversation, false)
+            .await
+            .unwrap();
+
+        let agent_conversation = compacted_conversation.agent_visible_messages();
+        let texts: Vec<String> = agent_conversation
+            .iter()
+            .map(|msg| msg.as_concat_text())
+            .collect();
+        assert_eq!(texts.len(), 5);
+        assert_eq!(texts[0], "<mock summary>");
+        assert_eq!(texts[1], RECENT_TURNS_CONTINUATION_TEXT);
+        assert_eq!(
+            texts[2..],
+            ["second question", "second answer", "third question"]
+        );
+        assert!(!compacted_conversation.messages()[0].is_agent_visible());
+        let _ = Conversation::new(agent_conversation)
+            .expect("compaction should produce a valid conversation");
+    }
+
+    #[test]
+    fn test_reduce_context_drops_tool_outputs_then_turns() {
+        let tool_exchange = |id: &str, output: &str| {
+            vec![
+                Message::assistant().with_tool_request(
+                    id,
+                    Ok(CallToolRequestParam {
+                        name: "read_file".into(),
+                        arguments: None,
+                    }),
+                ),
+                Message::user().with_tool_response(
+                    id,
+                    Ok(rmcp::model::CallToolResult {
+                        content: vec![RawContent::text(output).no_annotation()],
+                        structured_content: None,
+                        is_error: Some(false),
+                        meta: None,
+                    }),
+                ),
+            ]
+        };
+        let mut messages = vec![Message::user().with_text("first")];
+        messages.extend(tool_exchange("tool_0", "out0"));
+        messages.extend(tool_exchange("tool_1", "out1"));
+        messages.push(Message::assistant().with_text("done"));
+        messages.push(Message::user().with_text("second"));
+        messages.extend(tool_exchange("tool_2", "out2"));
+
+        let mut conversation = Conversation::new_unvalidated(messages);
+        let mut reductions = Vec::new();
+        while let Some((reduced, reduction)) = reduce_context(&conversation) {
+            conversation = reduced;
+            reductions.push(reduction);
+        }
+        assert_eq!(
+            reductions,
+            [
+                ContextReduction::ToolOutputs(1),
+                ContextReduction::ToolOutputs(1),
+                ContextReduction::OldestTurn(6),
+            ]
+        );
+
+        let agent_conversation = conversation.agent_visible_messages();
+        assert_eq!(agent_conversation.len(), 3);
+        assert_eq!(agent_conversation[0].as_concat_text(), "second");
+        assert_eq!(conversation.messages().len(), 11);
+        let _ = Conversation::new(agent_conversation)
+            .expect("reduction should produce a valid conversation");
+    }
+
     #[tokio::test]
     async fn test_progressive_removal_on_context_exceeded() {
         let response_message = Message::assistant().with_text("<mock summary>");
@@ -0,0 +1,182 @@
+//! Structural diff between two conversations, such as a session and a fork of it, or the same
+//! prompt run with and without streaming.
+//!
+//! Messages are compared by role and content; ids, timestamps and metadata are ignored. Tool
+//! call ids are compared by the order in which they first appear, since every run of a model
+//! makes up new ones.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::message::{Message, MessageContent};
+use super::Conversation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum MessageDiff {
+    /// The message is the same in both conversations
+    Unchanged { left: usize, right: usize },
+    /// A message in the same place, by the same role, with different content
+    Changed { left: usize, right: usize },
+    /// Only the left conversation has this message
+    Removed { left: usize },
+    /// Only the right conversation has this message
+    Added { right: usize },
+}
+
+/// The alignment of two conversations, message by message, with indices into each
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConversationDiff {
+    pub entries: Vec<MessageDiff>,
+}
+
+impl ConversationDiff {
+    /// Whether the conversations are structurally the same
+    pub fn is_identical(&self) -> bool {
+        self.entries
+            .iter()
+            .all(|entry| matches!(entry, MessageDiff::Unchanged { .. }))
+    }
+
+    /// The entries that aren't [`MessageDiff::Unchanged`]
+    pub fn changes(&self) -> impl Iterator<Item = &MessageDiff> {
+        self.entries
+            .iter()
+            .filter(|entry| !matches!(entry, MessageDiff::Unchanged { .. }))
+    }
+}
+
+/// `message` with tool call ids replaced by their order of first appearance in `ids`
+fn normalize(message: &Message, ids: &mut HashMap<String, usize>) -> Message {
+    let mut normalized = Message::new(message.role.clone(), 0, message.content.clone());
+    for content in &mut normalized.content {
+        let id = match content {
+            MessageContent::ToolRequest(request) => &mut request.id,
+            MessageContent::ToolResponse(response) => &mut response.id,
+            MessageContent::FrontendToolRequest(request) => &mut request.id,
+            MessageContent::ToolConfirmationRequest(request) => &mut request.id,
+            _ => continue,
+        };
+        let next = ids.len();
+        *id = ids.entry(id.clone()).or_insert(next).to_string();
+    }
+    normalized
+}
+
+fn normalize_all(conversation: &Conversation) -> Vec<Message> {
+    let mut ids = HashMap::new();
+    conversation
+        .messages()
+        .iter()
+        .map(|msg| normalize(msg, &mut ids))
+        .collect()
+}
+
+/// Align `left` and `right` along their longest common subsequence of messages. Where both
+/// have a message from the same role between two common ones, it is reported as changed
+/// rather than removed and added.
+pub fn diff_conversations(left: &Conversation, right: &Conversation) -> ConversationDiff {
+    let left = normalize_all(left);
+    let right = normalize_all(right);
+    let (n, m) = (left.len(), right.len());
+
+    // lcs[i][j] is the length of the longest common subsequence of left[i..] and right[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if left[i] == right[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut entries = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        let both = i < n && j < m;
+        if both && left[i] == right[j] {
+            entries.push(MessageDiff::Unchanged { left: i, right: j });
+            i += 1;
+            j += 1;
+        } else if both && left[i].role == right[j].role && lcs[i + 1][j + 1] == lcs[i][j] {
+            // Pairing the two up loses nothing from the common subsequence
+            entries.push(MessageDiff::Changed { left: i, right: j });
+            i += 1;
+            j += 1;
+        } else if j == m || (i < n && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            entries.push(MessageDiff::Removed { left: i });
+            i += 1;
+        } else {
+            entries.push(MessageDiff::Added { right: j });
+            j += 1;
+        }
+    }
+
+    ConversationDiff { entries }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::{CallToolRequestParam, CallToolResult, Content};
+
+    fn tool_turn(id: &str, output: &str) -> [Message; 2] {
+        [
+            Message::assistant().with_tool_request(
+                id,
+                Ok(CallToolRequestParam {
+                    name: "developer__shell".into(),
+                    arguments: None,
+                }),
+            ),
+            Message::user()
+                .with_tool_response(id, Ok(CallToolResult::success(vec![Content::text(output)]))),
+        ]
+    }
+
+    #[test]
+    fn test_identical_runs_with_different_tool_ids() {
+        let mut left = vec![Message::user().with_text("list files")];
+        left.extend(tool_turn("call_abc", "a.txt"));
+        let mut right = vec![Message::user().with_text("list files")];
+        right.extend(tool_turn("toolu_xyz", "a.txt"));
+
+        let diff = diff_conversations(
+            &Conversation::new_unvalidated(left),
+            &Conversation::new_unvalidated(right),
+        );
+        assert!(diff.is_identical());
+        assert_eq!(diff.entries.len(), 3);
+    }
+
+    #[test]
+    fn test_diff_against_fork() {
+        let original = Conversation::new_unvalidated(vec![
+            Message::user().with_text("plan the refactor"),
+            Message::assistant().with_text("Plan A"),
+            Message::user().with_text("go"),
+            Message::assistant().with_text("done"),
+        ]);
+        let mut fork = original.fork_at(1).unwrap();
+        fork.push(Message::assistant().with_text("Plan B"));
+        fork.push(Message::user().with_text("go"));
+        fork.push(Message::assistant().with_text("done"));
+        fork.push(Message::user().with_text("thanks"));
+
+        let diff = diff_conversations(&original, &fork);
+        assert_eq!(
+            diff.entries,
+            [
+                MessageDiff::Unchanged { left: 0, right: 0 },
+                MessageDiff::Changed { left: 1, right: 1 },
+                MessageDiff::Unchanged { left: 2, right: 2 },
+                MessageDiff::Unchanged { left: 3, right: 3 },
+                MessageDiff::Added { right: 4 },
+            ]
+        );
+        assert_eq!(diff.changes().count(), 2);
+    }
+}
@@ -0,0 +1,409 @@
+//! Readable transcripts of a conversation, for sharing a session with people who don't run
+//! goose.
+//!
+//! [`to_markdown`] and [`to_html`] render the messages the user saw. Tool calls and their
+//! results are collapsed behind `<details>` elements and images are embedded as data URIs, so
+//! either output is a single self-contained file.
+
+use rmcp::model::{RawContent, ResourceContents, Role};
+
+use super::message::{ActionRequiredData, Message, MessageContent, ToolRequest, ToolResponse};
+use super::Conversation;
+
+/// A piece of a rendered message, shared by both output formats
+enum Block {
+    Text(String),
+    Thinking(String),
+    Note(String),
+    Image { mime_type: String, data: String },
+    Code { language: String, code: String },
+    Details { summary: String, blocks: Vec<Block> },
+}
+
+/// Whether an image can be embedded as a data URI without escaping anything
+fn is_embeddable_image(mime_type: &str, data: &str) -> bool {
+    mime_type.starts_with("image/")
+        && mime_type
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '+' | '-' | '.'))
+        && data
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '='))
+}
+
+fn image_block(mime_type: &str, data: &str) -> Block {
+    if is_embeddable_image(mime_type, data) {
+        Block::Image {
+            mime_type: mime_type.to_string(),
+            data: data.to_string(),
+        }
+    } else {
+        Block::Note(format!(
+            "Binary content ({}, {} bytes)",
+            mime_type,
+            data.len()
+        ))
+    }
+}
+
+fn language_for(uri: &str) -> &'static str {
+    match uri.rsplit('.').next().unwrap_or("") {
+        "rs" => "rust",
+        "js" => "javascript",
+        "ts" => "typescript",
+        "py" => "python",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "md" => "markdown",
+        "html" => "html",
+        "sh" => "bash",
+        _ => "",
+    }
+}
+
+fn tool_request_blocks(request: &ToolRequest) -> Block {
+    match &request.tool_call {
+        Ok(call) => Block::Details {
+            summary: format!("Tool call: {}", call.name),
+            blocks: vec![Block::Code {
+                language: "json".to_string(),
+                code: serde_json::to_string_pretty(&call.arguments)
+                    .unwrap_or_else(|_| "null".to_string()),
+            }],
+        },
+        Err(e) => Block::Note(format!("Invalid tool call: {}", e)),
+    }
+}
+
+fn tool_response_blocks(response: &ToolResponse) -> Block {
+    let result = match &response.tool_result {
+        Ok(result) => result,
+        Err(e) => {
+            return Block::Details {
+                summary: "Tool error".to_string(),
+                blocks: vec![Block::Code {
+                    language: String::new(),
+                    code: e.to_string(),
+                }],
+            }
+        }
+    };
+
+    let mut blocks = Vec::new();
+    for content in &result.content {
+        // Tools often send the user a shorter copy of what they tell the model, keep one
+        if let Some(audience) = content.audience() {
+            if !audience.contains(&Role::Assistant) {
+                continue;
+            }
+        }
+        match &content.raw {
+            RawContent::Text(text) => blocks.push(Block::Code {
+                language: String::new(),
+                code: text.text.trim_end().to_string(),
+            }),
+            RawContent::Image(image) => blocks.push(image_block(&image.mime_type, &image.data)),
+            RawContent::Resource(resource) => match &resource.resource {
+                ResourceContents::TextResourceContents { uri, text, .. } => {
+                    blocks.push(Block::Note(format!("File: {}", uri)));
+                    blocks.push(Block::Code {
+                        language: language_for(uri).to_string(),
+                        code: text.trim_end().to_string(),
+                    });
+                }
+                ResourceContents::BlobResourceContents {
+                    uri,
+                    mime_type,
+                    blob,
+                    ..
+                } => match mime_type {
+                    Some(mime_type) if is_embeddable_image(mime_type, blob) => {
+                        blocks.push(image_block(mime_type, blob))
+                    }
+                    _ => blocks.push(Block::Note(format!(
+                        "Binary file: {} ({} bytes)",
+                        uri,
+                        blob.len()
+                    ))),
+                },
+            },
+            RawContent::ResourceLink(link) => {
+                blocks.push(Block::Note(format!("Resource link: {}", link.uri)))
+            }
+            RawContent::Audio(audio) => {
+                blocks.push(Block::Note(format!("Audio ({})", audio.mime_type)))
+            }
+        }
+    }
+    if blocks.is_empty() {
+        blocks.push(Block::Note("No output".to_string()));
+    }
+
+    Block::Details {
+        summary: "Tool result".to_string(),
+        blocks,
+    }
+}
+
+fn message_blocks(message: &Message) -> Vec<Block> {
+    message
+        .content
+        .iter()
+        .filter_map(|content| {
+            Some(match content {
+                MessageContent::Text(text) => Block::Text(text.text.clone()),
+                MessageContent::Image(image) => image_block(&image.mime_type, &image.data),
+                MessageContent::Document(document) => Block::Note(format!(
+                    "Document: {} ({})",
+                    document.name.as_deref().unwrap_or("unnamed"),
+                    document.mime_type
+                )),
+                MessageContent::Audio(audio) => Block::Note(format!("Audio ({})", audio.mime_type)),
+                MessageContent::ToolRequest(request) => tool_request_blocks(request),
+                MessageContent::ToolResponse(response) => tool_response_blocks(response),
+                MessageContent::FrontendToolRequest(request) => match &request.tool_call {
+                    Ok(call) => Block::Note(format!("Tool call: {}", call.name)),
+                    Err(e) => Block::Note(format!("Invalid tool call: {}", e)),
+                },
+                MessageContent::ToolConfirmationRequest(_) => return None,
+                MessageContent::ActionRequired(action) => match &action.data {
+                    ActionRequiredData::ToolConfirmation { tool_name, .. } => {
+                        Block::Note(format!("Confirmation requested for {}", tool_name))
+                    }
+                    ActionRequiredData::Elicitation { message, .. } => {
+                        Block::Note(format!("Input requested: {}", message))
+                    }
+                    ActionRequiredData::ElicitationResponse { .. } => return None,
+                },
+                MessageContent::Thinking(thinking) => Block::Thinking(thinking.thinking.clone()),
+                MessageContent::RedactedThinking(_) => return None,
+                MessageContent::SystemNotification(notification) => {
+                    Block::Note(notification.msg.clone())
+                }
+            })
+        })
+        .collect()
+}
+
+fn role_label(role: &Role) -> &'static str {
+    match role {
+        Role::User => "User",
+        Role::Assistant => "Assistant",
+    }
+}
+
+/// The messages to export with their blocks, skipping those with nothing to show
+fn transcript(conversation: &Conversation) -> Vec<(&'static str, Vec<Block>)> {
+    conversation
+        .messages()
+        .iter()
+        .filter(|msg| msg.is_user_visible())
+        .map(|msg| (role_label(&msg.role), message_blocks(msg)))
+        .filter(|(_, blocks)| !blocks.is_empty())
+        .collect()
+}
+
+/// A fence of backticks longer than any run of them in `code`
+fn code_fence(code: &str) -> String {
+    let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn push_markdown(out: &mut String, block: &Block) {
+    match block {
+        Block::Text(text) => out.push_str(text.trim_end()),
+        Block::Thinking(thinking) => {
+            out.push_str("> *Thinking:* ");
+            out.push_str(&thinking.trim_end().replace('\n', "\n> "));
+        }
+        Block::Note(note) => {
+            out.push('*');
+            out.push_str(&note.replace('*', "\\*"));
+            out.push('*');
+        }
+        Block::Image { mime_type, data } => {
+            out.push_str(&format!("![image](data:{};base64,{})", mime_type, data));
+        }
+        Block::Code { language, code } => {
+            let fence = code_fence(code);
+            out.push_str(&format!("{}{}\n{}\n{}", fence, language, code, fence));
+        }
+        Block::Details { summary, blocks } => {
+            out.push_str("<details>\n<summary>");
+            out.push_str(&escape_html(summary));
+            out.push_str("</summary>\n\n");
+            for block in blocks {
+                push_markdown(out, block);
+                out.push_str("\n\n");
+            }
+            out.push_str("</details>");
+        }
+    }
+}
+
+/// The conversation as a Markdown transcript titled `title`
+pub fn to_markdown(conversation: &Conversation, title: &str) -> String {
+    let mut out = format!("# {}\n\n", title.trim());
+    for (role, blocks) in transcript(conversation) {
+        out.push_str(&format!("## {}\n\n", role));
+        for block in &blocks {
+            push_markdown(&mut out, block);
+            out.push_str("\n\n");
+        }
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn push_html(out: &mut String, block: &Block) {
+    match block {
+        Block::Text(text) => {
+            out.push_str(&format!(
+                "<div class=\"text\">{}</div>\n",
+                escape_html(text)
+            ));
+        }
+        Block::Thinking(thinking) => {
+            out.push_str(&format!(
+                "<blockquote class=\"thinking\">{}</blockquote>\n",
+                escape_html(thinking)
+            ));
+        }
+        Block::Note(note) => {
+            out.push_str(&format!("<p class=\"note\">{}</p>\n", escape_html(note)));
+        }
+        Block::Image { mime_type, data } => {
+            out.push_str(&format!(
+                "<img alt=\"image\" src=\"data:{};base64,{}\">\n",
+                mime_type, data
+            ));
+        }
+        Block::Code { language, code } => {
+            out.push_str(&format!(
+                "<pre><code class=\"language-{}\">{}</code></pre>\n",
+                escape_html(language),
+                escape_html(code)
+            ));
+        }
+        Block::Details { summary, blocks } => {
+            out.push_str(&format!(
+                "<details>\n<summary>{}</summary>\n",
+                escape_html(summary)
+            ));
+            for block in blocks {
+                push_html(out, block);
+            }
+            out.push_str("</details>\n");
+        }
+    }
+}
+
+const HTML_STYLE: &str = r#"body { font-family: system-ui, sans-serif; max-width: 50rem; margin: 2rem auto; padding: 0 1rem; color: #1f2328; }
+.message { border-radius: 8px; padding: 0.75rem 1rem; margin: 1rem 0; }
+.user { background: #eef4ff; }
+.assistant { background: #f6f8fa; }
+.role { font-weight: 600; margin-bottom: 0.5rem; }
+.text { white-space: pre-wrap; }
+.thinking { color: #59636e; white-space: pre-wrap; border-left: 3px solid #d1d9e0; margin: 0.5rem 0; padding-left: 0.75rem; }
+.note { color: #59636e; font-style: italic; }
+pre { background: #ffffff; border: 1px solid #d1d9e0; border-radius: 6px; padding: 0.5rem; overflow-x: auto; }
+details { margin: 0.5rem 0; }
+summary { cursor: pointer; font-family: monospace; }
+img { max-width: 100%; }"#;
+
+/// The conversation as a standalone HTML page titled `title`
+pub fn to_html(conversation: &Conversation, title: &str) -> String {
+    let title = escape_html(title.trim());
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        title, HTML_STYLE, title
+    );
+    for (role, blocks) in transcript(conversation) {
+        out.push_str(&format!(
+            "<section class=\"message {}\">\n<div class=\"role\">{}</div>\n",
+            role.to_lowercase(),
+            role
+        ));
+        for block in &blocks {
+            push_html(&mut out, block);
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::{CallToolRequestParam, CallToolResult, Content};
+    use rmcp::object;
+
+    fn conversation() -> Conversation {
+        Conversation::new_unvalidated(vec![
+            Message::user().with_text("What's in <main.rs>?"),
+            Message::assistant()
+                .with_text("Let me look.")
+                .with_tool_request(
+                    "call_1",
+                    Ok(CallToolRequestParam {
+                        name: "developer__shell".into(),
+                        arguments: Some(object!({"command": "cat main.rs"})),
+                    }),
+                ),
+            Message::user().with_tool_response(
+                "call_1",
+                Ok(CallToolResult::success(vec![
+                    Content::text("fn main() {}\n```"),
+                    Content::image("aGVsbG8=", "image/png"),
+                ])),
+            ),
+            Message::assistant()
+                .with_text("hidden summary")
+                .agent_only(),
+            Message::assistant().with_text("An empty main function."),
+        ])
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let markdown = to_markdown(&conversation(), "Session");
+
+        assert!(markdown.starts_with("# Session\n\n## User\n\nWhat's in <main.rs>?"));
+        assert!(markdown.contains("<summary>Tool call: developer__shell</summary>"));
+        assert!(markdown.contains("\"command\": \"cat main.rs\""));
+        assert!(markdown.contains("````\nfn main() {}\n```\n````"));
+        assert!(markdown.contains("![image](data:image/png;base64,aGVsbG8=)"));
+        assert!(!markdown.contains("hidden summary"));
+        assert!(markdown.ends_with("An empty main function.\n"));
+    }
+
+    #[test]
+    fn test_to_html() {
+        let html = to_html(&conversation(), "Session <1>");
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Session &lt;1&gt;</title>"));
+        assert!(html.contains("What&#39;s in &lt;main.rs&gt;?"));
+        assert!(html.contains("<details>\n<summary>Tool result</summary>"));
+        assert!(html.contains("<img alt=\"image\" src=\"data:image/png;base64,aGVsbG8=\">"));
+        assert!(!html.contains("hidden summary"));
+    }
+}
@@ -0,0 +1,613 @@
+//! Import of conversations held outside goose, so they can be continued in a session.
+//!
+//! [`import_conversations`] reads the JSON of a ChatGPT data export (`conversations.json`), a
+//! Claude data export, an Anthropic Messages API request or transcript, or an OpenAI chat
+//! messages array, detecting which one it was given. System prompts are dropped, since goose
+//! brings its own.
+
+use std::borrow::Cow;
+
+use anyhow::{anyhow, Result};
+use chrono::DateTime;
+use rmcp::model::{
+    object, CallToolRequestParam, CallToolResult, Content, ErrorCode, ErrorData, Role,
+};
+use serde_json::Value;
+
+use super::message::{Message, MessageContent};
+use super::{merge_consecutive_messages, Conversation};
+use crate::providers::utils::safely_parse_json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// `conversations.json` from a ChatGPT data export
+    ChatGpt,
+    /// `conversations.json` from a Claude data export
+    Claude,
+    /// Messages in the shape of the Anthropic Messages API
+    AnthropicMessages,
+    /// Messages in the shape of the OpenAI chat completions API
+    OpenAiMessages,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedConversation {
+    pub title: Option<String>,
+    pub conversation: Conversation,
+}
+
+impl ImportFormat {
+    /// The format `value` is in, None if it isn't one we know
+    pub fn detect(value: &Value) -> Option<Self> {
+        let first = match value {
+            Value::Array(items) => items.first()?,
+            value => value,
+        };
+        if first.get("mapping").is_some() {
+            return Some(ImportFormat::ChatGpt);
+        }
+        if first.get("chat_messages").is_some() {
+            return Some(ImportFormat::Claude);
+        }
+
+        let messages = match value {
+            Value::Array(items) if first.get("role").is_some() => items,
+            Value::Object(_) => value.get("messages")?.as_array()?,
+            _ => return None,
+        };
+        let anthropic = messages
+            .iter()
+            .filter_map(|msg| msg.get("content")?.as_array())
+            .flatten()
+            .any(|block| {
+                matches!(
+                    block.get("type").and_then(Value::as_str),
+                    Some("tool_use" | "tool_result" | "thinking" | "redacted_thinking")
+                ) || block.get("source").is_some()
+            });
+        Some(if anthropic {
+            ImportFormat::AnthropicMessages
+        } else {
+            ImportFormat::OpenAiMessages
+        })
+    }
+}
+
+/// The conversations in `json`, in whichever supported format it is
+pub fn import_conversations(json: &str) -> Result<Vec<ImportedConversation>> {
+    let value: Value = serde_json::from_str(json)?;
+    let format =
+        ImportFormat::detect(&value).ok_or_else(|| anyhow!("Unrecognized conversation format"))?;
+    import_as(&value, format)
+}
+
+/// The conversations in `value`, read as `format`. Conversations without any messages to
+/// import are left out.
+pub fn import_as(value: &Value, format: ImportFormat) -> Result<Vec<ImportedConversation>> {
+    let items = match value {
+        Value::Array(items) if matches!(format, ImportFormat::ChatGpt | ImportFormat::Claude) => {
+            items.iter().collect()
+        }
+        value => vec![value],
+    };
+
+    let mut conversations = Vec::new();
+    for item in items {
+        let imported = match format {
+            ImportFormat::ChatGpt => chatgpt_conversation(item)?,
+            ImportFormat::Claude => claude_conversation(item)?,
+            ImportFormat::AnthropicMessages => messages_conversation(item, anthropic_message)?,
+            ImportFormat::OpenAiMessages => messages_conversation(item, openai_message)?,
+        };
+        if !imported.conversation.is_empty() {
+            conversations.push(imported);
+        }
+    }
+    Ok(conversations)
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn title_of(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .map(str::to_string)
+}
+
+fn chatgpt_conversation(value: &Value) -> Result<ImportedConversation> {
+    let mapping = value
+        .get("mapping")
+        .and_then(Value::as_object)
+        .ok_or_else(|| anyhow!("ChatGPT conversation has no mapping"))?;
+
+    // The export holds every branch the user explored; follow the one they were last on back
+    // to the root
+    let mut node_id = value
+        .get("current_node")
+        .and_then(Value::as_str)
+        .or_else(|| {
+            mapping
+                .iter()
+                .filter(|(_, node)| {
+                    node.get("children")
+                        .and_then(Value::as_array)
+                        .is_none_or(|children| children.is_empty())
+                })
+                .max_by(|(_, a), (_, b)| {
+                    let time = |node: &Value| node["message"]["create_time"].as_f64();
+                    time(a)
+                        .partial_cmp(&time(b))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .map(|(id, _)| id.as_str())
+        });
+    let mut path = Vec::new();
+    while let Some(id) = node_id {
+        if path.len() > mapping.len() {
+            return Err(anyhow!("ChatGPT conversation has a cycle"));
+        }
+        let Some(node) = mapping.get(id) else {
+            break;
+        };
+        path.push(node);
+        node_id = node.get("parent").and_then(Value::as_str);
+    }
+    path.reverse();
+
+    let messages: Vec<Message> = path
+        .into_iter()
+        .filter_map(|node| chatgpt_message(node.get("message")?))
+        .collect();
+    Ok(ImportedConversation {
+        title: title_of(value, "title"),
+        conversation: Conversation::new_unvalidated(messages),
+    })
+}
+
+fn chatgpt_message(message: &Value) -> Option<Message> {
+    let role = match message["author"]["role"].as_str()? {
+        "user" => Role::User,
+        "assistant" => Role::Assistant,
+        _ => return None,
+    };
+    if message["metadata"]["is_visually_hidden_from_conversation"].as_bool() == Some(true) {
+        return None;
+    }
+
+    let content = &message["content"];
+    let text = match content["content_type"].as_str()? {
+        "text" | "multimodal_text" => content["parts"]
+            .as_array()?
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "code" => format!(
+            "```{}\n{}\n```",
+            content["language"].as_str().unwrap_or(""),
+            content["text"].as_str()?
+        ),
+        _ => return None,
+    };
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    let created = message["create_time"]
+        .as_f64()
+        .map(|time| time as i64)
+        .unwrap_or_else(now);
+    Some(Message::new(
+        role,
+        created,
+        vec![MessageContent::text(text)],
+    ))
+}
+
+fn claude_conversation(value: &Value) -> Result<ImportedConversation> {
+    let chat_messages = value
+        .get("chat_messages")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("Claude conversation has no chat_messages"))?;
+
+    let messages: Vec<Message> = chat_messages
+        .iter()
+        .filter_map(|message| {
+            let role = match message.get("sender")?.as_str()? {
+                "human" => Role::User,
+                "assistant" => Role::Assistant,
+                _ => return None,
+            };
+            let blocks: Vec<&str> = message
+                .get("content")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter(|block| block["type"] == "text")
+                .filter_map(|block| block["text"].as_str())
+                .collect();
+            let text = if blocks.is_empty() {
+                message.get("text")?.as_str()?.to_string()
+            } else {
+                blocks.join("\n")
+            };
+            if text.trim().is_empty() {
+                return None;
+            }
+            let created = message
+                .get("created_at")
+                .and_then(Value::as_str)
+                .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                .map(|time| time.timestamp())
+                .unwrap_or_else(now);
+            Some(Message::new(
+                role,
+                created,
+                vec![MessageContent::text(text)],
+            ))
+        })
+        .collect();
+
+    Ok(ImportedConversation {
+        title: title_of(value, "name"),
+        conversation: Conversation::new_unvalidated(messages),
+    })
+}
+
+/// A conversation from an API-style messages array, given on its own or as the `messages`
+/// of a request
+fn messages_conversation(
+    value: &Value,
+    convert: fn(&Value) -> Result<Option<Message>>,
+) -> Result<ImportedConversation> {
+    let items = match value {
+        Value::Array(items) => items,
+        value => value
+            .get("messages")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow!("Expected an array of messages"))?,
+    };
+
+    let mut messages = Vec::new();
+    for item in items {
+        messages.extend(convert(item)?);
+    }
+    // OpenAI sends each tool result as a message of its own, goose groups them
+    let (messages, _) = merge_consecutive_messages(messages);
+
+    Ok(ImportedConversation {
+        title: title_of(value, "title"),
+        conversation: Conversation::new_unvalidated(messages),
+    })
+}
+
+fn message_role(message: &Value) -> Option<Role> {
+    match message.get("role")?.as_str()? {
+        "user" | "tool" => Some(Role::User),
+        "assistant" => Some(Role::Assistant),
+        _ => None,
+    }
+}
+
+fn tool_call(
+    name: &str,
+    arguments: Result<Value, String>,
+) -> Result<CallToolRequestParam, ErrorData> {
+    match arguments {
+        Ok(arguments) => Ok(CallToolRequestParam {
+            name: name.to_string().into(),
+            arguments: Some(object(arguments)),
+        }),
+        Err(e) => Err(ErrorData::new(
+            ErrorCode::INVALID_PARAMS,
+            Cow::from(format!("Could not interpret arguments for {}: {}", name, e)),
+            None,
+        )),
+    }
+}
+
+fn tool_result(text: String, is_error: bool) -> Result<CallToolResult, ErrorData> {
+    if is_error {
+        Err(ErrorData::new(ErrorCode::INTERNAL_ERROR, text, None))
+    } else {
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+}
+
+/// Text of a content field that is either a string or a list of text blocks
+fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| block.get("text")?.as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// A base64 image from a `data:` URL
+fn data_url_image(url: &str) -> Option<MessageContent> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let mime_type = header.strip_suffix(";base64")?;
+    Some(MessageContent::image(data, mime_type))
+}
+
+fn anthropic_message(message: &Value) -> Result<Option<Message>> {
+    let Some(role) = message_role(message) else {
+        return Ok(None);
+    };
+
+    let mut content = Vec::new();
+    let blocks = match message.get("content") {
+        Some(Value::String(text)) => {
+            content.push(MessageContent::text(text));
+            &[][..]
+        }
+        Some(Value::Array(blocks)) => blocks.as_slice(),
+        _ => &[][..],
+    };
+    for block in blocks {
+        match block.get("type").and_then(Value::as_str) {
+            Some("text") => {
+                if let Some(text) = block["text"].as_str() {
+                    content.push(MessageContent::text(text));
+                }
+            }
+            Some("image") => {
+                let source = &block["source"];
+                if let (Some(data), Some(media_type)) =
+                    (source["data"].as_str(), source["media_type"].as_str())
+                {
+                    content.push(MessageContent::image(data, media_type));
+                }
+            }
+            Some("tool_use") => {
+                let id = block["id"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("Missing tool_use id"))?;
+                let name = block["name"].as_str().unwrap_or_default();
+                let input = block.get("input").cloned().unwrap_or(Value::Null);
+                content.push(MessageContent::tool_request(id, tool_call(name, Ok(input))));
+            }
+            Some("tool_result") => {
+                let id = block["tool_use_id"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("Missing tool_result tool_use_id"))?;
+                let is_error = block["is_error"].as_bool().unwrap_or(false);
+                content.push(MessageContent::tool_response(
+                    id,
+                    tool_result(content_text(&block["content"]), is_error),
+                ));
+            }
+            Some("thinking") => {
+                if let Some(thinking) = block["thinking"].as_str() {
+                    let signature = block["signature"].as_str().unwrap_or_default();
+                    content.push(MessageContent::thinking(thinking, signature));
+                }
+            }
+            Some("redacted_thinking") => {
+                if let Some(data) = block["data"].as_str() {
+                    content.push(MessageContent::redacted_thinking(data));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok((!content.is_empty()).then(|| Message::new(role, now(), content)))
+}
+
+fn openai_message(message: &Value) -> Result<Option<Message>> {
+    let Some(role) = message_role(message) else {
+        return Ok(None);
+    };
+
+    if message["role"] == "tool" {
+        let id = message["tool_call_id"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Missing tool_call_id on tool message"))?;
+        let result = tool_result(content_text(&message["content"]), false);
+        return Ok(Some(Message::new(
+            role,
+            now(),
+            vec![MessageContent::tool_response(id, result)],
+        )));
+    }
+
+    let mut content = Vec::new();
+    match &message["content"] {
+        Value::String(text) if !text.is_empty() => content.push(MessageContent::text(text)),
+        Value::Array(parts) => {
+            for part in parts {
+                match part["type"].as_str() {
+                    Some("text") => {
+                        if let Some(text) = part["text"].as_str() {
+                            content.push(MessageContent::text(text));
+                        }
+                    }
+                    Some("image_url") => {
+                        if let Some(image) =
+                            part["image_url"]["url"].as_str().and_then(data_url_image)
+                        {
+                            content.push(image);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+
+    for call in message["tool_calls"].as_array().into_iter().flatten() {
+        let id = call["id"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Missing tool call id"))?;
+        let name = call["function"]["name"].as_str().unwrap_or_default();
+        let arguments = match call["function"]["arguments"].as_str().unwrap_or_default() {
+            "" => Ok(Value::Object(Default::default())),
+            arguments => safely_parse_json(arguments).map_err(|e| e.to_string()),
+        };
+        content.push(MessageContent::tool_request(id, tool_call(name, arguments)));
+    }
+
+    Ok((!content.is_empty()).then(|| Message::new(role, now(), content)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn summary(conversation: &Conversation) -> Vec<String> {
+        conversation
+            .messages()
+            .iter()
+            .map(|msg| format!("{:?}: {}", msg.role, msg.as_concat_text()))
+            .collect()
+    }
+
+    #[test]
+    fn test_import_chatgpt_export() {
+        let export = json!([{
+            "title": "Rust help",
+            "current_node": "c",
+            "mapping": {
+                "root": {"id": "root", "message": null, "parent": null, "children": ["a"]},
+                "a": {
+                    "id": "a", "parent": "root", "children": ["b", "old"],
+                    "message": {
+                        "author": {"role": "user"}, "create_time": 1700000000.5,
+                        "content": {"content_type": "text", "parts": ["How do I sort a Vec?"]}
+                    }
+                },
+                "old": {
+                    "id": "old", "parent": "a", "children": [],
+                    "message": {
+                        "author": {"role": "assistant"},
+                        "content": {"content_type": "text", "parts": ["An abandoned answer"]}
+                    }
+                },
+                "b": {
+                    "id": "b", "parent": "a", "children": ["c"],
+                    "message": {
+                        "author": {"role": "system"},
+                        "content": {"content_type": "text", "parts": ["hidden"]}
+                    }
+                },
+                "c": {
+                    "id": "c", "parent": "b", "children": [],
+                    "message": {
+                        "author": {"role": "assistant"},
+                        "content": {"content_type": "text", "parts": ["Use sort()."]}
+                    }
+                }
+            }
+        }]);
+
+        let imported = import_conversations(&export.to_string()).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].title.as_deref(), Some("Rust help"));
+        assert_eq!(
+            summary(&imported[0].conversation),
+            ["User: How do I sort a Vec?", "Assistant: Use sort()."]
+        );
+        assert_eq!(imported[0].conversation.messages()[0].created, 1700000000);
+    }
+
+    #[test]
+    fn test_import_claude_export() {
+        let export = json!([{
+            "name": "Greeting",
+            "chat_messages": [
+                {"sender": "human", "text": "hi", "created_at": "2024-05-01T10:00:00Z"},
+                {"sender": "assistant", "text": "", "content": [{"type": "text", "text": "hello"}]}
+            ]
+        }]);
+
+        let imported = import_conversations(&export.to_string()).unwrap();
+        assert_eq!(imported[0].title.as_deref(), Some("Greeting"));
+        assert_eq!(
+            summary(&imported[0].conversation),
+            ["User: hi", "Assistant: hello"]
+        );
+    }
+
+    #[test]
+    fn test_import_anthropic_messages() {
+        let request = json!({
+            "system": "You are helpful",
+            "messages": [
+                {"role": "user", "content": "list files"},
+                {"role": "assistant", "content": [
+                    {"type": "text", "text": "Listing"},
+                    {"type": "tool_use", "id": "toolu_1", "name": "shell", "input": {"command": "ls"}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": [{"type": "text", "text": "a.txt"}]}
+                ]}
+            ]
+        });
+
+        assert_eq!(
+            ImportFormat::detect(&request),
+            Some(ImportFormat::AnthropicMessages)
+        );
+        let imported = import_conversations(&request.to_string()).unwrap();
+        let messages = imported[0].conversation.messages();
+        assert_eq!(messages.len(), 3);
+        let request = messages[1].content[1].as_tool_request().unwrap();
+        assert_eq!(request.id, "toolu_1");
+        assert_eq!(request.tool_call.as_ref().unwrap().name, "shell");
+        let response = messages[2].content[0].as_tool_response().unwrap();
+        assert_eq!(response.id, "toolu_1");
+        assert!(response.tool_result.is_ok());
+    }
+
+    #[test]
+    fn test_import_openai_messages() {
+        let messages = json!([
+            {"role": "system", "content": "You are helpful"},
+            {"role": "user", "content": [
+                {"type": "text", "text": "what's this?"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,aGVsbG8="}}
+            ]},
+            {"role": "assistant", "content": null, "tool_calls": [
+                {"id": "call_1", "type": "function", "function": {"name": "look", "arguments": "{\"zoom\": 2}"}},
+                {"id": "call_2", "type": "function", "function": {"name": "look", "arguments": "{bad"}}
+            ]},
+            {"role": "tool", "tool_call_id": "call_1", "content": "a cat"},
+            {"role": "tool", "tool_call_id": "call_2", "content": "error"},
+            {"role": "assistant", "content": "It's a cat."}
+        ]);
+
+        assert_eq!(
+            ImportFormat::detect(&messages),
+            Some(ImportFormat::OpenAiMessages)
+        );
+        let imported = import_conversations(&messages.to_string()).unwrap();
+        let conversation = &imported[0].conversation;
+        assert_eq!(conversation.messages().len(), 4);
+        assert!(matches!(
+            conversation.messages()[0].content[1],
+            MessageContent::Image(_)
+        ));
+        let calls = &conversation.messages()[1].content;
+        assert!(calls[0].as_tool_request().unwrap().tool_call.is_ok());
+        assert!(calls[1].as_tool_request().unwrap().tool_call.is_err());
+        assert_eq!(conversation.messages()[2].content.len(), 2);
+    }
+
+    #[test]
+    fn test_unrecognized_format() {
+        assert!(import_conversations(r#"{"foo": 1}"#).is_err());
+        assert!(import_conversations("not json").is_err());
+    }
+}
@@ -1,12 +1,13 @@
 use crate::mcp_utils::ToolResult;
+use base64::Engine;
 use chrono::Utc;
 use rmcp::model::{
-    AnnotateAble, CallToolRequestParam, CallToolResult, Content, ImageContent, JsonObject,
+    AnnotateAble, CallToolRequestParam, CallToolResult, Content, ImageContent, JsonObject, Meta,
     PromptMessage, PromptMessageContent, PromptMessageRole, RawContent, RawImageContent,
     RawTextContent, ResourceContents, Role, TextContent,
 };
 use serde::{Deserialize, Deserializer, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt;
 use utoipa::ToSchema;
 
@@ -170,12 +171,106 @@ pub struct SystemNotificationContent {
     pub msg: String,
 }
 
+/// Largest document, in decoded bytes, accepted as message content
+pub const MAX_DOCUMENT_SIZE_BYTES: usize = 32 * 1024 * 1024;
+
+/// MIME types accepted for document content
+pub const SUPPORTED_DOCUMENT_MIME_TYPES: &[&str] = &[
+    "application/pdf",
+    "text/plain",
+    "text/markdown",
+    "text/csv",
+    "text/html",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// A document attachment, such as a PDF, carried as base64-encoded data
+pub struct DocumentContent {
+    pub data: String,
+    pub mime_type: String,
+    /// File name shown to the model, where the provider supports one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl DocumentContent {
+    /// Size of the decoded document in bytes
+    pub fn size_bytes(&self) -> usize {
+        let padding = self.data.chars().rev().take_while(|c| *c == '=').count();
+        (self.data.len() / 4 * 3).saturating_sub(padding)
+    }
+
+    pub fn is_pdf(&self) -> bool {
+        self.mime_type == "application/pdf"
+    }
+
+    /// Contents of a text document, or None for binary documents and invalid UTF-8
+    pub fn as_text(&self) -> Option<String> {
+        if !self.mime_type.starts_with("text/") {
+            return None;
+        }
+        let bytes = base64::prelude::BASE64_STANDARD.decode(&self.data).ok()?;
+        String::from_utf8(bytes).ok()
+    }
+
+    /// Check that the MIME type is supported and the document is within the size limit
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !SUPPORTED_DOCUMENT_MIME_TYPES.contains(&self.mime_type.as_str()) {
+            anyhow::bail!(
+                "Unsupported document type {}. Supported types are: {}",
+                self.mime_type,
+                SUPPORTED_DOCUMENT_MIME_TYPES.join(", ")
+            );
+        }
+        let size = self.size_bytes();
+        if size > MAX_DOCUMENT_SIZE_BYTES {
+            anyhow::bail!(
+                "Document is {} bytes, which exceeds the {} byte limit",
+                size,
+                MAX_DOCUMENT_SIZE_BYTES
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// A voice message or other audio input, carried as base64-encoded data
+pub struct AudioContent {
+    pub data: String,
+    pub mime_type: String,
+}
+
+impl AudioContent {
+    /// Short format name for the audio encoding, which doubles as its file extension
+    pub fn format(&self) -> Option<&'static str> {
+        Self::format_for_mime_type(&self.mime_type)
+    }
+
+    pub fn format_for_mime_type(mime_type: &str) -> Option<&'static str> {
+        match mime_type {
+            "audio/wav" | "audio/x-wav" | "audio/wave" => Some("wav"),
+            "audio/mpeg" | "audio/mp3" => Some("mp3"),
+            "audio/mp4" | "audio/m4a" | "audio/x-m4a" => Some("m4a"),
+            "audio/ogg" => Some("ogg"),
+            "audio/webm" => Some("webm"),
+            "audio/flac" | "audio/x-flac" => Some("flac"),
+            "audio/aac" => Some("aac"),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 /// Content passed inside a message, which can be both simple content and tool content
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum MessageContent {
     Text(TextContent),
     Image(ImageContent),
+    Document(DocumentContent),
+    Audio(AudioContent),
     ToolRequest(ToolRequest),
     ToolResponse(ToolResponse),
     ToolConfirmationRequest(ToolConfirmationRequest),
@@ -191,6 +286,8 @@ impl fmt::Display for MessageContent {
         match self {
             MessageContent::Text(t) => write!(f, "{}", t.text),
             MessageContent::Image(i) => write!(f, "[Image: {}]", i.mime_type),
+            MessageContent::Document(d) => write!(f, "[Document: {}]", d.mime_type),
+            MessageContent::Audio(a) => write!(f, "[Audio: {}]", a.mime_type),
             MessageContent::ToolRequest(r) => {
                 write!(f, "[ToolRequest: {}]", r.to_readable_string())
             }
@@ -240,6 +337,49 @@ impl MessageContent {
         )
     }
 
+    /// Text describing a call to one of the provider's built-in (server-side) tools, with the
+    /// raw call kept in `_meta.builtin_tool_call` so clients can render it as a tool result
+    pub fn builtin_tool_call<S: Into<String>>(summary: S, call: serde_json::Value) -> Self {
+        let mut meta = serde_json::Map::new();
+        meta.insert("builtin_tool_call".to_string(), call);
+        MessageContent::Text(
+            RawTextContent {
+                text: summary.into(),
+                meta: Some(Meta(meta)),
+            }
+            .no_annotation(),
+        )
+    }
+
+    /// A numbered list of the sources a grounded response cited, with the citations themselves
+    /// kept in `_meta.citations`
+    pub fn citations(citations: Vec<Citation>) -> Self {
+        let mut seen = HashSet::new();
+        let sources = citations
+            .iter()
+            .filter(|c| seen.insert(c.url.as_str()))
+            .enumerate()
+            .map(|(i, c)| match &c.title {
+                Some(title) => format!("{}. [{}]({})", i + 1, title, c.url),
+                None => format!("{}. {}", i + 1, c.url),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut meta = serde_json::Map::new();
+        meta.insert(
+            "citations".to_string(),
+            serde_json::to_value(&citations).unwrap_or_default(),
+        );
+        MessageContent::Text(
+            RawTextContent {
+                text: format!("\n\nSources:\n{}", sources),
+                meta: Some(Meta(meta)),
+            }
+            .no_annotation(),
+        )
+    }
+
     pub fn image<S: Into<String>, T: Into<String>>(data: S, mime_type: T) -> Self {
         MessageContent::Image(
             RawImageContent {
@@ -251,6 +391,65 @@ impl MessageContent {
         )
     }
 
+    pub fn audio<S: Into<String>, T: Into<String>>(data: S, mime_type: T) -> Self {
+        MessageContent::Audio(AudioContent {
+            data: data.into(),
+            mime_type: mime_type.into(),
+        })
+    }
+
+    /// Content for a resource attached to a message. Text resources become text headed by
+    /// their URI; images, audio and supported documents keep their type; other binary
+    /// resources become a note saying they couldn't be attached.
+    pub fn resource(resource: &ResourceContents) -> Self {
+        match resource {
+            ResourceContents::TextResourceContents { uri, text, .. } => {
+                MessageContent::text(format!("{}\n\n{}", uri, text))
+            }
+            ResourceContents::BlobResourceContents {
+                uri,
+                mime_type,
+                blob,
+                ..
+            } => {
+                let mime_type = mime_type.as_deref().unwrap_or("application/octet-stream");
+                if mime_type.starts_with("image/") {
+                    MessageContent::image(blob.clone(), mime_type)
+                } else if mime_type.starts_with("audio/") {
+                    MessageContent::audio(blob.clone(), mime_type)
+                } else {
+                    let name = uri
+                        .rsplit('/')
+                        .next()
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_string);
+                    MessageContent::document(blob.clone(), mime_type, name).unwrap_or_else(|e| {
+                        MessageContent::text(format!(
+                            "[Resource {} ({}) could not be attached: {}]",
+                            uri, mime_type, e
+                        ))
+                    })
+                }
+            }
+        }
+    }
+
+    /// Document content from base64-encoded data, rejecting unsupported types and oversized
+    /// documents
+    pub fn document<S: Into<String>, T: Into<String>>(
+        data: S,
+        mime_type: T,
+        name: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let document = DocumentContent {
+            data: data.into(),
+            mime_type: mime_type.into(),
+            name,
+        };
+        document.validate()?;
+        Ok(MessageContent::Document(document))
+    }
+
     pub fn tool_request<S: Into<String>>(
         id: S,
         tool_call: ToolResult<CallToolRequestParam>,
@@ -498,7 +697,37 @@ impl From<PromptMessage> for Message {
     }
 }
 
-#[derive(ToSchema, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+/// A web source cited by a provider's built-in search
+pub struct Citation {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The passage of the source, or of the response, that the citation supports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cited_text: Option<String>,
+}
+
+#[derive(ToSchema, Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+/// One of the most likely alternatives for an output token
+pub struct TopLogprob {
+    pub token: String,
+    pub logprob: f64,
+}
+
+#[derive(ToSchema, Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+/// Log probability of an output token, as reported by the provider
+pub struct TokenLogprob {
+    pub token: String,
+    pub logprob: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top_logprobs: Vec<TopLogprob>,
+}
+
+#[derive(ToSchema, Clone, PartialEq, Serialize, Deserialize, Debug)]
 /// Metadata for message visibility
 #[serde(rename_all = "camelCase")]
 pub struct MessageMetadata {
@@ -506,6 +735,37 @@ pub struct MessageMetadata {
     pub user_visible: bool,
     /// Whether the message should be included in the agent's context window
     pub agent_visible: bool,
+    /// Token log probabilities for the message text, when requested from the provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<Vec<TokenLogprob>>,
+    /// Who wrote the message when that isn't just the user or the model, such as a
+    /// teammate, a recipe or a subagent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Labels for filtering and grouping messages
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub importance: Option<MessageImportance>,
+    /// The tool whose output the message carries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_tool: Option<String>,
+    /// Whether parts of the message were redacted before it was stored
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub redacted: bool,
+    /// Entries defined by applications building on goose
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = Object)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// How much a message matters, for UIs that highlight or filter messages
+#[derive(ToSchema, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum MessageImportance {
+    Low,
+    Normal,
+    High,
 }
 
 impl Default for MessageMetadata {
@@ -513,6 +773,13 @@ impl Default for MessageMetadata {
         MessageMetadata {
             user_visible: true,
             agent_visible: true,
+            logprobs: None,
+            author: None,
+            tags: Vec::new(),
+            importance: None,
+            source_tool: None,
+            redacted: false,
+            extra: BTreeMap::new(),
         }
     }
 }
@@ -523,6 +790,7 @@ impl MessageMetadata {
         MessageMetadata {
             user_visible: false,
             agent_visible: true,
+            ..Default::default()
         }
     }
 
@@ -531,6 +799,7 @@ impl MessageMetadata {
         MessageMetadata {
             user_visible: true,
             agent_visible: false,
+            ..Default::default()
         }
     }
 
@@ -539,6 +808,7 @@ impl MessageMetadata {
         MessageMetadata {
             user_visible: false,
             agent_visible: false,
+            ..Default::default()
         }
     }
 
@@ -573,6 +843,57 @@ impl MessageMetadata {
             ..self
         }
     }
+
+    /// Return a copy with the given author
+    pub fn with_author(self, author: impl Into<String>) -> Self {
+        Self {
+            author: Some(author.into()),
+            ..self
+        }
+    }
+
+    /// Return a copy with `tag` added, unless it already has it
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        let tag = tag.into();
+        if !self.has_tag(&tag) {
+            self.tags.push(tag);
+        }
+        self
+    }
+
+    /// Return a copy with the given importance
+    pub fn with_importance(self, importance: MessageImportance) -> Self {
+        Self {
+            importance: Some(importance),
+            ..self
+        }
+    }
+
+    /// Return a copy with the given source tool
+    pub fn with_source_tool(self, tool_name: impl Into<String>) -> Self {
+        Self {
+            source_tool: Some(tool_name.into()),
+            ..self
+        }
+    }
+
+    /// Return a copy marked as redacted
+    pub fn with_redacted(self) -> Self {
+        Self {
+            redacted: true,
+            ..self
+        }
+    }
+
+    /// Return a copy with `key` set to `value` among the extra entries
+    pub fn with_extra(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
+        self.extra.insert(key.into(), value);
+        self
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
 }
 
 #[derive(ToSchema, Clone, PartialEq, Serialize, Deserialize, Debug)]
@@ -653,6 +974,11 @@ impl Message {
         self.with_content(MessageContent::image(data, mime_type))
     }
 
+    /// Add audio content to the message
+    pub fn with_audio<S: Into<String>, T: Into<String>>(self, data: S, mime_type: T) -> Self {
+        self.with_content(MessageContent::audio(data, mime_type))
+    }
+
     /// Add a tool request to the message
     pub fn with_tool_request<S: Into<String>>(
         self,
@@ -820,6 +1146,12 @@ impl Message {
         self
     }
 
+    /// Attach token log probabilities reported by the provider
+    pub fn with_logprobs(mut self, logprobs: Vec<TokenLogprob>) -> Self {
+        self.metadata.logprobs = Some(logprobs);
+        self
+    }
+
     /// Mark the message as only visible to the user (not the agent)
     pub fn user_only(mut self) -> Self {
         self.metadata.user_visible = true;
@@ -854,11 +1186,16 @@ pub struct TokenState {
     pub accumulated_input_tokens: i32,
     pub accumulated_output_tokens: i32,
     pub accumulated_total_tokens: i32,
+    /// Total cost of the session in USD, when the model's pricing is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accumulated_cost: Option<f64>,
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::conversation::message::{Message, MessageContent, MessageMetadata};
+    use crate::conversation::message::{
+        Message, MessageContent, MessageImportance, MessageMetadata,
+    };
     use crate::conversation::*;
     use rmcp::model::{
         AnnotateAble, CallToolRequestParam, PromptMessage, PromptMessageContent, PromptMessageRole,
@@ -1078,6 +1415,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_resource_content() {
+        let text = MessageContent::resource(&ResourceContents::TextResourceContents {
+            uri: "file:///notes.md".to_string(),
+            mime_type: Some("text/markdown".to_string()),
+            text: "# Notes".to_string(),
+            meta: None,
+        });
+        assert_eq!(text.as_text(), Some("file:///notes.md\n\n# Notes"));
+
+        let blob = |mime_type: &str| ResourceContents::BlobResourceContents {
+            uri: "file:///data/item".to_string(),
+            mime_type: Some(mime_type.to_string()),
+            blob: "aGVsbG8=".to_string(),
+            meta: None,
+        };
+        assert!(matches!(
+            MessageContent::resource(&blob("image/png")),
+            MessageContent::Image(_)
+        ));
+        assert!(matches!(
+            MessageContent::resource(&blob("audio/wav")),
+            MessageContent::Audio(_)
+        ));
+        let unsupported = MessageContent::resource(&blob("application/x-unknown"));
+        assert!(unsupported.as_text().unwrap().starts_with(
+            "[Resource file:///data/item (application/x-unknown) could not be attached"
+        ));
+    }
+
     #[test]
     fn test_from_prompt_message_blob_resource() {
         let resource = ResourceContents::BlobResourceContents {
@@ -1347,6 +1714,38 @@ mod tests {
         assert!(metadata.agent_visible);
     }
 
+    #[test]
+    fn test_message_metadata_tags_and_extra() {
+        let metadata = MessageMetadata::agent_only()
+            .with_author("reviewer")
+            .with_tag("decision")
+            .with_tag("decision")
+            .with_importance(MessageImportance::High)
+            .with_source_tool("developer__shell")
+            .with_redacted()
+            .with_extra("ticket", serde_json::json!({"id": 42}));
+        assert_eq!(metadata.tags, ["decision"]);
+        assert!(metadata.has_tag("decision"));
+
+        let message = Message::user()
+            .with_text("Ship it")
+            .with_metadata(metadata.clone());
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["metadata"]["sourceTool"], "developer__shell");
+        assert_eq!(json["metadata"]["importance"], "high");
+        let roundtrip: Message = serde_json::from_value(json).unwrap();
+        assert_eq!(roundtrip.metadata, metadata);
+
+        // Metadata written before these fields existed still reads, and defaults stay compact
+        let legacy: MessageMetadata =
+            serde_json::from_str(r#"{"userVisible": true, "agentVisible": false}"#).unwrap();
+        assert_eq!(legacy, MessageMetadata::user_only());
+        assert_eq!(
+            serde_json::to_string(&MessageMetadata::default()).unwrap(),
+            r#"{"userVisible":true,"agentVisible":true}"#
+        );
+    }
+
     #[test]
     fn test_legacy_tool_response_deserialization() {
         let legacy_json = r#"{
@@ -1429,4 +1828,24 @@ mod tests {
             panic!("Expected ToolResponse content");
         }
     }
+
+    #[test]
+    fn test_document_content_validation() {
+        let content =
+            MessageContent::document("aGVsbG8=", "text/plain", Some("a.txt".into())).unwrap();
+        let MessageContent::Document(document) = &content else {
+            panic!("Expected Document content");
+        };
+        assert_eq!(document.size_bytes(), 5);
+        assert_eq!(document.as_text().as_deref(), Some("hello"));
+        assert!(!document.is_pdf());
+
+        let json = serde_json::to_value(&content).unwrap();
+        assert_eq!(json["type"], "document");
+        assert_eq!(json["mimeType"], "text/plain");
+
+        assert!(MessageContent::document("aGVsbG8=", "application/zip", None).is_err());
+        let oversized = "A".repeat((super::MAX_DOCUMENT_SIZE_BYTES / 3 + 1) * 4);
+        assert!(MessageContent::document(oversized, "application/pdf", None).is_err());
+    }
 }
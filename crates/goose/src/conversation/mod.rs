@@ -5,7 +5,12 @@ use std::collections::HashSet;
 use thiserror::Error;
 use utoipa::ToSchema;
 
+pub mod diff;
+pub mod export;
+pub mod import;
 pub mod message;
+pub mod reencode;
+pub mod replay;
 mod tool_result_serde;
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
@@ -47,9 +52,16 @@ impl Conversation {
             .last_mut()
             .filter(|m| m.id.is_some() && m.id == message.id)
         {
+            if let Some(logprobs) = message.metadata.logprobs {
+                last.metadata
+                    .logprobs
+                    .get_or_insert_with(Vec::new)
+                    .extend(logprobs);
+            }
+            // Text carrying metadata, like citations, stays a block of its own
             match (last.content.last_mut(), message.content.last()) {
                 (Some(MessageContent::Text(ref mut last)), Some(MessageContent::Text(new)))
-                    if message.content.len() == 1 =>
+                    if message.content.len() == 1 && last.meta.is_none() && new.meta.is_none() =>
                 {
                     last.text.push_str(&new.text);
                 }
@@ -122,6 +134,21 @@ impl Conversation {
         self.filtered_messages(|meta| meta.user_visible)
     }
 
+    /// An independent copy of the first `message_index` messages, to continue differently
+    /// from there. None if the conversation is shorter than that.
+    pub fn fork_at(&self, message_index: usize) -> Option<Conversation> {
+        (message_index <= self.0.len()).then(|| Conversation(self.0[..message_index].to_vec()))
+    }
+
+    /// Number of leading messages this conversation has in common with `other`
+    pub fn common_prefix_len(&self, other: &Conversation) -> usize {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .take_while(|(a, b)| a.role == b.role && a.content == b.content)
+            .count()
+    }
+
     fn validate(self) -> Result<Self, InvalidConversation> {
         let (_messages, issues) = fix_messages(self.0.clone());
         if !issues.is_empty() {
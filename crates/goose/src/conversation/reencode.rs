@@ -0,0 +1,226 @@
+//! Re-encoding of a conversation for a different provider or model.
+//!
+//! Histories carry content only the producing provider understands: thinking blocks signed by
+//! the model that wrote them, provider metadata on tool calls (such as Gemini thought
+//! signatures), and tool call ids in that provider's format. Sent as is to another provider
+//! they are rejected or misread, so a resumed session is re-encoded when its provider or model
+//! changes.
+
+use std::collections::{HashMap, HashSet};
+
+use super::message::MessageContent;
+use super::Conversation;
+
+const MAX_TOOL_ID_LEN: usize = 64;
+
+/// Whether every provider accepts `id` as a tool call id
+fn is_portable_tool_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_TOOL_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Maps tool call ids to portable ones, consistently so requests and responses stay paired
+struct ToolIdMap {
+    taken: HashSet<String>,
+    mapped: HashMap<String, String>,
+}
+
+impl ToolIdMap {
+    fn new(conversation: &Conversation) -> Self {
+        let taken = conversation
+            .messages()
+            .iter()
+            .flat_map(|msg| msg.content.iter())
+            .filter_map(tool_id)
+            .filter(|id| is_portable_tool_id(id))
+            .map(str::to_string)
+            .collect();
+        Self {
+            taken,
+            mapped: HashMap::new(),
+        }
+    }
+
+    fn portable(&mut self, id: &str) -> String {
+        if is_portable_tool_id(id) {
+            return id.to_string();
+        }
+        if let Some(mapped) = self.mapped.get(id) {
+            return mapped.clone();
+        }
+
+        let mut base: String = id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .take(MAX_TOOL_ID_LEN - 8)
+            .collect();
+        if base.is_empty() {
+            base = "call".to_string();
+        }
+        let mut candidate = base.clone();
+        let mut suffix = 1;
+        while self.taken.contains(&candidate) {
+            candidate = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+
+        self.taken.insert(candidate.clone());
+        self.mapped.insert(id.to_string(), candidate.clone());
+        candidate
+    }
+}
+
+fn tool_id(content: &MessageContent) -> Option<&str> {
+    match content {
+        MessageContent::ToolRequest(request) => Some(&request.id),
+        MessageContent::ToolResponse(response) => Some(&response.id),
+        MessageContent::FrontendToolRequest(request) => Some(&request.id),
+        MessageContent::ToolConfirmationRequest(request) => Some(&request.id),
+        _ => None,
+    }
+}
+
+fn is_thinking(content: &MessageContent) -> bool {
+    matches!(
+        content,
+        MessageContent::Thinking(_) | MessageContent::RedactedThinking(_)
+    )
+}
+
+/// `conversation` made safe to continue with a different provider or model, or None if it
+/// needs no changes.
+///
+/// Thinking blocks are hidden from the model but kept for the user: a message containing
+/// them is made agent-invisible and followed by an agent-only copy without them. Provider
+/// metadata on tool calls is dropped and tool call ids are rewritten to
+/// `[A-Za-z0-9_-]{1,64}`.
+pub fn reencode_for_provider_switch(conversation: &Conversation) -> Option<Conversation> {
+    let mut ids = ToolIdMap::new(conversation);
+    let mut changed = false;
+    let mut messages = Vec::with_capacity(conversation.messages().len());
+
+    for msg in conversation.messages() {
+        let mut msg = msg.clone();
+        for content in &mut msg.content {
+            let (id, metadata) = match content {
+                MessageContent::ToolRequest(request) => (&mut request.id, &mut request.metadata),
+                MessageContent::ToolResponse(response) => {
+                    (&mut response.id, &mut response.metadata)
+                }
+                MessageContent::FrontendToolRequest(request) => {
+                    let portable = ids.portable(&request.id);
+                    changed |= portable != request.id;
+                    request.id = portable;
+                    continue;
+                }
+                MessageContent::ToolConfirmationRequest(request) => {
+                    let portable = ids.portable(&request.id);
+                    changed |= portable != request.id;
+                    request.id = portable;
+                    continue;
+                }
+                _ => continue,
+            };
+            let portable = ids.portable(id);
+            changed |= portable != *id || metadata.is_some();
+            *id = portable;
+            *metadata = None;
+        }
+
+        if msg.is_agent_visible() && msg.content.iter().any(is_thinking) {
+            changed = true;
+            let mut agent_copy = msg.clone().agent_only();
+            agent_copy.content.retain(|content| !is_thinking(content));
+            let metadata = msg.metadata.clone().with_agent_invisible();
+            messages.push(msg.with_metadata(metadata));
+            if !agent_copy.content.is_empty() {
+                messages.push(agent_copy);
+            }
+        } else {
+            messages.push(msg);
+        }
+    }
+
+    changed.then(|| Conversation::new_unvalidated(messages))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::message::Message;
+    use rmcp::model::{CallToolRequestParam, CallToolResult, Content};
+    use serde_json::json;
+
+    fn tool_exchange(id: &str) -> [Message; 2] {
+        let metadata = json!({"thoughtSignature": "abc"});
+        [
+            Message::assistant()
+                .with_thinking("I should list the files", "sig")
+                .with_tool_request_with_metadata(
+                    id,
+                    Ok(CallToolRequestParam {
+                        name: "developer__shell".into(),
+                        arguments: None,
+                    }),
+                    metadata.as_object(),
+                ),
+            Message::user().with_tool_response(
+                id,
+                Ok(CallToolResult::success(vec![Content::text("README.md")])),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_reencode_for_provider_switch() {
+        let mut messages = vec![Message::user().with_text("list files")];
+        messages.extend(tool_exchange("functions.shell:0"));
+        messages.extend(tool_exchange("functions_shell_0"));
+        let conversation = Conversation::new_unvalidated(messages);
+
+        let reencoded = reencode_for_provider_switch(&conversation).unwrap();
+
+        // The user still sees the thinking, the model doesn't
+        assert_eq!(reencoded.messages().len(), 7);
+        let agent_messages = reencoded.agent_visible_messages();
+        assert_eq!(agent_messages.len(), 5);
+        assert!(!agent_messages
+            .iter()
+            .flat_map(|msg| msg.content.iter())
+            .any(is_thinking));
+
+        let ids: Vec<_> = agent_messages
+            .iter()
+            .flat_map(|msg| msg.content.iter())
+            .filter_map(|content| match content {
+                MessageContent::ToolRequest(request) => {
+                    assert!(request.metadata.is_none());
+                    Some(request.id.clone())
+                }
+                MessageContent::ToolResponse(response) => Some(response.id.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                "functions_shell_0_1",
+                "functions_shell_0_1",
+                "functions_shell_0",
+                "functions_shell_0"
+            ]
+        );
+        Conversation::new(agent_messages).expect("re-encoded history should be valid");
+
+        assert!(reencode_for_provider_switch(&reencoded).is_none());
+    }
+}
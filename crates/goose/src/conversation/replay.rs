@@ -0,0 +1,91 @@
+//! Replay of a recorded conversation against a provider, for regression-testing prompt,
+//! tool or model changes.
+//!
+//! Each assistant message is generated again from the recorded history before it, at
+//! temperature zero. Tools are not run: later turns see the recorded tool results, so every
+//! regenerated message answers exactly the context the original did and the replay can be
+//! compared message by message with [`diff_conversations`].
+
+use rmcp::model::{Role, Tool};
+
+use super::diff::{diff_conversations, ConversationDiff};
+use super::Conversation;
+use crate::providers::base::{Provider, Usage};
+use crate::providers::errors::ProviderError;
+
+#[derive(Debug, Clone)]
+pub struct ReplayOutcome {
+    /// The recorded conversation with each assistant message replaced by the regenerated one
+    pub replayed: Conversation,
+    /// How the replay differs from the recording
+    pub diff: ConversationDiff,
+    pub usage: Usage,
+}
+
+/// Replay the agent-visible messages of `conversation` against `provider` with the given
+/// system prompt and tools
+pub async fn replay_conversation(
+    provider: &dyn Provider,
+    system: &str,
+    conversation: &Conversation,
+    tools: &[Tool],
+) -> Result<ReplayOutcome, ProviderError> {
+    let model_config = provider.get_model_config().with_temperature(Some(0.0));
+    let recorded = Conversation::new_unvalidated(conversation.agent_visible_messages());
+
+    let mut replayed = Vec::with_capacity(recorded.len());
+    let mut usage = Usage::default();
+    for (idx, message) in recorded.iter().enumerate() {
+        // A conversation opening with the assistant has nothing to regenerate it from
+        if message.role != Role::Assistant || idx == 0 {
+            replayed.push(message.clone());
+            continue;
+        }
+        let (response, provider_usage) = provider
+            .complete_with_model(&model_config, system, &recorded.messages()[..idx], tools)
+            .await?;
+        usage += provider_usage.usage;
+        replayed.push(response);
+    }
+
+    let replayed = Conversation::new_unvalidated(replayed);
+    Ok(ReplayOutcome {
+        diff: diff_conversations(&recorded, &replayed),
+        replayed,
+        usage,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::diff::MessageDiff;
+    use crate::conversation::message::Message;
+    use crate::providers::mock::{MockProvider, MockResponse};
+
+    #[tokio::test]
+    async fn test_replay_conversation() {
+        let recorded = Conversation::new_unvalidated(vec![
+            Message::user().with_text("name a color"),
+            Message::assistant().with_text("blue"),
+            Message::user().with_text("another"),
+            Message::assistant().with_text("green"),
+        ]);
+        let provider = MockProvider::new()
+            .with_responses([MockResponse::text("blue"), MockResponse::text("red")]);
+
+        let outcome = replay_conversation(&provider, "system", &recorded, &[])
+            .await
+            .unwrap();
+
+        // The second turn was generated from the recorded first answer
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].messages, recorded.messages()[..3]);
+        assert_eq!(
+            outcome.diff.changes().collect::<Vec<_>>(),
+            [&MessageDiff::Changed { left: 3, right: 3 }]
+        );
+        assert_eq!(outcome.replayed.messages()[3].as_concat_text(), "red");
+    }
+}
@@ -0,0 +1,444 @@
+//! Running a task from start to finish with nobody at the keyboard.
+//!
+//! [`run_task`] is for CI jobs and programs that embed goose. It gives the agent instructions
+//! or a recipe and lets it work until it is done. What came of the run is returned as a
+//! [`TaskResult`] that serializes to JSON: the answer, the session holding the transcript,
+//! token usage, the tools called and how the run ended. Nobody can approve tool calls, so
+//! those needing approval are denied unless the request allows them.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use anyhow::Result;
+use futures::StreamExt;
+use rmcp::model::Role;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use crate::agents::{Agent, AgentEvent, ExtensionConfig, SessionConfig};
+use crate::config::extensions::get_enabled_extensions;
+use crate::config::Config;
+use crate::conversation::message::{ActionRequiredData, Message, MessageContent};
+use crate::model::ModelConfig;
+use crate::permission::permission_confirmation::PrincipalType;
+use crate::permission::{Permission, PermissionConfirmation};
+use crate::providers;
+use crate::recipe::launch::launch_recipe;
+use crate::recipe::Recipe;
+use crate::session::{SessionManager, SessionType};
+use crate::webhooks::{self, WebhookEvent};
+
+/// What the agent is asked to do
+#[derive(Debug, Clone)]
+pub enum TaskInput {
+    Instructions(String),
+    Recipe {
+        recipe: Box<Recipe>,
+        /// Where relative paths in the recipe, such as those of sub-recipes, are resolved from
+        recipe_dir: PathBuf,
+        params: Vec<(String, String)>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskRequest {
+    pub input: TaskInput,
+    pub working_dir: PathBuf,
+    /// Extensions to run with. If not given, a recipe's own extensions are used, or else
+    /// those enabled in the config.
+    pub extensions: Option<Vec<ExtensionConfig>>,
+    pub max_turns: Option<u32>,
+    /// Allow tool calls that need approval instead of denying them
+    pub approve_tools: bool,
+    pub cancellation_token: Option<CancellationToken>,
+}
+
+impl TaskRequest {
+    pub fn instructions(instructions: impl Into<String>, working_dir: PathBuf) -> Self {
+        Self::new(TaskInput::Instructions(instructions.into()), working_dir)
+    }
+
+    pub fn recipe(
+        recipe: Recipe,
+        recipe_dir: PathBuf,
+        params: Vec<(String, String)>,
+        working_dir: PathBuf,
+    ) -> Self {
+        Self::new(
+            TaskInput::Recipe {
+                recipe: Box::new(recipe),
+                recipe_dir,
+                params,
+            },
+            working_dir,
+        )
+    }
+
+    fn new(input: TaskInput, working_dir: PathBuf) -> Self {
+        Self {
+            input,
+            working_dir,
+            extensions: None,
+            max_turns: None,
+            approve_tools: false,
+            cancellation_token: None,
+        }
+    }
+}
+
+/// How a task ended
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Completed,
+    Failed,
+    Cancelled,
+    /// The session reached a limit of its budget
+    BudgetExceeded,
+}
+
+impl TaskStatus {
+    /// The exit code a command line tool running the task should end with
+    pub fn exit_code(self) -> i32 {
+        match self {
+            TaskStatus::Completed => 0,
+            TaskStatus::Failed => 1,
+            TaskStatus::BudgetExceeded => 2,
+            TaskStatus::Cancelled => 130,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TaskUsage {
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub total_tokens: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+}
+
+/// How often the agent called a tool during a task
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ToolCallSummary {
+    pub name: String,
+    pub calls: usize,
+    /// Calls that returned an error or were denied
+    pub failures: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskResult {
+    pub status: TaskStatus,
+    pub exit_code: i32,
+    /// The agent's last message
+    pub answer: Option<String>,
+    /// The answer as JSON, if the recipe asked for a response matching a schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured_answer: Option<Value>,
+    /// The session holding the transcript, if the task got far enough to start one
+    pub session_id: Option<String>,
+    pub usage: TaskUsage,
+    /// Tools in the order they were first called
+    pub tool_calls: Vec<ToolCallSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// What a task has come to so far, gathered from the agent's messages
+#[derive(Debug, Default)]
+struct TaskProgress {
+    session_id: Option<String>,
+    answer: Option<String>,
+    structured_answer: Option<Value>,
+    tool_calls: Vec<ToolCallSummary>,
+    /// Tool names by the ID of the request calling them
+    tool_requests: HashMap<String, String>,
+    error: Option<String>,
+}
+
+impl TaskProgress {
+    fn record_message(&mut self, message: &Message) {
+        for content in &message.content {
+            match content {
+                MessageContent::ToolRequest(request) => {
+                    let name = match &request.tool_call {
+                        Ok(call) => call.name.to_string(),
+                        Err(_) => "invalid tool call".to_string(),
+                    };
+                    self.tool_requests.insert(request.id.clone(), name.clone());
+                    match self.tool_calls.iter_mut().find(|tool| tool.name == name) {
+                        Some(tool) => tool.calls += 1,
+                        None => self.tool_calls.push(ToolCallSummary {
+                            name,
+                            calls: 1,
+                            failures: 0,
+                        }),
+                    }
+                }
+                MessageContent::ToolResponse(response) => {
+                    let failed = match &response.tool_result {
+                        Ok(result) => result.is_error == Some(true),
+                        Err(_) => true,
+                    };
+                    let name = self.tool_requests.get(&response.id);
+                    if let Some(tool) = self
+                        .tool_calls
+                        .iter_mut()
+                        .find(|tool| Some(&tool.name) == name)
+                    {
+                        tool.failures += usize::from(failed);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if message.role == Role::Assistant {
+            let text = message.as_concat_text();
+            if !text.trim().is_empty() {
+                self.answer = Some(text);
+            }
+        }
+    }
+}
+
+async fn execute(request: &TaskRequest, progress: &mut TaskProgress) -> Result<TaskStatus> {
+    let agent = Agent::new();
+    let recipe_has_extensions = matches!(
+        &request.input,
+        TaskInput::Recipe { recipe, .. } if recipe.extensions.is_some()
+    );
+    let extensions = match &request.extensions {
+        Some(extensions) => extensions.clone(),
+        None if recipe_has_extensions => Vec::new(),
+        None => get_enabled_extensions(),
+    };
+    for extension in extensions {
+        let name = extension.name();
+        if let Err(e) = agent.add_extension(extension).await {
+            tracing::warn!("Failed to add extension {} to the task: {}", name, e);
+        }
+    }
+
+    let (session, prompt, retry_config) = match &request.input {
+        TaskInput::Instructions(instructions) => {
+            let session = SessionManager::create_session(
+                request.working_dir.clone(),
+                "Headless task".to_string(),
+                SessionType::User,
+            )
+            .await?;
+            progress.session_id = Some(session.id.clone());
+
+            let config = Config::global();
+            let model_config = ModelConfig::new(&config.get_goose_model()?)?;
+            let provider = providers::create(&config.get_goose_provider()?, model_config).await?;
+            agent.update_provider(provider, &session.id).await?;
+            (session, instructions.clone(), None)
+        }
+        TaskInput::Recipe {
+            recipe,
+            recipe_dir,
+            params,
+        } => {
+            let launch = launch_recipe(
+                &agent,
+                recipe,
+                recipe_dir,
+                params.clone(),
+                request.working_dir.clone(),
+            )
+            .await?;
+            progress.session_id = Some(launch.session.id.clone());
+            let prompt = launch.prompt.unwrap_or_else(|| "Begin.".to_string());
+            (launch.session, prompt, launch.recipe.retry)
+        }
+    };
+
+    webhooks::notify(
+        WebhookEvent::SessionStarted,
+        Some(&session.id),
+        json!({"source": "task"}),
+    );
+
+    let session_config = SessionConfig {
+        id: session.id.clone(),
+        schedule_id: None,
+        max_turns: request.max_turns,
+        retry_config,
+        moderation: None,
+        context_policy: None,
+        budget: None,
+        budget_session_id: None,
+        model_routing: None,
+        lead_worker: None,
+        reflection: None,
+        final_answer_schema: None,
+    };
+    let cancel_token = request.cancellation_token.clone().unwrap_or_default();
+    let user_message = Message::user().with_text(prompt);
+    let stream = crate::session_context::with_session_id(Some(session.id.clone()), async {
+        agent
+            .reply(user_message, session_config, Some(cancel_token.clone()))
+            .await
+    })
+    .await?;
+    let mut stream = std::pin::pin!(stream);
+
+    let permission = if request.approve_tools {
+        Permission::AllowOnce
+    } else {
+        Permission::DenyOnce
+    };
+    let mut status = TaskStatus::Completed;
+    while let Some(event) = stream.next().await {
+        match event? {
+            AgentEvent::Message(message) => {
+                for content in &message.content {
+                    if let MessageContent::ActionRequired(action) = content {
+                        if let ActionRequiredData::ToolConfirmation { id, tool_name, .. } =
+                            &action.data
+                        {
+                            if !request.approve_tools {
+                                tracing::info!("Denied {}, as nobody can approve it", tool_name);
+                            }
+                            agent
+                                .handle_confirmation(
+                                    id.clone(),
+                                    PermissionConfirmation {
+                                        principal_type: PrincipalType::Tool,
+                                        permission: permission.clone(),
+                                    },
+                                )
+                                .await;
+                        }
+                    }
+                }
+                progress.record_message(&message);
+            }
+            AgentEvent::FinalAnswer(answer) => progress.structured_answer = Some(answer),
+            AgentEvent::BudgetExceeded(exceeded) => {
+                progress.error = Some(exceeded.to_string());
+                status = TaskStatus::BudgetExceeded;
+            }
+            _ => {}
+        }
+    }
+
+    if cancel_token.is_cancelled() {
+        return Ok(TaskStatus::Cancelled);
+    }
+    Ok(status)
+}
+
+/// Run `request` to the end without interaction and report how it went. Failures, including
+/// those setting the task up, are reported in the result rather than returned.
+pub async fn run_task(request: TaskRequest) -> TaskResult {
+    let started = Instant::now();
+    let mut progress = TaskProgress::default();
+    let status = match execute(&request, &mut progress).await {
+        Ok(status) => status,
+        Err(e) => {
+            progress.error = Some(e.to_string());
+            TaskStatus::Failed
+        }
+    };
+
+    let mut usage = TaskUsage::default();
+    if let Some(session_id) = &progress.session_id {
+        match SessionManager::get_session(session_id, false).await {
+            Ok(session) => {
+                usage = TaskUsage {
+                    input_tokens: session.accumulated_input_tokens.unwrap_or_default().into(),
+                    output_tokens: session.accumulated_output_tokens.unwrap_or_default().into(),
+                    total_tokens: session.accumulated_total_tokens.unwrap_or_default().into(),
+                    cost: session.accumulated_cost,
+                }
+            }
+            Err(e) => tracing::warn!("Failed to read the usage of task {}: {}", session_id, e),
+        }
+    }
+
+    let event = match status {
+        TaskStatus::Completed => WebhookEvent::SessionFinished,
+        _ => WebhookEvent::SessionFailed,
+    };
+    webhooks::notify(
+        event,
+        progress.session_id.as_deref(),
+        json!({"source": "task", "status": status, "error": progress.error}),
+    );
+
+    TaskResult {
+        status,
+        exit_code: status.exit_code(),
+        answer: progress.answer,
+        structured_answer: progress.structured_answer,
+        session_id: progress.session_id,
+        usage,
+        tool_calls: progress.tool_calls,
+        error: progress.error,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::{CallToolRequestParam, CallToolResult, Content, ErrorCode, ErrorData};
+
+    fn tool_call(name: &str) -> CallToolRequestParam {
+        CallToolRequestParam {
+            name: name.to_string().into(),
+            arguments: None,
+        }
+    }
+
+    #[test]
+    fn test_progress_summarizes_tool_calls_and_answer() {
+        let mut progress = TaskProgress::default();
+        progress.record_message(
+            &Message::assistant()
+                .with_text("Looking around")
+                .with_tool_request("1", Ok(tool_call("shell")))
+                .with_tool_request("2", Ok(tool_call("read_file"))),
+        );
+        progress.record_message(
+            &Message::user()
+                .with_tool_response("1", Ok(CallToolResult::success(vec![Content::text("ok")])))
+                .with_tool_response(
+                    "2",
+                    Err(ErrorData::new(ErrorCode::INTERNAL_ERROR, "missing", None)),
+                ),
+        );
+        progress
+            .record_message(&Message::assistant().with_tool_request("3", Ok(tool_call("shell"))));
+        progress.record_message(&Message::user().with_tool_response(
+            "3",
+            Ok(CallToolResult::error(vec![Content::text("exit 1")])),
+        ));
+        progress.record_message(&Message::assistant().with_text("All done"));
+
+        assert_eq!(progress.answer.as_deref(), Some("All done"));
+        assert_eq!(
+            progress.tool_calls,
+            vec![
+                ToolCallSummary {
+                    name: "shell".to_string(),
+                    calls: 2,
+                    failures: 1,
+                },
+                ToolCallSummary {
+                    name: "read_file".to_string(),
+                    calls: 1,
+                    failures: 1,
+                },
+            ]
+        );
+        assert_eq!(TaskStatus::Cancelled.exit_code(), 130);
+    }
+}
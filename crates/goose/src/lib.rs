@@ -1,5 +1,7 @@
 pub mod action_required_manager;
 pub mod agents;
+pub mod audit;
+pub mod code_search;
 pub mod config;
 pub mod context_mgmt;
 pub mod conversation;
@@ -8,7 +10,11 @@ pub mod goose_apps;
 pub mod hints;
 pub mod logging;
 pub mod mcp_utils;
+pub mod memory;
+pub mod metrics;
 pub mod model;
+pub mod model_catalog;
+pub mod model_presets;
 pub mod oauth;
 pub mod permission;
 pub mod posthog;
@@ -16,6 +22,7 @@ pub mod prompt_template;
 pub mod providers;
 pub mod recipe;
 pub mod recipe_deeplink;
+pub mod redaction;
 pub mod scheduler;
 pub mod scheduler_trait;
 pub mod security;
@@ -28,3 +35,5 @@ pub mod tool_inspection;
 pub mod tool_monitor;
 pub mod tracing;
 pub mod utils;
+pub mod web_fetch;
+pub mod webhooks;
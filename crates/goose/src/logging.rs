@@ -1,9 +1,45 @@
 use crate::config::paths::Paths;
+use crate::config::Config;
 use anyhow::{Context, Result};
+use once_cell::sync::OnceCell;
 use std::fs;
 use std::path::PathBuf;
 use std::time::{Duration, SystemTime};
 
+/// Filter directives for logging, such as `info` or `goose=debug,warn`. RUST_LOG wins if set.
+pub const LOG_LEVEL_CONFIG_KEY: &str = "GOOSE_LOG_LEVEL";
+
+/// Replaces the filter of the running logger; `None` restores the default one
+pub type LogLevelReloader = Box<dyn Fn(Option<&str>) -> Result<()> + Send + Sync>;
+
+static LOG_LEVEL_RELOADER: OnceCell<LogLevelReloader> = OnceCell::new();
+
+/// The log filter set in the config, unless RUST_LOG is set
+pub fn configured_log_level() -> Option<String> {
+    if std::env::var("RUST_LOG").is_ok() {
+        return None;
+    }
+    Config::global().get_param(LOG_LEVEL_CONFIG_KEY).ok()
+}
+
+/// Let [`reload_log_level`] change the filter of the logger that was set up
+pub fn register_log_level_reloader(reloader: LogLevelReloader) {
+    if LOG_LEVEL_RELOADER.set(reloader).is_err() {
+        tracing::warn!("A log level reloader was already registered");
+    }
+}
+
+/// Apply the log filter in the config to the running logger, if it can be changed
+pub fn reload_log_level() -> Result<()> {
+    if std::env::var("RUST_LOG").is_ok() {
+        return Ok(());
+    }
+    match LOG_LEVEL_RELOADER.get() {
+        Some(reloader) => reloader(configured_log_level().as_deref()),
+        None => Ok(()),
+    }
+}
+
 /// Returns the directory where log files should be stored for a specific component.
 /// Creates the directory structure if it doesn't exist.
 ///
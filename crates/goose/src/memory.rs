@@ -0,0 +1,339 @@
+//! Long-term memory that outlives sessions.
+//!
+//! A memory is a short fact or note, such as one of the user's preferences or how a project is
+//! built, stored along with an embedding of its text. Memories live in a file in goose's data
+//! directory and are shared by all of the user's sessions. Recall ranks them by the cosine
+//! similarity of their embedding to the query's. Embeddings come from the provider named by
+//! GOOSE_MEMORY_EMBEDDING_PROVIDER, falling back to GOOSE_PROVIDER, with the options of
+//! [`EmbeddingOptions::from_config`].
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::config::paths::Paths;
+use crate::config::Config;
+use crate::providers::embedding::{create_embedding_provider, EmbeddingOptions, EmbeddingProvider};
+
+pub const MEMORY_EMBEDDING_PROVIDER_CONFIG_KEY: &str = "GOOSE_MEMORY_EMBEDDING_PROVIDER";
+/// Where memories are kept, relative to the data directory
+pub const MEMORY_FILE: &str = "memory/memories.json";
+/// Lowest similarity to a query at which a memory counts as relevant to it
+pub const MIN_RELEVANCE: f32 = 0.3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Memory {
+    pub id: String,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub embedding: Vec<f32>,
+}
+
+/// A memory found by [`MemoryStore::recall`], with its similarity to the query
+#[derive(Debug, Clone)]
+pub struct RecalledMemory {
+    pub memory: Memory,
+    pub relevance: f32,
+}
+
+/// Cosine similarity of two embeddings, 0 if they can't be compared
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 {
+        0.0
+    } else {
+        dot / denominator
+    }
+}
+
+/// An exclusive lock on the file next to the memory file, held by whichever goose process is
+/// changing it so that processes sharing the file don't undo each other's changes. It is
+/// released when dropped.
+struct FileLock {
+    _file: std::fs::File,
+}
+
+impl FileLock {
+    async fn acquire(memory_path: &Path) -> Result<Self> {
+        let lock_path = memory_path.with_extension("json.lock");
+        tokio::task::spawn_blocking(move || {
+            if let Some(parent) = lock_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(false)
+                .open(&lock_path)?;
+            file.lock_exclusive()?;
+            Ok(Self { _file: file })
+        })
+        .await?
+    }
+}
+
+pub struct MemoryStore {
+    path: PathBuf,
+    embedder: Arc<dyn EmbeddingProvider>,
+    options: EmbeddingOptions,
+    /// Serializes this store's changes to the memory file; [`FileLock`] does the same across
+    /// processes
+    lock: Mutex<()>,
+}
+
+impl MemoryStore {
+    pub fn new(
+        path: PathBuf,
+        embedder: Arc<dyn EmbeddingProvider>,
+        options: EmbeddingOptions,
+    ) -> Self {
+        Self {
+            path,
+            embedder,
+            options,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// The user's memory store, embedding with the configured provider
+    pub async fn from_config() -> Result<Self> {
+        let config = Config::global();
+        let provider_name: String = config
+            .get_param(MEMORY_EMBEDDING_PROVIDER_CONFIG_KEY)
+            .or_else(|_| config.get_param("GOOSE_PROVIDER"))
+            .map_err(|_| {
+                anyhow!(
+                    "No embedding provider for memory, set {}",
+                    MEMORY_EMBEDDING_PROVIDER_CONFIG_KEY
+                )
+            })?;
+        let embedder = create_embedding_provider(&provider_name).await?;
+        Ok(Self::new(
+            Paths::in_data_dir(MEMORY_FILE),
+            embedder,
+            EmbeddingOptions::from_config(),
+        ))
+    }
+
+    async fn load(&self) -> Result<Vec<Memory>> {
+        match tokio::fs::read_to_string(&self.path).await {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn save(&self, memories: &[Memory]) -> Result<()> {
+        let contents = serde_json::to_vec(memories)?;
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || {
+            let parent = path
+                .parent()
+                .ok_or_else(|| anyhow!("No directory for {}", path.display()))?;
+            std::fs::create_dir_all(parent)?;
+            // Write a temp file of our own then rename it, so a crash can't leave a half-written
+            // file behind and another process writing at the same time can't clobber it
+            let mut temp = tempfile::NamedTempFile::new_in(parent)?;
+            temp.write_all(&contents)?;
+            temp.as_file().sync_all()?;
+            temp.persist(&path)?;
+            Ok(())
+        })
+        .await?
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.embedder
+            .embed(vec![text.to_string()], &self.options)
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("The embedding provider returned no embedding"))
+    }
+
+    pub async fn list(&self) -> Result<Vec<Memory>> {
+        let _guard = self.lock.lock().await;
+        self.load().await
+    }
+
+    pub async fn remember(&self, text: &str, tags: Vec<String>) -> Result<Memory> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(anyhow!("Cannot remember an empty memory"));
+        }
+        let embedding = self.embed(text).await?;
+
+        let _guard = self.lock.lock().await;
+        let _file_lock = FileLock::acquire(&self.path).await?;
+        let mut memories = self.load().await?;
+        let memory = Memory {
+            id: Uuid::new_v4().to_string(),
+            text: text.to_string(),
+            tags,
+            created_at: Utc::now(),
+            embedding,
+        };
+        memories.push(memory.clone());
+        self.save(&memories).await?;
+        Ok(memory)
+    }
+
+    /// Up to `limit` memories relevant to `query`, most relevant first
+    pub async fn recall(&self, query: &str, limit: usize) -> Result<Vec<RecalledMemory>> {
+        let memories = self.list().await?;
+        if memories.is_empty() || limit == 0 {
+            return Ok(vec![]);
+        }
+        let query = self.embed(query).await?;
+
+        let mut recalled: Vec<RecalledMemory> = memories
+            .into_iter()
+            .map(|memory| RecalledMemory {
+                relevance: cosine_similarity(&query, &memory.embedding),
+                memory,
+            })
+            .filter(|recalled| recalled.relevance >= MIN_RELEVANCE)
+            .collect();
+        recalled.sort_by(|a, b| b.relevance.total_cmp(&a.relevance));
+        recalled.truncate(limit);
+        Ok(recalled)
+    }
+
+    /// Delete memory `id`, returning it if it existed
+    pub async fn forget(&self, id: &str) -> Result<Option<Memory>> {
+        let _guard = self.lock.lock().await;
+        let _file_lock = FileLock::acquire(&self.path).await?;
+        let mut memories = self.load().await?;
+        let Some(index) = memories.iter().position(|memory| memory.id == id) else {
+            return Ok(None);
+        };
+        let memory = memories.remove(index);
+        self.save(&memories).await?;
+        Ok(Some(memory))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::errors::ProviderError;
+    use async_trait::async_trait;
+
+    /// Embeds text by which of a few topics it mentions
+    struct TopicEmbedder;
+
+    #[async_trait]
+    impl EmbeddingProvider for TopicEmbedder {
+        fn default_embedding_model(&self) -> &str {
+            "topics"
+        }
+
+        fn max_embedding_batch_size(&self) -> usize {
+            16
+        }
+
+        async fn embed_batch(
+            &self,
+            texts: &[String],
+            _model: &str,
+            _dimensions: Option<usize>,
+        ) -> Result<Vec<Vec<f32>>, ProviderError> {
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    ["rust", "python", "coffee"]
+                        .iter()
+                        .map(|topic| if text.contains(topic) { 1.0 } else { 0.0 })
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remember_recall_forget() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(MEMORY_FILE);
+        let store = MemoryStore::new(
+            path.clone(),
+            Arc::new(TopicEmbedder),
+            EmbeddingOptions::default(),
+        );
+
+        let rust = store
+            .remember(
+                "prefers rust with clippy on",
+                vec!["preference".to_string()],
+            )
+            .await
+            .unwrap();
+        store.remember("drinks coffee black", vec![]).await.unwrap();
+        assert!(store.remember("  ", vec![]).await.is_err());
+
+        // Memories persist for other stores on the same file
+        let reopened = MemoryStore::new(path, Arc::new(TopicEmbedder), EmbeddingOptions::default());
+        let recalled = reopened.recall("how to build rust code", 5).await.unwrap();
+        assert_eq!(recalled.len(), 1);
+        assert_eq!(recalled[0].memory.id, rust.id);
+        assert_eq!(recalled[0].memory.tags, vec!["preference"]);
+        assert!(reopened.recall("python", 5).await.unwrap().is_empty());
+
+        assert!(reopened.forget(&rust.id).await.unwrap().is_some());
+        assert!(reopened.forget(&rust.id).await.unwrap().is_none());
+        assert_eq!(store.list().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stores_sharing_a_file_keep_each_others_memories() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(MEMORY_FILE);
+        // Separate stores on one file stand in for separate goose processes
+        let stores: Vec<_> = (0..2)
+            .map(|_| {
+                Arc::new(MemoryStore::new(
+                    path.clone(),
+                    Arc::new(TopicEmbedder),
+                    EmbeddingOptions::default(),
+                ))
+            })
+            .collect();
+
+        let writes = (0..20).map(|i| {
+            let store = stores[i % stores.len()].clone();
+            tokio::spawn(async move { store.remember(&format!("note {i}"), vec![]).await })
+        });
+        for write in futures::future::join_all(writes).await {
+            write.unwrap().unwrap();
+        }
+
+        assert_eq!(stores[0].list().await.unwrap().len(), 20);
+        let leftovers: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .filter(|name| name != "memories.json" && name != "memories.json.lock")
+            .collect();
+        assert!(leftovers.is_empty(), "left behind: {leftovers:?}");
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]), 1.0);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+}
@@ -0,0 +1,378 @@
+//! Prometheus metrics for goose internals.
+//!
+//! Collection starts with the first call to [`metrics_handle`], which registers provider
+//! middleware, so call it before creating agents. Servers embedding goose serve
+//! [`MetricsHandle::render`] from their scrape endpoint:
+//!
+//! - `goose_provider_requests_total{provider,model}`
+//! - `goose_provider_errors_total{provider,model,error_type}`
+//! - `goose_provider_tokens_total{provider,model,kind}`, kind being input, output or cached_input
+//! - `goose_provider_request_duration_seconds{provider,model}` (histogram)
+//! - `goose_tool_calls_total{tool,outcome}`, outcome being success or error
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use once_cell::sync::Lazy;
+
+use crate::conversation::message::Message;
+use crate::providers::base::ProviderUsage;
+use crate::providers::errors::ProviderError;
+use crate::providers::middleware::{register_middleware, ProviderMiddleware, ProviderRequest};
+
+const DURATION_BUCKETS: &[f64] = &[
+    0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static REGISTER: Once = Once::new();
+
+/// Label values of one series, in the order of the metric's label names
+type Labels = Vec<String>;
+
+struct Counter {
+    name: &'static str,
+    help: &'static str,
+    label_names: &'static [&'static str],
+    series: Mutex<BTreeMap<Labels, f64>>,
+}
+
+impl Counter {
+    fn new(name: &'static str, help: &'static str, label_names: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            help,
+            label_names,
+            series: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn inc_by(&self, labels: &[&str], value: f64) {
+        let labels = labels.iter().map(|l| l.to_string()).collect();
+        *self.series.lock().unwrap().entry(labels).or_default() += value;
+    }
+
+    fn render(&self, out: &mut String) {
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} counter", self.name);
+        for (labels, value) in self.series.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "{}{} {}",
+                self.name,
+                format_labels(self.label_names, labels, None),
+                value
+            );
+        }
+    }
+}
+
+#[derive(Clone)]
+struct HistogramSeries {
+    bucket_counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+struct Histogram {
+    name: &'static str,
+    help: &'static str,
+    label_names: &'static [&'static str],
+    buckets: &'static [f64],
+    series: Mutex<BTreeMap<Labels, HistogramSeries>>,
+}
+
+impl Histogram {
+    fn new(
+        name: &'static str,
+        help: &'static str,
+        label_names: &'static [&'static str],
+        buckets: &'static [f64],
+    ) -> Self {
+        Self {
+            name,
+            help,
+            label_names,
+            buckets,
+            series: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn observe(&self, labels: &[&str], value: f64) {
+        let labels = labels.iter().map(|l| l.to_string()).collect();
+        let mut series = self.series.lock().unwrap();
+        let series = series.entry(labels).or_insert_with(|| HistogramSeries {
+            bucket_counts: vec![0; self.buckets.len()],
+            sum: 0.0,
+            count: 0,
+        });
+        for (bound, count) in self.buckets.iter().zip(series.bucket_counts.iter_mut()) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        series.sum += value;
+        series.count += 1;
+    }
+
+    fn render(&self, out: &mut String) {
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} histogram", self.name);
+        for (labels, series) in self.series.lock().unwrap().iter() {
+            for (bound, count) in self.buckets.iter().zip(&series.bucket_counts) {
+                let le = bound.to_string();
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{} {}",
+                    self.name,
+                    format_labels(self.label_names, labels, Some(&le)),
+                    count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{}_bucket{} {}",
+                self.name,
+                format_labels(self.label_names, labels, Some("+Inf")),
+                series.count
+            );
+            let labels = format_labels(self.label_names, labels, None);
+            let _ = writeln!(out, "{}_sum{} {}", self.name, labels, series.sum);
+            let _ = writeln!(out, "{}_count{} {}", self.name, labels, series.count);
+        }
+    }
+}
+
+fn format_labels(names: &[&str], values: &[String], le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = names
+        .iter()
+        .zip(values)
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape_label_value(value)))
+        .collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{}\"", le));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+struct Metrics {
+    provider_requests: Counter,
+    provider_errors: Counter,
+    provider_tokens: Counter,
+    provider_duration: Histogram,
+    tool_calls: Counter,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            provider_requests: Counter::new(
+                "goose_provider_requests_total",
+                "Requests sent to model providers",
+                &["provider", "model"],
+            ),
+            provider_errors: Counter::new(
+                "goose_provider_errors_total",
+                "Failed provider requests by error type",
+                &["provider", "model", "error_type"],
+            ),
+            provider_tokens: Counter::new(
+                "goose_provider_tokens_total",
+                "Tokens used by provider requests",
+                &["provider", "model", "kind"],
+            ),
+            provider_duration: Histogram::new(
+                "goose_provider_request_duration_seconds",
+                "Time from sending a provider request to its complete response",
+                &["provider", "model"],
+                DURATION_BUCKETS,
+            ),
+            tool_calls: Counter::new(
+                "goose_tool_calls_total",
+                "Tool calls executed by agents",
+                &["tool", "outcome"],
+            ),
+        }
+    }
+
+    fn record_response(&self, request: &ProviderRequest, usage: &ProviderUsage) {
+        let provider = request.provider.as_str();
+        let model = request.model_config.model_name.as_str();
+        for (kind, tokens) in [
+            ("input", usage.usage.input_tokens),
+            ("output", usage.usage.output_tokens),
+            ("cached_input", usage.usage.cached_input_tokens),
+        ] {
+            if let Some(tokens) = tokens {
+                self.provider_tokens
+                    .inc_by(&[provider, model, kind], tokens.max(0) as f64);
+            }
+        }
+        self.record_duration(request);
+    }
+
+    fn record_duration(&self, request: &ProviderRequest) {
+        let elapsed = (Utc::now() - request.started_at)
+            .to_std()
+            .unwrap_or_default()
+            .as_secs_f64();
+        self.provider_duration.observe(
+            &[
+                request.provider.as_str(),
+                request.model_config.model_name.as_str(),
+            ],
+            elapsed,
+        );
+    }
+}
+
+/// Records provider calls into the global registry
+struct MetricsMiddleware;
+
+#[async_trait]
+impl ProviderMiddleware for MetricsMiddleware {
+    async fn on_request(&self, request: &mut ProviderRequest) -> Result<(), ProviderError> {
+        METRICS.provider_requests.inc_by(
+            &[
+                request.provider.as_str(),
+                request.model_config.model_name.as_str(),
+            ],
+            1.0,
+        );
+        Ok(())
+    }
+
+    async fn on_response(
+        &self,
+        request: &ProviderRequest,
+        _message: &mut Message,
+        usage: &mut ProviderUsage,
+    ) -> Result<(), ProviderError> {
+        METRICS.record_response(request, usage);
+        Ok(())
+    }
+
+    async fn on_error(&self, request: &ProviderRequest, error: ProviderError) -> ProviderError {
+        METRICS.provider_errors.inc_by(
+            &[
+                request.provider.as_str(),
+                request.model_config.model_name.as_str(),
+                error.telemetry_type(),
+            ],
+            1.0,
+        );
+        METRICS.record_duration(request);
+        error
+    }
+
+    fn on_stream_chunk(
+        &self,
+        request: &ProviderRequest,
+        _message: &mut Option<Message>,
+        usage: &mut Option<ProviderUsage>,
+    ) -> Result<(), ProviderError> {
+        // Final usage arrives with the last chunk, so it marks the end of the response
+        if let Some(usage) = usage.as_ref().filter(|usage| !usage.interim) {
+            METRICS.record_response(request, usage);
+        }
+        Ok(())
+    }
+}
+
+/// Count a finished tool call. Does nothing until [`metrics_handle`] has been called.
+pub fn record_tool_call(tool: &str, success: bool) {
+    if ENABLED.load(Ordering::Relaxed) {
+        let outcome = if success { "success" } else { "error" };
+        METRICS.tool_calls.inc_by(&[tool, outcome], 1.0);
+    }
+}
+
+/// Access to the metrics registry
+#[derive(Clone, Copy)]
+pub struct MetricsHandle {
+    metrics: &'static Metrics,
+}
+
+impl MetricsHandle {
+    /// All metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let metrics = self.metrics;
+        metrics.provider_requests.render(&mut out);
+        metrics.provider_errors.render(&mut out);
+        metrics.provider_tokens.render(&mut out);
+        metrics.provider_duration.render(&mut out);
+        metrics.tool_calls.render(&mut out);
+        out
+    }
+
+    /// Content type to serve [`render`](Self::render) with
+    pub fn content_type(&self) -> &'static str {
+        "text/plain; version=0.0.4"
+    }
+}
+
+/// The metrics registry, starting collection on first use
+pub fn metrics_handle() -> MetricsHandle {
+    REGISTER.call_once(|| {
+        ENABLED.store(true, Ordering::Relaxed);
+        register_middleware(Arc::new(MetricsMiddleware));
+    });
+    MetricsHandle { metrics: &METRICS }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counter_and_histogram() {
+        let counter = Counter::new("test_total", "A test counter", &["name"]);
+        counter.inc_by(&["a\"b"], 2.0);
+        let histogram = Histogram::new("test_seconds", "A test histogram", &["name"], &[1.0, 5.0]);
+        histogram.observe(&["x"], 0.5);
+        histogram.observe(&["x"], 3.0);
+
+        let mut out = String::new();
+        counter.render(&mut out);
+        histogram.render(&mut out);
+
+        assert!(out.contains("# TYPE test_total counter\n"));
+        assert!(out.contains("test_total{name=\"a\\\"b\"} 2\n"));
+        assert!(out.contains("test_seconds_bucket{name=\"x\",le=\"1\"} 1\n"));
+        assert!(out.contains("test_seconds_bucket{name=\"x\",le=\"5\"} 2\n"));
+        assert!(out.contains("test_seconds_bucket{name=\"x\",le=\"+Inf\"} 2\n"));
+        assert!(out.contains("test_seconds_sum{name=\"x\"} 3.5\n"));
+        assert!(out.contains("test_seconds_count{name=\"x\"} 2\n"));
+    }
+
+    #[test]
+    fn test_tool_calls_recorded_once_enabled() {
+        let handle = metrics_handle();
+        record_tool_call("developer__shell", true);
+        record_tool_call("developer__shell", false);
+
+        let out = handle.render();
+        assert!(
+            out.contains("goose_tool_calls_total{tool=\"developer__shell\",outcome=\"success\"}")
+        );
+        assert!(out.contains("goose_tool_calls_total{tool=\"developer__shell\",outcome=\"error\"}"));
+        assert!(out.contains("# TYPE goose_provider_request_duration_seconds histogram"));
+    }
+}
@@ -1,6 +1,8 @@
-use once_cell::sync::Lazy;
+use crate::model_catalog::{self, ModelCapabilities};
+use crate::model_presets;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tokio::task_local;
 use utoipa::ToSchema;
 
 const DEFAULT_CONTEXT_LIMIT: usize = 128_000;
@@ -15,58 +17,66 @@ pub enum ConfigError {
     InvalidRange(String, String),
 }
 
-static MODEL_SPECIFIC_LIMITS: Lazy<Vec<(&'static str, usize)>> = Lazy::new(|| {
-    vec![
-        // openai
-        ("gpt-5", 272_000),
-        ("gpt-4-turbo", 128_000),
-        ("gpt-4.1", 1_000_000),
-        ("gpt-4-1", 1_000_000),
-        ("gpt-4o", 128_000),
-        ("o4-mini", 200_000),
-        ("o3-mini", 200_000),
-        ("o3", 200_000),
-        // anthropic - all 200k
-        ("claude", 200_000),
-        // google
-        ("gemini-1.5-flash", 1_000_000),
-        ("gemini-1", 128_000),
-        ("gemini-2", 1_000_000),
-        ("gemma-3-27b", 128_000),
-        ("gemma-3-12b", 128_000),
-        ("gemma-3-4b", 128_000),
-        ("gemma-3-1b", 32_000),
-        ("gemma3-27b", 128_000),
-        ("gemma3-12b", 128_000),
-        ("gemma3-4b", 128_000),
-        ("gemma3-1b", 32_000),
-        ("gemma-2-27b", 8_192),
-        ("gemma-2-9b", 8_192),
-        ("gemma-2-2b", 8_192),
-        ("gemma2-", 8_192),
-        ("gemma-7b", 8_192),
-        ("gemma-2b", 8_192),
-        ("gemma1", 8_192),
-        ("gemma", 8_192),
-        // facebook
-        ("llama-2-1b", 32_000),
-        ("llama", 128_000),
-        // qwen
-        ("qwen3-coder", 262_144),
-        ("qwen2-7b", 128_000),
-        ("qwen2-14b", 128_000),
-        ("qwen2-32b", 131_072),
-        ("qwen2-70b", 262_144),
-        ("qwen2", 128_000),
-        ("qwen3-32b", 131_072),
-        // xai
-        ("grok-4", 256_000),
-        ("grok-code-fast-1", 256_000),
-        ("grok", 131_072),
-        // other
-        ("kimi-k2", 131_072),
-    ]
-});
+/// Controls whether and how the model may call tools for a request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolChoice {
+    /// The model decides whether to call a tool (the provider default)
+    Auto,
+    /// The model must call at least one tool
+    Required,
+    /// The model must not call any tools
+    None,
+    /// The model must call the named tool
+    Tool(String),
+}
+
+task_local! {
+    static TOOL_CHOICE: ToolChoice;
+}
+
+/// Run `f` with `tool_choice` overriding the model's tool choice for every request it makes
+pub async fn with_tool_choice_override<F>(tool_choice: Option<ToolChoice>, f: F) -> F::Output
+where
+    F: std::future::Future,
+{
+    match tool_choice {
+        Some(tool_choice) => TOOL_CHOICE.scope(tool_choice, f).await,
+        None => f.await,
+    }
+}
+
+/// How much effort reasoning models should spend thinking before they answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReasoningEffort::Low => "low",
+            ReasoningEffort::Medium => "medium",
+            ReasoningEffort::High => "high",
+        }
+    }
+}
+
+impl std::str::FromStr for ReasoningEffort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "low" => Ok(ReasoningEffort::Low),
+            "medium" => Ok(ReasoningEffort::Medium),
+            "high" => Ok(ReasoningEffort::High),
+            _ => Err(format!("unknown reasoning effort '{}'", s)),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ModelConfig {
@@ -77,6 +87,28 @@ pub struct ModelConfig {
     pub toolshim: bool,
     pub toolshim_model: Option<String>,
     pub fast_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking_budget_tokens: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    /// Request token log probabilities with this many top alternatives per token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<u8>,
+    /// JSON schema the response text must match, for providers that support structured output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub response_schema: Option<serde_json::Value>,
+    /// Provider-specific request fields that the other options don't cover. Bedrock forwards
+    /// these as `additionalModelRequestFields`, e.g. `{"top_k": 50}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub additional_request_fields: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -98,8 +130,14 @@ impl ModelConfig {
         let temperature = Self::parse_temperature()?;
         let toolshim = Self::parse_toolshim()?;
         let toolshim_model = Self::parse_toolshim_model()?;
+        let parallel_tool_calls = Self::parse_parallel_tool_calls()?;
+        let reasoning_effort = Self::parse_reasoning_effort()?;
+        let thinking_budget_tokens = Self::parse_thinking_budget_tokens()?;
+        let seed = Self::parse_seed()?;
+        let logprobs = Self::parse_logprobs()?;
+        let additional_request_fields = Self::parse_additional_request_fields()?;
 
-        Ok(Self {
+        let config = Self {
             model_name,
             context_limit,
             temperature,
@@ -107,7 +145,19 @@ impl ModelConfig {
             toolshim,
             toolshim_model,
             fast_model: None,
-        })
+            tool_choice: None,
+            parallel_tool_calls,
+            reasoning_effort,
+            thinking_budget_tokens,
+            seed,
+            logprobs,
+            response_schema: None,
+            additional_request_fields,
+        };
+        Ok(model_presets::apply_presets(
+            config,
+            &model_presets::configured_presets(),
+        ))
     }
 
     fn parse_context_limit(
@@ -212,15 +262,117 @@ impl ModelConfig {
         }
     }
 
+    fn parse_parallel_tool_calls() -> Result<Option<bool>, ConfigError> {
+        if let Ok(val) = std::env::var("GOOSE_PARALLEL_TOOL_CALLS") {
+            match val.to_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => Ok(Some(true)),
+                "0" | "false" | "no" | "off" => Ok(Some(false)),
+                _ => Err(ConfigError::InvalidValue(
+                    "GOOSE_PARALLEL_TOOL_CALLS".to_string(),
+                    val,
+                    "must be one of: 1, true, yes, on, 0, false, no, off".to_string(),
+                )),
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_reasoning_effort() -> Result<Option<ReasoningEffort>, ConfigError> {
+        match std::env::var("GOOSE_REASONING_EFFORT") {
+            Ok(val) => val.parse().map(Some).map_err(|_| {
+                ConfigError::InvalidValue(
+                    "GOOSE_REASONING_EFFORT".to_string(),
+                    val,
+                    "must be one of: low, medium, high".to_string(),
+                )
+            }),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn parse_thinking_budget_tokens() -> Result<Option<i32>, ConfigError> {
+        if let Ok(val) = std::env::var("GOOSE_THINKING_BUDGET") {
+            let budget = val.parse::<i32>().map_err(|_| {
+                ConfigError::InvalidValue(
+                    "GOOSE_THINKING_BUDGET".to_string(),
+                    val.clone(),
+                    "must be a valid integer".to_string(),
+                )
+            })?;
+            if budget < 0 {
+                return Err(ConfigError::InvalidRange(
+                    "GOOSE_THINKING_BUDGET".to_string(),
+                    val,
+                ));
+            }
+            Ok(Some(budget))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_seed() -> Result<Option<i64>, ConfigError> {
+        if let Ok(val) = std::env::var("GOOSE_SEED") {
+            let seed = val.parse::<i64>().map_err(|_| {
+                ConfigError::InvalidValue(
+                    "GOOSE_SEED".to_string(),
+                    val.clone(),
+                    "must be a valid integer".to_string(),
+                )
+            })?;
+            Ok(Some(seed))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_logprobs() -> Result<Option<u8>, ConfigError> {
+        if let Ok(val) = std::env::var("GOOSE_LOGPROBS") {
+            let top_logprobs = val.parse::<u8>().map_err(|_| {
+                ConfigError::InvalidValue(
+                    "GOOSE_LOGPROBS".to_string(),
+                    val.clone(),
+                    "must be the number of top alternatives to return (0-20)".to_string(),
+                )
+            })?;
+            if top_logprobs > 20 {
+                return Err(ConfigError::InvalidRange("GOOSE_LOGPROBS".to_string(), val));
+            }
+            Ok(Some(top_logprobs))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_additional_request_fields(
+    ) -> Result<Option<serde_json::Map<String, serde_json::Value>>, ConfigError> {
+        if let Ok(val) = std::env::var("GOOSE_ADDITIONAL_REQUEST_FIELDS") {
+            let fields = serde_json::from_str(&val).map_err(|_| {
+                ConfigError::InvalidValue(
+                    "GOOSE_ADDITIONAL_REQUEST_FIELDS".to_string(),
+                    val.clone(),
+                    "must be a JSON object".to_string(),
+                )
+            })?;
+            Ok(Some(fields))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn get_model_specific_limit(model_name: &str) -> Option<usize> {
-        MODEL_SPECIFIC_LIMITS
-            .iter()
-            .find(|(pattern, _)| model_name.contains(pattern))
-            .map(|(_, limit)| *limit)
+        model_catalog::lookup(None, model_name).context_limit
+    }
+
+    /// Context limit for a model name from the model catalog, ignoring GOOSE_CONTEXT_LIMIT.
+    /// Useful for models discovered at runtime.
+    pub fn known_context_limit(model_name: &str) -> usize {
+        Self::get_model_specific_limit(model_name).unwrap_or(DEFAULT_CONTEXT_LIMIT)
     }
 
     pub fn get_all_model_limits() -> Vec<ModelLimitConfig> {
-        MODEL_SPECIFIC_LIMITS
+        model_catalog::CONTEXT_LIMITS
             .iter()
             .map(|(pattern, context_limit)| ModelLimitConfig {
                 pattern: pattern.to_string(),
@@ -261,6 +413,79 @@ impl ModelConfig {
         self
     }
 
+    pub fn with_tool_choice(mut self, tool_choice: Option<ToolChoice>) -> Self {
+        self.tool_choice = tool_choice;
+        self
+    }
+
+    /// The tool choice for the request being built: the one set with
+    /// [`with_tool_choice_override`] for the call in progress, if any, otherwise this config's
+    pub fn tool_choice_for_call(&self) -> Option<ToolChoice> {
+        TOOL_CHOICE
+            .try_with(|tool_choice| tool_choice.clone())
+            .ok()
+            .or_else(|| self.tool_choice.clone())
+    }
+
+    pub fn with_response_schema(mut self, response_schema: Option<serde_json::Value>) -> Self {
+        self.response_schema = response_schema;
+        self
+    }
+
+    pub fn with_parallel_tool_calls(mut self, parallel_tool_calls: Option<bool>) -> Self {
+        self.parallel_tool_calls = parallel_tool_calls;
+        self
+    }
+
+    pub fn with_reasoning_effort(mut self, effort: Option<ReasoningEffort>) -> Self {
+        self.reasoning_effort = effort;
+        self
+    }
+
+    pub fn with_thinking_budget_tokens(mut self, budget: Option<i32>) -> Self {
+        self.thinking_budget_tokens = budget;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: Option<i64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_additional_request_fields(
+        mut self,
+        fields: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Self {
+        self.additional_request_fields = fields;
+        self
+    }
+
+    /// Request log probabilities for output tokens, with `top_logprobs` alternatives each
+    pub fn with_logprobs(mut self, top_logprobs: Option<u8>) -> Self {
+        self.logprobs = top_logprobs;
+        self
+    }
+
+    /// What the model catalog knows about this model
+    pub fn capabilities(&self) -> ModelCapabilities {
+        model_catalog::lookup(None, &self.model_name)
+    }
+
+    /// Output tokens to ask for: max_tokens if set, otherwise `default` capped at what the
+    /// model can produce
+    pub fn max_output_tokens(&self, default: i32) -> i32 {
+        self.max_tokens
+            .unwrap_or_else(|| match self.capabilities().max_output_tokens {
+                Some(max) => default.min(i32::try_from(max).unwrap_or(i32::MAX)),
+                None => default,
+            })
+    }
+
+    /// Whether tool calls from a single response may be executed concurrently
+    pub fn allows_parallel_tool_calls(&self) -> bool {
+        self.parallel_tool_calls.unwrap_or(true)
+    }
+
     pub fn use_fast_model(&self) -> Self {
         if let Some(fast_model) = &self.fast_model {
             let mut config = self.clone();
@@ -0,0 +1,194 @@
+//! What models can do: their context window, output limit, capabilities and pricing.
+//!
+//! The bundled catalog combines the context windows goose knows for model families with the
+//! canonical model registry. Entries under GOOSE_MODEL_CATALOG, keyed by `provider/model` or
+//! bare model name, fill in or correct what it has:
+//!
+//! ```yaml
+//! GOOSE_MODEL_CATALOG:
+//!   my-finetune:
+//!     context_limit: 32768
+//!     max_output_tokens: 4096
+//!     supports_tools: false
+//! ```
+//!
+//! Fields that are unknown are left unset, so callers decide what to assume.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::providers::canonical::{
+    maybe_get_canonical_model, maybe_get_canonical_model_by_name, CanonicalModel, Pricing,
+};
+
+pub const MODEL_CATALOG_CONFIG_KEY: &str = "GOOSE_MODEL_CATALOG";
+
+/// Context windows by model name pattern, first match wins. These take precedence over the
+/// canonical registry, whose figures are for the largest variant a host offers.
+pub const CONTEXT_LIMITS: &[(&str, usize)] = &[
+    // openai
+    ("gpt-5", 272_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4.1", 1_000_000),
+    ("gpt-4-1", 1_000_000),
+    ("gpt-4o", 128_000),
+    ("o4-mini", 200_000),
+    ("o3-mini", 200_000),
+    ("o3", 200_000),
+    // anthropic - all 200k
+    ("claude", 200_000),
+    // google
+    ("gemini-1.5-flash", 1_000_000),
+    ("gemini-1", 128_000),
+    ("gemini-2", 1_000_000),
+    ("gemma-3-27b", 128_000),
+    ("gemma-3-12b", 128_000),
+    ("gemma-3-4b", 128_000),
+    ("gemma-3-1b", 32_000),
+    ("gemma3-27b", 128_000),
+    ("gemma3-12b", 128_000),
+    ("gemma3-4b", 128_000),
+    ("gemma3-1b", 32_000),
+    ("gemma-2-27b", 8_192),
+    ("gemma-2-9b", 8_192),
+    ("gemma-2-2b", 8_192),
+    ("gemma2-", 8_192),
+    ("gemma-7b", 8_192),
+    ("gemma-2b", 8_192),
+    ("gemma1", 8_192),
+    ("gemma", 8_192),
+    // facebook
+    ("llama-2-1b", 32_000),
+    ("llama", 128_000),
+    // qwen
+    ("qwen3-coder", 262_144),
+    ("qwen2-7b", 128_000),
+    ("qwen2-14b", 128_000),
+    ("qwen2-32b", 131_072),
+    ("qwen2-70b", 262_144),
+    ("qwen2", 128_000),
+    ("qwen3-32b", 131_072),
+    // xai
+    ("grok-4", 256_000),
+    ("grok-code-fast-1", 256_000),
+    ("grok", 131_072),
+    // other
+    ("kimi-k2", 131_072),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelCapabilities {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_limit: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supports_tools: Option<bool>,
+    /// Whether the model accepts images
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supports_vision: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supports_streaming: Option<bool>,
+    /// Whether prompts can be marked for caching
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supports_caching: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<Pricing>,
+}
+
+impl ModelCapabilities {
+    /// `self`, with what it leaves unknown taken from `fallback`
+    pub fn or(self, fallback: ModelCapabilities) -> ModelCapabilities {
+        ModelCapabilities {
+            context_limit: self.context_limit.or(fallback.context_limit),
+            max_output_tokens: self.max_output_tokens.or(fallback.max_output_tokens),
+            supports_tools: self.supports_tools.or(fallback.supports_tools),
+            supports_vision: self.supports_vision.or(fallback.supports_vision),
+            supports_streaming: self.supports_streaming.or(fallback.supports_streaming),
+            supports_caching: self.supports_caching.or(fallback.supports_caching),
+            pricing: self.pricing.or(fallback.pricing),
+        }
+    }
+}
+
+impl From<CanonicalModel> for ModelCapabilities {
+    fn from(model: CanonicalModel) -> Self {
+        ModelCapabilities {
+            context_limit: Some(model.context_length),
+            max_output_tokens: model.max_completion_tokens,
+            supports_tools: Some(model.supports_tools),
+            supports_vision: Some(model.input_modalities.iter().any(|m| m == "image")),
+            supports_streaming: None,
+            supports_caching: None,
+            pricing: Some(model.pricing),
+        }
+    }
+}
+
+/// The context window of `model` from the bundled patterns
+pub fn pattern_context_limit(model: &str) -> Option<usize> {
+    CONTEXT_LIMITS
+        .iter()
+        .find(|(pattern, _)| model.contains(pattern))
+        .map(|(_, limit)| *limit)
+}
+
+/// What the bundled catalog knows about `model`, served by `provider` if known
+pub fn bundled(provider: Option<&str>, model: &str) -> ModelCapabilities {
+    let canonical = provider
+        .and_then(|provider| maybe_get_canonical_model(provider, model))
+        .or_else(|| maybe_get_canonical_model_by_name(model));
+    let known = ModelCapabilities {
+        context_limit: pattern_context_limit(model),
+        // Claude models take cache_control markers wherever they are hosted
+        supports_caching: model.contains("claude").then_some(true),
+        ..Default::default()
+    };
+    known.or(canonical.map(ModelCapabilities::from).unwrap_or_default())
+}
+
+/// What GOOSE_MODEL_CATALOG says about `model`
+pub fn configured(provider: Option<&str>, model: &str) -> ModelCapabilities {
+    let entries: HashMap<String, ModelCapabilities> = Config::global()
+        .get_param(MODEL_CATALOG_CONFIG_KEY)
+        .unwrap_or_default();
+    provider
+        .and_then(|provider| entries.get(&format!("{}/{}", provider, model)))
+        .or_else(|| entries.get(model))
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// What is known about `model`, from the config first and then the bundled catalog
+pub fn lookup(provider: Option<&str>, model: &str) -> ModelCapabilities {
+    configured(provider, model).or(bundled(provider, model))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_prefers_patterns_for_context() {
+        let claude = bundled(Some("anthropic"), "claude-sonnet-4");
+        assert_eq!(claude.context_limit, Some(200_000));
+        assert_eq!(claude.supports_caching, Some(true));
+        assert_eq!(claude.supports_vision, Some(true));
+        assert!(claude.max_output_tokens.is_some());
+
+        let unknown = bundled(None, "my-finetune");
+        assert_eq!(unknown, ModelCapabilities::default());
+    }
+
+    #[test]
+    fn test_configured_entries_fill_in_the_catalog() {
+        let configured: ModelCapabilities =
+            serde_yaml::from_str("context_limit: 32768\nsupports_tools: false\n").unwrap();
+        let merged = configured.or(bundled(None, "gpt-4o"));
+        assert_eq!(merged.context_limit, Some(32_768));
+        assert_eq!(merged.supports_tools, Some(false));
+        assert_eq!(merged.supports_vision, Some(true));
+    }
+}
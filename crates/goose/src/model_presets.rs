@@ -0,0 +1,116 @@
+//! Default request parameters for models, so each vendor's quirks only need setting once.
+//!
+//! Presets under GOOSE_MODEL_PRESETS apply to every model whose name contains their pattern,
+//! when its [`ModelConfig`] is created:
+//!
+//! ```yaml
+//! GOOSE_MODEL_PRESETS:
+//!   - pattern: o3
+//!     no_temperature: true
+//!     reasoning_effort: high
+//!   - pattern: claude
+//!     thinking_budget_tokens: 8000
+//! ```
+//!
+//! Presets only fill in parameters that aren't set otherwise, such as by GOOSE_REASONING_EFFORT,
+//! and where several match, the first one listed wins. `no_temperature` is the exception: it
+//! drops the temperature even when GOOSE_TEMPERATURE sets one, for models that reject it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::model::{ModelConfig, ReasoningEffort};
+
+pub const MODEL_PRESETS_CONFIG_KEY: &str = "GOOSE_MODEL_PRESETS";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelPreset {
+    /// Applies to models whose name contains this
+    pub pattern: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Never send a temperature
+    #[serde(default)]
+    pub no_temperature: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking_budget_tokens: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub additional_request_fields: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl ModelPreset {
+    pub fn matches(&self, model_name: &str) -> bool {
+        model_name.contains(&self.pattern)
+    }
+}
+
+/// The presets in the config
+pub fn configured_presets() -> Vec<ModelPreset> {
+    Config::global()
+        .get_param(MODEL_PRESETS_CONFIG_KEY)
+        .unwrap_or_default()
+}
+
+/// `config` with the parameters it leaves unset taken from the presets matching its model
+pub fn apply_presets(mut config: ModelConfig, presets: &[ModelPreset]) -> ModelConfig {
+    for preset in presets.iter().filter(|p| p.matches(&config.model_name)) {
+        if preset.no_temperature {
+            config.temperature = None;
+        } else {
+            config.temperature = config.temperature.or(preset.temperature);
+        }
+        config.max_tokens = config.max_tokens.or(preset.max_tokens);
+        config.reasoning_effort = config.reasoning_effort.or(preset.reasoning_effort);
+        config.thinking_budget_tokens = config
+            .thinking_budget_tokens
+            .or(preset.thinking_budget_tokens);
+        config.parallel_tool_calls = config.parallel_tool_calls.or(preset.parallel_tool_calls);
+        if config.additional_request_fields.is_none() {
+            config.additional_request_fields = preset.additional_request_fields.clone();
+        }
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn presets() -> Vec<ModelPreset> {
+        serde_yaml::from_str(
+            "- pattern: o3\n  no_temperature: true\n  reasoning_effort: high\n- pattern: o3-mini\n  reasoning_effort: low\n  max_tokens: 4000\n- pattern: claude\n  thinking_budget_tokens: 8000\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_matching_presets_fill_in_unset_parameters() {
+        let config = ModelConfig::new_or_fail("o3-mini")
+            .with_temperature(Some(0.2))
+            .with_reasoning_effort(None)
+            .with_max_tokens(None)
+            .with_thinking_budget_tokens(None);
+        let config = apply_presets(config, &presets());
+        assert_eq!(config.temperature, None);
+        // The first matching preset wins
+        assert_eq!(config.reasoning_effort, Some(ReasoningEffort::High));
+        assert_eq!(config.max_tokens, Some(4000));
+        assert_eq!(config.thinking_budget_tokens, None);
+    }
+
+    #[test]
+    fn test_set_parameters_win_over_presets() {
+        let config = ModelConfig::new_or_fail("claude-sonnet-4")
+            .with_thinking_budget_tokens(Some(2048))
+            .with_temperature(Some(0.5));
+        let config = apply_presets(config, &presets());
+        assert_eq!(config.thinking_budget_tokens, Some(2048));
+        assert_eq!(config.temperature, Some(0.5));
+    }
+}
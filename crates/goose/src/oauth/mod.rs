@@ -28,23 +28,47 @@ struct CallbackParams {
     state: String,
 }
 
-pub async fn oauth_flow(
-    mcp_server_url: &String,
-    name: &String,
-) -> Result<AuthorizationManager, anyhow::Error> {
-    let credential_store = GooseCredentialStore::new(name.clone());
+/// An authorization manager for the server of extension `name` from the credentials saved by
+/// an earlier [`oauth_flow`], if there are any and their token can be refreshed. Credentials
+/// that can't be refreshed are cleared.
+pub async fn stored_authorization(
+    mcp_server_url: &str,
+    name: &str,
+) -> Result<Option<AuthorizationManager>, anyhow::Error> {
+    let credential_store = GooseCredentialStore::new(name.to_string());
+    if !credential_store.has_credentials() {
+        return Ok(None);
+    }
     let mut auth_manager = AuthorizationManager::new(mcp_server_url).await?;
     auth_manager.set_credential_store(credential_store.clone());
 
     if auth_manager.initialize_from_store().await? {
         if auth_manager.refresh_token().await.is_ok() {
-            return Ok(auth_manager);
+            return Ok(Some(auth_manager));
         }
 
         if let Err(e) = credential_store.clear().await {
             warn!("error clearing bad credentials: {}", e);
         }
     }
+    Ok(None)
+}
+
+/// Forget the OAuth credentials saved for extension `name`, so the next connection to its
+/// server authorizes again
+pub fn clear_credentials(name: &str) -> Result<(), anyhow::Error> {
+    GooseCredentialStore::new(name.to_string()).delete()?;
+    Ok(())
+}
+
+pub async fn oauth_flow(
+    mcp_server_url: &String,
+    name: &String,
+) -> Result<AuthorizationManager, anyhow::Error> {
+    if let Some(auth_manager) = stored_authorization(mcp_server_url, name).await? {
+        return Ok(auth_manager);
+    }
+    let credential_store = GooseCredentialStore::new(name.clone());
 
     // No existing credentials or they were invalid - need to do the full oauth flow
     let (code_sender, code_receiver) = oneshot::channel::<CallbackParams>();
@@ -1,6 +1,6 @@
 use rmcp::transport::auth::{AuthError, CredentialStore, StoredCredentials};
 
-use crate::config::Config;
+use crate::config::{Config, ConfigError};
 
 /// Goose-specific credential store that uses the Config system
 ///
@@ -20,6 +20,21 @@ impl GooseCredentialStore {
     fn secret_key(&self) -> String {
         format!("oauth_creds_{}", self.name)
     }
+
+    /// Whether credentials have been saved for this server
+    pub fn has_credentials(&self) -> bool {
+        Config::global()
+            .get_secret::<StoredCredentials>(&self.secret_key())
+            .is_ok()
+    }
+
+    /// Delete the saved credentials, if there are any
+    pub fn delete(&self) -> Result<(), ConfigError> {
+        if !self.has_credentials() {
+            return Ok(());
+        }
+        Config::global().delete_secret(&self.secret_key())
+    }
 }
 
 #[async_trait::async_trait]
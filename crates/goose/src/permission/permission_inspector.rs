@@ -139,19 +139,24 @@ impl ToolInspector for PermissionInspector {
             if let Ok(tool_call) = &request.tool_call {
                 let tool_name = &tool_call.name;
 
+                let user_action = permission_manager
+                    .get_user_permission(tool_name)
+                    .map(|level| match level {
+                        PermissionLevel::AlwaysAllow => InspectionAction::Allow,
+                        PermissionLevel::NeverAllow => InspectionAction::Deny,
+                        PermissionLevel::AskBefore => InspectionAction::RequireApproval(None),
+                    });
+
                 let action = match *mode {
                     GooseMode::Chat => continue,
                     GooseMode::Auto => InspectionAction::Allow,
+                    // Only calls to tools annotated read-only reach the inspector in a dry run,
+                    // but the user's permissions still apply to them
+                    GooseMode::DryRun => user_action.unwrap_or(InspectionAction::Allow),
                     GooseMode::Approve | GooseMode::SmartApprove => {
                         // 1. Check user-defined permission first
-                        if let Some(level) = permission_manager.get_user_permission(tool_name) {
-                            match level {
-                                PermissionLevel::AlwaysAllow => InspectionAction::Allow,
-                                PermissionLevel::NeverAllow => InspectionAction::Deny,
-                                PermissionLevel::AskBefore => {
-                                    InspectionAction::RequireApproval(None)
-                                }
-                            }
+                        if let Some(action) = user_action {
+                            action
                         }
                         // 2. Check if it's a readonly or regular tool (both pre-approved)
                         else if self.readonly_tools.contains(tool_name.as_ref())
@@ -176,6 +181,8 @@ impl ToolInspector for PermissionInspector {
                     InspectionAction::Allow => {
                         if *mode == GooseMode::Auto {
                             "Auto mode - all tools approved".to_string()
+                        } else if *mode == GooseMode::DryRun {
+                            "Dry-run mode - read-only tool".to_string()
                         } else if self.readonly_tools.contains(tool_name.as_ref()) {
                             "Tool marked as read-only".to_string()
                         } else if self.regular_tools.contains(tool_name.as_ref()) {
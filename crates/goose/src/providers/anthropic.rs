@@ -12,8 +12,10 @@ use super::api_client::{ApiClient, ApiResponse, AuthMethod};
 use super::base::{ConfigKey, MessageStream, ModelInfo, Provider, ProviderMetadata, ProviderUsage};
 use super::errors::ProviderError;
 use super::formats::anthropic::{
-    create_request, get_usage, response_to_message, response_to_streaming_message,
+    add_server_tool, create_count_tokens_request, create_request, get_usage, response_to_message,
+    response_to_streaming_message, web_search_tool_spec,
 };
+use super::timeouts::{stream_error, StreamTimeouts};
 use super::utils::{get_model, handle_status_openai_compat, map_http_error_to_provider_error};
 use crate::config::declarative_providers::DeclarativeProviderConfig;
 use crate::conversation::message::Message;
@@ -53,6 +55,8 @@ pub struct AnthropicProvider {
     model: ModelConfig,
     supports_streaming: bool,
     name: String,
+    /// Spec for Anthropic's server-side web search tool, when enabled
+    web_search: Option<Value>,
 }
 
 impl AnthropicProvider {
@@ -73,11 +77,17 @@ impl AnthropicProvider {
         let api_client =
             ApiClient::new(host, auth)?.with_header("anthropic-version", ANTHROPIC_API_VERSION)?;
 
+        let web_search = config
+            .get_param::<bool>("ANTHROPIC_WEB_SEARCH")
+            .unwrap_or(false)
+            .then(|| web_search_tool_spec(config.get_param("ANTHROPIC_WEB_SEARCH_MAX_USES").ok()));
+
         Ok(Self {
             api_client,
             model,
             supports_streaming: true,
             name: Self::metadata().name,
+            web_search,
         })
     }
 
@@ -95,17 +105,36 @@ impl AnthropicProvider {
             key: api_key,
         };
 
-        let api_client = ApiClient::new(config.base_url, auth)?
+        let tls_config = config.tls_config()?;
+        let mut api_client = ApiClient::new(config.base_url, auth)?
             .with_header("anthropic-version", ANTHROPIC_API_VERSION)?;
+        if let Some(tls_config) = tls_config {
+            api_client = api_client.with_tls_config(&tls_config)?;
+        }
 
         Ok(Self {
             api_client,
             model,
             supports_streaming: config.supports_streaming.unwrap_or(true),
             name: config.name.clone(),
+            web_search: None,
         })
     }
 
+    fn create_request(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<Value> {
+        let mut payload = create_request(model_config, system, messages, tools)?;
+        if let Some(web_search) = &self.web_search {
+            add_server_tool(&mut payload, web_search.clone());
+        }
+        Ok(payload)
+    }
+
     fn get_conditional_headers(&self) -> Vec<(&str, &str)> {
         let mut headers = Vec::new();
 
@@ -152,10 +181,10 @@ impl AnthropicProvider {
                         }
                     }
                 }
-                Err(map_http_error_to_provider_error(
-                    response.status,
-                    response.payload,
-                ))
+                Err(
+                    map_http_error_to_provider_error(response.status, response.payload)
+                        .with_retry_delay(response.retry_delay),
+                )
             }
         }
     }
@@ -184,6 +213,8 @@ impl Provider for AnthropicProvider {
                     false,
                     Some("https://api.anthropic.com"),
                 ),
+                ConfigKey::new("ANTHROPIC_WEB_SEARCH", false, false, Some("false")),
+                ConfigKey::new("ANTHROPIC_WEB_SEARCH_MAX_USES", false, false, None),
             ],
         )
     }
@@ -207,7 +238,7 @@ impl Provider for AnthropicProvider {
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(model_config, system, messages, tools)?;
+        let payload = self.create_request(model_config, system, messages, tools)?;
 
         let response = self
             .with_retry(|| async { self.post(&payload).await })
@@ -231,6 +262,29 @@ impl Provider for AnthropicProvider {
         Ok((message, provider_usage))
     }
 
+    async fn count_tokens(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<usize, ProviderError> {
+        let payload = create_count_tokens_request(&self.model, system, messages, tools)?;
+
+        let response = self
+            .api_client
+            .api_post("v1/messages/count_tokens", &payload)
+            .await?;
+        let json_response = Self::anthropic_api_call_result(response)?;
+
+        json_response
+            .get("input_tokens")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .ok_or_else(|| {
+                ProviderError::RequestFailed("Missing input_tokens in response".to_string())
+            })
+    }
+
     async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
         let response = self.api_client.api_get("v1/models").await?;
 
@@ -255,13 +309,27 @@ impl Provider for AnthropicProvider {
         Ok(Some(models))
     }
 
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        // All current Claude models share a 200k context window and support tools and images
+        let models = self.fetch_supported_models().await?.unwrap_or_default();
+        Ok(models
+            .into_iter()
+            .map(|name| {
+                let mut info = ModelInfo::new(name, 200_000);
+                info.supports_tools = Some(true);
+                info.supports_vision = Some(true);
+                info
+            })
+            .collect())
+    }
+
     async fn stream(
         &self,
         system: &str,
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<MessageStream, ProviderError> {
-        let mut payload = create_request(&self.model, system, messages, tools)?;
+        let mut payload = self.create_request(&self.model, system, messages, tools)?;
         payload
             .as_object_mut()
             .unwrap()
@@ -286,11 +354,12 @@ impl Provider for AnthropicProvider {
         Ok(Box::pin(try_stream! {
             let stream_reader = StreamReader::new(stream);
             let framed = tokio_util::codec::FramedRead::new(stream_reader, tokio_util::codec::LinesCodec::new()).map_err(anyhow::Error::from);
+            let framed = StreamTimeouts::from_config().apply(framed);
 
             let message_stream = response_to_streaming_message(framed);
             pin!(message_stream);
             while let Some(message) = futures::StreamExt::next(&mut message_stream).await {
-                let (message, usage) = message.map_err(|e| ProviderError::RequestFailed(format!("Stream decode error: {}", e)))?;
+                let (message, usage) = message.map_err(stream_error)?;
                 log.write(&message, usage.as_ref().map(|f| f.usage).as_ref())?;
                 yield (message, usage);
             }
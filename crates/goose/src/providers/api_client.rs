@@ -1,3 +1,5 @@
+use super::http_client::{dedicated_client, shared_client};
+use super::timeouts::RequestTimeouts;
 use crate::session_context::SESSION_ID_HEADER;
 use anyhow::Result;
 use async_trait::async_trait;
@@ -17,6 +19,7 @@ pub struct ApiClient {
     auth: AuthMethod,
     default_headers: HeaderMap,
     timeout: Duration,
+    connect_timeout: Option<Duration>,
     tls_config: Option<TlsConfig>,
 }
 
@@ -52,45 +55,64 @@ impl TlsConfig {
     }
 
     pub fn from_config() -> Result<Option<Self>> {
+        Self::from_config_prefix("GOOSE")
+    }
+
+    /// TLS settings for a single provider, read from `<PREFIX>_CLIENT_CERT_PATH`,
+    /// `<PREFIX>_CLIENT_KEY_PATH` and `<PREFIX>_CA_CERT_PATH`
+    pub fn from_config_prefix(prefix: &str) -> Result<Option<Self>> {
         let config = crate::config::Config::global();
-        let mut tls_config = TlsConfig::new();
-        let mut has_tls_config = false;
+        let cert_key = format!("{}_CLIENT_CERT_PATH", prefix);
+        let key_key = format!("{}_CLIENT_KEY_PATH", prefix);
+        Self::from_paths(
+            config.get_param::<String>(&cert_key).ok(),
+            config.get_param::<String>(&key_key).ok(),
+            config
+                .get_param::<String>(&format!("{}_CA_CERT_PATH", prefix))
+                .ok(),
+            (&cert_key, &key_key),
+        )
+    }
 
-        let client_cert_path = config.get_param::<String>("GOOSE_CLIENT_CERT_PATH").ok();
-        let client_key_path = config.get_param::<String>("GOOSE_CLIENT_KEY_PATH").ok();
+    /// Build TLS settings from optional paths. `names` are the (certificate, key) setting names
+    /// used in errors when only one of the pair is given.
+    pub fn from_paths(
+        client_cert_path: Option<String>,
+        client_key_path: Option<String>,
+        ca_cert_path: Option<String>,
+        names: (&str, &str),
+    ) -> Result<Option<Self>> {
+        let mut tls_config = TlsConfig::new();
+        let (cert_name, key_name) = names;
 
         // Validate that both cert and key are provided if either is provided
         match (client_cert_path, client_key_path) {
             (Some(cert_path), Some(key_path)) => {
-                tls_config = tls_config.with_client_cert_and_key(
-                    std::path::PathBuf::from(cert_path),
-                    std::path::PathBuf::from(key_path),
-                );
-                has_tls_config = true;
+                tls_config = tls_config
+                    .with_client_cert_and_key(PathBuf::from(cert_path), PathBuf::from(key_path));
             }
             (Some(_), None) => {
                 return Err(anyhow::anyhow!(
-                    "Client certificate provided (GOOSE_CLIENT_CERT_PATH) but no private key (GOOSE_CLIENT_KEY_PATH)"
+                    "Client certificate provided ({}) but no private key ({})",
+                    cert_name,
+                    key_name
                 ));
             }
             (None, Some(_)) => {
                 return Err(anyhow::anyhow!(
-                    "Client private key provided (GOOSE_CLIENT_KEY_PATH) but no certificate (GOOSE_CLIENT_CERT_PATH)"
+                    "Client private key provided ({}) but no certificate ({})",
+                    key_name,
+                    cert_name
                 ));
             }
             (None, None) => {}
         }
 
-        if let Ok(ca_cert_path) = config.get_param::<String>("GOOSE_CA_CERT_PATH") {
-            tls_config = tls_config.with_ca_cert(std::path::PathBuf::from(ca_cert_path));
-            has_tls_config = true;
+        if let Some(ca_cert_path) = ca_cert_path {
+            tls_config = tls_config.with_ca_cert(PathBuf::from(ca_cert_path));
         }
 
-        if has_tls_config {
-            Ok(Some(tls_config))
-        } else {
-            Ok(None)
-        }
+        Ok(tls_config.is_configured().then_some(tls_config))
     }
 
     pub fn with_client_cert_and_key(mut self, cert_path: PathBuf, key_path: PathBuf) -> Self {
@@ -167,6 +189,8 @@ pub trait AuthProvider: Send + Sync {
 pub struct ApiResponse {
     pub status: StatusCode,
     pub payload: Option<Value>,
+    /// Delay requested by the server's rate limit headers, if any
+    pub retry_delay: Option<Duration>,
 }
 
 impl fmt::Debug for AuthMethod {
@@ -187,8 +211,17 @@ impl fmt::Debug for AuthMethod {
 impl ApiResponse {
     pub async fn from_response(response: Response) -> Result<Self> {
         let status = response.status();
+        let retry_delay = if status == StatusCode::TOO_MANY_REQUESTS {
+            super::utils::parse_retry_delay_headers(response.headers())
+        } else {
+            None
+        };
         let payload = response.json().await.ok();
-        Ok(Self { status, payload })
+        Ok(Self {
+            status,
+            payload,
+            retry_delay,
+        })
     }
 }
 
@@ -203,64 +236,49 @@ impl ApiClient {
         Self::with_timeout(host, auth, Duration::from_secs(600))
     }
 
+    /// Requests go through the shared client, with `timeout` applied to each one
     pub fn with_timeout(host: String, auth: AuthMethod, timeout: Duration) -> Result<Self> {
-        let mut client_builder = Client::builder().timeout(timeout);
-
-        // Configure TLS if needed
-        let tls_config = TlsConfig::from_config()?;
-        if let Some(ref config) = tls_config {
-            client_builder = Self::configure_tls(client_builder, config)?;
-        }
-
-        let client = client_builder.build()?;
-
         Ok(Self {
-            client,
+            client: shared_client()?,
             host,
             auth,
             default_headers: HeaderMap::new(),
             timeout,
-            tls_config,
+            connect_timeout: None,
+            tls_config: None,
         })
     }
 
-    fn rebuild_client(&mut self) -> Result<()> {
-        let mut client_builder = Client::builder()
-            .timeout(self.timeout)
-            .default_headers(self.default_headers.clone());
-
-        // Configure TLS if needed
-        if let Some(ref tls_config) = self.tls_config {
-            client_builder = Self::configure_tls(client_builder, tls_config)?;
+    /// Apply a provider's total and connect timeouts, see [`RequestTimeouts`]
+    pub fn with_timeouts(
+        host: String,
+        auth: AuthMethod,
+        timeouts: &RequestTimeouts,
+    ) -> Result<Self> {
+        let client = Self::with_timeout(host, auth, timeouts.total)?;
+        match timeouts.connect {
+            Some(connect_timeout) => client.with_connect_timeout(connect_timeout),
+            None => Ok(client),
         }
-
-        self.client = client_builder.build()?;
-        Ok(())
     }
 
-    /// Configure TLS settings on a reqwest ClientBuilder
-    fn configure_tls(
-        mut client_builder: reqwest::ClientBuilder,
-        tls_config: &TlsConfig,
-    ) -> Result<reqwest::ClientBuilder> {
-        if tls_config.is_configured() {
-            // Load client identity (certificate + private key)
-            if let Some(identity) = tls_config.load_identity()? {
-                client_builder = client_builder.identity(identity);
-            }
+    /// Send requests through a dedicated client that gives up connecting after `connect_timeout`
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Result<Self> {
+        self.connect_timeout = Some(connect_timeout);
+        self.client = dedicated_client(self.tls_config.as_ref(), self.connect_timeout)?;
+        Ok(self)
+    }
 
-            // Load CA certificates
-            let ca_certs = tls_config.load_ca_certificates()?;
-            for ca_cert in ca_certs {
-                client_builder = client_builder.add_root_certificate(ca_cert);
-            }
-        }
-        Ok(client_builder)
+    /// Send requests through a dedicated client that presents `tls_config`, instead of the
+    /// shared one
+    pub fn with_tls_config(mut self, tls_config: &TlsConfig) -> Result<Self> {
+        self.tls_config = Some(tls_config.clone());
+        self.client = dedicated_client(self.tls_config.as_ref(), self.connect_timeout)?;
+        Ok(self)
     }
 
     pub fn with_headers(mut self, headers: HeaderMap) -> Result<Self> {
         self.default_headers = headers;
-        self.rebuild_client()?;
         Ok(self)
     }
 
@@ -268,7 +286,6 @@ impl ApiClient {
         let header_name = HeaderName::from_bytes(key.as_bytes())?;
         let header_value = HeaderValue::from_str(value)?;
         self.default_headers.insert(header_name, header_value);
-        self.rebuild_client()?;
         Ok(self)
     }
 
@@ -288,6 +305,25 @@ impl ApiClient {
         self.request(path).response_post(payload).await
     }
 
+    pub async fn response_post_multipart(
+        &self,
+        path: &str,
+        form: reqwest::multipart::Form,
+    ) -> Result<Response> {
+        self.request(path).response_post_multipart(form).await
+    }
+
+    pub async fn response_post_bytes(
+        &self,
+        path: &str,
+        body: Vec<u8>,
+        content_type: &str,
+    ) -> Result<Response> {
+        self.request(path)
+            .response_post_bytes(body, content_type)
+            .await
+    }
+
     pub async fn api_get(&self, path: &str) -> Result<ApiResponse> {
         self.request(path).api_get().await
     }
@@ -352,6 +388,20 @@ impl<'a> ApiRequestBuilder<'a> {
         Ok(request.json(payload).send().await?)
     }
 
+    pub async fn response_post_multipart(self, form: reqwest::multipart::Form) -> Result<Response> {
+        let request = self.send_request(|url, client| client.post(url)).await?;
+        Ok(request.multipart(form).send().await?)
+    }
+
+    pub async fn response_post_bytes(self, body: Vec<u8>, content_type: &str) -> Result<Response> {
+        let request = self.send_request(|url, client| client.post(url)).await?;
+        Ok(request
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body)
+            .send()
+            .await?)
+    }
+
     pub async fn api_get(self) -> Result<ApiResponse> {
         let response = self.response_get().await?;
         ApiResponse::from_response(response).await
@@ -367,13 +417,19 @@ impl<'a> ApiRequestBuilder<'a> {
         F: FnOnce(url::Url, &Client) -> reqwest::RequestBuilder,
     {
         let url = self.client.build_url(self.path)?;
-        let mut request = request_builder(url, &self.client.client);
+        let mut request = request_builder(url, &self.client.client)
+            .timeout(self.client.timeout)
+            .headers(self.client.default_headers.clone());
         request = request.headers(self.headers.clone());
 
         if let Some(session_id) = crate::session_context::current_session_id() {
             request = request.header(SESSION_ID_HEADER, session_id);
         }
 
+        if let Some(headers) = super::middleware::current_request_headers() {
+            request = request.headers(headers);
+        }
+
         request = match &self.client.auth {
             AuthMethod::BearerToken(token) => {
                 request.header("Authorization", format!("Bearer {}", token))
@@ -399,6 +455,7 @@ impl fmt::Debug for ApiClient {
             .field("host", &self.host)
             .field("auth", &"[auth method]")
             .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
             .field("default_headers", &self.default_headers)
             .finish_non_exhaustive()
     }
@@ -454,4 +511,61 @@ mod tests {
 
         assert!(!headers.contains_key(SESSION_ID_HEADER));
     }
+
+    #[tokio::test]
+    async fn test_middleware_headers_injection() {
+        let client = ApiClient::new(
+            "http://localhost:8080".to_string(),
+            AuthMethod::BearerToken("test-token".to_string()),
+        )
+        .unwrap();
+
+        let mut extra = HeaderMap::new();
+        extra.insert("x-team", HeaderValue::from_static("platform"));
+        let headers = super::super::middleware::with_request_headers(extra, async {
+            let request = client
+                .request("/test")
+                .send_request(|url, client| client.get(url))
+                .await
+                .unwrap();
+            request.build().unwrap().headers().clone()
+        })
+        .await;
+
+        assert_eq!(headers.get("x-team").unwrap(), "platform");
+    }
+
+    #[test]
+    fn test_provider_tls_config_from_env() {
+        temp_env::with_vars(
+            [
+                ("OLLAMA_CLIENT_CERT_PATH", Some("/certs/client.pem")),
+                ("OLLAMA_CLIENT_KEY_PATH", Some("/certs/client.key")),
+                ("OLLAMA_CA_CERT_PATH", None::<&str>),
+            ],
+            || {
+                let tls_config = TlsConfig::from_config_prefix("OLLAMA").unwrap().unwrap();
+                let identity = tls_config.client_identity.unwrap();
+                assert_eq!(identity.cert_path, PathBuf::from("/certs/client.pem"));
+                assert_eq!(identity.key_path, PathBuf::from("/certs/client.key"));
+                assert!(tls_config.ca_cert_path.is_none());
+            },
+        );
+
+        temp_env::with_vars(
+            [
+                ("LITELLM_CLIENT_CERT_PATH", Some("/certs/client.pem")),
+                ("LITELLM_CLIENT_KEY_PATH", None),
+                ("LITELLM_CA_CERT_PATH", None),
+            ],
+            || {
+                let err = TlsConfig::from_config_prefix("LITELLM").unwrap_err();
+                assert!(err.to_string().contains("LITELLM_CLIENT_KEY_PATH"));
+            },
+        );
+
+        assert!(TlsConfig::from_paths(None, None, None, ("cert", "key"))
+            .unwrap()
+            .is_none());
+    }
 }
@@ -5,10 +5,12 @@ use serde::{Deserialize, Serialize};
 use super::canonical::{map_to_canonical_model, CanonicalModelRegistry};
 use super::errors::ProviderError;
 use super::retry::RetryConfig;
+use super::stream_event::{into_events, EventStream};
 use crate::config::base::ConfigValue;
 use crate::conversation::message::Message;
 use crate::conversation::Conversation;
 use crate::model::ModelConfig;
+use crate::token_counter::create_token_counter_for_model;
 use crate::utils::safe_truncate;
 use rmcp::model::Tool;
 use utoipa::ToSchema;
@@ -50,6 +52,12 @@ pub struct ModelInfo {
     pub currency: Option<String>,
     /// Whether this model supports cache control
     pub supports_cache_control: Option<bool>,
+    /// Whether this model supports tool calling (unknown if not set)
+    #[serde(default)]
+    pub supports_tools: Option<bool>,
+    /// Whether this model accepts image input (unknown if not set)
+    #[serde(default)]
+    pub supports_vision: Option<bool>,
 }
 
 impl ModelInfo {
@@ -62,6 +70,8 @@ impl ModelInfo {
             output_token_cost: None,
             currency: None,
             supports_cache_control: None,
+            supports_tools: None,
+            supports_vision: None,
         }
     }
 
@@ -79,6 +89,8 @@ impl ModelInfo {
             output_token_cost: Some(output_cost),
             currency: Some("$".to_string()),
             supports_cache_control: None,
+            supports_tools: None,
+            supports_vision: None,
         }
     }
 }
@@ -134,6 +146,8 @@ impl ProviderMetadata {
                     output_token_cost: None,
                     currency: None,
                     supports_cache_control: None,
+                    supports_tools: None,
+                    supports_vision: None,
                 })
                 .collect(),
             model_doc_link: model_doc_link.to_string(),
@@ -227,15 +241,79 @@ impl ConfigKey {
     }
 }
 
+/// Outcome of a provider health check, see [`Provider::verify`]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct ProviderVerification {
+    /// Whether the provider accepted the configured credentials
+    pub auth_ok: bool,
+    /// Whether the configured model is available, if the provider could tell
+    pub model_available: Option<bool>,
+    /// Round-trip time of the check in milliseconds
+    pub latency_ms: u64,
+    /// The error returned by the provider, if the check failed
+    pub error: Option<String>,
+}
+
+/// Whether `model_name` appears in a model listing. Tags such as Ollama's `:latest`
+/// are ignored so `llama3` matches `llama3:latest`.
+pub fn is_model_listed(models: &[ModelInfo], model_name: &str) -> bool {
+    models.iter().any(|m| {
+        m.name == model_name
+            || m.name
+                .strip_prefix(model_name)
+                .is_some_and(|tag| tag.starts_with(':'))
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderUsage {
     pub model: String,
     pub usage: Usage,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
+    /// Cost of the request in USD, if the provider reported it or the model has known pricing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+    /// Set on the tokens used so far by a response that is still streaming in. The usage that
+    /// follows without it gives the response's totals.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interim: bool,
 }
 
 impl ProviderUsage {
     pub fn new(model: String, usage: Usage) -> Self {
-        Self { model, usage }
+        Self {
+            model,
+            usage,
+            system_fingerprint: None,
+            cost: None,
+            interim: false,
+        }
+    }
+
+    pub fn as_interim(mut self) -> Self {
+        self.interim = true;
+        self
+    }
+
+    pub fn with_system_fingerprint(mut self, system_fingerprint: Option<String>) -> Self {
+        self.system_fingerprint = system_fingerprint;
+        self
+    }
+
+    pub fn with_cost(mut self, cost: Option<f64>) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    /// Compute the cost from the pricing table, unless the provider already reported one
+    pub fn with_estimated_cost(self, provider_name: &str) -> Self {
+        if self.cost.is_some() {
+            return self;
+        }
+        let cost =
+            crate::providers::pricing::estimate_cost(provider_name, &self.model, &self.usage);
+        self.with_cost(cost)
     }
 
     /// Ensures this ProviderUsage has token counts, estimating them if necessary
@@ -263,6 +341,12 @@ impl ProviderUsage {
         ProviderUsage {
             model: self.model.clone(),
             usage: self.usage + other.usage,
+            system_fingerprint: self
+                .system_fingerprint
+                .clone()
+                .or_else(|| other.system_fingerprint.clone()),
+            cost: sum_optionals(self.cost, other.cost),
+            interim: self.interim || other.interim,
         }
     }
 }
@@ -272,6 +356,9 @@ pub struct Usage {
     pub input_tokens: Option<i32>,
     pub output_tokens: Option<i32>,
     pub total_tokens: Option<i32>,
+    /// Input tokens served from the provider's prompt cache, included in `input_tokens`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_input_tokens: Option<i32>,
 }
 
 fn sum_optionals<T>(a: Option<T>, b: Option<T>) -> Option<T>
@@ -295,6 +382,10 @@ impl Add for Usage {
             sum_optionals(self.output_tokens, other.output_tokens),
             sum_optionals(self.total_tokens, other.total_tokens),
         )
+        .with_cached_input_tokens(sum_optionals(
+            self.cached_input_tokens,
+            other.cached_input_tokens,
+        ))
     }
 }
 
@@ -325,8 +416,14 @@ impl Usage {
             input_tokens,
             output_tokens,
             total_tokens: calculated_total,
+            cached_input_tokens: None,
         }
     }
+
+    pub fn with_cached_input_tokens(mut self, cached_input_tokens: Option<i32>) -> Self {
+        self.cached_input_tokens = cached_input_tokens;
+        self
+    }
 }
 
 use async_trait::async_trait;
@@ -411,14 +508,79 @@ pub trait Provider: Send + Sync {
     /// Get the model config from the provider
     fn get_model_config(&self) -> ModelConfig;
 
+    /// The provider this one wraps, for providers that add behavior around another one.
+    /// The defaults of the methods that don't make a completion forward to it, so a wrapper
+    /// only implements the calls it changes.
+    fn inner_provider(&self) -> Option<&dyn Provider> {
+        None
+    }
+
     fn retry_config(&self) -> RetryConfig {
+        if let Some(inner) = self.inner_provider() {
+            return inner.retry_config();
+        }
         RetryConfig::default()
     }
 
     async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        if let Some(inner) = self.inner_provider() {
+            return inner.fetch_supported_models().await;
+        }
         Ok(None)
     }
 
+    /// List the models available to this provider's credentials, with their context window
+    /// and capability flags where the provider reports them
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        if let Some(inner) = self.inner_provider() {
+            return inner.list_models().await;
+        }
+        Err(ProviderError::NotImplemented(format!(
+            "Listing models is not supported by {}",
+            self.get_name()
+        )))
+    }
+
+    /// Check that the provider is reachable with the configured credentials and model.
+    /// Uses the model listing where available and otherwise a 1-token completion.
+    async fn verify(&self) -> ProviderVerification {
+        let model_config = self.get_model_config();
+        let start = std::time::Instant::now();
+
+        let result = match self.list_models().await {
+            Ok(models) => Ok(Some(is_model_listed(&models, &model_config.model_name))),
+            Err(ProviderError::NotImplemented(_)) => {
+                let probe_config = model_config.clone().with_max_tokens(Some(1));
+                self.complete_with_model(
+                    &probe_config,
+                    "",
+                    &[Message::user().with_text("ping")],
+                    &[],
+                )
+                .await
+                .map(|_| Some(true))
+            }
+            Err(e) => Err(e),
+        };
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        match result {
+            Ok(model_available) => ProviderVerification {
+                auth_ok: true,
+                model_available,
+                latency_ms,
+                error: None,
+            },
+            Err(e) => ProviderVerification {
+                // A throttled request still got past authentication
+                auth_ok: matches!(e, ProviderError::RateLimitExceeded { .. }),
+                model_available: None,
+                latency_ms,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+
     /// Fetch models filtered by canonical registry and usability
     async fn fetch_recommended_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
         let all_models = match self.fetch_supported_models().await? {
@@ -465,16 +627,41 @@ pub trait Provider: Send + Sync {
         ))
     }
 
+    /// Count the input tokens a request would use, for pre-flight context checks.
+    /// The default implementation estimates locally with the tokenizer of the model;
+    /// providers with a counting endpoint override it for an exact count.
+    async fn count_tokens(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<usize, ProviderError> {
+        if let Some(inner) = self.inner_provider() {
+            return inner.count_tokens(system, messages, tools).await;
+        }
+        let token_counter = create_token_counter_for_model(&self.get_model_config().model_name)
+            .await
+            .map_err(ProviderError::ExecutionError)?;
+        Ok(token_counter.count_chat_tokens(system, messages, tools))
+    }
+
     fn supports_embeddings(&self) -> bool {
-        false
+        self.inner_provider()
+            .is_some_and(|inner| inner.supports_embeddings())
     }
 
     async fn supports_cache_control(&self) -> bool {
-        false
+        match self.inner_provider() {
+            Some(inner) => inner.supports_cache_control().await,
+            None => false,
+        }
     }
 
     /// Create embeddings if supported. Default implementation returns an error.
-    async fn create_embeddings(&self, _texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        if let Some(inner) = self.inner_provider() {
+            return inner.create_embeddings(texts).await;
+        }
         Err(ProviderError::ExecutionError(
             "This provider does not support embeddings".to_string(),
         ))
@@ -483,7 +670,8 @@ pub trait Provider: Send + Sync {
     /// Check if this provider is a LeadWorkerProvider
     /// This is used for logging model information at startup
     fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
-        None
+        self.inner_provider()
+            .and_then(|inner| inner.as_lead_worker())
     }
 
     async fn stream(
@@ -497,8 +685,32 @@ pub trait Provider: Send + Sync {
         ))
     }
 
+    /// The response of [`stream`](Provider::stream) as typed events
+    async fn stream_events(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<EventStream, ProviderError> {
+        Ok(into_events(self.stream(system, messages, tools).await?))
+    }
+
     fn supports_streaming(&self) -> bool {
-        false
+        self.inner_provider()
+            .is_some_and(|inner| inner.supports_streaming())
+    }
+
+    /// Whether the current model accepts audio input natively. Audio sent to a provider that
+    /// doesn't is transcribed first.
+    fn supports_audio_input(&self) -> bool {
+        self.inner_provider()
+            .is_some_and(|inner| inner.supports_audio_input())
+    }
+
+    /// Whether the provider holds its response text to [`ModelConfig::response_schema`]
+    fn supports_structured_output(&self) -> bool {
+        self.inner_provider()
+            .is_some_and(|inner| inner.supports_structured_output())
     }
 
     /// Get the currently active model name
@@ -576,6 +788,9 @@ pub trait Provider: Send + Sync {
     /// # Default Implementation
     /// The default implementation returns an error indicating OAuth is not supported.
     async fn configure_oauth(&self) -> Result<(), ProviderError> {
+        if let Some(inner) = self.inner_provider() {
+            return inner.configure_oauth().await;
+        }
         Err(ProviderError::ExecutionError(
             "OAuth configuration not supported by this provider".to_string(),
         ))
@@ -585,6 +800,11 @@ pub trait Provider: Send + Sync {
 /// A message stream yields partial text content but complete tool calls, all within the Message object
 /// So a message with text will contain potentially just a word of a longer response, but tool calls
 /// messages will only be yielded once concatenated.
+///
+/// Usage comes last, once the response is complete. Long responses may also report
+/// [interim](ProviderUsage::interim) usage along the way, each replacing the one before.
+///
+/// See [`StreamEvent`](super::stream_event::StreamEvent) for the same stream as typed events.
 pub type MessageStream = Pin<
     Box<dyn Stream<Item = Result<(Option<Message>, Option<ProviderUsage>), ProviderError>> + Send>,
 >;
@@ -627,6 +847,18 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_is_model_listed() {
+        let models = vec![
+            ModelInfo::new("gpt-4o", 128_000),
+            ModelInfo::new("llama3:latest", 8_192),
+        ];
+        assert!(is_model_listed(&models, "gpt-4o"));
+        assert!(is_model_listed(&models, "llama3"));
+        assert!(!is_model_listed(&models, "gpt-4"));
+        assert!(!is_model_listed(&models, "llama"));
+    }
+
     #[test]
     fn test_set_and_get_current_model() {
         // Set the model
@@ -687,6 +919,8 @@ mod tests {
             output_token_cost: None,
             currency: None,
             supports_cache_control: None,
+            supports_tools: None,
+            supports_vision: None,
         };
         assert_eq!(info.context_limit, 1000);
 
@@ -698,6 +932,8 @@ mod tests {
             output_token_cost: None,
             currency: None,
             supports_cache_control: None,
+            supports_tools: None,
+            supports_vision: None,
         };
         assert_eq!(info, info2);
 
@@ -709,6 +945,8 @@ mod tests {
             output_token_cost: None,
             currency: None,
             supports_cache_control: None,
+            supports_tools: None,
+            supports_vision: None,
         };
         assert_ne!(info, info3);
     }
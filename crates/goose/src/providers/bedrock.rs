@@ -1,17 +1,26 @@
 use std::collections::HashMap;
 
-use crate::conversation::message::Message;
+use crate::conversation::message::{Message, MessageContent};
 use crate::model::ModelConfig;
 use crate::providers::base::MessageStream;
-use crate::providers::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
+use crate::providers::base::{ConfigKey, ModelInfo, Provider, ProviderMetadata, ProviderUsage};
+use crate::providers::embedding::{parse_embedding_vectors, EmbeddingOptions, EmbeddingProvider};
 use crate::providers::errors::ProviderError;
+use crate::providers::http_client::configure_aws_loader;
+use crate::providers::image_generation::{
+    parse_base64_images, ImageGenerationOptions, ImageGenerationProvider,
+};
+use crate::providers::moderation::{ModerationResult, ModerationTarget};
 use crate::providers::retry::{ProviderRetry, RetryConfig};
+use crate::providers::usage_estimator::UsageMeter;
 use crate::providers::utils::RequestLog;
 use anyhow::Result;
 use async_trait::async_trait;
 use aws_sdk_bedrockruntime::config::ProvideCredentials;
+use aws_sdk_bedrockruntime::operation::converse::builders::ConverseFluentBuilder;
 use aws_sdk_bedrockruntime::operation::converse::ConverseError;
 use aws_sdk_bedrockruntime::operation::converse_stream::ConverseStreamError;
+use aws_sdk_bedrockruntime::primitives::Blob;
 use aws_sdk_bedrockruntime::{types as bedrock, Client};
 
 use rmcp::model::Tool;
@@ -21,8 +30,8 @@ use tokio_stream::wrappers::ReceiverStream;
 
 // Import the migrated helper functions from providers/formats/bedrock.rs
 use crate::providers::formats::bedrock::{
-    from_bedrock_message, from_bedrock_usage, to_bedrock_message, to_bedrock_tool_config,
-    BedrockStreamAccumulator,
+    from_bedrock_message, from_bedrock_usage, to_bedrock_json, to_bedrock_message,
+    to_bedrock_tool_config, BedrockStreamAccumulator,
 };
 
 pub const BEDROCK_DOC_LINK: &str =
@@ -37,6 +46,9 @@ pub const BEDROCK_KNOWN_MODELS: &[&str] = &[
     "us.anthropic.claude-opus-4-1-20250805-v1:0",
 ];
 
+pub const BEDROCK_DEFAULT_EMBEDDING_MODEL: &str = "amazon.titan-embed-text-v2:0";
+pub const BEDROCK_DEFAULT_IMAGE_MODEL: &str = "amazon.titan-image-generator-v2:0";
+
 pub const BEDROCK_DEFAULT_MAX_RETRIES: usize = 6;
 pub const BEDROCK_DEFAULT_INITIAL_RETRY_INTERVAL_MS: u64 = 2000;
 pub const BEDROCK_DEFAULT_BACKOFF_MULTIPLIER: f64 = 2.0;
@@ -46,6 +58,8 @@ pub const BEDROCK_DEFAULT_MAX_RETRY_INTERVAL_MS: u64 = 120_000;
 pub struct BedrockProvider {
     #[serde(skip)]
     client: Client,
+    #[serde(skip)]
+    control_client: aws_sdk_bedrock::Client,
     model: ModelConfig,
     #[serde(skip)]
     retry_config: RetryConfig,
@@ -55,7 +69,7 @@ pub struct BedrockProvider {
 
 impl BedrockProvider {
     #[allow(clippy::type_complexity)]
-    pub async fn from_env(model: ModelConfig) -> Result<Self> {
+    pub async fn from_env(mut model: ModelConfig) -> Result<Self> {
         let config = crate::config::Config::global();
 
         // Attempt to load config and secrets to get AWS_ prefixed keys
@@ -88,6 +102,7 @@ impl BedrockProvider {
             }
         }
 
+        let loader = configure_aws_loader(loader)?;
         let sdk_config = loader.load().await;
 
         // Validate credentials or return error back up
@@ -99,11 +114,30 @@ impl BedrockProvider {
             .map_err(|e| anyhow::anyhow!("Failed to load AWS credentials: {}. Make sure to run 'aws sso login --profile <your-profile>' if using SSO", e))?;
 
         let client = Client::new(&sdk_config);
+        let control_client = aws_sdk_bedrock::Client::new(&sdk_config);
+
+        // Application inference profile ARNs don't name the model, so look up the model
+        // behind the profile to pick the right context limit
+        if is_application_inference_profile(&model.model_name) && model.context_limit.is_none() {
+            match resolve_inference_profile_model(&control_client, &model.model_name).await {
+                Ok(Some(model_id)) => {
+                    model =
+                        model.with_context_limit(Some(ModelConfig::known_context_limit(&model_id)));
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!(
+                    "Failed to resolve Bedrock inference profile {}: {}",
+                    model.model_name,
+                    e
+                ),
+            }
+        }
 
         let retry_config = Self::load_retry_config(config);
 
         Ok(Self {
             client,
+            control_client,
             model,
             retry_config,
             name: Self::metadata().name,
@@ -135,19 +169,18 @@ impl BedrockProvider {
         }
     }
 
-    async fn converse(
-        &self,
+    /// The Converse request for a completion, ready to send
+    fn converse_request(
+        client: &Client,
+        model_config: &ModelConfig,
         system: &str,
         messages: &[Message],
         tools: &[Tool],
-    ) -> Result<(bedrock::Message, Option<bedrock::TokenUsage>), ProviderError> {
-        let model_name = &self.model.model_name;
-
-        let mut request = self
-            .client
+    ) -> Result<ConverseFluentBuilder, ProviderError> {
+        let mut request = client
             .converse()
             .system(bedrock::SystemContentBlock::Text(system.to_string()))
-            .model_id(model_name.to_string())
+            .model_id(model_config.model_name.clone())
             .set_messages(Some(
                 messages
                     .iter()
@@ -156,11 +189,27 @@ impl BedrockProvider {
                     .collect::<Result<_>>()?,
             ));
 
-        if !tools.is_empty() {
-            request = request.tool_config(to_bedrock_tool_config(tools)?);
+        request = request.set_tool_config(to_bedrock_tool_config(
+            tools,
+            model_config.tool_choice_for_call().as_ref(),
+        )?);
+
+        if let Some(fields) = &model_config.additional_request_fields {
+            request = request
+                .additional_model_request_fields(to_bedrock_json(&Value::Object(fields.clone())));
         }
 
-        let response = request
+        Ok(request)
+    }
+
+    async fn converse(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(bedrock::Message, Option<bedrock::TokenUsage>), ProviderError> {
+        let response = Self::converse_request(&self.client, model_config, system, messages, tools)?
             .send()
             .await
             .map_err(|err| match err.into_service_error() {
@@ -201,12 +250,13 @@ impl BedrockProvider {
     #[allow(clippy::type_complexity)]
     async fn converse_stream_internal(
         client: &Client,
-        model_name: &str,
+        model_config: &ModelConfig,
         system: &str,
         messages: &[Message],
         tools: &[Tool],
         tx: mpsc::Sender<Result<(Option<Message>, Option<ProviderUsage>), ProviderError>>,
     ) -> Result<(), ProviderError> {
+        let model_name = &model_config.model_name;
         let mut request = client.converse_stream().model_id(model_name.to_string());
 
         if !system.is_empty() {
@@ -220,8 +270,14 @@ impl BedrockProvider {
             .collect::<Result<_>>()?;
         request = request.set_messages(Some(bedrock_messages));
 
-        if !tools.is_empty() {
-            request = request.tool_config(to_bedrock_tool_config(tools)?);
+        request = request.set_tool_config(to_bedrock_tool_config(
+            tools,
+            model_config.tool_choice_for_call().as_ref(),
+        )?);
+
+        if let Some(fields) = &model_config.additional_request_fields {
+            request = request
+                .additional_model_request_fields(to_bedrock_json(&Value::Object(fields.clone())));
         }
 
         let response = request
@@ -230,10 +286,12 @@ impl BedrockProvider {
             .map_err(Self::map_converse_stream_error)?;
         let mut stream = response.stream;
         let mut accumulator = BedrockStreamAccumulator::new();
+        let mut meter = UsageMeter::new(model_name).await;
 
         loop {
             match stream.recv().await {
                 Ok(Some(event)) => {
+                    let mut interim_usage = None;
                     let maybe_message = match event {
                         bedrock::ConverseStreamOutput::MessageStart(msg_start) => {
                             accumulator.handle_message_start(&msg_start.role)?;
@@ -252,6 +310,15 @@ impl BedrockProvider {
                         }
                         bedrock::ConverseStreamOutput::ContentBlockDelta(delta_event) => {
                             if let Some(ref delta) = delta_event.delta {
+                                let output = match delta {
+                                    bedrock::ContentBlockDelta::Text(text) => Some(text.as_str()),
+                                    bedrock::ContentBlockDelta::ToolUse(tool_delta) => {
+                                        Some(tool_delta.input.as_str())
+                                    }
+                                    _ => None,
+                                };
+                                interim_usage =
+                                    output.and_then(|output| meter.record_output(output));
                                 let msg = accumulator.handle_content_block_delta(
                                     delta_event.content_block_index,
                                     delta,
@@ -285,6 +352,11 @@ impl BedrockProvider {
                             .await
                             .map_err(|_| ProviderError::RequestFailed("Channel closed".into()))?;
                     }
+                    if let Some(usage) = interim_usage {
+                        tx.send(Ok((None, Some(usage))))
+                            .await
+                            .map_err(|_| ProviderError::RequestFailed("Channel closed".into()))?;
+                    }
                 }
                 Ok(None) => {
                     tracing::debug!("Stream ended");
@@ -388,7 +460,7 @@ impl Provider for BedrockProvider {
         let model_name = model_config.model_name.clone();
 
         let (bedrock_message, bedrock_usage) = self
-            .with_retry(|| self.converse(system, messages, tools))
+            .with_retry(|| self.converse(model_config, system, messages, tools))
             .await?;
 
         let usage = bedrock_usage
@@ -425,7 +497,11 @@ impl Provider for BedrockProvider {
         let stream_receiver = ReceiverStream::new(rx);
 
         let client = self.client.clone();
-        let model_name = self.model.model_name.clone();
+        // The spawned task doesn't see a tool choice set for this call, so resolve it here
+        let model_config = self
+            .model
+            .clone()
+            .with_tool_choice(self.model.tool_choice_for_call());
         let system_prompt = system.to_string();
         let messages_clone = messages.to_vec();
         let tools_clone = tools.to_vec();
@@ -433,7 +509,7 @@ impl Provider for BedrockProvider {
         tokio::spawn(async move {
             let result = Self::converse_stream_internal(
                 &client,
-                &model_name,
+                &model_config,
                 &system_prompt,
                 &messages_clone,
                 &tools_clone,
@@ -452,4 +528,443 @@ impl Provider for BedrockProvider {
     fn supports_streaming(&self) -> bool {
         true
     }
+
+    fn supports_embeddings(&self) -> bool {
+        true
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.embed(texts, &EmbeddingOptions::from_config()).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        use aws_sdk_bedrock::types::ModelModality;
+
+        let response = self
+            .control_client
+            .list_foundation_models()
+            .by_output_modality(ModelModality::Text)
+            .send()
+            .await
+            .map_err(|err| {
+                ProviderError::RequestFailed(format!(
+                    "Failed to list Bedrock foundation models: {:?}",
+                    err.into_service_error()
+                ))
+            })?;
+
+        // ListFoundationModels doesn't report context windows, so use the known model patterns
+        let mut models: Vec<ModelInfo> = response
+            .model_summaries()
+            .iter()
+            .map(|summary| {
+                let model_id = summary.model_id();
+                let mut info = ModelInfo::new(model_id, ModelConfig::known_context_limit(model_id));
+                info.supports_vision =
+                    Some(summary.input_modalities().contains(&ModelModality::Image));
+                info
+            })
+            .collect();
+
+        // Listing profiles needs its own IAM permission, so don't fail the whole listing
+        // if it's missing
+        match self.list_inference_profiles().await {
+            Ok(profiles) => models.extend(profiles),
+            Err(e) => tracing::warn!("Failed to list Bedrock inference profiles: {}", e),
+        }
+
+        models.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(models)
+    }
+}
+
+impl BedrockProvider {
+    /// Screen text with a standalone guardrail. Categories are the names of denied topics,
+    /// content filter types, word policies and PII entity types the guardrail matched.
+    pub(crate) async fn apply_guardrail(
+        &self,
+        guardrail_id: &str,
+        guardrail_version: &str,
+        text: &str,
+        target: ModerationTarget,
+    ) -> Result<ModerationResult, ProviderError> {
+        use bedrock::{
+            GuardrailAction, GuardrailContentBlock, GuardrailContentSource, GuardrailTextBlock,
+        };
+
+        let source = match target {
+            ModerationTarget::Input => GuardrailContentSource::Input,
+            ModerationTarget::Output => GuardrailContentSource::Output,
+        };
+        let content = GuardrailContentBlock::Text(
+            GuardrailTextBlock::builder()
+                .text(text)
+                .build()
+                .map_err(|e| ProviderError::ExecutionError(e.to_string()))?,
+        );
+
+        let output = self
+            .with_retry(|| async {
+                self.client
+                    .apply_guardrail()
+                    .guardrail_identifier(guardrail_id)
+                    .guardrail_version(guardrail_version)
+                    .source(source.clone())
+                    .content(content.clone())
+                    .send()
+                    .await
+                    .map_err(|err| {
+                        ProviderError::ServerError(format!(
+                            "Failed to apply Bedrock guardrail: {:?}",
+                            err.into_service_error()
+                        ))
+                    })
+            })
+            .await?;
+
+        if output.action() != &GuardrailAction::GuardrailIntervened {
+            return Ok(ModerationResult::allowed());
+        }
+
+        let mut categories = Vec::new();
+        for assessment in output.assessments() {
+            if let Some(policy) = assessment.topic_policy() {
+                categories.extend(policy.topics().iter().map(|t| t.name().to_string()));
+            }
+            if let Some(policy) = assessment.content_policy() {
+                categories.extend(
+                    policy
+                        .filters()
+                        .iter()
+                        .map(|f| f.r#type().as_str().to_lowercase()),
+                );
+            }
+            if let Some(policy) = assessment.word_policy() {
+                if !policy.custom_words().is_empty() {
+                    categories.push("custom_words".to_string());
+                }
+                if !policy.managed_word_lists().is_empty() {
+                    categories.push("profanity".to_string());
+                }
+            }
+            if let Some(policy) = assessment.sensitive_information_policy() {
+                categories.extend(
+                    policy
+                        .pii_entities()
+                        .iter()
+                        .map(|e| e.r#type().as_str().to_lowercase()),
+                );
+            }
+        }
+        categories.dedup();
+        Ok(ModerationResult::flagged(categories))
+    }
+
+    /// Active cross-region and application inference profiles. Application profiles are
+    /// listed by ARN, which is how they're passed as a model ID.
+    async fn list_inference_profiles(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        use aws_sdk_bedrock::types::{InferenceProfileStatus, InferenceProfileType};
+
+        let mut models = Vec::new();
+        for profile_type in [
+            InferenceProfileType::SystemDefined,
+            InferenceProfileType::Application,
+        ] {
+            let mut next_token = None;
+            loop {
+                let response = self
+                    .control_client
+                    .list_inference_profiles()
+                    .type_equals(profile_type.clone())
+                    .set_next_token(next_token)
+                    .send()
+                    .await
+                    .map_err(|err| {
+                        ProviderError::RequestFailed(format!(
+                            "Failed to list Bedrock inference profiles: {:?}",
+                            err.into_service_error()
+                        ))
+                    })?;
+
+                for summary in response.inference_profile_summaries() {
+                    if summary.status() != &InferenceProfileStatus::Active {
+                        continue;
+                    }
+                    let name = if profile_type == InferenceProfileType::Application {
+                        summary.inference_profile_arn()
+                    } else {
+                        summary.inference_profile_id()
+                    };
+                    let context_limit = summary
+                        .models()
+                        .first()
+                        .and_then(|m| m.model_arn())
+                        .and_then(foundation_model_id)
+                        .map(ModelConfig::known_context_limit)
+                        .unwrap_or_else(|| ModelConfig::known_context_limit(name));
+                    models.push(ModelInfo::new(name, context_limit));
+                }
+
+                next_token = response.next_token().map(str::to_string);
+                if next_token.is_none() {
+                    break;
+                }
+            }
+        }
+        Ok(models)
+    }
+}
+
+/// Whether `model_id` is an application inference profile ARN, as opposed to a model ID or a
+/// cross-region profile ID such as `us.anthropic.claude-sonnet-4-20250514-v1:0`
+pub fn is_application_inference_profile(model_id: &str) -> bool {
+    model_id.starts_with("arn:") && model_id.contains(":application-inference-profile/")
+}
+
+/// The foundation model ID from a model ARN such as
+/// `arn:aws:bedrock:us-east-1::foundation-model/anthropic.claude-3-haiku-20240307-v1:0`
+fn foundation_model_id(model_arn: &str) -> Option<&str> {
+    model_arn
+        .split_once(":foundation-model/")
+        .map(|(_, model_id)| model_id)
+}
+
+/// Look up the foundation model an inference profile routes to
+async fn resolve_inference_profile_model(
+    control_client: &aws_sdk_bedrock::Client,
+    profile: &str,
+) -> Result<Option<String>, ProviderError> {
+    let response = control_client
+        .get_inference_profile()
+        .inference_profile_identifier(profile)
+        .send()
+        .await
+        .map_err(|err| {
+            ProviderError::RequestFailed(format!(
+                "Failed to get Bedrock inference profile: {:?}",
+                err.into_service_error()
+            ))
+        })?;
+
+    Ok(response
+        .models()
+        .first()
+        .and_then(|m| m.model_arn())
+        .and_then(foundation_model_id)
+        .map(str::to_string))
+}
+
+#[async_trait]
+impl EmbeddingProvider for BedrockProvider {
+    fn default_embedding_model(&self) -> &str {
+        BEDROCK_DEFAULT_EMBEDDING_MODEL
+    }
+
+    /// Titan text embedding models take a single input per invocation
+    fn max_embedding_batch_size(&self) -> usize {
+        1
+    }
+
+    async fn embed_batch(
+        &self,
+        texts: &[String],
+        model: &str,
+        dimensions: Option<usize>,
+    ) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for text in texts {
+            let mut body = serde_json::json!({"inputText": text});
+            if let Some(dimensions) = dimensions {
+                body["dimensions"] = serde_json::json!(dimensions);
+                body["normalize"] = serde_json::json!(true);
+            }
+            let body = serde_json::to_vec(&body)
+                .map_err(|e| ProviderError::ExecutionError(e.to_string()))?;
+
+            let response = self
+                .with_retry(|| async {
+                    self.client
+                        .invoke_model()
+                        .model_id(model)
+                        .content_type("application/json")
+                        .accept("application/json")
+                        .body(Blob::new(body.clone()))
+                        .send()
+                        .await
+                        .map_err(|err| {
+                            ProviderError::ServerError(format!(
+                                "Failed to call Bedrock embeddings: {:?}",
+                                err.into_service_error()
+                            ))
+                        })
+                })
+                .await?;
+
+            let json: Value = serde_json::from_slice(response.body().as_ref()).map_err(|e| {
+                ProviderError::RequestFailed(format!("Invalid embedding response: {}", e))
+            })?;
+            embeddings.extend(parse_embedding_vectors([&json["embedding"]])?);
+        }
+        Ok(embeddings)
+    }
+}
+
+#[async_trait]
+impl ImageGenerationProvider for BedrockProvider {
+    fn default_image_model(&self) -> &str {
+        BEDROCK_DEFAULT_IMAGE_MODEL
+    }
+
+    async fn generate_images(
+        &self,
+        prompt: &str,
+        options: &ImageGenerationOptions,
+    ) -> Result<Vec<MessageContent>, ProviderError> {
+        let model = options
+            .model
+            .as_deref()
+            .unwrap_or(BEDROCK_DEFAULT_IMAGE_MODEL);
+        let body = serde_json::to_vec(&create_image_request(model, prompt, options))
+            .map_err(|e| ProviderError::ExecutionError(e.to_string()))?;
+
+        let response = self
+            .with_retry(|| async {
+                self.client
+                    .invoke_model()
+                    .model_id(model)
+                    .content_type("application/json")
+                    .accept("application/json")
+                    .body(Blob::new(body.clone()))
+                    .send()
+                    .await
+                    .map_err(|err| {
+                        ProviderError::ServerError(format!(
+                            "Failed to call Bedrock image generation: {:?}",
+                            err.into_service_error()
+                        ))
+                    })
+            })
+            .await?;
+
+        let json: Value = serde_json::from_slice(response.body().as_ref()).map_err(|e| {
+            ProviderError::RequestFailed(format!("Invalid image generation response: {}", e))
+        })?;
+        if is_stability_model(model) {
+            let artifacts = json["artifacts"].as_array().ok_or_else(|| {
+                ProviderError::RequestFailed("Missing artifacts in response".to_string())
+            })?;
+            parse_base64_images(artifacts.iter().map(|a| &a["base64"]), "image/png")
+        } else {
+            let images = json["images"].as_array().ok_or_else(|| {
+                ProviderError::RequestFailed("Missing images in response".to_string())
+            })?;
+            parse_base64_images(images, "image/png")
+        }
+    }
+}
+
+fn is_stability_model(model: &str) -> bool {
+    model.starts_with("stability.")
+}
+
+/// Build an InvokeModel body for Stability (SDXL) or Titan Image models
+fn create_image_request(model: &str, prompt: &str, options: &ImageGenerationOptions) -> Value {
+    let (width, height) = options.dimensions().unwrap_or((1024, 1024));
+    let count = options.count.unwrap_or(1);
+
+    if is_stability_model(model) {
+        let mut text_prompts = vec![serde_json::json!({"text": prompt, "weight": 1.0})];
+        if let Some(negative_prompt) = &options.negative_prompt {
+            text_prompts.push(serde_json::json!({"text": negative_prompt, "weight": -1.0}));
+        }
+        serde_json::json!({
+            "text_prompts": text_prompts,
+            "width": width,
+            "height": height,
+            "samples": count,
+        })
+    } else {
+        let mut text_to_image_params = serde_json::json!({"text": prompt});
+        if let Some(negative_prompt) = &options.negative_prompt {
+            text_to_image_params["negativeText"] = serde_json::json!(negative_prompt);
+        }
+        serde_json::json!({
+            "taskType": "TEXT_IMAGE",
+            "textToImageParams": text_to_image_params,
+            "imageGenerationConfig": {
+                "numberOfImages": count,
+                "width": width,
+                "height": height,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inference_profile_ids() {
+        assert!(is_application_inference_profile(
+            "arn:aws:bedrock:us-east-1:123456789012:application-inference-profile/abc123"
+        ));
+        assert!(!is_application_inference_profile(
+            "us.anthropic.claude-sonnet-4-20250514-v1:0"
+        ));
+        assert!(!is_application_inference_profile(
+            "arn:aws:bedrock:us-east-1:123456789012:inference-profile/us.anthropic.claude-sonnet-4-20250514-v1:0"
+        ));
+
+        assert_eq!(
+            foundation_model_id(
+                "arn:aws:bedrock:us-east-1::foundation-model/anthropic.claude-3-haiku-20240307-v1:0"
+            ),
+            Some("anthropic.claude-3-haiku-20240307-v1:0")
+        );
+        assert_eq!(foundation_model_id("anthropic.claude-3-haiku"), None);
+    }
+
+    #[test]
+    fn test_create_image_request() {
+        let options = ImageGenerationOptions::default()
+            .with_size(Some("512x768".to_string()))
+            .with_negative_prompt(Some("blurry".to_string()));
+
+        let titan = create_image_request(BEDROCK_DEFAULT_IMAGE_MODEL, "a goose", &options);
+        assert_eq!(titan["taskType"], "TEXT_IMAGE");
+        assert_eq!(titan["textToImageParams"]["text"], "a goose");
+        assert_eq!(titan["textToImageParams"]["negativeText"], "blurry");
+        assert_eq!(titan["imageGenerationConfig"]["width"], 512);
+        assert_eq!(titan["imageGenerationConfig"]["height"], 768);
+        assert_eq!(titan["imageGenerationConfig"]["numberOfImages"], 1);
+
+        let sdxl = create_image_request("stability.stable-diffusion-xl-v1", "a goose", &options);
+        assert_eq!(sdxl["text_prompts"][0]["text"], "a goose");
+        assert_eq!(sdxl["text_prompts"][1]["weight"], -1.0);
+        assert_eq!(sdxl["samples"], 1);
+        assert!(sdxl.get("taskType").is_none());
+    }
+
+    #[test]
+    fn test_additional_request_fields_in_converse_request() {
+        let model_config = temp_env::with_var(
+            "GOOSE_ADDITIONAL_REQUEST_FIELDS",
+            Some(r#"{"top_k": 50}"#),
+            || ModelConfig::new("anthropic.claude-3-haiku-20240307-v1:0").unwrap(),
+        );
+        let client = Client::from_conf(
+            aws_sdk_bedrockruntime::Config::builder()
+                .behavior_version(aws_sdk_bedrockruntime::config::BehaviorVersion::latest())
+                .region(aws_sdk_bedrockruntime::config::Region::new("us-east-1"))
+                .build(),
+        );
+
+        let request =
+            BedrockProvider::converse_request(&client, &model_config, "system", &[], &[]).unwrap();
+        assert_eq!(
+            request.as_input().get_additional_model_request_fields(),
+            &Some(to_bedrock_json(&serde_json::json!({"top_k": 50})))
+        );
+    }
 }
@@ -487,6 +487,10 @@ async fn build_canonical_models() -> Result<()> {
                 .get("image")
                 .and_then(|v| v.as_str())
                 .and_then(|s| s.parse().ok()),
+            input_cache_read: pricing_obj
+                .get("input_cache_read")
+                .and_then(|v| v.as_str())
+                .and_then(|s| s.parse().ok()),
         };
 
         let canonical_model = CanonicalModel {
@@ -26,3 +26,9 @@ pub fn maybe_get_canonical_model(provider: &str, model: &str) -> Option<Canonica
     let canonical_id = map_to_canonical_model(provider, model, registry)?;
     registry.get(&canonical_id).cloned()
 }
+
+/// The canonical model for a model name whose provider isn't known
+pub fn maybe_get_canonical_model_by_name(model: &str) -> Option<CanonicalModel> {
+    // OpenRouter ids are canonical names already, and for bare names it infers the provider
+    maybe_get_canonical_model("openrouter", model)
+}
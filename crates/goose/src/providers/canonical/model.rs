@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 /// Pricing information for a model (all costs in USD per token)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Pricing {
     /// Cost per prompt token
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -18,6 +18,10 @@ pub struct Pricing {
     /// Cost per image
     #[serde(skip_serializing_if = "Option::is_none")]
     pub image: Option<f64>,
+
+    /// Cost per input token read from the prompt cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_cache_read: Option<f64>,
 }
 
 /// Canonical representation of a model
@@ -134,6 +134,9 @@ impl ClaudeCodeProvider {
             GooseMode::Chat => {
                 // Chat mode doesn't need permission flags
             }
+            GooseMode::DryRun => {
+                cmd.arg("--permission-mode").arg("plan");
+            }
         }
         Ok(())
     }
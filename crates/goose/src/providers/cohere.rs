@@ -0,0 +1,99 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use super::api_client::{ApiClient, AuthMethod};
+use super::embedding::{parse_embedding_vectors, EmbeddingProvider};
+use super::errors::ProviderError;
+use super::retry::ProviderRetry;
+use super::utils::handle_response_openai_compat;
+
+pub const COHERE_API_HOST: &str = "https://api.cohere.com";
+pub const COHERE_DEFAULT_EMBEDDING_MODEL: &str = "embed-v4.0";
+
+/// Embeddings from Cohere's v2 embed API. Cohere is only used for embeddings,
+/// so this is not a chat [`Provider`](super::base::Provider).
+pub struct CohereEmbeddingProvider {
+    api_client: ApiClient,
+}
+
+impl CohereEmbeddingProvider {
+    pub fn from_env() -> Result<Self> {
+        let config = crate::config::Config::global();
+        let api_key: String = config.get_secret("COHERE_API_KEY")?;
+        let host: String = config
+            .get_param("COHERE_HOST")
+            .unwrap_or_else(|_| COHERE_API_HOST.to_string());
+
+        let api_client = ApiClient::new(host, AuthMethod::BearerToken(api_key))?;
+        Ok(Self { api_client })
+    }
+}
+
+impl ProviderRetry for CohereEmbeddingProvider {}
+
+#[async_trait]
+impl EmbeddingProvider for CohereEmbeddingProvider {
+    fn default_embedding_model(&self) -> &str {
+        COHERE_DEFAULT_EMBEDDING_MODEL
+    }
+
+    fn max_embedding_batch_size(&self) -> usize {
+        96
+    }
+
+    async fn embed_batch(
+        &self,
+        texts: &[String],
+        model: &str,
+        dimensions: Option<usize>,
+    ) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let payload = create_embed_request(texts, model, dimensions);
+
+        let response = self
+            .with_retry(|| async {
+                let response = self.api_client.response_post("v2/embed", &payload).await?;
+                handle_response_openai_compat(response).await
+            })
+            .await?;
+
+        let embeddings = response
+            .pointer("/embeddings/float")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| {
+                ProviderError::RequestFailed("Missing float embeddings in response".to_string())
+            })?;
+        parse_embedding_vectors(embeddings)
+    }
+}
+
+fn create_embed_request(texts: &[String], model: &str, dimensions: Option<usize>) -> Value {
+    let mut payload = json!({
+        "model": model,
+        "texts": texts,
+        "input_type": "search_document",
+        "embedding_types": ["float"],
+    });
+    if let Some(dimensions) = dimensions {
+        payload["output_dimension"] = json!(dimensions);
+    }
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_embed_request() {
+        let texts = vec!["hello".to_string()];
+
+        let payload = create_embed_request(&texts, "embed-v4.0", None);
+        assert_eq!(payload["texts"], json!(["hello"]));
+        assert_eq!(payload["embedding_types"], json!(["float"]));
+        assert!(payload.get("output_dimension").is_none());
+
+        let payload = create_embed_request(&texts, "embed-v4.0", Some(512));
+        assert_eq!(payload["output_dimension"], 512);
+    }
+}
@@ -0,0 +1,293 @@
+//! What to do when a request doesn't fit the model's context window.
+//!
+//! Without a policy each provider reacts in its own way: most fail with
+//! [`ProviderError::ContextLengthExceeded`], while Ollama silently truncates. With a
+//! [`ContextPolicy`] set globally through `GOOSE_CONTEXT_POLICY` or per session through
+//! [`SessionConfig::context_policy`](crate::agents::types::SessionConfig),
+//! [`ContextPolicyProvider`] counts the tokens of every request before sending it and applies
+//! the policy to requests that are too large, the same way for every provider.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rmcp::model::Tool;
+use serde::{Deserialize, Serialize};
+
+use super::base::{MessageStream, Provider, ProviderMetadata, ProviderUsage};
+use super::errors::ProviderError;
+use super::stream_event::EventStream;
+use crate::config::Config;
+use crate::context_mgmt::{compact_messages, is_turn_start};
+use crate::conversation::message::Message;
+use crate::conversation::Conversation;
+use crate::model::ModelConfig;
+
+pub const CONTEXT_POLICY_CONFIG_KEY: &str = "GOOSE_CONTEXT_POLICY";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextPolicy {
+    /// Fail with `ContextLengthExceeded` before sending
+    Error,
+    /// Drop the oldest turns until the request fits
+    TruncateOldest,
+    /// Keep the first turn, which usually states the task, and drop the turns after it
+    TruncateMiddle,
+    /// Summarize older turns, keeping recent ones verbatim
+    Summarize,
+}
+
+impl ContextPolicy {
+    /// The policy set with GOOSE_CONTEXT_POLICY, if any
+    pub fn from_config() -> Option<Self> {
+        Config::global().get_param(CONTEXT_POLICY_CONFIG_KEY).ok()
+    }
+
+    /// Wrap a provider so its requests are held to this policy
+    pub fn wrap(self, provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+        Arc::new(ContextPolicyProvider::new(provider, self))
+    }
+}
+
+/// Start indices of the turns in `messages`, the first being 0 even if the conversation
+/// doesn't open with a user message
+fn turn_starts(messages: &[Message]) -> Vec<usize> {
+    let mut starts: Vec<usize> = messages
+        .iter()
+        .enumerate()
+        .filter(|(idx, msg)| *idx > 0 && is_turn_start(msg))
+        .map(|(idx, _)| idx)
+        .collect();
+    starts.insert(0, 0);
+    starts
+}
+
+/// The messages of the turns in `keep`, given as indices into `starts`
+fn select_turns(messages: &[Message], starts: &[usize], keep: &[usize]) -> Vec<Message> {
+    keep.iter()
+        .flat_map(|&turn| {
+            let end = starts.get(turn + 1).copied().unwrap_or(messages.len());
+            messages[starts[turn]..end].iter().cloned()
+        })
+        .collect()
+}
+
+/// A provider that applies a [`ContextPolicy`] to requests that exceed the context limit
+pub struct ContextPolicyProvider {
+    inner: Arc<dyn Provider>,
+    policy: ContextPolicy,
+}
+
+impl ContextPolicyProvider {
+    pub fn new(inner: Arc<dyn Provider>, policy: ContextPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// Tokens available for the request, leaving room for the completion
+    fn budget(model_config: &ModelConfig) -> usize {
+        let reserved = model_config.max_tokens.unwrap_or(0).max(0) as usize;
+        model_config.context_limit().saturating_sub(reserved)
+    }
+
+    async fn fits(
+        &self,
+        budget: usize,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<bool, ProviderError> {
+        Ok(self.inner.count_tokens(system, messages, tools).await? <= budget)
+    }
+
+    /// `messages`, reduced according to the policy if the request doesn't fit
+    async fn prepare(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<Vec<Message>, ProviderError> {
+        let budget = Self::budget(model_config);
+        let tokens = self.inner.count_tokens(system, messages, tools).await?;
+        if tokens <= budget {
+            return Ok(messages.to_vec());
+        }
+        let exceeded = || {
+            ProviderError::ContextLengthExceeded(format!(
+                "Request of {} tokens exceeds the {} tokens available for {}",
+                tokens, budget, model_config.model_name
+            ))
+        };
+
+        let starts = turn_starts(messages);
+        let mut keep: Vec<usize> = (0..starts.len()).collect();
+        match self.policy {
+            ContextPolicy::Error => return Err(exceeded()),
+            ContextPolicy::TruncateOldest => {
+                while keep.len() > 1 {
+                    keep.remove(0);
+                    let reduced = select_turns(messages, &starts, &keep);
+                    if self.fits(budget, system, &reduced, tools).await? {
+                        return Ok(reduced);
+                    }
+                }
+            }
+            ContextPolicy::TruncateMiddle => {
+                while keep.len() > 2 {
+                    keep.remove(1);
+                    let reduced = select_turns(messages, &starts, &keep);
+                    if self.fits(budget, system, &reduced, tools).await? {
+                        return Ok(reduced);
+                    }
+                }
+                let latest = select_turns(messages, &starts, &keep[keep.len() - 1..]);
+                if self.fits(budget, system, &latest, tools).await? {
+                    return Ok(latest);
+                }
+            }
+            ContextPolicy::Summarize => {
+                let conversation = Conversation::new_unvalidated(messages.to_vec());
+                let (compacted, _usage) =
+                    compact_messages(self.inner.as_ref(), &conversation, false)
+                        .await
+                        .map_err(|e| ProviderError::ExecutionError(e.to_string()))?;
+                let reduced = compacted.agent_visible_messages();
+                if self.fits(budget, system, &reduced, tools).await? {
+                    return Ok(reduced);
+                }
+            }
+        }
+        Err(exceeded())
+    }
+}
+
+#[async_trait]
+impl Provider for ContextPolicyProvider {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            "context_policy",
+            "Context Policy Provider",
+            "A provider that fits requests to the context window before sending them",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_name(&self) -> &str {
+        self.inner.get_name()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn inner_provider(&self) -> Option<&dyn Provider> {
+        Some(self.inner.as_ref())
+    }
+
+    async fn complete_with_model(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let messages = self.prepare(model_config, system, messages, tools).await?;
+        self.inner
+            .complete_with_model(model_config, system, &messages, tools)
+            .await
+    }
+
+    async fn stream(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let model_config = self.inner.get_model_config();
+        let messages = self.prepare(&model_config, system, messages, tools).await?;
+        self.inner.stream(system, &messages, tools).await
+    }
+
+    async fn stream_events(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<EventStream, ProviderError> {
+        let model_config = self.inner.get_model_config();
+        let messages = self.prepare(&model_config, system, messages, tools).await?;
+        self.inner.stream_events(system, &messages, tools).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockProvider, MockResponse};
+
+    fn conversation() -> Vec<Message> {
+        let mut messages = Vec::new();
+        for turn in ["task", "middle", "latest"] {
+            messages.push(Message::user().with_text(format!("{} {}", turn, "word ".repeat(40))));
+            messages.push(Message::assistant().with_text(format!("{} done", turn)));
+        }
+        messages
+    }
+
+    async fn sent_turns(
+        policy: ContextPolicy,
+        context_limit: usize,
+    ) -> Result<Vec<String>, ProviderError> {
+        let mock = Arc::new(
+            MockProvider::new()
+                .with_model_config(
+                    ModelConfig::new_or_fail("mock-model").with_context_limit(Some(context_limit)),
+                )
+                .with_response(MockResponse::text("ok")),
+        );
+        let provider = policy.wrap(mock.clone());
+        provider.complete("system", &conversation(), &[]).await?;
+        Ok(mock.requests()[0]
+            .messages
+            .iter()
+            .filter(|msg| is_turn_start(msg))
+            .map(|msg| msg.as_concat_text().split(' ').next().unwrap().to_string())
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn test_requests_that_fit_are_unchanged() {
+        let turns = sent_turns(ContextPolicy::Error, 100_000).await.unwrap();
+        assert_eq!(turns, ["task", "middle", "latest"]);
+    }
+
+    #[tokio::test]
+    async fn test_truncation_policies() {
+        // Each turn is about 50 tokens, so 130 fits two of them
+        assert!(matches!(
+            sent_turns(ContextPolicy::Error, 130).await,
+            Err(ProviderError::ContextLengthExceeded(_))
+        ));
+        assert_eq!(
+            sent_turns(ContextPolicy::TruncateOldest, 130)
+                .await
+                .unwrap(),
+            ["middle", "latest"]
+        );
+        assert_eq!(
+            sent_turns(ContextPolicy::TruncateMiddle, 130)
+                .await
+                .unwrap(),
+            ["task", "latest"]
+        );
+        assert_eq!(
+            sent_turns(ContextPolicy::TruncateOldest, 10)
+                .await
+                .unwrap_err(),
+            sent_turns(ContextPolicy::Error, 10).await.unwrap_err()
+        );
+    }
+}
@@ -6,14 +6,14 @@ use std::time::Duration;
 
 use super::api_client::{ApiClient, AuthMethod, AuthProvider};
 use super::base::{ConfigKey, MessageStream, Provider, ProviderMetadata, ProviderUsage, Usage};
-use super::embedding::EmbeddingCapable;
+use super::embedding::{parse_embedding_vectors, EmbeddingOptions, EmbeddingProvider};
 use super::errors::ProviderError;
 use super::formats::databricks::{create_request, response_to_message};
 use super::oauth;
 use super::retry::ProviderRetry;
 use super::utils::{
-    get_model, handle_response_openai_compat, map_http_error_to_provider_error,
-    stream_openai_compat, ImageFormat, RequestLog,
+    get_model, get_system_fingerprint, handle_response_openai_compat,
+    map_http_error_to_provider_error, stream_openai_compat, ImageFormat, RequestLog,
 };
 use crate::config::ConfigError;
 use crate::conversation::message::Message;
@@ -33,6 +33,8 @@ const DEFAULT_TIMEOUT_SECS: u64 = 600;
 
 pub const DATABRICKS_DEFAULT_MODEL: &str = "databricks-claude-sonnet-4";
 const DATABRICKS_DEFAULT_FAST_MODEL: &str = "gemini-2-5-flash";
+/// Serving endpoint for embeddings when GOOSE_EMBEDDING_MODEL doesn't name another
+const DATABRICKS_DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
 pub const DATABRICKS_KNOWN_MODELS: &[&str] = &[
     "databricks-claude-sonnet-4-5",
     "databricks-claude-3-7-sonnet",
@@ -218,18 +220,13 @@ impl DatabricksProvider {
         })
     }
 
-    fn get_endpoint_path(&self, model_name: &str, is_embedding: bool) -> String {
-        if is_embedding {
-            "serving-endpoints/text-embedding-3-small/invocations".to_string()
-        } else {
-            format!("serving-endpoints/{}/invocations", model_name)
-        }
+    fn get_endpoint_path(&self, model_name: &str) -> String {
+        format!("serving-endpoints/{}/invocations", model_name)
     }
 
     async fn post(&self, payload: Value, model_name: Option<&str>) -> Result<Value, ProviderError> {
-        let is_embedding = payload.get("input").is_some() && payload.get("messages").is_none();
         let model_to_use = model_name.unwrap_or(&self.model.model_name);
-        let path = self.get_endpoint_path(model_to_use, is_embedding);
+        let path = self.get_endpoint_path(model_to_use);
 
         let response = self.api_client.response_post(&path, &payload).await?;
         handle_response_openai_compat(response).await
@@ -297,7 +294,11 @@ impl Provider for DatabricksProvider {
         let response_model = get_model(&response);
         log.write(&response, Some(&usage))?;
 
-        Ok((message, ProviderUsage::new(response_model, usage)))
+        Ok((
+            message,
+            ProviderUsage::new(response_model, usage)
+                .with_system_fingerprint(get_system_fingerprint(&response)),
+        ))
     }
 
     async fn stream(
@@ -320,7 +321,7 @@ impl Provider for DatabricksProvider {
             .unwrap()
             .insert("stream".to_string(), Value::Bool(true));
 
-        let path = self.get_endpoint_path(&model_config.model_name, false);
+        let path = self.get_endpoint_path(&model_config.model_name);
         let mut log = RequestLog::start(&self.model, &payload)?;
         let response = self
             .with_retry(|| async {
@@ -352,9 +353,7 @@ impl Provider for DatabricksProvider {
     }
 
     async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
-        EmbeddingCapable::create_embeddings(self, texts)
-            .await
-            .map_err(|e| ProviderError::ExecutionError(e.to_string()))
+        self.embed(texts, &EmbeddingOptions::from_config()).await
     }
 
     async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
@@ -421,33 +420,34 @@ impl Provider for DatabricksProvider {
 }
 
 #[async_trait]
-impl EmbeddingCapable for DatabricksProvider {
-    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
-        if texts.is_empty() {
-            return Ok(vec![]);
-        }
+impl EmbeddingProvider for DatabricksProvider {
+    fn default_embedding_model(&self) -> &str {
+        DATABRICKS_DEFAULT_EMBEDDING_MODEL
+    }
 
-        let request = json!({
-            "input": texts,
-        });
+    /// The smallest limit of the embedding models Databricks hosts
+    fn max_embedding_batch_size(&self) -> usize {
+        150
+    }
 
-        let response = self.with_retry(|| self.post(request.clone(), None)).await?;
+    async fn embed_batch(
+        &self,
+        texts: &[String],
+        model: &str,
+        dimensions: Option<usize>,
+    ) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let mut request = json!({ "input": texts });
+        if let Some(dimensions) = dimensions {
+            request["dimensions"] = json!(dimensions);
+        }
 
-        let embeddings = response["data"]
-            .as_array()
-            .ok_or_else(|| anyhow::anyhow!("Invalid response format: missing data array"))?
-            .iter()
-            .map(|item| {
-                item["embedding"]
-                    .as_array()
-                    .ok_or_else(|| anyhow::anyhow!("Invalid embedding format"))?
-                    .iter()
-                    .map(|v| v.as_f64().map(|f| f as f32))
-                    .collect::<Option<Vec<f32>>>()
-                    .ok_or_else(|| anyhow::anyhow!("Invalid embedding values"))
-            })
-            .collect::<Result<Vec<Vec<f32>>>>()?;
+        let response = self
+            .with_retry(|| self.post(request.clone(), Some(model)))
+            .await?;
 
-        Ok(embeddings)
+        let data = response["data"].as_array().ok_or_else(|| {
+            ProviderError::RequestFailed("Invalid response format: missing data array".to_string())
+        })?;
+        parse_embedding_vectors(data.iter().map(|item| &item["embedding"]))
     }
 }
@@ -1,11 +1,17 @@
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::errors::ProviderError;
+use crate::model::ModelConfig;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingRequest {
     pub input: Vec<String>,
     pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,7 +24,267 @@ pub struct EmbeddingData {
     pub embedding: Vec<f32>,
 }
 
+/// Options for an embedding request. Unset fields fall back to the provider's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddingOptions {
+    /// Embedding model to use instead of the provider default
+    pub model: Option<String>,
+    /// Requested output dimensionality, for models that support shortened embeddings
+    pub dimensions: Option<usize>,
+    /// Maximum number of inputs per request, capped by the provider's own limit
+    pub batch_size: Option<usize>,
+}
+
+impl EmbeddingOptions {
+    /// Read options from GOOSE_EMBEDDING_MODEL, GOOSE_EMBEDDING_DIMENSIONS and
+    /// GOOSE_EMBEDDING_BATCH_SIZE
+    pub fn from_config() -> Self {
+        let config = crate::config::Config::global();
+        Self {
+            model: config.get_param("GOOSE_EMBEDDING_MODEL").ok(),
+            dimensions: config.get_param("GOOSE_EMBEDDING_DIMENSIONS").ok(),
+            batch_size: config.get_param("GOOSE_EMBEDDING_BATCH_SIZE").ok(),
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn with_dimensions(mut self, dimensions: Option<usize>) -> Self {
+        self.dimensions = dimensions;
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: Option<usize>) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+}
+
+/// A source of text embeddings. Implementors only need to embed a single batch;
+/// [`EmbeddingProvider::embed`] splits larger inputs to fit the provider's batch limit.
 #[async_trait]
-pub trait EmbeddingCapable {
-    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>>;
+pub trait EmbeddingProvider: Send + Sync {
+    /// Model used when the options don't name one
+    fn default_embedding_model(&self) -> &str;
+
+    /// Largest number of inputs the provider accepts in one request
+    fn max_embedding_batch_size(&self) -> usize;
+
+    /// Embed a batch of at most `max_embedding_batch_size` texts, in input order
+    async fn embed_batch(
+        &self,
+        texts: &[String],
+        model: &str,
+        dimensions: Option<usize>,
+    ) -> Result<Vec<Vec<f32>>, ProviderError>;
+
+    /// Embed any number of texts, batching requests as needed
+    async fn embed(
+        &self,
+        texts: Vec<String>,
+        options: &EmbeddingOptions,
+    ) -> Result<Vec<Vec<f32>>, ProviderError> {
+        if texts.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let model = options
+            .model
+            .clone()
+            .unwrap_or_else(|| self.default_embedding_model().to_string());
+        let batch_size = options
+            .batch_size
+            .unwrap_or(usize::MAX)
+            .min(self.max_embedding_batch_size())
+            .max(1);
+
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(batch_size) {
+            let batch_embeddings = self.embed_batch(batch, &model, options.dimensions).await?;
+            if batch_embeddings.len() != batch.len() {
+                return Err(ProviderError::ExecutionError(format!(
+                    "Expected {} embeddings but received {}",
+                    batch.len(),
+                    batch_embeddings.len()
+                )));
+            }
+            embeddings.extend(batch_embeddings);
+        }
+        Ok(embeddings)
+    }
+}
+
+/// Create an embedding provider by name using the same configuration keys as the
+/// corresponding chat provider
+pub async fn create_embedding_provider(name: &str) -> Result<Arc<dyn EmbeddingProvider>> {
+    use super::{
+        bedrock::BedrockProvider, cohere::CohereEmbeddingProvider, databricks::DatabricksProvider,
+        google::GoogleProvider, litellm::LiteLLMProvider, ollama::OllamaProvider,
+        openai::OpenAiProvider,
+    };
+
+    let provider: Arc<dyn EmbeddingProvider> = match name {
+        "openai" => Arc::new(
+            OpenAiProvider::from_env(ModelConfig::new_or_fail(
+                super::openai::OPEN_AI_DEFAULT_MODEL,
+            ))
+            .await?,
+        ),
+        "google" => Arc::new(
+            GoogleProvider::from_env(ModelConfig::new_or_fail(
+                super::google::GOOGLE_DEFAULT_MODEL,
+            ))
+            .await?,
+        ),
+        "ollama" => Arc::new(
+            OllamaProvider::from_env(ModelConfig::new_or_fail(
+                super::ollama::OLLAMA_DEFAULT_MODEL,
+            ))
+            .await?,
+        ),
+        "aws_bedrock" => Arc::new(
+            BedrockProvider::from_env(ModelConfig::new_or_fail(
+                super::bedrock::BEDROCK_DEFAULT_MODEL,
+            ))
+            .await?,
+        ),
+        "databricks" => Arc::new(
+            DatabricksProvider::from_env(ModelConfig::new_or_fail(
+                super::databricks::DATABRICKS_DEFAULT_MODEL,
+            ))
+            .await?,
+        ),
+        "litellm" => Arc::new(
+            LiteLLMProvider::from_env(ModelConfig::new_or_fail(
+                super::litellm::LITELLM_DEFAULT_MODEL,
+            ))
+            .await?,
+        ),
+        "cohere" => Arc::new(CohereEmbeddingProvider::from_env()?),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Provider {} does not support embeddings",
+                name
+            ))
+        }
+    };
+    Ok(provider)
+}
+
+/// Parse an array of JSON number arrays into embeddings
+pub(crate) fn parse_embedding_vectors<'a>(
+    vectors: impl IntoIterator<Item = &'a serde_json::Value>,
+) -> Result<Vec<Vec<f32>>, ProviderError> {
+    vectors
+        .into_iter()
+        .map(|vector| {
+            vector
+                .as_array()
+                .and_then(|values| {
+                    values
+                        .iter()
+                        .map(|v| v.as_f64().map(|f| f as f32))
+                        .collect::<Option<Vec<f32>>>()
+                })
+                .ok_or_else(|| {
+                    ProviderError::RequestFailed("Invalid embedding in response".to_string())
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    struct MockEmbeddingProvider {
+        batches: Mutex<Vec<(usize, String, Option<usize>)>>,
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for MockEmbeddingProvider {
+        fn default_embedding_model(&self) -> &str {
+            "mock-embed"
+        }
+
+        fn max_embedding_batch_size(&self) -> usize {
+            3
+        }
+
+        async fn embed_batch(
+            &self,
+            texts: &[String],
+            model: &str,
+            dimensions: Option<usize>,
+        ) -> Result<Vec<Vec<f32>>, ProviderError> {
+            self.batches
+                .lock()
+                .unwrap()
+                .push((texts.len(), model.to_string(), dimensions));
+            Ok(texts.iter().map(|t| vec![t.len() as f32]).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_batches_inputs() {
+        let provider = MockEmbeddingProvider {
+            batches: Mutex::new(vec![]),
+        };
+        let texts: Vec<String> = ["a", "bb", "ccc", "dddd", "eeeee"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let embeddings = provider
+            .embed(texts.clone(), &EmbeddingOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            embeddings,
+            vec![vec![1.0], vec![2.0], vec![3.0], vec![4.0], vec![5.0]]
+        );
+        assert_eq!(
+            *provider.batches.lock().unwrap(),
+            vec![
+                (3, "mock-embed".to_string(), None),
+                (2, "mock-embed".to_string(), None)
+            ]
+        );
+
+        provider.batches.lock().unwrap().clear();
+        let options = EmbeddingOptions::default()
+            .with_model("other")
+            .with_dimensions(Some(256))
+            .with_batch_size(Some(2));
+        provider.embed(texts, &options).await.unwrap();
+        let batch_sizes: Vec<usize> = provider
+            .batches
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(size, model, dimensions)| {
+                assert_eq!(model, "other");
+                assert_eq!(*dimensions, Some(256));
+                *size
+            })
+            .collect();
+        assert_eq!(batch_sizes, vec![2, 2, 1]);
+    }
+
+    #[test]
+    fn test_parse_embedding_vectors() {
+        let vectors = json!([[0.5, 1.0], [2.0, -1.5]]);
+        assert_eq!(
+            parse_embedding_vectors(vectors.as_array().unwrap()).unwrap(),
+            vec![vec![0.5, 1.0], vec![2.0, -1.5]]
+        );
+
+        let invalid = json!([["x"]]);
+        assert!(parse_embedding_vectors(invalid.as_array().unwrap()).is_err());
+    }
 }
@@ -1,35 +1,45 @@
+use super::moderation::ModerationTarget;
+use crate::redaction::redact;
 use reqwest::StatusCode;
 use std::time::Duration;
 use thiserror::Error;
 
+/// Messages are passed through [`redact`] when displayed, so credentials echoed back by a
+/// provider don't reach logs or error reports.
 #[derive(Error, Debug, PartialEq)]
 pub enum ProviderError {
-    #[error("Authentication error: {0}")]
+    #[error("Authentication error: {}", redact(.0))]
     Authentication(String),
 
-    #[error("Context length exceeded: {0}")]
+    #[error("Context length exceeded: {}", redact(.0))]
     ContextLengthExceeded(String),
 
-    #[error("Rate limit exceeded: {details}")]
+    #[error("Rate limit exceeded: {}", redact(.details))]
     RateLimitExceeded {
         details: String,
         retry_delay: Option<Duration>,
     },
 
-    #[error("Server error: {0}")]
+    #[error("Server error: {}", redact(.0))]
     ServerError(String),
 
-    #[error("Request failed: {0}")]
+    #[error("Request failed: {}", redact(.0))]
     RequestFailed(String),
 
-    #[error("Execution error: {0}")]
+    #[error("Execution error: {}", redact(.0))]
     ExecutionError(String),
 
-    #[error("Usage data error: {0}")]
+    #[error("Usage data error: {}", redact(.0))]
     UsageError(String),
 
-    #[error("Unsupported operation: {0}")]
+    #[error("Unsupported operation: {}", redact(.0))]
     NotImplemented(String),
+
+    #[error("Content flagged by moderation ({target}): {}", .categories.join(", "))]
+    ContentFlagged {
+        target: ModerationTarget,
+        categories: Vec<String>,
+    },
 }
 
 impl ProviderError {
@@ -43,6 +53,21 @@ impl ProviderError {
             ProviderError::ExecutionError(_) => "execution",
             ProviderError::UsageError(_) => "usage",
             ProviderError::NotImplemented(_) => "not_implemented",
+            ProviderError::ContentFlagged { .. } => "content_flagged",
+        }
+    }
+
+    /// Fill in the retry delay of a rate limit error that doesn't already have one
+    pub fn with_retry_delay(self, delay: Option<Duration>) -> Self {
+        match self {
+            ProviderError::RateLimitExceeded {
+                details,
+                retry_delay: None,
+            } => ProviderError::RateLimitExceeded {
+                details,
+                retry_delay: delay,
+            },
+            other => other,
         }
     }
 }
@@ -12,18 +12,24 @@ use super::{
     gemini_cli::GeminiCliProvider,
     githubcopilot::GithubCopilotProvider,
     google::GoogleProvider,
-    lead_worker::LeadWorkerProvider,
+    lead_worker::LeadWorkerSettings,
     litellm::LiteLLMProvider,
+    load_balanced::LoadBalancedProvider,
+    middleware::with_registered_middleware,
     ollama::OllamaProvider,
     openai::OpenAiProvider,
     openrouter::OpenRouterProvider,
+    pii::with_configured_pii_scrubbing,
     provider_registry::ProviderRegistry,
+    rate_limit::with_configured_rate_limit,
+    response_cache::with_configured_cache,
     sagemaker_tgi::SageMakerTgiProvider,
     snowflake::SnowflakeProvider,
     tetrate::TetrateProvider,
     venice::VeniceProvider,
     xai::XaiProvider,
 };
+use crate::config::remote::RemoteConfig;
 use crate::model::ModelConfig;
 use crate::providers::base::ProviderType;
 use crate::{
@@ -33,9 +39,8 @@ use crate::{
 use anyhow::Result;
 use tokio::sync::OnceCell;
 
-const DEFAULT_LEAD_TURNS: usize = 3;
-const DEFAULT_FAILURE_THRESHOLD: usize = 2;
-const DEFAULT_FALLBACK_TURNS: usize = 2;
+const DEFAULT_LOAD_BALANCE_FAILURE_THRESHOLD: usize = 3;
+const DEFAULT_LOAD_BALANCE_COOLDOWN_SECS: u64 = 60;
 
 static REGISTRY: OnceCell<RwLock<ProviderRegistry>> = OnceCell::const_new();
 
@@ -124,15 +129,24 @@ async fn get_from_registry(name: &str) -> Result<ProviderEntry> {
 }
 
 pub async fn create(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
+    check_provider_allowed(name)?;
     let config = crate::config::Config::global();
 
-    if let Ok(lead_model_name) = config.get_param::<String>("GOOSE_LEAD_MODEL") {
+    let provider = if let Some(lead_worker) = LeadWorkerSettings::from_config() {
         tracing::info!("Creating lead/worker provider from environment variables");
-        return create_lead_worker_from_env(name, &model, &lead_model_name).await;
-    }
+        create_lead_worker_from_env(name, &model, &lead_worker).await?
+    } else if let Ok(endpoints) = config.get_param::<String>("GOOSE_LOAD_BALANCE_PROVIDERS") {
+        tracing::info!("Creating load balanced provider from environment variables");
+        create_load_balanced_from_env(&model, &endpoints).await?
+    } else {
+        let constructor = get_from_registry(name).await?.constructor.clone();
+        constructor(model).await?
+    };
 
-    let constructor = get_from_registry(name).await?.constructor.clone();
-    constructor(model).await
+    let provider = with_configured_rate_limit(with_configured_pii_scrubbing(
+        with_registered_middleware(provider),
+    ));
+    Ok(with_configured_cache(provider))
 }
 
 pub async fn create_with_default_model(name: impl AsRef<str>) -> Result<Arc<dyn Provider>> {
@@ -150,66 +164,130 @@ pub async fn create_with_named_model(
     create(provider_name, config).await
 }
 
+fn check_provider_allowed(name: &str) -> Result<()> {
+    check_provider_allowed_by(crate::config::remote::active().as_deref(), name)
+}
+
+fn check_provider_allowed_by(remote: Option<&RemoteConfig>, name: &str) -> Result<()> {
+    if let Some(remote) = remote {
+        if !remote.allows_provider(name) {
+            anyhow::bail!("Provider {} is not allowed by the remote config", name);
+        }
+    }
+    Ok(())
+}
+
+fn lead_model_config(settings: &LeadWorkerSettings) -> Result<ModelConfig> {
+    Ok(ModelConfig::new_with_context_env(
+        settings.lead_model.clone(),
+        Some("GOOSE_LEAD_CONTEXT_LIMIT"),
+    )?)
+}
+
 async fn create_lead_worker_from_env(
     default_provider_name: &str,
     default_model: &ModelConfig,
-    lead_model_name: &str,
+    settings: &LeadWorkerSettings,
 ) -> Result<Arc<dyn Provider>> {
-    let config = crate::config::Config::global();
+    let lead_provider_name = settings
+        .lead_provider
+        .as_deref()
+        .unwrap_or(default_provider_name);
+    check_provider_allowed(lead_provider_name)?;
 
-    let lead_provider_name = config
-        .get_param::<String>("GOOSE_LEAD_PROVIDER")
-        .unwrap_or_else(|_| default_provider_name.to_string());
+    let lead_constructor = get_from_registry(lead_provider_name)
+        .await?
+        .constructor
+        .clone();
+    let worker_constructor = get_from_registry(default_provider_name)
+        .await?
+        .constructor
+        .clone();
 
-    let lead_turns = config
-        .get_param::<usize>("GOOSE_LEAD_TURNS")
-        .unwrap_or(DEFAULT_LEAD_TURNS);
-    let failure_threshold = config
-        .get_param::<usize>("GOOSE_LEAD_FAILURE_THRESHOLD")
-        .unwrap_or(DEFAULT_FAILURE_THRESHOLD);
-    let fallback_turns = config
-        .get_param::<usize>("GOOSE_LEAD_FALLBACK_TURNS")
-        .unwrap_or(DEFAULT_FALLBACK_TURNS);
-
-    let lead_model_config = ModelConfig::new_with_context_env(
-        lead_model_name.to_string(),
-        Some("GOOSE_LEAD_CONTEXT_LIMIT"),
-    )?;
+    let lead_provider = lead_constructor(lead_model_config(settings)?).await?;
+    let worker_provider = worker_constructor(create_worker_model_config(default_model)?).await?;
 
-    let worker_model_config = create_worker_model_config(default_model)?;
+    Ok(Arc::new(settings.wrap(lead_provider, worker_provider)))
+}
 
-    let registry = get_registry().await;
+/// Put `settings`' lead model in front of an existing provider, which becomes the worker
+pub async fn create_lead_worker(
+    worker: Arc<dyn Provider>,
+    settings: &LeadWorkerSettings,
+) -> Result<Arc<dyn Provider>> {
+    let lead_provider_name = settings
+        .lead_provider
+        .clone()
+        .unwrap_or_else(|| worker.get_name().to_string());
+    check_provider_allowed(&lead_provider_name)?;
 
-    let lead_constructor = {
-        let guard = registry.read().unwrap();
-        guard
-            .entries
-            .get(&lead_provider_name)
-            .ok_or_else(|| anyhow::anyhow!("Unknown provider: {}", lead_provider_name))?
-            .constructor
-            .clone()
-    };
+    let constructor = get_from_registry(&lead_provider_name)
+        .await?
+        .constructor
+        .clone();
+    let lead = constructor(lead_model_config(settings)?).await?;
+    let lead = with_configured_rate_limit(with_configured_pii_scrubbing(
+        with_registered_middleware(lead),
+    ));
+    Ok(Arc::new(settings.wrap(with_configured_cache(lead), worker)))
+}
 
-    let worker_constructor = {
-        let guard = registry.read().unwrap();
-        guard
-            .entries
-            .get(default_provider_name)
-            .ok_or_else(|| anyhow::anyhow!("Unknown provider: {}", default_provider_name))?
-            .constructor
-            .clone()
-    };
+/// Parse a `GOOSE_LOAD_BALANCE_PROVIDERS` value such as `openai-east:2,openai-west`
+/// into `(provider name, weight)` pairs. Weights default to 1. Every endpoint has to be allowed
+/// by `remote`.
+fn parse_load_balance_endpoints(
+    spec: &str,
+    remote: Option<&RemoteConfig>,
+) -> Result<Vec<(String, usize)>> {
+    let endpoints = spec
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.split_once(':') {
+            Some((name, weight)) => weight
+                .trim()
+                .parse::<usize>()
+                .map(|weight| (name.trim().to_string(), weight))
+                .map_err(|_| anyhow::anyhow!("Invalid load balance weight in '{}'", entry)),
+            None => Ok((entry.to_string(), 1)),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if endpoints.is_empty() {
+        return Err(anyhow::anyhow!("GOOSE_LOAD_BALANCE_PROVIDERS is empty"));
+    }
+    for (name, _) in &endpoints {
+        check_provider_allowed_by(remote, name)?;
+    }
+    Ok(endpoints)
+}
+
+async fn create_load_balanced_from_env(
+    model: &ModelConfig,
+    endpoints_spec: &str,
+) -> Result<Arc<dyn Provider>> {
+    let config = crate::config::Config::global();
 
-    let lead_provider = lead_constructor(lead_model_config).await?;
-    let worker_provider = worker_constructor(worker_model_config).await?;
+    let failure_threshold = config
+        .get_param::<usize>("GOOSE_LOAD_BALANCE_FAILURE_THRESHOLD")
+        .unwrap_or(DEFAULT_LOAD_BALANCE_FAILURE_THRESHOLD);
+    let cooldown_secs = config
+        .get_param::<u64>("GOOSE_LOAD_BALANCE_COOLDOWN_SECS")
+        .unwrap_or(DEFAULT_LOAD_BALANCE_COOLDOWN_SECS);
+
+    let mut endpoints = Vec::new();
+    let remote = crate::config::remote::active();
+    for (name, weight) in parse_load_balance_endpoints(endpoints_spec, remote.as_deref())? {
+        let constructor = get_from_registry(&name).await?.constructor.clone();
+        let provider = constructor(model.clone()).await?;
+        endpoints.push((name, provider, weight));
+    }
 
-    Ok(Arc::new(LeadWorkerProvider::new_with_settings(
-        lead_provider,
-        worker_provider,
-        lead_turns,
+    Ok(Arc::new(LoadBalancedProvider::new_with_settings(
+        endpoints,
         failure_threshold,
-        fallback_turns,
-    )))
+        std::time::Duration::from_secs(cooldown_secs),
+    )?))
 }
 
 fn create_worker_model_config(default_model: &ModelConfig) -> Result<ModelConfig> {
@@ -222,14 +300,11 @@ fn create_worker_model_config(default_model: &ModelConfig) -> Result<ModelConfig
 
     let global_config = crate::config::Config::global();
 
-    if let Ok(limit_str) = global_config.get_param::<String>("GOOSE_WORKER_CONTEXT_LIMIT") {
-        if let Ok(limit) = limit_str.parse::<usize>() {
-            worker_config = worker_config.with_context_limit(Some(limit));
-        }
-    } else if let Ok(limit_str) = global_config.get_param::<String>("GOOSE_CONTEXT_LIMIT") {
-        if let Ok(limit) = limit_str.parse::<usize>() {
-            worker_config = worker_config.with_context_limit(Some(limit));
-        }
+    let limit = global_config
+        .get_param::<usize>("GOOSE_WORKER_CONTEXT_LIMIT")
+        .or_else(|_| global_config.get_param::<usize>("GOOSE_CONTEXT_LIMIT"));
+    if let Ok(limit) = limit {
+        worker_config = worker_config.with_context_limit(Some(limit));
     }
 
     Ok(worker_config)
@@ -360,26 +435,62 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_worker_model_preserves_original_context_limit() {
+    #[tokio::test]
+    async fn test_worker_model_preserves_original_context_limit() {
         let _guard = EnvVarGuard::new(&[
-            "GOOSE_LEAD_MODEL",
+            "OPENAI_API_KEY",
             "GOOSE_WORKER_CONTEXT_LIMIT",
             "GOOSE_CONTEXT_LIMIT",
         ]);
 
-        _guard.set("GOOSE_LEAD_MODEL", "gpt-4o");
+        _guard.set("OPENAI_API_KEY", "fake-openai-no-keyring");
 
         let default_model =
             ModelConfig::new_or_fail("gpt-3.5-turbo").with_context_limit(Some(16_000));
+        let settings = LeadWorkerSettings::new("gpt-4o");
 
-        let _result = create_lead_worker_from_env("openai", &default_model, "gpt-4o");
+        let provider = create_lead_worker_from_env("openai", &default_model, &settings)
+            .await
+            .unwrap();
+        let (lead_model, worker_model) = provider.as_lead_worker().unwrap().get_model_info();
+        assert_eq!(lead_model, "gpt-4o");
+        assert_eq!(worker_model, "gpt-3.5-turbo");
+        let worker_config = create_worker_model_config(&default_model).unwrap();
+        assert_eq!(worker_config.context_limit, Some(16_000));
 
         _guard.set("GOOSE_WORKER_CONTEXT_LIMIT", "32000");
-        let _result = create_lead_worker_from_env("openai", &default_model, "gpt-4o");
+        let worker_config = create_worker_model_config(&default_model).unwrap();
+        assert_eq!(worker_config.context_limit, Some(32_000));
 
+        // The worker-specific limit wins over the general one
         _guard.set("GOOSE_CONTEXT_LIMIT", "64000");
-        let _result = create_lead_worker_from_env("openai", &default_model, "gpt-4o");
+        let worker_config = create_worker_model_config(&default_model).unwrap();
+        assert_eq!(worker_config.context_limit, Some(32_000));
+    }
+
+    #[test]
+    fn test_parse_load_balance_endpoints() {
+        assert_eq!(
+            parse_load_balance_endpoints("openai-east:2, openai-west", None).unwrap(),
+            vec![
+                ("openai-east".to_string(), 2),
+                ("openai-west".to_string(), 1)
+            ]
+        );
+        assert!(parse_load_balance_endpoints("openai:heavy", None).is_err());
+        assert!(parse_load_balance_endpoints(" , ", None).is_err());
+    }
+
+    #[test]
+    fn test_load_balance_endpoints_must_be_allowed() {
+        let remote = RemoteConfig {
+            allowed_providers: vec!["openai-east".to_string(), "openai-west".to_string()],
+            ..Default::default()
+        };
+        assert!(parse_load_balance_endpoints("openai-east:2,openai-west", Some(&remote)).is_ok());
+
+        let error = parse_load_balance_endpoints("openai-east,ollama", Some(&remote)).unwrap_err();
+        assert!(error.to_string().contains("ollama"));
     }
 
     #[tokio::test]
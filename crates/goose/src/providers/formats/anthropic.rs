@@ -1,8 +1,8 @@
-use crate::conversation::message::{Message, MessageContent};
-use crate::model::ModelConfig;
+use crate::conversation::message::{Citation, DocumentContent, Message, MessageContent};
+use crate::model::{ModelConfig, ToolChoice};
 use crate::providers::base::Usage;
 use crate::providers::errors::ProviderError;
-use crate::providers::utils::{convert_image, ImageFormat};
+use crate::providers::utils::{audio_placeholder, convert_image, document_to_text, ImageFormat};
 use anyhow::{anyhow, Result};
 use rmcp::model::{object, CallToolRequestParam, ErrorCode, ErrorData, JsonObject, Role, Tool};
 use rmcp::object as json_object;
@@ -21,6 +21,7 @@ const TOOL_USE_TYPE: &str = "tool_use";
 const TOOL_RESULT_TYPE: &str = "tool_result";
 const THINKING_TYPE: &str = "thinking";
 const REDACTED_THINKING_TYPE: &str = "redacted_thinking";
+const DOCUMENT_TYPE: &str = "document";
 const CACHE_CONTROL_FIELD: &str = "cache_control";
 const ID_FIELD: &str = "id";
 const NAME_FIELD: &str = "name";
@@ -29,6 +30,9 @@ const TOOL_USE_ID_FIELD: &str = "tool_use_id";
 const IS_ERROR_FIELD: &str = "is_error";
 const SIGNATURE_FIELD: &str = "signature";
 const DATA_FIELD: &str = "data";
+const SERVER_TOOL_USE_TYPE: &str = "server_tool_use";
+const CITATIONS_FIELD: &str = "citations";
+const WEB_SEARCH_TOOL_TYPE: &str = "web_search_20250305";
 
 /// Convert internal Message format to Anthropic's API message specification
 pub fn format_messages(messages: &[Message]) -> Vec<Value> {
@@ -98,6 +102,8 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                 MessageContent::SystemNotification(_) => {
                     // Skip
                 }
+                // Thinking without a signature came from another provider and can't be replayed
+                MessageContent::Thinking(thinking) if thinking.signature.is_empty() => {}
                 MessageContent::Thinking(thinking) => {
                     content.push(json!({
                         TYPE_FIELD: THINKING_TYPE,
@@ -114,6 +120,15 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                 MessageContent::Image(image) => {
                     content.push(convert_image(image, &ImageFormat::Anthropic));
                 }
+                MessageContent::Document(document) => {
+                    content.push(format_document(document));
+                }
+                MessageContent::Audio(audio) => {
+                    content.push(json!({
+                        TYPE_FIELD: TEXT_TYPE,
+                        TEXT_TYPE: audio_placeholder(audio)
+                    }));
+                }
                 MessageContent::FrontendToolRequest(tool_request) => {
                     if let Ok(tool_call) = &tool_request.tool_call {
                         content.push(json!({
@@ -174,6 +189,38 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
     anthropic_messages
 }
 
+/// Convert a document to an Anthropic document block. PDFs are sent as base64 and text
+/// documents as plain text; anything else falls back to a text block.
+fn format_document(document: &DocumentContent) -> Value {
+    let source = if document.is_pdf() {
+        json!({
+            TYPE_FIELD: "base64",
+            "media_type": document.mime_type,
+            DATA_FIELD: document.data,
+        })
+    } else if let Some(text) = document.as_text() {
+        json!({
+            TYPE_FIELD: TEXT_TYPE,
+            "media_type": "text/plain",
+            DATA_FIELD: text,
+        })
+    } else {
+        return json!({
+            TYPE_FIELD: TEXT_TYPE,
+            TEXT_TYPE: document_to_text(document),
+        });
+    };
+
+    let mut block = json!({
+        TYPE_FIELD: DOCUMENT_TYPE,
+        "source": source,
+    });
+    if let Some(name) = &document.name {
+        block["title"] = json!(name);
+    }
+    block
+}
+
 fn anthropic_flavored_input_schema(input_schema: Arc<JsonObject>) -> Arc<JsonObject> {
     if input_schema.is_empty() {
         return Arc::new(json_object!({
@@ -210,6 +257,74 @@ pub fn format_tools(tools: &[Tool]) -> Vec<Value> {
     tool_specs
 }
 
+/// Spec for Anthropic's server-side web search tool, optionally capping the number of searches
+/// per request
+pub fn web_search_tool_spec(max_uses: Option<u32>) -> Value {
+    let mut spec = json!({
+        TYPE_FIELD: WEB_SEARCH_TOOL_TYPE,
+        NAME_FIELD: "web_search",
+    });
+    if let Some(max_uses) = max_uses {
+        spec["max_uses"] = json!(max_uses);
+    }
+    spec
+}
+
+/// Append a server-side tool spec to the request's tools
+pub fn add_server_tool(payload: &mut Value, spec: Value) {
+    let tools = payload
+        .as_object_mut()
+        .unwrap()
+        .entry("tools")
+        .or_insert_with(|| json!([]));
+    if let Some(tools) = tools.as_array_mut() {
+        tools.push(spec);
+    }
+}
+
+/// Summarize a server-side tool call. The call runs on Anthropic's side, so it is surfaced as
+/// annotated text rather than a tool request for the agent to dispatch.
+fn server_tool_use_content(id: &str, name: &str, input: &Value) -> MessageContent {
+    let summary = match input.get("query").and_then(|q| q.as_str()) {
+        Some(query) => format!("[{}] Searched the web for \"{}\"", name, query),
+        None => format!("[{}] Called built-in tool", name),
+    };
+    MessageContent::builtin_tool_call(
+        summary,
+        json!({
+            TYPE_FIELD: SERVER_TOOL_USE_TYPE,
+            ID_FIELD: id,
+            NAME_FIELD: name,
+            INPUT_FIELD: input,
+        }),
+    )
+}
+
+/// Parse a `web_search_result_location` citation
+fn parse_citation(citation: &Value) -> Option<Citation> {
+    Some(Citation {
+        url: citation.get("url")?.as_str()?.to_string(),
+        title: citation
+            .get("title")
+            .and_then(|t| t.as_str())
+            .map(String::from),
+        cited_text: citation
+            .get("cited_text")
+            .and_then(|t| t.as_str())
+            .map(String::from),
+    })
+}
+
+/// Convert a ToolChoice to Anthropic's API tool_choice specification
+pub fn format_tool_choice(tool_choice: &ToolChoice) -> Value {
+    match tool_choice {
+        ToolChoice::Auto => json!({ TYPE_FIELD: "auto" }),
+        ToolChoice::Required => json!({ TYPE_FIELD: "any" }),
+        ToolChoice::None => json!({ TYPE_FIELD: "none" }),
+        ToolChoice::Tool(name) => json!({ TYPE_FIELD: "tool", NAME_FIELD: name }),
+    }
+}
+
 /// Convert system message to Anthropic's API system specification
 pub fn format_system(system: &str) -> Value {
     json!([{
@@ -227,6 +342,7 @@ pub fn response_to_message(response: &Value) -> Result<Message> {
         .ok_or_else(|| anyhow!("Invalid response format: missing content array"))?;
 
     let mut message = Message::assistant();
+    let mut citations = Vec::new();
 
     for block in content_blocks {
         match block.get(TYPE_FIELD).and_then(|t| t.as_str()) {
@@ -234,6 +350,19 @@ pub fn response_to_message(response: &Value) -> Result<Message> {
                 if let Some(text) = block.get(TEXT_TYPE).and_then(|t| t.as_str()) {
                     message = message.with_text(text.to_string());
                 }
+                if let Some(block_citations) = block.get(CITATIONS_FIELD).and_then(|c| c.as_array())
+                {
+                    citations.extend(block_citations.iter().filter_map(parse_citation));
+                }
+            }
+            Some(SERVER_TOOL_USE_TYPE) => {
+                let id = block.get(ID_FIELD).and_then(|i| i.as_str()).unwrap_or("");
+                let name = block
+                    .get(NAME_FIELD)
+                    .and_then(|n| n.as_str())
+                    .unwrap_or("server_tool");
+                let input = block.get(INPUT_FIELD).cloned().unwrap_or(json!({}));
+                message = message.with_content(server_tool_use_content(id, name, &input));
             }
             Some(TOOL_USE_TYPE) => {
                 let id = block
@@ -278,6 +407,10 @@ pub fn response_to_message(response: &Value) -> Result<Message> {
         }
     }
 
+    if !citations.is_empty() {
+        message = message.with_content(MessageContent::citations(citations));
+    }
+
     Ok(message)
 }
 
@@ -320,7 +453,8 @@ pub fn get_usage(data: &Value) -> Result<Usage> {
             Some(total_input_i32),
             Some(output_tokens_i32),
             Some(total_tokens_i32),
-        ))
+        )
+        .with_cached_input_tokens(Some(cache_read_tokens.min(i32::MAX as u64) as i32)))
     } else if data.as_object().is_some() {
         // Check if the data itself is the usage object (for message_delta events that might have usage at top level)
         let input_tokens = data
@@ -363,7 +497,8 @@ pub fn get_usage(data: &Value) -> Result<Usage> {
                 Some(total_input_i32),
                 Some(output_tokens_i32),
                 Some(total_tokens_i32),
-            ))
+            )
+            .with_cached_input_tokens(Some(cache_read_tokens.min(i32::MAX as u64) as i32)))
         } else {
             tracing::debug!("🔍 Anthropic no token data found in object");
             Ok(Usage::new(None, None, None))
@@ -396,7 +531,7 @@ pub fn create_request(
 
     // https://docs.anthropic.com/en/docs/about-claude/models/all-models#model-comparison-table
     // Claude 3.7 supports max output tokens up to 8192
-    let max_tokens = model_config.max_tokens.unwrap_or(8192);
+    let max_tokens = model_config.max_output_tokens(8192);
     let mut payload = json!({
         "model": model_config.model_name,
         "messages": anthropic_messages,
@@ -417,12 +552,54 @@ pub fn create_request(
             .as_object_mut()
             .unwrap()
             .insert("tools".to_string(), json!(tool_specs));
+
+        // Anthropic expresses parallel tool use as a flag on tool_choice, so a
+        // sequential request needs an explicit tool_choice even when none was set
+        let tool_choice = match (
+            model_config.tool_choice_for_call(),
+            model_config.parallel_tool_calls,
+        ) {
+            (Some(tool_choice), _) => Some(tool_choice),
+            (None, Some(false)) => Some(ToolChoice::Auto),
+            (None, _) => None,
+        };
+        if let Some(tool_choice) = tool_choice {
+            let mut tool_choice_spec = format_tool_choice(&tool_choice);
+            if model_config.parallel_tool_calls == Some(false) && tool_choice != ToolChoice::None {
+                tool_choice_spec
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("disable_parallel_tool_use".to_string(), json!(true));
+            }
+            payload
+                .as_object_mut()
+                .unwrap()
+                .insert("tool_choice".to_string(), tool_choice_spec);
+        }
     }
 
-    // Add temperature if specified and not using extended thinking model
+    // Extended thinking is enabled by a configured budget, or for claude-3-7-sonnet
+    // through the legacy CLAUDE_THINKING_ENABLED environment variable
+    let is_legacy_thinking_enabled = model_config.model_name.starts_with("claude-3-7-sonnet-")
+        && std::env::var("CLAUDE_THINKING_ENABLED").is_ok();
+    let thinking_budget = model_config
+        .thinking_budget_tokens
+        .filter(|budget| *budget > 0)
+        .or_else(|| {
+            is_legacy_thinking_enabled.then(|| {
+                std::env::var("CLAUDE_THINKING_BUDGET")
+                    .unwrap_or_else(|_| "16000".to_string())
+                    .parse()
+                    .unwrap_or(16000)
+            })
+        })
+        // Minimum budget_tokens is 1024
+        .map(|budget: i32| budget.max(1024));
+
+    // Add temperature if specified and not using extended thinking
     if let Some(temp) = model_config.temperature {
         // Claude 3.7 models with thinking enabled don't support temperature
-        if !model_config.model_name.starts_with("claude-3-7-sonnet-") {
+        if !model_config.model_name.starts_with("claude-3-7-sonnet-") && thinking_budget.is_none() {
             payload
                 .as_object_mut()
                 .unwrap()
@@ -430,15 +607,7 @@ pub fn create_request(
         }
     }
 
-    // Add thinking parameters for claude-3-7-sonnet model
-    let is_thinking_enabled = std::env::var("CLAUDE_THINKING_ENABLED").is_ok();
-    if model_config.model_name.starts_with("claude-3-7-sonnet-") && is_thinking_enabled {
-        // Minimum budget_tokens is 1024
-        let budget_tokens = std::env::var("CLAUDE_THINKING_BUDGET")
-            .unwrap_or_else(|_| "16000".to_string())
-            .parse()
-            .unwrap_or(16000);
-
+    if let Some(budget_tokens) = thinking_budget {
         payload
             .as_object_mut()
             .unwrap()
@@ -456,6 +625,24 @@ pub fn create_request(
     Ok(payload)
 }
 
+/// Create a request for the `/v1/messages/count_tokens` endpoint, which accepts the
+/// same input as a message request but none of the sampling parameters
+pub fn create_count_tokens_request(
+    model_config: &ModelConfig,
+    system: &str,
+    messages: &[Message],
+    tools: &[Tool],
+) -> Result<Value> {
+    let mut payload = create_request(model_config, system, messages, tools)?;
+    payload.as_object_mut().unwrap().retain(|key, _| {
+        matches!(
+            key.as_str(),
+            "model" | "messages" | "system" | "tools" | "tool_choice" | "thinking"
+        )
+    });
+    Ok(payload)
+}
+
 /// Process streaming response from Anthropic's API
 pub fn response_to_streaming_message<S>(
     mut stream: S,
@@ -484,7 +671,10 @@ where
         let mut accumulated_text = String::new();
         let mut accumulated_tool_calls: std::collections::HashMap<String, (String, String)> = std::collections::HashMap::new();
         let mut current_tool_id: Option<String> = None;
+        let mut current_server_tool: Option<(String, String, String)> = None;
+        let mut citations = Vec::new();
         let mut final_usage: Option<crate::providers::base::ProviderUsage> = None;
+        let mut meter: Option<crate::providers::usage_estimator::UsageMeter> = None;
         let mut message_id: Option<String> = None;
 
         while let Some(line_result) = stream.next().await {
@@ -528,6 +718,9 @@ where
                                 .and_then(|v| v.as_str())
                                 .unwrap_or("unknown")
                                 .to_string();
+                            let mut usage_meter = crate::providers::usage_estimator::UsageMeter::new(&model).await;
+                            usage_meter.set_input_tokens(usage.input_tokens);
+                            meter = Some(usage_meter);
                             final_usage = Some(crate::providers::base::ProviderUsage::new(model, usage));
                         } else {
                             tracing::debug!("🔍 Anthropic message_start has no usage data");
@@ -545,6 +738,10 @@ where
                                     accumulated_tool_calls.insert(id.to_string(), (name.to_string(), String::new()));
                                 }
                             }
+                        } else if content_block.get("type") == Some(&json!(SERVER_TOOL_USE_TYPE)) {
+                            let id = content_block.get("id").and_then(|v| v.as_str()).unwrap_or("");
+                            let name = content_block.get("name").and_then(|v| v.as_str()).unwrap_or("server_tool");
+                            current_server_tool = Some((id.to_string(), name.to_string(), String::new()));
                         }
                     }
                     continue;
@@ -564,15 +761,32 @@ where
                                 );
                                 message.id = message_id.clone();
                                 yield (Some(message), None);
+
+                                if let Some(usage) = meter.as_mut().and_then(|m| m.record_output(text)) {
+                                    yield (None, Some(usage));
+                                }
                             }
                         } else if delta.get("type") == Some(&json!("input_json_delta")) {
                             // Tool input delta
+                            let partial_json = delta.get("partial_json").and_then(|v| v.as_str());
                             if let Some(tool_id) = &current_tool_id {
-                                if let Some(partial_json) = delta.get("partial_json").and_then(|v| v.as_str()) {
+                                if let Some(partial_json) = partial_json {
                                     if let Some((_name, args)) = accumulated_tool_calls.get_mut(tool_id) {
                                         args.push_str(partial_json);
                                     }
                                 }
+                            } else if let Some((_, _, args)) = current_server_tool.as_mut() {
+                                if let Some(partial_json) = partial_json {
+                                    args.push_str(partial_json);
+                                }
+                            }
+                            if let Some(usage) = partial_json.and_then(|json| meter.as_mut()?.record_output(json)) {
+                                yield (None, Some(usage));
+                            }
+                        } else if delta.get("type") == Some(&json!("citations_delta")) {
+                            // Citations are collected and listed once the message is complete
+                            if let Some(citation) = delta.get("citation").and_then(parse_citation) {
+                                citations.push(citation);
                             }
                         }
                     }
@@ -580,6 +794,17 @@ where
                 }
                 "content_block_stop" => {
                     // Content block finished
+                    if let Some((id, name, args)) = current_server_tool.take() {
+                        let input = serde_json::from_str::<Value>(&args).unwrap_or(json!({}));
+                        let mut message = Message::new(
+                            Role::Assistant,
+                            chrono::Utc::now().timestamp(),
+                            vec![server_tool_use_content(&id, &name, &input)],
+                        );
+                        message.id = message_id.clone();
+                        yield (Some(message), None);
+                        continue;
+                    }
                     if let Some(tool_id) = current_tool_id.take() {
                         // Tool call finished, yield complete tool call
                         if let Some((name, args)) = accumulated_tool_calls.remove(&tool_id) {
@@ -685,6 +910,16 @@ where
             }
         }
 
+        if !citations.is_empty() {
+            let mut message = Message::new(
+                Role::Assistant,
+                chrono::Utc::now().timestamp(),
+                vec![MessageContent::citations(citations)],
+            );
+            message.id = message_id.clone();
+            yield (Some(message), None);
+        }
+
         // Yield final usage information if available
         if let Some(usage) = final_usage {
             yield (None, Some(usage));
@@ -875,6 +1110,30 @@ mod tests {
         assert_eq!(spec[2]["content"][0]["text"], "How are you?");
     }
 
+    #[test]
+    fn test_document_to_anthropic_spec() -> Result<()> {
+        let messages = vec![Message::user()
+            .with_text("Summarize these")
+            .with_content(MessageContent::document(
+                "JVBERi0xLjc=",
+                "application/pdf",
+                Some("report.pdf".to_string()),
+            )?)
+            .with_content(MessageContent::document("aGVsbG8=", "text/plain", None)?)];
+
+        let spec = format_messages(&messages);
+        let content = &spec[0]["content"];
+
+        assert_eq!(content[1]["type"], "document");
+        assert_eq!(content[1]["title"], "report.pdf");
+        assert_eq!(content[1]["source"]["type"], "base64");
+        assert_eq!(content[1]["source"]["media_type"], "application/pdf");
+        assert_eq!(content[2]["source"]["type"], "text");
+        assert_eq!(content[2]["source"]["data"], "hello");
+
+        Ok(())
+    }
+
     #[test]
     fn test_tools_to_anthropic_spec() {
         let tools = vec![
@@ -931,6 +1190,79 @@ mod tests {
         assert!(spec_array[0].get("cache_control").is_some());
     }
 
+    #[test]
+    fn test_create_request_with_tool_choice() -> Result<()> {
+        let tools = vec![Tool::new(
+            "weather",
+            "Get weather information",
+            object!({"type": "object"}),
+        )];
+        let messages = vec![Message::user().with_text("Hello")];
+
+        let model_config = ModelConfig::new_or_fail("claude-sonnet-4-5")
+            .with_tool_choice(Some(ToolChoice::Tool("weather".to_string())));
+        let payload = create_request(&model_config, "system", &messages, &tools)?;
+        assert_eq!(
+            payload["tool_choice"],
+            json!({"type": "tool", "name": "weather"})
+        );
+
+        let model_config = model_config.with_tool_choice(Some(ToolChoice::Required));
+        let payload = create_request(&model_config, "system", &messages, &tools)?;
+        assert_eq!(payload["tool_choice"], json!({"type": "any"}));
+
+        let model_config = model_config.with_tool_choice(None);
+        let payload = create_request(&model_config, "system", &messages, &tools)?;
+        assert!(payload.get("tool_choice").is_none());
+
+        let model_config = model_config.with_parallel_tool_calls(Some(false));
+        let payload = create_request(&model_config, "system", &messages, &tools)?;
+        assert_eq!(
+            payload["tool_choice"],
+            json!({"type": "auto", "disable_parallel_tool_use": true})
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request_with_thinking_budget() -> Result<()> {
+        let model_config = ModelConfig::new_or_fail("claude-sonnet-4-5")
+            .with_temperature(Some(0.5))
+            .with_max_tokens(Some(4096))
+            .with_thinking_budget_tokens(Some(2048));
+        let messages = vec![Message::user().with_text("Hello")];
+
+        let payload = create_request(&model_config, "system", &messages, &[])?;
+
+        assert_eq!(
+            payload["thinking"],
+            json!({"type": "enabled", "budget_tokens": 2048})
+        );
+        assert_eq!(payload["max_tokens"], 4096 + 2048);
+        assert!(payload.get("temperature").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_count_tokens_request() -> Result<()> {
+        let model_config = ModelConfig::new_or_fail("claude-sonnet-4-5")
+            .with_temperature(Some(0.5))
+            .with_max_tokens(Some(4096));
+        let messages = vec![Message::user().with_text("Hello")];
+
+        let payload = create_count_tokens_request(&model_config, "system", &messages, &[])?;
+
+        assert_eq!(payload["model"], "claude-sonnet-4-5");
+        assert!(payload.get("messages").is_some());
+        assert!(payload.get("system").is_some());
+        assert!(payload.get("max_tokens").is_none());
+        assert!(payload.get("temperature").is_none());
+
+        Ok(())
+    }
+
     #[test]
     fn test_create_request_with_thinking() -> Result<()> {
         let original_value = std::env::var("CLAUDE_THINKING_ENABLED").ok();
@@ -1044,4 +1376,147 @@ mod tests {
         );
         assert_eq!(spec[1]["content"][0]["is_error"], true);
     }
+
+    #[test]
+    fn test_parse_web_search_response() -> Result<()> {
+        let response = json!({
+            "id": "msg_123",
+            "type": "message",
+            "role": "assistant",
+            "content": [
+                {
+                    "type": "server_tool_use",
+                    "id": "srvtoolu_1",
+                    "name": "web_search",
+                    "input": {"query": "goose agent"}
+                },
+                {
+                    "type": "web_search_tool_result",
+                    "tool_use_id": "srvtoolu_1",
+                    "content": [{
+                        "type": "web_search_result",
+                        "url": "https://example.com/goose",
+                        "title": "Goose",
+                        "encrypted_content": "abc"
+                    }]
+                },
+                {
+                    "type": "text",
+                    "text": "Goose is an open source agent.",
+                    "citations": [{
+                        "type": "web_search_result_location",
+                        "url": "https://example.com/goose",
+                        "title": "Goose",
+                        "encrypted_index": "xyz",
+                        "cited_text": "Goose is an open source AI agent"
+                    }]
+                }
+            ],
+            "model": "claude-sonnet-4-5",
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 10, "output_tokens": 5}
+        });
+
+        let message = response_to_message(&response)?;
+        assert_eq!(message.content.len(), 3);
+        assert_eq!(
+            message.content[0].as_text(),
+            Some("[web_search] Searched the web for \"goose agent\"")
+        );
+        assert_eq!(
+            message.content[1].as_text(),
+            Some("Goose is an open source agent.")
+        );
+
+        let MessageContent::Text(sources) = &message.content[2] else {
+            panic!("Expected citations text content");
+        };
+        assert_eq!(
+            sources.text,
+            "\n\nSources:\n1. [Goose](https://example.com/goose)"
+        );
+        let citations = &sources.raw.meta.as_ref().unwrap().0["citations"];
+        assert_eq!(
+            citations[0]["citedText"],
+            "Goose is an open source AI agent"
+        );
+
+        let mut payload = json!({"tools": [{"name": "shell"}]});
+        add_server_tool(&mut payload, web_search_tool_spec(Some(3)));
+        assert_eq!(payload["tools"][1]["type"], "web_search_20250305");
+        assert_eq!(payload["tools"][1]["max_uses"], 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_streamed_citations_survive_into_conversation() {
+        use crate::conversation::Conversation;
+        use crate::providers::base::MessageStream;
+        use crate::providers::errors::ProviderError;
+        use crate::providers::stream_event::{into_events, into_message_stream};
+        use futures::TryStreamExt;
+
+        let events = [
+            json!({"type": "message_start", "message": {"id": "msg_1"}}),
+            json!({"type": "content_block_start", "index": 0, "content_block": {
+                "type": "server_tool_use", "id": "srvtoolu_1", "name": "web_search"
+            }}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {
+                "type": "input_json_delta", "partial_json": "{\"query\": \"goose agent\"}"
+            }}),
+            json!({"type": "content_block_stop", "index": 0}),
+            json!({"type": "content_block_start", "index": 1, "content_block": {"type": "text", "text": ""}}),
+            json!({"type": "content_block_delta", "index": 1, "delta": {
+                "type": "text_delta", "text": "Goose is an open source agent."
+            }}),
+            json!({"type": "content_block_delta", "index": 1, "delta": {
+                "type": "citations_delta", "citation": {
+                    "type": "web_search_result_location",
+                    "url": "https://example.com/goose",
+                    "title": "Goose",
+                    "cited_text": "Goose is an open source AI agent"
+                }
+            }}),
+            json!({"type": "content_block_stop", "index": 1}),
+            json!({"type": "message_stop"}),
+        ];
+        let lines: Vec<anyhow::Result<String>> = events
+            .iter()
+            .map(|event| Ok(format!("data: {}", event)))
+            .collect();
+        let stream: MessageStream = Box::pin(
+            response_to_streaming_message(futures::stream::iter(lines))
+                .map_err(|e| ProviderError::RequestFailed(e.to_string())),
+        );
+        let chunks: Vec<_> = into_message_stream(into_events(stream))
+            .try_collect()
+            .await
+            .unwrap();
+
+        let mut conversation = Conversation::empty();
+        for message in chunks.into_iter().filter_map(|(message, _)| message) {
+            conversation.push(message);
+        }
+
+        assert_eq!(conversation.len(), 1);
+        let content = &conversation.messages()[0].content;
+        assert_eq!(content.len(), 3);
+        let MessageContent::Text(summary) = &content[0] else {
+            panic!("Expected built-in tool summary");
+        };
+        assert_eq!(
+            summary.raw.meta.as_ref().unwrap().0["builtin_tool_call"]["id"],
+            "srvtoolu_1"
+        );
+        assert_eq!(content[1].as_text(), Some("Goose is an open source agent."));
+        let MessageContent::Text(sources) = &content[2] else {
+            panic!("Expected citations text content");
+        };
+        assert!(sources.text.starts_with("\n\nSources:"));
+        assert_eq!(
+            sources.raw.meta.as_ref().unwrap().0["citations"][0]["url"],
+            "https://example.com/goose"
+        );
+    }
 }
@@ -15,7 +15,9 @@ use rmcp::model::{
 use serde_json::Value;
 
 use super::super::base::Usage;
-use crate::conversation::message::{Message, MessageContent};
+use crate::conversation::message::{DocumentContent, Message, MessageContent};
+use crate::model::ToolChoice;
+use crate::providers::utils::audio_placeholder;
 
 /// Accumulates streaming chunks into a complete message
 #[derive(Debug, Default)]
@@ -192,6 +194,10 @@ pub fn to_bedrock_message_content(content: &MessageContent) -> Result<bedrock::C
         MessageContent::Image(image) => {
             bedrock::ContentBlock::Image(to_bedrock_image(&image.data, &image.mime_type)?)
         }
+        MessageContent::Document(document) => {
+            bedrock::ContentBlock::Document(to_bedrock_message_document(document)?)
+        }
+        MessageContent::Audio(audio) => bedrock::ContentBlock::Text(audio_placeholder(audio)),
         MessageContent::Thinking(_) => bedrock::ContentBlock::Text("".to_string()),
         MessageContent::RedactedThinking(_) => bedrock::ContentBlock::Text("".to_string()),
         MessageContent::SystemNotification(_) => {
@@ -322,12 +328,102 @@ pub fn to_bedrock_image(data: &String, mime_type: &String) -> Result<bedrock::Im
         .build()?)
 }
 
-pub fn to_bedrock_tool_config(tools: &[Tool]) -> Result<bedrock::ToolConfiguration> {
-    Ok(bedrock::ToolConfiguration::builder()
-        .set_tools(Some(
-            tools.iter().map(to_bedrock_tool).collect::<Result<_>>()?,
-        ))
-        .build()?)
+pub fn to_bedrock_message_document(document: &DocumentContent) -> Result<bedrock::DocumentBlock> {
+    let format = match document.mime_type.as_str() {
+        "application/pdf" => bedrock::DocumentFormat::Pdf,
+        "text/plain" => bedrock::DocumentFormat::Txt,
+        "text/markdown" => bedrock::DocumentFormat::Md,
+        "text/csv" => bedrock::DocumentFormat::Csv,
+        "text/html" => bedrock::DocumentFormat::Html,
+        _ => bail!(
+            "Unsupported document format: {}. Bedrock supports pdf, txt, md, csv, html",
+            document.mime_type
+        ),
+    };
+
+    let bytes = base64::prelude::BASE64_STANDARD
+        .decode(&document.data)
+        .map_err(|e| anyhow!("Failed to decode base64 document data: {}", e))?;
+
+    bedrock::DocumentBlock::builder()
+        .format(format)
+        .name(to_bedrock_document_name(document.name.as_deref()))
+        .source(bedrock::DocumentSource::Bytes(aws_smithy_types::Blob::new(
+            bytes,
+        )))
+        .build()
+        .map_err(|err| anyhow!("Failed to construct Bedrock document: {}", err))
+}
+
+/// Bedrock document names may only contain alphanumerics, single spaces, hyphens, parentheses
+/// and square brackets, so the extension and any other characters are dropped
+fn to_bedrock_document_name(name: Option<&str>) -> String {
+    let stem = name
+        .map(|n| {
+            Path::new(n)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or(n)
+        })
+        .unwrap_or("");
+    let sanitized = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '(' | ')' | '[' | ']') {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if sanitized.is_empty() {
+        "document".to_string()
+    } else {
+        sanitized
+    }
+}
+
+/// The tool config of a request, if it should have one. Bedrock has no way to disable tool use
+/// while tools are configured, so tools are left out for `ToolChoice::None`.
+pub fn to_bedrock_tool_config(
+    tools: &[Tool],
+    tool_choice: Option<&ToolChoice>,
+) -> Result<Option<bedrock::ToolConfiguration>> {
+    if tools.is_empty() || tool_choice == Some(&ToolChoice::None) {
+        return Ok(None);
+    }
+    Ok(Some(
+        bedrock::ToolConfiguration::builder()
+            .set_tools(Some(
+                tools.iter().map(to_bedrock_tool).collect::<Result<_>>()?,
+            ))
+            .set_tool_choice(
+                tool_choice
+                    .map(to_bedrock_tool_choice)
+                    .transpose()?
+                    .flatten(),
+            )
+            .build()?,
+    ))
+}
+
+/// `None` has no Bedrock tool choice; [`to_bedrock_tool_config`] leaves the tools out instead
+pub fn to_bedrock_tool_choice(tool_choice: &ToolChoice) -> Result<Option<bedrock::ToolChoice>> {
+    Ok(match tool_choice {
+        ToolChoice::Auto => Some(bedrock::ToolChoice::Auto(
+            bedrock::AutoToolChoice::builder().build(),
+        )),
+        ToolChoice::Required => Some(bedrock::ToolChoice::Any(
+            bedrock::AnyToolChoice::builder().build(),
+        )),
+        ToolChoice::None => None,
+        ToolChoice::Tool(name) => Some(bedrock::ToolChoice::Tool(
+            bedrock::SpecificToolChoice::builder().name(name).build()?,
+        )),
+    })
 }
 
 pub fn to_bedrock_tool(tool: &Tool) -> Result<bedrock::Tool> {
@@ -548,6 +644,26 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_to_bedrock_tool_config_leaves_tools_out_for_tool_choice_none() -> Result<()> {
+        let tools = [Tool::new(
+            "weather",
+            "Get the weather",
+            rmcp::object!({"type": "object"}),
+        )];
+
+        let config = to_bedrock_tool_config(&tools, Some(&ToolChoice::Required))?.unwrap();
+        assert_eq!(config.tools().len(), 1);
+        assert!(matches!(
+            config.tool_choice(),
+            Some(bedrock::ToolChoice::Any(_))
+        ));
+
+        assert!(to_bedrock_tool_config(&tools, Some(&ToolChoice::None))?.is_none());
+        assert!(to_bedrock_tool_config(&[], Some(&ToolChoice::Auto))?.is_none());
+        Ok(())
+    }
+
     #[test]
     fn test_to_bedrock_image_unsupported_format() {
         let image = RawImageContent {
@@ -605,4 +721,24 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_to_bedrock_message_content_document() -> Result<()> {
+        let message_content = MessageContent::document(
+            "JVBERi0xLjc=",
+            "application/pdf",
+            Some("Q3 report_final.pdf".to_string()),
+        )?;
+        let result = to_bedrock_message_content(&message_content)?;
+
+        let bedrock::ContentBlock::Document(document) = result else {
+            panic!("Expected a document block");
+        };
+        assert_eq!(document.format(), &bedrock::DocumentFormat::Pdf);
+        assert_eq!(document.name(), "Q3 report final");
+
+        assert_eq!(to_bedrock_document_name(None), "document");
+
+        Ok(())
+    }
 }
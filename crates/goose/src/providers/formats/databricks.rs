@@ -1,9 +1,10 @@
 use crate::conversation::message::{Message, MessageContent};
 use crate::model::ModelConfig;
 use crate::providers::formats::google as gemini_schema;
+use crate::providers::formats::openai::{format_tool_choice, get_logprobs};
 use crate::providers::utils::{
-    convert_image, detect_image_path, is_valid_function_name, load_image_file, safely_parse_json,
-    sanitize_function_name, ImageFormat,
+    audio_placeholder, convert_image, detect_image_path, document_to_text, is_valid_function_name,
+    load_image_file, safely_parse_json, sanitize_function_name, ImageFormat,
 };
 use anyhow::{anyhow, Error};
 use rmcp::model::{
@@ -214,6 +215,18 @@ fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<Data
                 MessageContent::Image(image) => {
                     content_array.push(convert_image(image, image_format));
                 }
+                MessageContent::Document(document) => {
+                    content_array.push(json!({
+                        "type": "text",
+                        "text": document_to_text(document)
+                    }));
+                }
+                MessageContent::Audio(audio) => {
+                    content_array.push(json!({
+                        "type": "text",
+                        "text": audio_placeholder(audio)
+                    }));
+                }
                 MessageContent::FrontendToolRequest(req) => {
                     // Frontend tool requests are converted to text messages
                     if let Ok(tool_call) = &req.tool_call {
@@ -401,11 +414,11 @@ pub fn response_to_message(response: &Value) -> anyhow::Result<Message> {
         }
     }
 
-    Ok(Message::new(
-        Role::Assistant,
-        chrono::Utc::now().timestamp(),
-        content,
-    ))
+    let message = Message::new(Role::Assistant, chrono::Utc::now().timestamp(), content);
+    match get_logprobs(&response["choices"][0]) {
+        Some(logprobs) => Ok(message.with_logprobs(logprobs)),
+        None => Ok(message),
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -525,7 +538,12 @@ pub fn create_request(
             }
             _ => (
                 model_config.model_name.to_string(),
-                Some("medium".to_string()),
+                Some(
+                    model_config
+                        .reasoning_effort
+                        .map_or("medium", |effort| effort.as_str())
+                        .to_string(),
+                ),
             ),
         }
     } else {
@@ -570,6 +588,20 @@ pub fn create_request(
             .as_object_mut()
             .unwrap()
             .insert("tools".to_string(), json!(tools_spec));
+
+        if let Some(tool_choice) = &model_config.tool_choice_for_call() {
+            payload
+                .as_object_mut()
+                .unwrap()
+                .insert("tool_choice".to_string(), format_tool_choice(tool_choice));
+        }
+
+        if let Some(parallel_tool_calls) = model_config.parallel_tool_calls {
+            payload.as_object_mut().unwrap().insert(
+                "parallel_tool_calls".to_string(),
+                json!(parallel_tool_calls),
+            );
+        }
     }
 
     // Add thinking parameters for Claude 3.7 Sonnet model when requested
@@ -583,7 +615,7 @@ pub fn create_request(
 
         // For Claude models with thinking enabled, we need to add max_tokens + budget_tokens
         // Default to 8192 (Claude max output) + budget if not specified
-        let max_completion_tokens = model_config.max_tokens.unwrap_or(8192);
+        let max_completion_tokens = model_config.max_output_tokens(8192);
         payload.as_object_mut().unwrap().insert(
             "max_tokens".to_string(),
             json!(max_completion_tokens + budget_tokens),
@@ -613,6 +645,21 @@ pub fn create_request(
             }
         }
 
+        if let Some(seed) = model_config.seed {
+            payload
+                .as_object_mut()
+                .unwrap()
+                .insert("seed".to_string(), json!(seed));
+        }
+
+        if let Some(top_logprobs) = model_config.logprobs {
+            let payload = payload.as_object_mut().unwrap();
+            payload.insert("logprobs".to_string(), json!(true));
+            if top_logprobs > 0 {
+                payload.insert("top_logprobs".to_string(), json!(top_logprobs));
+            }
+        }
+
         // open ai reasoning models use max_completion_tokens instead of max_tokens
         if let Some(tokens) = model_config.max_tokens {
             let key = if is_openai_reasoning_model {
@@ -996,6 +1043,14 @@ mod tests {
             toolshim: false,
             toolshim_model: None,
             fast_model: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            reasoning_effort: None,
+            thinking_budget_tokens: None,
+            seed: None,
+            logprobs: None,
+            response_schema: None,
+            additional_request_fields: None,
         };
         let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
         let obj = request.as_object().unwrap();
@@ -1027,6 +1082,14 @@ mod tests {
             toolshim: false,
             toolshim_model: None,
             fast_model: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            reasoning_effort: None,
+            thinking_budget_tokens: None,
+            seed: None,
+            logprobs: None,
+            response_schema: None,
+            additional_request_fields: None,
         };
         let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
         assert_eq!(request["reasoning_effort"], "high");
@@ -1,4 +1,4 @@
-use crate::model::ModelConfig;
+use crate::model::{ModelConfig, ToolChoice};
 use crate::providers::base::Usage;
 use crate::providers::errors::ProviderError;
 use crate::providers::utils::{is_valid_function_name, sanitize_function_name};
@@ -9,7 +9,9 @@ use rmcp::model::{
 };
 use std::borrow::Cow;
 
-use crate::conversation::message::{Message, MessageContent, ProviderMetadata};
+use crate::conversation::message::{
+    Citation, Message, MessageContent, ProviderMetadata, TokenLogprob, TopLogprob,
+};
 use serde_json::{json, Map, Value};
 use std::ops::Deref;
 
@@ -173,6 +175,24 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                             }
                         }
                     }
+                    MessageContent::Document(document) => {
+                        parts.push(json!({
+                            "inline_data": {
+                                "mime_type": document.mime_type,
+                                "data": document.data,
+                            }
+                        }));
+                    }
+                    MessageContent::Audio(audio) => {
+                        parts.push(json!({
+                            "inline_data": {
+                                "mime_type": audio.mime_type,
+                                "data": audio.data,
+                            }
+                        }));
+                    }
+                    // Thought summaries carry no signature and are not sent back
+                    MessageContent::Thinking(thinking) if thinking.signature.is_empty() => {}
                     MessageContent::Thinking(thinking) => {
                         let mut part = Map::new();
                         part.insert("text".to_string(), json!(thinking.thinking));
@@ -212,6 +232,17 @@ pub fn format_tools(tools: &[Tool]) -> Vec<Value> {
         .collect()
 }
 
+/// Convert a ToolChoice to Gemini's toolConfig specification
+pub fn format_tool_config(tool_choice: &ToolChoice) -> Value {
+    let function_calling_config = match tool_choice {
+        ToolChoice::Auto => json!({"mode": "AUTO"}),
+        ToolChoice::Required => json!({"mode": "ANY"}),
+        ToolChoice::None => json!({"mode": "NONE"}),
+        ToolChoice::Tool(name) => json!({"mode": "ANY", "allowedFunctionNames": [name]}),
+    };
+    json!({"functionCallingConfig": function_calling_config})
+}
+
 pub fn get_accepted_keys(parent_key: Option<&str>) -> Vec<&str> {
     match parent_key {
         Some("properties") => vec![
@@ -343,7 +374,15 @@ pub fn response_to_message(response: Value) -> Result<Message> {
             last_signature = signature.clone();
         }
 
-        if let Some(text) = part.get("text").and_then(|v| v.as_str()) {
+        let is_thought = part.get("thought").and_then(|v| v.as_bool()) == Some(true);
+
+        if let (true, Some(text)) = (is_thought, part.get("text").and_then(|v| v.as_str())) {
+            // Thought summaries are returned when includeThoughts is set
+            content.push(MessageContent::thinking(
+                text.to_string(),
+                signature.clone().unwrap_or_default(),
+            ));
+        } else if let Some(text) = part.get("text").and_then(|v| v.as_str()) {
             // Text is "thinking" only if:
             // 1. It has a signature AND
             // 2. The response also contains function calls (meaning this is reasoning before acting)
@@ -389,7 +428,120 @@ pub fn response_to_message(response: Value) -> Result<Message> {
             }
         }
     }
-    Ok(Message::new(role, created, content))
+    if let Some(grounding) = candidate.get("groundingMetadata") {
+        let queries: Vec<&str> = grounding
+            .get("webSearchQueries")
+            .and_then(|q| q.as_array())
+            .map(|q| q.iter().filter_map(|q| q.as_str()).collect())
+            .unwrap_or_default();
+        if !queries.is_empty() {
+            let summary = format!(
+                "[google_search] Searched the web for {}",
+                queries
+                    .iter()
+                    .map(|q| format!("\"{}\"", q))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            content.insert(
+                0,
+                MessageContent::builtin_tool_call(
+                    summary,
+                    json!({"type": "google_search", "webSearchQueries": queries}),
+                ),
+            );
+        }
+
+        let citations = get_grounding_citations(grounding);
+        if !citations.is_empty() {
+            content.push(MessageContent::citations(citations));
+        }
+    }
+
+    let message = Message::new(role, created, content);
+    match get_logprobs(candidate) {
+        Some(logprobs) => Ok(message.with_logprobs(logprobs)),
+        None => Ok(message),
+    }
+}
+
+/// Extract the web sources from a candidate's `groundingMetadata`, taking each source's cited
+/// text from the first response segment it supports
+fn get_grounding_citations(grounding: &Value) -> Vec<Citation> {
+    let binding = vec![];
+    let chunks = grounding
+        .get("groundingChunks")
+        .and_then(|c| c.as_array())
+        .unwrap_or(&binding);
+    let supports = grounding
+        .get("groundingSupports")
+        .and_then(|s| s.as_array())
+        .unwrap_or(&binding);
+
+    chunks
+        .iter()
+        .enumerate()
+        .filter_map(|(i, chunk)| {
+            let web = chunk.get("web")?;
+            let cited_text = supports
+                .iter()
+                .find(|support| {
+                    support
+                        .get("groundingChunkIndices")
+                        .and_then(|indices| indices.as_array())
+                        .is_some_and(|indices| {
+                            indices.iter().any(|idx| idx.as_u64() == Some(i as u64))
+                        })
+                })
+                .and_then(|support| support.pointer("/segment/text"))
+                .and_then(|t| t.as_str())
+                .map(String::from);
+            Some(Citation {
+                url: web.get("uri")?.as_str()?.to_string(),
+                title: web.get("title").and_then(|t| t.as_str()).map(String::from),
+                cited_text,
+            })
+        })
+        .collect()
+}
+
+fn get_logprob_candidate(candidate: &Value) -> Option<TopLogprob> {
+    Some(TopLogprob {
+        token: candidate.get("token")?.as_str()?.to_string(),
+        logprob: candidate.get("logProbability")?.as_f64()?,
+    })
+}
+
+/// Extract token log probabilities from a candidate's `logprobsResult`, pairing each chosen
+/// token with the top alternatives at the same position
+fn get_logprobs(candidate: &Value) -> Option<Vec<TokenLogprob>> {
+    let result = candidate.get("logprobsResult")?;
+    let chosen = result.get("chosenCandidates")?.as_array()?;
+    let top = result
+        .get("topCandidates")
+        .and_then(|t| t.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    Some(
+        chosen
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| {
+                let chosen = get_logprob_candidate(entry)?;
+                Some(TokenLogprob {
+                    token: chosen.token,
+                    logprob: chosen.logprob,
+                    top_logprobs: top
+                        .get(i)
+                        .and_then(|t| t.get("candidates"))
+                        .and_then(|c| c.as_array())
+                        .map(|c| c.iter().filter_map(get_logprob_candidate).collect())
+                        .unwrap_or_default(),
+                })
+            })
+            .collect(),
+    )
 }
 
 /// Extract usage information from Google's API response
@@ -436,6 +588,9 @@ pub fn create_request(
             "tools".to_string(),
             json!({"functionDeclarations": format_tools(tools)}),
         );
+        if let Some(tool_choice) = &model_config.tool_choice_for_call() {
+            payload.insert("toolConfig".to_string(), format_tool_config(tool_choice));
+        }
     }
     let mut generation_config = Map::new();
     if let Some(temp) = model_config.temperature {
@@ -444,6 +599,21 @@ pub fn create_request(
     if let Some(tokens) = model_config.max_tokens {
         generation_config.insert("maxOutputTokens".to_string(), json!(tokens));
     }
+    if let Some(seed) = model_config.seed {
+        generation_config.insert("seed".to_string(), json!(seed));
+    }
+    if let Some(top_logprobs) = model_config.logprobs {
+        generation_config.insert("responseLogprobs".to_string(), json!(true));
+        if top_logprobs > 0 {
+            generation_config.insert("logprobs".to_string(), json!(top_logprobs));
+        }
+    }
+    if let Some(budget) = model_config.thinking_budget_tokens {
+        generation_config.insert(
+            "thinkingConfig".to_string(),
+            json!({"thinkingBudget": budget, "includeThoughts": budget > 0}),
+        );
+    }
     if !generation_config.is_empty() {
         payload.insert("generationConfig".to_string(), json!(generation_config));
     }
@@ -451,6 +621,37 @@ pub fn create_request(
     Ok(json!(payload))
 }
 
+/// Enable Gemini's built-in Google Search grounding alongside any function declarations
+pub fn add_google_search_tool(payload: &mut Value) {
+    let google_search = json!({"google_search": {}});
+    let payload = payload.as_object_mut().unwrap();
+    let tools = match payload.remove("tools") {
+        Some(Value::Array(mut tools)) => {
+            tools.push(google_search);
+            tools
+        }
+        Some(tools) => vec![tools, google_search],
+        None => vec![google_search],
+    };
+    payload.insert("tools".to_string(), Value::Array(tools));
+}
+
+/// Create a request for the `countTokens` endpoint, which wraps a full
+/// generateContent request so system instructions and tools are counted too
+pub fn create_count_tokens_request(
+    model_config: &ModelConfig,
+    system: &str,
+    messages: &[Message],
+    tools: &[Tool],
+) -> Result<Value> {
+    let mut request = create_request(model_config, system, messages, tools)?;
+    request.as_object_mut().unwrap().insert(
+        "model".to_string(),
+        json!(format!("models/{}", model_config.model_name)),
+    );
+    Ok(json!({"generateContentRequest": request}))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -828,6 +1029,45 @@ mod tests {
         assert!(result[0]["parameters"].get("properties").is_none());
     }
 
+    #[test]
+    fn test_response_to_message_with_thought_summary() {
+        let response = google_response(vec![
+            json!({"text": "Considering the question", "thought": true}),
+            json!({"text": "The answer"}),
+        ]);
+        let message = response_to_message(response).unwrap();
+        assert_eq!(
+            message.content[0].as_thinking().unwrap().thinking,
+            "Considering the question"
+        );
+        assert_eq!(message.content[1].as_text(), Some("The answer"));
+    }
+
+    #[test]
+    fn test_format_tool_config() {
+        assert_eq!(
+            format_tool_config(&ToolChoice::Tool("tool1".to_string())),
+            json!({"functionCallingConfig": {"mode": "ANY", "allowedFunctionNames": ["tool1"]}})
+        );
+        assert_eq!(
+            format_tool_config(&ToolChoice::None),
+            json!({"functionCallingConfig": {"mode": "NONE"}})
+        );
+    }
+
+    #[test]
+    fn test_create_count_tokens_request() {
+        let model_config = ModelConfig::new_or_fail("gemini-2.5-flash");
+        let messages = vec![Message::user().with_text("Hello")];
+
+        let payload = create_count_tokens_request(&model_config, "system", &messages, &[]).unwrap();
+        let request = &payload["generateContentRequest"];
+
+        assert_eq!(request["model"], "models/gemini-2.5-flash");
+        assert_eq!(request["contents"][0]["parts"][0]["text"], "Hello");
+        assert_eq!(request["system_instruction"]["parts"][0]["text"], "system");
+    }
+
     #[test]
     fn test_response_to_message_with_no_candidates() {
         let response = json!({});
@@ -836,6 +1076,48 @@ mod tests {
         assert!(message.content.is_empty());
     }
 
+    #[test]
+    fn test_response_to_message_with_grounding() {
+        let response = json!({
+            "candidates": [{
+                "content": {"parts": [{"text": "Goose is an open source agent."}]},
+                "groundingMetadata": {
+                    "webSearchQueries": ["goose agent"],
+                    "groundingChunks": [
+                        {"web": {"uri": "https://example.com/goose", "title": "example.com"}},
+                        {"web": {"uri": "https://example.com/other"}}
+                    ],
+                    "groundingSupports": [{
+                        "segment": {"startIndex": 0, "endIndex": 29, "text": "Goose is an open source agent"},
+                        "groundingChunkIndices": [0]
+                    }]
+                }
+            }]
+        });
+
+        let message = response_to_message(response).unwrap();
+        assert_eq!(message.content.len(), 3);
+        assert_eq!(
+            message.content[0].as_text(),
+            Some("[google_search] Searched the web for \"goose agent\"")
+        );
+        assert_eq!(
+            message.content[1].as_text(),
+            Some("Goose is an open source agent.")
+        );
+        assert_eq!(
+            message.content[2].as_text(),
+            Some("\n\nSources:\n1. [example.com](https://example.com/goose)\n2. https://example.com/other")
+        );
+
+        let mut payload = json!({"tools": {"functionDeclarations": []}});
+        add_google_search_tool(&mut payload);
+        assert_eq!(
+            payload["tools"],
+            json!([{"functionDeclarations": []}, {"google_search": {}}])
+        );
+    }
+
     #[test]
     fn test_response_to_message_with_text_part() {
         let response = json!({
@@ -857,6 +1139,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_response_to_message_with_logprobs() {
+        let response = json!({
+            "candidates": [{
+                "content": {"parts": [{"text": "Yes"}]},
+                "logprobsResult": {
+                    "topCandidates": [{
+                        "candidates": [
+                            {"token": "Yes", "logProbability": -0.1},
+                            {"token": "No", "logProbability": -2.4}
+                        ]
+                    }],
+                    "chosenCandidates": [{"token": "Yes", "logProbability": -0.1}]
+                }
+            }]
+        });
+        let message = response_to_message(response).unwrap();
+        let logprobs = message.metadata.logprobs.unwrap();
+        assert_eq!(logprobs.len(), 1);
+        assert_eq!(logprobs[0].token, "Yes");
+        assert_eq!(logprobs[0].logprob, -0.1);
+        assert_eq!(logprobs[0].top_logprobs.len(), 2);
+        assert_eq!(logprobs[0].top_logprobs[1].token, "No");
+    }
+
     #[test]
     fn test_response_to_message_with_invalid_function_name() {
         let response = json!({
@@ -1,9 +1,11 @@
-use crate::conversation::message::{Message, MessageContent};
-use crate::model::ModelConfig;
+use crate::conversation::message::{Message, MessageContent, TokenLogprob, TopLogprob};
+use crate::model::{ModelConfig, ToolChoice};
 use crate::providers::base::{ProviderUsage, Usage};
+use crate::providers::stream_event::StreamEvent;
+use crate::providers::usage_estimator::UsageMeter;
 use crate::providers::utils::{
-    convert_image, detect_image_path, is_valid_function_name, load_image_file, safely_parse_json,
-    sanitize_function_name, ImageFormat,
+    audio_placeholder, convert_image, detect_image_path, document_to_text, is_valid_function_name,
+    load_image_file, safely_parse_json, sanitize_function_name, ImageFormat,
 };
 use anyhow::{anyhow, Error};
 use async_stream::try_stream;
@@ -44,6 +46,8 @@ struct StreamingChoice {
     delta: Delta,
     index: Option<i32>,
     finish_reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    logprobs: Option<Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -53,6 +57,7 @@ struct StreamingChunk {
     id: Option<String>,
     usage: Option<Value>,
     model: Option<String>,
+    system_fingerprint: Option<String>,
 }
 
 pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<Value> {
@@ -204,6 +209,32 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
                 MessageContent::Image(image) => {
                     content_array.push(convert_image(image, image_format));
                 }
+                // Chat completions only accept PDFs as file inputs
+                MessageContent::Document(document) if document.is_pdf() => {
+                    content_array.push(json!({
+                        "type": "file",
+                        "file": {
+                            "filename": document.name.as_deref().unwrap_or("document.pdf"),
+                            "file_data": format!("data:{};base64,{}", document.mime_type, document.data),
+                        }
+                    }));
+                }
+                MessageContent::Document(document) => {
+                    text_array.push(document_to_text(document));
+                }
+                // Audio-capable chat models accept wav and mp3 input
+                MessageContent::Audio(audio) => match audio.format() {
+                    Some(format @ ("wav" | "mp3")) => {
+                        content_array.push(json!({
+                            "type": "input_audio",
+                            "input_audio": {
+                                "data": audio.data,
+                                "format": format,
+                            }
+                        }));
+                    }
+                    _ => text_array.push(audio_placeholder(audio)),
+                },
                 MessageContent::FrontendToolRequest(request) => match &request.tool_call {
                     Ok(tool_call) => {
                         let sanitized_name = sanitize_function_name(&tool_call.name);
@@ -241,6 +272,10 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
         }
 
         if !content_array.is_empty() {
+            // Keep any plain text alongside images and files
+            if !text_array.is_empty() {
+                content_array.insert(0, json!({"type": "text", "text": text_array.join("\n")}));
+            }
             converted["content"] = json!(content_array);
         } else if !text_array.is_empty() {
             converted["content"] = json!(text_array.join("\n"));
@@ -278,6 +313,19 @@ pub fn format_tools(tools: &[Tool]) -> anyhow::Result<Vec<Value>> {
     Ok(result)
 }
 
+/// Convert a ToolChoice into the OpenAI `tool_choice` request field
+pub fn format_tool_choice(tool_choice: &ToolChoice) -> Value {
+    match tool_choice {
+        ToolChoice::Auto => json!("auto"),
+        ToolChoice::Required => json!("required"),
+        ToolChoice::None => json!("none"),
+        ToolChoice::Tool(name) => json!({
+            "type": "function",
+            "function": {"name": sanitize_function_name(name)}
+        }),
+    }
+}
+
 /// Convert OpenAI's API response to internal Message format
 pub fn response_to_message(response: &Value) -> anyhow::Result<Message> {
     let Some(original) = response
@@ -294,6 +342,14 @@ pub fn response_to_message(response: &Value) -> anyhow::Result<Message> {
 
     let mut content = Vec::new();
 
+    // OpenAI-compatible servers for reasoning models (e.g. DeepSeek, vLLM) return the
+    // model's reasoning separately from the final answer
+    if let Some(reasoning) = original.get("reasoning_content").and_then(|r| r.as_str()) {
+        if !reasoning.is_empty() {
+            content.push(MessageContent::thinking(reasoning, ""));
+        }
+    }
+
     if let Some(text) = original.get("content") {
         if let Some(text_str) = text.as_str() {
             content.push(MessageContent::text(text_str));
@@ -360,11 +416,51 @@ pub fn response_to_message(response: &Value) -> anyhow::Result<Message> {
         }
     }
 
-    Ok(Message::new(
-        Role::Assistant,
-        chrono::Utc::now().timestamp(),
-        content,
-    ))
+    let message = Message::new(Role::Assistant, chrono::Utc::now().timestamp(), content);
+    match response
+        .get("choices")
+        .and_then(|c| c.get(0))
+        .and_then(get_logprobs)
+    {
+        Some(logprobs) => Ok(message.with_logprobs(logprobs)),
+        None => Ok(message),
+    }
+}
+
+/// Extract token log probabilities from a chat completion choice, if they were requested
+pub fn get_logprobs(choice: &Value) -> Option<Vec<TokenLogprob>> {
+    parse_logprobs(choice.get("logprobs")?)
+}
+
+/// Parse the `logprobs` of a choice, which streamed chunks carry for the tokens of their delta
+fn parse_logprobs(logprobs: &Value) -> Option<Vec<TokenLogprob>> {
+    let content = logprobs.get("content")?.as_array()?;
+    Some(
+        content
+            .iter()
+            .filter_map(|entry| {
+                Some(TokenLogprob {
+                    token: entry.get("token")?.as_str()?.to_string(),
+                    logprob: entry.get("logprob")?.as_f64()?,
+                    top_logprobs: entry
+                        .get("top_logprobs")
+                        .and_then(|t| t.as_array())
+                        .map(|alternatives| {
+                            alternatives
+                                .iter()
+                                .filter_map(|alt| {
+                                    Some(TopLogprob {
+                                        token: alt.get("token")?.as_str()?.to_string(),
+                                        logprob: alt.get("logprob")?.as_f64()?,
+                                    })
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                })
+            })
+            .collect(),
+    )
 }
 
 pub fn get_usage(usage: &Value) -> Usage {
@@ -387,7 +483,19 @@ pub fn get_usage(usage: &Value) -> Usage {
             _ => None,
         });
 
+    let cached_input_tokens = usage
+        .pointer("/prompt_tokens_details/cached_tokens")
+        .and_then(|v| v.as_i64())
+        .map(|v| v as i32);
+
     Usage::new(input_tokens, output_tokens, total_tokens)
+        .with_cached_input_tokens(cached_input_tokens)
+}
+
+/// The billed cost in USD, for OpenAI-compatible APIs such as OpenRouter that report it
+/// alongside token usage
+pub fn get_reported_cost(usage: &Value) -> Option<f64> {
+    usage.get("cost").and_then(|v| v.as_f64())
 }
 
 /// Validates and fixes tool schemas to ensure they have proper parameter structure.
@@ -449,6 +557,7 @@ where
     try_stream! {
         use futures::StreamExt;
 
+        let mut meter: Option<UsageMeter> = None;
         'outer: while let Some(response) = stream.next().await {
             if response.as_ref().is_ok_and(|s| s == "data: [DONE]") {
                 break 'outer;
@@ -466,13 +575,21 @@ where
 
             let usage = chunk.usage.as_ref().and_then(|u| {
                 chunk.model.as_ref().map(|model| {
-                    ProviderUsage {
-                        usage: get_usage(u),
-                        model: model.clone(),
-                    }
+                    ProviderUsage::new(model.clone(), get_usage(u))
+                        .with_system_fingerprint(chunk.system_fingerprint.clone())
+                        .with_cost(get_reported_cost(u))
                 })
             });
 
+            if meter.is_none() {
+                if let Some(model) = &chunk.model {
+                    meter = Some(UsageMeter::new(model).await);
+                }
+            }
+            if let (Some(meter), Some(usage)) = (meter.as_mut(), usage.as_ref()) {
+                meter.set_input_tokens(usage.usage.input_tokens);
+            }
+
             if chunk.choices.is_empty() {
                 yield (None, usage)
             } else if chunk.choices[0].delta.tool_calls.as_ref().is_some_and(|tc| !tc.is_empty()) {
@@ -505,6 +622,9 @@ where
                                     if let Some(delta_tool_calls) = &tool_chunk.choices[0].delta.tool_calls {
                                         for delta_call in delta_tool_calls {
                                             if let Some(index) = delta_call.index {
+                                                if let Some(usage) = meter.as_mut().and_then(|m| m.record_output(&delta_call.function.arguments)) {
+                                                    yield (None, Some(usage));
+                                                }
                                                 if let Some((_, _, ref mut args)) = tool_call_data.get_mut(&index) {
                                                     args.push_str(&delta_call.function.arguments);
                                                 } else if let (Some(id), Some(name)) = (&delta_call.id, &delta_call.function.name) {
@@ -591,15 +711,18 @@ where
                 if let Some(id) = chunk.id {
                     msg = msg.with_id(id);
                 }
+                if let Some(logprobs) = chunk.choices[0].logprobs.as_ref().and_then(parse_logprobs) {
+                    msg = msg.with_logprobs(logprobs);
+                }
 
-                yield (
-                    Some(msg),
-                    if chunk.choices[0].finish_reason.is_some() {
-                        usage
-                    } else {
-                        None
-                    },
-                )
+                if chunk.choices[0].finish_reason.is_some() {
+                    yield (Some(msg), usage)
+                } else {
+                    yield (Some(msg), None);
+                    if let Some(usage) = meter.as_mut().and_then(|m| m.record_output(text)) {
+                        yield (None, Some(usage));
+                    }
+                }
             } else if usage.is_some() {
                 yield (None, usage)
             }
@@ -607,6 +730,95 @@ where
     }
 }
 
+/// The events of a streamed chat completion. Text and tool call arguments are passed on as they
+/// arrive. Only the first delta of a tool call carries its id, so later ones are matched to it by
+/// index. Some servers send usage with every chunk; the last is passed on once the stream ends.
+pub fn response_to_stream_events<S>(
+    mut stream: S,
+) -> impl Stream<Item = anyhow::Result<StreamEvent>> + 'static
+where
+    S: Stream<Item = anyhow::Result<String>> + Unpin + Send + 'static,
+{
+    try_stream! {
+        use futures::StreamExt;
+
+        let mut meter: Option<UsageMeter> = None;
+        let mut tool_call_ids: std::collections::HashMap<i32, String> = std::collections::HashMap::new();
+        let mut final_usage = None;
+        while let Some(response) = stream.next().await {
+            let response_str = response?;
+            if response_str == "data: [DONE]" {
+                break;
+            }
+            let Some(line) = strip_data_prefix(&response_str).filter(|l| !l.is_empty()) else {
+                continue;
+            };
+
+            let chunk: StreamingChunk = serde_json::from_str(line)
+                .map_err(|e| anyhow!("Failed to parse streaming chunk: {}: {:?}", e, &line))?;
+
+            let usage = chunk.usage.as_ref().and_then(|u| {
+                chunk.model.as_ref().map(|model| {
+                    ProviderUsage::new(model.clone(), get_usage(u))
+                        .with_system_fingerprint(chunk.system_fingerprint.clone())
+                        .with_cost(get_reported_cost(u))
+                })
+            });
+
+            if meter.is_none() {
+                if let Some(model) = &chunk.model {
+                    meter = Some(UsageMeter::new(model).await);
+                }
+            }
+            if let (Some(meter), Some(usage)) = (meter.as_mut(), usage.as_ref()) {
+                meter.set_input_tokens(usage.usage.input_tokens);
+            }
+
+            if let Some(choice) = chunk.choices.first() {
+                if let Some(text) = choice.delta.content.as_ref().filter(|text| !text.is_empty()) {
+                    yield StreamEvent::TextDelta {
+                        message_id: chunk.id.clone(),
+                        text: text.clone(),
+                        logprobs: choice.logprobs.as_ref().and_then(parse_logprobs),
+                    };
+                    if let Some(usage) = meter.as_mut().and_then(|m| m.record_output(text)) {
+                        yield StreamEvent::Usage(usage);
+                    }
+                }
+                for tool_call in choice.delta.tool_calls.iter().flatten() {
+                    let id = match (tool_call.index, &tool_call.id) {
+                        (Some(index), Some(id)) => {
+                            tool_call_ids.insert(index, id.clone());
+                            Some(id.clone())
+                        }
+                        (Some(index), None) => tool_call_ids.get(&index).cloned(),
+                        (None, id) => id.clone(),
+                    };
+                    let Some(id) = id else {
+                        continue;
+                    };
+                    let arguments = &tool_call.function.arguments;
+                    yield StreamEvent::ToolCallDelta {
+                        id,
+                        name: tool_call.function.name.clone(),
+                        arguments: arguments.clone(),
+                    };
+                    if let Some(usage) = meter.as_mut().and_then(|m| m.record_output(arguments)) {
+                        yield StreamEvent::Usage(usage);
+                    }
+                }
+            }
+
+            if usage.is_some() {
+                final_usage = usage;
+            }
+        }
+        if let Some(usage) = final_usage {
+            yield StreamEvent::Usage(usage);
+        }
+    }
+}
+
 pub fn create_request(
     model_config: &ModelConfig,
     system: &str,
@@ -627,7 +839,8 @@ pub fn create_request(
         || model_config.model_name.starts_with("o4")
         || model_config.model_name.starts_with("gpt-5");
 
-    // Only extract reasoning effort for O-series models
+    // Only extract reasoning effort for O-series models. An effort suffix on the model
+    // name wins over the configured effort, which in turn wins over the default
     let (model_name, reasoning_effort) = if is_ox_model {
         let parts: Vec<&str> = model_config.model_name.split('-').collect();
         let last_part = parts.last().unwrap();
@@ -639,7 +852,12 @@ pub fn create_request(
             }
             _ => (
                 model_config.model_name.to_string(),
-                Some("medium".to_string()),
+                Some(
+                    model_config
+                        .reasoning_effort
+                        .map_or("medium", |effort| effort.as_str())
+                        .to_string(),
+                ),
             ),
         }
     } else {
@@ -671,6 +889,19 @@ pub fn create_request(
 
     if !tools_spec.is_empty() {
         payload["tools"] = json!(tools_spec);
+        if let Some(tool_choice) = &model_config.tool_choice_for_call() {
+            payload["tool_choice"] = format_tool_choice(tool_choice);
+        }
+        if let Some(parallel_tool_calls) = model_config.parallel_tool_calls {
+            payload["parallel_tool_calls"] = json!(parallel_tool_calls);
+        }
+    }
+
+    if let Some(schema) = &model_config.response_schema {
+        payload["response_format"] = json!({
+            "type": "json_schema",
+            "json_schema": {"name": "response", "schema": schema}
+        });
     }
 
     // o1, o3 models currently don't support temperature
@@ -680,6 +911,17 @@ pub fn create_request(
         }
     }
 
+    if let Some(seed) = model_config.seed {
+        payload["seed"] = json!(seed);
+    }
+
+    if let Some(top_logprobs) = model_config.logprobs {
+        payload["logprobs"] = json!(true);
+        if top_logprobs > 0 {
+            payload["top_logprobs"] = json!(top_logprobs);
+        }
+    }
+
     // o1 models use max_completion_tokens instead of max_tokens
     if let Some(tokens) = model_config.max_tokens {
         let key = if is_ox_model {
@@ -704,7 +946,8 @@ pub fn create_request(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::conversation::message::Message;
+    use crate::conversation::message::{Message, MessageMetadata};
+    use crate::providers::stream_event::into_message_stream;
     use rmcp::model::CallToolResult;
     use rmcp::object;
     use serde_json::json;
@@ -818,6 +1061,17 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_format_messages_excludes_message_metadata() {
+        let metadata = MessageMetadata::default()
+            .with_author("reviewer")
+            .with_tag("draft");
+        let message = Message::user().with_text("Hello").with_metadata(metadata);
+        let spec = format_messages(&[message], &ImageFormat::OpenAi);
+
+        assert_eq!(spec, vec![json!({"role": "user", "content": "Hello"})]);
+    }
+
     #[test]
     fn test_format_tools() -> anyhow::Result<()> {
         let tool = Tool::new(
@@ -978,6 +1232,38 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_format_messages_with_audio_and_document() -> anyhow::Result<()> {
+        let message = Message::user()
+            .with_text("What is said here?")
+            .with_audio("UklGRg==", "audio/wav")
+            .with_audio("T2dnUw==", "audio/ogg")
+            .with_content(MessageContent::document(
+                "JVBERi0xLjc=",
+                "application/pdf",
+                Some("report.pdf".to_string()),
+            )?);
+
+        let spec = format_messages(&[message], &ImageFormat::OpenAi);
+        let content = spec[0]["content"].as_array().unwrap();
+
+        assert_eq!(content[0]["type"], "text");
+        assert!(content[0]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("What is said here?\n[Audio (audio/ogg)"));
+        assert_eq!(content[1]["type"], "input_audio");
+        assert_eq!(content[1]["input_audio"]["format"], "wav");
+        assert_eq!(content[2]["type"], "file");
+        assert_eq!(content[2]["file"]["filename"], "report.pdf");
+        assert_eq!(
+            content[2]["file"]["file_data"],
+            "data:application/pdf;base64,JVBERi0xLjc="
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_format_messages_with_image_path() -> anyhow::Result<()> {
         // Create a temporary PNG file with valid PNG magic numbers
@@ -1270,6 +1556,14 @@ mod tests {
             toolshim: false,
             toolshim_model: None,
             fast_model: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            reasoning_effort: None,
+            thinking_budget_tokens: None,
+            seed: None,
+            logprobs: None,
+            response_schema: None,
+            additional_request_fields: None,
         };
         let request = create_request(
             &model_config,
@@ -1309,6 +1603,14 @@ mod tests {
             toolshim: false,
             toolshim_model: None,
             fast_model: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            reasoning_effort: None,
+            thinking_budget_tokens: None,
+            seed: None,
+            logprobs: None,
+            response_schema: None,
+            additional_request_fields: None,
         };
         let request = create_request(
             &model_config,
@@ -1349,6 +1651,14 @@ mod tests {
             toolshim: false,
             toolshim_model: None,
             fast_model: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            reasoning_effort: None,
+            thinking_budget_tokens: None,
+            seed: None,
+            logprobs: None,
+            response_schema: None,
+            additional_request_fields: None,
         };
         let request = create_request(
             &model_config,
@@ -1378,6 +1688,343 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_create_request_configured_reasoning_effort() -> anyhow::Result<()> {
+        let model_config = ModelConfig::new_or_fail("o3")
+            .with_reasoning_effort(Some(crate::model::ReasoningEffort::High));
+        let request = create_request(
+            &model_config,
+            "system",
+            &[],
+            &[],
+            &ImageFormat::OpenAi,
+            false,
+        )?;
+        assert_eq!(request["reasoning_effort"], "high");
+
+        // Non-reasoning models never receive a reasoning effort
+        let model_config = ModelConfig::new_or_fail("gpt-4o")
+            .with_reasoning_effort(Some(crate::model::ReasoningEffort::High));
+        let request = create_request(
+            &model_config,
+            "system",
+            &[],
+            &[],
+            &ImageFormat::OpenAi,
+            false,
+        )?;
+        assert!(request.get("reasoning_effort").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request_with_seed() -> anyhow::Result<()> {
+        let model_config = ModelConfig::new_or_fail("gpt-4o").with_seed(Some(42));
+        let request = create_request(
+            &model_config,
+            "system",
+            &[],
+            &[],
+            &ImageFormat::OpenAi,
+            false,
+        )?;
+        assert_eq!(request["seed"], 42);
+
+        let request = create_request(
+            &model_config.with_seed(None),
+            "system",
+            &[],
+            &[],
+            &ImageFormat::OpenAi,
+            false,
+        )?;
+        assert!(request.get("seed").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_logprobs_request_and_response() -> anyhow::Result<()> {
+        let model_config = ModelConfig::new_or_fail("gpt-4o").with_logprobs(Some(2));
+        let request = create_request(
+            &model_config,
+            "system",
+            &[],
+            &[],
+            &ImageFormat::OpenAi,
+            false,
+        )?;
+        assert_eq!(request["logprobs"], true);
+        assert_eq!(request["top_logprobs"], 2);
+
+        let response = json!({
+            "choices": [{
+                "message": {"role": "assistant", "content": "Hi"},
+                "logprobs": {
+                    "content": [{
+                        "token": "Hi",
+                        "logprob": -0.25,
+                        "top_logprobs": [
+                            {"token": "Hi", "logprob": -0.25},
+                            {"token": "Hello", "logprob": -1.5}
+                        ]
+                    }]
+                }
+            }]
+        });
+        let message = response_to_message(&response)?;
+        let logprobs = message.metadata.logprobs.unwrap();
+        assert_eq!(logprobs.len(), 1);
+        assert_eq!(logprobs[0].token, "Hi");
+        assert_eq!(logprobs[0].logprob, -0.25);
+        assert_eq!(logprobs[0].top_logprobs[1].token, "Hello");
+
+        let message = response_to_message(&json!({
+            "choices": [{"message": {"role": "assistant", "content": "Hi"}}]
+        }))?;
+        assert!(message.metadata.logprobs.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_response_to_message_reasoning_content() -> anyhow::Result<()> {
+        let response = json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "reasoning_content": "Thinking it through",
+                    "content": "Answer"
+                }
+            }]
+        });
+        let message = response_to_message(&response)?;
+        assert_eq!(
+            message.content[0].as_thinking().unwrap().thinking,
+            "Thinking it through"
+        );
+        assert_eq!(message.content[1].as_text(), Some("Answer"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request_with_response_schema() -> anyhow::Result<()> {
+        let schema = json!({"type": "object", "properties": {"answer": {"type": "string"}}});
+        let model_config =
+            ModelConfig::new_or_fail("gpt-4o").with_response_schema(Some(schema.clone()));
+        let request = create_request(
+            &model_config,
+            "system",
+            &[],
+            &[],
+            &ImageFormat::OpenAi,
+            false,
+        )?;
+        assert_eq!(request["response_format"]["type"], "json_schema");
+        assert_eq!(request["response_format"]["json_schema"]["schema"], schema);
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request_with_tool_choice() -> anyhow::Result<()> {
+        let tools = vec![Tool::new(
+            "test_tool",
+            "A test tool",
+            object!({"type": "object"}),
+        )];
+        let model_config = ModelConfig::new_or_fail("gpt-4o")
+            .with_tool_choice(Some(ToolChoice::Tool("test_tool".to_string())));
+        let request = create_request(
+            &model_config,
+            "system",
+            &[],
+            &tools,
+            &ImageFormat::OpenAi,
+            false,
+        )?;
+        assert_eq!(
+            request["tool_choice"],
+            json!({"type": "function", "function": {"name": "test_tool"}})
+        );
+
+        // tool_choice is only sent alongside tools
+        let request = create_request(
+            &model_config.with_tool_choice(Some(ToolChoice::None)),
+            "system",
+            &[],
+            &[],
+            &ImageFormat::OpenAi,
+            false,
+        )?;
+        assert!(request.get("tool_choice").is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tool_choice_override_for_call() -> anyhow::Result<()> {
+        let tools = vec![Tool::new(
+            "test_tool",
+            "A test tool",
+            object!({"type": "object"}),
+        )];
+        let model_config =
+            ModelConfig::new_or_fail("gpt-4o").with_tool_choice(Some(ToolChoice::Auto));
+        let request = crate::model::with_tool_choice_override(Some(ToolChoice::Required), async {
+            create_request(
+                &model_config,
+                "system",
+                &[],
+                &tools,
+                &ImageFormat::OpenAi,
+                false,
+            )
+        })
+        .await?;
+        assert_eq!(request["tool_choice"], json!("required"));
+
+        // Outside the override the config's own tool choice applies
+        let request = create_request(
+            &model_config,
+            "system",
+            &[],
+            &tools,
+            &ImageFormat::OpenAi,
+            false,
+        )?;
+        assert_eq!(request["tool_choice"], json!("auto"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_streamed_tool_calls_to_events() -> anyhow::Result<()> {
+        let response_lines = r#"
+data: {"model":"gpt-4o","choices":[{"delta":{"role":"assistant","content":"Listing both"},"index":0,"finish_reason":null}],"usage":{"prompt_tokens":100,"completion_tokens":null,"total_tokens":null},"id":"chatcmpl-1"}
+data: {"model":"gpt-4o","choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_a","type":"function","function":{"name":"developer__shell","arguments":""}}]},"index":0,"finish_reason":null}],"usage":{"prompt_tokens":100,"completion_tokens":null,"total_tokens":null},"id":"chatcmpl-1"}
+data: {"model":"gpt-4o","choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"command\": \"l"}}]},"index":0,"finish_reason":null}],"id":"chatcmpl-1"}
+data: {"model":"gpt-4o","choices":[{"delta":{"tool_calls":[{"index":1,"id":"call_b","type":"function","function":{"name":"developer__shell","arguments":"{\"command\": "}}]},"index":0,"finish_reason":null}],"id":"chatcmpl-1"}
+data: {"model":"gpt-4o","choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"s\"}"}}]},"index":0,"finish_reason":null}],"id":"chatcmpl-1"}
+data: {"model":"gpt-4o","choices":[{"delta":{"tool_calls":[{"index":1,"function":{"arguments":"\"pwd\"}"}}]},"index":0,"finish_reason":"tool_calls"}],"id":"chatcmpl-1"}
+data: {"model":"gpt-4o","choices":[],"usage":{"prompt_tokens":100,"completion_tokens":20,"total_tokens":120},"id":"chatcmpl-1"}
+data: [DONE]
+"#;
+
+        let response_stream =
+            tokio_stream::iter(response_lines.lines().map(|line| Ok(line.to_string())));
+        let events: Vec<_> = response_to_stream_events(response_stream)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<anyhow::Result<_>>()?;
+
+        let deltas: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                StreamEvent::ToolCallDelta { id, arguments, .. } => {
+                    Some((id.as_str(), arguments.as_str()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            deltas,
+            vec![
+                ("call_a", ""),
+                ("call_a", "{\"command\": \"l"),
+                ("call_b", "{\"command\": "),
+                ("call_a", "s\"}"),
+                ("call_b", "\"pwd\"}"),
+            ]
+        );
+        let final_usage: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                StreamEvent::Usage(usage) if !usage.interim => Some(usage.usage.total_tokens),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(final_usage, vec![Some(120)]);
+
+        // Put back together, the calls come out whole in one message
+        let events = events.into_iter().chain([StreamEvent::Done]);
+        let chunks: Vec<_> = into_message_stream(Box::pin(tokio_stream::iter(events)))
+            .collect::<Vec<_>>()
+            .await;
+        let tool_calls: Vec<_> = chunks
+            .iter()
+            .filter_map(|chunk| chunk.as_ref().ok()?.0.as_ref())
+            .filter(|message| message.is_tool_call())
+            .collect();
+        assert_eq!(tool_calls.len(), 1);
+        let commands: Vec<_> = tool_calls[0]
+            .content
+            .iter()
+            .filter_map(|content| match content {
+                MessageContent::ToolRequest(request) => request.tool_call.as_ref().ok(),
+                _ => None,
+            })
+            .map(|call| call.arguments.as_ref().unwrap()["command"].clone())
+            .collect();
+        assert_eq!(commands, vec![json!("ls"), json!("pwd")]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_streamed_logprobs() -> anyhow::Result<()> {
+        let response_lines = r#"
+data: {"model":"gpt-4o","choices":[{"delta":{"role":"assistant","content":"Yes"},"index":0,"logprobs":{"content":[{"token":"Yes","logprob":-0.1,"top_logprobs":[{"token":"Yes","logprob":-0.1},{"token":"No","logprob":-2.4}]}]},"finish_reason":null}],"id":"chatcmpl-1"}
+data: {"model":"gpt-4o","choices":[{"delta":{"content":"."},"index":0,"logprobs":{"content":[{"token":".","logprob":-0.01,"top_logprobs":[]}]},"finish_reason":"stop"}],"id":"chatcmpl-1"}
+data: {"model":"gpt-4o","choices":[],"usage":{"prompt_tokens":10,"completion_tokens":2,"total_tokens":12},"id":"chatcmpl-1"}
+data: [DONE]
+"#;
+
+        let lines = || tokio_stream::iter(response_lines.lines().map(|line| Ok(line.to_string())));
+        let tokens = |message: &Message| -> Vec<String> {
+            message
+                .metadata
+                .logprobs
+                .iter()
+                .flatten()
+                .map(|logprob| logprob.token.clone())
+                .collect()
+        };
+
+        let messages: Vec<_> = response_to_streaming_message(lines())
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .filter_map(|chunk| chunk.ok()?.0)
+            .collect();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(tokens(&messages[0]), vec!["Yes"]);
+        assert_eq!(tokens(&messages[1]), vec!["."]);
+        let top = &messages[0].metadata.logprobs.as_ref().unwrap()[0].top_logprobs;
+        assert_eq!(top[1].token, "No");
+
+        let events = response_to_stream_events(lines())
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .into_iter()
+            .chain([StreamEvent::Done]);
+        let mut conversation = crate::conversation::Conversation::default();
+        let mut chunks = into_message_stream(Box::pin(tokio_stream::iter(events)));
+        while let Some(chunk) = chunks.next().await {
+            if let (Some(message), _) = chunk? {
+                conversation.push(message);
+            }
+        }
+        assert_eq!(conversation.len(), 1);
+        let message = conversation.first().unwrap();
+        assert_eq!(message.as_concat_text(), "Yes.");
+        assert_eq!(tokens(message), vec!["Yes", "."]);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_streamed_multi_tool_response_to_messages() -> anyhow::Result<()> {
         let response_lines = r#"
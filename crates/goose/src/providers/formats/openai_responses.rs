@@ -1,6 +1,7 @@
 use crate::conversation::message::{Message, MessageContent};
-use crate::model::ModelConfig;
+use crate::model::{ModelConfig, ToolChoice};
 use crate::providers::base::{ProviderUsage, Usage};
+use crate::providers::utils::{audio_placeholder, document_to_text};
 use anyhow::{anyhow, Error};
 use async_stream::try_stream;
 use chrono;
@@ -47,6 +48,18 @@ pub enum ResponseOutputItem {
         name: String,
         arguments: String,
     },
+    WebSearchCall {
+        id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        action: Option<Value>,
+    },
+    FileSearchCall {
+        id: String,
+        #[serde(default)]
+        queries: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        results: Option<Vec<Value>>,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -205,6 +218,18 @@ pub enum ResponseOutputItemInfo {
         name: String,
         arguments: String,
     },
+    WebSearchCall {
+        id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        action: Option<Value>,
+    },
+    FileSearchCall {
+        id: String,
+        #[serde(default)]
+        queries: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        results: Option<Vec<Value>>,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -249,8 +274,8 @@ fn add_conversation_history(input_items: &mut Vec<Value>, messages: &[Message])
 
         let mut content_items = Vec::new();
         for content in &message.content {
-            if let MessageContent::Text(text) = content {
-                if !text.text.is_empty() {
+            match content {
+                MessageContent::Text(text) if !text.text.is_empty() => {
                     let content_type = if message.role == Role::Assistant {
                         "output_text"
                     } else {
@@ -261,6 +286,27 @@ fn add_conversation_history(input_items: &mut Vec<Value>, messages: &[Message])
                         "text": text.text
                     }));
                 }
+                MessageContent::Document(document) if message.role == Role::User => {
+                    if document.is_pdf() {
+                        content_items.push(json!({
+                            "type": "input_file",
+                            "filename": document.name.as_deref().unwrap_or("document.pdf"),
+                            "file_data": format!("data:{};base64,{}", document.mime_type, document.data),
+                        }));
+                    } else {
+                        content_items.push(json!({
+                            "type": "input_text",
+                            "text": document_to_text(document)
+                        }));
+                    }
+                }
+                MessageContent::Audio(audio) if message.role == Role::User => {
+                    content_items.push(json!({
+                        "type": "input_text",
+                        "text": audio_placeholder(audio)
+                    }));
+                }
+                _ => {}
             }
         }
 
@@ -354,6 +400,75 @@ fn add_function_call_outputs(input_items: &mut Vec<Value>, messages: &[Message])
     }
 }
 
+fn format_responses_tool_choice(tool_choice: &ToolChoice) -> Value {
+    match tool_choice {
+        ToolChoice::Auto => json!("auto"),
+        ToolChoice::Required => json!("required"),
+        ToolChoice::None => json!("none"),
+        ToolChoice::Tool(name) => json!({"type": "function", "name": name}),
+    }
+}
+
+/// Tool spec for one of the tools OpenAI runs server-side in the Responses API.
+/// `file_search` searches the given vector stores.
+pub fn builtin_tool_spec(name: &str, vector_store_ids: &[String]) -> anyhow::Result<Value> {
+    match name {
+        "web_search" => Ok(json!({"type": "web_search"})),
+        "file_search" => {
+            if vector_store_ids.is_empty() {
+                return Err(anyhow!("file_search requires at least one vector store id"));
+            }
+            Ok(json!({"type": "file_search", "vector_store_ids": vector_store_ids}))
+        }
+        other => Err(anyhow!("Unsupported built-in tool: {}", other)),
+    }
+}
+
+/// Add server-side tools to a Responses API request, alongside any function tools
+pub fn add_builtin_tools(payload: &mut Value, builtin_tools: &[Value]) {
+    if builtin_tools.is_empty() {
+        return;
+    }
+    let tools = payload
+        .as_object_mut()
+        .unwrap()
+        .entry("tools")
+        .or_insert_with(|| json!([]));
+    if let Some(tools) = tools.as_array_mut() {
+        tools.extend(builtin_tools.iter().cloned());
+    }
+}
+
+// Built-in tool calls run on OpenAI's side, and the response carries each call with its outcome.
+// They come back as text rather than a tool request/response pair, because a `ToolRequest` would
+// have the agent dispatch a tool it doesn't have. The text is a one-line summary of the call, and
+// the raw call item is kept in `_meta.builtin_tool_call` for clients that render it as a tool.
+fn web_search_call_content(id: &str, action: Option<&Value>) -> MessageContent {
+    let summary = match action.and_then(|a| a.get("query")).and_then(|q| q.as_str()) {
+        Some(query) => format!("[web_search] Searched the web for \"{}\"", query),
+        None => "[web_search] Searched the web".to_string(),
+    };
+    MessageContent::builtin_tool_call(
+        summary,
+        json!({"type": "web_search_call", "id": id, "action": action}),
+    )
+}
+
+fn file_search_call_content(
+    id: &str,
+    queries: &[String],
+    results: Option<&Vec<Value>>,
+) -> MessageContent {
+    let mut summary = format!("[file_search] Searched files for {}", queries.join(", "));
+    if let Some(results) = results {
+        summary.push_str(&format!(" ({} results)", results.len()));
+    }
+    MessageContent::builtin_tool_call(
+        summary,
+        json!({"type": "file_search_call", "id": id, "queries": queries, "results": results}),
+    )
+}
+
 pub fn create_responses_request(
     model_config: &ModelConfig,
     system: &str,
@@ -399,6 +514,20 @@ pub fn create_responses_request(
             .as_object_mut()
             .unwrap()
             .insert("tools".to_string(), json!(tools_spec));
+
+        if let Some(tool_choice) = &model_config.tool_choice_for_call() {
+            payload.as_object_mut().unwrap().insert(
+                "tool_choice".to_string(),
+                format_responses_tool_choice(tool_choice),
+            );
+        }
+
+        if let Some(parallel_tool_calls) = model_config.parallel_tool_calls {
+            payload.as_object_mut().unwrap().insert(
+                "parallel_tool_calls".to_string(),
+                json!(parallel_tool_calls),
+            );
+        }
     }
 
     if let Some(temp) = model_config.temperature {
@@ -408,6 +537,13 @@ pub fn create_responses_request(
             .insert("temperature".to_string(), json!(temp));
     }
 
+    if let Some(effort) = model_config.reasoning_effort {
+        payload
+            .as_object_mut()
+            .unwrap()
+            .insert("reasoning".to_string(), json!({"effort": effort.as_str()}));
+    }
+
     if let Some(tokens) = model_config.max_tokens {
         payload
             .as_object_mut()
@@ -470,6 +606,16 @@ pub fn responses_api_to_message(response: &ResponsesApiResponse) -> anyhow::Resu
                     }),
                 ));
             }
+            ResponseOutputItem::WebSearchCall { id, action } => {
+                content.push(web_search_call_content(id, action.as_ref()));
+            }
+            ResponseOutputItem::FileSearchCall {
+                id,
+                queries,
+                results,
+            } => {
+                content.push(file_search_call_content(id, queries, results.as_ref()));
+            }
         }
     }
 
@@ -551,6 +697,16 @@ fn process_streaming_output_items(
                     }),
                 ));
             }
+            ResponseOutputItemInfo::WebSearchCall { id, action } => {
+                content.push(web_search_call_content(&id, action.as_ref()));
+            }
+            ResponseOutputItemInfo::FileSearchCall {
+                id,
+                queries,
+                results,
+            } => {
+                content.push(file_search_call_content(&id, &queries, results.as_ref()));
+            }
         }
     }
 
@@ -644,10 +800,7 @@ where
                             Some(u.total_tokens),
                         ),
                     );
-                    final_usage = Some(ProviderUsage {
-                        usage,
-                        model: model.clone(),
-                    });
+                    final_usage = Some(ProviderUsage::new(model.clone(), usage));
 
                     // For complete output, use the response output items
                     if !response.output.is_empty() {
@@ -694,3 +847,80 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::object;
+
+    #[test]
+    fn test_builtin_tools_in_request() -> anyhow::Result<()> {
+        let model_config = ModelConfig::new_or_fail("gpt-4.1");
+        let tools = vec![Tool::new(
+            "test_tool",
+            "A test tool",
+            object!({"type": "object"}),
+        )];
+        let mut payload = create_responses_request(&model_config, "system", &[], &tools)?;
+        let builtin_tools = vec![
+            builtin_tool_spec("web_search", &[])?,
+            builtin_tool_spec("file_search", &["vs_123".to_string()])?,
+        ];
+        add_builtin_tools(&mut payload, &builtin_tools);
+
+        let tool_types: Vec<&str> = payload["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(tool_types, vec!["function", "web_search", "file_search"]);
+        assert_eq!(payload["tools"][2]["vector_store_ids"], json!(["vs_123"]));
+
+        assert!(builtin_tool_spec("file_search", &[]).is_err());
+        assert!(builtin_tool_spec("code_interpreter", &[]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_builtin_tool_calls_in_response() -> anyhow::Result<()> {
+        let response: ResponsesApiResponse = serde_json::from_value(json!({
+            "id": "resp_1",
+            "object": "response",
+            "created_at": 0,
+            "status": "completed",
+            "model": "gpt-4.1",
+            "output": [
+                {
+                    "type": "web_search_call",
+                    "id": "ws_1",
+                    "status": "completed",
+                    "action": {"type": "search", "query": "goose agent"}
+                },
+                {
+                    "type": "message",
+                    "id": "msg_1",
+                    "status": "completed",
+                    "role": "assistant",
+                    "content": [{"type": "output_text", "text": "Goose is an agent."}]
+                }
+            ]
+        }))?;
+
+        let message = responses_api_to_message(&response)?;
+        assert_eq!(message.content.len(), 2);
+        let MessageContent::Text(search) = &message.content[0] else {
+            panic!("expected text content for the web search call");
+        };
+        assert_eq!(
+            search.text,
+            "[web_search] Searched the web for \"goose agent\""
+        );
+        assert_eq!(
+            search.raw.meta.as_ref().unwrap().0["builtin_tool_call"]["id"],
+            "ws_1"
+        );
+        assert_eq!(message.content[1].as_text(), Some("Goose is an agent."));
+        Ok(())
+    }
+}
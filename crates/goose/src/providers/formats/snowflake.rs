@@ -2,6 +2,7 @@ use crate::conversation::message::{Message, MessageContent};
 use crate::model::ModelConfig;
 use crate::providers::base::Usage;
 use crate::providers::errors::ProviderError;
+use crate::providers::utils::{audio_placeholder, document_to_text};
 use anyhow::{anyhow, Result};
 use rmcp::model::{object, CallToolRequestParam, Role, Tool};
 use rmcp::object;
@@ -63,6 +64,18 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                     // Skip redacted thinking for now
                 }
                 MessageContent::Image(_) => continue, // Snowflake doesn't support image content yet
+                MessageContent::Document(document) => {
+                    if !text_content.is_empty() {
+                        text_content.push('\n');
+                    }
+                    text_content.push_str(&document_to_text(document));
+                }
+                MessageContent::Audio(audio) => {
+                    if !text_content.is_empty() {
+                        text_content.push('\n');
+                    }
+                    text_content.push_str(&audio_placeholder(audio));
+                }
                 MessageContent::FrontendToolRequest(_tool_request) => {
                     // Skip frontend tool requests
                 }
@@ -344,7 +357,7 @@ pub fn create_request(
         format_tools(tools)
     };
 
-    let max_tokens = model_config.max_tokens.unwrap_or(4096);
+    let max_tokens = model_config.max_output_tokens(4096);
     let mut payload = json!({
         "model": model_config.model_name,
         "messages": snowflake_messages,
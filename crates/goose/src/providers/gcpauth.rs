@@ -7,6 +7,8 @@ use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{env, fmt, io};
 use tokio::sync::RwLock;
 
+use super::http_client::shared_client;
+
 /// Represents errors that can occur during GCP authentication.
 ///
 /// This enum encompasses various error conditions that might arise during
@@ -240,7 +242,7 @@ impl AdcCredentials {
     }
 
     async fn load_from_metadata_server(base_url: &str) -> Result<Self, AuthError> {
-        let client = reqwest::Client::new();
+        let client = shared_client().map_err(|e| AuthError::Credentials(e.to_string()))?;
         let metadata_path = "/computeMetadata/v1/instance/service-accounts/default/token";
 
         let response = client
@@ -356,7 +358,7 @@ impl GcpAuth {
     pub async fn new() -> Result<Self, AuthError> {
         Ok(Self {
             credentials: AdcCredentials::load().await?,
-            client: reqwest::Client::new(),
+            client: shared_client().map_err(|e| AuthError::Credentials(e.to_string()))?,
             cached_token: Arc::new(RwLock::new(None)),
         })
     }
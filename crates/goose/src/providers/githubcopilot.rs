@@ -9,12 +9,13 @@ use serde_json::Value;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::time::Duration;
 
 use super::base::{Provider, ProviderMetadata, ProviderUsage, Usage};
 use super::errors::ProviderError;
 use super::formats::openai::{create_request, get_usage, response_to_message};
+use super::http_client::shared_client;
 use super::retry::ProviderRetry;
+use super::timeouts::StreamTimeouts;
 use super::utils::{get_model, handle_response_openai_compat, ImageFormat, RequestLog};
 
 use crate::config::{Config, ConfigError};
@@ -156,9 +157,7 @@ impl GithubCopilotProvider {
     }
 
     pub async fn from_env(model: ModelConfig) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(600))
-            .build()?;
+        let client = shared_client()?;
         let cache = DiskCache::new();
         let mu = tokio::sync::Mutex::new(RefCell::new(None));
         Ok(Self {
@@ -172,7 +171,7 @@ impl GithubCopilotProvider {
 
     async fn post(&self, payload: &mut Value) -> Result<Value, ProviderError> {
         use crate::providers::utils_universal_openai_stream::{OAIStreamChunk, OAIStreamCollector};
-        use futures::StreamExt;
+        use futures::{StreamExt, TryStreamExt};
         // Detect gpt-4.1 and stream
         let model_name = payload.get("model").and_then(|v| v.as_str()).unwrap_or("");
         let stream_only_model = GITHUB_COPILOT_STREAM_MODELS
@@ -204,7 +203,8 @@ impl GithubCopilotProvider {
 
         if stream_only_model {
             let mut collector = OAIStreamCollector::new();
-            let mut stream = response.bytes_stream();
+            let mut stream = StreamTimeouts::from_config()
+                .apply(response.bytes_stream().map_err(anyhow::Error::from));
             while let Some(chunk) = stream.next().await {
                 let chunk = chunk.map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
                 let text = String::from_utf8_lossy(&chunk);
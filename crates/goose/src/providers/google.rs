@@ -1,16 +1,23 @@
 use super::api_client::{ApiClient, AuthMethod};
+use super::embedding::{parse_embedding_vectors, EmbeddingOptions, EmbeddingProvider};
 use super::errors::ProviderError;
+use super::image_generation::{
+    parse_base64_images, ImageGenerationOptions, ImageGenerationProvider,
+};
 use super::retry::ProviderRetry;
 use super::utils::{handle_response_google_compat, unescape_json_values, RequestLog};
-use crate::conversation::message::Message;
+use crate::conversation::message::{Message, MessageContent};
 
 use crate::model::ModelConfig;
 use crate::providers::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
-use crate::providers::formats::google::{create_request, get_usage, response_to_message};
+use crate::providers::formats::google::{
+    add_google_search_tool, create_count_tokens_request, create_request, get_usage,
+    response_to_message,
+};
 use anyhow::Result;
 use async_trait::async_trait;
 use rmcp::model::Tool;
-use serde_json::Value;
+use serde_json::{json, Value};
 
 pub const GOOGLE_API_HOST: &str = "https://generativelanguage.googleapis.com";
 pub const GOOGLE_DEFAULT_MODEL: &str = "gemini-2.5-pro";
@@ -43,6 +50,8 @@ pub const GOOGLE_KNOWN_MODELS: &[&str] = &[
     "gemini-2.0-flash-lite-001",
 ];
 
+pub const GOOGLE_DEFAULT_EMBEDDING_MODEL: &str = "gemini-embedding-001";
+pub const GOOGLE_DEFAULT_IMAGE_MODEL: &str = "imagen-4.0-generate-001";
 pub const GOOGLE_DOC_URL: &str = "https://ai.google.dev/gemini-api/docs/models";
 
 #[derive(Debug, serde::Serialize)]
@@ -52,6 +61,8 @@ pub struct GoogleProvider {
     model: ModelConfig,
     #[serde(skip)]
     name: String,
+    /// Ground responses with Gemini's built-in Google Search tool
+    google_search: bool,
 }
 
 impl GoogleProvider {
@@ -72,10 +83,15 @@ impl GoogleProvider {
         let api_client =
             ApiClient::new(host, auth)?.with_header("Content-Type", "application/json")?;
 
+        let google_search = config
+            .get_param::<bool>("GOOGLE_SEARCH_GROUNDING")
+            .unwrap_or(false);
+
         Ok(Self {
             api_client,
             model,
             name: Self::metadata().name,
+            google_search,
         })
     }
 
@@ -99,6 +115,7 @@ impl Provider for GoogleProvider {
             vec![
                 ConfigKey::new("GOOGLE_API_KEY", true, true, None),
                 ConfigKey::new("GOOGLE_HOST", false, false, Some(GOOGLE_API_HOST)),
+                ConfigKey::new("GOOGLE_SEARCH_GROUNDING", false, false, Some("false")),
             ],
         )
     }
@@ -122,7 +139,10 @@ impl Provider for GoogleProvider {
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(model_config, system, messages, tools)?;
+        let mut payload = create_request(model_config, system, messages, tools)?;
+        if self.google_search {
+            add_google_search_tool(&mut payload);
+        }
         let mut log = RequestLog::start(model_config, &payload)?;
 
         let response = self
@@ -143,6 +163,38 @@ impl Provider for GoogleProvider {
         Ok((message, provider_usage))
     }
 
+    async fn count_tokens(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<usize, ProviderError> {
+        let payload = create_count_tokens_request(&self.model, system, messages, tools)?;
+        let path = format!("v1beta/models/{}:countTokens", self.model.model_name);
+        let response = self.api_client.response_post(&path, &payload).await?;
+        let response = handle_response_google_compat(response).await?;
+
+        response
+            .get("totalTokens")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .ok_or_else(|| {
+                ProviderError::RequestFailed("Missing totalTokens in response".to_string())
+            })
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        true
+    }
+
+    fn supports_audio_input(&self) -> bool {
+        true
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.embed(texts, &EmbeddingOptions::from_config()).await
+    }
+
     async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
         let response = self.api_client.response_get("v1beta/models").await?;
         let json: serde_json::Value = response.json().await?;
@@ -159,3 +211,105 @@ impl Provider for GoogleProvider {
         Ok(Some(models))
     }
 }
+
+#[async_trait]
+impl EmbeddingProvider for GoogleProvider {
+    fn default_embedding_model(&self) -> &str {
+        GOOGLE_DEFAULT_EMBEDDING_MODEL
+    }
+
+    fn max_embedding_batch_size(&self) -> usize {
+        100
+    }
+
+    async fn embed_batch(
+        &self,
+        texts: &[String],
+        model: &str,
+        dimensions: Option<usize>,
+    ) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let requests: Vec<Value> = texts
+            .iter()
+            .map(|text| {
+                let mut request = json!({
+                    "model": format!("models/{}", model),
+                    "content": {"parts": [{"text": text}]},
+                });
+                if let Some(dimensions) = dimensions {
+                    request["outputDimensionality"] = json!(dimensions);
+                }
+                request
+            })
+            .collect();
+        let payload = json!({"requests": requests});
+
+        let path = format!("v1beta/models/{}:batchEmbedContents", model);
+        let response = self
+            .with_retry(|| async {
+                let response = self.api_client.response_post(&path, &payload).await?;
+                handle_response_google_compat(response).await
+            })
+            .await?;
+
+        let embeddings = response
+            .get("embeddings")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| {
+                ProviderError::RequestFailed("Missing embeddings in response".to_string())
+            })?;
+        parse_embedding_vectors(embeddings.iter().map(|e| &e["values"]))
+    }
+}
+
+#[async_trait]
+impl ImageGenerationProvider for GoogleProvider {
+    fn default_image_model(&self) -> &str {
+        GOOGLE_DEFAULT_IMAGE_MODEL
+    }
+
+    async fn generate_images(
+        &self,
+        prompt: &str,
+        options: &ImageGenerationOptions,
+    ) -> Result<Vec<MessageContent>, ProviderError> {
+        let model = options
+            .model
+            .as_deref()
+            .unwrap_or(GOOGLE_DEFAULT_IMAGE_MODEL);
+        let mut parameters = json!({"sampleCount": options.count.unwrap_or(1)});
+        if let Some(aspect_ratio) = options.aspect_ratio() {
+            parameters["aspectRatio"] = json!(aspect_ratio);
+        }
+        if let Some(negative_prompt) = &options.negative_prompt {
+            parameters["negativePrompt"] = json!(negative_prompt);
+        }
+        let payload = json!({
+            "instances": [{"prompt": prompt}],
+            "parameters": parameters,
+        });
+
+        let path = format!("v1beta/models/{}:predict", model);
+        let response = self
+            .with_retry(|| async {
+                let response = self.api_client.response_post(&path, &payload).await?;
+                handle_response_google_compat(response).await
+            })
+            .await?;
+
+        let predictions = response
+            .get("predictions")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| {
+                ProviderError::RequestFailed("Missing predictions in response".to_string())
+            })?;
+        let mime_type = predictions
+            .first()
+            .and_then(|p| p.get("mimeType"))
+            .and_then(|m| m.as_str())
+            .unwrap_or("image/png");
+        parse_base64_images(
+            predictions.iter().map(|p| &p["bytesBase64Encoded"]),
+            mime_type,
+        )
+    }
+}
@@ -0,0 +1,303 @@
+//! The HTTP client shared by providers.
+//!
+//! Providers send requests through one `reqwest::Client` so connections are pooled and reused
+//! across providers and sessions, and so connection settings are configured in one place:
+//!
+//! - GOOSE_HTTP_POOL_MAX_IDLE_PER_HOST: idle connections kept per host (unlimited by default)
+//! - GOOSE_HTTP_POOL_IDLE_TIMEOUT: seconds an idle connection is kept (default 90)
+//! - GOOSE_HTTP_TCP_KEEPALIVE: seconds between TCP keep-alive probes, 0 to disable (default 60)
+//! - GOOSE_CONNECT_TIMEOUT_MS: milliseconds allowed to establish a connection (no limit by default)
+//! - GOOSE_HTTP2_PRIOR_KNOWLEDGE: speak HTTP/2 without negotiating it, for h2c endpoints
+//! - GOOSE_HTTP_PROXY: proxy URL for all requests, with GOOSE_NO_PROXY listing hosts to reach
+//!   directly, e.g. `localhost,*.internal`
+//! - GOOSE_CA_BUNDLE: PEM file of extra trusted CA certificates, e.g. for a TLS-intercepting proxy
+//! - GOOSE_INSECURE_TLS: skip certificate verification entirely; for debugging only
+//!
+//! HTTP/2 is otherwise negotiated with each server. The client certificate and CA settings of
+//! [`TlsConfig`] apply to the shared client as well; providers with their own TLS settings or
+//! connect timeout get a separate client from [`dedicated_client`]. [`configure_aws_loader`] applies the proxy and CA
+//! bundle to the AWS SDK clients used by Bedrock and SageMaker.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use aws_config::ConfigLoader;
+use aws_smithy_http_client::proxy::ProxyConfig;
+use aws_smithy_http_client::tls::{self, rustls_provider::CryptoMode, TlsContext, TrustStore};
+use aws_smithy_http_client::{Builder as AwsClientBuilder, Connector};
+use once_cell::sync::OnceCell;
+use reqwest::{Certificate, Client, ClientBuilder, NoProxy, Proxy};
+
+use super::api_client::TlsConfig;
+use crate::config::Config;
+
+/// Overall timeout for requests that don't set their own
+pub const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(600);
+
+static SHARED_CLIENT: OnceCell<Client> = OnceCell::new();
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpClientConfig {
+    pub pool_max_idle_per_host: Option<usize>,
+    pub pool_idle_timeout: Duration,
+    pub tcp_keepalive: Option<Duration>,
+    pub connect_timeout: Option<Duration>,
+    pub http2_prior_knowledge: bool,
+    pub proxy: Option<String>,
+    pub no_proxy: Option<String>,
+    pub ca_bundle: Option<PathBuf>,
+    pub insecure_tls: bool,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: Duration::from_secs(90),
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            connect_timeout: None,
+            http2_prior_knowledge: false,
+            proxy: None,
+            no_proxy: None,
+            ca_bundle: None,
+            insecure_tls: false,
+        }
+    }
+}
+
+impl HttpClientConfig {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let defaults = Self::default();
+        Self {
+            pool_max_idle_per_host: config.get_param("GOOSE_HTTP_POOL_MAX_IDLE_PER_HOST").ok(),
+            pool_idle_timeout: config
+                .get_param::<u64>("GOOSE_HTTP_POOL_IDLE_TIMEOUT")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.pool_idle_timeout),
+            tcp_keepalive: match config.get_param::<u64>("GOOSE_HTTP_TCP_KEEPALIVE") {
+                Ok(0) => None,
+                Ok(secs) => Some(Duration::from_secs(secs)),
+                Err(_) => defaults.tcp_keepalive,
+            },
+            connect_timeout: config
+                .get_param::<u64>("GOOSE_CONNECT_TIMEOUT_MS")
+                .ok()
+                .map(Duration::from_millis),
+            http2_prior_knowledge: config
+                .get_param("GOOSE_HTTP2_PRIOR_KNOWLEDGE")
+                .unwrap_or(false),
+            proxy: config.get_param("GOOSE_HTTP_PROXY").ok(),
+            no_proxy: config.get_param("GOOSE_NO_PROXY").ok(),
+            ca_bundle: config
+                .get_param::<String>("GOOSE_CA_BUNDLE")
+                .ok()
+                .map(PathBuf::from),
+            insecure_tls: config.get_param("GOOSE_INSECURE_TLS").unwrap_or(false),
+        }
+    }
+
+    /// A client builder with these connection settings and the configured TLS settings
+    pub fn client_builder(&self) -> Result<ClientBuilder> {
+        let mut builder = Client::builder()
+            .timeout(DEFAULT_HTTP_TIMEOUT)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .tcp_keepalive(self.tcp_keepalive);
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(max_idle) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+
+        if let Some(proxy) = &self.proxy {
+            let no_proxy = self.no_proxy.as_deref().and_then(NoProxy::from_string);
+            builder = builder.proxy(Proxy::all(proxy)?.no_proxy(no_proxy));
+        }
+        if let Some(pem) = self.read_ca_bundle()? {
+            for cert in Certificate::from_pem_bundle(&pem)
+                .map_err(|e| anyhow::anyhow!("Failed to parse GOOSE_CA_BUNDLE: {}", e))?
+            {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        if self.insecure_tls {
+            tracing::warn!("GOOSE_INSECURE_TLS is set; TLS certificates will not be verified");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        if let Some(tls_config) = TlsConfig::from_config()? {
+            builder = configure_tls(builder, &tls_config)?;
+        }
+        Ok(builder)
+    }
+}
+
+impl HttpClientConfig {
+    fn read_ca_bundle(&self) -> Result<Option<Vec<u8>>> {
+        self.ca_bundle
+            .as_ref()
+            .map(|path| {
+                fs::read(path).map_err(|e| {
+                    anyhow::anyhow!("Failed to read CA bundle {}: {}", path.display(), e)
+                })
+            })
+            .transpose()
+    }
+
+    /// Give an AWS SDK config loader an HTTP client with this proxy and CA bundle. The loader
+    /// keeps the SDK's default client when neither is configured.
+    pub fn configure_aws_loader(&self, loader: ConfigLoader) -> Result<ConfigLoader> {
+        if self.insecure_tls {
+            tracing::warn!("GOOSE_INSECURE_TLS is not supported by AWS clients and is ignored");
+        }
+        if self.proxy.is_none() && self.ca_bundle.is_none() {
+            return Ok(loader);
+        }
+
+        let proxy_config = match &self.proxy {
+            Some(proxy) => {
+                let config = ProxyConfig::all(proxy.as_str())
+                    .map_err(|e| anyhow::anyhow!("Invalid GOOSE_HTTP_PROXY: {}", e))?;
+                match &self.no_proxy {
+                    Some(rules) => config.no_proxy(rules),
+                    None => config,
+                }
+            }
+            None => ProxyConfig::disabled(),
+        };
+        let trust_store = match self.read_ca_bundle()? {
+            Some(pem) => TrustStore::default().with_pem_certificate(pem),
+            None => TrustStore::default(),
+        };
+        let tls_context = TlsContext::builder()
+            .with_trust_store(trust_store)
+            .build()
+            .map_err(|e| anyhow::anyhow!("Invalid GOOSE_CA_BUNDLE: {}", e))?;
+
+        Ok(
+            loader.http_client(AwsClientBuilder::new().build_with_connector_fn(
+                move |settings, runtime_components| {
+                    let mut builder = Connector::builder().proxy_config(proxy_config.clone());
+                    if let Some(settings) = settings {
+                        builder = builder.connector_settings(settings.clone());
+                    }
+                    if let Some(sleep) = runtime_components.and_then(|rc| rc.sleep_impl()) {
+                        builder = builder.sleep_impl(sleep);
+                    }
+                    builder
+                        .tls_provider(tls::Provider::Rustls(CryptoMode::AwsLc))
+                        .tls_context(tls_context.clone())
+                        .build()
+                },
+            )),
+        )
+    }
+}
+
+/// Configure TLS settings on a reqwest ClientBuilder
+fn configure_tls(mut builder: ClientBuilder, tls_config: &TlsConfig) -> Result<ClientBuilder> {
+    if tls_config.is_configured() {
+        // Load client identity (certificate + private key)
+        if let Some(identity) = tls_config.load_identity()? {
+            builder = builder.identity(identity);
+        }
+
+        // Load CA certificates
+        let ca_certs = tls_config.load_ca_certificates()?;
+        for ca_cert in ca_certs {
+            builder = builder.add_root_certificate(ca_cert);
+        }
+    }
+    Ok(builder)
+}
+
+/// The process-wide client, built from [`HttpClientConfig::from_config`] on first use.
+/// Cloning it is cheap and shares the connection pool.
+pub fn shared_client() -> Result<Client> {
+    SHARED_CLIENT
+        .get_or_try_init(|| -> Result<Client> {
+            Ok(HttpClientConfig::from_config().client_builder()?.build()?)
+        })
+        .cloned()
+}
+
+/// A client of its own for a provider that presents `tls_config`, e.g. a client certificate for
+/// an internal inference gateway, or that sets its own `connect_timeout`. It has the shared
+/// client's other connection settings but not its connection pool, since pooled connections are
+/// bound to the identity they were opened with.
+pub fn dedicated_client(
+    tls_config: Option<&TlsConfig>,
+    connect_timeout: Option<Duration>,
+) -> Result<Client> {
+    let mut config = HttpClientConfig::from_config();
+    if connect_timeout.is_some() {
+        config.connect_timeout = connect_timeout;
+    }
+    let mut builder = config.client_builder()?;
+    if let Some(tls_config) = tls_config {
+        builder = configure_tls(builder, tls_config)?;
+    }
+    Ok(builder.build()?)
+}
+
+/// Apply the configured proxy and CA bundle to an AWS SDK config loader
+pub fn configure_aws_loader(loader: ConfigLoader) -> Result<ConfigLoader> {
+    HttpClientConfig::from_config().configure_aws_loader(loader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_client_config_from_env() {
+        temp_env::with_vars(
+            [
+                ("GOOSE_HTTP_POOL_MAX_IDLE_PER_HOST", Some("4")),
+                ("GOOSE_HTTP_POOL_IDLE_TIMEOUT", Some("30")),
+                ("GOOSE_HTTP_TCP_KEEPALIVE", Some("0")),
+                ("GOOSE_HTTP2_PRIOR_KNOWLEDGE", None),
+                ("GOOSE_CONNECT_TIMEOUT_MS", Some("2500")),
+            ],
+            || {
+                let config = HttpClientConfig::from_config();
+                assert_eq!(config.pool_max_idle_per_host, Some(4));
+                assert_eq!(config.pool_idle_timeout, Duration::from_secs(30));
+                assert_eq!(config.tcp_keepalive, None);
+                assert!(!config.http2_prior_knowledge);
+                assert_eq!(config.connect_timeout, Some(Duration::from_millis(2500)));
+                assert!(config.client_builder().unwrap().build().is_ok());
+            },
+        );
+    }
+
+    #[test]
+    fn test_proxy_and_ca_bundle() {
+        let config = HttpClientConfig {
+            proxy: Some("http://proxy.internal:3128".to_string()),
+            no_proxy: Some("localhost,*.internal".to_string()),
+            ..Default::default()
+        };
+        assert!(config.client_builder().unwrap().build().is_ok());
+        let loader = aws_config::defaults(aws_config::BehaviorVersion::latest());
+        assert!(config.configure_aws_loader(loader).is_ok());
+
+        let missing = HttpClientConfig {
+            ca_bundle: Some(PathBuf::from("/nonexistent/ca.pem")),
+            ..Default::default()
+        };
+        assert!(missing.client_builder().is_err());
+
+        let invalid = HttpClientConfig {
+            proxy: Some("not a url".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid.client_builder().is_err());
+    }
+}
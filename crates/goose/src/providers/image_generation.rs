@@ -0,0 +1,190 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+use super::errors::ProviderError;
+use crate::conversation::message::MessageContent;
+use crate::model::ModelConfig;
+
+/// Options for an image generation request. Unset fields fall back to the provider's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImageGenerationOptions {
+    /// Image model to use instead of the provider default
+    pub model: Option<String>,
+    /// Output size as `WIDTHxHEIGHT`, e.g. `1024x1024`
+    pub size: Option<String>,
+    /// Number of images to generate
+    pub count: Option<usize>,
+    /// What the image should not contain, for models that support it
+    pub negative_prompt: Option<String>,
+}
+
+impl ImageGenerationOptions {
+    /// Read options from GOOSE_IMAGE_MODEL and GOOSE_IMAGE_SIZE
+    pub fn from_config() -> Self {
+        let config = crate::config::Config::global();
+        Self {
+            model: config.get_param("GOOSE_IMAGE_MODEL").ok(),
+            size: config.get_param("GOOSE_IMAGE_SIZE").ok(),
+            count: None,
+            negative_prompt: None,
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn with_size(mut self, size: Option<String>) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_count(mut self, count: Option<usize>) -> Self {
+        self.count = count;
+        self
+    }
+
+    pub fn with_negative_prompt(mut self, negative_prompt: Option<String>) -> Self {
+        self.negative_prompt = negative_prompt;
+        self
+    }
+
+    /// Requested size as (width, height), if set and well formed
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        let (width, height) = self.size.as_deref()?.split_once('x')?;
+        Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+    }
+
+    /// Requested size reduced to an aspect ratio such as `16:9`
+    pub fn aspect_ratio(&self) -> Option<String> {
+        let (width, height) = self.dimensions()?;
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let divisor = gcd(width, height);
+        Some(format!("{}:{}", width / divisor, height / divisor))
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// A source of generated images
+#[async_trait]
+pub trait ImageGenerationProvider: Send + Sync {
+    /// Model used when the options don't name one
+    fn default_image_model(&self) -> &str;
+
+    /// Generate images for `prompt`, each returned as [`MessageContent::Image`]
+    async fn generate_images(
+        &self,
+        prompt: &str,
+        options: &ImageGenerationOptions,
+    ) -> Result<Vec<MessageContent>, ProviderError>;
+}
+
+/// Create an image generation provider by name using the same configuration keys as the
+/// corresponding chat provider
+pub async fn create_image_generation_provider(
+    name: &str,
+) -> Result<Arc<dyn ImageGenerationProvider>> {
+    use super::{bedrock::BedrockProvider, google::GoogleProvider, openai::OpenAiProvider};
+
+    let provider: Arc<dyn ImageGenerationProvider> = match name {
+        "openai" => Arc::new(
+            OpenAiProvider::from_env(ModelConfig::new_or_fail(
+                super::openai::OPEN_AI_DEFAULT_MODEL,
+            ))
+            .await?,
+        ),
+        "google" => Arc::new(
+            GoogleProvider::from_env(ModelConfig::new_or_fail(
+                super::google::GOOGLE_DEFAULT_MODEL,
+            ))
+            .await?,
+        ),
+        "aws_bedrock" => Arc::new(
+            BedrockProvider::from_env(ModelConfig::new_or_fail(
+                super::bedrock::BEDROCK_DEFAULT_MODEL,
+            ))
+            .await?,
+        ),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Provider {} does not support image generation",
+                name
+            ))
+        }
+    };
+    Ok(provider)
+}
+
+/// Convert base64 strings found in a response into image content
+pub(crate) fn parse_base64_images<'a>(
+    images: impl IntoIterator<Item = &'a Value>,
+    mime_type: &str,
+) -> Result<Vec<MessageContent>, ProviderError> {
+    let images = images
+        .into_iter()
+        .map(|image| {
+            image
+                .as_str()
+                .map(|data| MessageContent::image(data, mime_type))
+                .ok_or_else(|| {
+                    ProviderError::RequestFailed("Invalid image data in response".to_string())
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if images.is_empty() {
+        return Err(ProviderError::RequestFailed(
+            "No images in response".to_string(),
+        ));
+    }
+    Ok(images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_size_parsing() {
+        let options = ImageGenerationOptions::default().with_size(Some("1792x1024".to_string()));
+        assert_eq!(options.dimensions(), Some((1792, 1024)));
+        assert_eq!(options.aspect_ratio(), Some("7:4".to_string()));
+
+        let options = ImageGenerationOptions::default().with_size(Some("1024x1024".to_string()));
+        assert_eq!(options.aspect_ratio(), Some("1:1".to_string()));
+
+        let options = ImageGenerationOptions::default().with_size(Some("large".to_string()));
+        assert_eq!(options.dimensions(), None);
+        assert_eq!(ImageGenerationOptions::default().aspect_ratio(), None);
+    }
+
+    #[test]
+    fn test_parse_base64_images() {
+        let response = json!({"images": ["aGVsbG8=", "d29ybGQ="]});
+        let images =
+            parse_base64_images(response["images"].as_array().unwrap(), "image/png").unwrap();
+        assert_eq!(images.len(), 2);
+        let MessageContent::Image(image) = &images[0] else {
+            panic!("Expected image content");
+        };
+        assert_eq!(image.data, "aGVsbG8=");
+        assert_eq!(image.mime_type, "image/png");
+
+        assert!(parse_base64_images(&[json!(42)], "image/png").is_err());
+        assert!(parse_base64_images(&[], "image/png").is_err());
+    }
+}
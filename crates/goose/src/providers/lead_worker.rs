@@ -1,16 +1,113 @@
 use anyhow::Result;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use std::ops::Deref;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use super::base::{LeadWorkerProviderTrait, Provider, ProviderMetadata, ProviderUsage};
+use super::base::{LeadWorkerProviderTrait, ModelInfo, Provider, ProviderMetadata, ProviderUsage};
 use super::errors::ProviderError;
+use crate::config::Config;
 use crate::conversation::message::{Message, MessageContent};
 use crate::model::ModelConfig;
 use rmcp::model::Tool;
 use rmcp::model::{Content, RawContent};
 
+pub const LEAD_MODEL_CONFIG_KEY: &str = "GOOSE_LEAD_MODEL";
+pub const LEAD_PROVIDER_CONFIG_KEY: &str = "GOOSE_LEAD_PROVIDER";
+pub const LEAD_TURNS_CONFIG_KEY: &str = "GOOSE_LEAD_TURNS";
+pub const LEAD_FAILURE_THRESHOLD_CONFIG_KEY: &str = "GOOSE_LEAD_FAILURE_THRESHOLD";
+pub const LEAD_FALLBACK_TURNS_CONFIG_KEY: &str = "GOOSE_LEAD_FALLBACK_TURNS";
+
+pub const DEFAULT_LEAD_TURNS: usize = 3;
+pub const DEFAULT_FAILURE_THRESHOLD: usize = 2;
+pub const DEFAULT_FALLBACK_TURNS: usize = 2;
+
+fn default_lead_turns() -> usize {
+    DEFAULT_LEAD_TURNS
+}
+
+fn default_failure_threshold() -> usize {
+    DEFAULT_FAILURE_THRESHOLD
+}
+
+fn default_fallback_turns() -> usize {
+    DEFAULT_FALLBACK_TURNS
+}
+
+/// A lead model for the first turns and for recovering from worker failures. The worker is the
+/// model the provider was created with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeadWorkerSettings {
+    pub lead_model: String,
+    /// The worker's provider if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lead_provider: Option<String>,
+    /// Turns on the lead model before handing over to the worker
+    #[serde(default = "default_lead_turns")]
+    pub lead_turns: usize,
+    /// Consecutive failed worker turns that switch back to the lead model
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: usize,
+    /// Turns on the lead model after such a switch
+    #[serde(default = "default_fallback_turns")]
+    pub fallback_turns: usize,
+}
+
+impl LeadWorkerSettings {
+    pub fn new(lead_model: impl Into<String>) -> Self {
+        Self {
+            lead_model: lead_model.into(),
+            lead_provider: None,
+            lead_turns: DEFAULT_LEAD_TURNS,
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            fallback_turns: DEFAULT_FALLBACK_TURNS,
+        }
+    }
+
+    /// The settings under GOOSE_LEAD_MODEL and the other GOOSE_LEAD_* keys, if a lead model is set
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        let lead_model: String = config.get_param(LEAD_MODEL_CONFIG_KEY).ok()?;
+        Some(Self {
+            lead_model,
+            lead_provider: config.get_param(LEAD_PROVIDER_CONFIG_KEY).ok(),
+            lead_turns: config
+                .get_param(LEAD_TURNS_CONFIG_KEY)
+                .unwrap_or(DEFAULT_LEAD_TURNS),
+            failure_threshold: config
+                .get_param(LEAD_FAILURE_THRESHOLD_CONFIG_KEY)
+                .unwrap_or(DEFAULT_FAILURE_THRESHOLD),
+            fallback_turns: config
+                .get_param(LEAD_FALLBACK_TURNS_CONFIG_KEY)
+                .unwrap_or(DEFAULT_FALLBACK_TURNS),
+        })
+    }
+
+    /// Whether `provider` already switches between models as these settings ask
+    pub fn is_applied_to(&self, provider: &dyn Provider) -> bool {
+        provider.as_lead_worker().is_some_and(|lead_worker| {
+            lead_worker.get_model_info().0 == self.lead_model
+                && lead_worker.get_settings()
+                    == (self.lead_turns, self.failure_threshold, self.fallback_turns)
+        })
+    }
+
+    pub fn wrap(
+        &self,
+        lead_provider: Arc<dyn Provider>,
+        worker_provider: Arc<dyn Provider>,
+    ) -> LeadWorkerProvider {
+        LeadWorkerProvider::new_with_settings(
+            lead_provider,
+            worker_provider,
+            self.lead_turns,
+            self.failure_threshold,
+            self.fallback_turns,
+        )
+    }
+}
+
 /// A provider that switches between a lead model and a worker model based on turn count
 /// and can fallback to lead model on consecutive failures
 pub struct LeadWorkerProvider {
@@ -40,11 +137,11 @@ impl LeadWorkerProvider {
         Self {
             lead_provider,
             worker_provider,
-            lead_turns: lead_turns.unwrap_or(3),
+            lead_turns: lead_turns.unwrap_or(DEFAULT_LEAD_TURNS),
             turn_count: Arc::new(Mutex::new(0)),
             failure_count: Arc::new(Mutex::new(0)),
-            max_failures_before_fallback: 2, // Fallback after 2 consecutive failures
-            fallback_turns: 2,               // Use lead model for 2 turns when in fallback mode
+            max_failures_before_fallback: DEFAULT_FAILURE_THRESHOLD,
+            fallback_turns: DEFAULT_FALLBACK_TURNS,
             in_fallback_mode: Arc::new(Mutex::new(false)),
             fallback_remaining: Arc::new(Mutex::new(0)),
         }
@@ -442,6 +539,34 @@ impl Provider for LeadWorkerProvider {
         }
     }
 
+    async fn count_tokens(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<usize, ProviderError> {
+        let provider = self.get_active_provider().await;
+        provider.count_tokens(system, messages, tools).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        // Combine models from both providers, tolerating one side not supporting listing
+        let lead_models = self.lead_provider.list_models().await;
+        let worker_models = self.worker_provider.list_models().await;
+
+        let mut all_models = match (lead_models, worker_models) {
+            (Ok(mut lead), Ok(worker)) => {
+                lead.extend(worker);
+                lead
+            }
+            (Ok(models), Err(_)) | (Err(_), Ok(models)) => models,
+            (Err(e), Err(_)) => return Err(e),
+        };
+        all_models.sort_by(|a, b| a.name.cmp(&b.name));
+        all_models.dedup_by(|a, b| a.name == b.name);
+        Ok(all_models)
+    }
+
     fn supports_embeddings(&self) -> bool {
         // Support embeddings if either provider supports them
         self.lead_provider.supports_embeddings() || self.worker_provider.supports_embeddings()
@@ -476,11 +601,31 @@ impl Provider for LeadWorkerProvider {
         provider.stream(system, messages, tools).await
     }
 
+    async fn stream_events(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<super::stream_event::EventStream, ProviderError> {
+        let provider = self.get_active_provider().await;
+        provider.stream_events(system, messages, tools).await
+    }
+
     /// Check if the active provider supports streaming
     fn supports_streaming(&self) -> bool {
         // Check both providers - if either supports streaming, we support it
         self.lead_provider.supports_streaming() || self.worker_provider.supports_streaming()
     }
+
+    fn supports_audio_input(&self) -> bool {
+        // Either model may handle the turn, so both must accept audio
+        self.lead_provider.supports_audio_input() && self.worker_provider.supports_audio_input()
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.lead_provider.supports_structured_output()
+            && self.worker_provider.supports_structured_output()
+    }
 }
 
 #[cfg(test)]
@@ -575,6 +720,35 @@ mod tests {
         assert_eq!(usage.model, "lead");
     }
 
+    #[tokio::test]
+    async fn test_lead_worker_settings() {
+        let settings: LeadWorkerSettings = serde_json::from_value(
+            serde_json::json!({"lead_model": "lead-model", "lead_turns": 1}),
+        )
+        .unwrap();
+        assert_eq!(settings.failure_threshold, DEFAULT_FAILURE_THRESHOLD);
+        assert_eq!(settings.fallback_turns, DEFAULT_FALLBACK_TURNS);
+
+        let lead_provider = Arc::new(MockProvider {
+            name: "lead".to_string(),
+            model_config: ModelConfig::new_or_fail("lead-model"),
+        });
+        let worker_provider: Arc<dyn Provider> = Arc::new(MockProvider {
+            name: "worker".to_string(),
+            model_config: ModelConfig::new_or_fail("worker-model"),
+        });
+        assert!(!settings.is_applied_to(worker_provider.as_ref()));
+
+        let provider = settings.wrap(lead_provider, worker_provider);
+        assert!(settings.is_applied_to(&provider));
+        assert!(!LeadWorkerSettings::new("lead-model").is_applied_to(&provider));
+
+        let (_message, usage) = provider.complete("system", &[], &[]).await.unwrap();
+        assert_eq!(usage.model, "lead");
+        let (_message, usage) = provider.complete("system", &[], &[]).await.unwrap();
+        assert_eq!(usage.model, "worker");
+    }
+
     #[tokio::test]
     async fn test_technical_failure_retry() {
         let lead_provider = Arc::new(MockFailureProvider {
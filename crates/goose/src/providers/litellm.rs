@@ -3,12 +3,15 @@ use async_trait::async_trait;
 use serde_json::{json, Value};
 use std::collections::HashMap;
 
-use super::api_client::{ApiClient, AuthMethod};
+use super::api_client::{ApiClient, AuthMethod, TlsConfig};
 use super::base::{ConfigKey, ModelInfo, Provider, ProviderMetadata, ProviderUsage};
-use super::embedding::EmbeddingCapable;
+use super::embedding::{parse_embedding_vectors, EmbeddingOptions, EmbeddingProvider};
 use super::errors::ProviderError;
 use super::retry::ProviderRetry;
-use super::utils::{get_model, handle_response_openai_compat, ImageFormat, RequestLog};
+use super::timeouts::RequestTimeouts;
+use super::utils::{
+    get_model, get_system_fingerprint, handle_response_openai_compat, ImageFormat, RequestLog,
+};
 use crate::conversation::message::Message;
 
 use crate::model::ModelConfig;
@@ -16,6 +19,7 @@ use rmcp::model::Tool;
 
 pub const LITELLM_DEFAULT_MODEL: &str = "gpt-4o-mini";
 pub const LITELLM_DOC_URL: &str = "https://docs.litellm.ai/docs/";
+const LITELLM_DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
 
 #[derive(Debug, serde::Serialize)]
 pub struct LiteLLMProvider {
@@ -44,7 +48,7 @@ impl LiteLLMProvider {
             .get("LITELLM_CUSTOM_HEADERS")
             .cloned()
             .map(parse_custom_headers);
-        let timeout_secs: u64 = config.get_param("LITELLM_TIMEOUT").unwrap_or(600);
+        let timeouts = RequestTimeouts::from_config("LITELLM", std::time::Duration::from_secs(600));
 
         let auth = if api_key.is_empty() {
             AuthMethod::Custom(Box::new(NoAuth))
@@ -52,8 +56,10 @@ impl LiteLLMProvider {
             AuthMethod::BearerToken(api_key)
         };
 
-        let mut api_client =
-            ApiClient::with_timeout(host, auth, std::time::Duration::from_secs(timeout_secs))?;
+        let mut api_client = ApiClient::with_timeouts(host, auth, &timeouts)?;
+        if let Some(tls_config) = TlsConfig::from_config_prefix("LITELLM")? {
+            api_client = api_client.with_tls_config(&tls_config)?;
+        }
 
         if let Some(headers) = custom_headers {
             let mut header_map = reqwest::header::HeaderMap::new();
@@ -105,6 +111,8 @@ impl LiteLLMProvider {
 
                 let mut model_info_obj = ModelInfo::new(model_name, context_length);
                 model_info_obj.supports_cache_control = supports_cache_control;
+                model_info_obj.supports_tools = model_info["supports_function_calling"].as_bool();
+                model_info_obj.supports_vision = model_info["supports_vision"].as_bool();
                 models.push(model_info_obj);
             }
         }
@@ -198,13 +206,21 @@ impl Provider for LiteLLMProvider {
         let response_model = get_model(&response);
         let mut log = RequestLog::start(model_config, &payload)?;
         log.write(&response, Some(&usage))?;
-        Ok((message, ProviderUsage::new(response_model, usage)))
+        Ok((
+            message,
+            ProviderUsage::new(response_model, usage)
+                .with_system_fingerprint(get_system_fingerprint(&response)),
+        ))
     }
 
     fn supports_embeddings(&self) -> bool {
         true
     }
 
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.embed(texts, &EmbeddingOptions::from_config()).await
+    }
+
     async fn supports_cache_control(&self) -> bool {
         if let Ok(models) = self.fetch_models().await {
             if let Some(model_info) = models.iter().find(|m| m.name == self.model.model_name) {
@@ -215,6 +231,10 @@ impl Provider for LiteLLMProvider {
         self.model.model_name.to_lowercase().contains("claude")
     }
 
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        self.fetch_models().await
+    }
+
     async fn fetch_supported_models(&self) -> Result<Option<Vec<String>>, ProviderError> {
         match self.fetch_models().await {
             Ok(models) => {
@@ -230,40 +250,44 @@ impl Provider for LiteLLMProvider {
 }
 
 #[async_trait]
-impl EmbeddingCapable for LiteLLMProvider {
-    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, anyhow::Error> {
-        let embedding_model = std::env::var("GOOSE_EMBEDDING_MODEL")
-            .unwrap_or_else(|_| "text-embedding-3-small".to_string());
+impl EmbeddingProvider for LiteLLMProvider {
+    fn default_embedding_model(&self) -> &str {
+        LITELLM_DEFAULT_EMBEDDING_MODEL
+    }
+
+    fn max_embedding_batch_size(&self) -> usize {
+        2048
+    }
 
-        let payload = json!({
+    async fn embed_batch(
+        &self,
+        texts: &[String],
+        model: &str,
+        dimensions: Option<usize>,
+    ) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let mut payload = json!({
             "input": texts,
-            "model": embedding_model,
+            "model": model,
             "encoding_format": "float"
         });
+        if let Some(dimensions) = dimensions {
+            payload["dimensions"] = json!(dimensions);
+        }
 
         let response = self
-            .api_client
-            .response_post("v1/embeddings", &payload)
+            .with_retry(|| async {
+                let response = self
+                    .api_client
+                    .response_post("v1/embeddings", &payload)
+                    .await?;
+                handle_response_openai_compat(response).await
+            })
             .await?;
-        let response_text = response.text().await?;
-        let response_json: Value = serde_json::from_str(&response_text)?;
-
-        let data = response_json["data"]
-            .as_array()
-            .ok_or_else(|| anyhow::anyhow!("Missing data field"))?;
-
-        let mut embeddings = Vec::new();
-        for item in data {
-            let embedding: Vec<f32> = item["embedding"]
-                .as_array()
-                .ok_or_else(|| anyhow::anyhow!("Missing embedding field"))?
-                .iter()
-                .map(|v| v.as_f64().unwrap_or(0.0) as f32)
-                .collect();
-            embeddings.push(embedding);
-        }
 
-        Ok(embeddings)
+        let data = response["data"].as_array().ok_or_else(|| {
+            ProviderError::RequestFailed("Missing data field in embedding response".to_string())
+        })?;
+        parse_embedding_vectors(data.iter().map(|item| &item["embedding"]))
     }
 }
 
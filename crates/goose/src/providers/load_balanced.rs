@@ -0,0 +1,590 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::base::{MessageStream, Provider, ProviderMetadata, ProviderUsage};
+use super::errors::ProviderError;
+use super::stream_event::EventStream;
+use crate::conversation::message::Message;
+use crate::model::ModelConfig;
+use rmcp::model::Tool;
+
+const DEFAULT_FAILURE_THRESHOLD: usize = 3;
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Health and usage counters for a single endpoint
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EndpointStats {
+    pub name: String,
+    pub weight: usize,
+    pub requests: u64,
+    pub failures: u64,
+    pub healthy: bool,
+}
+
+impl EndpointStats {
+    /// Fraction of requests to this endpoint that failed
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.requests as f64
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct EndpointHealth {
+    requests: u64,
+    failures: u64,
+    consecutive_failures: usize,
+    unhealthy_until: Option<Instant>,
+}
+
+struct Endpoint {
+    name: String,
+    provider: Arc<dyn Provider>,
+    weight: usize,
+    health: Mutex<EndpointHealth>,
+}
+
+impl Endpoint {
+    fn is_healthy(&self, now: Instant) -> bool {
+        let health = self.health.lock().unwrap();
+        health.unhealthy_until.is_none_or(|until| until <= now)
+    }
+}
+
+/// A provider that spreads requests across several configured instances of the same
+/// provider (e.g. multiple API keys, regions or Azure deployments).
+///
+/// Endpoints are chosen by weighted round-robin. An endpoint that fails
+/// `failure_threshold` times in a row is taken out of rotation for `cooldown`, and
+/// requests that hit an endpoint-level failure are retried on the next healthy endpoint.
+pub struct LoadBalancedProvider {
+    endpoints: Vec<Endpoint>,
+    total_weight: usize,
+    cursor: AtomicUsize,
+    failure_threshold: usize,
+    cooldown: Duration,
+}
+
+impl LoadBalancedProvider {
+    /// Create a new LoadBalancedProvider
+    ///
+    /// # Arguments
+    /// * `endpoints` - `(name, provider, weight)` tuples; a weight of 0 is treated as 1
+    pub fn new(endpoints: Vec<(String, Arc<dyn Provider>, usize)>) -> Result<Self> {
+        Self::new_with_settings(endpoints, DEFAULT_FAILURE_THRESHOLD, DEFAULT_COOLDOWN)
+    }
+
+    /// Create a new LoadBalancedProvider with custom health settings
+    ///
+    /// # Arguments
+    /// * `endpoints` - `(name, provider, weight)` tuples; a weight of 0 is treated as 1
+    /// * `failure_threshold` - Consecutive failures before an endpoint is marked unhealthy
+    /// * `cooldown` - How long an unhealthy endpoint is kept out of rotation
+    pub fn new_with_settings(
+        endpoints: Vec<(String, Arc<dyn Provider>, usize)>,
+        failure_threshold: usize,
+        cooldown: Duration,
+    ) -> Result<Self> {
+        if endpoints.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Load balanced provider requires at least one endpoint"
+            ));
+        }
+
+        let endpoints: Vec<Endpoint> = endpoints
+            .into_iter()
+            .map(|(name, provider, weight)| Endpoint {
+                name,
+                provider,
+                weight: weight.max(1),
+                health: Mutex::new(EndpointHealth::default()),
+            })
+            .collect();
+        let total_weight = endpoints.iter().map(|e| e.weight).sum();
+
+        Ok(Self {
+            endpoints,
+            total_weight,
+            cursor: AtomicUsize::new(0),
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+        })
+    }
+
+    /// Snapshot of per-endpoint request counts, error rates and health
+    pub fn endpoint_stats(&self) -> Vec<EndpointStats> {
+        let now = Instant::now();
+        self.endpoints
+            .iter()
+            .map(|endpoint| {
+                let health = endpoint.health.lock().unwrap();
+                EndpointStats {
+                    name: endpoint.name.clone(),
+                    weight: endpoint.weight,
+                    requests: health.requests,
+                    failures: health.failures,
+                    healthy: health.unhealthy_until.is_none_or(|until| until <= now),
+                }
+            })
+            .collect()
+    }
+
+    /// Order in which endpoints should be tried for the next request: the weighted
+    /// round-robin pick first, then the remaining healthy endpoints, then unhealthy ones
+    /// as a last resort so a request is never refused outright.
+    fn endpoint_order(&self) -> Vec<usize> {
+        let slot = self.cursor.fetch_add(1, Ordering::Relaxed) % self.total_weight;
+        let mut acc = 0;
+        let start = self
+            .endpoints
+            .iter()
+            .position(|endpoint| {
+                acc += endpoint.weight;
+                slot < acc
+            })
+            .unwrap_or(0);
+
+        let now = Instant::now();
+        let rotated = (0..self.endpoints.len()).map(|i| (start + i) % self.endpoints.len());
+        let (healthy, unhealthy): (Vec<usize>, Vec<usize>) =
+            rotated.partition(|&i| self.endpoints[i].is_healthy(now));
+        healthy.into_iter().chain(unhealthy).collect()
+    }
+
+    /// The first healthy endpoint, for calls that don't take part in the rotation, like
+    /// metadata queries. Leaves the round-robin cursor where it is.
+    fn first_healthy(&self) -> &Endpoint {
+        let now = Instant::now();
+        self.endpoints
+            .iter()
+            .find(|endpoint| endpoint.is_healthy(now))
+            .unwrap_or(&self.endpoints[0])
+    }
+
+    /// Open a stream with `open` on the next endpoint, failing over if the request is rejected
+    async fn open_stream<T, F, Fut>(&self, open: F) -> Result<T, ProviderError>
+    where
+        F: Fn(usize) -> Fut,
+        Fut: std::future::Future<Output = Result<T, ProviderError>>,
+    {
+        let mut last_error = None;
+        for index in self.endpoint_order() {
+            match open(index).await {
+                Ok(stream) => {
+                    self.record_success(index);
+                    return Ok(stream);
+                }
+                Err(error) => {
+                    self.record_failure(index, &error);
+                    if !is_endpoint_failure(&error) {
+                        return Err(error);
+                    }
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            ProviderError::ExecutionError("No load balanced endpoints available".to_string())
+        }))
+    }
+
+    fn record_success(&self, index: usize) {
+        let mut health = self.endpoints[index].health.lock().unwrap();
+        health.requests += 1;
+        health.consecutive_failures = 0;
+        health.unhealthy_until = None;
+    }
+
+    fn record_failure(&self, index: usize, error: &ProviderError) {
+        let endpoint = &self.endpoints[index];
+        let mut health = endpoint.health.lock().unwrap();
+        health.requests += 1;
+
+        if !is_endpoint_failure(error) {
+            return;
+        }
+
+        health.failures += 1;
+        health.consecutive_failures += 1;
+        if health.consecutive_failures >= self.failure_threshold {
+            let cooldown = match error {
+                ProviderError::RateLimitExceeded {
+                    retry_delay: Some(delay),
+                    ..
+                } => (*delay).max(self.cooldown),
+                _ => self.cooldown,
+            };
+            tracing::warn!(
+                "Load balanced endpoint '{}' marked unhealthy for {:?} after {} consecutive failures",
+                endpoint.name,
+                cooldown,
+                health.consecutive_failures
+            );
+            health.unhealthy_until = Some(Instant::now() + cooldown);
+        }
+    }
+}
+
+/// Errors that say something about the endpoint rather than the request. Only these
+/// count against an endpoint's health and trigger a retry on another endpoint.
+fn is_endpoint_failure(error: &ProviderError) -> bool {
+    matches!(
+        error,
+        ProviderError::Authentication(_)
+            | ProviderError::RateLimitExceeded { .. }
+            | ProviderError::ServerError(_)
+            | ProviderError::ExecutionError(_)
+    )
+}
+
+#[async_trait]
+impl Provider for LoadBalancedProvider {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            "load_balanced",
+            "Load Balanced Provider",
+            "A provider that distributes requests across several instances of the same provider",
+            "",     // No default model as this is determined by the wrapped providers
+            vec![], // No known models as this depends on wrapped providers
+            "",     // No doc link
+            vec![], // No config keys as configuration is done through wrapped providers
+        )
+    }
+
+    fn get_name(&self) -> &str {
+        self.endpoints[0].provider.get_name()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.endpoints[0].provider.get_model_config()
+    }
+
+    /// Calls outside the rotation, like metadata queries, go to the first healthy endpoint
+    fn inner_provider(&self) -> Option<&dyn Provider> {
+        Some(self.first_healthy().provider.as_ref())
+    }
+
+    async fn complete_with_model(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let mut last_error = None;
+        for index in self.endpoint_order() {
+            let endpoint = &self.endpoints[index];
+            tracing::debug!("Using load balanced endpoint '{}'", endpoint.name);
+
+            match endpoint
+                .provider
+                .complete_with_model(model_config, system, messages, tools)
+                .await
+            {
+                Ok(result) => {
+                    self.record_success(index);
+                    return Ok(result);
+                }
+                Err(error) => {
+                    self.record_failure(index, &error);
+                    if !is_endpoint_failure(&error) {
+                        return Err(error);
+                    }
+                    tracing::warn!(
+                        "Load balanced endpoint '{}' failed, trying next endpoint: {}",
+                        endpoint.name,
+                        error
+                    );
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            ProviderError::ExecutionError("No load balanced endpoints available".to_string())
+        }))
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.endpoints
+            .iter()
+            .any(|endpoint| endpoint.provider.supports_embeddings())
+    }
+
+    /// Embeddings go to the first healthy endpoint that supports them, without advancing the
+    /// rotation used for completions
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let now = Instant::now();
+        let (healthy, unhealthy): (Vec<&Endpoint>, Vec<&Endpoint>) = self
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint.provider.supports_embeddings())
+            .partition(|endpoint| endpoint.is_healthy(now));
+        let endpoint = healthy.into_iter().chain(unhealthy).next().ok_or_else(|| {
+            ProviderError::ExecutionError(
+                "No load balanced endpoint supports embeddings".to_string(),
+            )
+        })?;
+        endpoint.provider.create_embeddings(texts).await
+    }
+
+    /// Open the stream on the next endpoint, failing over if the request is rejected
+    async fn stream(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        self.open_stream(move |index| {
+            self.endpoints[index]
+                .provider
+                .stream(system, messages, tools)
+        })
+        .await
+    }
+
+    async fn stream_events(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<EventStream, ProviderError> {
+        self.open_stream(move |index| {
+            self.endpoints[index]
+                .provider
+                .stream_events(system, messages, tools)
+        })
+        .await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.endpoints
+            .iter()
+            .all(|endpoint| endpoint.provider.supports_streaming())
+    }
+
+    fn supports_audio_input(&self) -> bool {
+        self.endpoints
+            .iter()
+            .all(|endpoint| endpoint.provider.supports_audio_input())
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.endpoints
+            .iter()
+            .all(|endpoint| endpoint.provider.supports_structured_output())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::message::{Message, MessageContent};
+    use crate::providers::base::{ProviderMetadata, ProviderUsage, Usage};
+    use chrono::Utc;
+    use rmcp::model::{AnnotateAble, RawTextContent, Role};
+    use std::sync::atomic::AtomicBool;
+
+    struct MockProvider {
+        name: String,
+        model_config: ModelConfig,
+        should_fail: AtomicBool,
+    }
+
+    impl MockProvider {
+        fn new(name: &str, should_fail: bool) -> Arc<Self> {
+            Arc::new(Self {
+                name: name.to_string(),
+                model_config: ModelConfig::new_or_fail("mock-model"),
+                should_fail: AtomicBool::new(should_fail),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Provider for MockProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_name(&self) -> &str {
+            "mock"
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            self.model_config.clone()
+        }
+
+        async fn complete_with_model(
+            &self,
+            _model_config: &ModelConfig,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            if self.should_fail.load(Ordering::Relaxed) {
+                return Err(ProviderError::ServerError("Simulated outage".to_string()));
+            }
+            Ok((
+                Message::new(
+                    Role::Assistant,
+                    Utc::now().timestamp(),
+                    vec![MessageContent::Text(
+                        RawTextContent {
+                            text: format!("Response from {}", self.name),
+                            meta: None,
+                        }
+                        .no_annotation(),
+                    )],
+                ),
+                ProviderUsage::new(self.name.clone(), Usage::default()),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_weighted_round_robin() {
+        let provider = LoadBalancedProvider::new(vec![
+            (
+                "a".to_string(),
+                MockProvider::new("a", false) as Arc<dyn Provider>,
+                2,
+            ),
+            (
+                "b".to_string(),
+                MockProvider::new("b", false) as Arc<dyn Provider>,
+                1,
+            ),
+        ])
+        .unwrap();
+
+        let mut used = Vec::new();
+        for _ in 0..6 {
+            let (_message, usage) = provider.complete("system", &[], &[]).await.unwrap();
+            used.push(usage.model);
+        }
+        assert_eq!(used, vec!["a", "a", "b", "a", "a", "b"]);
+
+        let stats = provider.endpoint_stats();
+        assert_eq!(stats[0].requests, 4);
+        assert_eq!(stats[1].requests, 2);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_calls_leave_rotation_alone() {
+        let provider = LoadBalancedProvider::new(vec![
+            (
+                "a".to_string(),
+                MockProvider::new("a", false) as Arc<dyn Provider>,
+                1,
+            ),
+            (
+                "b".to_string(),
+                MockProvider::new("b", false) as Arc<dyn Provider>,
+                1,
+            ),
+        ])
+        .unwrap();
+
+        let mut used = Vec::new();
+        for _ in 0..4 {
+            provider.supports_cache_control().await;
+            provider.retry_config();
+            let _ = provider.list_models().await;
+            let _ = provider.fetch_supported_models().await;
+            let (_message, usage) = provider.complete("system", &[], &[]).await.unwrap();
+            used.push(usage.model);
+        }
+        assert_eq!(used, vec!["a", "b", "a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_endpoint_is_skipped() {
+        let failing = MockProvider::new("a", true);
+        let provider = LoadBalancedProvider::new_with_settings(
+            vec![
+                ("a".to_string(), failing.clone() as Arc<dyn Provider>, 1),
+                (
+                    "b".to_string(),
+                    MockProvider::new("b", false) as Arc<dyn Provider>,
+                    1,
+                ),
+            ],
+            2,
+            Duration::from_secs(60),
+        )
+        .unwrap();
+
+        // Failures on "a" fail over to "b" until "a" is taken out of rotation
+        for _ in 0..4 {
+            let (_message, usage) = provider.complete("system", &[], &[]).await.unwrap();
+            assert_eq!(usage.model, "b");
+        }
+
+        let stats = provider.endpoint_stats();
+        assert!(!stats[0].healthy);
+        assert_eq!(stats[0].failures, 2);
+        assert_eq!(stats[0].error_rate(), 1.0);
+        assert!(stats[1].healthy);
+
+        // Even once "a" recovers it stays out of rotation until the cooldown expires
+        failing.should_fail.store(false, Ordering::Relaxed);
+        let (_message, usage) = provider.complete("system", &[], &[]).await.unwrap();
+        assert_eq!(usage.model, "b");
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_returns_after_cooldown() {
+        let failing = MockProvider::new("a", true);
+        let provider = LoadBalancedProvider::new_with_settings(
+            vec![
+                ("a".to_string(), failing.clone() as Arc<dyn Provider>, 1),
+                (
+                    "b".to_string(),
+                    MockProvider::new("b", false) as Arc<dyn Provider>,
+                    1,
+                ),
+            ],
+            1,
+            Duration::ZERO,
+        )
+        .unwrap();
+
+        let (_message, usage) = provider.complete("system", &[], &[]).await.unwrap();
+        assert_eq!(usage.model, "b");
+
+        failing.should_fail.store(false, Ordering::Relaxed);
+        let (_message, usage) = provider.complete("system", &[], &[]).await.unwrap();
+        assert_eq!(usage.model, "b");
+        let (_message, usage) = provider.complete("system", &[], &[]).await.unwrap();
+        assert_eq!(usage.model, "a");
+        assert!(provider.endpoint_stats()[0].healthy);
+    }
+
+    #[tokio::test]
+    async fn test_all_endpoints_failing_returns_error() {
+        let provider = LoadBalancedProvider::new(vec![
+            (
+                "a".to_string(),
+                MockProvider::new("a", true) as Arc<dyn Provider>,
+                1,
+            ),
+            (
+                "b".to_string(),
+                MockProvider::new("b", true) as Arc<dyn Provider>,
+                1,
+            ),
+        ])
+        .unwrap();
+
+        let result = provider.complete("system", &[], &[]).await;
+        assert!(matches!(result, Err(ProviderError::ServerError(_))));
+    }
+}
@@ -0,0 +1,512 @@
+//! Hooks around provider calls.
+//!
+//! A [`ProviderMiddleware`] sees each request before it is sent and each response, error or
+//! streamed chunk on the way back, and may rewrite any of them. Middleware is composed with
+//! [`MiddlewareProvider`], or registered process-wide with [`register_middleware`] so that every
+//! provider built by the factory picks it up.
+//!
+//! Requests pass through middleware in registration order and results pass back in reverse, so
+//! the first middleware registered is the outermost.
+//!
+//! Middleware sees a streamed response as chunks, also when it is taken as events. The events
+//! are put into chunks for it, so tool calls reach it whole rather than as argument deltas.
+
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use reqwest::header::HeaderMap;
+use rmcp::model::Tool;
+use tokio::task_local;
+
+use super::base::{MessageStream, Provider, ProviderMetadata, ProviderUsage};
+use super::errors::ProviderError;
+use super::stream_event::{into_events, into_message_stream, EventStream};
+use crate::conversation::message::Message;
+use crate::model::ModelConfig;
+
+static GLOBAL_MIDDLEWARE: Lazy<RwLock<Vec<Arc<dyn ProviderMiddleware>>>> =
+    Lazy::new(|| RwLock::new(Vec::new()));
+
+task_local! {
+    static REQUEST_HEADERS: HeaderMap;
+}
+
+/// Run `f` with extra headers for every `ApiClient` request it makes
+pub(crate) async fn with_request_headers<F>(headers: HeaderMap, f: F) -> F::Output
+where
+    F: std::future::Future,
+{
+    REQUEST_HEADERS.scope(headers, f).await
+}
+
+/// Extra headers set by middleware for the provider call in progress, if any
+pub(crate) fn current_request_headers() -> Option<HeaderMap> {
+    REQUEST_HEADERS.try_with(|headers| headers.clone()).ok()
+}
+
+/// A provider request as seen by middleware
+#[derive(Debug, Clone)]
+pub struct ProviderRequest {
+    /// Unique id of the call, shared by its response, error and streamed chunks
+    pub id: String,
+    /// When the call was made
+    pub started_at: DateTime<Utc>,
+    /// Name of the provider handling the call
+    pub provider: String,
+    /// Session the call belongs to, if any
+    pub session_id: Option<String>,
+    pub model_config: ModelConfig,
+    pub system: String,
+    pub messages: Vec<Message>,
+    pub tools: Vec<Tool>,
+    /// Extra HTTP headers, sent by providers built on `ApiClient`
+    pub headers: HeaderMap,
+}
+
+#[async_trait]
+pub trait ProviderMiddleware: Send + Sync {
+    /// Inspect or rewrite a request before it is sent. Returning an error stops the call.
+    async fn on_request(&self, _request: &mut ProviderRequest) -> Result<(), ProviderError> {
+        Ok(())
+    }
+
+    /// Inspect or rewrite a completed response
+    async fn on_response(
+        &self,
+        _request: &ProviderRequest,
+        _message: &mut Message,
+        _usage: &mut ProviderUsage,
+    ) -> Result<(), ProviderError> {
+        Ok(())
+    }
+
+    /// Inspect or replace an error from the provider or from later middleware
+    async fn on_error(&self, _request: &ProviderRequest, error: ProviderError) -> ProviderError {
+        error
+    }
+
+    /// Inspect or rewrite each chunk of a streamed response
+    fn on_stream_chunk(
+        &self,
+        _request: &ProviderRequest,
+        _message: &mut Option<Message>,
+        _usage: &mut Option<ProviderUsage>,
+    ) -> Result<(), ProviderError> {
+        Ok(())
+    }
+}
+
+/// Add middleware applied to every provider created after this call
+pub fn register_middleware(middleware: Arc<dyn ProviderMiddleware>) {
+    GLOBAL_MIDDLEWARE.write().unwrap().push(middleware);
+}
+
+/// Remove all process-wide middleware
+pub fn clear_middleware() {
+    GLOBAL_MIDDLEWARE.write().unwrap().clear();
+}
+
+/// Wrap `provider` with the process-wide middleware, if any has been registered
+pub fn with_registered_middleware(provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+    let middleware = GLOBAL_MIDDLEWARE.read().unwrap().clone();
+    if middleware.is_empty() {
+        return provider;
+    }
+    Arc::new(MiddlewareProvider::new(provider, middleware))
+}
+
+/// A provider that runs each call through a chain of middleware
+pub struct MiddlewareProvider {
+    inner: Arc<dyn Provider>,
+    middleware: Vec<Arc<dyn ProviderMiddleware>>,
+}
+
+impl MiddlewareProvider {
+    pub fn new(inner: Arc<dyn Provider>, middleware: Vec<Arc<dyn ProviderMiddleware>>) -> Self {
+        Self { inner, middleware }
+    }
+
+    /// Add middleware inside those already present
+    pub fn with_middleware(mut self, middleware: Arc<dyn ProviderMiddleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    async fn prepare(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<ProviderRequest, ProviderError> {
+        let mut request = ProviderRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            started_at: Utc::now(),
+            provider: self.inner.get_name().to_string(),
+            session_id: crate::session_context::current_session_id(),
+            model_config: model_config.clone(),
+            system: system.to_string(),
+            messages: messages.to_vec(),
+            tools: tools.to_vec(),
+            headers: HeaderMap::new(),
+        };
+        for middleware in &self.middleware {
+            if let Err(e) = middleware.on_request(&mut request).await {
+                return Err(handle_error(&self.middleware, &request, e).await);
+            }
+        }
+        Ok(request)
+    }
+}
+
+async fn handle_error(
+    middleware: &[Arc<dyn ProviderMiddleware>],
+    request: &ProviderRequest,
+    mut error: ProviderError,
+) -> ProviderError {
+    for layer in middleware.iter().rev() {
+        error = layer.on_error(request, error).await;
+    }
+    error
+}
+
+/// `stream` with each chunk passed back through the middleware
+fn stream_through(
+    middleware: Vec<Arc<dyn ProviderMiddleware>>,
+    request: ProviderRequest,
+    stream: MessageStream,
+) -> MessageStream {
+    Box::pin(stream.then(move |item| {
+        let middleware = middleware.clone();
+        let request = request.clone();
+        async move {
+            let (mut message, mut usage) = match item {
+                Ok(chunk) => chunk,
+                Err(e) => return Err(handle_error(&middleware, &request, e).await),
+            };
+            for layer in middleware.iter().rev() {
+                if let Err(e) = layer.on_stream_chunk(&request, &mut message, &mut usage) {
+                    return Err(handle_error(&middleware, &request, e).await);
+                }
+            }
+            Ok((message, usage))
+        }
+    }))
+}
+
+#[async_trait]
+impl Provider for MiddlewareProvider {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            "middleware",
+            "Middleware Provider",
+            "A provider that runs calls through middleware",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_name(&self) -> &str {
+        self.inner.get_name()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn inner_provider(&self) -> Option<&dyn Provider> {
+        Some(self.inner.as_ref())
+    }
+
+    async fn complete_with_model(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let request = self.prepare(model_config, system, messages, tools).await?;
+
+        let result = with_request_headers(
+            request.headers.clone(),
+            self.inner.complete_with_model(
+                &request.model_config,
+                &request.system,
+                &request.messages,
+                &request.tools,
+            ),
+        )
+        .await;
+
+        let (mut message, mut usage) = match result {
+            Ok(response) => response,
+            Err(e) => return Err(handle_error(&self.middleware, &request, e).await),
+        };
+        for middleware in self.middleware.iter().rev() {
+            if let Err(e) = middleware
+                .on_response(&request, &mut message, &mut usage)
+                .await
+            {
+                return Err(handle_error(&self.middleware, &request, e).await);
+            }
+        }
+        Ok((message, usage))
+    }
+
+    /// Middleware can't change the model of a streamed request, since `stream` always uses the
+    /// provider's configured model
+    async fn stream(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let model_config = self.inner.get_model_config();
+        let request = self.prepare(&model_config, system, messages, tools).await?;
+
+        let result = with_request_headers(
+            request.headers.clone(),
+            self.inner
+                .stream(&request.system, &request.messages, &request.tools),
+        )
+        .await;
+        match result {
+            Ok(stream) => Ok(stream_through(self.middleware.clone(), request, stream)),
+            Err(e) => Err(handle_error(&self.middleware, &request, e).await),
+        }
+    }
+
+    async fn stream_events(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<EventStream, ProviderError> {
+        let model_config = self.inner.get_model_config();
+        let request = self.prepare(&model_config, system, messages, tools).await?;
+
+        let result = with_request_headers(
+            request.headers.clone(),
+            self.inner
+                .stream_events(&request.system, &request.messages, &request.tools),
+        )
+        .await;
+        match result {
+            Ok(events) => {
+                let chunks = into_message_stream(events);
+                Ok(into_events(stream_through(
+                    self.middleware.clone(),
+                    request,
+                    chunks,
+                )))
+            }
+            Err(e) => Err(handle_error(&self.middleware, &request, e).await),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use futures::TryStreamExt;
+    use std::sync::Mutex;
+
+    struct Tagging {
+        tag: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl ProviderMiddleware for Tagging {
+        async fn on_request(&self, request: &mut ProviderRequest) -> Result<(), ProviderError> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("request {}", self.tag));
+            request.system.push_str(self.tag);
+            Ok(())
+        }
+
+        async fn on_response(
+            &self,
+            _request: &ProviderRequest,
+            message: &mut Message,
+            _usage: &mut ProviderUsage,
+        ) -> Result<(), ProviderError> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("response {}", self.tag));
+            *message = Message::assistant().with_text(message.as_concat_text() + self.tag);
+            Ok(())
+        }
+
+        async fn on_error(
+            &self,
+            _request: &ProviderRequest,
+            error: ProviderError,
+        ) -> ProviderError {
+            ProviderError::ExecutionError(format!("{} {}", self.tag, error))
+        }
+
+        fn on_stream_chunk(
+            &self,
+            _request: &ProviderRequest,
+            message: &mut Option<Message>,
+            _usage: &mut Option<ProviderUsage>,
+        ) -> Result<(), ProviderError> {
+            if let Some(m) = message {
+                *m = Message::assistant().with_text(m.as_concat_text().to_uppercase());
+            }
+            Ok(())
+        }
+    }
+
+    fn chain(mock: Arc<MockProvider>, log: &Arc<Mutex<Vec<String>>>) -> MiddlewareProvider {
+        MiddlewareProvider::new(mock, vec![])
+            .with_middleware(Arc::new(Tagging {
+                tag: "a",
+                log: log.clone(),
+            }))
+            .with_middleware(Arc::new(Tagging {
+                tag: "b",
+                log: log.clone(),
+            }))
+    }
+
+    #[tokio::test]
+    async fn test_middleware_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mock = Arc::new(MockProvider::new().with_response(MockResponse::text("hi ")));
+        let provider = chain(mock.clone(), &log);
+
+        let (message, _) = provider.complete("system ", &[], &[]).await.unwrap();
+        assert_eq!(mock.requests()[0].system, "system ab");
+        assert_eq!(message.as_concat_text(), "hi ba");
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["request a", "request b", "response b", "response a"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_calls_reach_the_inner_provider() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let provider = chain(Arc::new(MockProvider::new()), &log);
+
+        assert!(provider.supports_streaming());
+        assert!(!provider.supports_embeddings());
+        assert!(matches!(
+            provider.list_models().await,
+            Err(ProviderError::NotImplemented(message)) if message.contains("mock")
+        ));
+        assert!(log.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_middleware_errors_and_stream_chunks() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mock = MockProvider::new()
+            .with_response(MockResponse::error(ProviderError::ServerError(
+                "down".to_string(),
+            )))
+            .with_response(MockResponse::stream(["hel", "lo"]));
+        let provider = chain(Arc::new(mock), &log);
+
+        let error = provider.complete("system", &[], &[]).await.unwrap_err();
+        assert_eq!(
+            error,
+            ProviderError::ExecutionError("a Execution error: b Server error: down".to_string())
+        );
+
+        let chunks: Vec<_> = provider
+            .stream("system", &[], &[])
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let texts: Vec<_> = chunks
+            .iter()
+            .map(|(message, _)| message.as_ref().unwrap().as_concat_text())
+            .collect();
+        assert_eq!(texts, vec!["HEL", "LO"]);
+    }
+
+    /// Streams only as events, like providers that parse their response into events natively
+    struct EventsOnly;
+
+    #[async_trait]
+    impl Provider for EventsOnly {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_name(&self) -> &str {
+            "events-only"
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new_or_fail("events-model")
+        }
+
+        async fn complete_with_model(
+            &self,
+            _model_config: &ModelConfig,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            Err(ProviderError::NotImplemented("complete".to_string()))
+        }
+
+        async fn stream_events(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<EventStream, ProviderError> {
+            use crate::providers::stream_event::StreamEvent;
+            Ok(Box::pin(futures::stream::iter(vec![
+                StreamEvent::TextDelta {
+                    message_id: None,
+                    text: "hi".to_string(),
+                    logprobs: None,
+                },
+                StreamEvent::Done,
+            ])))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_passes_native_stream_events() {
+        use crate::providers::stream_event::StreamEvent;
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let provider = MiddlewareProvider::new(Arc::new(EventsOnly), vec![]).with_middleware(
+            Arc::new(Tagging {
+                tag: "a",
+                log: log.clone(),
+            }),
+        );
+
+        let events: Vec<_> = provider
+            .stream_events("system", &[], &[])
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert!(matches!(
+            &events[0],
+            StreamEvent::TextDelta { text, .. } if text == "HI"
+        ));
+        assert!(matches!(events.last(), Some(StreamEvent::Done)));
+        assert_eq!(*log.lock().unwrap(), vec!["request a"]);
+    }
+}
@@ -0,0 +1,376 @@
+//! A scriptable provider for unit tests, available with the `test-utils` feature.
+//!
+//! [`MockProvider`] serves a queue of [`MockResponse`]s in order, one per `complete` or `stream`
+//! call, and remembers each request so tests can assert on what the agent sent.
+//!
+//! ```ignore
+//! let provider = MockProvider::new()
+//!     .with_response(MockResponse::tool_call("get_weather", json!({"city": "Paris"})))
+//!     .with_response(MockResponse::stream(["It's ", "sunny"]).with_latency(Duration::from_millis(50)));
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rmcp::model::{CallToolRequestParam, Tool};
+use serde_json::Value;
+
+use super::base::{MessageStream, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::errors::ProviderError;
+use crate::conversation::message::Message;
+use crate::model::ModelConfig;
+
+pub const MOCK_DEFAULT_MODEL: &str = "mock-model";
+
+enum MockResponseKind {
+    Message(Message),
+    Stream(Vec<String>),
+    Error(ProviderError),
+}
+
+/// One scripted reply from a [`MockProvider`]
+pub struct MockResponse {
+    kind: MockResponseKind,
+    latency: Duration,
+    chunk_interval: Duration,
+    usage: Usage,
+    interim_usage: Option<Usage>,
+}
+
+impl MockResponse {
+    fn new(kind: MockResponseKind) -> Self {
+        Self {
+            kind,
+            latency: Duration::ZERO,
+            chunk_interval: Duration::ZERO,
+            usage: Usage::default(),
+            interim_usage: None,
+        }
+    }
+
+    /// An assistant message with a single text block
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::message(Message::assistant().with_text(text.into()))
+    }
+
+    /// An assistant message requesting one tool call. `arguments` should be a JSON object.
+    pub fn tool_call(name: impl Into<String>, arguments: Value) -> Self {
+        Self::tool_calls([(name, arguments)])
+    }
+
+    /// An assistant message requesting several tool calls, with ids `mock_call_0`, `mock_call_1`, ...
+    pub fn tool_calls<S: Into<String>>(calls: impl IntoIterator<Item = (S, Value)>) -> Self {
+        let message = calls.into_iter().enumerate().fold(
+            Message::assistant(),
+            |message, (index, (name, arguments))| {
+                let name: String = name.into();
+                message.with_tool_request(
+                    format!("mock_call_{}", index),
+                    Ok(CallToolRequestParam {
+                        name: name.into(),
+                        arguments: arguments.as_object().cloned(),
+                    }),
+                )
+            },
+        );
+        Self::message(message)
+    }
+
+    /// Any assistant message
+    pub fn message(message: Message) -> Self {
+        Self::new(MockResponseKind::Message(message))
+    }
+
+    /// Text delivered as one chunk per item when streamed, or concatenated when completed
+    pub fn stream<S: Into<String>>(chunks: impl IntoIterator<Item = S>) -> Self {
+        Self::new(MockResponseKind::Stream(
+            chunks.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    /// Fail the call with `error`
+    pub fn error(error: ProviderError) -> Self {
+        Self::new(MockResponseKind::Error(error))
+    }
+
+    /// Wait this long before replying
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Wait this long between streamed chunks
+    pub fn with_chunk_interval(mut self, interval: Duration) -> Self {
+        self.chunk_interval = interval;
+        self
+    }
+
+    /// Usage reported with the reply
+    pub fn with_usage(mut self, usage: Usage) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    /// Interim usage reported with every streamed chunk before the last
+    pub fn with_interim_usage(mut self, usage: Usage) -> Self {
+        self.interim_usage = Some(usage);
+        self
+    }
+}
+
+/// A request received by a [`MockProvider`]
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub system: String,
+    pub messages: Vec<Message>,
+    pub tools: Vec<Tool>,
+}
+
+/// A provider that replies with scripted responses
+pub struct MockProvider {
+    model_config: ModelConfig,
+    responses: Mutex<VecDeque<MockResponse>>,
+    requests: Mutex<Vec<MockRequest>>,
+}
+
+impl Default for MockProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self {
+            model_config: ModelConfig::new_or_fail(MOCK_DEFAULT_MODEL),
+            responses: Mutex::new(VecDeque::new()),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn with_model_config(mut self, model_config: ModelConfig) -> Self {
+        self.model_config = model_config;
+        self
+    }
+
+    /// Queue a response after those already scripted
+    pub fn with_response(self, response: MockResponse) -> Self {
+        self.push_response(response);
+        self
+    }
+
+    pub fn with_responses(self, responses: impl IntoIterator<Item = MockResponse>) -> Self {
+        self.responses.lock().unwrap().extend(responses);
+        self
+    }
+
+    /// Queue a response on a provider that's already in use
+    pub fn push_response(&self, response: MockResponse) {
+        self.responses.lock().unwrap().push_back(response);
+    }
+
+    /// Number of scripted responses not yet served
+    pub fn remaining_responses(&self) -> usize {
+        self.responses.lock().unwrap().len()
+    }
+
+    /// Every request received so far, oldest first
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn next_response(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MockResponse, ProviderError> {
+        self.requests.lock().unwrap().push(MockRequest {
+            system: system.to_string(),
+            messages: messages.to_vec(),
+            tools: tools.to_vec(),
+        });
+        self.responses.lock().unwrap().pop_front().ok_or_else(|| {
+            ProviderError::ExecutionError("MockProvider has no scripted responses left".to_string())
+        })
+    }
+
+    fn usage(&self, usage: Usage) -> ProviderUsage {
+        ProviderUsage::new(self.model_config.model_name.clone(), usage)
+    }
+}
+
+#[async_trait]
+impl Provider for MockProvider {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            "mock",
+            "Mock Provider",
+            "Scriptable provider for unit tests",
+            MOCK_DEFAULT_MODEL,
+            vec![MOCK_DEFAULT_MODEL],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_name(&self) -> &str {
+        "mock"
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.model_config.clone()
+    }
+
+    async fn complete_with_model(
+        &self,
+        _model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let response = self.next_response(system, messages, tools)?;
+        tokio::time::sleep(response.latency).await;
+
+        let message = match response.kind {
+            MockResponseKind::Message(message) => message,
+            MockResponseKind::Stream(chunks) => Message::assistant().with_text(chunks.concat()),
+            MockResponseKind::Error(error) => return Err(error),
+        };
+        Ok((message, self.usage(response.usage)))
+    }
+
+    async fn stream(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let response = self.next_response(system, messages, tools)?;
+        tokio::time::sleep(response.latency).await;
+
+        let usage = self.usage(response.usage);
+        let interim_usage = response
+            .interim_usage
+            .map(|usage| self.usage(usage).as_interim());
+        let chunks = match response.kind {
+            MockResponseKind::Message(message) => vec![message],
+            MockResponseKind::Stream(chunks) => {
+                let id = uuid::Uuid::new_v4().to_string();
+                chunks
+                    .into_iter()
+                    .map(|chunk| Message::assistant().with_id(&id).with_text(chunk))
+                    .collect()
+            }
+            MockResponseKind::Error(error) => return Err(error),
+        };
+
+        let interval = response.chunk_interval;
+        Ok(Box::pin(async_stream::stream! {
+            let last = chunks.len().saturating_sub(1);
+            for (index, chunk) in chunks.into_iter().enumerate() {
+                if index > 0 {
+                    tokio::time::sleep(interval).await;
+                }
+                let usage = if index == last {
+                    Some(usage.clone())
+                } else {
+                    interim_usage.clone()
+                };
+                yield Ok((Some(chunk), usage));
+            }
+        }))
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::message::MessageContent;
+    use futures::TryStreamExt;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_scripted_responses_in_order() {
+        let provider = MockProvider::new()
+            .with_response(MockResponse::text("hello").with_usage(Usage::new(
+                Some(1),
+                Some(2),
+                Some(3),
+            )))
+            .with_response(MockResponse::tool_call(
+                "get_weather",
+                json!({"city": "Paris"}),
+            ))
+            .with_response(MockResponse::error(ProviderError::ContextLengthExceeded(
+                "too long".to_string(),
+            )));
+
+        let user = [Message::user().with_text("hi")];
+        let (message, usage) = provider.complete("system", &user, &[]).await.unwrap();
+        assert_eq!(message.as_concat_text(), "hello");
+        assert_eq!(usage.model, MOCK_DEFAULT_MODEL);
+        assert_eq!(usage.usage.total_tokens, Some(3));
+
+        let (message, _) = provider.complete("system", &user, &[]).await.unwrap();
+        let MessageContent::ToolRequest(request) = &message.content[0] else {
+            panic!("Expected tool request");
+        };
+        assert_eq!(request.id, "mock_call_0");
+        let call = request.tool_call.as_ref().unwrap();
+        assert_eq!(call.name, "get_weather");
+        assert_eq!(call.arguments.as_ref().unwrap()["city"], "Paris");
+
+        assert!(matches!(
+            provider.complete("system", &user, &[]).await,
+            Err(ProviderError::ContextLengthExceeded(_))
+        ));
+        assert!(provider.complete("system", &user, &[]).await.is_err());
+
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0].system, "system");
+        assert_eq!(provider.remaining_responses(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_stream_chunks() {
+        let provider = MockProvider::new()
+            .with_response(MockResponse::stream(["Hel", "lo"]))
+            .with_response(MockResponse::stream(["Hel", "lo"]));
+
+        let chunks: Vec<_> = provider
+            .stream("system", &[], &[])
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 2);
+        let first = chunks[0].0.as_ref().unwrap();
+        let second = chunks[1].0.as_ref().unwrap();
+        assert_eq!(first.as_concat_text(), "Hel");
+        assert_eq!(first.id, second.id);
+        assert!(chunks[0].1.is_none());
+        assert!(chunks[1].1.is_some());
+
+        let (message, _) = provider.complete("system", &[], &[]).await.unwrap();
+        assert_eq!(message.as_concat_text(), "Hello");
+    }
+
+    #[tokio::test]
+    async fn test_latency() {
+        let provider = MockProvider::new()
+            .with_response(MockResponse::text("slow").with_latency(Duration::from_millis(50)));
+
+        let start = std::time::Instant::now();
+        provider.complete("system", &[], &[]).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -7,6 +7,8 @@ pub mod base;
 pub mod bedrock;
 pub mod canonical;
 pub mod claude_code;
+pub mod cohere;
+pub mod context_policy;
 pub mod cursor_agent;
 pub mod databricks;
 pub mod embedding;
@@ -18,26 +20,46 @@ pub mod gcpvertexai;
 pub mod gemini_cli;
 pub mod githubcopilot;
 pub mod google;
+pub mod http_client;
+pub mod image_generation;
 pub mod lead_worker;
 pub mod litellm;
+pub mod load_balanced;
+pub mod middleware;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod mock;
+pub mod model_router;
+pub mod moderation;
 pub mod oauth;
 pub mod ollama;
 pub mod openai;
 pub mod openrouter;
+pub mod pii;
+pub mod pricing;
 pub mod provider_registry;
 pub mod provider_test;
+pub mod rate_limit;
+pub mod recording;
+pub mod response_cache;
 mod retry;
 pub mod sagemaker_tgi;
 pub mod snowflake;
+pub mod speech;
+pub mod stream_event;
+pub mod stream_limits;
 pub mod testprovider;
 pub mod tetrate;
+pub mod timeouts;
 pub mod toolshim;
+pub mod transcription;
 pub mod usage_estimator;
+pub mod usage_tracker;
 pub mod utils;
 pub mod utils_universal_openai_stream;
 pub mod venice;
 pub mod xai;
 
 pub use factory::{
-    create, create_with_default_model, create_with_named_model, providers, refresh_custom_providers,
+    create, create_lead_worker, create_with_default_model, create_with_named_model, providers,
+    refresh_custom_providers,
 };
@@ -0,0 +1,410 @@
+//! Switching models within a session by the kind of turn.
+//!
+//! Most turns of a long task only pick the next tool call from the result of the last one,
+//! which a small model does about as well as a large one. With [`ModelRouting`] set globally
+//! through `GOOSE_ROUTER_TOOL_MODEL` or per session through
+//! [`SessionConfig::model_routing`](crate::agents::types::SessionConfig),
+//! [`ModelRouterProvider`] sends turns that follow tool results to the tool model. When the
+//! tool model stops calling tools and starts answering, its response is set aside and the turn
+//! is asked of the synthesis model instead, so the answer the user reads comes from the stronger
+//! model. Other turns, such as the first after a user message, go to the synthesis model, which
+//! is `GOOSE_ROUTER_SYNTHESIS_MODEL` or else the session's model. Both models are served by the
+//! session's provider.
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use rmcp::model::Tool;
+use serde::{Deserialize, Serialize};
+
+use super::base::{
+    stream_from_single_message, MessageStream, Provider, ProviderMetadata, ProviderUsage,
+};
+use super::errors::ProviderError;
+use super::stream_event::{into_events, EventStream};
+use crate::config::Config;
+use crate::conversation::message::Message;
+use crate::model::ModelConfig;
+
+pub const ROUTER_TOOL_MODEL_CONFIG_KEY: &str = "GOOSE_ROUTER_TOOL_MODEL";
+pub const ROUTER_SYNTHESIS_MODEL_CONFIG_KEY: &str = "GOOSE_ROUTER_SYNTHESIS_MODEL";
+
+/// The kind of turn a request is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnType {
+    /// The turn follows tool results, so it most likely makes the next tool call
+    ToolLoop,
+    /// Any other turn, including the final answer
+    Synthesis,
+}
+
+impl TurnType {
+    pub fn of(messages: &[Message]) -> Self {
+        match messages.last() {
+            Some(message) if message.is_tool_response() => Self::ToolLoop,
+            _ => Self::Synthesis,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ToolLoop => "tool",
+            Self::Synthesis => "synthesis",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelRouting {
+    /// Model for turns that follow tool results
+    pub tool_model: String,
+    /// Model for other turns and for final answers, the session's model if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synthesis_model: Option<String>,
+}
+
+impl ModelRouting {
+    /// The routing set with GOOSE_ROUTER_TOOL_MODEL and GOOSE_ROUTER_SYNTHESIS_MODEL, if any
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        let tool_model: String = config.get_param(ROUTER_TOOL_MODEL_CONFIG_KEY).ok()?;
+        Some(Self {
+            tool_model,
+            synthesis_model: config.get_param(ROUTER_SYNTHESIS_MODEL_CONFIG_KEY).ok(),
+        })
+    }
+
+    /// Wrap a provider so its requests are routed between the two models
+    pub fn wrap(&self, provider: Arc<dyn Provider>) -> Arc<ModelRouterProvider> {
+        Arc::new(ModelRouterProvider::new(provider, self.clone()))
+    }
+}
+
+/// A provider that picks the model for each request by its [`TurnType`]
+pub struct ModelRouterProvider {
+    inner: Arc<dyn Provider>,
+    routing: ModelRouting,
+    /// Usage of tool model responses that were replaced by the synthesis model's
+    discarded_usage: Mutex<Vec<ProviderUsage>>,
+}
+
+impl ModelRouterProvider {
+    pub fn new(inner: Arc<dyn Provider>, routing: ModelRouting) -> Self {
+        Self {
+            inner,
+            routing,
+            discarded_usage: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Usage of the responses the router threw away since last taken. The tokens were still
+    /// spent, so callers that account for usage should count them.
+    pub fn take_discarded_usage(&self) -> Vec<ProviderUsage> {
+        std::mem::take(&mut *self.discarded_usage.lock().unwrap())
+    }
+
+    /// The turn type whose model served `model`, if it's one of the routed models
+    pub fn turn_type_of_model(&self, model: &str) -> Option<TurnType> {
+        if model == self.routing.tool_model {
+            Some(TurnType::ToolLoop)
+        } else if model == self.synthesis_model_name() {
+            Some(TurnType::Synthesis)
+        } else {
+            None
+        }
+    }
+
+    fn synthesis_model_name(&self) -> String {
+        self.routing
+            .synthesis_model
+            .clone()
+            .unwrap_or_else(|| self.inner.get_model_config().model_name)
+    }
+
+    fn with_model(model_config: &ModelConfig, model_name: &str) -> ModelConfig {
+        if model_config.model_name == model_name {
+            return model_config.clone();
+        }
+        ModelConfig {
+            model_name: model_name.to_string(),
+            // A limit set for the session's model may not hold for this one
+            context_limit: None,
+            ..model_config.clone()
+        }
+    }
+
+    /// The tool model's response for a tool loop turn, unless the turn isn't one or the tool
+    /// model answered instead of calling a tool
+    async fn complete_tool_turn(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Option<(Message, ProviderUsage)> {
+        if TurnType::of(messages) != TurnType::ToolLoop {
+            return None;
+        }
+        let tool_config = Self::with_model(model_config, &self.routing.tool_model);
+        match self
+            .inner
+            .complete_with_model(&tool_config, system, messages, tools)
+            .await
+        {
+            Ok((message, usage)) if message.is_tool_call() => Some((message, usage)),
+            Ok((_, usage)) => {
+                tracing::debug!(
+                    "Tool model {} answered, asking the synthesis model instead",
+                    self.routing.tool_model
+                );
+                self.discarded_usage
+                    .lock()
+                    .unwrap()
+                    .push(usage.with_estimated_cost(self.inner.get_name()));
+                None
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Tool model {} failed, falling back to the synthesis model: {}",
+                    self.routing.tool_model,
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// The whole response of a turn that can't be streamed from the provider: a tool loop turn
+    /// the tool model handled, or one for a synthesis model other than the provider's own.
+    /// Providers only stream their own model. None when the turn can be streamed.
+    async fn unstreamed_response(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<Option<(Message, ProviderUsage)>, ProviderError> {
+        if let Some(response) = self
+            .complete_tool_turn(model_config, system, messages, tools)
+            .await
+        {
+            return Ok(Some(response));
+        }
+        let synthesis_model = self.synthesis_model_name();
+        if synthesis_model == model_config.model_name {
+            return Ok(None);
+        }
+        self.inner
+            .complete_with_model(
+                &Self::with_model(model_config, &synthesis_model),
+                system,
+                messages,
+                tools,
+            )
+            .await
+            .map(Some)
+    }
+}
+
+#[async_trait]
+impl Provider for ModelRouterProvider {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            "model_router",
+            "Model Router Provider",
+            "A provider that switches models by the kind of turn",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_name(&self) -> &str {
+        self.inner.get_name()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn inner_provider(&self) -> Option<&dyn Provider> {
+        Some(self.inner.as_ref())
+    }
+
+    async fn complete_with_model(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        // Requests for some other model, such as the fast model, aren't routed
+        if model_config.model_name != self.inner.get_model_config().model_name {
+            return self
+                .inner
+                .complete_with_model(model_config, system, messages, tools)
+                .await;
+        }
+        if let Some(response) = self
+            .complete_tool_turn(model_config, system, messages, tools)
+            .await
+        {
+            return Ok(response);
+        }
+        let synthesis_config = Self::with_model(model_config, &self.synthesis_model_name());
+        self.inner
+            .complete_with_model(&synthesis_config, system, messages, tools)
+            .await
+    }
+
+    async fn stream(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let model_config = self.inner.get_model_config();
+        match self
+            .unstreamed_response(&model_config, system, messages, tools)
+            .await?
+        {
+            Some((message, usage)) => Ok(stream_from_single_message(message, usage)),
+            None => self.inner.stream(system, messages, tools).await,
+        }
+    }
+
+    async fn stream_events(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<EventStream, ProviderError> {
+        let model_config = self.inner.get_model_config();
+        match self
+            .unstreamed_response(&model_config, system, messages, tools)
+            .await?
+        {
+            Some((message, usage)) => Ok(into_events(stream_from_single_message(message, usage))),
+            None => self.inner.stream_events(system, messages, tools).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::message::MessageContent;
+    use crate::providers::base::Usage;
+    use rmcp::model::{CallToolRequestParam, CallToolResult, Content};
+    use rmcp::object;
+
+    /// Answers as the model it was asked for: "cheap" calls a tool until told `finish`, and
+    /// every model answers with its own name otherwise
+    struct ModelEchoProvider {
+        calls: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Provider for ModelEchoProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_name(&self) -> &str {
+            "echo"
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new_or_fail("strong")
+        }
+
+        async fn complete_with_model(
+            &self,
+            model_config: &ModelConfig,
+            _system: &str,
+            messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            let model = model_config.model_name.clone();
+            self.calls.lock().unwrap().push(model.clone());
+            let finished = messages
+                .last()
+                .into_iter()
+                .flat_map(|message| &message.content)
+                .filter_map(|content| content.as_tool_response())
+                .filter_map(|response| response.tool_result.as_ref().ok())
+                .flat_map(|result| &result.content)
+                .any(|content| content.as_text().is_some_and(|text| text.text == "finish"));
+            let message = if model == "cheap" && !finished {
+                Message::assistant().with_tool_request(
+                    "call",
+                    Ok(CallToolRequestParam {
+                        name: "read".into(),
+                        arguments: Some(object!({})),
+                    }),
+                )
+            } else {
+                Message::assistant().with_text(&model)
+            };
+            Ok((
+                message,
+                ProviderUsage::new(model, Usage::new(Some(10), Some(1), Some(11))),
+            ))
+        }
+    }
+
+    fn tool_result(text: &str) -> Message {
+        Message::user().with_tool_response(
+            "call",
+            Ok(CallToolResult::success(vec![Content::text(text)])),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_routes_by_turn_type() {
+        let inner = Arc::new(ModelEchoProvider {
+            calls: Mutex::new(Vec::new()),
+        });
+        let router = ModelRouting {
+            tool_model: "cheap".to_string(),
+            synthesis_model: None,
+        }
+        .wrap(inner.clone());
+
+        // The first turn after the user's message goes to the synthesis model
+        let user = Message::user().with_text("read the file");
+        let (message, usage) = router.complete("", &[user.clone()], &[]).await.unwrap();
+        assert_eq!(message.as_concat_text(), "strong");
+        assert_eq!(usage.model, "strong");
+
+        // Turns after tool results go to the tool model while it keeps calling tools
+        let (message, usage) = router
+            .complete("", &[user.clone(), tool_result("contents")], &[])
+            .await
+            .unwrap();
+        assert!(matches!(message.content[0], MessageContent::ToolRequest(_)));
+        assert_eq!(usage.model, "cheap");
+        assert!(router.take_discarded_usage().is_empty());
+
+        // Once the tool model would answer, the synthesis model answers instead
+        let (message, _) = router
+            .complete("", &[user, tool_result("finish")], &[])
+            .await
+            .unwrap();
+        assert_eq!(message.as_concat_text(), "strong");
+        let discarded = router.take_discarded_usage();
+        assert_eq!(discarded.len(), 1);
+        assert_eq!(discarded[0].model, "cheap");
+        assert_eq!(
+            *inner.calls.lock().unwrap(),
+            vec!["strong", "cheap", "cheap", "strong"]
+        );
+
+        assert_eq!(router.turn_type_of_model("cheap"), Some(TurnType::ToolLoop));
+        assert_eq!(
+            router.turn_type_of_model("strong"),
+            Some(TurnType::Synthesis)
+        );
+    }
+}
@@ -0,0 +1,717 @@
+//! Content moderation around provider calls.
+//!
+//! A [`Moderator`] screens text with a content-safety service. [`ModeratedProvider`] wraps a
+//! provider so the latest user message is screened before it is sent and the completion is
+//! screened before it is returned, failing with [`ProviderError::ContentFlagged`] when either is
+//! flagged. Moderation is enabled globally with `GOOSE_MODERATION_PROVIDER` or per session
+//! through [`SessionConfig::moderation`](crate::agents::types::SessionConfig).
+//!
+//! With `GOOSE_PII_SCRUBBING` enabled, text is scrubbed the same way as the request before it
+//! goes to the moderator, so personal data doesn't reach a cloud moderation service either.
+
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::api_client::{ApiClient, AuthMethod};
+use super::base::{MessageStream, Provider, ProviderMetadata, ProviderUsage};
+use super::bedrock::BedrockProvider;
+use super::errors::ProviderError;
+use super::ollama::OllamaProvider;
+use super::pii::{configured_pii_scrubber, PiiScrubber};
+use super::retry::ProviderRetry;
+use super::stream_event::{into_events, EventStream};
+use super::utils::handle_response_openai_compat;
+use crate::config::Config;
+use crate::conversation::message::Message;
+use crate::model::ModelConfig;
+use rmcp::model::{Role, Tool};
+
+pub const OPENAI_DEFAULT_MODERATION_MODEL: &str = "omni-moderation-latest";
+pub const LLAMA_GUARD_DEFAULT_MODEL: &str = "llama-guard3";
+
+/// Hazard categories reported by Llama Guard 3
+const LLAMA_GUARD_CATEGORIES: &[(&str, &str)] = &[
+    ("S1", "violent_crimes"),
+    ("S2", "non_violent_crimes"),
+    ("S3", "sex_related_crimes"),
+    ("S4", "child_sexual_exploitation"),
+    ("S5", "defamation"),
+    ("S6", "specialized_advice"),
+    ("S7", "privacy"),
+    ("S8", "intellectual_property"),
+    ("S9", "indiscriminate_weapons"),
+    ("S10", "hate"),
+    ("S11", "suicide_and_self_harm"),
+    ("S12", "sexual_content"),
+    ("S13", "elections"),
+    ("S14", "code_interpreter_abuse"),
+];
+
+/// Which side of a provider call is being screened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationTarget {
+    /// Content sent to the model
+    Input,
+    /// Content returned by the model
+    Output,
+}
+
+impl fmt::Display for ModerationTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModerationTarget::Input => write!(f, "input"),
+            ModerationTarget::Output => write!(f, "output"),
+        }
+    }
+}
+
+/// Verdict from a moderation check
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModerationResult {
+    pub flagged: bool,
+    /// Categories the content was flagged for, in the moderator's own naming
+    pub categories: Vec<String>,
+}
+
+impl ModerationResult {
+    pub fn allowed() -> Self {
+        Self::default()
+    }
+
+    pub fn flagged(categories: Vec<String>) -> Self {
+        Self {
+            flagged: true,
+            categories,
+        }
+    }
+}
+
+/// A content-safety service
+#[async_trait]
+pub trait Moderator: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn moderate(
+        &self,
+        text: &str,
+        target: ModerationTarget,
+    ) -> Result<ModerationResult, ProviderError>;
+}
+
+/// Moderation settings for a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModerationSettings {
+    /// Moderator to use: `openai`, `bedrock_guardrails` or `llama_guard`
+    pub moderator: String,
+    /// Screen user content before it is sent to the provider
+    #[serde(default = "default_true")]
+    pub screen_input: bool,
+    /// Screen completions before they are returned
+    #[serde(default = "default_true")]
+    pub screen_output: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl ModerationSettings {
+    pub fn new(moderator: impl Into<String>) -> Self {
+        Self {
+            moderator: moderator.into(),
+            screen_input: true,
+            screen_output: true,
+        }
+    }
+
+    /// Read settings from GOOSE_MODERATION_PROVIDER, GOOSE_MODERATION_SCREEN_INPUT and
+    /// GOOSE_MODERATION_SCREEN_OUTPUT. Returns None when no moderator is configured.
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        let moderator: String = config.get_param("GOOSE_MODERATION_PROVIDER").ok()?;
+        Some(Self {
+            moderator,
+            screen_input: config
+                .get_param("GOOSE_MODERATION_SCREEN_INPUT")
+                .unwrap_or(true),
+            screen_output: config
+                .get_param("GOOSE_MODERATION_SCREEN_OUTPUT")
+                .unwrap_or(true),
+        })
+    }
+}
+
+/// A moderator together with the settings that say when to apply it
+#[derive(Clone)]
+pub struct Moderation {
+    moderator: Arc<dyn Moderator>,
+    screen_input: bool,
+    screen_output: bool,
+    scrubber: Option<Arc<PiiScrubber>>,
+}
+
+impl Moderation {
+    pub fn new(moderator: Arc<dyn Moderator>, screen_input: bool, screen_output: bool) -> Self {
+        Self {
+            moderator,
+            screen_input,
+            screen_output,
+            scrubber: None,
+        }
+    }
+
+    /// Scrub personal data from text before the moderator sees it
+    pub fn with_pii_scrubber(mut self, scrubber: Arc<PiiScrubber>) -> Self {
+        self.scrubber = Some(scrubber);
+        self
+    }
+
+    pub async fn from_settings(settings: &ModerationSettings) -> Result<Self> {
+        let moderator = create_moderator(&settings.moderator).await?;
+        let moderation = Self::new(moderator, settings.screen_input, settings.screen_output);
+        Ok(match configured_pii_scrubber() {
+            Some(scrubber) => moderation.with_pii_scrubber(scrubber),
+            None => moderation,
+        })
+    }
+
+    /// Wrap a provider so its calls are screened
+    pub fn wrap(&self, provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+        Arc::new(ModeratedProvider::new(provider, self.clone()))
+    }
+
+    async fn check(&self, text: &str, target: ModerationTarget) -> Result<(), ProviderError> {
+        if text.trim().is_empty() {
+            return Ok(());
+        }
+        let text = match &self.scrubber {
+            Some(scrubber) => scrubber.scrub_text(
+                crate::session_context::current_session_id().as_deref(),
+                text,
+            ),
+            None => text.to_string(),
+        };
+        let result = self.moderator.moderate(&text, target).await?;
+        if result.flagged {
+            tracing::warn!(
+                "{} flagged {} content: {:?}",
+                self.moderator.name(),
+                target,
+                result.categories
+            );
+            return Err(ProviderError::ContentFlagged {
+                target,
+                categories: result.categories,
+            });
+        }
+        Ok(())
+    }
+
+    async fn check_input(&self, messages: &[Message]) -> Result<(), ProviderError> {
+        if !self.screen_input {
+            return Ok(());
+        }
+        // Earlier messages were screened when they were sent
+        match messages.last() {
+            Some(message) if message.role == Role::User => {
+                self.check(&message.as_concat_text(), ModerationTarget::Input)
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn check_output(&self, message: &Message) -> Result<(), ProviderError> {
+        if !self.screen_output {
+            return Ok(());
+        }
+        self.check(&message.as_concat_text(), ModerationTarget::Output)
+            .await
+    }
+}
+
+/// Create a moderator by name using the same configuration keys as the corresponding provider
+pub async fn create_moderator(name: &str) -> Result<Arc<dyn Moderator>> {
+    let moderator: Arc<dyn Moderator> = match name {
+        "openai" => Arc::new(OpenAiModerator::from_env()?),
+        "bedrock_guardrails" => Arc::new(BedrockGuardrailModerator::from_env().await?),
+        "llama_guard" => Arc::new(LlamaGuardModerator::from_env().await?),
+        _ => return Err(anyhow::anyhow!("Unknown moderator: {}", name)),
+    };
+    Ok(moderator)
+}
+
+/// Moderation with OpenAI's moderations API
+pub struct OpenAiModerator {
+    api_client: ApiClient,
+    model: String,
+}
+
+impl OpenAiModerator {
+    /// Configured with OPENAI_API_KEY, OPENAI_HOST and GOOSE_MODERATION_MODEL
+    pub fn from_env() -> Result<Self> {
+        let config = Config::global();
+        let api_key: String = config.get_secret("OPENAI_API_KEY")?;
+        let host: String = config
+            .get_param("OPENAI_HOST")
+            .unwrap_or_else(|_| "https://api.openai.com".to_string());
+        let model: String = config
+            .get_param("GOOSE_MODERATION_MODEL")
+            .unwrap_or_else(|_| OPENAI_DEFAULT_MODERATION_MODEL.to_string());
+
+        let api_client = ApiClient::new(host, AuthMethod::BearerToken(api_key))?;
+        Ok(Self { api_client, model })
+    }
+}
+
+impl ProviderRetry for OpenAiModerator {}
+
+#[async_trait]
+impl Moderator for OpenAiModerator {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    async fn moderate(
+        &self,
+        text: &str,
+        _target: ModerationTarget,
+    ) -> Result<ModerationResult, ProviderError> {
+        let payload = json!({"model": self.model, "input": text});
+        let response = self
+            .with_retry(|| async {
+                let response = self
+                    .api_client
+                    .response_post("v1/moderations", &payload)
+                    .await?;
+                handle_response_openai_compat(response).await
+            })
+            .await?;
+        parse_openai_moderation(&response)
+    }
+}
+
+fn parse_openai_moderation(response: &Value) -> Result<ModerationResult, ProviderError> {
+    let result = response.pointer("/results/0").ok_or_else(|| {
+        ProviderError::RequestFailed("Missing results in moderation response".to_string())
+    })?;
+    if !result["flagged"].as_bool().unwrap_or(false) {
+        return Ok(ModerationResult::allowed());
+    }
+
+    let categories = result["categories"]
+        .as_object()
+        .map(|categories| {
+            categories
+                .iter()
+                .filter(|(_, flagged)| flagged.as_bool().unwrap_or(false))
+                .map(|(category, _)| category.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(ModerationResult::flagged(categories))
+}
+
+/// Moderation with a standalone Bedrock guardrail through ApplyGuardrail
+pub struct BedrockGuardrailModerator {
+    provider: BedrockProvider,
+    guardrail_id: String,
+    guardrail_version: String,
+}
+
+impl BedrockGuardrailModerator {
+    /// Configured with the Bedrock provider's AWS settings plus BEDROCK_GUARDRAIL_ID and
+    /// BEDROCK_GUARDRAIL_VERSION
+    pub async fn from_env() -> Result<Self> {
+        let config = Config::global();
+        let guardrail_id: String = config.get_param("BEDROCK_GUARDRAIL_ID")?;
+        let guardrail_version: String = config
+            .get_param("BEDROCK_GUARDRAIL_VERSION")
+            .unwrap_or_else(|_| "DRAFT".to_string());
+        let provider = BedrockProvider::from_env(ModelConfig::new_or_fail(
+            super::bedrock::BEDROCK_DEFAULT_MODEL,
+        ))
+        .await?;
+
+        Ok(Self {
+            provider,
+            guardrail_id,
+            guardrail_version,
+        })
+    }
+}
+
+#[async_trait]
+impl Moderator for BedrockGuardrailModerator {
+    fn name(&self) -> &str {
+        "bedrock_guardrails"
+    }
+
+    async fn moderate(
+        &self,
+        text: &str,
+        target: ModerationTarget,
+    ) -> Result<ModerationResult, ProviderError> {
+        self.provider
+            .apply_guardrail(&self.guardrail_id, &self.guardrail_version, text, target)
+            .await
+    }
+}
+
+/// Moderation with Llama Guard served by Ollama
+pub struct LlamaGuardModerator {
+    provider: OllamaProvider,
+}
+
+impl LlamaGuardModerator {
+    /// Configured with the Ollama provider's settings, using the model named by
+    /// GOOSE_MODERATION_MODEL (llama-guard3 by default)
+    pub async fn from_env() -> Result<Self> {
+        let model: String = Config::global()
+            .get_param("GOOSE_MODERATION_MODEL")
+            .unwrap_or_else(|_| LLAMA_GUARD_DEFAULT_MODEL.to_string());
+        let provider = OllamaProvider::from_env(ModelConfig::new(&model)?).await?;
+        Ok(Self { provider })
+    }
+}
+
+#[async_trait]
+impl Moderator for LlamaGuardModerator {
+    fn name(&self) -> &str {
+        "llama_guard"
+    }
+
+    async fn moderate(
+        &self,
+        text: &str,
+        target: ModerationTarget,
+    ) -> Result<ModerationResult, ProviderError> {
+        // Llama Guard classifies the last turn of the conversation it is given
+        let messages = match target {
+            ModerationTarget::Input => vec![Message::user().with_text(text)],
+            ModerationTarget::Output => vec![
+                Message::user().with_text(""),
+                Message::assistant().with_text(text),
+            ],
+        };
+        let (response, _) = self.provider.complete("", &messages, &[]).await?;
+        Ok(parse_llama_guard_verdict(&response.as_concat_text()))
+    }
+}
+
+/// Parse a Llama Guard verdict: `safe`, or `unsafe` followed by comma separated hazard codes
+fn parse_llama_guard_verdict(verdict: &str) -> ModerationResult {
+    let mut lines = verdict.trim().lines();
+    if lines.next().map(str::trim) != Some("unsafe") {
+        return ModerationResult::allowed();
+    }
+
+    let categories = lines
+        .flat_map(|line| line.split(','))
+        .map(str::trim)
+        .filter(|code| !code.is_empty())
+        .map(|code| {
+            LLAMA_GUARD_CATEGORIES
+                .iter()
+                .find(|(known, _)| *known == code)
+                .map(|(_, name)| name.to_string())
+                .unwrap_or_else(|| code.to_string())
+        })
+        .collect();
+    ModerationResult::flagged(categories)
+}
+
+/// A provider whose requests and completions are screened by a [`Moderator`]
+pub struct ModeratedProvider {
+    inner: Arc<dyn Provider>,
+    moderation: Moderation,
+}
+
+impl ModeratedProvider {
+    pub fn new(inner: Arc<dyn Provider>, moderation: Moderation) -> Self {
+        Self { inner, moderation }
+    }
+}
+
+#[async_trait]
+impl Provider for ModeratedProvider {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            "moderated",
+            "Moderated Provider",
+            "A provider that screens requests and completions with a moderation service",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_name(&self) -> &str {
+        self.inner.get_name()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn inner_provider(&self) -> Option<&dyn Provider> {
+        Some(self.inner.as_ref())
+    }
+
+    async fn complete_with_model(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.moderation.check_input(messages).await?;
+        let (message, usage) = self
+            .inner
+            .complete_with_model(model_config, system, messages, tools)
+            .await?;
+        self.moderation.check_output(&message).await?;
+        Ok((message, usage))
+    }
+
+    /// Completions can only be screened once they are whole, so output screening turns the
+    /// stream into a single message
+    async fn stream(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        if self.moderation.screen_output {
+            let (message, usage) = self.complete(system, messages, tools).await?;
+            return Ok(super::base::stream_from_single_message(message, usage));
+        }
+        self.moderation.check_input(messages).await?;
+        self.inner.stream(system, messages, tools).await
+    }
+
+    async fn stream_events(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<EventStream, ProviderError> {
+        if self.moderation.screen_output {
+            return Ok(into_events(self.stream(system, messages, tools).await?));
+        }
+        self.moderation.check_input(messages).await?;
+        self.inner.stream_events(system, messages, tools).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+    use std::sync::Mutex;
+
+    struct KeywordModerator {
+        keyword: &'static str,
+        screened: Mutex<Vec<(String, ModerationTarget)>>,
+    }
+
+    #[async_trait]
+    impl Moderator for KeywordModerator {
+        fn name(&self) -> &str {
+            "keyword"
+        }
+
+        async fn moderate(
+            &self,
+            text: &str,
+            target: ModerationTarget,
+        ) -> Result<ModerationResult, ProviderError> {
+            self.screened
+                .lock()
+                .unwrap()
+                .push((text.to_string(), target));
+            if text.contains(self.keyword) {
+                Ok(ModerationResult::flagged(vec!["violence".to_string()]))
+            } else {
+                Ok(ModerationResult::allowed())
+            }
+        }
+    }
+
+    struct EchoProvider;
+
+    #[async_trait]
+    impl Provider for EchoProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_name(&self) -> &str {
+            "echo"
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new_or_fail("echo-model")
+        }
+
+        async fn complete_with_model(
+            &self,
+            _model_config: &ModelConfig,
+            _system: &str,
+            messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            let text = messages.last().unwrap().as_concat_text();
+            Ok((
+                Message::assistant().with_text(format!("echo: {}", text)),
+                ProviderUsage::new("echo-model".to_string(), Usage::default()),
+            ))
+        }
+    }
+
+    fn moderated(keyword: &'static str) -> (ModeratedProvider, Arc<KeywordModerator>) {
+        let moderator = Arc::new(KeywordModerator {
+            keyword,
+            screened: Mutex::new(vec![]),
+        });
+        let provider = ModeratedProvider::new(
+            Arc::new(EchoProvider),
+            Moderation::new(moderator.clone(), true, true),
+        );
+        (provider, moderator)
+    }
+
+    #[tokio::test]
+    async fn test_moderated_provider_screens_input_and_output() {
+        let (provider, moderator) = moderated("attack");
+
+        let (message, _) = provider
+            .complete("", &[Message::user().with_text("hello")], &[])
+            .await
+            .unwrap();
+        assert_eq!(message.as_concat_text(), "echo: hello");
+        assert_eq!(
+            *moderator.screened.lock().unwrap(),
+            vec![
+                ("hello".to_string(), ModerationTarget::Input),
+                ("echo: hello".to_string(), ModerationTarget::Output),
+            ]
+        );
+
+        let error = provider
+            .complete("", &[Message::user().with_text("plan an attack")], &[])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            ProviderError::ContentFlagged {
+                target: ModerationTarget::Input,
+                categories: vec!["violence".to_string()],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_moderated_provider_skips_disabled_checks() {
+        let moderator = Arc::new(KeywordModerator {
+            keyword: "attack",
+            screened: Mutex::new(vec![]),
+        });
+        let provider = ModeratedProvider::new(
+            Arc::new(EchoProvider),
+            Moderation::new(moderator.clone(), false, true),
+        );
+
+        let error = provider
+            .complete("", &[Message::user().with_text("attack")], &[])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ProviderError::ContentFlagged {
+                target: ModerationTarget::Output,
+                ..
+            }
+        ));
+        assert_eq!(moderator.screened.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_moderator_sees_scrubbed_text() {
+        let moderator = Arc::new(KeywordModerator {
+            keyword: "attack",
+            screened: Mutex::new(vec![]),
+        });
+        let scrubber = Arc::new(PiiScrubber::new());
+        let provider = ModeratedProvider::new(
+            Arc::new(EchoProvider),
+            Moderation::new(moderator.clone(), true, true).with_pii_scrubber(scrubber.clone()),
+        );
+
+        provider
+            .complete(
+                "",
+                &[Message::user().with_text("mail bob@example.org")],
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            *moderator.screened.lock().unwrap(),
+            vec![
+                ("mail <EMAIL_1>".to_string(), ModerationTarget::Input),
+                ("echo: mail <EMAIL_1>".to_string(), ModerationTarget::Output),
+            ]
+        );
+        assert_eq!(
+            scrubber
+                .mapping(None)
+                .unwrap()
+                .entries()
+                .collect::<Vec<_>>(),
+            vec![("<EMAIL_1>", "bob@example.org")]
+        );
+    }
+
+    #[test]
+    fn test_parse_openai_moderation() {
+        let response = json!({
+            "results": [{
+                "flagged": true,
+                "categories": {"hate": true, "violence": false, "self-harm": true}
+            }]
+        });
+        let result = parse_openai_moderation(&response).unwrap();
+        assert!(result.flagged);
+        assert_eq!(result.categories, vec!["hate", "self-harm"]);
+
+        let response = json!({"results": [{"flagged": false, "categories": {"hate": false}}]});
+        assert_eq!(
+            parse_openai_moderation(&response).unwrap(),
+            ModerationResult::allowed()
+        );
+    }
+
+    #[test]
+    fn test_parse_llama_guard_verdict() {
+        assert_eq!(
+            parse_llama_guard_verdict("safe"),
+            ModerationResult::allowed()
+        );
+        assert_eq!(
+            parse_llama_guard_verdict("\nunsafe\nS1,S10"),
+            ModerationResult::flagged(vec!["violent_crimes".to_string(), "hate".to_string()])
+        );
+        assert_eq!(
+            parse_llama_guard_verdict("unsafe\nS99"),
+            ModerationResult::flagged(vec!["S99".to_string()])
+        );
+    }
+}
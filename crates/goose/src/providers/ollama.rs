@@ -1,10 +1,14 @@
-use super::api_client::{ApiClient, AuthMethod};
-use super::base::{ConfigKey, MessageStream, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::api_client::{ApiClient, AuthMethod, TlsConfig};
+use super::base::{
+    ConfigKey, MessageStream, ModelInfo, Provider, ProviderMetadata, ProviderUsage, Usage,
+};
+use super::embedding::{parse_embedding_vectors, EmbeddingOptions, EmbeddingProvider};
 use super::errors::ProviderError;
 use super::retry::ProviderRetry;
+use super::timeouts::RequestTimeouts;
 use super::utils::{
-    get_model, handle_response_openai_compat, handle_status_openai_compat, stream_openai_compat,
-    RequestLog,
+    get_model, get_system_fingerprint, handle_response_openai_compat, handle_status_openai_compat,
+    stream_openai_compat, RequestLog,
 };
 use crate::config::declarative_providers::DeclarativeProviderConfig;
 use crate::config::GooseMode;
@@ -25,6 +29,7 @@ use url::Url;
 pub const OLLAMA_HOST: &str = "localhost";
 pub const OLLAMA_TIMEOUT: u64 = 600;
 pub const OLLAMA_DEFAULT_PORT: u16 = 11434;
+pub const OLLAMA_DEFAULT_EMBEDDING_MODEL: &str = "nomic-embed-text";
 pub const OLLAMA_DEFAULT_MODEL: &str = "qwen3";
 pub const OLLAMA_KNOWN_MODELS: &[&str] = &[
     OLLAMA_DEFAULT_MODEL,
@@ -49,8 +54,7 @@ impl OllamaProvider {
             .get_param("OLLAMA_HOST")
             .unwrap_or_else(|_| OLLAMA_HOST.to_string());
 
-        let timeout: Duration =
-            Duration::from_secs(config.get_param("OLLAMA_TIMEOUT").unwrap_or(OLLAMA_TIMEOUT));
+        let timeouts = RequestTimeouts::from_config("OLLAMA", Duration::from_secs(OLLAMA_TIMEOUT));
 
         let base = if host.starts_with("http://") || host.starts_with("https://") {
             host.clone()
@@ -72,7 +76,10 @@ impl OllamaProvider {
         }
 
         let auth = AuthMethod::Custom(Box::new(NoAuth));
-        let api_client = ApiClient::with_timeout(base_url.to_string(), auth, timeout)?;
+        let mut api_client = ApiClient::with_timeouts(base_url.to_string(), auth, &timeouts)?;
+        if let Some(tls_config) = TlsConfig::from_config_prefix("OLLAMA")? {
+            api_client = api_client.with_tls_config(&tls_config)?;
+        }
 
         Ok(Self {
             api_client,
@@ -109,7 +116,10 @@ impl OllamaProvider {
         }
 
         let auth = AuthMethod::Custom(Box::new(NoAuth));
-        let api_client = ApiClient::with_timeout(base_url.to_string(), auth, timeout)?;
+        let mut api_client = ApiClient::with_timeout(base_url.to_string(), auth, timeout)?;
+        if let Some(tls_config) = config.tls_config()? {
+            api_client = api_client.with_tls_config(&tls_config)?;
+        }
 
         Ok(Self {
             api_client,
@@ -214,7 +224,11 @@ impl Provider for OllamaProvider {
         });
         let response_model = get_model(&response);
         log.write(&response, Some(&usage))?;
-        Ok((message, ProviderUsage::new(response_model, usage)))
+        Ok((
+            message,
+            ProviderUsage::new(response_model, usage)
+                .with_system_fingerprint(get_system_fingerprint(&response)),
+        ))
     }
 
     async fn generate_session_name(
@@ -314,6 +328,67 @@ impl Provider for OllamaProvider {
 
         Ok(Some(model_names))
     }
+
+    fn supports_embeddings(&self) -> bool {
+        true
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.embed(texts, &EmbeddingOptions::from_config()).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        // api/tags doesn't report context windows, so use the known model patterns
+        let models = self.fetch_supported_models().await?.unwrap_or_default();
+        Ok(models
+            .into_iter()
+            .map(|name| {
+                let context_limit = ModelConfig::known_context_limit(&name);
+                ModelInfo::new(name, context_limit)
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OllamaProvider {
+    fn default_embedding_model(&self) -> &str {
+        OLLAMA_DEFAULT_EMBEDDING_MODEL
+    }
+
+    fn max_embedding_batch_size(&self) -> usize {
+        128
+    }
+
+    async fn embed_batch(
+        &self,
+        texts: &[String],
+        model: &str,
+        dimensions: Option<usize>,
+    ) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let mut payload = serde_json::json!({
+            "model": model,
+            "input": texts,
+        });
+        if let Some(dimensions) = dimensions {
+            payload["dimensions"] = serde_json::json!(dimensions);
+        }
+
+        let response = self
+            .with_retry(|| async {
+                let response = self.api_client.response_post("api/embed", &payload).await?;
+                handle_response_openai_compat(response).await
+            })
+            .await?;
+
+        let embeddings = response
+            .get("embeddings")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| {
+                ProviderError::RequestFailed("Missing embeddings in response".to_string())
+            })?;
+        parse_embedding_vectors(embeddings)
+    }
 }
 
 impl OllamaProvider {
@@ -1,25 +1,31 @@
-use super::api_client::{ApiClient, AuthMethod};
+use super::api_client::{ApiClient, AuthMethod, TlsConfig};
 use super::base::{ConfigKey, ModelInfo, Provider, ProviderMetadata, ProviderUsage, Usage};
-use super::embedding::{EmbeddingCapable, EmbeddingRequest, EmbeddingResponse};
+use super::embedding::{EmbeddingOptions, EmbeddingProvider, EmbeddingRequest, EmbeddingResponse};
 use super::errors::ProviderError;
 use super::formats::openai::{create_request, get_usage, response_to_message};
 use super::formats::openai_responses::{
-    create_responses_request, get_responses_usage, responses_api_to_message,
-    responses_api_to_streaming_message, ResponsesApiResponse,
+    add_builtin_tools, builtin_tool_spec, create_responses_request, get_responses_usage,
+    responses_api_to_message, responses_api_to_streaming_message, ResponsesApiResponse,
+};
+use super::image_generation::{
+    parse_base64_images, ImageGenerationOptions, ImageGenerationProvider,
 };
 use super::retry::ProviderRetry;
+use super::stream_event::{into_events, into_message_stream, EventStream};
+use super::timeouts::{stream_error, RequestTimeouts, StreamTimeouts};
 use super::utils::{
-    get_model, handle_response_openai_compat, handle_status_openai_compat, stream_openai_compat,
-    ImageFormat,
+    get_model, get_system_fingerprint, handle_response_openai_compat, handle_status_openai_compat,
+    map_http_error_to_provider_error, stream_events_openai_compat, ImageFormat,
 };
 use crate::config::declarative_providers::DeclarativeProviderConfig;
-use crate::conversation::message::Message;
+use crate::config::ConfigError;
+use crate::conversation::message::{Message, MessageContent};
 use anyhow::Result;
 use async_stream::try_stream;
 use async_trait::async_trait;
 use futures::{StreamExt, TryStreamExt};
 use reqwest::StatusCode;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::io;
 use tokio::pin;
@@ -47,6 +53,8 @@ pub const OPEN_AI_KNOWN_MODELS: &[(&str, usize)] = &[
     ("gpt-5-codex", 400_000),
 ];
 
+pub const OPEN_AI_DEFAULT_IMAGE_MODEL: &str = "gpt-image-1";
+
 pub const OPEN_AI_DOC_URL: &str = "https://platform.openai.com/docs/models";
 
 #[derive(Debug, serde::Serialize)]
@@ -59,6 +67,10 @@ pub struct OpenAiProvider {
     model: ModelConfig,
     custom_headers: Option<HashMap<String, String>>,
     supports_streaming: bool,
+    /// Send every request through the Responses API instead of chat completions
+    use_responses_api: bool,
+    /// Server-side tools (web_search, file_search) added to Responses API requests
+    builtin_tools: Vec<Value>,
     name: String,
 }
 
@@ -81,11 +93,15 @@ impl OpenAiProvider {
             .get("OPENAI_CUSTOM_HEADERS")
             .cloned()
             .map(parse_custom_headers);
-        let timeout_secs: u64 = config.get_param("OPENAI_TIMEOUT").unwrap_or(600);
+        let timeouts = RequestTimeouts::from_config("OPENAI", std::time::Duration::from_secs(600));
+        let use_responses_api = Self::load_use_responses_api(config)?;
+        let builtin_tools = Self::load_builtin_tools(config)?;
 
         let auth = AuthMethod::BearerToken(api_key);
-        let mut api_client =
-            ApiClient::with_timeout(host, auth, std::time::Duration::from_secs(timeout_secs))?;
+        let mut api_client = ApiClient::with_timeouts(host, auth, &timeouts)?;
+        if let Some(tls_config) = TlsConfig::from_config_prefix("OPENAI")? {
+            api_client = api_client.with_tls_config(&tls_config)?;
+        }
 
         if let Some(org) = &organization {
             api_client = api_client.with_header("OpenAI-Organization", org)?;
@@ -113,6 +129,8 @@ impl OpenAiProvider {
             model,
             custom_headers,
             supports_streaming: true,
+            use_responses_api,
+            builtin_tools,
             name: Self::metadata().name,
         })
     }
@@ -127,6 +145,8 @@ impl OpenAiProvider {
             model,
             custom_headers: None,
             supports_streaming: true,
+            use_responses_api: false,
+            builtin_tools: Vec::new(),
             name: Self::metadata().name,
         }
     }
@@ -164,6 +184,9 @@ impl OpenAiProvider {
         let auth = AuthMethod::BearerToken(api_key);
         let mut api_client =
             ApiClient::with_timeout(host, auth, std::time::Duration::from_secs(timeout_secs))?;
+        if let Some(tls_config) = config.tls_config()? {
+            api_client = api_client.with_tls_config(&tls_config)?;
+        }
 
         // Add custom headers if present
         if let Some(headers) = &config.headers {
@@ -184,12 +207,65 @@ impl OpenAiProvider {
             model,
             custom_headers: config.headers,
             supports_streaming: config.supports_streaming.unwrap_or(true),
+            use_responses_api: false,
+            builtin_tools: Vec::new(),
             name: config.name.clone(),
         })
     }
 
-    fn uses_responses_api(model_name: &str) -> bool {
-        model_name.starts_with("gpt-5-codex") || model_name.starts_with("gpt-5.1-codex")
+    /// Read OPENAI_API_MODE, either `chat_completions` (the default) or `responses`
+    fn load_use_responses_api(config: &crate::config::Config) -> Result<bool, ConfigError> {
+        let mode = match config.get_param::<String>("OPENAI_API_MODE") {
+            Ok(mode) => mode,
+            Err(ConfigError::NotFound(_)) => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        match mode.as_str() {
+            "chat_completions" => Ok(false),
+            "responses" => Ok(true),
+            other => Err(ConfigError::DeserializeError(format!(
+                "OPENAI_API_MODE must be 'chat_completions' or 'responses', got '{}'",
+                other
+            ))),
+        }
+    }
+
+    /// Read OPENAI_BUILTIN_TOOLS, a comma-separated list of server-side tools. file_search
+    /// searches the vector stores listed in OPENAI_VECTOR_STORE_IDS. Their calls come back as
+    /// text content, not as tool requests.
+    fn load_builtin_tools(config: &crate::config::Config) -> Result<Vec<Value>> {
+        let Ok(names) = config.get_param::<String>("OPENAI_BUILTIN_TOOLS") else {
+            return Ok(Vec::new());
+        };
+        let vector_store_ids: Vec<String> = config
+            .get_param::<String>("OPENAI_VECTOR_STORE_IDS")
+            .map(|ids| split_list(&ids))
+            .unwrap_or_default();
+
+        split_list(&names)
+            .iter()
+            .map(|name| builtin_tool_spec(name, &vector_store_ids))
+            .collect()
+    }
+
+    fn uses_responses_api(&self, model_name: &str) -> bool {
+        // Built-in tools only exist in the Responses API
+        self.use_responses_api
+            || !self.builtin_tools.is_empty()
+            || model_name.starts_with("gpt-5-codex")
+            || model_name.starts_with("gpt-5.1-codex")
+    }
+
+    fn create_responses_request(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<Value, ProviderError> {
+        let mut payload = create_responses_request(model_config, system, messages, tools)?;
+        add_builtin_tools(&mut payload, &self.builtin_tools);
+        Ok(payload)
     }
 
     async fn post(&self, payload: &Value) -> Result<Value, ProviderError> {
@@ -231,6 +307,9 @@ impl Provider for OpenAiProvider {
                 ConfigKey::new("OPENAI_PROJECT", false, false, None),
                 ConfigKey::new("OPENAI_CUSTOM_HEADERS", false, true, None),
                 ConfigKey::new("OPENAI_TIMEOUT", false, false, Some("600")),
+                ConfigKey::new("OPENAI_API_MODE", false, false, Some("chat_completions")),
+                ConfigKey::new("OPENAI_BUILTIN_TOOLS", false, false, None),
+                ConfigKey::new("OPENAI_VECTOR_STORE_IDS", false, false, None),
             ],
         )
     }
@@ -254,8 +333,8 @@ impl Provider for OpenAiProvider {
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        if Self::uses_responses_api(&model_config.model_name) {
-            let payload = create_responses_request(model_config, system, messages, tools)?;
+        if self.uses_responses_api(&model_config.model_name) {
+            let payload = self.create_responses_request(model_config, system, messages, tools)?;
             let mut log = RequestLog::start(&self.model, &payload)?;
 
             let json_response = self
@@ -313,8 +392,12 @@ impl Provider for OpenAiProvider {
                 });
 
             let model = get_model(&json_response);
+            let system_fingerprint = get_system_fingerprint(&json_response);
             log.write(&json_response, Some(&usage))?;
-            Ok((message, ProviderUsage::new(model, usage)))
+            Ok((
+                message,
+                ProviderUsage::new(model, usage).with_system_fingerprint(system_fingerprint),
+            ))
         }
     }
 
@@ -352,28 +435,49 @@ impl Provider for OpenAiProvider {
         Ok(Some(models))
     }
 
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        // The models endpoint only returns ids, so context limits come from known patterns
+        let models = self.fetch_supported_models().await?.unwrap_or_default();
+        Ok(models
+            .into_iter()
+            .map(|name| {
+                let context_limit = ModelConfig::known_context_limit(&name);
+                ModelInfo::new(name, context_limit)
+            })
+            .collect())
+    }
+
     fn supports_embeddings(&self) -> bool {
         true
     }
 
     async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
-        EmbeddingCapable::create_embeddings(self, texts)
-            .await
-            .map_err(|e| ProviderError::ExecutionError(e.to_string()))
+        self.embed(texts, &EmbeddingOptions::from_config()).await
     }
 
     fn supports_streaming(&self) -> bool {
         self.supports_streaming
     }
 
+    fn supports_audio_input(&self) -> bool {
+        // Only the audio chat models take input_audio, and the Responses API doesn't accept it
+        let model_name = &self.model.model_name;
+        model_name.contains("-audio") && !self.uses_responses_api(model_name)
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        !self.uses_responses_api(&self.model.model_name)
+    }
+
     async fn stream(
         &self,
         system: &str,
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<MessageStream, ProviderError> {
-        if Self::uses_responses_api(&self.model.model_name) {
-            let mut payload = create_responses_request(&self.model, system, messages, tools)?;
+        if self.uses_responses_api(&self.model.model_name) {
+            let mut payload =
+                self.create_responses_request(&self.model, system, messages, tools)?;
             payload["stream"] = serde_json::Value::Bool(true);
 
             let mut log = RequestLog::start(&self.model, &payload)?;
@@ -397,44 +501,68 @@ impl Provider for OpenAiProvider {
             Ok(Box::pin(try_stream! {
                 let stream_reader = StreamReader::new(stream);
                 let framed = FramedRead::new(stream_reader, LinesCodec::new()).map_err(anyhow::Error::from);
+                let framed = StreamTimeouts::from_config().apply(framed);
 
                 let message_stream = responses_api_to_streaming_message(framed);
                 pin!(message_stream);
                 while let Some(message) = message_stream.next().await {
-                    let (message, usage) = message.map_err(|e| ProviderError::RequestFailed(format!("Stream decode error: {}", e)))?;
+                    let (message, usage) = message.map_err(stream_error)?;
                     log.write(&message, usage.as_ref().map(|f| f.usage).as_ref())?;
                     yield (message, usage);
                 }
             }))
         } else {
-            let payload = create_request(
-                &self.model,
-                system,
-                messages,
-                tools,
-                &ImageFormat::OpenAi,
-                true,
-            )?;
-            let mut log = RequestLog::start(&self.model, &payload)?;
-
-            let response = self
-                .with_retry(|| async {
-                    let resp = self
-                        .api_client
-                        .response_post(&self.base_path, &payload)
-                        .await?;
-                    handle_status_openai_compat(resp).await
-                })
-                .await
-                .inspect_err(|e| {
-                    let _ = log.error(e);
-                })?;
+            Ok(into_message_stream(
+                self.stream_events(system, messages, tools).await?,
+            ))
+        }
+    }
 
-            stream_openai_compat(response, log)
+    /// Chat completions are parsed into events as they arrive; the Responses API goes through
+    /// [`stream`](Provider::stream)
+    async fn stream_events(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<EventStream, ProviderError> {
+        if self.uses_responses_api(&self.model.model_name) {
+            return Ok(into_events(self.stream(system, messages, tools).await?));
         }
+        let payload = create_request(
+            &self.model,
+            system,
+            messages,
+            tools,
+            &ImageFormat::OpenAi,
+            true,
+        )?;
+        let mut log = RequestLog::start(&self.model, &payload)?;
+
+        let response = self
+            .with_retry(|| async {
+                let resp = self
+                    .api_client
+                    .response_post(&self.base_path, &payload)
+                    .await?;
+                handle_status_openai_compat(resp).await
+            })
+            .await
+            .inspect_err(|e| {
+                let _ = log.error(e);
+            })?;
+
+        Ok(stream_events_openai_compat(response, log))
     }
 }
 
+fn split_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
 fn parse_custom_headers(s: String) -> HashMap<String, String> {
     s.split(',')
         .filter_map(|header| {
@@ -447,28 +575,31 @@ fn parse_custom_headers(s: String) -> HashMap<String, String> {
 }
 
 #[async_trait]
-impl EmbeddingCapable for OpenAiProvider {
-    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
-        if texts.is_empty() {
-            return Ok(vec![]);
-        }
+impl EmbeddingProvider for OpenAiProvider {
+    fn default_embedding_model(&self) -> &str {
+        "text-embedding-3-small"
+    }
 
-        let embedding_model = std::env::var("GOOSE_EMBEDDING_MODEL")
-            .unwrap_or_else(|_| "text-embedding-3-small".to_string());
+    fn max_embedding_batch_size(&self) -> usize {
+        2048
+    }
 
+    async fn embed_batch(
+        &self,
+        texts: &[String],
+        model: &str,
+        dimensions: Option<usize>,
+    ) -> Result<Vec<Vec<f32>>, ProviderError> {
         let request = EmbeddingRequest {
-            input: texts,
-            model: embedding_model,
+            input: texts.to_vec(),
+            model: model.to_string(),
+            dimensions,
         };
+        let request_value = serde_json::to_value(&request)
+            .map_err(|e| ProviderError::ExecutionError(e.to_string()))?;
 
         let response = self
             .with_retry(|| async {
-                let request_clone = EmbeddingRequest {
-                    input: request.input.clone(),
-                    model: request.model.clone(),
-                };
-                let request_value = serde_json::to_value(request_clone)
-                    .map_err(|e| ProviderError::ExecutionError(e.to_string()))?;
                 self.api_client
                     .api_post("v1/embeddings", &request_value)
                     .await
@@ -477,19 +608,18 @@ impl EmbeddingCapable for OpenAiProvider {
             .await?;
 
         if response.status != StatusCode::OK {
-            let error_text = response
-                .payload
-                .as_ref()
-                .and_then(|p| p.as_str())
-                .unwrap_or("Unknown error");
-            return Err(anyhow::anyhow!("Embedding API error: {}", error_text));
+            return Err(
+                map_http_error_to_provider_error(response.status, response.payload)
+                    .with_retry_delay(response.retry_delay),
+            );
         }
 
         let embedding_response: EmbeddingResponse = serde_json::from_value(
             response
                 .payload
-                .ok_or_else(|| anyhow::anyhow!("Empty response body"))?,
-        )?;
+                .ok_or_else(|| ProviderError::RequestFailed("Empty response body".to_string()))?,
+        )
+        .map_err(|e| ProviderError::RequestFailed(format!("Invalid embedding response: {}", e)))?;
 
         Ok(embedding_response
             .data
@@ -498,3 +628,48 @@ impl EmbeddingCapable for OpenAiProvider {
             .collect())
     }
 }
+
+#[async_trait]
+impl ImageGenerationProvider for OpenAiProvider {
+    fn default_image_model(&self) -> &str {
+        OPEN_AI_DEFAULT_IMAGE_MODEL
+    }
+
+    async fn generate_images(
+        &self,
+        prompt: &str,
+        options: &ImageGenerationOptions,
+    ) -> Result<Vec<MessageContent>, ProviderError> {
+        let mut payload = json!({
+            "model": options.model.as_deref().unwrap_or(OPEN_AI_DEFAULT_IMAGE_MODEL),
+            "prompt": prompt,
+            "n": options.count.unwrap_or(1),
+        });
+        if let Some(size) = &options.size {
+            payload["size"] = json!(size);
+        }
+        // dall-e models return URLs unless asked for base64; gpt-image models always return base64
+        if payload["model"]
+            .as_str()
+            .is_some_and(|model| model.starts_with("dall-e"))
+        {
+            payload["response_format"] = json!("b64_json");
+        }
+
+        let response = self
+            .with_retry(|| async {
+                let response = self
+                    .api_client
+                    .response_post("v1/images/generations", &payload)
+                    .await?;
+                handle_response_openai_compat(response).await
+            })
+            .await?;
+
+        let data = response
+            .get("data")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| ProviderError::RequestFailed("Missing data in response".to_string()))?;
+        parse_base64_images(data.iter().map(|d| &d["b64_json"]), "image/png")
+    }
+}
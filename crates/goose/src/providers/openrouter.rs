@@ -3,22 +3,26 @@ use async_trait::async_trait;
 use serde_json::{json, Value};
 
 use super::api_client::{ApiClient, AuthMethod};
-use super::base::{ConfigKey, MessageStream, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::base::{
+    ConfigKey, MessageStream, ModelInfo, Provider, ProviderMetadata, ProviderUsage, Usage,
+};
 use super::errors::ProviderError;
 use super::retry::ProviderRetry;
 use super::utils::{
-    get_model, handle_response_google_compat, handle_response_openai_compat,
-    handle_status_openai_compat, is_google_model, stream_openai_compat, RequestLog,
+    get_model, get_system_fingerprint, handle_response_google_compat,
+    handle_response_openai_compat, handle_status_openai_compat, is_google_model,
+    stream_openai_compat, RequestLog,
 };
 use crate::conversation::message::Message;
 
 use crate::model::ModelConfig;
-use crate::providers::formats::openai::{create_request, get_usage, response_to_message};
+use crate::providers::formats::openai::{
+    create_request, get_reported_cost, get_usage, response_to_message,
+};
 use rmcp::model::Tool;
 
 pub const OPENROUTER_DEFAULT_MODEL: &str = "anthropic/claude-sonnet-4";
 pub const OPENROUTER_DEFAULT_FAST_MODEL: &str = "google/gemini-flash-2.5";
-pub const OPENROUTER_MODEL_PREFIX_ANTHROPIC: &str = "anthropic";
 
 // OpenRouter can run many models, we suggest the default
 pub const OPENROUTER_KNOWN_MODELS: &[&str] = &[
@@ -220,10 +224,10 @@ async fn create_request_based_on_model(
         payload = update_request_for_anthropic(&payload);
     }
 
-    payload
-        .as_object_mut()
-        .unwrap()
-        .insert("transforms".to_string(), json!(["middle-out"]));
+    let payload_obj = payload.as_object_mut().unwrap();
+    payload_obj.insert("transforms".to_string(), json!(["middle-out"]));
+    // Ask OpenRouter to include the billed cost in the usage block
+    payload_obj.insert("usage".to_string(), json!({"include": true}));
 
     Ok(payload)
 }
@@ -288,7 +292,12 @@ impl Provider for OpenRouterProvider {
         });
         let response_model = get_model(&response);
         log.write(&response, Some(&usage))?;
-        Ok((message, ProviderUsage::new(response_model, usage)))
+        Ok((
+            message,
+            ProviderUsage::new(response_model, usage)
+                .with_system_fingerprint(get_system_fingerprint(&response))
+                .with_cost(response.get("usage").and_then(get_reported_cost)),
+        ))
     }
 
     /// Fetch supported models from OpenRouter API (only models with tool support)
@@ -368,10 +377,43 @@ impl Provider for OpenRouterProvider {
         Ok(Some(models))
     }
 
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        let response = self.api_client.response_get("api/v1/models").await?;
+        let json = handle_response_openai_compat(response).await?;
+
+        let data = json.get("data").and_then(|v| v.as_array()).ok_or_else(|| {
+            ProviderError::UsageError("Missing data field in JSON response".into())
+        })?;
+
+        let mut models: Vec<ModelInfo> = data
+            .iter()
+            .filter_map(|model| {
+                let id = model.get("id").and_then(|v| v.as_str())?;
+                let context_limit = model
+                    .get("context_length")
+                    .and_then(|v| v.as_u64())
+                    .map(|n| n as usize)
+                    .unwrap_or_else(|| ModelConfig::known_context_limit(id));
+
+                let mut info = ModelInfo::new(id, context_limit);
+                info.supports_tools = model
+                    .get("supported_parameters")
+                    .and_then(|v| v.as_array())
+                    .map(|params| params.iter().any(|p| p.as_str() == Some("tools")));
+                info.supports_vision = model
+                    .pointer("/architecture/input_modalities")
+                    .and_then(|v| v.as_array())
+                    .map(|modalities| modalities.iter().any(|m| m.as_str() == Some("image")));
+                Some(info)
+            })
+            .collect();
+
+        models.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(models)
+    }
+
     async fn supports_cache_control(&self) -> bool {
-        self.model
-            .model_name
-            .starts_with(OPENROUTER_MODEL_PREFIX_ANTHROPIC)
+        self.model.capabilities().supports_caching.unwrap_or(false)
     }
 
     fn supports_streaming(&self) -> bool {
@@ -397,10 +439,10 @@ impl Provider for OpenRouterProvider {
             payload = update_request_for_anthropic(&payload);
         }
 
-        payload
-            .as_object_mut()
-            .unwrap()
-            .insert("transforms".to_string(), json!(["middle-out"]));
+        let payload_obj = payload.as_object_mut().unwrap();
+        payload_obj.insert("transforms".to_string(), json!(["middle-out"]));
+        // Ask OpenRouter to include the billed cost in the usage block
+        payload_obj.insert("usage".to_string(), json!({"include": true}));
 
         let mut log = RequestLog::start(&self.model, &payload)?;
 
@@ -0,0 +1,446 @@
+//! Pseudonymization of personal data in outgoing prompts.
+//!
+//! With `GOOSE_PII_SCRUBBING` enabled, [`PiiScrubber`] replaces email addresses, phone numbers
+//! and credit card numbers in the system prompt, messages and tool calls with placeholders such
+//! as `<EMAIL_1>` before a request leaves the machine, and puts the original values back into
+//! the response. Each session keeps its own mapping in memory, so a value gets the same
+//! placeholder every turn and the model can refer to it consistently.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use rmcp::model::RawContent;
+use serde_json::Value;
+
+use super::base::{Provider, ProviderUsage};
+use super::errors::ProviderError;
+use super::middleware::{MiddlewareProvider, ProviderMiddleware, ProviderRequest};
+use crate::config::Config;
+use crate::conversation::message::{Message, MessageContent};
+
+pub const PII_SCRUBBING_CONFIG_KEY: &str = "GOOSE_PII_SCRUBBING";
+
+/// Placeholders are never longer than this, which bounds how much streamed text is held back
+const MAX_PLACEHOLDER_LEN: usize = 24;
+
+static EMAIL_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b[A-Za-z0-9._%+\-]+@[A-Za-z0-9\-]+(?:\.[A-Za-z0-9\-]+)*\.[A-Za-z]{2,}\b")
+        .expect("valid email pattern")
+});
+
+/// 13 to 19 digits, optionally grouped with spaces or dashes; matches are checked with Luhn
+static CARD_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b\d(?:[ \-]?\d){12,18}\b").expect("valid card pattern"));
+
+/// International numbers with a leading `+`, and North American numbers written with
+/// separators, so bare digit runs such as ids and timestamps are left alone
+static PHONE_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?:\+\d{1,3}(?:[ .\-]?\(?\d{1,4}\)?){2,5}\b|(?:\(\d{3}\)\s?|\b\d{3}[.\-\s])\d{3}[.\-\s]\d{4}\b)",
+    )
+    .expect("valid phone pattern")
+});
+
+static PLACEHOLDER_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<(?:EMAIL|PHONE|CARD)_\d+>").expect("valid placeholder pattern"));
+
+static PARTIAL_PLACEHOLDER_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<[A-Z]*_?\d*$").expect("valid partial placeholder pattern"));
+
+static SCRUBBER: Lazy<Arc<PiiScrubber>> = Lazy::new(|| Arc::new(PiiScrubber::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PiiKind {
+    Email,
+    Phone,
+    CreditCard,
+}
+
+impl PiiKind {
+    fn label(self) -> &'static str {
+        match self {
+            PiiKind::Email => "EMAIL",
+            PiiKind::Phone => "PHONE",
+            PiiKind::CreditCard => "CARD",
+        }
+    }
+}
+
+fn luhn_valid(digits: &str) -> bool {
+    let digits: Vec<u32> = digits.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+/// The reversible mapping between personal data and placeholders for one session
+#[derive(Debug, Clone, Default)]
+pub struct PiiMapping {
+    placeholders: HashMap<String, String>,
+    originals: HashMap<String, String>,
+    counts: HashMap<PiiKind, usize>,
+}
+
+impl PiiMapping {
+    fn placeholder(&mut self, kind: PiiKind, original: &str) -> String {
+        if let Some(placeholder) = self.placeholders.get(original) {
+            return placeholder.clone();
+        }
+        let count = self.counts.entry(kind).or_default();
+        *count += 1;
+        let placeholder = format!("<{}_{}>", kind.label(), count);
+        self.placeholders
+            .insert(original.to_string(), placeholder.clone());
+        self.originals
+            .insert(placeholder.clone(), original.to_string());
+        placeholder
+    }
+
+    /// `text` with personal data replaced by placeholders
+    pub fn scrub(&mut self, text: &str) -> String {
+        let text = EMAIL_PATTERN.replace_all(text, |caps: &Captures| {
+            self.placeholder(PiiKind::Email, &caps[0])
+        });
+        let text = CARD_PATTERN.replace_all(&text, |caps: &Captures| {
+            if luhn_valid(&caps[0]) {
+                self.placeholder(PiiKind::CreditCard, &caps[0])
+            } else {
+                caps[0].to_string()
+            }
+        });
+        PHONE_PATTERN
+            .replace_all(&text, |caps: &Captures| {
+                self.placeholder(PiiKind::Phone, &caps[0])
+            })
+            .into_owned()
+    }
+
+    /// `text` with known placeholders replaced by the values they stand for
+    pub fn restore(&self, text: &str) -> String {
+        PLACEHOLDER_PATTERN
+            .replace_all(text, |caps: &Captures| {
+                self.originals
+                    .get(&caps[0])
+                    .cloned()
+                    .unwrap_or_else(|| caps[0].to_string())
+            })
+            .into_owned()
+    }
+
+    /// Placeholders and the values they stand for
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.originals
+            .iter()
+            .map(|(placeholder, original)| (placeholder.as_str(), original.as_str()))
+    }
+
+    fn scrub_json(&mut self, value: &mut Value) {
+        match value {
+            Value::String(s) => *s = self.scrub(s),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.scrub_json(item)),
+            Value::Object(map) => map.values_mut().for_each(|item| self.scrub_json(item)),
+            _ => {}
+        }
+    }
+
+    fn restore_json(&self, value: &mut Value) {
+        match value {
+            Value::String(s) => *s = self.restore(s),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.restore_json(item)),
+            Value::Object(map) => map.values_mut().for_each(|item| self.restore_json(item)),
+            _ => {}
+        }
+    }
+
+    fn scrub_message(&mut self, message: &mut Message) {
+        for content in &mut message.content {
+            match content {
+                MessageContent::Text(text) => text.text = self.scrub(&text.text),
+                MessageContent::ToolRequest(request) => {
+                    if let Ok(call) = &mut request.tool_call {
+                        if let Some(arguments) = &mut call.arguments {
+                            arguments
+                                .values_mut()
+                                .for_each(|value| self.scrub_json(value));
+                        }
+                    }
+                }
+                MessageContent::ToolResponse(response) => {
+                    if let Ok(result) = &mut response.tool_result {
+                        for content in &mut result.content {
+                            if let RawContent::Text(text) = &mut content.raw {
+                                text.text = self.scrub(&text.text);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn restore_message(&self, message: &mut Message) {
+        for content in &mut message.content {
+            match content {
+                MessageContent::Text(text) => text.text = self.restore(&text.text),
+                MessageContent::ToolRequest(request) => {
+                    if let Ok(call) = &mut request.tool_call {
+                        if let Some(arguments) = &mut call.arguments {
+                            arguments
+                                .values_mut()
+                                .for_each(|value| self.restore_json(value));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Split off a trailing `<EMA...` that may be the start of a placeholder continued in the next
+/// chunk
+fn split_partial_placeholder(text: &str) -> (&str, &str) {
+    match PARTIAL_PLACEHOLDER_PATTERN.find(text) {
+        Some(partial) if text.len() - partial.start() <= MAX_PLACEHOLDER_LEN => {
+            text.split_at(partial.start())
+        }
+        _ => (text, ""),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PiiScrubber {
+    sessions: Mutex<HashMap<Option<String>, PiiMapping>>,
+    /// Streamed text held back per request until a placeholder it may start is complete
+    pending: Mutex<HashMap<String, String>>,
+}
+
+impl PiiScrubber {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The mapping used for `session_id` so far, if any data has been scrubbed
+    pub fn mapping(&self, session_id: Option<&str>) -> Option<PiiMapping> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(&session_id.map(str::to_string))
+            .cloned()
+    }
+
+    /// `text` with personal data replaced by the placeholders `session_id`'s requests use
+    pub fn scrub_text(&self, session_id: Option<&str>, text: &str) -> String {
+        self.with_mapping(&session_id.map(str::to_string), |mapping| {
+            mapping.scrub(text)
+        })
+    }
+
+    /// Forget the mapping of a finished session
+    pub fn clear_session(&self, session_id: Option<&str>) {
+        self.sessions
+            .lock()
+            .unwrap()
+            .remove(&session_id.map(str::to_string));
+    }
+
+    fn with_mapping<T>(
+        &self,
+        session_id: &Option<String>,
+        f: impl FnOnce(&mut PiiMapping) -> T,
+    ) -> T {
+        let mut sessions = self.sessions.lock().unwrap();
+        f(sessions.entry(session_id.clone()).or_default())
+    }
+}
+
+#[async_trait]
+impl ProviderMiddleware for PiiScrubber {
+    async fn on_request(&self, request: &mut ProviderRequest) -> Result<(), ProviderError> {
+        let session_id = request.session_id.clone();
+        self.with_mapping(&session_id, |mapping| {
+            request.system = mapping.scrub(&request.system);
+            for message in &mut request.messages {
+                mapping.scrub_message(message);
+            }
+        });
+        Ok(())
+    }
+
+    async fn on_response(
+        &self,
+        request: &ProviderRequest,
+        message: &mut Message,
+        _usage: &mut ProviderUsage,
+    ) -> Result<(), ProviderError> {
+        self.with_mapping(&request.session_id, |mapping| {
+            mapping.restore_message(message)
+        });
+        Ok(())
+    }
+
+    async fn on_error(&self, request: &ProviderRequest, error: ProviderError) -> ProviderError {
+        self.pending.lock().unwrap().remove(&request.id);
+        error
+    }
+
+    fn on_stream_chunk(
+        &self,
+        request: &ProviderRequest,
+        message: &mut Option<Message>,
+        usage: &mut Option<ProviderUsage>,
+    ) -> Result<(), ProviderError> {
+        let mut pending = self.pending.lock().unwrap();
+        let mut held = pending.remove(&request.id).unwrap_or_default();
+        let last_chunk = usage.as_ref().is_some_and(|usage| !usage.interim);
+
+        if let Some(message) = message {
+            for content in &mut message.content {
+                if let MessageContent::Text(text) = content {
+                    let combined = std::mem::take(&mut held) + &text.text;
+                    let (ready, rest) = if last_chunk {
+                        (combined.as_str(), "")
+                    } else {
+                        split_partial_placeholder(&combined)
+                    };
+                    text.text = ready.to_string();
+                    held = rest.to_string();
+                }
+            }
+        }
+        if !held.is_empty() {
+            if last_chunk {
+                match message {
+                    Some(message) => message.content.push(MessageContent::text(held)),
+                    None => *message = Some(Message::assistant().with_text(held)),
+                }
+            } else {
+                pending.insert(request.id.clone(), held);
+            }
+        }
+        drop(pending);
+
+        if let Some(message) = message {
+            self.with_mapping(&request.session_id, |mapping| {
+                mapping.restore_message(message)
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The process-wide scrubber used by [`with_configured_pii_scrubbing`]
+pub fn pii_scrubber() -> Arc<PiiScrubber> {
+    SCRUBBER.clone()
+}
+
+/// The process-wide scrubber when `GOOSE_PII_SCRUBBING` is enabled
+pub fn configured_pii_scrubber() -> Option<Arc<PiiScrubber>> {
+    Config::global()
+        .get_param::<bool>(PII_SCRUBBING_CONFIG_KEY)
+        .unwrap_or(false)
+        .then(pii_scrubber)
+}
+
+/// Wrap `provider` with the process-wide [`PiiScrubber`] when `GOOSE_PII_SCRUBBING` is enabled
+pub fn with_configured_pii_scrubbing(provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+    match configured_pii_scrubber() {
+        Some(scrubber) => Arc::new(MiddlewareProvider::new(
+            provider,
+            vec![scrubber as Arc<dyn ProviderMiddleware>],
+        )),
+        None => provider,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use futures::TryStreamExt;
+
+    #[test]
+    fn test_scrub_and_restore() {
+        let mut mapping = PiiMapping::default();
+        let text = "Mail jane.doe@example.com or call +1 415 555 0100 / (415) 555-0199, \
+                    card 4111 1111 1111 1111, order 1234567890123";
+        let scrubbed = mapping.scrub(text);
+        assert_eq!(
+            scrubbed,
+            "Mail <EMAIL_1> or call <PHONE_1> / <PHONE_2>, card <CARD_1>, order 1234567890123"
+        );
+        assert_eq!(
+            mapping.scrub("again jane.doe@example.com"),
+            "again <EMAIL_1>"
+        );
+        assert_eq!(mapping.restore(&scrubbed), text);
+        assert_eq!(mapping.restore("<EMAIL_9>"), "<EMAIL_9>");
+    }
+
+    #[tokio::test]
+    async fn test_scrubs_requests_and_restores_responses() {
+        let scrubber = Arc::new(PiiScrubber::new());
+        let mock = Arc::new(
+            MockProvider::new()
+                .with_response(MockResponse::text("I'll email <EMAIL_1> now"))
+                .with_response(
+                    MockResponse::stream(["Sent to <EMA", "IL_1>", " <EMAIL_2"])
+                        .with_usage(Usage::new(Some(1), Some(1), Some(2))),
+                ),
+        );
+        let provider = MiddlewareProvider::new(
+            mock.clone(),
+            vec![scrubber.clone() as Arc<dyn ProviderMiddleware>],
+        );
+
+        let user = [Message::user().with_text("Email bob@example.org please")];
+        let (message, _) = provider.complete("system", &user, &[]).await.unwrap();
+        assert_eq!(message.as_concat_text(), "I'll email bob@example.org now");
+        assert_eq!(
+            mock.requests()[0].messages[0].as_concat_text(),
+            "Email <EMAIL_1> please"
+        );
+
+        let chunks: Vec<_> = provider
+            .stream("system", &user, &[])
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let text: String = chunks
+            .iter()
+            .filter_map(|(message, _)| message.as_ref().map(Message::as_concat_text))
+            .collect();
+        assert_eq!(text, "Sent to bob@example.org <EMAIL_2");
+
+        let mapping = scrubber.mapping(None).unwrap();
+        assert_eq!(
+            mapping.entries().collect::<Vec<_>>(),
+            vec![("<EMAIL_1>", "bob@example.org")]
+        );
+    }
+}
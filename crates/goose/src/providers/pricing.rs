@@ -0,0 +1,117 @@
+//! Per-model token pricing used to compute the cost of a request.
+//!
+//! Prices come from the bundled canonical model registry. They can be overridden, or added for
+//! models the registry doesn't know about, with the `GOOSE_MODEL_PRICING` config key. Entries
+//! are keyed by `provider/model` or by bare model name, with costs in USD per token:
+//!
+//! ```yaml
+//! GOOSE_MODEL_PRICING:
+//!   openai/gpt-4o:
+//!     prompt: 0.0000025
+//!     completion: 0.00001
+//!     input_cache_read: 0.00000125
+//! ```
+
+use std::collections::HashMap;
+
+use super::base::Usage;
+use super::canonical::{maybe_get_canonical_model, Pricing};
+use crate::config::Config;
+use crate::model_catalog;
+
+pub const MODEL_PRICING_CONFIG_KEY: &str = "GOOSE_MODEL_PRICING";
+
+/// Look up pricing for a model, preferring user overrides over the bundled table
+pub fn get_model_pricing(provider: &str, model: &str) -> Option<Pricing> {
+    let overrides: HashMap<String, Pricing> = Config::global()
+        .get_param(MODEL_PRICING_CONFIG_KEY)
+        .unwrap_or_default();
+
+    find_pricing_override(&overrides, provider, model)
+        .or_else(|| model_catalog::configured(Some(provider), model).pricing)
+        .or_else(|| maybe_get_canonical_model(provider, model).map(|m| m.pricing))
+}
+
+fn find_pricing_override(
+    overrides: &HashMap<String, Pricing>,
+    provider: &str,
+    model: &str,
+) -> Option<Pricing> {
+    overrides
+        .get(&format!("{}/{}", provider, model))
+        .or_else(|| overrides.get(model))
+        .cloned()
+}
+
+/// Cost in USD of a request with the given usage. Cached input tokens are billed at the cache
+/// read rate when the pricing has one, and at the normal prompt rate otherwise.
+pub fn calculate_cost(pricing: &Pricing, usage: &Usage) -> Option<f64> {
+    if usage.input_tokens.is_none() && usage.output_tokens.is_none() {
+        return None;
+    }
+
+    let prompt_rate = pricing.prompt?;
+    let completion_rate = pricing.completion?;
+    let cache_read_rate = pricing.input_cache_read.unwrap_or(prompt_rate);
+
+    let input_tokens = usage.input_tokens.unwrap_or(0).max(0);
+    let cached_tokens = usage
+        .cached_input_tokens
+        .unwrap_or(0)
+        .clamp(0, input_tokens);
+    let output_tokens = usage.output_tokens.unwrap_or(0).max(0);
+
+    Some(
+        (input_tokens - cached_tokens) as f64 * prompt_rate
+            + cached_tokens as f64 * cache_read_rate
+            + output_tokens as f64 * completion_rate
+            + pricing.request.unwrap_or(0.0),
+    )
+}
+
+/// Estimate the cost of a request from the pricing table, if the model's pricing is known
+pub fn estimate_cost(provider: &str, model: &str, usage: &Usage) -> Option<f64> {
+    let pricing = get_model_pricing(provider, model)?;
+    calculate_cost(&pricing, usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pricing(prompt: f64, completion: f64, input_cache_read: Option<f64>) -> Pricing {
+        Pricing {
+            prompt: Some(prompt),
+            completion: Some(completion),
+            request: None,
+            image: None,
+            input_cache_read,
+        }
+    }
+
+    #[test]
+    fn test_calculate_cost() {
+        let usage = Usage::new(Some(1000), Some(500), None);
+        let cost = calculate_cost(&pricing(0.000002, 0.00001, None), &usage).unwrap();
+        assert!((cost - 0.007).abs() < 1e-12);
+
+        let cached = usage.with_cached_input_tokens(Some(800));
+        let cost = calculate_cost(&pricing(0.000002, 0.00001, Some(0.0000005)), &cached).unwrap();
+        assert!((cost - (200.0 * 0.000002 + 800.0 * 0.0000005 + 0.005)).abs() < 1e-12);
+
+        assert!(calculate_cost(&pricing(0.000002, 0.00001, None), &Usage::default()).is_none());
+    }
+
+    #[test]
+    fn test_find_pricing_override() {
+        let mut overrides = HashMap::new();
+        overrides.insert("gpt-4o".to_string(), pricing(1.0, 1.0, None));
+        overrides.insert("azure/gpt-4o".to_string(), pricing(2.0, 2.0, None));
+
+        let found = find_pricing_override(&overrides, "azure", "gpt-4o").unwrap();
+        assert_eq!(found.prompt, Some(2.0));
+        let found = find_pricing_override(&overrides, "openai", "gpt-4o").unwrap();
+        assert_eq!(found.prompt, Some(1.0));
+        assert!(find_pricing_override(&overrides, "openai", "gpt-4o-mini").is_none());
+    }
+}
@@ -0,0 +1,419 @@
+//! Client-side rate limiting for providers.
+//!
+//! Limits are token buckets for requests and tokens per minute, configured with
+//! `<PROVIDER>_REQUESTS_PER_MINUTE` / `<PROVIDER>_TOKENS_PER_MINUTE` (e.g.
+//! `ANTHROPIC_TOKENS_PER_MINUTE`) or globally with `GOOSE_RATE_LIMIT_REQUESTS_PER_MINUTE` /
+//! `GOOSE_RATE_LIMIT_TOKENS_PER_MINUTE`. Buckets are shared by every instance of a provider in
+//! the process, so parallel subagents draw from the same budget. When the limits in the config
+//! change, [`reload_rate_limits`] swaps in new buckets that running providers move over to.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use rmcp::model::Tool;
+
+use super::base::{MessageStream, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::errors::ProviderError;
+use super::stream_event::{EventStream, StreamEvent};
+use crate::config::Config;
+use crate::conversation::message::Message;
+use crate::model::ModelConfig;
+use crate::token_counter::create_token_counter_for_model;
+
+static LIMITERS: Lazy<Mutex<HashMap<String, Arc<RateLimiter>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Per-minute request and token limits. Unset limits aren't enforced.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateLimitConfig {
+    pub requests_per_minute: Option<u32>,
+    pub tokens_per_minute: Option<u32>,
+}
+
+impl RateLimitConfig {
+    /// Read limits for a provider, preferring provider-specific keys over the global ones
+    pub fn from_config(provider_name: &str) -> Self {
+        let config = Config::global();
+        let prefix = provider_name.to_uppercase().replace('-', "_");
+        let limit = |name: &str| {
+            config
+                .get_param::<u32>(&format!("{}_{}", prefix, name))
+                .or_else(|_| config.get_param::<u32>(&format!("GOOSE_RATE_LIMIT_{}", name)))
+                .ok()
+                .filter(|limit| *limit > 0)
+        };
+        Self {
+            requests_per_minute: limit("REQUESTS_PER_MINUTE"),
+            tokens_per_minute: limit("TOKENS_PER_MINUTE"),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.requests_per_minute.is_some() || self.tokens_per_minute.is_some()
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    available: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn per_minute(limit: u32, now: Instant) -> Self {
+        Self {
+            capacity: limit as f64,
+            available: limit as f64,
+            refill_per_sec: limit as f64 / 60.0,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.available = (self.available + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Take `amount` from the bucket, returning how long the caller must wait before the
+    /// reservation is covered. The balance may go negative so later callers queue behind it.
+    fn reserve(&mut self, amount: f64, now: Instant) -> Duration {
+        self.refill(now);
+        let amount = amount.min(self.capacity);
+        self.available -= amount;
+        if self.available >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.available / self.refill_per_sec)
+        }
+    }
+
+    /// Correct an earlier reservation once the real amount is known
+    fn adjust(&mut self, delta: f64, now: Instant) {
+        self.refill(now);
+        self.available = (self.available - delta).min(self.capacity);
+    }
+}
+
+/// Request and token buckets shared by all instances of one provider
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    requests: Option<Mutex<TokenBucket>>,
+    tokens: Option<Mutex<TokenBucket>>,
+    /// The limiter that took over when the limits changed
+    replaced_by: Mutex<Option<Arc<RateLimiter>>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            config,
+            requests: config
+                .requests_per_minute
+                .map(|limit| Mutex::new(TokenBucket::per_minute(limit, now))),
+            tokens: config
+                .tokens_per_minute
+                .map(|limit| Mutex::new(TokenBucket::per_minute(limit, now))),
+            replaced_by: Mutex::new(None),
+        }
+    }
+
+    /// This limiter, or the one that replaced it if the limits have changed since
+    pub fn current(self: &Arc<Self>) -> Arc<Self> {
+        let mut limiter = self.clone();
+        loop {
+            let next = limiter.replaced_by.lock().unwrap().clone();
+            match next {
+                Some(next) => limiter = next,
+                None => return limiter,
+            }
+        }
+    }
+
+    /// The process-wide limiter for a provider, replaced if its limits have changed
+    pub fn shared(provider_name: &str, config: RateLimitConfig) -> Arc<Self> {
+        let mut limiters = LIMITERS.lock().unwrap();
+        match limiters.get(provider_name) {
+            Some(limiter) if limiter.config == config => limiter.clone(),
+            previous => {
+                let limiter = Arc::new(Self::new(config));
+                if let Some(previous) = previous {
+                    *previous.replaced_by.lock().unwrap() = Some(limiter.clone());
+                }
+                limiters.insert(provider_name.to_string(), limiter.clone());
+                limiter
+            }
+        }
+    }
+
+    pub fn limits_tokens(&self) -> bool {
+        self.tokens.is_some()
+    }
+
+    /// How long to wait before a request expected to use `estimated_tokens` may be sent
+    fn reserve(&self, estimated_tokens: usize, now: Instant) -> Duration {
+        let request_wait = self
+            .requests
+            .as_ref()
+            .map(|bucket| bucket.lock().unwrap().reserve(1.0, now))
+            .unwrap_or_default();
+        let token_wait = self
+            .tokens
+            .as_ref()
+            .map(|bucket| bucket.lock().unwrap().reserve(estimated_tokens as f64, now))
+            .unwrap_or_default();
+        request_wait.max(token_wait)
+    }
+
+    /// Wait until a request expected to use `estimated_tokens` may be sent
+    pub async fn acquire(&self, estimated_tokens: usize) {
+        let wait = self.reserve(estimated_tokens, Instant::now());
+        if !wait.is_zero() {
+            tracing::info!("Rate limit reached, waiting {:?} before sending", wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Charge the difference between the reported usage and the estimate made at acquire time
+    pub fn record_usage(&self, estimated_tokens: usize, usage: &Usage) {
+        let Some(bucket) = &self.tokens else {
+            return;
+        };
+        let actual = usage
+            .total_tokens
+            .or_else(|| Some(usage.input_tokens? + usage.output_tokens.unwrap_or(0)));
+        if let Some(actual) = actual {
+            bucket
+                .lock()
+                .unwrap()
+                .adjust(actual as f64 - estimated_tokens as f64, Instant::now());
+        }
+    }
+}
+
+/// Read the limits of every provider that has a limiter again, replacing those whose limits
+/// have changed. Providers without limits when they were created aren't limited until they are
+/// created again.
+pub fn reload_rate_limits() {
+    let provider_names: Vec<String> = LIMITERS.lock().unwrap().keys().cloned().collect();
+    for provider_name in provider_names {
+        RateLimiter::shared(&provider_name, RateLimitConfig::from_config(&provider_name));
+    }
+}
+
+/// Wrap a provider in a rate limiter if limits are configured for it
+pub fn with_configured_rate_limit(provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+    let config = RateLimitConfig::from_config(provider.get_name());
+    if !config.is_enabled() {
+        return provider;
+    }
+    let limiter = RateLimiter::shared(provider.get_name(), config);
+    Arc::new(RateLimitedProvider::new(provider, limiter))
+}
+
+/// A provider whose requests wait for capacity in a shared [`RateLimiter`]
+pub struct RateLimitedProvider {
+    inner: Arc<dyn Provider>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitedProvider {
+    pub fn new(inner: Arc<dyn Provider>, limiter: Arc<RateLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+
+    async fn estimate_tokens(
+        &self,
+        limiter: &RateLimiter,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> usize {
+        if !limiter.limits_tokens() {
+            return 0;
+        }
+        match create_token_counter_for_model(&self.inner.get_model_config().model_name).await {
+            Ok(counter) => counter.count_chat_tokens(system, messages, tools),
+            Err(e) => {
+                tracing::warn!("Failed to estimate tokens for rate limiting: {}", e);
+                0
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for RateLimitedProvider {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            "rate_limited",
+            "Rate Limited Provider",
+            "A provider that limits requests and tokens per minute",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_name(&self) -> &str {
+        self.inner.get_name()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn inner_provider(&self) -> Option<&dyn Provider> {
+        Some(self.inner.as_ref())
+    }
+
+    async fn complete_with_model(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let limiter = self.limiter.current();
+        let estimate = self
+            .estimate_tokens(&limiter, system, messages, tools)
+            .await;
+        limiter.acquire(estimate).await;
+        let (message, usage) = self
+            .inner
+            .complete_with_model(model_config, system, messages, tools)
+            .await?;
+        limiter.record_usage(estimate, &usage.usage);
+        Ok((message, usage))
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.limiter.current().acquire(0).await;
+        self.inner.create_embeddings(texts).await
+    }
+
+    async fn stream(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let limiter = self.limiter.current();
+        let estimate = self
+            .estimate_tokens(&limiter, system, messages, tools)
+            .await;
+        limiter.acquire(estimate).await;
+        let stream = self.inner.stream(system, messages, tools).await?;
+
+        Ok(Box::pin(stream.inspect(move |item| {
+            if let Ok((_, Some(usage))) = item {
+                if !usage.interim {
+                    limiter.record_usage(estimate, &usage.usage);
+                }
+            }
+        })))
+    }
+
+    async fn stream_events(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<EventStream, ProviderError> {
+        let limiter = self.limiter.current();
+        let estimate = self
+            .estimate_tokens(&limiter, system, messages, tools)
+            .await;
+        limiter.acquire(estimate).await;
+        let events = self.inner.stream_events(system, messages, tools).await?;
+
+        Ok(Box::pin(events.inspect(move |event| {
+            if let StreamEvent::Usage(usage) = event {
+                if !usage.interim {
+                    limiter.record_usage(estimate, &usage.usage);
+                }
+            }
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_waits_for_refill() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::per_minute(60, start);
+
+        assert_eq!(bucket.reserve(60.0, start), Duration::ZERO);
+        // Empty bucket refills at one per second
+        assert_eq!(bucket.reserve(2.0, start), Duration::from_secs(2));
+        // Queued behind the previous reservation
+        assert_eq!(bucket.reserve(1.0, start), Duration::from_secs(3));
+        // After ten seconds the debt is repaid with seven to spare
+        assert_eq!(
+            bucket.reserve(7.0, start + Duration::from_secs(10)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_token_bucket_adjust_refunds_up_to_capacity() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::per_minute(100, start);
+
+        bucket.reserve(80.0, start);
+        bucket.adjust(-500.0, start);
+        assert_eq!(bucket.available, 100.0);
+
+        bucket.adjust(130.0, start);
+        assert_eq!(bucket.reserve(0.0, start), Duration::from_secs(18));
+    }
+
+    #[test]
+    fn test_rate_limiter_takes_longest_wait() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_minute: Some(60),
+            tokens_per_minute: Some(600),
+        });
+        let now = Instant::now();
+        assert_eq!(limiter.reserve(600, now), Duration::ZERO);
+        // One request is still available but the tokens need five seconds to refill
+        assert_eq!(limiter.reserve(50, now), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_shared_limiter_is_reused_until_limits_change() {
+        let config = RateLimitConfig {
+            requests_per_minute: Some(10),
+            tokens_per_minute: None,
+        };
+        let first = RateLimiter::shared("rate_limit_test", config);
+        let second = RateLimiter::shared("rate_limit_test", config);
+        assert!(Arc::ptr_eq(&first, &second));
+
+        let changed = RateLimiter::shared(
+            "rate_limit_test",
+            RateLimitConfig {
+                requests_per_minute: Some(20),
+                tokens_per_minute: None,
+            },
+        );
+        assert!(!Arc::ptr_eq(&first, &changed));
+        // Providers holding the old limiter move over to the new one
+        assert!(Arc::ptr_eq(&first.current(), &changed));
+        assert!(Arc::ptr_eq(&changed.current(), &changed));
+    }
+}
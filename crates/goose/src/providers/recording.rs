@@ -0,0 +1,660 @@
+//! Record and replay provider interactions.
+//!
+//! [`RecordingProvider`] wraps a real provider and appends every request and its response,
+//! including each chunk of a streamed response and any error, to a JSON file. [`ReplayProvider`]
+//! serves those responses back without network access or credentials, so provider tests can run
+//! deterministically in CI.
+//!
+//! Interactions are keyed by a hash of the system prompt, message roles and content, and tools.
+//! Requests that were made more than once are replayed in the order they were recorded.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::StreamExt;
+use rmcp::model::Tool;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::base::{
+    stream_from_single_message, MessageStream, Provider, ProviderMetadata, ProviderUsage,
+};
+use super::errors::ProviderError;
+use super::moderation::ModerationTarget;
+use super::stream_event::{into_events, into_message_stream, EventStream};
+use crate::conversation::message::Message;
+use crate::model::ModelConfig;
+
+const RECORDING_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedRequest {
+    model: String,
+    system: String,
+    messages: Vec<Message>,
+    tools: Vec<Tool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedChunk {
+    message: Option<Message>,
+    usage: Option<ProviderUsage>,
+}
+
+/// A provider error in a form that can be written to disk and restored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum RecordedError {
+    Authentication {
+        details: String,
+    },
+    ContextLengthExceeded {
+        details: String,
+    },
+    RateLimitExceeded {
+        details: String,
+    },
+    ServerError {
+        details: String,
+    },
+    RequestFailed {
+        details: String,
+    },
+    ExecutionError {
+        details: String,
+    },
+    UsageError {
+        details: String,
+    },
+    NotImplemented {
+        details: String,
+    },
+    ContentFlagged {
+        target: ModerationTarget,
+        categories: Vec<String>,
+    },
+}
+
+impl From<&ProviderError> for RecordedError {
+    fn from(error: &ProviderError) -> Self {
+        match error {
+            ProviderError::Authentication(details) => RecordedError::Authentication {
+                details: details.clone(),
+            },
+            ProviderError::ContextLengthExceeded(details) => RecordedError::ContextLengthExceeded {
+                details: details.clone(),
+            },
+            ProviderError::RateLimitExceeded { details, .. } => RecordedError::RateLimitExceeded {
+                details: details.clone(),
+            },
+            ProviderError::ServerError(details) => RecordedError::ServerError {
+                details: details.clone(),
+            },
+            ProviderError::RequestFailed(details) => RecordedError::RequestFailed {
+                details: details.clone(),
+            },
+            ProviderError::ExecutionError(details) => RecordedError::ExecutionError {
+                details: details.clone(),
+            },
+            ProviderError::UsageError(details) => RecordedError::UsageError {
+                details: details.clone(),
+            },
+            ProviderError::NotImplemented(details) => RecordedError::NotImplemented {
+                details: details.clone(),
+            },
+            ProviderError::ContentFlagged { target, categories } => RecordedError::ContentFlagged {
+                target: *target,
+                categories: categories.clone(),
+            },
+        }
+    }
+}
+
+impl From<RecordedError> for ProviderError {
+    fn from(error: RecordedError) -> Self {
+        match error {
+            RecordedError::Authentication { details } => ProviderError::Authentication(details),
+            RecordedError::ContextLengthExceeded { details } => {
+                ProviderError::ContextLengthExceeded(details)
+            }
+            // Replays shouldn't make tests wait, so the delay isn't restored
+            RecordedError::RateLimitExceeded { details } => ProviderError::RateLimitExceeded {
+                details,
+                retry_delay: None,
+            },
+            RecordedError::ServerError { details } => ProviderError::ServerError(details),
+            RecordedError::RequestFailed { details } => ProviderError::RequestFailed(details),
+            RecordedError::ExecutionError { details } => ProviderError::ExecutionError(details),
+            RecordedError::UsageError { details } => ProviderError::UsageError(details),
+            RecordedError::NotImplemented { details } => ProviderError::NotImplemented(details),
+            RecordedError::ContentFlagged { target, categories } => {
+                ProviderError::ContentFlagged { target, categories }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RecordedResponse {
+    Complete {
+        message: Box<Message>,
+        usage: ProviderUsage,
+    },
+    Stream {
+        chunks: Vec<RecordedChunk>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<RecordedError>,
+    },
+    Error {
+        error: RecordedError,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordingFile {
+    version: u32,
+    interactions: BTreeMap<String, Vec<Interaction>>,
+}
+
+impl Default for RecordingFile {
+    fn default() -> Self {
+        Self {
+            version: RECORDING_FORMAT_VERSION,
+            interactions: BTreeMap::new(),
+        }
+    }
+}
+
+impl RecordingFile {
+    fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let file: RecordingFile = serde_json::from_str(&content)?;
+        if file.version != RECORDING_FORMAT_VERSION {
+            return Err(anyhow::anyhow!(
+                "Unsupported recording version {} in {}",
+                file.version,
+                path.display()
+            ));
+        }
+        Ok(file)
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+fn request_key(system: &str, messages: &[Message], tools: &[Tool]) -> String {
+    let stable_messages: Vec<_> = messages
+        .iter()
+        .map(|msg| (&msg.role, &msg.content))
+        .collect();
+    let serialized = serde_json::to_string(&(system, stable_messages, tools)).unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(serialized.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+struct Recorder {
+    path: PathBuf,
+    file: Mutex<RecordingFile>,
+}
+
+impl Recorder {
+    fn append(&self, key: String, interaction: Interaction) {
+        let mut file = self.file.lock().unwrap();
+        file.interactions.entry(key).or_default().push(interaction);
+        if let Err(e) = file.save(&self.path) {
+            tracing::warn!("Failed to write recording {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// A provider that passes calls through to `inner` and writes each interaction to disk
+pub struct RecordingProvider {
+    inner: Arc<dyn Provider>,
+    recorder: Arc<Recorder>,
+}
+
+impl RecordingProvider {
+    /// Record to `path`, starting a new recording even if the file already exists
+    pub fn new(inner: Arc<dyn Provider>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            recorder: Arc::new(Recorder {
+                path: path.into(),
+                file: Mutex::new(RecordingFile::default()),
+            }),
+        }
+    }
+
+    fn request(&self, system: &str, messages: &[Message], tools: &[Tool]) -> RecordedRequest {
+        RecordedRequest {
+            model: self.inner.get_model_config().model_name,
+            system: system.to_string(),
+            messages: messages.to_vec(),
+            tools: tools.to_vec(),
+        }
+    }
+
+    /// `inner` with each chunk recorded as it passes, and the whole stream appended to the
+    /// recording once it ends
+    fn record_stream(
+        &self,
+        key: String,
+        request: RecordedRequest,
+        mut inner: MessageStream,
+    ) -> MessageStream {
+        let recorder = self.recorder.clone();
+        Box::pin(async_stream::stream! {
+            let mut chunks = Vec::new();
+            let mut error = None;
+            while let Some(item) = inner.next().await {
+                match item {
+                    Ok((message, usage)) => {
+                        chunks.push(RecordedChunk {
+                            message: message.clone(),
+                            usage: usage.clone(),
+                        });
+                        yield Ok((message, usage));
+                    }
+                    Err(e) => {
+                        error = Some(RecordedError::from(&e));
+                        yield Err(e);
+                        break;
+                    }
+                }
+            }
+            let response = RecordedResponse::Stream { chunks, error };
+            recorder.append(key, Interaction { request, response });
+        })
+    }
+}
+
+#[async_trait]
+impl Provider for RecordingProvider {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            "recording",
+            "Recording Provider",
+            "A provider that records interactions for later replay",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_name(&self) -> &str {
+        self.inner.get_name()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn inner_provider(&self) -> Option<&dyn Provider> {
+        Some(self.inner.as_ref())
+    }
+
+    async fn complete_with_model(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let result = self
+            .inner
+            .complete_with_model(model_config, system, messages, tools)
+            .await;
+
+        let response = match &result {
+            Ok((message, usage)) => RecordedResponse::Complete {
+                message: Box::new(message.clone()),
+                usage: usage.clone(),
+            },
+            Err(e) => RecordedResponse::Error { error: e.into() },
+        };
+        let mut request = self.request(system, messages, tools);
+        request.model = model_config.model_name.clone();
+        self.recorder.append(
+            request_key(system, messages, tools),
+            Interaction { request, response },
+        );
+
+        result
+    }
+
+    /// The interaction is written once the stream ends; a stream dropped early isn't recorded
+    async fn stream(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let key = request_key(system, messages, tools);
+        let request = self.request(system, messages, tools);
+
+        match self.inner.stream(system, messages, tools).await {
+            Ok(stream) => Ok(self.record_stream(key, request, stream)),
+            Err(e) => {
+                let response = RecordedResponse::Error { error: (&e).into() };
+                self.recorder.append(key, Interaction { request, response });
+                Err(e)
+            }
+        }
+    }
+
+    /// Recordings keep the chunks of a [`MessageStream`], so the events are recorded as chunks,
+    /// with tool call arguments put together, and passed on from those
+    async fn stream_events(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<EventStream, ProviderError> {
+        let key = request_key(system, messages, tools);
+        let request = self.request(system, messages, tools);
+
+        match self.inner.stream_events(system, messages, tools).await {
+            Ok(events) => Ok(into_events(self.record_stream(
+                key,
+                request,
+                into_message_stream(events),
+            ))),
+            Err(e) => {
+                let response = RecordedResponse::Error { error: (&e).into() };
+                self.recorder.append(key, Interaction { request, response });
+                Err(e)
+            }
+        }
+    }
+}
+
+/// A provider that serves responses from a file written by [`RecordingProvider`]
+pub struct ReplayProvider {
+    interactions: BTreeMap<String, Vec<Interaction>>,
+    cursors: Mutex<HashMap<String, usize>>,
+    model_config: ModelConfig,
+    name: String,
+}
+
+impl ReplayProvider {
+    /// Load a recording, reporting `model_config` as the model in use
+    pub fn new(path: impl AsRef<Path>, model_config: ModelConfig) -> Result<Self> {
+        let file = RecordingFile::load(path.as_ref())?;
+        Ok(Self {
+            interactions: file.interactions,
+            cursors: Mutex::new(HashMap::new()),
+            model_config,
+            name: Self::metadata().name,
+        })
+    }
+
+    /// The next recorded response for this request. Once every recording of a repeated request
+    /// has been served, the last one is served again.
+    fn next_response(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<RecordedResponse, ProviderError> {
+        let key = request_key(system, messages, tools);
+        let interactions = self.interactions.get(&key).ok_or_else(|| {
+            ProviderError::ExecutionError(format!(
+                "No recorded response found for request hash: {}",
+                key
+            ))
+        })?;
+
+        let mut cursors = self.cursors.lock().unwrap();
+        let cursor = cursors.entry(key).or_insert(0);
+        let index = (*cursor).min(interactions.len() - 1);
+        *cursor += 1;
+        Ok(interactions[index].response.clone())
+    }
+}
+
+#[async_trait]
+impl Provider for ReplayProvider {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            "replay",
+            "Replay Provider",
+            "A provider that replays recorded interactions",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.model_config.clone()
+    }
+
+    async fn complete_with_model(
+        &self,
+        _model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        match self.next_response(system, messages, tools)? {
+            RecordedResponse::Complete { message, usage } => Ok((*message, usage)),
+            RecordedResponse::Error { error } => Err(error.into()),
+            RecordedResponse::Stream { .. } => Err(ProviderError::ExecutionError(
+                "Request was recorded as a stream and can only be replayed with stream()"
+                    .to_string(),
+            )),
+        }
+    }
+
+    async fn stream(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        match self.next_response(system, messages, tools)? {
+            RecordedResponse::Complete { message, usage } => {
+                Ok(stream_from_single_message(*message, usage))
+            }
+            RecordedResponse::Error { error } => Err(error.into()),
+            RecordedResponse::Stream { chunks, error } => {
+                let items = chunks
+                    .into_iter()
+                    .map(|chunk| Ok((chunk.message, chunk.usage)))
+                    .chain(error.map(|e| Err(e.into())));
+                Ok(Box::pin(futures::stream::iter(items.collect::<Vec<_>>())))
+            }
+        }
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+    use futures::TryStreamExt;
+    use tempfile::tempdir;
+
+    struct ScriptedProvider {
+        model_config: ModelConfig,
+    }
+
+    #[async_trait]
+    impl Provider for ScriptedProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_name(&self) -> &str {
+            "scripted"
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            self.model_config.clone()
+        }
+
+        async fn complete_with_model(
+            &self,
+            model_config: &ModelConfig,
+            _system: &str,
+            messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            let text = messages.last().unwrap().as_concat_text();
+            if text == "too long" {
+                return Err(ProviderError::ContextLengthExceeded(
+                    "too many tokens".into(),
+                ));
+            }
+            Ok((
+                Message::assistant().with_text(format!("echo: {}", text)),
+                ProviderUsage::new(
+                    model_config.model_name.clone(),
+                    Usage::new(Some(1), Some(2), Some(3)),
+                ),
+            ))
+        }
+
+        async fn stream(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<MessageStream, ProviderError> {
+            let usage = ProviderUsage::new("scripted".into(), Usage::default());
+            Ok(Box::pin(futures::stream::iter(vec![
+                Ok((Some(Message::assistant().with_text("Hel")), None)),
+                Ok((Some(Message::assistant().with_text("lo")), None)),
+                Ok((None, Some(usage))),
+            ])))
+        }
+
+        fn supports_streaming(&self) -> bool {
+            true
+        }
+    }
+
+    fn texts(chunks: &[(Option<Message>, Option<ProviderUsage>)]) -> Vec<String> {
+        chunks
+            .iter()
+            .filter_map(|(message, _)| message.as_ref().map(|m| m.as_concat_text()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("recordings").join("scripted.json");
+        let model_config = ModelConfig::new_or_fail("scripted-model");
+
+        let recording = RecordingProvider::new(
+            Arc::new(ScriptedProvider {
+                model_config: model_config.clone(),
+            }),
+            &path,
+        );
+        let hello = [Message::user().with_text("hello")];
+        let tell_me = [Message::user().with_text("tell me")];
+        let too_long = [Message::user().with_text("too long")];
+
+        let (recorded, _) = recording.complete("system", &hello, &[]).await.unwrap();
+        let recorded_error = recording.complete("system", &too_long, &[]).await;
+        let recorded_chunks: Vec<_> = recording
+            .stream("system", &tell_me, &[])
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        let replay = ReplayProvider::new(&path, model_config).unwrap();
+
+        let (message, usage) = replay.complete("system", &hello, &[]).await.unwrap();
+        assert_eq!(message.as_concat_text(), recorded.as_concat_text());
+        assert_eq!(usage.model, "scripted-model");
+        assert_eq!(usage.usage.total_tokens, Some(3));
+
+        assert_eq!(
+            replay.complete("system", &too_long, &[]).await.unwrap_err(),
+            recorded_error.unwrap_err()
+        );
+
+        let chunks: Vec<_> = replay
+            .stream("system", &tell_me, &[])
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(texts(&chunks), vec!["Hel", "lo"]);
+        assert_eq!(texts(&chunks), texts(&recorded_chunks));
+        assert!(chunks.last().unwrap().1.is_some());
+
+        // Unrecorded requests and a different system prompt don't match
+        assert!(replay.complete("other", &hello, &[]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_repeated_requests_replay_in_order() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("repeated.json");
+        let messages = [Message::user().with_text("again")];
+        let key = request_key("system", &messages, &[]);
+
+        let mut file = RecordingFile::default();
+        for text in ["first", "second"] {
+            file.interactions
+                .entry(key.clone())
+                .or_default()
+                .push(Interaction {
+                    request: RecordedRequest {
+                        model: "m".into(),
+                        system: "system".into(),
+                        messages: messages.to_vec(),
+                        tools: vec![],
+                    },
+                    response: RecordedResponse::Complete {
+                        message: Box::new(Message::assistant().with_text(text)),
+                        usage: ProviderUsage::new("m".into(), Usage::default()),
+                    },
+                });
+        }
+        file.save(&path).unwrap();
+
+        let replay = ReplayProvider::new(&path, ModelConfig::new_or_fail("m")).unwrap();
+        let mut replies = Vec::new();
+        for _ in 0..3 {
+            let (message, _) = replay.complete("system", &messages, &[]).await.unwrap();
+            replies.push(message.as_concat_text());
+        }
+        assert_eq!(replies, vec!["first", "second", "second"]);
+    }
+}
@@ -0,0 +1,394 @@
+//! Exact-match caching of provider responses.
+//!
+//! When GOOSE_RESPONSE_CACHE is enabled, completions are cached by a hash of the provider, model
+//! configuration, system prompt, messages and tools, so repeated recipe runs and evals don't pay
+//! for identical requests twice. Entries live in an in-memory LRU of GOOSE_RESPONSE_CACHE_SIZE
+//! entries and, when GOOSE_RESPONSE_CACHE_DIR is set, in one JSON file per entry in that
+//! directory. GOOSE_RESPONSE_CACHE_TTL expires entries after that many seconds.
+//!
+//! Streamed responses bypass the cache: they are passed through as they arrive rather than held
+//! back to be stored whole.
+
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use rmcp::model::Tool;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::base::{MessageStream, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::errors::ProviderError;
+use super::stream_event::EventStream;
+use crate::config::Config;
+use crate::conversation::message::Message;
+use crate::model::ModelConfig;
+
+pub const DEFAULT_RESPONSE_CACHE_SIZE: usize = 512;
+
+static SHARED_CACHE: Lazy<Mutex<Option<Arc<ResponseCache>>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseCacheConfig {
+    /// Entries kept in memory
+    pub capacity: usize,
+    /// Age after which an entry is no longer served
+    pub ttl: Option<Duration>,
+    /// Directory for the disk backend
+    pub directory: Option<PathBuf>,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_RESPONSE_CACHE_SIZE,
+            ttl: None,
+            directory: None,
+        }
+    }
+}
+
+impl ResponseCacheConfig {
+    /// Read GOOSE_RESPONSE_CACHE_SIZE, GOOSE_RESPONSE_CACHE_TTL and GOOSE_RESPONSE_CACHE_DIR, or
+    /// None if GOOSE_RESPONSE_CACHE isn't enabled
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        if !config
+            .get_param::<bool>("GOOSE_RESPONSE_CACHE")
+            .unwrap_or(false)
+        {
+            return None;
+        }
+        Some(Self {
+            capacity: config
+                .get_param("GOOSE_RESPONSE_CACHE_SIZE")
+                .unwrap_or(DEFAULT_RESPONSE_CACHE_SIZE),
+            ttl: config
+                .get_param::<u64>("GOOSE_RESPONSE_CACHE_TTL")
+                .ok()
+                .map(Duration::from_secs),
+            directory: config
+                .get_param::<String>("GOOSE_RESPONSE_CACHE_DIR")
+                .ok()
+                .map(PathBuf::from),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    created_at: DateTime<Utc>,
+    message: Message,
+    usage: ProviderUsage,
+}
+
+/// An LRU of responses, optionally backed by a directory on disk
+pub struct ResponseCache {
+    entries: Mutex<LruCache<String, CacheEntry>>,
+    ttl: Option<Duration>,
+    directory: Option<PathBuf>,
+}
+
+impl ResponseCache {
+    pub fn new(config: ResponseCacheConfig) -> Self {
+        let capacity = NonZeroUsize::new(config.capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+            ttl: config.ttl,
+            directory: config.directory,
+        }
+    }
+
+    /// The cache shared by every provider in the process, created from `config` on first use
+    pub fn shared(config: ResponseCacheConfig) -> Arc<Self> {
+        SHARED_CACHE
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| Arc::new(Self::new(config)))
+            .clone()
+    }
+
+    /// Cache key for a request
+    pub fn key(
+        provider: &str,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> String {
+        let stable_messages: Vec<_> = messages
+            .iter()
+            .map(|msg| (&msg.role, &msg.content))
+            .collect();
+        let serialized =
+            serde_json::to_string(&(provider, model_config, system, stable_messages, tools))
+                .unwrap_or_default();
+        let mut hasher = Sha256::new();
+        hasher.update(serialized.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    fn entry_path(&self, key: &str) -> Option<PathBuf> {
+        self.directory
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", key)))
+    }
+
+    fn is_fresh(&self, entry: &CacheEntry, now: DateTime<Utc>) -> bool {
+        match self.ttl {
+            Some(ttl) => (now - entry.created_at)
+                .to_std()
+                .map(|age| age < ttl)
+                .unwrap_or(true),
+            None => true,
+        }
+    }
+
+    fn get(&self, key: &str, now: DateTime<Utc>) -> Option<(Message, ProviderUsage)> {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(entry) = entries.get(key) {
+            if self.is_fresh(entry, now) {
+                return Some((entry.message.clone(), entry.usage.clone()));
+            }
+            entries.pop(key);
+        }
+
+        let path = self.entry_path(key)?;
+        let entry: CacheEntry = serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+        if !self.is_fresh(&entry, now) {
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+        let response = (entry.message.clone(), entry.usage.clone());
+        entries.put(key.to_string(), entry);
+        Some(response)
+    }
+
+    fn put(&self, key: String, message: Message, usage: ProviderUsage, now: DateTime<Utc>) {
+        let entry = CacheEntry {
+            created_at: now,
+            message,
+            usage,
+        };
+        if let Some(path) = self.entry_path(&key) {
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&path, serde_json::to_string(&entry)?));
+            if let Err(e) = written {
+                tracing::warn!("Failed to write cache entry {}: {}", path.display(), e);
+            }
+        }
+        self.entries.lock().unwrap().put(key, entry);
+    }
+}
+
+/// Wrap `provider` with the shared response cache if GOOSE_RESPONSE_CACHE is enabled
+pub fn with_configured_cache(provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+    match ResponseCacheConfig::from_config() {
+        Some(config) => Arc::new(CachedProvider::new(provider, ResponseCache::shared(config))),
+        None => provider,
+    }
+}
+
+/// A provider that serves repeated requests from a [`ResponseCache`]
+pub struct CachedProvider {
+    inner: Arc<dyn Provider>,
+    cache: Arc<ResponseCache>,
+}
+
+impl CachedProvider {
+    pub fn new(inner: Arc<dyn Provider>, cache: Arc<ResponseCache>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+#[async_trait]
+impl Provider for CachedProvider {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            "cached",
+            "Cached Provider",
+            "A provider that caches responses to identical requests",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_name(&self) -> &str {
+        self.inner.get_name()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn inner_provider(&self) -> Option<&dyn Provider> {
+        Some(self.inner.as_ref())
+    }
+
+    /// Cache hits report zero usage, since no tokens were spent on them
+    async fn complete_with_model(
+        &self,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let key = ResponseCache::key(self.inner.get_name(), model_config, system, messages, tools);
+        if let Some((message, usage)) = self.cache.get(&key, Utc::now()) {
+            tracing::debug!("Serving response from cache ({})", key);
+            return Ok((message, ProviderUsage::new(usage.model, Usage::default())));
+        }
+
+        let (message, usage) = self
+            .inner
+            .complete_with_model(model_config, system, messages, tools)
+            .await?;
+        self.cache
+            .put(key, message.clone(), usage.clone(), Utc::now());
+        Ok((message, usage))
+    }
+
+    async fn stream(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        self.inner.stream(system, messages, tools).await
+    }
+
+    async fn stream_events(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<EventStream, ProviderError> {
+        self.inner.stream_events(system, messages, tools).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use futures::TryStreamExt;
+    use tempfile::tempdir;
+
+    fn usage() -> ProviderUsage {
+        ProviderUsage::new(
+            "mock-model".to_string(),
+            Usage::new(Some(10), Some(5), Some(15)),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_repeated_requests_served_from_cache() {
+        let mock = Arc::new(
+            MockProvider::new()
+                .with_response(MockResponse::text("first").with_usage(usage().usage))
+                .with_response(MockResponse::text("second")),
+        );
+        let provider = CachedProvider::new(
+            mock.clone(),
+            Arc::new(ResponseCache::new(ResponseCacheConfig::default())),
+        );
+        let messages = [Message::user().with_text("hi")];
+
+        let (message, usage) = provider.complete("system", &messages, &[]).await.unwrap();
+        assert_eq!(message.as_concat_text(), "first");
+        assert_eq!(usage.usage.total_tokens, Some(15));
+
+        let (message, usage) = provider.complete("system", &messages, &[]).await.unwrap();
+        assert_eq!(message.as_concat_text(), "first");
+        assert_eq!(usage.usage.total_tokens, None);
+        assert_eq!(mock.requests().len(), 1);
+
+        let (message, _) = provider.complete("other", &messages, &[]).await.unwrap();
+        assert_eq!(message.as_concat_text(), "second");
+    }
+
+    #[tokio::test]
+    async fn test_streams_bypass_the_cache() {
+        let mock = Arc::new(MockProvider::new().with_responses([
+            MockResponse::stream(["Hel", "lo"]),
+            MockResponse::stream(["Hel", "lo"]),
+        ]));
+        let provider = CachedProvider::new(
+            mock.clone(),
+            Arc::new(ResponseCache::new(ResponseCacheConfig::default())),
+        );
+        assert!(provider.supports_streaming());
+
+        let messages = [Message::user().with_text("hi")];
+        for _ in 0..2 {
+            let chunks: Vec<_> = provider
+                .stream("system", &messages, &[])
+                .await
+                .unwrap()
+                .try_collect()
+                .await
+                .unwrap();
+            assert_eq!(chunks.len(), 2);
+        }
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_ttl_and_disk_backend() {
+        let dir = tempdir().unwrap();
+        let config = ResponseCacheConfig {
+            capacity: 1,
+            ttl: Some(Duration::from_secs(60)),
+            directory: Some(dir.path().to_path_buf()),
+        };
+        let now = Utc::now();
+        let cache = ResponseCache::new(config.clone());
+        cache.put(
+            "a".to_string(),
+            Message::assistant().with_text("a"),
+            usage(),
+            now,
+        );
+        cache.put(
+            "b".to_string(),
+            Message::assistant().with_text("b"),
+            usage(),
+            now,
+        );
+
+        // "a" was evicted from memory but is still on disk, even for a new cache
+        let (message, _) = cache.get("a", now).unwrap();
+        assert_eq!(message.as_concat_text(), "a");
+        let reopened = ResponseCache::new(config);
+        assert!(reopened.get("b", now).is_some());
+
+        let later = now + chrono::Duration::seconds(61);
+        assert!(reopened.get("b", later).is_none());
+        assert!(!dir.path().join("b.json").exists());
+    }
+
+    #[test]
+    fn test_key_covers_model_settings() {
+        let messages = [Message::user().with_text("hi")];
+        let model = ModelConfig::new_or_fail("gpt-4o");
+        let warmer = model.clone().with_temperature(Some(0.9));
+        assert_ne!(
+            ResponseCache::key("openai", &model, "system", &messages, &[]),
+            ResponseCache::key("openai", &warmer, "system", &messages, &[])
+        );
+        assert_eq!(
+            ResponseCache::key("openai", &model, "system", &messages, &[]),
+            ResponseCache::key("openai", &model, "system", &messages, &[])
+        );
+    }
+}
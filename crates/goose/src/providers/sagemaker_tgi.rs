@@ -11,6 +11,7 @@ use serde_json::{json, Value};
 
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
 use super::errors::ProviderError;
+use super::http_client::configure_aws_loader;
 use super::retry::ProviderRetry;
 use super::utils::RequestLog;
 use crate::conversation::message::{Message, MessageContent};
@@ -56,7 +57,9 @@ impl SageMakerTgiProvider {
         set_aws_env_vars(config.all_values());
         set_aws_env_vars(config.all_secrets());
 
-        let aws_config = aws_config::load_from_env().await;
+        let loader =
+            configure_aws_loader(aws_config::defaults(aws_config::BehaviorVersion::latest()))?;
+        let aws_config = loader.load().await;
 
         // Validate credentials
         aws_config
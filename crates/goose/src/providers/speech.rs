@@ -0,0 +1,334 @@
+//! Text-to-speech providers for voice-mode applications.
+//!
+//! [`SpeechProvider`] is implemented for OpenAI TTS and ElevenLabs. Synthesized audio is returned
+//! as a stream of encoded chunks so playback can start before the whole message is spoken.
+
+use std::pin::Pin;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::{Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::api_client::{ApiClient, AuthMethod};
+use super::errors::ProviderError;
+use super::retry::ProviderRetry;
+use super::utils::handle_status_openai_compat;
+use crate::config::Config;
+use crate::conversation::message::Message;
+
+pub const OPENAI_DEFAULT_SPEECH_MODEL: &str = "gpt-4o-mini-tts";
+pub const OPENAI_DEFAULT_VOICE: &str = "alloy";
+pub const ELEVENLABS_API_HOST: &str = "https://api.elevenlabs.io";
+pub const ELEVENLABS_DEFAULT_SPEECH_MODEL: &str = "eleven_multilingual_v2";
+pub const ELEVENLABS_DEFAULT_VOICE: &str = "21m00Tcm4TlvDq8ikWAM";
+
+/// Encoded audio chunks, in playback order
+pub type AudioStream = Pin<Box<dyn Stream<Item = Result<Vec<u8>, ProviderError>> + Send>>;
+
+/// Encoding of synthesized audio
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeechFormat {
+    #[default]
+    Mp3,
+    Opus,
+    Pcm,
+}
+
+impl SpeechFormat {
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            SpeechFormat::Mp3 => "audio/mpeg",
+            SpeechFormat::Opus => "audio/ogg",
+            SpeechFormat::Pcm => "audio/pcm",
+        }
+    }
+}
+
+/// Options for a speech request. Unset fields fall back to the provider's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpeechOptions {
+    /// Speech model to use instead of the provider default
+    pub model: Option<String>,
+    /// Voice name (OpenAI) or voice ID (ElevenLabs)
+    pub voice: Option<String>,
+    #[serde(default)]
+    pub format: SpeechFormat,
+    /// Playback speed multiplier, where supported
+    pub speed: Option<f32>,
+}
+
+impl SpeechOptions {
+    /// Read options from GOOSE_SPEECH_MODEL, GOOSE_SPEECH_VOICE and GOOSE_SPEECH_SPEED
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        Self {
+            model: config.get_param("GOOSE_SPEECH_MODEL").ok(),
+            voice: config.get_param("GOOSE_SPEECH_VOICE").ok(),
+            format: SpeechFormat::default(),
+            speed: config.get_param("GOOSE_SPEECH_SPEED").ok(),
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn with_voice(mut self, voice: impl Into<String>) -> Self {
+        self.voice = Some(voice.into());
+        self
+    }
+
+    pub fn with_format(mut self, format: SpeechFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_speed(mut self, speed: Option<f32>) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+/// A text-to-speech service
+#[async_trait]
+pub trait SpeechProvider: Send + Sync {
+    /// Model used when the options don't name one
+    fn default_speech_model(&self) -> &str;
+
+    /// Voice used when the options don't name one
+    fn default_voice(&self) -> &str;
+
+    /// Synthesize speech for `text`
+    async fn synthesize(
+        &self,
+        text: &str,
+        options: &SpeechOptions,
+    ) -> Result<AudioStream, ProviderError>;
+
+    /// Synthesize speech for the text content of a message, skipping tool calls and other
+    /// non-text content
+    async fn speak_message(
+        &self,
+        message: &Message,
+        options: &SpeechOptions,
+    ) -> Result<AudioStream, ProviderError> {
+        let text = message.as_concat_text();
+        if text.trim().is_empty() {
+            return Err(ProviderError::RequestFailed(
+                "Message has no text content to speak".to_string(),
+            ));
+        }
+        self.synthesize(&text, options).await
+    }
+}
+
+fn audio_stream(response: reqwest::Response) -> AudioStream {
+    Box::pin(
+        response
+            .bytes_stream()
+            .map_ok(|chunk| chunk.to_vec())
+            .map_err(|e| ProviderError::RequestFailed(format!("Audio stream error: {}", e))),
+    )
+}
+
+/// Speech from OpenAI's `audio/speech` API
+pub struct OpenAiSpeechProvider {
+    api_client: ApiClient,
+}
+
+impl OpenAiSpeechProvider {
+    /// Configured with OPENAI_API_KEY and OPENAI_HOST
+    pub fn from_env() -> Result<Self> {
+        let config = Config::global();
+        let api_key: String = config.get_secret("OPENAI_API_KEY")?;
+        let host: String = config
+            .get_param("OPENAI_HOST")
+            .unwrap_or_else(|_| "https://api.openai.com".to_string());
+
+        let api_client = ApiClient::new(host, AuthMethod::BearerToken(api_key))?;
+        Ok(Self { api_client })
+    }
+}
+
+impl ProviderRetry for OpenAiSpeechProvider {}
+
+#[async_trait]
+impl SpeechProvider for OpenAiSpeechProvider {
+    fn default_speech_model(&self) -> &str {
+        OPENAI_DEFAULT_SPEECH_MODEL
+    }
+
+    fn default_voice(&self) -> &str {
+        OPENAI_DEFAULT_VOICE
+    }
+
+    async fn synthesize(
+        &self,
+        text: &str,
+        options: &SpeechOptions,
+    ) -> Result<AudioStream, ProviderError> {
+        let payload = create_openai_speech_request(text, options);
+
+        let response = self
+            .with_retry(|| async {
+                let response = self
+                    .api_client
+                    .response_post("v1/audio/speech", &payload)
+                    .await?;
+                handle_status_openai_compat(response).await
+            })
+            .await?;
+        Ok(audio_stream(response))
+    }
+}
+
+fn create_openai_speech_request(text: &str, options: &SpeechOptions) -> Value {
+    let mut payload = json!({
+        "model": options.model.as_deref().unwrap_or(OPENAI_DEFAULT_SPEECH_MODEL),
+        "input": text,
+        "voice": options.voice.as_deref().unwrap_or(OPENAI_DEFAULT_VOICE),
+        "response_format": match options.format {
+            SpeechFormat::Mp3 => "mp3",
+            SpeechFormat::Opus => "opus",
+            SpeechFormat::Pcm => "pcm",
+        },
+    });
+    if let Some(speed) = options.speed {
+        payload["speed"] = json!(speed);
+    }
+    payload
+}
+
+/// Speech from ElevenLabs' streaming text-to-speech API
+pub struct ElevenLabsSpeechProvider {
+    api_client: ApiClient,
+}
+
+impl ElevenLabsSpeechProvider {
+    /// Configured with ELEVENLABS_API_KEY and ELEVENLABS_HOST
+    pub fn from_env() -> Result<Self> {
+        let config = Config::global();
+        let api_key: String = config.get_secret("ELEVENLABS_API_KEY")?;
+        let host: String = config
+            .get_param("ELEVENLABS_HOST")
+            .unwrap_or_else(|_| ELEVENLABS_API_HOST.to_string());
+
+        let auth = AuthMethod::ApiKey {
+            header_name: "xi-api-key".to_string(),
+            key: api_key,
+        };
+        let api_client = ApiClient::new(host, auth)?;
+        Ok(Self { api_client })
+    }
+}
+
+impl ProviderRetry for ElevenLabsSpeechProvider {}
+
+#[async_trait]
+impl SpeechProvider for ElevenLabsSpeechProvider {
+    fn default_speech_model(&self) -> &str {
+        ELEVENLABS_DEFAULT_SPEECH_MODEL
+    }
+
+    fn default_voice(&self) -> &str {
+        ELEVENLABS_DEFAULT_VOICE
+    }
+
+    async fn synthesize(
+        &self,
+        text: &str,
+        options: &SpeechOptions,
+    ) -> Result<AudioStream, ProviderError> {
+        let path = elevenlabs_stream_path(options);
+        let payload = create_elevenlabs_speech_request(text, options);
+
+        let response = self
+            .with_retry(|| async {
+                let response = self.api_client.response_post(&path, &payload).await?;
+                handle_status_openai_compat(response).await
+            })
+            .await?;
+        Ok(audio_stream(response))
+    }
+}
+
+fn elevenlabs_stream_path(options: &SpeechOptions) -> String {
+    let voice = options.voice.as_deref().unwrap_or(ELEVENLABS_DEFAULT_VOICE);
+    let output_format = match options.format {
+        SpeechFormat::Mp3 => "mp3_44100_128",
+        SpeechFormat::Opus => "opus_48000_128",
+        SpeechFormat::Pcm => "pcm_24000",
+    };
+    format!(
+        "v1/text-to-speech/{}/stream?output_format={}",
+        urlencoding::encode(voice),
+        output_format
+    )
+}
+
+fn create_elevenlabs_speech_request(text: &str, options: &SpeechOptions) -> Value {
+    let mut payload = json!({
+        "text": text,
+        "model_id": options.model.as_deref().unwrap_or(ELEVENLABS_DEFAULT_SPEECH_MODEL),
+    });
+    if let Some(speed) = options.speed {
+        payload["voice_settings"] = json!({ "speed": speed });
+    }
+    payload
+}
+
+/// Create a speech provider by name: `openai` or `elevenlabs`
+pub fn create_speech_provider(name: &str) -> Result<Arc<dyn SpeechProvider>> {
+    let provider: Arc<dyn SpeechProvider> = match name {
+        "openai" => Arc::new(OpenAiSpeechProvider::from_env()?),
+        "elevenlabs" => Arc::new(ElevenLabsSpeechProvider::from_env()?),
+        _ => return Err(anyhow::anyhow!("Provider {} does not support speech", name)),
+    };
+    Ok(provider)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_openai_speech_request() {
+        let payload = create_openai_speech_request("hello", &SpeechOptions::default());
+        assert_eq!(
+            payload,
+            json!({
+                "model": "gpt-4o-mini-tts",
+                "input": "hello",
+                "voice": "alloy",
+                "response_format": "mp3",
+            })
+        );
+
+        let options = SpeechOptions::default()
+            .with_voice("nova")
+            .with_format(SpeechFormat::Opus)
+            .with_speed(Some(1.5));
+        let payload = create_openai_speech_request("hello", &options);
+        assert_eq!(payload["voice"], "nova");
+        assert_eq!(payload["response_format"], "opus");
+        assert_eq!(payload["speed"], 1.5);
+    }
+
+    #[test]
+    fn test_elevenlabs_request() {
+        let options = SpeechOptions::default().with_voice("my voice");
+        assert_eq!(
+            elevenlabs_stream_path(&options),
+            "v1/text-to-speech/my%20voice/stream?output_format=mp3_44100_128"
+        );
+
+        let payload = create_elevenlabs_speech_request("hello", &options);
+        assert_eq!(payload["model_id"], "eleven_multilingual_v2");
+        assert!(payload.get("voice_settings").is_none());
+    }
+}
@@ -0,0 +1,343 @@
+//! Typed events for streamed responses.
+//!
+//! A [`MessageStream`] yields `(Option<Message>, Option<ProviderUsage>)` tuples, which leaves every
+//! consumer to work out what each combination means. An [`EventStream`] says it outright: one
+//! [`StreamEvent`] per item, ending with [`StreamEvent::Done`] or [`StreamEvent::Error`].
+//!
+//! The agent takes a response as events. Providers that parse their stream into events
+//! themselves, like OpenAI's chat completions, pass text and tool call arguments on as they
+//! arrive; the rest get [`into_events`] of their [`MessageStream`]. [`into_message_stream`] goes
+//! the other way, for consumers that still take tuples.
+
+use std::borrow::Cow;
+use std::pin::Pin;
+
+use async_stream::{stream, try_stream};
+use futures::{Stream, StreamExt};
+use rmcp::model::{object, CallToolRequestParam, ErrorCode, ErrorData};
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+
+use super::base::{MessageStream, ProviderUsage};
+use super::errors::ProviderError;
+use crate::conversation::message::{Message, MessageContent, MessageMetadata, TokenLogprob};
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// A piece of the response text. Deltas with the same `message_id` belong to one message.
+    TextDelta {
+        message_id: Option<String>,
+        text: String,
+        /// Log probabilities of the tokens in `text`, when they were requested
+        #[serde(skip_serializing_if = "Option::is_none")]
+        logprobs: Option<Vec<TokenLogprob>>,
+    },
+    /// A piece of the model's reasoning. The signature, where the provider sends one, comes with
+    /// the last piece of a block.
+    ThinkingDelta {
+        message_id: Option<String>,
+        thinking: String,
+        signature: Option<String>,
+    },
+    /// A piece of a tool call's JSON arguments. Deltas with the same `id` belong to one call, and
+    /// the first of them carries the tool name.
+    ToolCallDelta {
+        id: String,
+        name: Option<String>,
+        arguments: String,
+    },
+    /// Content that arrives whole, like a complete tool call
+    MessageComplete(Message),
+    /// Token usage. [Interim](ProviderUsage::interim) usage may come any time; the final usage
+    /// comes once the response is complete.
+    Usage(ProviderUsage),
+    /// The response is complete
+    Done,
+    /// The response failed; nothing follows
+    Error(#[serde(serialize_with = "serialize_error")] ProviderError),
+}
+
+fn serialize_error<S: Serializer>(error: &ProviderError, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(error)
+}
+
+pub type EventStream = Pin<Box<dyn Stream<Item = StreamEvent> + Send>>;
+
+/// The events for one message from a [`MessageStream`]. Messages of only plain text and thinking
+/// become deltas, with any log probabilities going along with the text; anything else, including
+/// text with `_meta`, is passed on whole.
+fn message_events(mut message: Message) -> Vec<StreamEvent> {
+    let mut logprobs = message.metadata.logprobs.take();
+    let only_deltas = message.metadata == MessageMetadata::default()
+        && message.content.iter().all(|content| match content {
+            MessageContent::Text(text) => text.meta.is_none(),
+            MessageContent::Thinking(_) => true,
+            _ => false,
+        });
+    if !only_deltas {
+        message.metadata.logprobs = logprobs;
+        return vec![StreamEvent::MessageComplete(message)];
+    }
+
+    let message_id = message.id;
+    message
+        .content
+        .into_iter()
+        .filter_map(|content| match content {
+            MessageContent::Text(text) => Some(StreamEvent::TextDelta {
+                message_id: message_id.clone(),
+                text: text.text.clone(),
+                logprobs: logprobs.take(),
+            }),
+            MessageContent::Thinking(thinking) => Some(StreamEvent::ThinkingDelta {
+                message_id: message_id.clone(),
+                thinking: thinking.thinking,
+                signature: Some(thinking.signature).filter(|s| !s.is_empty()),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The events of a [`MessageStream`]
+pub fn into_events(mut stream: MessageStream) -> EventStream {
+    Box::pin(stream! {
+        while let Some(item) = stream.next().await {
+            match item {
+                Ok((message, usage)) => {
+                    for event in message.map(message_events).unwrap_or_default() {
+                        yield event;
+                    }
+                    if let Some(usage) = usage {
+                        yield StreamEvent::Usage(usage);
+                    }
+                }
+                Err(e) => {
+                    yield StreamEvent::Error(e);
+                    return;
+                }
+            }
+        }
+        yield StreamEvent::Done;
+    })
+}
+
+/// Tool calls whose arguments are still streaming in, in the order they started
+#[derive(Default)]
+struct ToolCallBuffer {
+    calls: Vec<(String, String, String)>,
+}
+
+impl ToolCallBuffer {
+    fn push(&mut self, id: String, name: Option<String>, arguments: &str) {
+        match self.calls.iter_mut().find(|(call_id, _, _)| *call_id == id) {
+            Some((_, call_name, call_arguments)) => {
+                if let Some(name) = name {
+                    *call_name = name;
+                }
+                call_arguments.push_str(arguments);
+            }
+            None => self
+                .calls
+                .push((id, name.unwrap_or_default(), arguments.to_string())),
+        }
+    }
+
+    /// A message with the buffered tool calls, if there are any
+    fn take_message(&mut self) -> Option<Message> {
+        if self.calls.is_empty() {
+            return None;
+        }
+        let content = self.calls.drain(..).map(|(id, name, arguments)| {
+            let parsed = if arguments.is_empty() {
+                Ok(json!({}))
+            } else {
+                serde_json::from_str::<Value>(&arguments)
+            };
+            match parsed {
+                Ok(params) => MessageContent::tool_request(
+                    id,
+                    Ok(CallToolRequestParam {
+                        name: name.into(),
+                        arguments: Some(object(params)),
+                    }),
+                ),
+                Err(e) => {
+                    let error = ErrorData {
+                        code: ErrorCode::INVALID_PARAMS,
+                        message: Cow::from(format!(
+                            "Could not interpret tool use parameters for id {}: {}",
+                            id, e
+                        )),
+                        data: None,
+                    };
+                    MessageContent::tool_request(id, Err(error))
+                }
+            }
+        });
+        Some(content.fold(Message::assistant(), Message::with_content))
+    }
+}
+
+fn with_message_id(message: Message, message_id: Option<String>) -> Message {
+    match message_id {
+        Some(id) => message.with_id(id),
+        None => message,
+    }
+}
+
+/// A [`MessageStream`] of the events, for consumers that still take tuples. Tool call deltas are
+/// put together and passed on as one message before the final usage, or at the end.
+pub fn into_message_stream(mut events: EventStream) -> MessageStream {
+    Box::pin(try_stream! {
+        let mut tool_calls = ToolCallBuffer::default();
+        while let Some(event) = events.next().await {
+            match event {
+                StreamEvent::TextDelta { message_id, text, logprobs } => {
+                    let mut message = with_message_id(Message::assistant().with_text(text), message_id);
+                    if let Some(logprobs) = logprobs {
+                        message = message.with_logprobs(logprobs);
+                    }
+                    yield (Some(message), None);
+                }
+                StreamEvent::ThinkingDelta { message_id, thinking, signature } => {
+                    let message = Message::assistant().with_thinking(thinking, signature.unwrap_or_default());
+                    yield (Some(with_message_id(message, message_id)), None);
+                }
+                StreamEvent::ToolCallDelta { id, name, arguments } => {
+                    tool_calls.push(id, name, &arguments);
+                }
+                StreamEvent::MessageComplete(message) => {
+                    yield (Some(message), None);
+                }
+                StreamEvent::Usage(usage) => {
+                    if !usage.interim {
+                        if let Some(message) = tool_calls.take_message() {
+                            yield (Some(message), None);
+                        }
+                    }
+                    yield (None, Some(usage));
+                }
+                StreamEvent::Done => break,
+                StreamEvent::Error(e) => Err(e)?,
+            }
+        }
+        if let Some(message) = tool_calls.take_message() {
+            yield (Some(message), None);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{stream_from_single_message, Usage};
+    use futures::TryStreamExt;
+
+    fn usage() -> ProviderUsage {
+        ProviderUsage::new("model".to_string(), Usage::new(Some(10), Some(5), Some(15)))
+    }
+
+    #[tokio::test]
+    async fn test_into_events() {
+        let chunks: Vec<Result<_, ProviderError>> = vec![
+            Ok((
+                Some(Message::assistant().with_id("msg").with_text("Hel")),
+                None,
+            )),
+            Ok((
+                Some(Message::assistant().with_tool_request(
+                    "call",
+                    Ok(CallToolRequestParam {
+                        name: "shell".into(),
+                        arguments: None,
+                    }),
+                )),
+                Some(usage()),
+            )),
+            Err(ProviderError::ServerError("gone".to_string())),
+            Ok((None, Some(usage()))),
+        ];
+        let stream: MessageStream = Box::pin(futures::stream::iter(chunks));
+        let events: Vec<_> = into_events(stream).collect().await;
+
+        assert_eq!(events.len(), 4);
+        assert!(matches!(
+            &events[0],
+            StreamEvent::TextDelta { message_id: Some(id), text, .. } if id == "msg" && text == "Hel"
+        ));
+        assert!(matches!(&events[1], StreamEvent::MessageComplete(m) if m.is_tool_call()));
+        assert!(matches!(&events[2], StreamEvent::Usage(u) if u.usage.total_tokens == Some(15)));
+        assert!(matches!(&events[3], StreamEvent::Error(_)));
+
+        let single = stream_from_single_message(Message::assistant().with_text("hi"), usage());
+        let events: Vec<_> = into_events(single).collect().await;
+        assert!(matches!(events.last(), Some(StreamEvent::Done)));
+    }
+
+    #[tokio::test]
+    async fn test_into_message_stream_puts_tool_calls_together() {
+        let events = vec![
+            StreamEvent::TextDelta {
+                message_id: Some("msg".to_string()),
+                text: "Running it".to_string(),
+                logprobs: None,
+            },
+            StreamEvent::ToolCallDelta {
+                id: "call".to_string(),
+                name: Some("shell".to_string()),
+                arguments: "{\"command\":".to_string(),
+            },
+            StreamEvent::Usage(usage().as_interim()),
+            StreamEvent::ToolCallDelta {
+                id: "call".to_string(),
+                name: None,
+                arguments: " \"ls\"}".to_string(),
+            },
+            StreamEvent::Usage(usage()),
+            StreamEvent::Done,
+        ];
+        let chunks: Vec<_> = into_message_stream(Box::pin(futures::stream::iter(events)))
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(chunks.len(), 4);
+        let text = chunks[0].0.as_ref().unwrap();
+        assert_eq!(text.id.as_deref(), Some("msg"));
+        assert_eq!(text.as_concat_text(), "Running it");
+        assert!(chunks[1].1.as_ref().unwrap().interim);
+
+        let MessageContent::ToolRequest(request) = &chunks[2].0.as_ref().unwrap().content[0] else {
+            panic!("Expected tool request");
+        };
+        let call = request.tool_call.as_ref().unwrap();
+        assert_eq!(call.name, "shell");
+        assert_eq!(call.arguments.as_ref().unwrap()["command"], "ls");
+        assert!(!chunks[3].1.as_ref().unwrap().interim);
+    }
+
+    #[tokio::test]
+    async fn test_into_message_stream_ends_with_error() {
+        let events = vec![
+            StreamEvent::TextDelta {
+                message_id: None,
+                text: "Hel".to_string(),
+                logprobs: None,
+            },
+            StreamEvent::Error(ProviderError::ServerError("gone".to_string())),
+            StreamEvent::TextDelta {
+                message_id: None,
+                text: "lo".to_string(),
+                logprobs: None,
+            },
+        ];
+        let chunks: Vec<_> = into_message_stream(Box::pin(futures::stream::iter(events)))
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].is_ok());
+        assert!(matches!(chunks[1], Err(ProviderError::ServerError(_))));
+    }
+}
@@ -0,0 +1,337 @@
+//! Cutting streamed responses short on the client.
+//!
+//! - GOOSE_STOP_SEQUENCES: strings that end a response where they appear in its text. The stop
+//!   sequence itself is left out.
+//! - GOOSE_MAX_OUTPUT_TOKENS: most tokens of text, thinking and tool arguments to take from one
+//!   response, 0 or unset for no limit. The response ends after the chunk that reaches it.
+//!
+//! A response cut short ends with the usage counted so far, like any complete response. The
+//! provider's stream is dropped, which closes its request so the model stops generating tokens
+//! nobody will read.
+
+use async_stream::try_stream;
+use futures::StreamExt;
+
+use super::base::{MessageStream, ProviderUsage, Usage};
+use crate::config::Config;
+use crate::conversation::message::{Message, MessageContent};
+use crate::token_counter::{create_token_counter_for_model, TokenCounter};
+
+pub const STOP_SEQUENCES_CONFIG_KEY: &str = "GOOSE_STOP_SEQUENCES";
+pub const MAX_OUTPUT_TOKENS_CONFIG_KEY: &str = "GOOSE_MAX_OUTPUT_TOKENS";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamLimits {
+    pub stop_sequences: Vec<String>,
+    pub max_output_tokens: Option<usize>,
+}
+
+impl StreamLimits {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let stop_sequences = config
+            .get_param::<Vec<String>>(STOP_SEQUENCES_CONFIG_KEY)
+            .unwrap_or_default();
+        let max_output_tokens = config
+            .get_param::<usize>(MAX_OUTPUT_TOKENS_CONFIG_KEY)
+            .ok()
+            .filter(|&max| max > 0);
+        Self::new(stop_sequences, max_output_tokens)
+    }
+
+    pub fn new(stop_sequences: Vec<String>, max_output_tokens: Option<usize>) -> Self {
+        Self {
+            stop_sequences: stop_sequences
+                .into_iter()
+                .filter(|s| !s.is_empty())
+                .collect(),
+            max_output_tokens,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stop_sequences.is_empty() && self.max_output_tokens.is_none()
+    }
+
+    /// End `stream` at the first stop sequence or once the response reaches the token limit.
+    /// Tokens are counted with the tokenizer of `model`.
+    pub fn apply(self, stream: MessageStream, model: &str) -> MessageStream {
+        if self.is_empty() {
+            return stream;
+        }
+        let mut model = model.to_string();
+        Box::pin(try_stream! {
+            let mut stream = stream;
+            let counter = create_token_counter_for_model(&model)
+                .await
+                .inspect_err(|e| tracing::warn!("Not limiting output tokens: {}", e))
+                .ok();
+            let mut scanner = StopScanner::new(self.stop_sequences);
+            let mut input_tokens = None;
+            let mut output_tokens = 0;
+
+            while let Some(item) = stream.next().await {
+                let (message, usage) = item?;
+                if let Some(usage) = &usage {
+                    model = usage.model.clone();
+                    input_tokens = usage.usage.input_tokens.or(input_tokens);
+                }
+
+                let mut messages = Vec::new();
+                let mut stop_sequence_found = false;
+                if let Some(message) = message {
+                    if scanner.applies_to(&message) {
+                        let (text, found) = scanner.push(&message);
+                        messages = text;
+                        stop_sequence_found = found;
+                    } else {
+                        messages.extend(scanner.flush());
+                        messages.push(message);
+                    }
+                }
+                if usage.as_ref().is_some_and(|usage| !usage.interim) {
+                    messages.extend(scanner.flush());
+                }
+
+                if let Some(counter) = &counter {
+                    output_tokens += messages.iter().map(|m| count_output(counter, m)).sum::<usize>();
+                }
+                let limit_reached = counter.is_some()
+                    && self.max_output_tokens.is_some_and(|max| output_tokens >= max);
+
+                let mut items: Vec<_> = messages.into_iter().map(|m| (Some(m), None)).collect();
+                if stop_sequence_found || limit_reached {
+                    let reason = if stop_sequence_found { "a stop sequence" } else { "the output token limit" };
+                    tracing::info!("Ending the response at {}", reason);
+                    let output_tokens = counter.as_ref().map(|_| output_tokens as i32);
+                    let total_tokens = input_tokens.zip(output_tokens).map(|(i, o)| i + o);
+                    let usage = Usage::new(input_tokens, output_tokens, total_tokens);
+                    items.push((None, Some(ProviderUsage::new(model.clone(), usage))));
+                    for item in items {
+                        yield item;
+                    }
+                    // Dropping the provider's stream closes its request
+                    return;
+                }
+
+                if let Some(usage) = usage {
+                    match items.last_mut() {
+                        Some(last) => last.1 = Some(usage),
+                        None => items.push((None, Some(usage))),
+                    }
+                }
+                for item in items {
+                    yield item;
+                }
+            }
+            if let Some(message) = scanner.flush() {
+                yield (Some(message), None);
+            }
+        })
+    }
+}
+
+/// The text, thinking and tool arguments of a message
+fn count_output(counter: &TokenCounter, message: &Message) -> usize {
+    message
+        .content
+        .iter()
+        .map(|content| match content {
+            MessageContent::Text(text) => counter.count_tokens(&text.text),
+            MessageContent::Thinking(thinking) => counter.count_tokens(&thinking.thinking),
+            MessageContent::ToolRequest(request) => request
+                .tool_call
+                .as_ref()
+                .ok()
+                .and_then(|call| call.arguments.as_ref())
+                .map(|arguments| {
+                    counter.count_tokens(&serde_json::to_string(arguments).unwrap_or_default())
+                })
+                .unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Holds back the end of the streamed text until it can't be the start of a stop sequence
+struct StopScanner {
+    stop_sequences: Vec<String>,
+    /// Bytes to hold back: one less than the longest stop sequence
+    hold: usize,
+    held: String,
+    /// The last message pushed, whose id, creation time and metadata the passed on text keeps
+    message: Option<Message>,
+}
+
+impl StopScanner {
+    fn new(stop_sequences: Vec<String>) -> Self {
+        let hold = stop_sequences
+            .iter()
+            .map(|s| s.len().saturating_sub(1))
+            .max()
+            .unwrap_or(0);
+        Self {
+            stop_sequences,
+            hold,
+            held: String::new(),
+            message: None,
+        }
+    }
+
+    /// Whether the message is streamed text to look for stop sequences in
+    fn applies_to(&self, message: &Message) -> bool {
+        !self.stop_sequences.is_empty()
+            && !message.content.is_empty()
+            && message
+                .content
+                .iter()
+                .all(|content| matches!(content, MessageContent::Text(_)))
+    }
+
+    /// Add a chunk of text, returning the text that can be passed on and whether a stop sequence
+    /// was found
+    fn push(&mut self, message: &Message) -> (Vec<Message>, bool) {
+        let mut ready = Vec::new();
+        if self.message.as_ref().map(|last| &last.id) != Some(&message.id) {
+            // Text of one message can't run into the next
+            ready.extend(self.flush());
+        }
+        self.message = Some(message.clone());
+
+        self.held.push_str(&message.as_concat_text());
+        let found = self
+            .stop_sequences
+            .iter()
+            .filter_map(|stop| self.held.find(stop.as_str()))
+            .min();
+        if let Some(position) = found {
+            self.held.truncate(position);
+            ready.extend(self.flush());
+            return (ready, true);
+        }
+
+        let mut split = self.held.len().saturating_sub(self.hold);
+        while !self.held.is_char_boundary(split) {
+            split -= 1;
+        }
+        let rest = self.held.split_off(split);
+        let text = std::mem::replace(&mut self.held, rest);
+        ready.extend(self.text_message(text));
+        (ready, false)
+    }
+
+    /// The held back text
+    fn flush(&mut self) -> Option<Message> {
+        let held = std::mem::take(&mut self.held);
+        self.text_message(held)
+    }
+
+    fn text_message(&self, text: String) -> Option<Message> {
+        if text.is_empty() {
+            return None;
+        }
+        let mut message = self.message.clone()?;
+        message.content = vec![MessageContent::text(text)];
+        Some(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::errors::ProviderError;
+    use futures::TryStreamExt;
+
+    fn text_stream(chunks: &[&str]) -> MessageStream {
+        let mut items: Vec<Result<_, ProviderError>> = chunks
+            .iter()
+            .map(|chunk| {
+                Ok((
+                    Some(Message::assistant().with_id("msg").with_text(*chunk)),
+                    None,
+                ))
+            })
+            .collect();
+        let usage = ProviderUsage::new(
+            "gpt-4o".to_string(),
+            Usage::new(Some(10), Some(50), Some(60)),
+        );
+        items.push(Ok((None, Some(usage))));
+        Box::pin(futures::stream::iter(items))
+    }
+
+    async fn collect(stream: MessageStream) -> (String, ProviderUsage) {
+        let items: Vec<_> = stream.try_collect().await.unwrap();
+        let text = items
+            .iter()
+            .filter_map(|(message, _)| message.as_ref())
+            .map(|message| message.as_concat_text())
+            .collect();
+        let usage = items
+            .iter()
+            .rev()
+            .find_map(|(_, usage)| usage.clone())
+            .unwrap();
+        (text, usage)
+    }
+
+    #[tokio::test]
+    async fn test_stop_sequence_across_chunks() {
+        let limits = StreamLimits::new(vec!["STOP".to_string(), String::new()], None);
+        let stream = limits.apply(
+            text_stream(&["The answer is 4", "2.ST", "OP and more", "text"]),
+            "gpt-4o",
+        );
+        let (text, usage) = collect(stream).await;
+        assert_eq!(text, "The answer is 42.");
+        // Usage counted so far; the provider's never arrived
+        assert_eq!(usage.usage.input_tokens, None);
+        assert!(usage.usage.output_tokens.is_some_and(|tokens| tokens < 50));
+    }
+
+    #[tokio::test]
+    async fn test_passed_on_text_keeps_message_metadata() {
+        let message = Message::assistant()
+            .with_id("msg")
+            .with_text("Hidden STOP")
+            .agent_only();
+        let stream: MessageStream = Box::pin(futures::stream::iter([Ok((Some(message), None))]));
+        let items: Vec<_> = StreamLimits::new(vec!["STOP".to_string()], None)
+            .apply(stream, "gpt-4o")
+            .try_collect()
+            .await
+            .unwrap();
+        let message = items[0].0.as_ref().unwrap();
+        assert_eq!(message.as_concat_text(), "Hidden ");
+        assert_eq!(message.id.as_deref(), Some("msg"));
+        assert!(!message.is_user_visible());
+    }
+
+    #[tokio::test]
+    async fn test_held_back_text_is_passed_on() {
+        let limits = StreamLimits::new(vec!["</answer>".to_string()], None);
+        let stream = limits.apply(text_stream(&["All ", "done </"]), "gpt-4o");
+        let (text, usage) = collect(stream).await;
+        assert_eq!(text, "All done </");
+        assert_eq!(usage.usage.output_tokens, Some(50));
+    }
+
+    #[tokio::test]
+    async fn test_max_output_tokens() {
+        let limits = StreamLimits::new(Vec::new(), Some(3));
+        let stream = limits.apply(
+            text_stream(&["one two", " three four", " five six", " seven"]),
+            "gpt-4o",
+        );
+        let (text, usage) = collect(stream).await;
+        assert_eq!(text, "one two three four");
+        assert_eq!(usage.usage.output_tokens, Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_no_limits_pass_the_stream_through() {
+        let stream = StreamLimits::default().apply(text_stream(&["a", "b"]), "gpt-4o");
+        let items: Vec<_> = stream.try_collect().await.unwrap();
+        assert_eq!(items.len(), 3);
+    }
+}
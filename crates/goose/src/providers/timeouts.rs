@@ -0,0 +1,215 @@
+//! Request deadlines for providers.
+//!
+//! [`RequestTimeouts`] holds the per-provider limits applied by the HTTP client:
+//!
+//! - `<PREFIX>_TIMEOUT_MS`: milliseconds allowed for a whole request, including reading a streamed
+//!   body. `<PREFIX>_TIMEOUT` in seconds is still read when the millisecond key isn't set.
+//! - `<PREFIX>_CONNECT_TIMEOUT_MS`: milliseconds allowed to establish a connection, overriding
+//!   GOOSE_CONNECT_TIMEOUT_MS
+//!
+//! [`StreamTimeouts`] guards streamed responses, which can otherwise hang with the connection open:
+//!
+//! - GOOSE_FIRST_TOKEN_TIMEOUT_MS: milliseconds to wait for the first line of a stream
+//!   (defaults to the idle timeout)
+//! - GOOSE_STREAM_IDLE_TIMEOUT_MS: milliseconds to wait between lines of a stream (default 300000),
+//!   0 to disable
+
+use std::pin::Pin;
+use std::time::Duration;
+
+use futures::{Stream, StreamExt};
+
+use super::errors::ProviderError;
+use crate::config::Config;
+
+pub const DEFAULT_STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequestTimeouts {
+    pub total: Duration,
+    pub connect: Option<Duration>,
+}
+
+impl RequestTimeouts {
+    /// Read the timeouts for the provider whose config keys start with `prefix`, e.g. `OPENAI`
+    pub fn from_config(prefix: &str, default_total: Duration) -> Self {
+        let config = Config::global();
+        let total = config
+            .get_param::<u64>(&format!("{}_TIMEOUT_MS", prefix))
+            .map(Duration::from_millis)
+            .or_else(|_| {
+                config
+                    .get_param::<u64>(&format!("{}_TIMEOUT", prefix))
+                    .map(Duration::from_secs)
+            })
+            .unwrap_or(default_total);
+        let connect = config
+            .get_param::<u64>(&format!("{}_CONNECT_TIMEOUT_MS", prefix))
+            .ok()
+            .map(Duration::from_millis);
+        Self { total, connect }
+    }
+}
+
+/// Raised by a stream wrapped with [`StreamTimeouts::apply`] when the provider goes quiet
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum StreamTimeout {
+    #[error("No response from provider within {0:?} of starting the stream")]
+    FirstToken(Duration),
+    #[error("Provider stream was idle for {0:?}")]
+    Idle(Duration),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamTimeouts {
+    pub first_token: Option<Duration>,
+    pub idle: Option<Duration>,
+}
+
+impl Default for StreamTimeouts {
+    fn default() -> Self {
+        Self {
+            first_token: None,
+            idle: Some(DEFAULT_STREAM_IDLE_TIMEOUT),
+        }
+    }
+}
+
+impl StreamTimeouts {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let idle = match config.get_param::<u64>("GOOSE_STREAM_IDLE_TIMEOUT_MS") {
+            Ok(0) => None,
+            Ok(ms) => Some(Duration::from_millis(ms)),
+            Err(_) => Self::default().idle,
+        };
+        let first_token = config
+            .get_param::<u64>("GOOSE_FIRST_TOKEN_TIMEOUT_MS")
+            .ok()
+            .map(Duration::from_millis);
+        Self { first_token, idle }
+    }
+
+    /// End `stream` with a [`StreamTimeout`] error when its first item, or any later one, takes
+    /// longer than allowed
+    pub fn apply<T, S>(self, stream: S) -> Pin<Box<dyn Stream<Item = anyhow::Result<T>> + Send>>
+    where
+        T: Send + 'static,
+        S: Stream<Item = anyhow::Result<T>> + Send + 'static,
+    {
+        Box::pin(async_stream::stream! {
+            let mut stream = Box::pin(stream);
+            let mut limit = self.first_token.or(self.idle);
+            let mut timeout_error = self.first_token.map(StreamTimeout::FirstToken);
+            loop {
+                let next = match limit {
+                    Some(limit) => match tokio::time::timeout(limit, stream.next()).await {
+                        Ok(next) => next,
+                        Err(_) => {
+                            let error = timeout_error.unwrap_or(StreamTimeout::Idle(limit));
+                            tracing::warn!("{}", error);
+                            yield Err(error.into());
+                            break;
+                        }
+                    },
+                    None => stream.next().await,
+                };
+                let Some(item) = next else { break };
+                limit = self.idle;
+                timeout_error = None;
+                yield item;
+            }
+        })
+    }
+}
+
+/// Convert an error from a decoded provider stream, keeping timeouts distinct from malformed data
+pub fn stream_error(error: anyhow::Error) -> ProviderError {
+    match error.downcast_ref::<StreamTimeout>() {
+        Some(timeout) => ProviderError::RequestFailed(timeout.to_string()),
+        None => ProviderError::RequestFailed(format!("Stream decode error: {}", error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt;
+
+    #[test]
+    fn test_request_timeouts_from_env() {
+        temp_env::with_vars(
+            [
+                ("TESTPROV_TIMEOUT_MS", Some("1500")),
+                ("TESTPROV_TIMEOUT", Some("30")),
+                ("TESTPROV_CONNECT_TIMEOUT_MS", Some("250")),
+            ],
+            || {
+                let timeouts = RequestTimeouts::from_config("TESTPROV", Duration::from_secs(600));
+                assert_eq!(timeouts.total, Duration::from_millis(1500));
+                assert_eq!(timeouts.connect, Some(Duration::from_millis(250)));
+            },
+        );
+
+        temp_env::with_vars(
+            [
+                ("TESTPROV_TIMEOUT_MS", None),
+                ("TESTPROV_TIMEOUT", Some("30")),
+                ("TESTPROV_CONNECT_TIMEOUT_MS", None),
+            ],
+            || {
+                let timeouts = RequestTimeouts::from_config("TESTPROV", Duration::from_secs(600));
+                assert_eq!(timeouts.total, Duration::from_secs(30));
+                assert_eq!(timeouts.connect, None);
+            },
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_idle_timeout() {
+        let timeouts = StreamTimeouts {
+            first_token: None,
+            idle: Some(Duration::from_millis(20)),
+        };
+        let lines = async_stream::stream! {
+            yield Ok::<_, anyhow::Error>("data: 1".to_string());
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            yield Ok("data: 2".to_string());
+        };
+
+        let mut stream = timeouts.apply(lines);
+        assert_eq!(stream.next().await.unwrap().unwrap(), "data: 1");
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<StreamTimeout>(),
+            Some(&StreamTimeout::Idle(Duration::from_millis(20)))
+        );
+        assert!(stream.next().await.is_none());
+        assert!(!stream_error(error).to_string().contains("decode"));
+    }
+
+    #[tokio::test]
+    async fn test_first_token_timeout() {
+        let timeouts = StreamTimeouts {
+            first_token: Some(Duration::from_millis(20)),
+            idle: Some(Duration::from_secs(10)),
+        };
+        let slow_start = async_stream::stream! {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            yield Ok::<_, anyhow::Error>("data: 1".to_string());
+        };
+        let error = timeouts
+            .apply(slow_start)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<StreamTimeout>(),
+            Some(&StreamTimeout::FirstToken(Duration::from_millis(20)))
+        );
+
+        let quick = futures::stream::iter(vec![Ok::<_, anyhow::Error>(1), Ok(2)]);
+        let items: Vec<_> = timeouts.apply(quick).try_collect().await.unwrap();
+        assert_eq!(items, vec![1, 2]);
+    }
+}
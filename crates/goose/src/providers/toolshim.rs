@@ -31,6 +31,7 @@
 //!
 
 use super::errors::ProviderError;
+use super::http_client::shared_client;
 use super::ollama::OLLAMA_DEFAULT_PORT;
 use super::ollama::OLLAMA_HOST;
 use crate::conversation::message::{Message, MessageContent};
@@ -42,7 +43,6 @@ use reqwest::Client;
 use rmcp::model::{object, CallToolRequestParam, RawContent, Tool};
 use serde_json::{json, Value};
 use std::ops::Deref;
-use std::time::Duration;
 use uuid::Uuid;
 
 /// Default model to use for tool interpretation
@@ -70,10 +70,9 @@ pub struct OllamaInterpreter {
 
 impl OllamaInterpreter {
     pub fn new() -> Result<Self, ProviderError> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(600))
-            .build()
-            .expect("Failed to create HTTP client");
+        let client = shared_client().map_err(|e| {
+            ProviderError::ExecutionError(format!("Failed to create HTTP client: {}", e))
+        })?;
 
         let base_url = Self::get_ollama_base_url()?;
 
@@ -0,0 +1,434 @@
+//! Speech-to-text providers, and transcription of audio input for models that can't accept
+//! audio natively.
+//!
+//! [`TranscriptionProvider`] is implemented for OpenAI Whisper, Groq Whisper and Deepgram, each
+//! configured with the same keys as the corresponding chat provider. Audio input is transcribed
+//! with the provider named by `GOOSE_TRANSCRIPTION_PROVIDER` (OpenAI by default) and the model
+//! named by `GOOSE_TRANSCRIPTION_MODEL`. Transcripts are cached in memory so a voice message is
+//! only transcribed once per process, not once per turn.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use base64::Engine;
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::api_client::{ApiClient, AuthMethod};
+use super::errors::ProviderError;
+use super::retry::ProviderRetry;
+use super::utils::handle_response_openai_compat;
+use crate::config::Config;
+use crate::conversation::message::{AudioContent, Message, MessageContent};
+
+pub const TRANSCRIPTION_PROVIDER_CONFIG_KEY: &str = "GOOSE_TRANSCRIPTION_PROVIDER";
+pub const TRANSCRIPTION_MODEL_CONFIG_KEY: &str = "GOOSE_TRANSCRIPTION_MODEL";
+pub const DEFAULT_TRANSCRIPTION_PROVIDER: &str = "openai";
+pub const DEFAULT_TRANSCRIPTION_MODEL: &str = "whisper-1";
+
+pub const GROQ_API_HOST: &str = "https://api.groq.com/openai";
+pub const GROQ_DEFAULT_TRANSCRIPTION_MODEL: &str = "whisper-large-v3-turbo";
+pub const DEEPGRAM_API_HOST: &str = "https://api.deepgram.com";
+pub const DEEPGRAM_DEFAULT_TRANSCRIPTION_MODEL: &str = "nova-3";
+
+static TRANSCRIPTS: Lazy<Mutex<LruCache<u64, String>>> =
+    Lazy::new(|| Mutex::new(LruCache::new(NonZeroUsize::new(64).unwrap())));
+
+/// Options for a transcription request. Unset fields fall back to the provider's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptionOptions {
+    /// Transcription model to use instead of the provider default
+    pub model: Option<String>,
+    /// ISO-639-1 language of the audio; detected automatically when unset
+    pub language: Option<String>,
+    /// Text to guide the transcript's spelling and style, where supported
+    pub prompt: Option<String>,
+}
+
+impl TranscriptionOptions {
+    /// Read options from GOOSE_TRANSCRIPTION_MODEL and GOOSE_TRANSCRIPTION_LANGUAGE
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        Self {
+            model: config.get_param(TRANSCRIPTION_MODEL_CONFIG_KEY).ok(),
+            language: config.get_param("GOOSE_TRANSCRIPTION_LANGUAGE").ok(),
+            prompt: None,
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn with_language(mut self, language: Option<String>) -> Self {
+        self.language = language;
+        self
+    }
+
+    pub fn with_prompt(mut self, prompt: Option<String>) -> Self {
+        self.prompt = prompt;
+        self
+    }
+}
+
+/// A speech-to-text service
+#[async_trait]
+pub trait TranscriptionProvider: Send + Sync {
+    /// Model used when the options don't name one
+    fn default_transcription_model(&self) -> &str;
+
+    /// Transcribe raw audio bytes encoded as `mime_type`, returning the trimmed transcript
+    async fn transcribe(
+        &self,
+        audio: &[u8],
+        mime_type: &str,
+        options: &TranscriptionOptions,
+    ) -> Result<String, ProviderError>;
+
+    /// Transcribe audio message content
+    async fn transcribe_content(
+        &self,
+        audio: &AudioContent,
+        options: &TranscriptionOptions,
+    ) -> Result<String, ProviderError> {
+        let bytes = base64::prelude::BASE64_STANDARD
+            .decode(&audio.data)
+            .map_err(|e| {
+                ProviderError::RequestFailed(format!("Invalid base64 audio data: {}", e))
+            })?;
+        self.transcribe(&bytes, &audio.mime_type, options).await
+    }
+}
+
+/// Transcription through an OpenAI-compatible `audio/transcriptions` endpoint, which covers
+/// both OpenAI and Groq Whisper
+pub struct WhisperTranscriptionProvider {
+    api_client: ApiClient,
+    base_path: String,
+    default_model: String,
+}
+
+impl WhisperTranscriptionProvider {
+    pub fn new(
+        api_client: ApiClient,
+        base_path: impl Into<String>,
+        default_model: impl Into<String>,
+    ) -> Self {
+        Self {
+            api_client,
+            base_path: base_path.into(),
+            default_model: default_model.into(),
+        }
+    }
+
+    /// OpenAI Whisper, configured with OPENAI_API_KEY and OPENAI_HOST
+    pub fn openai_from_env() -> Result<Self> {
+        let config = Config::global();
+        let api_key: String = config.get_secret("OPENAI_API_KEY")?;
+        let host: String = config
+            .get_param("OPENAI_HOST")
+            .unwrap_or_else(|_| "https://api.openai.com".to_string());
+
+        let api_client = ApiClient::new(host, AuthMethod::BearerToken(api_key))?;
+        Ok(Self::new(api_client, "v1/", DEFAULT_TRANSCRIPTION_MODEL))
+    }
+
+    /// Groq Whisper, configured with GROQ_API_KEY and GROQ_HOST
+    pub fn groq_from_env() -> Result<Self> {
+        let config = Config::global();
+        let api_key: String = config.get_secret("GROQ_API_KEY")?;
+        let host: String = config
+            .get_param("GROQ_HOST")
+            .unwrap_or_else(|_| GROQ_API_HOST.to_string());
+
+        let api_client = ApiClient::new(host, AuthMethod::BearerToken(api_key))?;
+        Ok(Self::new(
+            api_client,
+            "v1/",
+            GROQ_DEFAULT_TRANSCRIPTION_MODEL,
+        ))
+    }
+}
+
+impl ProviderRetry for WhisperTranscriptionProvider {}
+
+#[async_trait]
+impl TranscriptionProvider for WhisperTranscriptionProvider {
+    fn default_transcription_model(&self) -> &str {
+        &self.default_model
+    }
+
+    async fn transcribe(
+        &self,
+        audio: &[u8],
+        mime_type: &str,
+        options: &TranscriptionOptions,
+    ) -> Result<String, ProviderError> {
+        let model = options
+            .model
+            .clone()
+            .unwrap_or_else(|| self.default_model.clone());
+        let extension = AudioContent::format_for_mime_type(mime_type).unwrap_or("wav");
+        let path = format!("{}audio/transcriptions", self.base_path);
+
+        let json = self
+            .with_retry(|| async {
+                let file = Part::bytes(audio.to_vec())
+                    .file_name(format!("audio.{}", extension))
+                    .mime_str(mime_type)
+                    .map_err(|e| {
+                        ProviderError::RequestFailed(format!("Invalid audio MIME type: {}", e))
+                    })?;
+                let mut form = Form::new().text("model", model.clone()).part("file", file);
+                if let Some(language) = &options.language {
+                    form = form.text("language", language.clone());
+                }
+                if let Some(prompt) = &options.prompt {
+                    form = form.text("prompt", prompt.clone());
+                }
+
+                let response = self.api_client.response_post_multipart(&path, form).await?;
+                handle_response_openai_compat(response).await
+            })
+            .await?;
+
+        json.get("text")
+            .and_then(|t| t.as_str())
+            .map(|t| t.trim().to_string())
+            .ok_or_else(|| {
+                ProviderError::RequestFailed("Missing text in transcription response".to_string())
+            })
+    }
+}
+
+/// Transcription with Deepgram's pre-recorded audio API
+pub struct DeepgramTranscriptionProvider {
+    api_client: ApiClient,
+}
+
+impl DeepgramTranscriptionProvider {
+    /// Configured with DEEPGRAM_API_KEY and DEEPGRAM_HOST
+    pub fn from_env() -> Result<Self> {
+        let config = Config::global();
+        let api_key: String = config.get_secret("DEEPGRAM_API_KEY")?;
+        let host: String = config
+            .get_param("DEEPGRAM_HOST")
+            .unwrap_or_else(|_| DEEPGRAM_API_HOST.to_string());
+
+        let auth = AuthMethod::ApiKey {
+            header_name: "Authorization".to_string(),
+            key: format!("Token {}", api_key),
+        };
+        let api_client = ApiClient::new(host, auth)?;
+        Ok(Self { api_client })
+    }
+}
+
+impl ProviderRetry for DeepgramTranscriptionProvider {}
+
+#[async_trait]
+impl TranscriptionProvider for DeepgramTranscriptionProvider {
+    fn default_transcription_model(&self) -> &str {
+        DEEPGRAM_DEFAULT_TRANSCRIPTION_MODEL
+    }
+
+    async fn transcribe(
+        &self,
+        audio: &[u8],
+        mime_type: &str,
+        options: &TranscriptionOptions,
+    ) -> Result<String, ProviderError> {
+        let path = deepgram_listen_path(options);
+
+        let json = self
+            .with_retry(|| async {
+                let response = self
+                    .api_client
+                    .response_post_bytes(&path, audio.to_vec(), mime_type)
+                    .await?;
+                handle_response_openai_compat(response).await
+            })
+            .await?;
+
+        parse_deepgram_transcript(&json)
+    }
+}
+
+fn deepgram_listen_path(options: &TranscriptionOptions) -> String {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    query.append_pair(
+        "model",
+        options
+            .model
+            .as_deref()
+            .unwrap_or(DEEPGRAM_DEFAULT_TRANSCRIPTION_MODEL),
+    );
+    query.append_pair("smart_format", "true");
+    match &options.language {
+        Some(language) => query.append_pair("language", language),
+        None => query.append_pair("detect_language", "true"),
+    };
+    if let Some(prompt) = &options.prompt {
+        query.append_pair("keyterm", prompt);
+    }
+    format!("v1/listen?{}", query.finish())
+}
+
+fn parse_deepgram_transcript(json: &Value) -> Result<String, ProviderError> {
+    json.pointer("/results/channels/0/alternatives/0/transcript")
+        .and_then(|t| t.as_str())
+        .map(|t| t.trim().to_string())
+        .ok_or_else(|| {
+            ProviderError::RequestFailed("Missing transcript in Deepgram response".to_string())
+        })
+}
+
+/// Create a transcription provider by name: `openai`, `groq` or `deepgram`
+pub fn create_transcription_provider(name: &str) -> Result<Arc<dyn TranscriptionProvider>> {
+    let provider: Arc<dyn TranscriptionProvider> = match name {
+        "openai" => Arc::new(WhisperTranscriptionProvider::openai_from_env()?),
+        "groq" => Arc::new(WhisperTranscriptionProvider::groq_from_env()?),
+        "deepgram" => Arc::new(DeepgramTranscriptionProvider::from_env()?),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Provider {} does not support transcription",
+                name
+            ))
+        }
+    };
+    Ok(provider)
+}
+
+fn cache_key(audio: &AudioContent) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    audio.data.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Transcribe audio with the configured transcription provider
+pub async fn transcribe(audio: &AudioContent) -> Result<String, ProviderError> {
+    let name: String = Config::global()
+        .get_param(TRANSCRIPTION_PROVIDER_CONFIG_KEY)
+        .unwrap_or_else(|_| DEFAULT_TRANSCRIPTION_PROVIDER.to_string());
+    let provider = create_transcription_provider(&name).map_err(|e| {
+        ProviderError::Authentication(format!(
+            "Transcription provider {} is not configured: {}",
+            name, e
+        ))
+    })?;
+    provider
+        .transcribe_content(audio, &TranscriptionOptions::from_config())
+        .await
+}
+
+async fn cached_transcript(audio: &AudioContent) -> Result<String, ProviderError> {
+    let key = cache_key(audio);
+    let cached = TRANSCRIPTS.lock().unwrap().get(&key).cloned();
+    if let Some(transcript) = cached {
+        return Ok(transcript);
+    }
+
+    let transcript = transcribe(audio).await?;
+    TRANSCRIPTS.lock().unwrap().put(key, transcript.clone());
+    Ok(transcript)
+}
+
+/// Replace audio content with its transcript. Audio that can't be transcribed is left in place
+/// for the provider format to substitute a placeholder.
+pub async fn transcribe_audio_input(messages: &[Message]) -> Vec<Message> {
+    let mut transcribed = Vec::with_capacity(messages.len());
+    for message in messages {
+        if !message
+            .content
+            .iter()
+            .any(|c| matches!(c, MessageContent::Audio(_)))
+        {
+            transcribed.push(message.clone());
+            continue;
+        }
+
+        let mut message = message.clone();
+        for content in message.content.iter_mut() {
+            let MessageContent::Audio(audio) = content else {
+                continue;
+            };
+            match cached_transcript(audio).await {
+                Ok(transcript) => {
+                    *content = MessageContent::text(format!("[Transcribed audio] {}", transcript));
+                }
+                Err(e) => tracing::warn!("Failed to transcribe audio input: {}", e),
+            }
+        }
+        transcribed.push(message);
+    }
+    transcribed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_transcribe_audio_input_uses_cached_transcript() {
+        let audio = AudioContent {
+            data: "dm9pY2UgbWVzc2FnZQ==".to_string(),
+            mime_type: "audio/wav".to_string(),
+        };
+        TRANSCRIPTS
+            .lock()
+            .unwrap()
+            .put(cache_key(&audio), "hello there".to_string());
+
+        let messages = vec![
+            Message::user().with_text("no audio here"),
+            Message::user()
+                .with_text("listen to this")
+                .with_content(MessageContent::Audio(audio)),
+        ];
+        let transcribed = transcribe_audio_input(&messages).await;
+
+        assert_eq!(transcribed[0], messages[0]);
+        assert_eq!(
+            transcribed[1].content[1].as_text(),
+            Some("[Transcribed audio] hello there")
+        );
+    }
+
+    #[test]
+    fn test_deepgram_listen_path() {
+        let path = deepgram_listen_path(&TranscriptionOptions::default());
+        assert_eq!(
+            path,
+            "v1/listen?model=nova-3&smart_format=true&detect_language=true"
+        );
+
+        let options = TranscriptionOptions::default()
+            .with_model("nova-2")
+            .with_language(Some("en".to_string()));
+        assert_eq!(
+            deepgram_listen_path(&options),
+            "v1/listen?model=nova-2&smart_format=true&language=en"
+        );
+    }
+
+    #[test]
+    fn test_parse_deepgram_transcript() {
+        let json = serde_json::json!({
+            "results": {
+                "channels": [{
+                    "alternatives": [{ "transcript": " hello world ", "confidence": 0.98 }]
+                }]
+            }
+        });
+        assert_eq!(parse_deepgram_transcript(&json).unwrap(), "hello world");
+        assert!(parse_deepgram_transcript(&serde_json::json!({})).is_err());
+    }
+}
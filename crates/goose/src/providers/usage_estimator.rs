@@ -1,8 +1,12 @@
 use crate::conversation::message::Message;
-use crate::providers::base::ProviderUsage;
-use crate::token_counter::create_token_counter;
+use crate::providers::base::{ProviderUsage, Usage};
+use crate::token_counter::{create_token_counter_for_model, TokenCounter};
 use anyhow::Result;
 use rmcp::model::Tool;
+use std::time::{Duration, Instant};
+
+/// How often streaming responses report the tokens they have used so far
+pub const INTERIM_USAGE_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Ensures that ProviderUsage has token counts, estimating them if necessary.
 /// This provides a single place to handle the fallback logic for providers that don't return usage data.
@@ -17,7 +21,7 @@ pub async fn ensure_usage_tokens(
         return Ok(());
     }
 
-    let token_counter = create_token_counter()
+    let token_counter = create_token_counter_for_model(&provider_usage.model)
         .await
         .map_err(|e| anyhow::anyhow!("Failed to create token counter: {}", e))?;
 
@@ -47,11 +51,62 @@ pub async fn ensure_usage_tokens(
     Ok(())
 }
 
+/// Counts the output of a response as it streams in, for [interim](ProviderUsage::interim) usage
+pub struct UsageMeter {
+    model: String,
+    input_tokens: Option<i32>,
+    output_tokens: i32,
+    counter: Option<TokenCounter>,
+    interval: Duration,
+    last_report: Instant,
+}
+
+impl UsageMeter {
+    pub async fn new(model: &str) -> Self {
+        let counter = match create_token_counter_for_model(model).await {
+            Ok(counter) => Some(counter),
+            Err(e) => {
+                tracing::debug!("Not reporting interim usage: {}", e);
+                None
+            }
+        };
+        Self {
+            model: model.to_string(),
+            input_tokens: None,
+            output_tokens: 0,
+            counter,
+            interval: INTERIM_USAGE_INTERVAL,
+            last_report: Instant::now(),
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The prompt's tokens, as reported by the provider
+    pub fn set_input_tokens(&mut self, input_tokens: Option<i32>) {
+        self.input_tokens = input_tokens.or(self.input_tokens);
+    }
+
+    /// Count generated text, returning the usage so far if a report is due
+    pub fn record_output(&mut self, text: &str) -> Option<ProviderUsage> {
+        let counter = self.counter.as_ref()?;
+        self.output_tokens += counter.count_tokens(text) as i32;
+        if self.last_report.elapsed() < self.interval {
+            return None;
+        }
+        self.last_report = Instant::now();
+        let usage = Usage::new(self.input_tokens, Some(self.output_tokens), None);
+        Some(ProviderUsage::new(self.model.clone(), usage).as_interim())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::conversation::message::Message;
-    use crate::providers::base::Usage;
 
     #[tokio::test]
     async fn test_ensure_usage_tokens_already_complete() {
@@ -125,4 +180,23 @@ mod tests {
             usage.usage.input_tokens.unwrap() + usage.usage.output_tokens.unwrap()
         );
     }
+
+    #[tokio::test]
+    async fn test_usage_meter_reports_tokens_so_far() {
+        let mut meter = UsageMeter::new("gpt-4o").await;
+        meter.set_input_tokens(Some(120));
+        // Nothing is due until the interval has passed
+        assert!(meter.record_output("Hello").is_none());
+
+        let mut meter = meter.with_interval(Duration::ZERO);
+        let first = meter.record_output(" there, how are you?").unwrap();
+        assert!(first.interim);
+        assert_eq!(first.usage.input_tokens, Some(120));
+        let second = meter.record_output(" Fine, thanks.").unwrap();
+        assert!(second.usage.output_tokens > first.usage.output_tokens);
+        assert_eq!(
+            second.usage.total_tokens,
+            Some(120 + second.usage.output_tokens.unwrap())
+        );
+    }
 }
@@ -0,0 +1,315 @@
+//! Live usage totals for hosting applications.
+//!
+//! A [`UsageTracker`] accumulates tokens, cost, requests and errors per session, provider and
+//! model. It is a [`ProviderMiddleware`], so registering it records every provider call:
+//!
+//! ```ignore
+//! let tracker = Arc::new(UsageTracker::new());
+//! register_middleware(tracker.clone());
+//! // ...
+//! let snapshot = tracker.snapshot();
+//! println!("{} tokens, ${:.4}", snapshot.totals.total_tokens, snapshot.totals.cost);
+//! ```
+//!
+//! Cost comes from the provider when it reports one and from the pricing table otherwise.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde::Serialize;
+
+use super::base::ProviderUsage;
+use super::errors::ProviderError;
+use super::middleware::{ProviderMiddleware, ProviderRequest};
+use super::pricing::estimate_cost;
+use crate::conversation::message::Message;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct UsageKey {
+    session_id: Option<String>,
+    provider: String,
+    model: String,
+}
+
+/// Counters for a set of provider calls
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub errors: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cached_input_tokens: u64,
+    pub total_tokens: u64,
+    /// Cost in USD of the calls whose cost is known
+    pub cost: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, other: &UsageTotals) {
+        self.requests += other.requests;
+        self.errors += other.errors;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cached_input_tokens += other.cached_input_tokens;
+        self.total_tokens += other.total_tokens;
+        self.cost += other.cost;
+    }
+}
+
+/// Totals for one session, provider and model
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageEntry {
+    pub session_id: Option<String>,
+    pub provider: String,
+    pub model: String,
+    pub totals: UsageTotals,
+}
+
+/// A point-in-time copy of a tracker's counters
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageSnapshot {
+    /// Entries sorted by session, provider and model
+    pub entries: Vec<UsageEntry>,
+    /// Sum of all entries
+    pub totals: UsageTotals,
+}
+
+#[derive(Debug, Default)]
+pub struct UsageTracker {
+    entries: Mutex<HashMap<UsageKey, UsageTotals>>,
+}
+
+impl UsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(
+        &self,
+        session_id: Option<&str>,
+        provider: &str,
+        model: &str,
+        f: impl FnOnce(&mut UsageTotals),
+    ) {
+        let key = UsageKey {
+            session_id: session_id.map(str::to_string),
+            provider: provider.to_string(),
+            model: model.to_string(),
+        };
+        f(self.entries.lock().unwrap().entry(key).or_default());
+    }
+
+    /// Count a request sent to `provider`
+    pub fn record_request(&self, session_id: Option<&str>, provider: &str, model: &str) {
+        self.update(session_id, provider, model, |totals| totals.requests += 1);
+    }
+
+    /// Count a failed request
+    pub fn record_error(&self, session_id: Option<&str>, provider: &str, model: &str) {
+        self.update(session_id, provider, model, |totals| totals.errors += 1);
+    }
+
+    /// Add the tokens and cost of a response
+    pub fn record_usage(&self, session_id: Option<&str>, provider: &str, usage: &ProviderUsage) {
+        let tokens = |count: Option<i32>| count.unwrap_or(0).max(0) as u64;
+        let cost = usage
+            .cost
+            .or_else(|| estimate_cost(provider, &usage.model, &usage.usage));
+        self.update(session_id, provider, &usage.model, |totals| {
+            totals.input_tokens += tokens(usage.usage.input_tokens);
+            totals.output_tokens += tokens(usage.usage.output_tokens);
+            totals.cached_input_tokens += tokens(usage.usage.cached_input_tokens);
+            totals.total_tokens += tokens(usage.usage.total_tokens);
+            totals.cost += cost.unwrap_or(0.0);
+        });
+    }
+
+    /// Totals for every session, provider and model seen so far
+    pub fn snapshot(&self) -> UsageSnapshot {
+        self.snapshot_where(|_| true)
+    }
+
+    /// Totals for one session
+    pub fn session_snapshot(&self, session_id: &str) -> UsageSnapshot {
+        self.snapshot_where(|key| key.session_id.as_deref() == Some(session_id))
+    }
+
+    fn snapshot_where(&self, include: impl Fn(&UsageKey) -> bool) -> UsageSnapshot {
+        let mut entries: Vec<UsageEntry> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(key, _)| include(key))
+            .map(|(key, totals)| UsageEntry {
+                session_id: key.session_id.clone(),
+                provider: key.provider.clone(),
+                model: key.model.clone(),
+                totals: totals.clone(),
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            (&a.session_id, &a.provider, &a.model).cmp(&(&b.session_id, &b.provider, &b.model))
+        });
+
+        let mut totals = UsageTotals::default();
+        for entry in &entries {
+            totals.add(&entry.totals);
+        }
+        UsageSnapshot { entries, totals }
+    }
+
+    /// Clear all counters
+    pub fn reset(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+#[async_trait]
+impl ProviderMiddleware for UsageTracker {
+    async fn on_request(&self, request: &mut ProviderRequest) -> Result<(), ProviderError> {
+        self.record_request(
+            request.session_id.as_deref(),
+            &request.provider,
+            &request.model_config.model_name,
+        );
+        Ok(())
+    }
+
+    async fn on_response(
+        &self,
+        request: &ProviderRequest,
+        _message: &mut Message,
+        usage: &mut ProviderUsage,
+    ) -> Result<(), ProviderError> {
+        self.record_usage(request.session_id.as_deref(), &request.provider, usage);
+        Ok(())
+    }
+
+    async fn on_error(&self, request: &ProviderRequest, error: ProviderError) -> ProviderError {
+        self.record_error(
+            request.session_id.as_deref(),
+            &request.provider,
+            &request.model_config.model_name,
+        );
+        error
+    }
+
+    fn on_stream_chunk(
+        &self,
+        request: &ProviderRequest,
+        _message: &mut Option<Message>,
+        usage: &mut Option<ProviderUsage>,
+    ) -> Result<(), ProviderError> {
+        // Interim usage is a running count that the final usage replaces
+        if let Some(usage) = usage.as_ref().filter(|usage| !usage.interim) {
+            self.record_usage(request.session_id.as_deref(), &request.provider, usage);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{Provider, Usage};
+    use crate::providers::middleware::MiddlewareProvider;
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use futures::TryStreamExt;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_tracks_usage_per_session() {
+        let tracker = Arc::new(UsageTracker::new());
+        let mock = MockProvider::new()
+            .with_response(MockResponse::text("one").with_usage(Usage::new(
+                Some(10),
+                Some(5),
+                Some(15),
+            )))
+            .with_response(MockResponse::stream(["t", "wo"]).with_usage(Usage::new(
+                Some(20),
+                Some(2),
+                Some(22),
+            )))
+            .with_response(MockResponse::error(ProviderError::ServerError(
+                "down".to_string(),
+            )));
+        let provider = MiddlewareProvider::new(
+            Arc::new(mock),
+            vec![tracker.clone() as Arc<dyn ProviderMiddleware>],
+        );
+
+        crate::session_context::with_session_id(Some("s1".to_string()), async {
+            provider.complete("system", &[], &[]).await.unwrap();
+            let _: Vec<_> = provider
+                .stream("system", &[], &[])
+                .await
+                .unwrap()
+                .try_collect()
+                .await
+                .unwrap();
+            assert!(provider.complete("system", &[], &[]).await.is_err());
+        })
+        .await;
+
+        let snapshot = tracker.session_snapshot("s1");
+        assert_eq!(snapshot.entries.len(), 1);
+        let entry = &snapshot.entries[0];
+        assert_eq!(entry.provider, "mock");
+        assert_eq!(entry.model, "mock-model");
+        assert_eq!(entry.totals.requests, 3);
+        assert_eq!(entry.totals.errors, 1);
+        assert_eq!(entry.totals.input_tokens, 30);
+        assert_eq!(entry.totals.output_tokens, 7);
+        assert_eq!(entry.totals.total_tokens, 37);
+
+        assert!(tracker.session_snapshot("s2").entries.is_empty());
+        assert_eq!(tracker.snapshot().totals, snapshot.totals);
+        tracker.reset();
+        assert!(tracker.snapshot().entries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_interim_usage_is_not_counted() {
+        let tracker = Arc::new(UsageTracker::new());
+        let mock = MockProvider::new().with_response(
+            MockResponse::stream(["a", "b", "c"])
+                .with_interim_usage(Usage::new(Some(20), Some(1), Some(21)))
+                .with_usage(Usage::new(Some(20), Some(3), Some(23))),
+        );
+        let provider = MiddlewareProvider::new(
+            Arc::new(mock),
+            vec![tracker.clone() as Arc<dyn ProviderMiddleware>],
+        );
+
+        let _: Vec<_> = provider
+            .stream("system", &[], &[])
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        let totals = tracker.snapshot().totals;
+        assert_eq!(totals.requests, 1);
+        assert_eq!(totals.input_tokens, 20);
+        assert_eq!(totals.output_tokens, 3);
+        assert_eq!(totals.total_tokens, 23);
+    }
+
+    #[test]
+    fn test_reported_cost_is_summed() {
+        let tracker = UsageTracker::new();
+        let usage = ProviderUsage::new("m".to_string(), Usage::new(Some(1), Some(1), Some(2)))
+            .with_cost(Some(0.25));
+        tracker.record_usage(None, "p", &usage);
+        tracker.record_usage(Some("s"), "p", &usage);
+
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.entries.len(), 2);
+        assert_eq!(snapshot.entries[0].session_id, None);
+        assert_eq!(snapshot.totals.cost, 0.5);
+    }
+}
@@ -1,14 +1,21 @@
 use super::base::{MessageStream, Usage};
 use super::errors::GoogleErrorCode;
+use super::stream_event::{EventStream, StreamEvent};
+use super::timeouts::{stream_error, StreamTimeouts};
+use crate::audit::{self, AuditEvent};
 use crate::config::paths::Paths;
+use crate::conversation::message::{AudioContent, DocumentContent};
 use crate::model::ModelConfig;
 use crate::providers::errors::ProviderError;
-use crate::providers::formats::openai::response_to_streaming_message;
+use crate::providers::formats::openai::{response_to_stream_events, response_to_streaming_message};
+use crate::redaction::redact_json;
 use anyhow::{anyhow, Result};
-use async_stream::try_stream;
+use async_stream::{stream, try_stream};
 use base64::Engine;
+use chrono::{DateTime, Utc};
 use futures::TryStreamExt;
 use regex::Regex;
+use reqwest::header::HeaderMap;
 use reqwest::{Response, StatusCode};
 use rmcp::model::{AnnotateAble, ImageContent, RawImageContent};
 use serde::{Deserialize, Serialize};
@@ -171,13 +178,106 @@ pub fn map_http_error_to_provider_error(
 pub async fn handle_status_openai_compat(response: Response) -> Result<Response, ProviderError> {
     let status = response.status();
     if !status.is_success() {
+        let retry_delay = parse_retry_delay_headers(response.headers());
         let body = response.text().await.unwrap_or_default();
         let payload = serde_json::from_str::<Value>(&body).ok();
-        return Err(map_http_error_to_provider_error(status, payload));
+        return Err(map_http_error_to_provider_error(status, payload).with_retry_delay(retry_delay));
     }
     Ok(response)
 }
 
+/// Work out how long to wait before retrying a rate limited request from its response headers.
+///
+/// `retry-after-ms` and `retry-after` take precedence. Otherwise the reset time of each
+/// exhausted limit is used, from OpenAI-style `x-ratelimit-reset-*` durations or Anthropic's
+/// `anthropic-ratelimit-*-reset` timestamps, waiting for the latest one.
+pub fn parse_retry_delay_headers(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+    };
+
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.parse::<f64>().ok()) {
+        return Some(Duration::from_secs_f64(ms.max(0.0) / 1000.0));
+    }
+    if let Some(retry_after) = header("retry-after") {
+        if let Ok(secs) = retry_after.parse::<f64>() {
+            return Some(Duration::from_secs_f64(secs.max(0.0)));
+        }
+        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(retry_after) {
+            return Some(duration_until(date.with_timezone(&chrono::Utc)));
+        }
+    }
+
+    // A limit only explains the 429 if it's exhausted, or if the remaining count isn't reported
+    let exhausted = |remaining: &str| {
+        header(remaining)
+            .and_then(|v| v.parse::<f64>().ok())
+            .is_none_or(|remaining| remaining <= 0.0)
+    };
+
+    let openai_resets = ["requests", "tokens"].into_iter().filter_map(|limit| {
+        if !exhausted(&format!("x-ratelimit-remaining-{}", limit)) {
+            return None;
+        }
+        header(&format!("x-ratelimit-reset-{}", limit)).and_then(parse_reset_duration)
+    });
+
+    let anthropic_resets = ["requests", "tokens", "input-tokens", "output-tokens"]
+        .into_iter()
+        .filter_map(|limit| {
+            if !exhausted(&format!("anthropic-ratelimit-{}-remaining", limit)) {
+                return None;
+            }
+            header(&format!("anthropic-ratelimit-{}-reset", limit))
+                .and_then(|v| chrono::DateTime::parse_from_rfc3339(v).ok())
+                .map(|reset| duration_until(reset.with_timezone(&chrono::Utc)))
+        });
+
+    openai_resets.chain(anthropic_resets).max()
+}
+
+fn duration_until(time: chrono::DateTime<chrono::Utc>) -> Duration {
+    (time - chrono::Utc::now())
+        .to_std()
+        .unwrap_or(Duration::ZERO)
+}
+
+/// Parse a reset duration such as `20ms`, `1.5s` or `6m0s`
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    if let Ok(secs) = value.parse::<f64>() {
+        return Some(Duration::from_secs_f64(secs.max(0.0)));
+    }
+
+    let mut total = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let (number, after) = rest.split_at(number_end);
+        let number: f64 = number.parse().ok()?;
+        rest = after;
+
+        let unit_end = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let (unit, after) = rest.split_at(unit_end);
+        let multiplier = match unit {
+            "ms" => 0.001,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => return None,
+        };
+        rest = after;
+        total += number * multiplier;
+    }
+    Some(Duration::from_secs_f64(total))
+}
+
 pub async fn handle_response_openai_compat(response: Response) -> Result<Value, ProviderError> {
     let response = handle_status_openai_compat(response).await?;
 
@@ -196,19 +296,50 @@ pub fn stream_openai_compat(
         let stream_reader = StreamReader::new(stream);
         let framed = FramedRead::new(stream_reader, LinesCodec::new())
             .map_err(anyhow::Error::from);
+        let framed = StreamTimeouts::from_config().apply(framed);
 
         let message_stream = response_to_streaming_message(framed);
         pin!(message_stream);
         while let Some(message) = message_stream.next().await {
-            let (message, usage) = message.map_err(|e|
-                ProviderError::RequestFailed(format!("Stream decode error: {}", e))
-            )?;
+            let (message, usage) = message.map_err(stream_error)?;
             log.write(&message, usage.as_ref().map(|f| f.usage).as_ref())?;
             yield (message, usage);
         }
     }))
 }
 
+/// The events of an OpenAI compatible streaming response, logging each as it arrives
+pub fn stream_events_openai_compat(response: Response, mut log: RequestLog) -> EventStream {
+    let stream = response.bytes_stream().map_err(io::Error::other);
+
+    Box::pin(stream! {
+        let stream_reader = StreamReader::new(stream);
+        let framed = FramedRead::new(stream_reader, LinesCodec::new())
+            .map_err(anyhow::Error::from);
+        let framed = StreamTimeouts::from_config().apply(framed);
+
+        let events = response_to_stream_events(framed);
+        pin!(events);
+        while let Some(event) = events.next().await {
+            let event = event.unwrap_or_else(|e| StreamEvent::Error(stream_error(e)));
+            let usage = match &event {
+                StreamEvent::Usage(usage) => Some(usage.usage),
+                _ => None,
+            };
+            if let Err(e) = log.write(&event, usage.as_ref()) {
+                yield StreamEvent::Error(e.into());
+                return;
+            }
+            let failed = matches!(event, StreamEvent::Error(_));
+            yield event;
+            if failed {
+                return;
+            }
+        }
+        yield StreamEvent::Done;
+    })
+}
+
 pub fn is_google_model(payload: &Value) -> bool {
     payload
         .get("model")
@@ -279,6 +410,7 @@ fn parse_google_retry_delay(payload: &Value) -> Option<Duration> {
 /// - `Err(ProviderError)`: Describes the failure reason.
 pub async fn handle_response_google_compat(response: Response) -> Result<Value, ProviderError> {
     let status = response.status();
+    let header_retry_delay = parse_retry_delay_headers(response.headers());
     let payload: Option<Value> = response.json().await.ok();
     let final_status = get_google_final_status(status, payload.as_ref());
 
@@ -305,7 +437,10 @@ pub async fn handle_response_google_compat(response: Response) -> Result<Value,
             Err(ProviderError::RequestFailed(format!("Request failed with status: {}. Message: {}", final_status, error_msg)))
         }
         StatusCode::TOO_MANY_REQUESTS => {
-            let retry_delay = payload.as_ref().and_then(parse_google_retry_delay);
+            let retry_delay = payload
+                .as_ref()
+                .and_then(parse_google_retry_delay)
+                .or(header_retry_delay);
             Err(ProviderError::RateLimitExceeded {
                 details: format!("{:?}", payload),
                 retry_delay,
@@ -346,6 +481,14 @@ pub fn get_model(data: &Value) -> String {
     }
 }
 
+/// The backend configuration fingerprint OpenAI-compatible APIs return alongside
+/// seeded requests, used to tell whether two runs can be expected to match
+pub fn get_system_fingerprint(data: &Value) -> Option<String> {
+    data.get("system_fingerprint")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+}
+
 /// Check if a file is actually an image by examining its magic bytes
 fn is_image_file(path: &Path) -> bool {
     if let Ok(mut file) = std::fs::File::open(path) {
@@ -434,6 +577,72 @@ pub fn load_image_file(path: &str) -> Result<ImageContent, ProviderError> {
     .no_annotation())
 }
 
+/// Convert a local PDF or text file to base64 encoded DocumentContent
+pub fn load_document_file(path: &str) -> Result<DocumentContent, ProviderError> {
+    let path = Path::new(path);
+
+    let mime_type = match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .as_deref()
+    {
+        Some("pdf") => "application/pdf",
+        Some("txt") => "text/plain",
+        Some("md") | Some("markdown") => "text/markdown",
+        Some("csv") => "text/csv",
+        Some("html") | Some("htm") => "text/html",
+        _ => {
+            return Err(ProviderError::RequestFailed(
+                "Unsupported document format".to_string(),
+            ))
+        }
+    };
+
+    let bytes = std::fs::read(path).map_err(|e| {
+        ProviderError::RequestFailed(format!("Failed to read document file: {}", e))
+    })?;
+    if mime_type == "application/pdf" && !bytes.starts_with(b"%PDF-") {
+        return Err(ProviderError::RequestFailed(
+            "File is not a valid PDF".to_string(),
+        ));
+    }
+
+    let document = DocumentContent {
+        data: base64::prelude::BASE64_STANDARD.encode(&bytes),
+        mime_type: mime_type.to_string(),
+        name: path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(|n| n.to_string()),
+    };
+    document
+        .validate()
+        .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
+    Ok(document)
+}
+
+/// Text stand-in for a document the provider can't accept natively: the contents of a text
+/// document, or a note that a binary document was left out
+pub fn document_to_text(document: &DocumentContent) -> String {
+    let name = document.name.as_deref().unwrap_or("document");
+    match document.as_text() {
+        Some(text) => format!("<document name=\"{}\">\n{}\n</document>", name, text),
+        None => format!(
+            "[{} ({}) was attached but this provider does not accept {} documents]",
+            name, document.mime_type, document.mime_type
+        ),
+    }
+}
+
+/// Text stand-in for audio that reached a provider without being transcribed
+pub fn audio_placeholder(audio: &AudioContent) -> String {
+    format!(
+        "[Audio ({}) was attached but could not be sent to this model]",
+        audio.mime_type
+    )
+}
+
 pub fn unescape_json_values(value: &Value) -> Value {
     match value {
         Value::Object(map) => {
@@ -466,6 +675,36 @@ pub fn unescape_json_values(value: &Value) -> Value {
 pub struct RequestLog {
     writer: Option<BufWriter<File>>,
     temp_path: PathBuf,
+    audit: Option<AuditedRequest>,
+}
+
+/// What the audit log records of a request, gathered until the log is finished
+struct AuditedRequest {
+    session_id: Option<String>,
+    request_id: String,
+    model: String,
+    started_at: DateTime<Utc>,
+    request: Value,
+    response: Vec<Value>,
+    usage: Option<Usage>,
+    error: Option<String>,
+}
+
+impl AuditedRequest {
+    fn record(self) {
+        audit::record(
+            self.session_id,
+            AuditEvent::ProviderRequest {
+                request_id: self.request_id,
+                model: self.model,
+                started_at: self.started_at,
+                request: self.request,
+                response: self.response,
+                usage: self.usage,
+                error: self.error,
+            },
+        );
+    }
 }
 
 pub const LOGS_TO_KEEP: usize = 10;
@@ -489,15 +728,28 @@ impl RequestLog {
                 .open(&temp_path)?,
         );
 
-        let data = serde_json::json!({
+        let mut data = serde_json::json!({
             "model_config": model_config,
             "input": payload,
         });
+        redact_json(&mut data);
         writeln!(writer, "{}", serde_json::to_string(&data)?)?;
 
+        let audit = audit::enabled().then(|| AuditedRequest {
+            session_id: crate::session_context::current_session_id(),
+            request_id: request_id.to_string(),
+            model: model_config.model_name.clone(),
+            started_at: Utc::now(),
+            request: data["input"].clone(),
+            response: Vec::new(),
+            usage: None,
+            error: None,
+        });
+
         Ok(Self {
             writer: Some(writer),
             temp_path,
+            audit,
         })
     }
 
@@ -506,7 +758,9 @@ impl RequestLog {
             .writer
             .as_mut()
             .ok_or_else(|| anyhow!("logger is finished"))?;
-        writeln!(writer, "{}", serde_json::to_string(line)?)?;
+        let mut line = line.clone();
+        redact_json(&mut line);
+        writeln!(writer, "{}", serde_json::to_string(&line)?)?;
         Ok(())
     }
 
@@ -514,8 +768,12 @@ impl RequestLog {
     where
         E: Display,
     {
+        let error = format!("{}", error);
+        if let Some(audit) = self.audit.as_mut() {
+            audit.error = Some(error.clone());
+        }
         self.write_json(&serde_json::json!({
-            "error": format!("{}", error),
+            "error": error,
         }))
     }
 
@@ -523,13 +781,23 @@ impl RequestLog {
     where
         Payload: Serialize,
     {
-        self.write_json(&serde_json::json!({
+        let line = serde_json::json!({
             "data": data,
             "usage": usage,
-        }))
+        });
+        if let Some(audit) = self.audit.as_mut() {
+            audit.response.push(line["data"].clone());
+            if usage.is_some() {
+                audit.usage = usage.copied();
+            }
+        }
+        self.write_json(&line)
     }
 
     fn finish(&mut self) -> Result<()> {
+        if let Some(audit) = self.audit.take() {
+            audit.record();
+        }
         if let Some(mut writer) = self.writer.take() {
             writer.flush()?;
             let logs_dir = Paths::in_state_dir("logs");
@@ -715,6 +983,35 @@ mod tests {
             .contains("Unsupported image format"));
     }
 
+    #[test]
+    fn test_load_document_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let pdf_path = temp_dir.path().join("report.pdf");
+        std::fs::write(&pdf_path, b"%PDF-1.7 fake").unwrap();
+        let document = load_document_file(pdf_path.to_str().unwrap()).unwrap();
+        assert_eq!(document.mime_type, "application/pdf");
+        assert_eq!(document.name.as_deref(), Some("report.pdf"));
+        assert!(document_to_text(&document).contains("does not accept application/pdf"));
+
+        let fake_pdf_path = temp_dir.path().join("fake.pdf");
+        std::fs::write(&fake_pdf_path, b"not a pdf").unwrap();
+        assert!(load_document_file(fake_pdf_path.to_str().unwrap()).is_err());
+
+        let notes_path = temp_dir.path().join("notes.md");
+        std::fs::write(&notes_path, b"# Notes").unwrap();
+        let document = load_document_file(notes_path.to_str().unwrap()).unwrap();
+        assert_eq!(document.mime_type, "text/markdown");
+        assert_eq!(
+            document_to_text(&document),
+            "<document name=\"notes.md\">\n# Notes\n</document>"
+        );
+
+        let zip_path = temp_dir.path().join("archive.zip");
+        std::fs::write(&zip_path, b"PK").unwrap();
+        assert!(load_document_file(zip_path.to_str().unwrap()).is_err());
+    }
+
     #[test]
     fn test_sanitize_function_name() {
         assert_eq!(sanitize_function_name("hello-world"), "hello-world");
@@ -948,4 +1245,83 @@ mod tests {
             Some(Duration::from_secs(42))
         );
     }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_parse_retry_delay_headers() {
+        assert_eq!(parse_retry_delay_headers(&HeaderMap::new()), None);
+        assert_eq!(
+            parse_retry_delay_headers(&headers(&[("retry-after", "7")])),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(
+            parse_retry_delay_headers(&headers(&[
+                ("retry-after", "7"),
+                ("retry-after-ms", "1500")
+            ])),
+            Some(Duration::from_millis(1500))
+        );
+
+        // Only exhausted limits count, and the latest reset wins
+        assert_eq!(
+            parse_retry_delay_headers(&headers(&[
+                ("x-ratelimit-remaining-requests", "0"),
+                ("x-ratelimit-reset-requests", "6m0s"),
+                ("x-ratelimit-remaining-tokens", "1000"),
+                ("x-ratelimit-reset-tokens", "1h"),
+            ])),
+            Some(Duration::from_secs(360))
+        );
+
+        let reset = (chrono::Utc::now() + chrono::Duration::seconds(30)).to_rfc3339();
+        let delay = parse_retry_delay_headers(&headers(&[
+            ("anthropic-ratelimit-tokens-remaining", "0"),
+            ("anthropic-ratelimit-tokens-reset", &reset),
+        ]))
+        .unwrap();
+        assert!(delay > Duration::from_secs(25) && delay <= Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_parse_reset_duration() {
+        assert_eq!(
+            parse_reset_duration("20ms"),
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(
+            parse_reset_duration("1m30.5s"),
+            Some(Duration::from_secs_f64(90.5))
+        );
+        assert_eq!(parse_reset_duration("2"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_reset_duration("soon"), None);
+    }
+
+    #[test]
+    fn test_with_retry_delay() {
+        let rate_limited = |retry_delay| ProviderError::RateLimitExceeded {
+            details: "slow down".to_string(),
+            retry_delay,
+        };
+        assert_eq!(
+            rate_limited(None).with_retry_delay(Some(Duration::from_secs(5))),
+            rate_limited(Some(Duration::from_secs(5)))
+        );
+        assert_eq!(
+            rate_limited(Some(Duration::from_secs(1)))
+                .with_retry_delay(Some(Duration::from_secs(5))),
+            rate_limited(Some(Duration::from_secs(1)))
+        );
+        assert_eq!(
+            ProviderError::ServerError("oops".to_string())
+                .with_retry_delay(Some(Duration::from_secs(5))),
+            ProviderError::ServerError("oops".to_string())
+        );
+    }
 }
@@ -1,6 +1,8 @@
 use crate::recipe::read_recipe_file_content::read_parameter_file_content;
 use crate::recipe::template_recipe::render_recipe_content_with_params;
-use crate::recipe::validate_recipe::validate_recipe_template_from_content;
+use crate::recipe::validate_recipe::{
+    validate_parameter_values, validate_recipe_template_from_content,
+};
 use crate::recipe::{
     Recipe, RecipeParameter, RecipeParameterInputType, RecipeParameterRequirement,
     BUILT_IN_RECIPE_DIR_PARAM,
@@ -13,6 +15,8 @@ use std::path::Path;
 pub enum RecipeError {
     #[error("Missing required parameters: {parameters:?}")]
     MissingParams { parameters: Vec<String> },
+    #[error("Invalid parameter values: {}", errors.join("; "))]
+    InvalidParams { errors: Vec<String> },
     #[error("Template rendering failed: {source}")]
     TemplateRendering { source: anyhow::Error },
     #[error("Recipe parsing failed: {source}")]
@@ -34,6 +38,14 @@ where
         validate_recipe_template_from_content(&recipe_content, Some(recipe_dir_str.clone()))?
             .parameters;
 
+    let errors = validate_parameter_values(
+        recipe_parameters.as_deref().unwrap_or_default(),
+        &params.iter().cloned().collect(),
+    );
+    if !errors.is_empty() {
+        return Err(RecipeError::InvalidParams { errors }.into());
+    }
+
     let (params_for_template, missing_params) =
         apply_values_to_parameters(&params, recipe_parameters, &recipe_dir_str, user_prompt_fn)?;
 
@@ -57,7 +69,10 @@ where
 {
     let (rendered_content, missing_params) =
         render_recipe_template(recipe_content, recipe_dir, params.clone(), user_prompt_fn)
-            .map_err(|source| RecipeError::TemplateRendering { source })?;
+            .map_err(|source| match source.downcast::<RecipeError>() {
+                Ok(error) => error,
+                Err(source) => RecipeError::TemplateRendering { source },
+            })?;
 
     if !missing_params.is_empty() {
         return Err(RecipeError::MissingParams {
@@ -118,6 +118,30 @@ fn test_build_recipe_from_template_success() {
     assert_eq!(param.description, "A test parameter");
 }
 
+#[test]
+fn test_build_recipe_from_template_invalid_parameter_values() {
+    let instructions_and_parameters = r#"
+                "instructions": "Retry {{ attempts }} times",
+                "parameters": [
+                    {
+                        "key": "attempts",
+                        "input_type": "number",
+                        "requirement": "required",
+                        "description": "How many times to retry"
+                    }
+                ]"#;
+
+    let (_temp_dir, recipe_content, recipe_dir) = setup_recipe_file(instructions_and_parameters);
+
+    let params = vec![("attempts".to_string(), "a few".to_string())];
+    match build_recipe_from_template(recipe_content, &recipe_dir, params, NO_USER_PROMPT) {
+        Err(RecipeError::InvalidParams { errors }) => {
+            assert_eq!(errors, vec!["attempts must be a number, not 'a few'"]);
+        }
+        _ => panic!("Expected RecipeError::InvalidParams"),
+    }
+}
+
 #[test]
 fn test_build_recipe_from_template_success_variable_in_prompt() {
     let instructions_and_parameters = r#"
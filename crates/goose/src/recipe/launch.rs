@@ -0,0 +1,129 @@
+//! Starting agent sessions from recipes.
+//!
+//! [`launch_recipe`] does for any frontend what `goose run --recipe` and the desktop's recipe
+//! deeplinks do: it fills in the recipe's parameters, creates a session that remembers the
+//! recipe, and sets the agent up with the recipe's model, extensions, sub-recipes, response
+//! schema and instructions, and runs the recipe's steps. The caller then sends the returned
+//! prompt, if any, as the first message.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::agents::{Agent, TaskConfig};
+use crate::config::Config;
+use crate::model::ModelConfig;
+use crate::providers;
+use crate::recipe::build_recipe::build_recipe_from_template;
+use crate::recipe::steps::{prompt_with_step_outputs, run_recipe_steps, StepOutput};
+use crate::recipe::Recipe;
+use crate::session::{Session, SessionManager, SessionType};
+
+/// A session started by [`launch_recipe`]
+pub struct RecipeLaunch {
+    pub session: Session,
+    /// The recipe with its parameters filled in
+    pub recipe: Recipe,
+    /// The message to start the session with, if the recipe has one, after the outputs of
+    /// the recipe's steps
+    pub prompt: Option<String>,
+    /// What the recipe's steps came back with, in the order they ran
+    pub step_outputs: Vec<StepOutput>,
+}
+
+/// Start a session in `working_dir` running `recipe` with `params`, setting up `agent` for it.
+/// Relative paths in the recipe, such as those of sub-recipes, are resolved against
+/// `recipe_dir`. Fails if parameters are missing or their values don't fit their types, or if
+/// one of the recipe's steps fails.
+pub async fn launch_recipe(
+    agent: &Agent,
+    recipe: &Recipe,
+    recipe_dir: &Path,
+    params: Vec<(String, String)>,
+    working_dir: PathBuf,
+) -> Result<RecipeLaunch> {
+    let rendered = build_recipe_from_template(
+        recipe.to_yaml()?,
+        recipe_dir,
+        params.clone(),
+        None::<fn(&str, &str) -> Result<String>>,
+    )?;
+
+    let session =
+        SessionManager::create_session(working_dir, rendered.title.clone(), SessionType::User)
+            .await?;
+    SessionManager::update_session(&session.id)
+        .recipe(Some(recipe.clone()))
+        .user_recipe_values(Some(params.into_iter().collect::<HashMap<_, _>>()))
+        .apply()
+        .await?;
+
+    let config = Config::global();
+    let settings = rendered.settings.as_ref();
+    let provider_name = match settings.and_then(|settings| settings.goose_provider.clone()) {
+        Some(provider_name) => provider_name,
+        None => config.get_goose_provider()?,
+    };
+    let model_name = match settings.and_then(|settings| settings.goose_model.clone()) {
+        Some(model_name) => model_name,
+        None => config.get_goose_model()?,
+    };
+    let model_config = ModelConfig::new(&model_name)?
+        .with_temperature(settings.and_then(|settings| settings.temperature));
+    let provider = providers::create(&provider_name, model_config)
+        .await
+        .with_context(|| format!("Failed to create the recipe's provider {}", provider_name))?;
+    agent.update_provider(provider, &session.id).await?;
+
+    let enabled = agent.list_extensions().await;
+    for extension in rendered.extensions.clone().unwrap_or_default() {
+        if enabled.contains(&extension.name()) {
+            continue;
+        }
+        let name = extension.name();
+        agent
+            .attach_extension(extension, &session.id)
+            .await
+            .with_context(|| format!("Failed to add the recipe's extension {}", name))?;
+    }
+
+    agent
+        .apply_recipe_components(
+            rendered.sub_recipes.clone(),
+            rendered.response.clone(),
+            true,
+        )
+        .await;
+    if let Some(instructions) = &rendered.instructions {
+        agent.extend_system_prompt(instructions.clone()).await;
+    }
+
+    let step_outputs = if rendered
+        .steps
+        .as_ref()
+        .is_some_and(|steps| !steps.is_empty())
+    {
+        let task_config = TaskConfig::new(
+            agent.provider().await?,
+            &session.id,
+            &session.working_dir,
+            agent.get_extension_configs().await,
+        );
+        run_recipe_steps(&rendered, task_config, session.working_dir.clone(), None).await?
+    } else {
+        Vec::new()
+    };
+
+    let session = SessionManager::get_session(&session.id, false).await?;
+    let prompt = rendered
+        .prompt
+        .as_deref()
+        .map(|prompt| prompt_with_step_outputs(prompt, &step_outputs));
+    Ok(RecipeLaunch {
+        session,
+        recipe: rendered,
+        prompt,
+        step_outputs,
+    })
+}
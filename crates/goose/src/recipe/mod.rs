@@ -14,9 +14,11 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 pub mod build_recipe;
+pub mod launch;
 pub mod local_recipes;
 pub mod read_recipe_file_content;
 mod recipe_extension_adapter;
+pub mod steps;
 pub mod template_recipe;
 pub mod validate_recipe;
 pub mod yaml_format_utils;
@@ -71,6 +73,9 @@ pub struct Recipe {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sub_recipes: Option<Vec<SubRecipe>>, // sub-recipes for the recipe
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub steps: Option<Vec<RecipeStep>>, // sub-recipes to run in order before the recipe starts
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub retry: Option<RetryConfig>,
 }
@@ -114,6 +119,29 @@ pub struct SubRecipe {
     pub description: Option<String>,
 }
 
+/// A sub-recipe run as a step of its parent recipe; see [`steps`](crate::recipe::steps)
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct RecipeStep {
+    /// Name of the sub-recipe to run, from the recipe's `sub_recipes`
+    pub sub_recipe: String,
+    /// Name later steps use to refer to this step's output; the sub-recipe's name if not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Parameter values for the sub-recipe, on top of those of its `sub_recipes` entry
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_value_map_as_string"
+    )]
+    pub values: Option<HashMap<String, String>>,
+}
+
+impl RecipeStep {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.sub_recipe)
+    }
+}
+
 fn deserialize_value_map_as_string<'de, D>(
     deserializer: D,
 ) -> Result<Option<HashMap<String, String>>, D::Error>
@@ -209,6 +237,7 @@ pub struct RecipeBuilder {
     parameters: Option<Vec<RecipeParameter>>,
     response: Option<Response>,
     sub_recipes: Option<Vec<SubRecipe>>,
+    steps: Option<Vec<RecipeStep>>,
     retry: Option<RetryConfig>,
 }
 
@@ -254,6 +283,7 @@ impl Recipe {
             parameters: None,
             response: None,
             sub_recipes: None,
+            steps: None,
             retry: None,
         }
     }
@@ -352,6 +382,11 @@ impl RecipeBuilder {
         self
     }
 
+    pub fn steps(mut self, steps: Vec<RecipeStep>) -> Self {
+        self.steps = Some(steps);
+        self
+    }
+
     pub fn retry(mut self, retry: RetryConfig) -> Self {
         self.retry = Some(retry);
         self
@@ -378,6 +413,7 @@ impl RecipeBuilder {
             parameters: self.parameters,
             response: self.response,
             sub_recipes: self.sub_recipes,
+            steps: self.steps,
             retry: self.retry,
         })
     }
@@ -716,6 +752,7 @@ isGlobal: true"#;
             parameters: None,
             response: None,
             sub_recipes: None,
+            steps: None,
             retry: None,
         };
 
@@ -1,4 +1,4 @@
-use crate::agents::extension::{Envs, ExtensionConfig};
+use crate::agents::extension::{Envs, ExtensionConfig, WasmCapabilities};
 use rmcp::model::Tool;
 use serde::de::Deserializer;
 use serde::Deserialize;
@@ -104,6 +104,24 @@ enum RecipeExtensionConfigInternal {
         #[serde(default)]
         available_tools: Vec<String>,
     },
+    #[serde(rename = "wasm")]
+    Wasm {
+        name: String,
+        #[serde(default)]
+        description: Option<String>,
+        path: String,
+        #[serde(default)]
+        capabilities: WasmCapabilities,
+        #[serde(default)]
+        envs: Envs,
+        #[serde(default)]
+        env_keys: Vec<String>,
+        timeout: Option<u64>,
+        #[serde(default)]
+        bundled: Option<bool>,
+        #[serde(default)]
+        available_tools: Vec<String>,
+    },
 }
 
 macro_rules! map_recipe_extensions {
@@ -175,6 +193,15 @@ impl From<RecipeExtensionConfigInternal> for ExtensionConfig {
                 timeout,
                 dependencies,
                 available_tools
+            },
+            Wasm {
+                path,
+                capabilities,
+                envs,
+                env_keys,
+                timeout,
+                bundled,
+                available_tools
             }
         )
     }
@@ -280,4 +307,44 @@ mod tests {
             other => panic!("unexpected extension variant: {:?}", other),
         }
     }
+
+    #[test]
+    fn wasm_extension_capabilities_default_to_none() {
+        let wrapper: Wrapper = serde_json::from_value(json!({
+            "extensions": [
+                {
+                    "type": "wasm",
+                    "name": "sandboxed",
+                    "path": "~/extensions/sandboxed.wasm",
+                },
+                {
+                    "type": "wasm",
+                    "name": "fetcher",
+                    "path": "fetcher.wasm",
+                    "capabilities": {"read_dirs": ["/data"], "network": true},
+                },
+            ]
+        }))
+        .expect("failed to deserialize wasm extensions");
+
+        let extensions = wrapper.extensions.expect("expected extensions");
+        match (&extensions[0], &extensions[1]) {
+            (
+                ExtensionConfig::Wasm {
+                    path, capabilities, ..
+                },
+                ExtensionConfig::Wasm {
+                    capabilities: granted,
+                    ..
+                },
+            ) => {
+                assert_eq!(path, "~/extensions/sandboxed.wasm");
+                assert_eq!(capabilities, &WasmCapabilities::default());
+                assert_eq!(granted.read_dirs, vec!["/data".to_string()]);
+                assert!(granted.write_dirs.is_empty());
+                assert!(granted.network);
+            }
+            other => panic!("unexpected extension variants: {:?}", other),
+        }
+    }
 }
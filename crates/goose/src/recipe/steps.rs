@@ -0,0 +1,318 @@
+//! Composing recipes from other recipes.
+//!
+//! A recipe's `steps` run some of its sub-recipes one after another before the recipe itself
+//! starts, so a workflow can be built from reusable recipes instead of asking the model to call
+//! them. Each step runs in a sub-session of its own and sees none of the other steps'
+//! conversations; what it passes on is its final message. A step's values can use the output
+//! of an earlier step as `${steps.<name>}`.
+//!
+//! ```yaml
+//! sub_recipes:
+//!   - name: research
+//!     path: ./research.yaml
+//!   - name: write_report
+//!     path: ./write_report.yaml
+//! steps:
+//!   - sub_recipe: research
+//!     values:
+//!       topic: "{{ topic }}"
+//!   - sub_recipe: write_report
+//!     values:
+//!       findings: "${steps.research}"
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use tokio_util::sync::CancellationToken;
+
+use crate::agents::subagent_handler::run_complete_subagent_task;
+use crate::agents::TaskConfig;
+use crate::recipe::build_recipe::build_recipe_from_template;
+use crate::recipe::local_recipes::load_local_recipe_file;
+use crate::recipe::{Recipe, RecipeStep, SubRecipe};
+use crate::session::{SessionManager, SessionType};
+
+const STEP_REFERENCE_START: &str = "${steps.";
+
+/// The final message of a step that has run
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepOutput {
+    pub name: String,
+    pub output: String,
+}
+
+/// Names of the steps `value` refers to, in order
+fn step_references(value: &str) -> Result<Vec<&str>> {
+    let mut references = Vec::new();
+    let mut rest = value;
+    while let Some((_, after)) = rest.split_once(STEP_REFERENCE_START) {
+        let (name, after) = after
+            .split_once('}')
+            .ok_or_else(|| anyhow!("Unclosed step reference in '{}'", value))?;
+        references.push(name.trim());
+        rest = after;
+    }
+    Ok(references)
+}
+
+/// `value` with its step references replaced by the outputs of those steps
+fn resolve_step_references(value: &str, outputs: &[StepOutput]) -> Result<String> {
+    let mut resolved = String::new();
+    let mut rest = value;
+    while let Some((before, after)) = rest.split_once(STEP_REFERENCE_START) {
+        resolved.push_str(before);
+        let (name, after) = after
+            .split_once('}')
+            .ok_or_else(|| anyhow!("Unclosed step reference in '{}'", value))?;
+        let name = name.trim();
+        let output = outputs
+            .iter()
+            .find(|output| output.name == name)
+            .ok_or_else(|| {
+                anyhow!(
+                    "'{}' refers to step '{}', which hasn't run; steps can only use the output of earlier steps",
+                    value,
+                    name
+                )
+            })?;
+        resolved.push_str(&output.output);
+        rest = after;
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
+
+/// Check that `recipe`'s steps name sub-recipes it has, have distinct names, and only use the
+/// output of steps before them
+pub fn validate_steps(recipe: &Recipe) -> Result<()> {
+    let Some(steps) = &recipe.steps else {
+        return Ok(());
+    };
+    let sub_recipes: HashSet<&str> = recipe
+        .sub_recipes
+        .iter()
+        .flatten()
+        .map(|sub_recipe| sub_recipe.name.as_str())
+        .collect();
+
+    let mut earlier: HashSet<&str> = HashSet::new();
+    for step in steps {
+        if !sub_recipes.contains(step.sub_recipe.as_str()) {
+            return Err(anyhow!(
+                "Step '{}' runs sub-recipe '{}', which isn't in the recipe's sub_recipes",
+                step.name(),
+                step.sub_recipe
+            ));
+        }
+        for value in step.values.iter().flat_map(|values| values.values()) {
+            for reference in step_references(value)? {
+                if !earlier.contains(reference) {
+                    return Err(anyhow!(
+                        "Step '{}' uses the output of '{}', which isn't an earlier step",
+                        step.name(),
+                        reference
+                    ));
+                }
+            }
+        }
+        if !earlier.insert(step.name()) {
+            return Err(anyhow!(
+                "There is more than one step named '{}'; give them distinct names",
+                step.name()
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn build_step_recipe(
+    step: &RecipeStep,
+    sub_recipe: &SubRecipe,
+    outputs: &[StepOutput],
+) -> Result<Recipe> {
+    let mut values: HashMap<String, String> = sub_recipe.values.clone().unwrap_or_default();
+    for (key, value) in step.values.iter().flatten() {
+        values.insert(key.clone(), resolve_step_references(value, outputs)?);
+    }
+
+    let recipe_file = load_local_recipe_file(&sub_recipe.path)
+        .with_context(|| format!("Failed to load sub-recipe '{}'", sub_recipe.name))?;
+    build_recipe_from_template(
+        recipe_file.content,
+        &recipe_file.parent_dir,
+        values.into_iter().collect(),
+        None::<fn(&str, &str) -> Result<String>>,
+    )
+    .with_context(|| format!("Failed to build sub-recipe '{}'", sub_recipe.name))
+}
+
+/// Run `recipe`'s steps in order, each in a sub-session in `working_dir`, stopping at the
+/// first that fails
+pub async fn run_recipe_steps(
+    recipe: &Recipe,
+    task_config: TaskConfig,
+    working_dir: PathBuf,
+    cancellation_token: Option<CancellationToken>,
+) -> Result<Vec<StepOutput>> {
+    validate_steps(recipe)?;
+    let sub_recipes: HashMap<&str, &SubRecipe> = recipe
+        .sub_recipes
+        .iter()
+        .flatten()
+        .map(|sub_recipe| (sub_recipe.name.as_str(), sub_recipe))
+        .collect();
+
+    let mut outputs = Vec::new();
+    for step in recipe.steps.iter().flatten() {
+        if cancellation_token
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+        {
+            return Err(anyhow!("Cancelled before step '{}'", step.name()));
+        }
+        let step_recipe = build_step_recipe(step, sub_recipes[step.sub_recipe.as_str()], &outputs)
+            .with_context(|| format!("Step '{}' failed", step.name()))?;
+        let session = SessionManager::create_session(
+            working_dir.clone(),
+            format!("Recipe step: {}", step.name()),
+            SessionType::SubAgent,
+        )
+        .await?;
+        tracing::info!(
+            "Running recipe step {} in session {}",
+            step.name(),
+            session.id
+        );
+        let output = run_complete_subagent_task(
+            step_recipe,
+            task_config.clone(),
+            true,
+            session.id,
+            cancellation_token.clone(),
+        )
+        .await
+        .with_context(|| format!("Step '{}' failed", step.name()))?;
+        outputs.push(StepOutput {
+            name: step.name().to_string(),
+            output,
+        });
+    }
+    Ok(outputs)
+}
+
+/// `prompt` preceded by the outputs of the steps that ran before it
+pub fn prompt_with_step_outputs(prompt: &str, outputs: &[StepOutput]) -> String {
+    if outputs.is_empty() {
+        return prompt.to_string();
+    }
+    let mut combined = String::from("These steps of the recipe have already run:\n\n");
+    for output in outputs {
+        combined.push_str(&format!(
+            "<step name=\"{}\">\n{}\n</step>\n\n",
+            output.name, output.output
+        ));
+    }
+    combined.push_str(prompt);
+    combined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(steps: &str) -> Recipe {
+        Recipe::from_content(&format!(
+            r#"
+title: Report
+description: Research a topic and write a report on it
+prompt: Review the report
+sub_recipes:
+  - name: research
+    path: ./research.yaml
+  - name: write_report
+    path: ./write_report.yaml
+steps:
+{}
+"#,
+            steps
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_steps() {
+        let valid = recipe(
+            r#"
+  - sub_recipe: research
+  - sub_recipe: write_report
+    values:
+      findings: "${steps.research}"
+      count: 3
+"#,
+        );
+        assert!(validate_steps(&valid).is_ok());
+        assert_eq!(
+            valid.steps.as_ref().unwrap()[1].values.as_ref().unwrap()["count"],
+            "3"
+        );
+
+        let errors = [
+            (
+                r#"  - sub_recipe: publish"#,
+                "isn't in the recipe's sub_recipes",
+            ),
+            (
+                r#"
+  - sub_recipe: write_report
+    values:
+      findings: "${steps.research}"
+  - sub_recipe: research
+"#,
+                "isn't an earlier step",
+            ),
+            (
+                r#"
+  - sub_recipe: research
+  - sub_recipe: research
+"#,
+                "more than one step named 'research'",
+            ),
+        ];
+        for (steps, error) in errors {
+            let message = validate_steps(&recipe(steps)).unwrap_err().to_string();
+            assert!(message.contains(error), "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_resolve_step_references() {
+        let outputs = vec![
+            StepOutput {
+                name: "research".to_string(),
+                output: "three sources".to_string(),
+            },
+            StepOutput {
+                name: "outline".to_string(),
+                output: "two sections".to_string(),
+            },
+        ];
+        assert_eq!(
+            resolve_step_references("Use ${steps.research} and ${steps.outline}", &outputs)
+                .unwrap(),
+            "Use three sources and two sections"
+        );
+        assert_eq!(
+            resolve_step_references("no references", &outputs).unwrap(),
+            "no references"
+        );
+        assert!(resolve_step_references("${steps.missing}", &outputs).is_err());
+        assert!(resolve_step_references("${steps.research", &outputs).is_err());
+
+        let prompt = prompt_with_step_outputs("Review the report", &outputs[..1]);
+        assert!(prompt.starts_with("These steps of the recipe have already run:"));
+        assert!(prompt.contains("<step name=\"research\">\nthree sources\n</step>"));
+        assert!(prompt.ends_with("Review the report"));
+    }
+}
@@ -1,11 +1,12 @@
 use crate::recipe::read_recipe_file_content::RecipeFile;
+use crate::recipe::steps::validate_steps;
 use crate::recipe::template_recipe::parse_recipe_content;
 use crate::recipe::{
     Recipe, RecipeParameter, RecipeParameterInputType, RecipeParameterRequirement,
     BUILT_IN_RECIPE_DIR_PARAM,
 };
 use anyhow::Result;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 pub fn parse_and_validate_parameters(
     recipe_file_content: &str,
@@ -44,6 +45,7 @@ pub fn validate_recipe_template_from_content(
     let (recipe, _) = parse_recipe_content(recipe_content, recipe_dir)?;
 
     validate_prompt_or_instructions(&recipe)?;
+    validate_steps(&recipe)?;
     if let Some(response) = &recipe.response {
         if let Some(json_schema) = &response.json_schema {
             validate_json_schema(json_schema)?;
@@ -155,6 +157,52 @@ fn validate_optional_parameters(parameters: &Option<Vec<RecipeParameter>>) -> Re
     }
 }
 
+/// What is wrong with the values given for `parameters`, such as a number that isn't one or a
+/// choice that isn't among the options. Parameters without a value are left to the caller.
+pub fn validate_parameter_values(
+    parameters: &[RecipeParameter],
+    values: &HashMap<String, String>,
+) -> Vec<String> {
+    parameters
+        .iter()
+        .filter_map(|param| {
+            let value = values.get(&param.key)?;
+            let trimmed = value.trim();
+            let valid = match param.input_type {
+                RecipeParameterInputType::Number => trimmed.parse::<f64>().is_ok(),
+                RecipeParameterInputType::Boolean => {
+                    trimmed.eq_ignore_ascii_case("true") || trimmed.eq_ignore_ascii_case("false")
+                }
+                RecipeParameterInputType::Date => {
+                    chrono::NaiveDate::parse_from_str(trimmed, "%Y-%m-%d").is_ok()
+                        || chrono::DateTime::parse_from_rfc3339(trimmed).is_ok()
+                }
+                RecipeParameterInputType::Select => param
+                    .options
+                    .as_ref()
+                    .is_none_or(|options| options.iter().any(|option| option == value)),
+                RecipeParameterInputType::String | RecipeParameterInputType::File => true,
+            };
+            if valid {
+                return None;
+            }
+            let expected = match param.input_type {
+                RecipeParameterInputType::Number => "a number".to_string(),
+                RecipeParameterInputType::Boolean => "true or false".to_string(),
+                RecipeParameterInputType::Date => "a date such as 2025-01-31".to_string(),
+                _ => format!(
+                    "one of: {}",
+                    param.options.as_deref().unwrap_or_default().join(", ")
+                ),
+            };
+            Some(format!(
+                "{} must be {}, not '{}'",
+                param.key, expected, value
+            ))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -197,4 +245,62 @@ parameters:
         assert!(recipe.instructions.is_some());
         println!("Recipe: {:?}", recipe.prompt);
     }
+
+    #[test]
+    fn test_validate_parameter_values() {
+        let param = |key: &str, input_type, options: Option<Vec<&str>>| RecipeParameter {
+            key: key.to_string(),
+            input_type,
+            requirement: RecipeParameterRequirement::Required,
+            description: key.to_string(),
+            default: None,
+            options: options.map(|options| options.into_iter().map(String::from).collect()),
+        };
+        let parameters = vec![
+            param("count", RecipeParameterInputType::Number, None),
+            param("verbose", RecipeParameterInputType::Boolean, None),
+            param("since", RecipeParameterInputType::Date, None),
+            param(
+                "env",
+                RecipeParameterInputType::Select,
+                Some(vec!["dev", "prod"]),
+            ),
+            param("notes", RecipeParameterInputType::String, None),
+        ];
+        let values = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+
+        assert!(validate_parameter_values(
+            &parameters,
+            &values(&[
+                ("count", "3.5"),
+                ("verbose", "True"),
+                ("since", "2025-01-31"),
+                ("env", "prod"),
+                ("notes", "anything"),
+            ])
+        )
+        .is_empty());
+        assert_eq!(
+            validate_parameter_values(
+                &parameters,
+                &values(&[
+                    ("count", "three"),
+                    ("verbose", "maybe"),
+                    ("since", "yesterday"),
+                    ("env", "staging"),
+                ])
+            ),
+            vec![
+                "count must be a number, not 'three'",
+                "verbose must be true or false, not 'maybe'",
+                "since must be a date such as 2025-01-31, not 'yesterday'",
+                "env must be one of: dev, prod, not 'staging'",
+            ]
+        );
+    }
 }
@@ -8,6 +8,7 @@ use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use chrono::{DateTime, Local, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use tokio::sync::Mutex;
 use tokio_cron_scheduler::{job::JobId, Job, JobScheduler as TokioJobScheduler};
 use tokio_util::sync::CancellationToken;
@@ -24,9 +25,14 @@ use crate::recipe::Recipe;
 use crate::scheduler_trait::SchedulerTrait;
 use crate::session::session_manager::SessionType;
 use crate::session::{Session, SessionManager};
+use crate::webhooks::{self, WebhookEvent};
 
 type RunningTasksMap = HashMap<String, CancellationToken>;
 type JobsMap = HashMap<String, (JobId, ScheduledJob)>;
+type RunsMap = HashMap<String, Vec<ScheduledRun>>;
+
+/// Runs kept in the history of each schedule; older ones are dropped
+pub const MAX_RUNS_PER_SCHEDULE: usize = 100;
 
 pub fn get_default_scheduler_storage_path() -> Result<PathBuf, io::Error> {
     let data_dir = Paths::data_dir();
@@ -34,6 +40,11 @@ pub fn get_default_scheduler_storage_path() -> Result<PathBuf, io::Error> {
     Ok(data_dir.join("schedules.json"))
 }
 
+/// Where the run history of the schedules stored at `storage_path` is kept
+fn run_history_path(storage_path: &Path) -> PathBuf {
+    storage_path.with_file_name("schedule_runs.json")
+}
+
 pub fn get_default_scheduled_recipes_dir() -> Result<PathBuf, SchedulerError> {
     let data_dir = Paths::data_dir();
     let recipes_dir = data_dir.join("scheduled_recipes");
@@ -104,7 +115,12 @@ impl From<anyhow::Error> for SchedulerError {
 pub struct ScheduledJob {
     pub id: String,
     pub source: String,
+    /// When the job runs; unused for jobs that run once at `run_at`
+    #[serde(default)]
     pub cron: String,
+    /// Run the job once at this time instead of on `cron`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_at: Option<DateTime<Utc>>,
     pub last_run: Option<DateTime<Utc>>,
     #[serde(default)]
     pub currently_running: bool,
@@ -116,17 +132,286 @@ pub struct ScheduledJob {
     pub process_start_time: Option<DateTime<Utc>>,
 }
 
+impl ScheduledJob {
+    /// Whether the job runs once rather than on a cron schedule
+    pub fn is_one_shot(&self) -> bool {
+        self.run_at.is_some()
+    }
+}
+
+/// What started a run of a scheduled job
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RunTrigger {
+    Schedule,
+    Manual,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, utoipa::ToSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RunOutcome {
+    Completed,
+    Failed { error: String },
+    Cancelled,
+}
+
+/// A finished run of a scheduled job
+#[derive(Clone, Serialize, Deserialize, Debug, utoipa::ToSchema)]
+pub struct ScheduledRun {
+    pub schedule_id: String,
+    pub trigger: RunTrigger,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// The session the run happened in, if it got far enough to start one
+    pub session_id: Option<String>,
+    pub outcome: RunOutcome,
+}
+
+/// A run of a scheduled job as seen by the [`ScheduledJobRunner`] carrying it out
+pub struct RunContext {
+    job_id: String,
+    jobs: Arc<Mutex<JobsMap>>,
+    cancel_token: CancellationToken,
+    session_id: std::sync::Mutex<Option<String>>,
+}
+
+impl RunContext {
+    /// Cancelled when the run is killed
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel_token.clone()
+    }
+
+    /// Record the session the run happens in, so it can be inspected while it runs and is
+    /// kept in the run history even if the run fails
+    pub async fn set_session_id(&self, session_id: &str) {
+        *self.session_id.lock().unwrap() = Some(session_id.to_string());
+        if let Some((_, job)) = self.jobs.lock().await.get_mut(&self.job_id) {
+            job.current_session_id = Some(session_id.to_string());
+        }
+    }
+}
+
+/// Carries out the runs of scheduled jobs. [`RecipeJobRunner`] runs them in goose; hosts with
+/// their own job runners can hand runs to them by passing another to [`Scheduler::with_runner`].
+#[async_trait]
+pub trait ScheduledJobRunner: Send + Sync {
+    /// Run `job`, returning the ID of the session it ran in
+    async fn run(&self, job: ScheduledJob, context: &RunContext) -> Result<String>;
+}
+
+/// Runs a job's recipe unattended in a new scheduled session
+pub struct RecipeJobRunner;
+
+#[async_trait]
+impl ScheduledJobRunner for RecipeJobRunner {
+    async fn run(&self, job: ScheduledJob, context: &RunContext) -> Result<String> {
+        execute_job(job, context).await
+    }
+}
+
+/// Replace `path` with `data` through a temporary file, so a crash mid-write can't truncate it
+fn write_replacing(path: &Path, data: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, data)?;
+    fs::rename(temp_path, path)
+}
+
+async fn persist_runs(runs_path: &Path, runs: &RunsMap) -> Result<(), SchedulerError> {
+    write_replacing(runs_path, &serde_json::to_string_pretty(runs)?)?;
+    Ok(())
+}
+
+fn load_runs(runs_path: &Path) -> RunsMap {
+    let data = match fs::read_to_string(runs_path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return RunsMap::new(),
+        Err(e) => {
+            tracing::error!(
+                "Failed to read run history {}: {}. Schedules start with no run history.",
+                runs_path.display(),
+                e
+            );
+            return RunsMap::new();
+        }
+    };
+    match serde_json::from_str(&data) {
+        Ok(runs) => runs,
+        Err(e) => {
+            // The next run would overwrite the file, so keep it for whoever wants the history back
+            let backup_path = runs_path.with_extension("json.bak");
+            match fs::rename(runs_path, &backup_path) {
+                Ok(()) => tracing::error!(
+                    "Failed to parse run history {}: {}. Schedules start with no run history; \
+                     the unreadable file was moved to {}.",
+                    runs_path.display(),
+                    e,
+                    backup_path.display()
+                ),
+                Err(rename_error) => tracing::error!(
+                    "Failed to parse run history {}: {}. Schedules start with no run history, \
+                     and the unreadable file couldn't be kept: {}.",
+                    runs_path.display(),
+                    e,
+                    rename_error
+                ),
+            }
+            RunsMap::new()
+        }
+    }
+}
+
+/// What the tasks running jobs share with the scheduler
+#[derive(Clone)]
+struct JobRuntime {
+    jobs: Arc<Mutex<JobsMap>>,
+    storage_path: PathBuf,
+    running_tasks: Arc<Mutex<RunningTasksMap>>,
+    runs: Arc<Mutex<RunsMap>>,
+    runner: Arc<dyn ScheduledJobRunner>,
+}
+
+impl JobRuntime {
+    /// Run the job `job_id`, keeping its state and run history up to date
+    async fn run(&self, job_id: &str, trigger: RunTrigger) -> Result<String, SchedulerError> {
+        let started_at = Utc::now();
+        let job = {
+            let mut jobs_guard = self.jobs.lock().await;
+            let Some((_, job)) = jobs_guard.get_mut(job_id) else {
+                return Err(SchedulerError::JobNotFound(job_id.to_string()));
+            };
+            if job.currently_running {
+                return Err(SchedulerError::AnyhowError(anyhow!(
+                    "Job '{}' is already running",
+                    job_id
+                )));
+            }
+            if trigger == RunTrigger::Schedule && job.paused {
+                return Err(SchedulerError::AnyhowError(anyhow!(
+                    "Job '{}' is paused",
+                    job_id
+                )));
+            }
+            // Manual runs set `last_run` when they finish, scheduled ones when they start
+            if trigger == RunTrigger::Schedule {
+                job.last_run = Some(started_at);
+            }
+            job.currently_running = true;
+            job.process_start_time = Some(started_at);
+            job.clone()
+        };
+        if let Err(e) = persist_jobs(&self.storage_path, &self.jobs).await {
+            tracing::error!("Failed to persist job status: {}", e);
+        }
+        webhooks::notify(
+            WebhookEvent::SessionStarted,
+            None,
+            json!({"schedule_id": job_id, "trigger": trigger}),
+        );
+
+        let context = RunContext {
+            job_id: job_id.to_string(),
+            jobs: self.jobs.clone(),
+            cancel_token: CancellationToken::new(),
+            session_id: std::sync::Mutex::new(None),
+        };
+        self.running_tasks
+            .lock()
+            .await
+            .insert(job_id.to_string(), context.cancellation_token());
+
+        let result = self.runner.run(job, &context).await;
+        let finished_at = Utc::now();
+
+        self.running_tasks.lock().await.remove(job_id);
+        {
+            let mut jobs_guard = self.jobs.lock().await;
+            if let Some((_, job)) = jobs_guard.get_mut(job_id) {
+                job.currently_running = false;
+                job.current_session_id = None;
+                job.process_start_time = None;
+                if trigger == RunTrigger::Manual {
+                    job.last_run = Some(finished_at);
+                }
+            }
+        }
+        if let Err(e) = persist_jobs(&self.storage_path, &self.jobs).await {
+            tracing::error!("Failed to persist job completion: {}", e);
+        }
+
+        let outcome = match &result {
+            _ if context.cancel_token.is_cancelled() => RunOutcome::Cancelled,
+            Ok(_) => RunOutcome::Completed,
+            Err(e) => RunOutcome::Failed {
+                error: e.to_string(),
+            },
+        };
+        let session_id = match &result {
+            Ok(session_id) => Some(session_id.clone()),
+            Err(_) => context.session_id.lock().unwrap().clone(),
+        };
+        let event = match &outcome {
+            RunOutcome::Completed => WebhookEvent::SessionFinished,
+            _ => WebhookEvent::SessionFailed,
+        };
+        webhooks::notify(
+            event,
+            session_id.as_deref(),
+            json!({"schedule_id": job_id, "trigger": trigger, "outcome": outcome}),
+        );
+        self.record_run(ScheduledRun {
+            schedule_id: job_id.to_string(),
+            trigger,
+            started_at,
+            finished_at,
+            session_id,
+            outcome,
+        })
+        .await;
+
+        match result {
+            Ok(session_id) => {
+                tracing::info!("Job '{}' completed", job_id);
+                Ok(session_id)
+            }
+            Err(e) => {
+                tracing::error!("Job '{}' failed: {}", job_id, e);
+                crate::posthog::emit_error("scheduler_job_failed", &e.to_string());
+                Err(SchedulerError::AnyhowError(anyhow!(
+                    "Job '{}' failed: {}",
+                    job_id,
+                    e
+                )))
+            }
+        }
+    }
+
+    async fn record_run(&self, run: ScheduledRun) {
+        // A schedule removed while it ran has no history to add to
+        if !self.jobs.lock().await.contains_key(&run.schedule_id) {
+            return;
+        }
+        let mut runs = self.runs.lock().await;
+        let history = runs.entry(run.schedule_id.clone()).or_default();
+        history.push(run);
+        if history.len() > MAX_RUNS_PER_SCHEDULE {
+            history.drain(..history.len() - MAX_RUNS_PER_SCHEDULE);
+        }
+        if let Err(e) = persist_runs(&run_history_path(&self.storage_path), &runs).await {
+            tracing::error!("Failed to persist run history: {}", e);
+        }
+    }
+}
+
 async fn persist_jobs(
     storage_path: &Path,
     jobs: &Arc<Mutex<JobsMap>>,
 ) -> Result<(), SchedulerError> {
     let jobs_guard = jobs.lock().await;
     let list: Vec<ScheduledJob> = jobs_guard.values().map(|(_, j)| j.clone()).collect();
-    if let Some(parent) = storage_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    let data = serde_json::to_string_pretty(&list)?;
-    fs::write(storage_path, data)?;
+    write_replacing(storage_path, &serde_json::to_string_pretty(&list)?)?;
     Ok(())
 }
 
@@ -135,22 +420,35 @@ pub struct Scheduler {
     jobs: Arc<Mutex<JobsMap>>,
     storage_path: PathBuf,
     running_tasks: Arc<Mutex<RunningTasksMap>>,
+    runs: Arc<Mutex<RunsMap>>,
+    runner: Arc<dyn ScheduledJobRunner>,
 }
 
 impl Scheduler {
     pub async fn new(storage_path: PathBuf) -> Result<Arc<Self>, SchedulerError> {
+        Self::with_runner(storage_path, Arc::new(RecipeJobRunner)).await
+    }
+
+    /// A scheduler whose jobs are run by `runner`
+    pub async fn with_runner(
+        storage_path: PathBuf,
+        runner: Arc<dyn ScheduledJobRunner>,
+    ) -> Result<Arc<Self>, SchedulerError> {
         let internal_scheduler = TokioJobScheduler::new()
             .await
             .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
 
         let jobs = Arc::new(Mutex::new(HashMap::new()));
         let running_tasks = Arc::new(Mutex::new(HashMap::new()));
+        let runs = Arc::new(Mutex::new(load_runs(&run_history_path(&storage_path))));
 
         let arc_self = Arc::new(Self {
             tokio_scheduler: internal_scheduler,
             jobs,
             storage_path,
             running_tasks,
+            runs,
+            runner,
         });
 
         arc_self.load_jobs_from_storage().await;
@@ -163,14 +461,43 @@ impl Scheduler {
         Ok(arc_self)
     }
 
-    fn create_cron_task(&self, job: ScheduledJob) -> Result<Job, SchedulerError> {
-        let job_for_task = job.clone();
-        let jobs_arc = self.jobs.clone();
-        let storage_path = self.storage_path.clone();
-        let running_tasks_arc = self.running_tasks.clone();
+    fn runtime(&self) -> JobRuntime {
+        JobRuntime {
+            jobs: self.jobs.clone(),
+            storage_path: self.storage_path.clone(),
+            running_tasks: self.running_tasks.clone(),
+            runs: self.runs.clone(),
+            runner: self.runner.clone(),
+        }
+    }
+
+    fn create_job_task(&self, job: ScheduledJob) -> Result<Job, SchedulerError> {
+        let runtime = self.runtime();
+        let job_id = job.id.clone();
+        let run = move |_uuid: JobId, _l: TokioJobScheduler| {
+            tracing::info!("Scheduled task triggered for job '{}'", job_id);
+            let runtime = runtime.clone();
+            let job_id = job_id.clone();
+            Box::pin(async move {
+                // Failures are logged and recorded in the run history by the runtime
+                let _ = runtime.run(&job_id, RunTrigger::Schedule).await;
+            }) as std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send>>
+        };
+
+        if let Some(run_at) = job.run_at {
+            let delay = (run_at - Utc::now()).to_std().unwrap_or_default();
+            return Job::new_one_shot_async(delay, run)
+                .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()));
+        }
 
         let cron_parts: Vec<&str> = job.cron.split_whitespace().collect();
         let cron = match cron_parts.len() {
+            0 => {
+                return Err(SchedulerError::CronParseError(format!(
+                    "Job '{}' needs a cron expression or a time to run at",
+                    job.id
+                )))
+            }
             5 => {
                 tracing::warn!(
                     "Job '{}' has legacy 5-field cron '{}', converting to 6-field",
@@ -191,83 +518,34 @@ impl Scheduler {
 
         let local_tz = Local::now().timezone();
 
-        Job::new_async_tz(&cron, local_tz, move |_uuid, _l| {
-            tracing::info!("Cron task triggered for job '{}'", job_for_task.id);
-            let task_job_id = job_for_task.id.clone();
-            let current_jobs_arc = jobs_arc.clone();
-            let local_storage_path = storage_path.clone();
-            let job_to_execute = job_for_task.clone();
-            let running_tasks = running_tasks_arc.clone();
-
-            Box::pin(async move {
-                let should_execute = {
-                    let jobs_guard = current_jobs_arc.lock().await;
-                    jobs_guard
-                        .get(&task_job_id)
-                        .map(|(_, j)| !j.paused)
-                        .unwrap_or(false)
-                };
-
-                if !should_execute {
-                    return;
-                }
-
-                let current_time = Utc::now();
-                {
-                    let mut jobs_guard = current_jobs_arc.lock().await;
-                    if let Some((_, job)) = jobs_guard.get_mut(&task_job_id) {
-                        job.last_run = Some(current_time);
-                        job.currently_running = true;
-                        job.process_start_time = Some(current_time);
-                    }
-                }
-
-                if let Err(e) = persist_jobs(&local_storage_path, &current_jobs_arc).await {
-                    tracing::error!("Failed to persist job status: {}", e);
-                }
-
-                let cancel_token = CancellationToken::new();
-                {
-                    let mut tasks = running_tasks.lock().await;
-                    tasks.insert(task_job_id.clone(), cancel_token.clone());
-                }
-
-                let result = execute_job(
-                    job_to_execute,
-                    current_jobs_arc.clone(),
-                    task_job_id.clone(),
-                    cancel_token.clone(),
-                )
-                .await;
-
-                {
-                    let mut tasks = running_tasks.lock().await;
-                    tasks.remove(&task_job_id);
-                }
-
-                {
-                    let mut jobs_guard = current_jobs_arc.lock().await;
-                    if let Some((_, job)) = jobs_guard.get_mut(&task_job_id) {
-                        job.currently_running = false;
-                        job.current_session_id = None;
-                        job.process_start_time = None;
-                    }
-                }
+        Job::new_async_tz(&cron, local_tz, run)
+            .map_err(|e| SchedulerError::CronParseError(e.to_string()))
+    }
 
-                if let Err(e) = persist_jobs(&local_storage_path, &current_jobs_arc).await {
-                    tracing::error!("Failed to persist job completion: {}", e);
-                }
+    /// Add the task running `job` to the tokio scheduler; one-shot jobs that already ran at
+    /// their time get none, which [`JobId::nil`] stands for
+    async fn add_job_task(&self, job: &ScheduledJob) -> Result<JobId, SchedulerError> {
+        if job
+            .run_at
+            .is_some_and(|run_at| job.last_run.is_some_and(|last_run| last_run >= run_at))
+        {
+            return Ok(JobId::nil());
+        }
+        let task = self.create_job_task(job.clone())?;
+        self.tokio_scheduler
+            .add(task)
+            .await
+            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))
+    }
 
-                match result {
-                    Ok(_) => tracing::info!("Job '{}' completed", task_job_id),
-                    Err(ref e) => {
-                        tracing::error!("Job '{}' failed: {}", task_job_id, e);
-                        crate::posthog::emit_error("scheduler_job_failed", &e.to_string());
-                    }
-                }
-            })
-        })
-        .map_err(|e| SchedulerError::CronParseError(e.to_string()))
+    async fn remove_job_task(&self, job_uuid: &JobId) -> Result<(), SchedulerError> {
+        if job_uuid.is_nil() {
+            return Ok(());
+        }
+        self.tokio_scheduler
+            .remove(job_uuid)
+            .await
+            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))
     }
 
     pub async fn add_scheduled_job(
@@ -307,13 +585,7 @@ impl Scheduler {
             stored_job.process_start_time = None;
         }
 
-        let cron_task = self.create_cron_task(stored_job.clone())?;
-
-        let job_uuid = self
-            .tokio_scheduler
-            .add(cron_task)
-            .await
-            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+        let job_uuid = self.add_job_task(&stored_job).await?;
 
         {
             let mut jobs_guard = self.jobs.lock().await;
@@ -349,6 +621,7 @@ impl Scheduler {
                         id: job_id,
                         source: recipe_path_str,
                         cron,
+                        run_at: None,
                         last_run: None,
                         currently_running: false,
                         paused: false,
@@ -426,19 +699,7 @@ impl Scheduler {
                 continue;
             }
 
-            let cron_task = match self.create_cron_task(job_to_load.clone()) {
-                Ok(task) => task,
-                Err(e) => {
-                    tracing::error!(
-                        "Failed to create cron task for job '{}': {}. Skipping.",
-                        job_to_load.id,
-                        e
-                    );
-                    continue;
-                }
-            };
-
-            let job_uuid = match self.tokio_scheduler.add(cron_task).await {
+            let job_uuid = match self.add_job_task(&job_to_load).await {
                 Ok(uuid) => uuid,
                 Err(e) => {
                     tracing::error!(
@@ -477,10 +738,14 @@ impl Scheduler {
             }
         };
 
-        self.tokio_scheduler
-            .remove(&job_uuid)
-            .await
-            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+        self.remove_job_task(&job_uuid).await?;
+
+        {
+            let mut runs = self.runs.lock().await;
+            if runs.remove(id).is_some() {
+                persist_runs(&run_history_path(&self.storage_path), &runs).await?;
+            }
+        }
 
         if remove_recipe {
             let path = Path::new(&recipe_path);
@@ -515,65 +780,24 @@ impl Scheduler {
     }
 
     pub async fn run_now(&self, sched_id: &str) -> Result<String, SchedulerError> {
-        let job_to_run = {
-            let mut jobs_guard = self.jobs.lock().await;
-            match jobs_guard.get_mut(sched_id) {
-                Some((_, job)) => {
-                    if job.currently_running {
-                        return Err(SchedulerError::AnyhowError(anyhow!(
-                            "Job '{}' is already running",
-                            sched_id
-                        )));
-                    }
-                    job.currently_running = true;
-                    job.process_start_time = Some(Utc::now());
-                    job.clone()
-                }
-                None => return Err(SchedulerError::JobNotFound(sched_id.to_string())),
-            }
-        };
-
-        persist_jobs(&self.storage_path, &self.jobs).await?;
-
-        let cancel_token = CancellationToken::new();
-        {
-            let mut tasks = self.running_tasks.lock().await;
-            tasks.insert(sched_id.to_string(), cancel_token.clone());
-        }
-
-        let result = execute_job(
-            job_to_run,
-            self.jobs.clone(),
-            sched_id.to_string(),
-            cancel_token.clone(),
-        )
-        .await;
-
-        {
-            let mut tasks = self.running_tasks.lock().await;
-            tasks.remove(sched_id);
-        }
-
-        {
-            let mut jobs_guard = self.jobs.lock().await;
-            if let Some((_, job)) = jobs_guard.get_mut(sched_id) {
-                job.currently_running = false;
-                job.current_session_id = None;
-                job.process_start_time = None;
-                job.last_run = Some(Utc::now());
-            }
-        }
-
-        persist_jobs(&self.storage_path, &self.jobs).await?;
+        self.runtime().run(sched_id, RunTrigger::Manual).await
+    }
 
-        match result {
-            Ok(session_id) => Ok(session_id),
-            Err(e) => Err(SchedulerError::AnyhowError(anyhow!(
-                "Job '{}' failed: {}",
-                sched_id,
-                e
-            ))),
-        }
+    /// The last `limit` runs of the schedule `sched_id`, most recent first
+    pub async fn run_history(
+        &self,
+        sched_id: &str,
+        limit: usize,
+    ) -> Result<Vec<ScheduledRun>, SchedulerError> {
+        let runs = self.runs.lock().await;
+        let Some(history) = runs.get(sched_id) else {
+            return if self.jobs.lock().await.contains_key(sched_id) {
+                Ok(Vec::new())
+            } else {
+                Err(SchedulerError::JobNotFound(sched_id.to_string()))
+            };
+        };
+        Ok(history.iter().rev().take(limit).cloned().collect())
     }
 
     pub async fn pause_schedule(&self, sched_id: &str) -> Result<(), SchedulerError> {
@@ -623,27 +847,20 @@ impl Scheduler {
                             sched_id
                         )));
                     }
-                    if new_cron == job.cron {
+                    if new_cron == job.cron && !job.is_one_shot() {
                         return Ok(());
                     }
+                    // A new cron expression makes a one-shot job recurring
                     job.cron = new_cron.clone();
+                    job.run_at = None;
                     (*uuid, job.clone())
                 }
                 None => return Err(SchedulerError::JobNotFound(sched_id.to_string())),
             }
         };
 
-        self.tokio_scheduler
-            .remove(&old_uuid)
-            .await
-            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
-
-        let cron_task = self.create_cron_task(updated_job)?;
-        let new_uuid = self
-            .tokio_scheduler
-            .add(cron_task)
-            .await
-            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+        self.remove_job_task(&old_uuid).await?;
+        let new_uuid = self.add_job_task(&updated_job).await?;
 
         {
             let mut jobs_guard = self.jobs.lock().await;
@@ -699,12 +916,7 @@ impl Scheduler {
 }
 
 #[allow(clippy::too_many_lines)]
-async fn execute_job(
-    job: ScheduledJob,
-    jobs: Arc<Mutex<JobsMap>>,
-    job_id: String,
-    cancel_token: CancellationToken,
-) -> Result<String> {
+async fn execute_job(job: ScheduledJob, context: &RunContext) -> Result<String> {
     if job.source.is_empty() {
         return Ok(job.id.to_string());
     }
@@ -749,11 +961,7 @@ async fn execute_job(
 
     agent.update_provider(agent_provider, &session.id).await?;
 
-    let mut jobs_guard = jobs.lock().await;
-    if let Some((_, job_def)) = jobs_guard.get_mut(job_id.as_str()) {
-        job_def.current_session_id = Some(session.id.clone());
-    }
-    drop(jobs_guard);
+    context.set_session_id(&session.id).await;
 
     let start_time = std::time::Instant::now();
     tokio::spawn(async move {
@@ -781,12 +989,24 @@ async fn execute_job(
         schedule_id: Some(job.id.clone()),
         max_turns: None,
         retry_config: None,
+        moderation: None,
+        context_policy: None,
+        budget: None,
+        budget_session_id: None,
+        model_routing: None,
+        lead_worker: None,
+        reflection: None,
+        final_answer_schema: None,
     };
 
     let session_id = session_config.id.clone();
     let stream = crate::session_context::with_session_id(Some(session_id.clone()), async {
         agent
-            .reply(user_message, session_config, Some(cancel_token))
+            .reply(
+                user_message,
+                session_config,
+                Some(context.cancellation_token()),
+            )
             .await
     })
     .await?;
@@ -794,6 +1014,7 @@ async fn execute_job(
     use futures::StreamExt;
     let mut stream = std::pin::pin!(stream);
 
+    let mut stream_error = None;
     while let Some(message_result) = stream.next().await {
         tokio::task::yield_now().await;
 
@@ -807,6 +1028,7 @@ async fn execute_job(
             Ok(_) => {}
             Err(e) => {
                 tracing::error!("Error in agent stream: {}", e);
+                stream_error = Some(e);
                 break;
             }
         }
@@ -817,6 +1039,9 @@ async fn execute_job(
         .recipe(Some(recipe))
         .apply()
         .await?;
+    if let Some(e) = stream_error {
+        return Err(e);
+    }
 
     let duration_secs = start_time.elapsed().as_secs();
     tokio::spawn(async move {
@@ -909,6 +1134,14 @@ impl SchedulerTrait for Scheduler {
     ) -> Result<Option<(String, DateTime<Utc>)>, SchedulerError> {
         self.get_running_job_info(sched_id).await
     }
+
+    async fn run_history(
+        &self,
+        sched_id: &str,
+        limit: usize,
+    ) -> Result<Vec<ScheduledRun>, SchedulerError> {
+        self.run_history(sched_id, limit).await
+    }
 }
 
 #[cfg(test)]
@@ -934,6 +1167,7 @@ mod tests {
             id: "scheduled_job".to_string(),
             source: recipe_path.to_string_lossy().to_string(),
             cron: "* * * * * *".to_string(),
+            run_at: None,
             last_run: None,
             currently_running: false,
             paused: false,
@@ -959,6 +1193,7 @@ mod tests {
             id: "paused_job".to_string(),
             source: recipe_path.to_string_lossy().to_string(),
             cron: "* * * * * *".to_string(),
+            run_at: None,
             last_run: None,
             currently_running: false,
             paused: false,
@@ -973,4 +1208,147 @@ mod tests {
         let jobs = scheduler.list_scheduled_jobs().await;
         assert!(jobs[0].last_run.is_none(), "Paused job should not run");
     }
+
+    /// Runs jobs by failing those whose ID says so
+    struct TestRunner;
+
+    #[async_trait]
+    impl ScheduledJobRunner for TestRunner {
+        async fn run(&self, job: ScheduledJob, context: &RunContext) -> Result<String> {
+            context.set_session_id(&format!("{}_session", job.id)).await;
+            if job.id.contains("failing") {
+                return Err(anyhow!("recipe went wrong"));
+            }
+            Ok(format!("{}_session", job.id))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_one_shot_job_runs_once_with_history() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("schedules.json");
+        let recipe_path = create_test_recipe(temp_dir.path(), "one_shot");
+        let scheduler = Scheduler::with_runner(storage_path.clone(), Arc::new(TestRunner))
+            .await
+            .unwrap();
+
+        let job = ScheduledJob {
+            id: "one_shot".to_string(),
+            source: recipe_path.to_string_lossy().to_string(),
+            cron: String::new(),
+            run_at: Some(Utc::now() + chrono::Duration::milliseconds(200)),
+            last_run: None,
+            currently_running: false,
+            paused: false,
+            current_session_id: None,
+            process_start_time: None,
+        };
+        scheduler
+            .add_scheduled_job(job.clone(), false)
+            .await
+            .unwrap();
+        // A job needs either a cron expression or a time to run at
+        scheduler
+            .add_scheduled_job(
+                ScheduledJob {
+                    id: "no_schedule".to_string(),
+                    run_at: None,
+                    ..job
+                },
+                false,
+            )
+            .await
+            .unwrap_err();
+        sleep(Duration::from_millis(1500)).await;
+
+        let runs = scheduler.run_history("one_shot", 10).await.unwrap();
+        assert_eq!(runs.len(), 1, "A one-shot job should run once");
+        assert_eq!(runs[0].trigger, RunTrigger::Schedule);
+        assert_eq!(runs[0].outcome, RunOutcome::Completed);
+        assert_eq!(runs[0].session_id.as_deref(), Some("one_shot_session"));
+
+        // The run is remembered, so a restarted scheduler doesn't run the job again
+        drop(scheduler);
+        let scheduler = Scheduler::with_runner(storage_path, Arc::new(TestRunner))
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(500)).await;
+        assert_eq!(
+            scheduler.run_history("one_shot", 10).await.unwrap().len(),
+            1
+        );
+        assert!(scheduler.run_history("missing", 10).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_run_is_recorded() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("schedules.json");
+        let recipe_path = create_test_recipe(temp_dir.path(), "failing_job");
+        let scheduler = Scheduler::with_runner(storage_path.clone(), Arc::new(TestRunner))
+            .await
+            .unwrap();
+
+        let job = ScheduledJob {
+            id: "failing_job".to_string(),
+            source: recipe_path.to_string_lossy().to_string(),
+            cron: "0 0 0 1 1 *".to_string(),
+            run_at: None,
+            last_run: None,
+            currently_running: false,
+            paused: false,
+            current_session_id: None,
+            process_start_time: None,
+        };
+        scheduler.add_scheduled_job(job, false).await.unwrap();
+        assert!(scheduler.run_now("failing_job").await.is_err());
+
+        let runs = scheduler.run_history("failing_job", 10).await.unwrap();
+        assert_eq!(runs[0].trigger, RunTrigger::Manual);
+        assert_eq!(
+            runs[0].outcome,
+            RunOutcome::Failed {
+                error: "recipe went wrong".to_string()
+            }
+        );
+        assert_eq!(runs[0].session_id.as_deref(), Some("failing_job_session"));
+        let jobs = scheduler.list_scheduled_jobs().await;
+        assert_eq!(jobs[0].last_run, Some(runs[0].finished_at));
+
+        // Removing the schedule removes its history
+        scheduler
+            .remove_scheduled_job("failing_job", false)
+            .await
+            .unwrap();
+        assert!(scheduler.run_history("failing_job", 10).await.is_err());
+        assert!(!load_runs(&run_history_path(&storage_path)).contains_key("failing_job"));
+    }
+
+    #[test]
+    fn test_unreadable_run_history_is_kept() {
+        let temp_dir = tempdir().unwrap();
+        let runs_path = run_history_path(&temp_dir.path().join("schedules.json"));
+        fs::write(&runs_path, "{ not json").unwrap();
+
+        assert!(load_runs(&runs_path).is_empty());
+        assert!(!runs_path.exists());
+        assert_eq!(
+            fs::read_to_string(runs_path.with_extension("json.bak")).unwrap(),
+            "{ not json"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_persisted_runs_replace_the_file() {
+        let temp_dir = tempdir().unwrap();
+        let runs_path = run_history_path(&temp_dir.path().join("schedules.json"));
+        let mut runs = RunsMap::new();
+        runs.insert("job".to_string(), Vec::new());
+
+        persist_runs(&runs_path, &runs).await.unwrap();
+        persist_runs(&runs_path, &runs).await.unwrap();
+
+        assert!(load_runs(&runs_path).contains_key("job"));
+        assert!(!runs_path.with_extension("tmp").exists());
+    }
 }
@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use std::path::PathBuf;
 
-use crate::scheduler::{ScheduledJob, SchedulerError};
+use crate::scheduler::{ScheduledJob, ScheduledRun, SchedulerError};
 use crate::session::Session;
 
 #[async_trait]
@@ -38,4 +38,9 @@ pub trait SchedulerTrait: Send + Sync {
         &self,
         sched_id: &str,
     ) -> Result<Option<(String, DateTime<Utc>)>, SchedulerError>;
+    async fn run_history(
+        &self,
+        sched_id: &str,
+        limit: usize,
+    ) -> Result<Vec<ScheduledRun>, SchedulerError>;
 }
@@ -1,6 +1,7 @@
 pub mod patterns;
 pub mod scanner;
 pub mod security_inspector;
+pub mod tool_output;
 
 use crate::conversation::message::{Message, ToolRequest};
 use crate::permission::permission_judge::PermissionCheckResult;
@@ -0,0 +1,493 @@
+//! Durable conversation storage for applications embedding goose.
+//!
+//! [`ConversationStore`] persists the messages of a session, including tool requests and
+//! results, along with the token usage accumulated by the session. [`SqliteConversationStore`]
+//! is the bundled implementation; the `postgres` feature adds
+//! [`PostgresConversationStore`](super::postgres_conversation_store::PostgresConversationStore)
+//! for server deployments, the `s3-archive` feature adds
+//! [`S3ConversationArchive`](super::s3_conversation_archive::S3ConversationArchive) for
+//! long-term retention, and other backends implement the same trait.
+
+use std::path::Path;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rmcp::model::Role;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+use sqlx::{Pool, Sqlite, Transaction};
+
+use crate::conversation::message::Message;
+use crate::conversation::Conversation;
+use crate::providers::base::Usage;
+
+/// A stored session's conversation and accumulated usage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredConversation {
+    pub session_id: String,
+    pub conversation: Conversation,
+    pub usage: Usage,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A stored session, without its messages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredConversationSummary {
+    pub session_id: String,
+    pub message_count: usize,
+    pub usage: Usage,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[async_trait]
+pub trait ConversationStore: Send + Sync {
+    /// Store `conversation` as the session's messages, replacing any stored before, and set
+    /// the session's usage to `usage`
+    async fn save(&self, session_id: &str, conversation: &Conversation, usage: Usage)
+        -> Result<()>;
+
+    /// Add `message` to the end of the session, creating the session if needed, and add
+    /// `usage` to the session's usage
+    async fn append(&self, session_id: &str, message: &Message, usage: Option<Usage>)
+        -> Result<()>;
+
+    /// The session's conversation, or None if nothing is stored for it
+    async fn load(&self, session_id: &str) -> Result<Option<StoredConversation>>;
+
+    /// All stored sessions, most recently updated first
+    async fn list(&self) -> Result<Vec<StoredConversationSummary>>;
+
+    /// Remove the session, returning whether it existed
+    async fn delete(&self, session_id: &str) -> Result<bool>;
+}
+
+pub(super) type UsageRow = (Option<i32>, Option<i32>, Option<i32>, Option<i32>);
+type ConversationRow = (
+    Option<i32>,
+    Option<i32>,
+    Option<i32>,
+    Option<i32>,
+    DateTime<Utc>,
+    DateTime<Utc>,
+);
+
+pub(super) fn usage_from_row((input, output, total, cached): UsageRow) -> Usage {
+    Usage::new(input, output, total).with_cached_input_tokens(cached)
+}
+
+pub(super) fn role_to_string(role: &Role) -> &'static str {
+    match role {
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+pub(super) fn role_from_str(role: &str) -> Option<Role> {
+    match role {
+        "user" => Some(Role::User),
+        "assistant" => Some(Role::Assistant),
+        _ => None,
+    }
+}
+
+/// A [`ConversationStore`] backed by a SQLite database file
+pub struct SqliteConversationStore {
+    pool: Pool<Sqlite>,
+}
+
+impl SqliteConversationStore {
+    /// Open the database at `path`, creating it and its tables if needed
+    pub async fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .busy_timeout(std::time::Duration::from_secs(5))
+            .journal_mode(SqliteJournalMode::Wal)
+            .foreign_keys(true);
+        let pool = sqlx::SqlitePool::connect_with(options).await.map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to open SQLite database at '{}': {}",
+                path.display(),
+                e
+            )
+        })?;
+
+        let store = Self { pool };
+        store.create_tables().await?;
+        Ok(store)
+    }
+
+    async fn create_tables(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS conversations (
+                session_id TEXT PRIMARY KEY,
+                input_tokens INTEGER,
+                output_tokens INTEGER,
+                total_tokens INTEGER,
+                cached_input_tokens INTEGER,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS conversation_messages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL REFERENCES conversations(session_id) ON DELETE CASCADE,
+                message_id TEXT,
+                role TEXT NOT NULL,
+                content_json TEXT NOT NULL,
+                created_timestamp INTEGER NOT NULL,
+                metadata_json TEXT
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_conversation_messages_session ON conversation_messages(session_id)",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn ensure_session(tx: &mut Transaction<'_, Sqlite>, session_id: &str) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO conversations (session_id) VALUES (?)")
+            .bind(session_id)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_usage(
+        tx: &mut Transaction<'_, Sqlite>,
+        session_id: &str,
+        usage: Usage,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE conversations
+            SET input_tokens = ?, output_tokens = ?, total_tokens = ?, cached_input_tokens = ?,
+                updated_at = datetime('now')
+            WHERE session_id = ?
+        "#,
+        )
+        .bind(usage.input_tokens)
+        .bind(usage.output_tokens)
+        .bind(usage.total_tokens)
+        .bind(usage.cached_input_tokens)
+        .bind(session_id)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_message(
+        tx: &mut Transaction<'_, Sqlite>,
+        session_id: &str,
+        message: &Message,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO conversation_messages
+                (session_id, message_id, role, content_json, created_timestamp, metadata_json)
+            VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        )
+        .bind(session_id)
+        .bind(&message.id)
+        .bind(role_to_string(&message.role))
+        .bind(serde_json::to_string(&message.content)?)
+        .bind(message.created)
+        .bind(serde_json::to_string(&message.metadata)?)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ConversationStore for SqliteConversationStore {
+    async fn save(
+        &self,
+        session_id: &str,
+        conversation: &Conversation,
+        usage: Usage,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        Self::ensure_session(&mut tx, session_id).await?;
+
+        sqlx::query("DELETE FROM conversation_messages WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+        for message in conversation.messages() {
+            Self::insert_message(&mut tx, session_id, message).await?;
+        }
+        Self::set_usage(&mut tx, session_id, usage).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn append(
+        &self,
+        session_id: &str,
+        message: &Message,
+        usage: Option<Usage>,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        Self::ensure_session(&mut tx, session_id).await?;
+        Self::insert_message(&mut tx, session_id, message).await?;
+
+        let stored = sqlx::query_as::<_, UsageRow>(
+            "SELECT input_tokens, output_tokens, total_tokens, cached_input_tokens FROM conversations WHERE session_id = ?",
+        )
+        .bind(session_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let usage = match usage {
+            Some(usage) => usage_from_row(stored) + usage,
+            None => usage_from_row(stored),
+        };
+        Self::set_usage(&mut tx, session_id, usage).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Option<StoredConversation>> {
+        let row = sqlx::query_as::<_, ConversationRow>(
+            r#"
+            SELECT input_tokens, output_tokens, total_tokens, cached_input_tokens, created_at, updated_at
+            FROM conversations
+            WHERE session_id = ?
+        "#,
+        )
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some((input, output, total, cached, created_at, updated_at)) = row else {
+            return Ok(None);
+        };
+
+        let rows = sqlx::query_as::<_, (Option<String>, String, String, i64, Option<String>)>(
+            r#"
+            SELECT message_id, role, content_json, created_timestamp, metadata_json
+            FROM conversation_messages
+            WHERE session_id = ?
+            ORDER BY id
+        "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut messages = Vec::with_capacity(rows.len());
+        for (message_id, role, content_json, created_timestamp, metadata_json) in rows {
+            let Some(role) = role_from_str(&role) else {
+                continue;
+            };
+            let mut message = Message::new(
+                role,
+                created_timestamp,
+                serde_json::from_str(&content_json)?,
+            );
+            message.id = message_id;
+            message.metadata = metadata_json
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default();
+            messages.push(message);
+        }
+
+        Ok(Some(StoredConversation {
+            session_id: session_id.to_string(),
+            conversation: Conversation::new_unvalidated(messages),
+            usage: usage_from_row((input, output, total, cached)),
+            created_at,
+            updated_at,
+        }))
+    }
+
+    async fn list(&self) -> Result<Vec<StoredConversationSummary>> {
+        let rows = sqlx::query_as::<
+            _,
+            (
+                String,
+                i64,
+                Option<i32>,
+                Option<i32>,
+                Option<i32>,
+                Option<i32>,
+                DateTime<Utc>,
+                DateTime<Utc>,
+            ),
+        >(
+            r#"
+            SELECT c.session_id, COUNT(m.id), c.input_tokens, c.output_tokens, c.total_tokens,
+                   c.cached_input_tokens, c.created_at, c.updated_at
+            FROM conversations c
+            LEFT JOIN conversation_messages m ON c.session_id = m.session_id
+            GROUP BY c.session_id
+            ORDER BY c.updated_at DESC, c.session_id
+        "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(session_id, count, input, output, total, cached, created_at, updated_at)| {
+                    StoredConversationSummary {
+                        session_id,
+                        message_count: count as usize,
+                        usage: usage_from_row((input, output, total, cached)),
+                        created_at,
+                        updated_at,
+                    }
+                },
+            )
+            .collect())
+    }
+
+    async fn delete(&self, session_id: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM conversation_messages WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+        let deleted = sqlx::query("DELETE FROM conversations WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(deleted > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::{CallToolRequestParam, CallToolResult, Content};
+    use tempfile::TempDir;
+
+    async fn store(dir: &TempDir) -> SqliteConversationStore {
+        SqliteConversationStore::open(&dir.path().join("conversations.db"))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_append_and_load() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir).await;
+
+        store
+            .append("s1", &Message::user().with_text("list files"), None)
+            .await
+            .unwrap();
+        store
+            .append(
+                "s1",
+                &Message::assistant().with_tool_request(
+                    "call_1",
+                    Ok(CallToolRequestParam {
+                        name: "developer__shell".into(),
+                        arguments: None,
+                    }),
+                ),
+                Some(Usage::new(Some(10), Some(5), None)),
+            )
+            .await
+            .unwrap();
+        store
+            .append(
+                "s1",
+                &Message::user().with_tool_response(
+                    "call_1",
+                    Ok(CallToolResult::success(vec![Content::text("README.md")])),
+                ),
+                None,
+            )
+            .await
+            .unwrap();
+        store
+            .append(
+                "s1",
+                &Message::assistant().with_text("There is a README"),
+                Some(Usage::new(Some(20), Some(3), None)),
+            )
+            .await
+            .unwrap();
+
+        let stored = store.load("s1").await.unwrap().unwrap();
+        let messages = stored.conversation.messages();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0].as_concat_text(), "list files");
+        assert!(matches!(
+            &messages[2].content[0],
+            crate::conversation::message::MessageContent::ToolResponse(response)
+                if response.id == "call_1"
+        ));
+        assert_eq!(stored.usage.input_tokens, Some(30));
+        assert_eq!(stored.usage.output_tokens, Some(8));
+        assert_eq!(stored.usage.total_tokens, Some(38));
+
+        assert!(store.load("missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_save_list_and_delete() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir).await;
+
+        store
+            .append("s1", &Message::user().with_text("old"), None)
+            .await
+            .unwrap();
+        let conversation = Conversation::new_unvalidated(vec![
+            Message::user().with_text("hello"),
+            Message::assistant().with_text("hi"),
+        ]);
+        store
+            .save("s1", &conversation, Usage::new(Some(7), Some(2), None))
+            .await
+            .unwrap();
+        store
+            .save("s2", &Conversation::empty(), Usage::default())
+            .await
+            .unwrap();
+
+        let stored = store.load("s1").await.unwrap().unwrap();
+        assert_eq!(stored.conversation.messages().len(), 2);
+        assert_eq!(stored.conversation.messages()[0].as_concat_text(), "hello");
+        assert_eq!(stored.usage.total_tokens, Some(9));
+
+        let mut summaries = store.list().await.unwrap();
+        summaries.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].message_count, 2);
+        assert_eq!(summaries[1].message_count, 0);
+
+        assert!(store.delete("s1").await.unwrap());
+        assert!(!store.delete("s1").await.unwrap());
+        assert!(store.load("s1").await.unwrap().is_none());
+        assert_eq!(store.list().await.unwrap().len(), 1);
+    }
+}
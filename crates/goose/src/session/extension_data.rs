@@ -1,11 +1,16 @@
 // Extension data management for sessions
 // Provides a simple way to store extension-specific data with versioned keys
 
+use crate::agents::budget::SessionBudget;
+use crate::agents::plan::Plan;
+use crate::agents::shell_sandbox::ShellSandbox;
 use crate::config::ExtensionConfig;
+use crate::conversation::message::Message;
+use crate::providers::base::ProviderUsage;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use utoipa::ToSchema;
 
 /// Extension data containing all extension states
@@ -113,6 +118,130 @@ impl EnabledExtensionsState {
     }
 }
 
+/// Turns the agent has taken in a session under a budget, for enforcing its turn limit
+/// across replies
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetState {
+    pub turns_taken: u32,
+    /// The budget the session last ran under, which its subagents spend as well
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<SessionBudget>,
+}
+
+impl ExtensionState for BudgetState {
+    const EXTENSION_NAME: &'static str = "budget";
+    const VERSION: &'static str = "v0";
+}
+
+/// Tokens and cost spent on one model
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    pub requests: u64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub total_tokens: i64,
+    /// Cost in USD of the requests whose cost is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+}
+
+/// Usage of a session attributed to the models that served it, for sessions that switch
+/// models as they go
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelUsageState {
+    pub models: BTreeMap<String, ModelUsage>,
+}
+
+impl ExtensionState for ModelUsageState {
+    const EXTENSION_NAME: &'static str = "model_usage";
+    const VERSION: &'static str = "v0";
+}
+
+impl ModelUsage {
+    /// Count one request
+    pub fn add(&mut self, usage: &ProviderUsage) {
+        let tokens = |count: Option<i32>| count.unwrap_or(0).max(0) as i64;
+        self.requests += 1;
+        self.input_tokens += tokens(usage.usage.input_tokens);
+        self.output_tokens += tokens(usage.usage.output_tokens);
+        self.total_tokens += tokens(usage.usage.total_tokens);
+        if let Some(cost) = usage.cost {
+            self.cost = Some(self.cost.unwrap_or(0.0) + cost);
+        }
+    }
+}
+
+impl ModelUsageState {
+    /// Count a request served by `usage.model`
+    pub fn record(&mut self, usage: &ProviderUsage) {
+        self.models
+            .entry(usage.model.clone())
+            .or_default()
+            .add(usage);
+    }
+}
+
+/// Completions that extensions requested through MCP sampling, attributed to the extension
+/// that requested them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SamplingUsageState {
+    pub extensions: BTreeMap<String, ModelUsage>,
+}
+
+impl ExtensionState for SamplingUsageState {
+    const EXTENSION_NAME: &'static str = "sampling_usage";
+    const VERSION: &'static str = "v0";
+}
+
+impl SamplingUsageState {
+    /// Count a completion requested by `extension_name`
+    pub fn record(&mut self, extension_name: &str, usage: &ProviderUsage) {
+        self.extensions
+            .entry(extension_name.to_string())
+            .or_default()
+            .add(usage);
+    }
+}
+
+/// The sandbox a session chose for its shell commands, overriding the configured one
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShellSandboxState {
+    /// None if the session's commands run on the host
+    pub sandbox: Option<ShellSandbox>,
+}
+
+impl ExtensionState for ShellSandboxState {
+    const EXTENSION_NAME: &'static str = "shell_sandbox";
+    const VERSION: &'static str = "v0";
+}
+
+/// The state of the run under way in a session that isn't in its conversation yet, saved at
+/// turn boundaries so the run can be resumed after a crash
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunCheckpoint {
+    /// The plan the agent is carrying out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<Plan>,
+    /// Tool calls of the turn under way, in the order the model made them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending_tool_calls: Vec<PendingToolCall>,
+}
+
+impl ExtensionState for RunCheckpoint {
+    const EXTENSION_NAME: &'static str = "run_checkpoint";
+    const VERSION: &'static str = "v0";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingToolCall {
+    pub request_id: String,
+    /// The assistant message making the call
+    pub request: Message,
+    /// The user message with its result, once the call finished
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<Message>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -188,4 +317,24 @@ mod tests {
             Some(&json!({"key": "value"}))
         );
     }
+
+    #[test]
+    fn test_model_usage_state() {
+        use crate::providers::base::Usage;
+
+        let mut state = ModelUsageState::default();
+        let usage = |model: &str, cost| {
+            ProviderUsage::new(model.to_string(), Usage::new(Some(10), Some(5), Some(15)))
+                .with_cost(cost)
+        };
+        state.record(&usage("small", Some(0.01)));
+        state.record(&usage("small", None));
+        state.record(&usage("large", None));
+
+        let small = &state.models["small"];
+        assert_eq!(small.requests, 2);
+        assert_eq!(small.total_tokens, 30);
+        assert_eq!(small.cost, Some(0.01));
+        assert_eq!(state.models["large"].cost, None);
+    }
 }
@@ -1,9 +1,25 @@
 mod chat_history_search;
+pub mod conversation_store;
 mod diagnostics;
 pub mod extension_data;
 mod legacy;
+#[cfg(feature = "postgres")]
+pub mod postgres_conversation_store;
+#[cfg(feature = "s3-archive")]
+pub mod s3_conversation_archive;
 pub mod session_manager;
 
+pub use conversation_store::{ConversationStore, SqliteConversationStore};
 pub use diagnostics::generate_diagnostics;
-pub use extension_data::{EnabledExtensionsState, ExtensionData, ExtensionState, TodoState};
-pub use session_manager::{Session, SessionInsights, SessionManager, SessionType};
+pub use extension_data::{
+    BudgetState, EnabledExtensionsState, ExtensionData, ExtensionState, ModelUsage,
+    ModelUsageState, PendingToolCall, RunCheckpoint, SamplingUsageState, ShellSandboxState,
+    TodoState,
+};
+#[cfg(feature = "postgres")]
+pub use postgres_conversation_store::PostgresConversationStore;
+#[cfg(feature = "s3-archive")]
+pub use s3_conversation_archive::S3ConversationArchive;
+pub use session_manager::{
+    BranchComparison, Session, SessionInsights, SessionManager, SessionType,
+};
@@ -0,0 +1,479 @@
+//! A [`ConversationStore`] backed by PostgreSQL, for server deployments.
+//!
+//! Sessions are namespaced, so one database can serve many tenants: each store handle reads
+//! and writes only the sessions of its namespace. Writes to a session lock its row first, so
+//! several servers can append to the same session without interleaving or losing usage.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{PgPool, Postgres, Transaction};
+
+use super::conversation_store::{
+    role_from_str, role_to_string, usage_from_row, ConversationStore, StoredConversation,
+    StoredConversationSummary, UsageRow,
+};
+use crate::conversation::message::Message;
+use crate::conversation::Conversation;
+use crate::providers::base::Usage;
+
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// Arbitrary key for the advisory lock held while migrating, so concurrent servers starting
+/// up don't apply the same migration twice
+const MIGRATION_LOCK_KEY: i64 = 0x676f_6f73_6563_7331;
+
+/// Schema migrations, applied in order. Never edit a released entry, append a new one.
+const MIGRATIONS: &[&[&str]] = &[&[
+    r#"
+    CREATE TABLE goose_conversations (
+        namespace TEXT NOT NULL,
+        session_id TEXT NOT NULL,
+        input_tokens INTEGER,
+        output_tokens INTEGER,
+        total_tokens INTEGER,
+        cached_input_tokens INTEGER,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+        PRIMARY KEY (namespace, session_id)
+    )
+    "#,
+    r#"
+    CREATE TABLE goose_conversation_messages (
+        namespace TEXT NOT NULL,
+        session_id TEXT NOT NULL,
+        seq BIGINT NOT NULL,
+        message_id TEXT,
+        role TEXT NOT NULL,
+        content JSONB NOT NULL,
+        created_timestamp BIGINT NOT NULL,
+        metadata JSONB,
+        PRIMARY KEY (namespace, session_id, seq),
+        FOREIGN KEY (namespace, session_id)
+            REFERENCES goose_conversations (namespace, session_id) ON DELETE CASCADE
+    )
+    "#,
+    "CREATE INDEX idx_goose_conversations_updated ON goose_conversations (namespace, updated_at DESC)",
+]];
+
+type ConversationRow = (
+    Option<i32>,
+    Option<i32>,
+    Option<i32>,
+    Option<i32>,
+    DateTime<Utc>,
+    DateTime<Utc>,
+);
+
+type SummaryRow = (
+    String,
+    i64,
+    Option<i32>,
+    Option<i32>,
+    Option<i32>,
+    Option<i32>,
+    DateTime<Utc>,
+    DateTime<Utc>,
+);
+
+pub struct PostgresConversationStore {
+    pool: PgPool,
+    namespace: String,
+}
+
+impl PostgresConversationStore {
+    /// Connect to the database at `url` and bring its schema up to date. The store uses the
+    /// default namespace; see [`with_namespace`](Self::with_namespace).
+    pub async fn connect(url: &str) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .connect(url)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to connect to PostgreSQL: {}", e))?;
+        Self::from_pool(pool).await
+    }
+
+    /// Use an existing pool, bringing the schema up to date
+    pub async fn from_pool(pool: PgPool) -> Result<Self> {
+        let store = Self {
+            pool,
+            namespace: DEFAULT_NAMESPACE.to_string(),
+        };
+        store.run_migrations().await?;
+        Ok(store)
+    }
+
+    /// A handle on the sessions of `namespace`, sharing this store's connections
+    pub fn with_namespace(&self, namespace: impl Into<String>) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            namespace: namespace.into(),
+        }
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    async fn run_migrations(&self) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("SELECT pg_advisory_lock($1)")
+            .bind(MIGRATION_LOCK_KEY)
+            .execute(&mut *conn)
+            .await?;
+
+        let result = async {
+            sqlx::query(
+                r#"
+                CREATE TABLE IF NOT EXISTS goose_conversation_store_migrations (
+                    version INTEGER PRIMARY KEY,
+                    applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+                )
+            "#,
+            )
+            .execute(&mut *conn)
+            .await?;
+
+            let current = sqlx::query_scalar::<_, Option<i32>>(
+                "SELECT MAX(version) FROM goose_conversation_store_migrations",
+            )
+            .fetch_one(&mut *conn)
+            .await?
+            .unwrap_or(0);
+
+            for (idx, statements) in MIGRATIONS.iter().enumerate() {
+                let version = idx as i32 + 1;
+                if version <= current {
+                    continue;
+                }
+                let mut tx = sqlx::Connection::begin(&mut *conn).await?;
+                for statement in statements.iter().copied() {
+                    sqlx::query(statement).execute(&mut *tx).await?;
+                }
+                sqlx::query(
+                    "INSERT INTO goose_conversation_store_migrations (version) VALUES ($1)",
+                )
+                .bind(version)
+                .execute(&mut *tx)
+                .await?;
+                tx.commit().await?;
+            }
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+
+        sqlx::query("SELECT pg_advisory_unlock($1)")
+            .bind(MIGRATION_LOCK_KEY)
+            .execute(&mut *conn)
+            .await?;
+        result
+    }
+
+    /// Create the session if needed and lock its row until the transaction ends, returning
+    /// its usage
+    async fn lock_session(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        session_id: &str,
+    ) -> Result<Usage> {
+        sqlx::query(
+            r#"
+            INSERT INTO goose_conversations (namespace, session_id) VALUES ($1, $2)
+            ON CONFLICT (namespace, session_id) DO NOTHING
+        "#,
+        )
+        .bind(&self.namespace)
+        .bind(session_id)
+        .execute(&mut **tx)
+        .await?;
+
+        let usage = sqlx::query_as::<_, UsageRow>(
+            r#"
+            SELECT input_tokens, output_tokens, total_tokens, cached_input_tokens
+            FROM goose_conversations
+            WHERE namespace = $1 AND session_id = $2
+            FOR UPDATE
+        "#,
+        )
+        .bind(&self.namespace)
+        .bind(session_id)
+        .fetch_one(&mut **tx)
+        .await?;
+        Ok(usage_from_row(usage))
+    }
+
+    async fn set_usage(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        session_id: &str,
+        usage: Usage,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE goose_conversations
+            SET input_tokens = $3, output_tokens = $4, total_tokens = $5, cached_input_tokens = $6,
+                updated_at = now()
+            WHERE namespace = $1 AND session_id = $2
+        "#,
+        )
+        .bind(&self.namespace)
+        .bind(session_id)
+        .bind(usage.input_tokens)
+        .bind(usage.output_tokens)
+        .bind(usage.total_tokens)
+        .bind(usage.cached_input_tokens)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_message(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        session_id: &str,
+        seq: i64,
+        message: &Message,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO goose_conversation_messages
+                (namespace, session_id, seq, message_id, role, content, created_timestamp, metadata)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        )
+        .bind(&self.namespace)
+        .bind(session_id)
+        .bind(seq)
+        .bind(&message.id)
+        .bind(role_to_string(&message.role))
+        .bind(serde_json::to_value(&message.content)?)
+        .bind(message.created)
+        .bind(serde_json::to_value(&message.metadata)?)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ConversationStore for PostgresConversationStore {
+    async fn save(
+        &self,
+        session_id: &str,
+        conversation: &Conversation,
+        usage: Usage,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        self.lock_session(&mut tx, session_id).await?;
+
+        sqlx::query(
+            "DELETE FROM goose_conversation_messages WHERE namespace = $1 AND session_id = $2",
+        )
+        .bind(&self.namespace)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+        for (seq, message) in conversation.messages().iter().enumerate() {
+            self.insert_message(&mut tx, session_id, seq as i64 + 1, message)
+                .await?;
+        }
+        self.set_usage(&mut tx, session_id, usage).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn append(
+        &self,
+        session_id: &str,
+        message: &Message,
+        usage: Option<Usage>,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let stored = self.lock_session(&mut tx, session_id).await?;
+
+        let seq = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COALESCE(MAX(seq), 0) + 1
+            FROM goose_conversation_messages
+            WHERE namespace = $1 AND session_id = $2
+        "#,
+        )
+        .bind(&self.namespace)
+        .bind(session_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        self.insert_message(&mut tx, session_id, seq, message)
+            .await?;
+        let usage = match usage {
+            Some(usage) => stored + usage,
+            None => stored,
+        };
+        self.set_usage(&mut tx, session_id, usage).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Option<StoredConversation>> {
+        let row = sqlx::query_as::<_, ConversationRow>(
+            r#"
+            SELECT input_tokens, output_tokens, total_tokens, cached_input_tokens, created_at, updated_at
+            FROM goose_conversations
+            WHERE namespace = $1 AND session_id = $2
+        "#,
+        )
+        .bind(&self.namespace)
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some((input, output, total, cached, created_at, updated_at)) = row else {
+            return Ok(None);
+        };
+
+        let rows = sqlx::query_as::<_, (Option<String>, String, Value, i64, Option<Value>)>(
+            r#"
+            SELECT message_id, role, content, created_timestamp, metadata
+            FROM goose_conversation_messages
+            WHERE namespace = $1 AND session_id = $2
+            ORDER BY seq
+        "#,
+        )
+        .bind(&self.namespace)
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut messages = Vec::with_capacity(rows.len());
+        for (message_id, role, content, created_timestamp, metadata) in rows {
+            let Some(role) = role_from_str(&role) else {
+                continue;
+            };
+            let mut message =
+                Message::new(role, created_timestamp, serde_json::from_value(content)?);
+            message.id = message_id;
+            message.metadata = metadata
+                .and_then(|metadata| serde_json::from_value(metadata).ok())
+                .unwrap_or_default();
+            messages.push(message);
+        }
+
+        Ok(Some(StoredConversation {
+            session_id: session_id.to_string(),
+            conversation: Conversation::new_unvalidated(messages),
+            usage: usage_from_row((input, output, total, cached)),
+            created_at,
+            updated_at,
+        }))
+    }
+
+    async fn list(&self) -> Result<Vec<StoredConversationSummary>> {
+        let rows = sqlx::query_as::<_, SummaryRow>(
+            r#"
+            SELECT c.session_id, COUNT(m.seq), c.input_tokens, c.output_tokens, c.total_tokens,
+                   c.cached_input_tokens, c.created_at, c.updated_at
+            FROM goose_conversations c
+            LEFT JOIN goose_conversation_messages m
+                ON c.namespace = m.namespace AND c.session_id = m.session_id
+            WHERE c.namespace = $1
+            GROUP BY c.namespace, c.session_id
+            ORDER BY c.updated_at DESC, c.session_id
+        "#,
+        )
+        .bind(&self.namespace)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(session_id, count, input, output, total, cached, created_at, updated_at)| {
+                    StoredConversationSummary {
+                        session_id,
+                        message_count: count as usize,
+                        usage: usage_from_row((input, output, total, cached)),
+                        created_at,
+                        updated_at,
+                    }
+                },
+            )
+            .collect())
+    }
+
+    async fn delete(&self, session_id: &str) -> Result<bool> {
+        // Messages go with the session through the cascading foreign key
+        let deleted =
+            sqlx::query("DELETE FROM goose_conversations WHERE namespace = $1 AND session_id = $2")
+                .bind(&self.namespace)
+                .bind(session_id)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+        Ok(deleted > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// A store on the database named by GOOSE_TEST_POSTGRES_URL, in a namespace of its own so
+    /// tests don't see each other's sessions. None when the variable isn't set.
+    async fn test_store() -> Option<PostgresConversationStore> {
+        let url = std::env::var("GOOSE_TEST_POSTGRES_URL").ok()?;
+        let store = PostgresConversationStore::connect(&url).await.unwrap();
+        Some(store.with_namespace(uuid::Uuid::new_v4().to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_namespaces_are_isolated() {
+        let Some(store) = test_store().await else {
+            return;
+        };
+        let other = store.with_namespace(format!("{}-other", store.namespace()));
+
+        store
+            .append("s1", &Message::user().with_text("hello"), None)
+            .await
+            .unwrap();
+
+        assert!(store.load("s1").await.unwrap().is_some());
+        assert!(other.load("s1").await.unwrap().is_none());
+        assert!(other.list().await.unwrap().is_empty());
+        assert!(!other.delete("s1").await.unwrap());
+        assert!(store.delete("s1").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_appends() {
+        let Some(store) = test_store().await else {
+            return;
+        };
+        let store = Arc::new(store);
+
+        let handles: Vec<_> = (0..10)
+            .map(|i| {
+                let store = Arc::clone(&store);
+                tokio::spawn(async move {
+                    store
+                        .append(
+                            "s1",
+                            &Message::user().with_text(format!("message {}", i)),
+                            Some(Usage::new(Some(1), Some(1), None)),
+                        )
+                        .await
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+
+        let stored = store.load("s1").await.unwrap().unwrap();
+        assert_eq!(stored.conversation.messages().len(), 10);
+        assert_eq!(stored.usage.total_tokens, Some(20));
+        store.delete("s1").await.unwrap();
+    }
+}
@@ -0,0 +1,449 @@
+//! A [`ConversationStore`] that archives sessions to S3-compatible object storage.
+//!
+//! Each session is one gzip-compressed JSONL object: a header line with the session id, usage
+//! and timestamps, then one line per message. It is meant for long-term retention of completed
+//! sessions, saved once with [`ConversationStore::save`]; objects can't be appended to, so
+//! [`ConversationStore::append`] downloads and rewrites the whole object.
+//!
+//! GCS and other S3-compatible services work through `GOOSE_SESSION_ARCHIVE_ENDPOINT` (for GCS,
+//! `https://storage.googleapis.com` with HMAC keys as the AWS credentials).
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use aws_sdk_s3::error::ProvideErrorMetadata;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use super::conversation_store::{ConversationStore, StoredConversation, StoredConversationSummary};
+use crate::config::Config;
+use crate::conversation::message::Message;
+use crate::conversation::Conversation;
+use crate::providers::base::Usage;
+
+const OBJECT_SUFFIX: &str = ".jsonl.gz";
+const DEFAULT_PREFIX: &str = "goose/sessions";
+const METADATA_MESSAGE_COUNT: &str = "goose-message-count";
+const METADATA_USAGE: &str = "goose-usage";
+const METADATA_CREATED_AT: &str = "goose-created-at";
+
+/// How object keys are laid out under the prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveLayout {
+    /// `<prefix>/<session id>.jsonl.gz`
+    Flat,
+    /// `<prefix>/<yyyy>/<mm>/<dd>/<session id>.jsonl.gz`, dated by when the session started,
+    /// which suits lifecycle rules and date-ranged retrieval
+    Dated,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct S3ArchiveConfig {
+    pub bucket: String,
+    pub prefix: String,
+    pub layout: ArchiveLayout,
+    /// Endpoint of an S3-compatible service other than AWS
+    pub endpoint: Option<String>,
+    pub region: Option<String>,
+}
+
+impl S3ArchiveConfig {
+    /// Read GOOSE_SESSION_ARCHIVE_BUCKET, GOOSE_SESSION_ARCHIVE_PREFIX,
+    /// GOOSE_SESSION_ARCHIVE_LAYOUT, GOOSE_SESSION_ARCHIVE_ENDPOINT and
+    /// GOOSE_SESSION_ARCHIVE_REGION. None if no bucket is configured.
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        let bucket = config
+            .get_param::<String>("GOOSE_SESSION_ARCHIVE_BUCKET")
+            .ok()?;
+        Some(Self {
+            bucket,
+            prefix: config
+                .get_param("GOOSE_SESSION_ARCHIVE_PREFIX")
+                .unwrap_or_else(|_| DEFAULT_PREFIX.to_string()),
+            layout: config
+                .get_param("GOOSE_SESSION_ARCHIVE_LAYOUT")
+                .unwrap_or(ArchiveLayout::Flat),
+            endpoint: config.get_param("GOOSE_SESSION_ARCHIVE_ENDPOINT").ok(),
+            region: config.get_param("GOOSE_SESSION_ARCHIVE_REGION").ok(),
+        })
+    }
+
+    fn prefix(&self) -> String {
+        let prefix = self.prefix.trim_matches('/');
+        if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", prefix)
+        }
+    }
+
+    fn object_key(&self, session_id: &str, created_at: DateTime<Utc>) -> String {
+        match self.layout {
+            ArchiveLayout::Flat => format!("{}{}{}", self.prefix(), session_id, OBJECT_SUFFIX),
+            ArchiveLayout::Dated => format!(
+                "{}{}/{}{}",
+                self.prefix(),
+                created_at.format("%Y/%m/%d"),
+                session_id,
+                OBJECT_SUFFIX
+            ),
+        }
+    }
+
+    /// The session id of an object key under this archive's prefix
+    fn session_id<'a>(&self, key: &'a str) -> Option<&'a str> {
+        let name = key
+            .strip_prefix(&self.prefix())?
+            .strip_suffix(OBJECT_SUFFIX)?;
+        Some(name.rsplit('/').next().unwrap_or(name))
+    }
+}
+
+/// The first line of an archived session
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveHeader {
+    session_id: String,
+    usage: Usage,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    message_count: usize,
+}
+
+fn encode(stored: &StoredConversation) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let header = ArchiveHeader {
+        session_id: stored.session_id.clone(),
+        usage: stored.usage,
+        created_at: stored.created_at,
+        updated_at: stored.updated_at,
+        message_count: stored.conversation.messages().len(),
+    };
+    serde_json::to_writer(&mut encoder, &header)?;
+    encoder.write_all(b"\n")?;
+    for message in stored.conversation.messages() {
+        serde_json::to_writer(&mut encoder, message)?;
+        encoder.write_all(b"\n")?;
+    }
+    Ok(encoder.finish()?)
+}
+
+fn decode(bytes: &[u8]) -> Result<StoredConversation> {
+    let mut lines = BufReader::new(GzDecoder::new(bytes)).lines();
+    let header: ArchiveHeader =
+        serde_json::from_str(&lines.next().context("Archived session is empty")??)?;
+    let mut messages = Vec::with_capacity(header.message_count);
+    for line in lines {
+        let line = line?;
+        if !line.trim().is_empty() {
+            messages.push(serde_json::from_str::<Message>(&line)?);
+        }
+    }
+    Ok(StoredConversation {
+        session_id: header.session_id,
+        conversation: Conversation::new_unvalidated(messages),
+        usage: header.usage,
+        created_at: header.created_at,
+        updated_at: header.updated_at,
+    })
+}
+
+fn created_at(metadata: &HashMap<String, String>) -> Option<DateTime<Utc>> {
+    let time = DateTime::parse_from_rfc3339(metadata.get(METADATA_CREATED_AT)?).ok()?;
+    Some(time.with_timezone(&Utc))
+}
+
+pub struct S3ConversationArchive {
+    client: Client,
+    config: S3ArchiveConfig,
+}
+
+impl S3ConversationArchive {
+    /// Connect with credentials from the standard AWS sources
+    pub async fn new(config: S3ArchiveConfig) -> Result<Self> {
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest());
+        if let Some(region) = &config.region {
+            loader = loader.region(aws_config::Region::new(region.clone()));
+        }
+        let sdk_config = loader.load().await;
+
+        let mut builder = aws_sdk_s3::config::Builder::from(&sdk_config);
+        if let Some(endpoint) = &config.endpoint {
+            // Most S3-compatible services don't support virtual-hosted bucket names
+            builder = builder.endpoint_url(endpoint).force_path_style(true);
+        }
+        Ok(Self::with_client(
+            Client::from_conf(builder.build()),
+            config,
+        ))
+    }
+
+    pub fn with_client(client: Client, config: S3ArchiveConfig) -> Self {
+        Self { client, config }
+    }
+
+    /// The key of the session's object, if it exists. With the dated layout the date isn't
+    /// known up front, so the prefix is searched.
+    async fn find_key(&self, session_id: &str) -> Result<Option<String>> {
+        if self.config.layout == ArchiveLayout::Flat {
+            let key = self.config.object_key(session_id, Utc::now());
+            return Ok(self.head(&key).await?.map(|_| key));
+        }
+
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.config.bucket)
+            .prefix(self.config.prefix())
+            .into_paginator()
+            .send();
+        while let Some(page) = pages.next().await {
+            let page = page.context("Failed to list archived sessions")?;
+            for object in page.contents() {
+                if let Some(key) = object.key() {
+                    if self.config.session_id(key) == Some(session_id) {
+                        return Ok(Some(key.to_string()));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// The object's user metadata, or None if it doesn't exist
+    async fn head(&self, key: &str) -> Result<Option<HashMap<String, String>>> {
+        match self
+            .client
+            .head_object()
+            .bucket(&self.config.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(output) => Ok(Some(output.metadata().cloned().unwrap_or_default())),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(None),
+            Err(e) => Err(anyhow::anyhow!(
+                "Failed to read archived session '{}': {}",
+                key,
+                e.code().unwrap_or("unknown error")
+            )),
+        }
+    }
+
+    async fn put(&self, stored: &StoredConversation) -> Result<()> {
+        let key = self
+            .config
+            .object_key(&stored.session_id, stored.created_at);
+        self.client
+            .put_object()
+            .bucket(&self.config.bucket)
+            .key(&key)
+            .content_type("application/gzip")
+            .metadata(
+                METADATA_MESSAGE_COUNT,
+                stored.conversation.messages().len().to_string(),
+            )
+            .metadata(METADATA_USAGE, serde_json::to_string(&stored.usage)?)
+            .metadata(METADATA_CREATED_AT, stored.created_at.to_rfc3339())
+            .body(ByteStream::from(encode(stored)?))
+            .send()
+            .await
+            .with_context(|| format!("Failed to archive session to '{}'", key))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ConversationStore for S3ConversationArchive {
+    async fn save(
+        &self,
+        session_id: &str,
+        conversation: &Conversation,
+        usage: Usage,
+    ) -> Result<()> {
+        // Keep the start date, which dated keys are derived from
+        let created_at = match self.find_key(session_id).await? {
+            Some(key) => self
+                .head(&key)
+                .await?
+                .and_then(|metadata| created_at(&metadata)),
+            None => None,
+        }
+        .unwrap_or_else(Utc::now);
+        self.put(&StoredConversation {
+            session_id: session_id.to_string(),
+            conversation: conversation.clone(),
+            usage,
+            created_at,
+            updated_at: Utc::now(),
+        })
+        .await
+    }
+
+    async fn append(
+        &self,
+        session_id: &str,
+        message: &Message,
+        usage: Option<Usage>,
+    ) -> Result<()> {
+        let now = Utc::now();
+        let mut stored = self
+            .load(session_id)
+            .await?
+            .unwrap_or_else(|| StoredConversation {
+                session_id: session_id.to_string(),
+                conversation: Conversation::empty(),
+                usage: Usage::default(),
+                created_at: now,
+                updated_at: now,
+            });
+        stored.conversation.push(message.clone());
+        if let Some(usage) = usage {
+            stored.usage += usage;
+        }
+        stored.updated_at = now;
+        self.put(&stored).await
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Option<StoredConversation>> {
+        let Some(key) = self.find_key(session_id).await? else {
+            return Ok(None);
+        };
+        let object = self
+            .client
+            .get_object()
+            .bucket(&self.config.bucket)
+            .key(&key)
+            .send()
+            .await
+            .with_context(|| format!("Failed to read archived session '{}'", key))?;
+        let bytes = object.body.collect().await?.into_bytes();
+        decode(&bytes).map(Some)
+    }
+
+    async fn list(&self) -> Result<Vec<StoredConversationSummary>> {
+        let mut summaries = Vec::new();
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.config.bucket)
+            .prefix(self.config.prefix())
+            .into_paginator()
+            .send();
+        while let Some(page) = pages.next().await {
+            let page = page.context("Failed to list archived sessions")?;
+            for object in page.contents() {
+                let Some(key) = object.key() else {
+                    continue;
+                };
+                let Some(session_id) = self.config.session_id(key) else {
+                    continue;
+                };
+                let Some(metadata) = self.head(key).await? else {
+                    continue;
+                };
+                let updated_at = object
+                    .last_modified()
+                    .and_then(|time| DateTime::from_timestamp(time.secs(), time.subsec_nanos()))
+                    .unwrap_or_default();
+                summaries.push(StoredConversationSummary {
+                    session_id: session_id.to_string(),
+                    message_count: metadata
+                        .get(METADATA_MESSAGE_COUNT)
+                        .and_then(|count| count.parse().ok())
+                        .unwrap_or(0),
+                    usage: metadata
+                        .get(METADATA_USAGE)
+                        .and_then(|usage| serde_json::from_str(usage).ok())
+                        .unwrap_or_default(),
+                    created_at: created_at(&metadata).unwrap_or(updated_at),
+                    updated_at,
+                });
+            }
+        }
+        summaries.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        Ok(summaries)
+    }
+
+    async fn delete(&self, session_id: &str) -> Result<bool> {
+        let Some(key) = self.find_key(session_id).await? else {
+            return Ok(false);
+        };
+        self.client
+            .delete_object()
+            .bucket(&self.config.bucket)
+            .key(&key)
+            .send()
+            .await
+            .with_context(|| format!("Failed to delete archived session '{}'", key))?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn config(layout: ArchiveLayout) -> S3ArchiveConfig {
+        S3ArchiveConfig {
+            bucket: "archive".to_string(),
+            prefix: "/compliance/goose/".to_string(),
+            layout,
+            endpoint: None,
+            region: None,
+        }
+    }
+
+    #[test]
+    fn test_object_keys() {
+        let created_at = Utc.with_ymd_and_hms(2025, 3, 7, 12, 0, 0).unwrap();
+
+        let flat = config(ArchiveLayout::Flat);
+        let key = flat.object_key("20250307_4", created_at);
+        assert_eq!(key, "compliance/goose/20250307_4.jsonl.gz");
+        assert_eq!(flat.session_id(&key), Some("20250307_4"));
+
+        let dated = config(ArchiveLayout::Dated);
+        let key = dated.object_key("20250307_4", created_at);
+        assert_eq!(key, "compliance/goose/2025/03/07/20250307_4.jsonl.gz");
+        assert_eq!(dated.session_id(&key), Some("20250307_4"));
+        assert_eq!(dated.session_id("other/20250307_4.jsonl.gz"), None);
+        assert_eq!(dated.session_id("compliance/goose/notes.txt"), None);
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let stored = StoredConversation {
+            session_id: "s1".to_string(),
+            conversation: Conversation::new_unvalidated(vec![
+                Message::user().with_text("hello"),
+                Message::assistant().with_text("hi"),
+            ]),
+            usage: Usage::new(Some(10), Some(2), None),
+            created_at: Utc.with_ymd_and_hms(2025, 3, 7, 12, 0, 0).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2025, 3, 7, 12, 5, 0).unwrap(),
+        };
+
+        let bytes = encode(&stored).unwrap();
+        let mut jsonl = String::new();
+        std::io::Read::read_to_string(&mut GzDecoder::new(bytes.as_slice()), &mut jsonl).unwrap();
+        assert_eq!(jsonl.lines().count(), 3);
+
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(decoded.session_id, "s1");
+        assert_eq!(decoded.conversation.messages().len(), 2);
+        assert_eq!(decoded.conversation.messages()[1].as_concat_text(), "hi");
+        assert_eq!(decoded.usage.total_tokens, Some(12));
+        assert_eq!(decoded.created_at, stored.created_at);
+        assert_eq!(decoded.updated_at, stored.updated_at);
+    }
+}
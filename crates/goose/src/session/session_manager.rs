@@ -19,7 +19,7 @@ use tokio::sync::OnceCell;
 use tracing::{info, warn};
 use utoipa::ToSchema;
 
-pub const CURRENT_SCHEMA_VERSION: i32 = 6;
+pub const CURRENT_SCHEMA_VERSION: i32 = 8;
 pub const SESSIONS_FOLDER: &str = "sessions";
 pub const DB_NAME: &str = "sessions.db";
 
@@ -88,6 +88,9 @@ pub struct Session {
     pub accumulated_total_tokens: Option<i32>,
     pub accumulated_input_tokens: Option<i32>,
     pub accumulated_output_tokens: Option<i32>,
+    /// Total cost of the session in USD, for models with known pricing
+    #[serde(default)]
+    pub accumulated_cost: Option<f64>,
     pub schedule_id: Option<String>,
     pub recipe: Option<Recipe>,
     pub user_recipe_values: Option<HashMap<String, String>>,
@@ -95,6 +98,26 @@ pub struct Session {
     pub message_count: usize,
     pub provider_name: Option<String>,
     pub model_config: Option<ModelConfig>,
+    /// Session this one was forked from with [`SessionManager::fork_session`]
+    #[serde(default)]
+    pub parent_session_id: Option<String>,
+    /// Number of leading messages this session shares with its parent
+    #[serde(default)]
+    pub fork_message_index: Option<usize>,
+}
+
+/// How two sessions' conversations relate, from [`SessionManager::compare_branches`]
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchComparison {
+    /// Leading messages the two conversations have in common
+    pub common_messages: usize,
+    /// Messages of the first session after the common ones
+    #[schema(value_type = Vec<Object>)]
+    pub left_only: Vec<Message>,
+    /// Messages of the second session after the common ones
+    #[schema(value_type = Vec<Object>)]
+    pub right_only: Vec<Message>,
 }
 
 pub struct SessionUpdateBuilder {
@@ -110,6 +133,7 @@ pub struct SessionUpdateBuilder {
     accumulated_total_tokens: Option<Option<i32>>,
     accumulated_input_tokens: Option<Option<i32>>,
     accumulated_output_tokens: Option<Option<i32>>,
+    accumulated_cost: Option<Option<f64>>,
     schedule_id: Option<Option<String>>,
     recipe: Option<Option<Recipe>>,
     user_recipe_values: Option<Option<HashMap<String, String>>>,
@@ -139,6 +163,7 @@ impl SessionUpdateBuilder {
             accumulated_total_tokens: None,
             accumulated_input_tokens: None,
             accumulated_output_tokens: None,
+            accumulated_cost: None,
             schedule_id: None,
             recipe: None,
             user_recipe_values: None,
@@ -210,6 +235,11 @@ impl SessionUpdateBuilder {
         self
     }
 
+    pub fn accumulated_cost(mut self, cost: Option<f64>) -> Self {
+        self.accumulated_cost = Some(cost);
+        self
+    }
+
     pub fn schedule_id(mut self, schedule_id: Option<String>) -> Self {
         self.schedule_id = Some(schedule_id);
         self
@@ -321,6 +351,28 @@ impl SessionManager {
             .await
     }
 
+    pub async fn fork_session(
+        session_id: &str,
+        message_index: usize,
+        new_name: String,
+    ) -> Result<Session> {
+        Self::instance()
+            .await?
+            .fork_session(session_id, message_index, new_name)
+            .await
+    }
+
+    pub async fn list_branches(session_id: &str) -> Result<Vec<Session>> {
+        Self::instance().await?.list_branches(session_id).await
+    }
+
+    pub async fn compare_branches(left_id: &str, right_id: &str) -> Result<BranchComparison> {
+        Self::instance()
+            .await?
+            .compare_branches(left_id, right_id)
+            .await
+    }
+
     pub async fn truncate_conversation(session_id: &str, timestamp: i64) -> Result<()> {
         Self::instance()
             .await?
@@ -408,6 +460,7 @@ impl Default for Session {
             accumulated_total_tokens: None,
             accumulated_input_tokens: None,
             accumulated_output_tokens: None,
+            accumulated_cost: None,
             schedule_id: None,
             recipe: None,
             user_recipe_values: None,
@@ -415,6 +468,8 @@ impl Default for Session {
             message_count: 0,
             provider_name: None,
             model_config: None,
+            parent_session_id: None,
+            fork_message_index: None,
         }
     }
 }
@@ -472,6 +527,7 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Session {
             accumulated_total_tokens: row.try_get("accumulated_total_tokens")?,
             accumulated_input_tokens: row.try_get("accumulated_input_tokens")?,
             accumulated_output_tokens: row.try_get("accumulated_output_tokens")?,
+            accumulated_cost: row.try_get("accumulated_cost").ok().flatten(),
             schedule_id: row.try_get("schedule_id")?,
             recipe,
             user_recipe_values,
@@ -479,6 +535,12 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Session {
             message_count: row.try_get("message_count").unwrap_or(0) as usize,
             provider_name: row.try_get("provider_name").ok().flatten(),
             model_config,
+            parent_session_id: row.try_get("parent_session_id").ok().flatten(),
+            fork_message_index: row
+                .try_get::<Option<i64>, _>("fork_message_index")
+                .ok()
+                .flatten()
+                .map(|index| index as usize),
         })
     }
 }
@@ -564,11 +626,14 @@ impl SessionStorage {
                 accumulated_total_tokens INTEGER,
                 accumulated_input_tokens INTEGER,
                 accumulated_output_tokens INTEGER,
+                accumulated_cost REAL,
                 schedule_id TEXT,
                 recipe_json TEXT,
                 user_recipe_values_json TEXT,
                 provider_name TEXT,
-                model_config_json TEXT
+                model_config_json TEXT,
+                parent_session_id TEXT,
+                fork_message_index INTEGER
             )
         "#,
         )
@@ -676,9 +741,9 @@ impl SessionStorage {
             id, name, user_set_name, session_type, working_dir, created_at, updated_at, extension_data,
             total_tokens, input_tokens, output_tokens,
             accumulated_total_tokens, accumulated_input_tokens, accumulated_output_tokens,
-            schedule_id, recipe_json, user_recipe_values_json,
+            accumulated_cost, schedule_id, recipe_json, user_recipe_values_json,
             provider_name, model_config_json
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         )
             .bind(&session.id)
@@ -695,6 +760,7 @@ impl SessionStorage {
             .bind(session.accumulated_total_tokens)
             .bind(session.accumulated_input_tokens)
             .bind(session.accumulated_output_tokens)
+            .bind(session.accumulated_cost)
             .bind(&session.schedule_id)
             .bind(recipe_json)
             .bind(user_recipe_values_json)
@@ -843,6 +909,32 @@ impl SessionStorage {
                 .execute(&self.pool)
                 .await?;
             }
+            7 => {
+                sqlx::query(
+                    r#"
+                    ALTER TABLE sessions ADD COLUMN accumulated_cost REAL
+                "#,
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+            8 => {
+                sqlx::query(
+                    r#"
+                    ALTER TABLE sessions ADD COLUMN parent_session_id TEXT
+                "#,
+                )
+                .execute(&self.pool)
+                .await?;
+
+                sqlx::query(
+                    r#"
+                    ALTER TABLE sessions ADD COLUMN fork_message_index INTEGER
+                "#,
+                )
+                .execute(&self.pool)
+                .await?;
+            }
             _ => {
                 anyhow::bail!("Unknown migration version: {}", version);
             }
@@ -897,8 +989,8 @@ impl SessionStorage {
         SELECT id, working_dir, name, description, user_set_name, session_type, created_at, updated_at, extension_data,
                total_tokens, input_tokens, output_tokens,
                accumulated_total_tokens, accumulated_input_tokens, accumulated_output_tokens,
-               schedule_id, recipe_json, user_recipe_values_json,
-               provider_name, model_config_json
+               accumulated_cost, schedule_id, recipe_json, user_recipe_values_json,
+               provider_name, model_config_json, parent_session_id, fork_message_index
         FROM sessions
         WHERE id = ?
     "#,
@@ -956,6 +1048,7 @@ impl SessionStorage {
             builder.accumulated_output_tokens,
             "accumulated_output_tokens"
         );
+        add_update!(builder.accumulated_cost, "accumulated_cost");
         add_update!(builder.schedule_id, "schedule_id");
         add_update!(builder.recipe, "recipe_json");
         add_update!(builder.user_recipe_values, "user_recipe_values_json");
@@ -1004,6 +1097,9 @@ impl SessionStorage {
         if let Some(aot) = builder.accumulated_output_tokens {
             q = q.bind(aot);
         }
+        if let Some(cost) = builder.accumulated_cost {
+            q = q.bind(cost);
+        }
         if let Some(sid) = builder.schedule_id {
             q = q.bind(sid);
         }
@@ -1140,8 +1236,8 @@ impl SessionStorage {
             SELECT s.id, s.working_dir, s.name, s.description, s.user_set_name, s.session_type, s.created_at, s.updated_at, s.extension_data,
                    s.total_tokens, s.input_tokens, s.output_tokens,
                    s.accumulated_total_tokens, s.accumulated_input_tokens, s.accumulated_output_tokens,
-                   s.schedule_id, s.recipe_json, s.user_recipe_values_json,
-                   s.provider_name, s.model_config_json,
+                   s.accumulated_cost, s.schedule_id, s.recipe_json, s.user_recipe_values_json,
+                   s.provider_name, s.model_config_json, s.parent_session_id, s.fork_message_index,
                    COUNT(m.id) as message_count
             FROM sessions s
             INNER JOIN messages m ON s.id = m.session_id
@@ -1233,6 +1329,7 @@ impl SessionStorage {
             .accumulated_total_tokens(import.accumulated_total_tokens)
             .accumulated_input_tokens(import.accumulated_input_tokens)
             .accumulated_output_tokens(import.accumulated_output_tokens)
+            .accumulated_cost(import.accumulated_cost)
             .schedule_id(import.schedule_id)
             .recipe(import.recipe)
             .user_recipe_values(import.user_recipe_values);
@@ -1278,6 +1375,105 @@ impl SessionStorage {
         self.get_session(&new_session.id, true).await
     }
 
+    /// A new session continuing from the first `message_index` messages of `session_id`
+    async fn fork_session(
+        &self,
+        session_id: &str,
+        message_index: usize,
+        new_name: String,
+    ) -> Result<Session> {
+        let parent = self.get_session(session_id, true).await?;
+        let conversation = parent
+            .conversation
+            .unwrap_or_default()
+            .fork_at(message_index)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Cannot fork session {} at message {} of {}",
+                    session_id,
+                    message_index,
+                    parent.message_count
+                )
+            })?;
+
+        let fork = self
+            .create_session(parent.working_dir.clone(), new_name, parent.session_type)
+            .await?;
+
+        let builder = SessionUpdateBuilder::new(fork.id.clone())
+            .extension_data(parent.extension_data)
+            .schedule_id(parent.schedule_id)
+            .recipe(parent.recipe)
+            .user_recipe_values(parent.user_recipe_values);
+        let builder = match parent.provider_name {
+            Some(provider_name) => builder.provider_name(provider_name),
+            None => builder,
+        };
+        let builder = match parent.model_config {
+            Some(model_config) => builder.model_config(model_config),
+            None => builder,
+        };
+        self.apply_update(builder).await?;
+
+        sqlx::query(
+            "UPDATE sessions SET parent_session_id = ?, fork_message_index = ? WHERE id = ?",
+        )
+        .bind(session_id)
+        .bind(message_index as i64)
+        .bind(&fork.id)
+        .execute(&self.pool)
+        .await?;
+
+        self.replace_conversation(&fork.id, &conversation).await?;
+        self.get_session(&fork.id, true).await
+    }
+
+    /// Every session in the fork tree `session_id` belongs to, oldest first
+    async fn list_branches(&self, session_id: &str) -> Result<Vec<Session>> {
+        sqlx::query_as::<_, Session>(
+            r#"
+            WITH RECURSIVE
+                ancestors(id, parent_session_id) AS (
+                    SELECT id, parent_session_id FROM sessions WHERE id = ?
+                    UNION
+                    SELECT s.id, s.parent_session_id
+                    FROM sessions s JOIN ancestors a ON s.id = a.parent_session_id
+                ),
+                tree(id) AS (
+                    SELECT id FROM ancestors WHERE parent_session_id IS NULL
+                    UNION
+                    SELECT s.id FROM sessions s JOIN tree t ON s.parent_session_id = t.id
+                )
+            SELECT s.id, s.working_dir, s.name, s.description, s.user_set_name, s.session_type, s.created_at, s.updated_at, s.extension_data,
+                   s.total_tokens, s.input_tokens, s.output_tokens,
+                   s.accumulated_total_tokens, s.accumulated_input_tokens, s.accumulated_output_tokens,
+                   s.accumulated_cost, s.schedule_id, s.recipe_json, s.user_recipe_values_json,
+                   s.provider_name, s.model_config_json, s.parent_session_id, s.fork_message_index,
+                   COUNT(m.id) as message_count
+            FROM sessions s
+            LEFT JOIN messages m ON s.id = m.session_id
+            WHERE s.id IN (SELECT id FROM tree)
+            GROUP BY s.id
+            ORDER BY s.created_at, s.id
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    async fn compare_branches(&self, left_id: &str, right_id: &str) -> Result<BranchComparison> {
+        let left = self.get_conversation(left_id).await?;
+        let right = self.get_conversation(right_id).await?;
+        let common_messages = left.common_prefix_len(&right);
+        Ok(BranchComparison {
+            common_messages,
+            left_only: left.messages()[common_messages..].to_vec(),
+            right_only: right.messages()[common_messages..].to_vec(),
+        })
+    }
+
     async fn truncate_conversation(&self, session_id: &str, timestamp: i64) -> Result<()> {
         sqlx::query("DELETE FROM messages WHERE session_id = ? AND created_timestamp >= ?")
             .bind(session_id)
@@ -1492,6 +1688,69 @@ mod tests {
         assert_eq!(conversation.messages()[1].role, Role::Assistant);
     }
 
+    #[tokio::test]
+    async fn test_fork_and_compare_branches() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test_fork.db");
+        let storage = Arc::new(SessionStorage::create(&db_path).await.unwrap());
+
+        let root = storage
+            .create_session(
+                PathBuf::from("/tmp/test"),
+                "root".to_string(),
+                SessionType::User,
+            )
+            .await
+            .unwrap();
+        for text in ["first", "reply", "second", "second reply"] {
+            let message = if text.contains("reply") {
+                Message::assistant().with_text(text)
+            } else {
+                Message::user().with_text(text)
+            };
+            storage.add_message(&root.id, &message).await.unwrap();
+        }
+
+        let fork = storage
+            .fork_session(&root.id, 2, "fork".to_string())
+            .await
+            .unwrap();
+        assert_eq!(fork.parent_session_id.as_deref(), Some(root.id.as_str()));
+        assert_eq!(fork.fork_message_index, Some(2));
+        assert_eq!(fork.message_count, 2);
+        storage
+            .add_message(&fork.id, &Message::user().with_text("another approach"))
+            .await
+            .unwrap();
+
+        let nested = storage
+            .fork_session(&fork.id, 3, "nested".to_string())
+            .await
+            .unwrap();
+        assert!(storage
+            .fork_session(&root.id, 5, "too far".to_string())
+            .await
+            .is_err());
+
+        let branches: Vec<_> = storage
+            .list_branches(&nested.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|session| session.id)
+            .collect();
+        assert_eq!(branches, [root.id.clone(), fork.id.clone(), nested.id]);
+
+        let comparison = storage.compare_branches(&root.id, &fork.id).await.unwrap();
+        assert_eq!(comparison.common_messages, 2);
+        assert_eq!(comparison.left_only.len(), 2);
+        assert_eq!(comparison.right_only.len(), 1);
+        assert_eq!(
+            comparison.right_only[0].as_concat_text(),
+            "another approach"
+        );
+    }
+
     #[tokio::test]
     async fn test_import_session_with_description_field() {
         const OLD_FORMAT_JSON: &str = r#"{
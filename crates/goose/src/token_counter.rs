@@ -1,17 +1,42 @@
+//! Counting tokens the way a model's tokenizer would.
+//!
+//! [`create_token_counter_for_model`] picks the tokenizer by model name: OpenAI's encodings via
+//! tiktoken, an approximation for Claude, whose tokenizer isn't published, and SentencePiece for
+//! models given a vocabulary under GOOSE_SENTENCEPIECE_VOCABS, which maps model name patterns to
+//! `.vocab` files as shipped alongside a SentencePiece `tokenizer.model`:
+//!
+//! ```yaml
+//! GOOSE_SENTENCEPIECE_VOCABS:
+//!   gemma: /models/gemma/tokenizer.vocab
+//! ```
+//!
+//! Other models are counted with o200k_base.
+
 use ahash::AHasher;
 use dashmap::DashMap;
+use once_cell::sync::Lazy;
 use rmcp::model::Tool;
+use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tiktoken_rs::CoreBPE;
 use tokio::sync::OnceCell;
 
+use crate::config::Config;
 use crate::conversation::message::Message;
 
-static TOKENIZER: OnceCell<Arc<CoreBPE>> = OnceCell::const_new();
+pub const SENTENCEPIECE_VOCABS_CONFIG_KEY: &str = "GOOSE_SENTENCEPIECE_VOCABS";
+
+static O200K_BASE: OnceCell<Arc<CoreBPE>> = OnceCell::const_new();
+static CL100K_BASE: OnceCell<Arc<CoreBPE>> = OnceCell::const_new();
+static SENTENCEPIECE_VOCABS: Lazy<DashMap<PathBuf, Arc<SentencePiece>>> = Lazy::new(DashMap::new);
 
 const MAX_TOKEN_CACHE_SIZE: usize = 10_000;
 
+/// Claude splits text into somewhat more tokens than cl100k_base does
+const CLAUDE_TOKENS_PER_CL100K_TOKEN: f64 = 1.1;
+
 // token use for various bits of a tool calls:
 const FUNC_INIT: usize = 7;
 const PROP_INIT: usize = 3;
@@ -20,18 +45,169 @@ const ENUM_INIT: isize = -3;
 const ENUM_ITEM: usize = 3;
 const FUNC_END: usize = 12;
 
+/// Splits text the way some model does, to count the tokens it would take
+pub trait Tokenizer: Send + Sync {
+    fn count(&self, text: &str) -> usize;
+}
+
+impl Tokenizer for CoreBPE {
+    fn count(&self, text: &str) -> usize {
+        self.encode_with_special_tokens(text).len()
+    }
+}
+
+struct ClaudeApproximation(Arc<CoreBPE>);
+
+impl Tokenizer for ClaudeApproximation {
+    fn count(&self, text: &str) -> usize {
+        (self.0.count(text) as f64 * CLAUDE_TOKENS_PER_CL100K_TOKEN).ceil() as usize
+    }
+}
+
+/// The tokenizers goose knows without being given a vocabulary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenizerKind {
+    O200kBase,
+    Cl100kBase,
+    Claude,
+}
+
+impl TokenizerKind {
+    pub fn for_model(model_name: &str) -> Self {
+        let name = model_name.to_lowercase();
+        let name = name.rsplit('/').next().unwrap_or_default();
+        if name.contains("claude") {
+            Self::Claude
+        } else if ["gpt-4o", "gpt-4.1", "gpt-4.5", "gpt-5", "chatgpt"]
+            .iter()
+            .any(|p| name.contains(p))
+            || ["o1", "o3", "o4"].iter().any(|p| name.starts_with(p))
+        {
+            Self::O200kBase
+        } else if ["gpt-4", "gpt-3.5", "text-embedding"]
+            .iter()
+            .any(|p| name.contains(p))
+        {
+            Self::Cl100kBase
+        } else {
+            Self::O200kBase
+        }
+    }
+}
+
+/// A SentencePiece unigram model, read from its `.vocab` file of tab-separated pieces and scores
+pub struct SentencePiece {
+    scores: HashMap<String, f32>,
+    max_piece_chars: usize,
+    /// Below any piece, so characters missing from the vocab are a last resort
+    unknown_score: f32,
+}
+
+/// SentencePiece's stand-in for spaces, also put before the first word
+const WORD_BOUNDARY: char = '\u{2581}';
+
+impl SentencePiece {
+    pub fn from_vocab(vocab: &str) -> Result<Self, String> {
+        let mut scores = HashMap::new();
+        for (number, line) in vocab.lines().enumerate() {
+            let Some((piece, score)) = line.split_once('\t') else {
+                continue;
+            };
+            let score = score
+                .trim()
+                .parse::<f32>()
+                .map_err(|_| format!("Invalid score on line {} of vocab", number + 1))?;
+            // Control pieces such as <s> never match text
+            if !(piece.starts_with('<') && piece.ends_with('>')) {
+                scores.insert(piece.to_string(), score);
+            }
+        }
+        if scores.is_empty() {
+            return Err("Vocab has no pieces".to_string());
+        }
+        let max_piece_chars = scores.keys().map(|p| p.chars().count()).max().unwrap_or(1);
+        let unknown_score = scores.values().copied().fold(0.0, f32::min) - 10.0;
+        Ok(Self {
+            scores,
+            max_piece_chars,
+            unknown_score,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let vocab = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_vocab(&vocab)
+    }
+}
+
+impl Tokenizer for SentencePiece {
+    /// The pieces in the most likely segmentation of `text`. Characters the vocab lacks take a
+    /// token per UTF-8 byte, as with byte fallback.
+    fn count(&self, text: &str) -> usize {
+        let chars: Vec<char> = std::iter::once(WORD_BOUNDARY)
+            .chain(
+                text.chars()
+                    .map(|c| if c == ' ' { WORD_BOUNDARY } else { c }),
+            )
+            .collect();
+
+        // (score, tokens) of the best segmentation of each prefix
+        let mut best = vec![(f32::NEG_INFINITY, 0); chars.len() + 1];
+        best[0] = (0.0, 0);
+        for end in 1..=chars.len() {
+            for start in end.saturating_sub(self.max_piece_chars)..end {
+                let piece: String = chars[start..end].iter().collect();
+                if let Some(score) = self.scores.get(&piece) {
+                    let candidate = (best[start].0 + score, best[start].1 + 1);
+                    if candidate.0 > best[end].0 {
+                        best[end] = candidate;
+                    }
+                }
+            }
+            if best[end].0 == f32::NEG_INFINITY {
+                let (score, tokens) = best[end - 1];
+                best[end] = (
+                    score + self.unknown_score,
+                    tokens + chars[end - 1].len_utf8(),
+                );
+            }
+        }
+        best[chars.len()].1
+    }
+}
+
 pub struct TokenCounter {
-    tokenizer: Arc<CoreBPE>,
+    tokenizer: Arc<dyn Tokenizer>,
     token_cache: Arc<DashMap<u64, usize>>,
 }
 
 impl TokenCounter {
     pub async fn new() -> Result<Self, String> {
-        let tokenizer = get_tokenizer().await?;
-        Ok(Self {
+        Ok(Self::with_tokenizer(
+            tiktoken(&O200K_BASE, tiktoken_rs::o200k_base).await?,
+        ))
+    }
+
+    pub async fn for_model(model_name: &str) -> Result<Self, String> {
+        if let Some(sentencepiece) = configured_sentencepiece(model_name) {
+            return Ok(Self::with_tokenizer(sentencepiece));
+        }
+        let tokenizer: Arc<dyn Tokenizer> = match TokenizerKind::for_model(model_name) {
+            TokenizerKind::O200kBase => tiktoken(&O200K_BASE, tiktoken_rs::o200k_base).await?,
+            TokenizerKind::Cl100kBase => tiktoken(&CL100K_BASE, tiktoken_rs::cl100k_base).await?,
+            TokenizerKind::Claude => Arc::new(ClaudeApproximation(
+                tiktoken(&CL100K_BASE, tiktoken_rs::cl100k_base).await?,
+            )),
+        };
+        Ok(Self::with_tokenizer(tokenizer))
+    }
+
+    pub fn with_tokenizer(tokenizer: Arc<dyn Tokenizer>) -> Self {
+        Self {
             tokenizer,
             token_cache: Arc::new(DashMap::new()),
-        })
+        }
     }
 
     pub fn count_tokens(&self, text: &str) -> usize {
@@ -43,8 +219,7 @@ impl TokenCounter {
             return *count;
         }
 
-        let tokens = self.tokenizer.encode_with_special_tokens(text);
-        let count = tokens.len();
+        let count = self.tokenizer.count(text);
 
         if self.token_cache.len() >= MAX_TOKEN_CACHE_SIZE {
             if let Some(entry) = self.token_cache.iter().next() {
@@ -182,26 +357,109 @@ impl TokenCounter {
     }
 }
 
-async fn get_tokenizer() -> Result<Arc<CoreBPE>, String> {
-    let tokenizer = TOKENIZER
-        .get_or_init(|| async {
-            match tiktoken_rs::o200k_base() {
-                Ok(bpe) => Arc::new(bpe),
-                Err(e) => panic!("Failed to initialize o200k_base tokenizer: {}", e),
-            }
-        })
-        .await;
-    Ok(tokenizer.clone())
+async fn tiktoken(
+    cell: &'static OnceCell<Arc<CoreBPE>>,
+    load: fn() -> anyhow::Result<CoreBPE>,
+) -> Result<Arc<CoreBPE>, String> {
+    cell.get_or_try_init(|| async {
+        load()
+            .map(Arc::new)
+            .map_err(|e| format!("Failed to initialize tokenizer: {}", e))
+    })
+    .await
+    .cloned()
+}
+
+/// The SentencePiece vocab configured for `model_name`, by its longest matching pattern
+fn configured_sentencepiece(model_name: &str) -> Option<Arc<dyn Tokenizer>> {
+    let vocabs: HashMap<String, PathBuf> = Config::global()
+        .get_param(SENTENCEPIECE_VOCABS_CONFIG_KEY)
+        .ok()?;
+    let path = vocabs
+        .into_iter()
+        .filter(|(pattern, _)| model_name.contains(pattern.as_str()))
+        .max_by_key(|(pattern, _)| pattern.len())?
+        .1;
+
+    if let Some(sentencepiece) = SENTENCEPIECE_VOCABS.get(&path) {
+        let sentencepiece: Arc<dyn Tokenizer> = sentencepiece.clone();
+        return Some(sentencepiece);
+    }
+    match SentencePiece::load(&path) {
+        Ok(sentencepiece) => {
+            let sentencepiece = Arc::new(sentencepiece);
+            SENTENCEPIECE_VOCABS.insert(path, sentencepiece.clone());
+            Some(sentencepiece as Arc<dyn Tokenizer>)
+        }
+        Err(e) => {
+            tracing::warn!(
+                "Counting tokens for {} without its vocab: {}",
+                model_name,
+                e
+            );
+            None
+        }
+    }
 }
 
 pub async fn create_token_counter() -> Result<TokenCounter, String> {
     TokenCounter::new().await
 }
 
+/// A counter using the tokenizer of `model_name`, or the closest one goose has
+pub async fn create_token_counter_for_model(model_name: &str) -> Result<TokenCounter, String> {
+    TokenCounter::for_model(model_name).await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_tokenizer_for_model() {
+        assert_eq!(
+            TokenizerKind::for_model("claude-sonnet-4-20250514"),
+            TokenizerKind::Claude
+        );
+        assert_eq!(
+            TokenizerKind::for_model("openai/gpt-4o-mini"),
+            TokenizerKind::O200kBase
+        );
+        assert_eq!(
+            TokenizerKind::for_model("o3-mini"),
+            TokenizerKind::O200kBase
+        );
+        assert_eq!(
+            TokenizerKind::for_model("gpt-4-turbo"),
+            TokenizerKind::Cl100kBase
+        );
+        assert_eq!(
+            TokenizerKind::for_model("qwen2.5-coder"),
+            TokenizerKind::O200kBase
+        );
+    }
+
+    #[tokio::test]
+    async fn test_counts_differ_by_model() {
+        let text = "The quick brown fox jumps over the lazy dog, again and again.";
+        let gpt4 = create_token_counter_for_model("gpt-4").await.unwrap();
+        let claude = create_token_counter_for_model("claude-3-5-haiku")
+            .await
+            .unwrap();
+        assert!(claude.count_tokens(text) > gpt4.count_tokens(text));
+    }
+
+    #[test]
+    fn test_sentencepiece_counts_best_segmentation() {
+        let vocab = "<unk>\t0\n<s>\t0\n\u{2581}hello\t-1\n\u{2581}hell\t-2\no\t-2\n\u{2581}world\t-1\n\u{2581}\t-3\nw\t-4\n";
+        let sentencepiece = SentencePiece::from_vocab(vocab).unwrap();
+        assert_eq!(sentencepiece.count("hello world"), 2);
+        assert_eq!(sentencepiece.count("hello w"), 3);
+        // Unknown characters take a token per byte
+        assert_eq!(sentencepiece.count("hello é"), 4);
+        assert!(SentencePiece::from_vocab("<s>\t0\n").is_err());
+    }
+
     #[tokio::test]
     async fn test_token_caching() {
         let counter = create_token_counter().await.unwrap();
@@ -0,0 +1,117 @@
+//! Spans for model calls, following the OpenTelemetry GenAI semantic conventions.
+//!
+//! Attributes are named `gen_ai.*` so backends receiving spans through the OTLP layer can show
+//! them as LLM traces; those without a convention yet use the `goose.` prefix. Tool executions
+//! (`Agent::dispatch_tool_call`) and agent turns (`Agent::reply`) carry the matching
+//! `execute_tool` and `invoke_agent` attributes on their own spans. The spans are ordinary
+//! `tracing` spans and cost nothing when no subscriber is listening.
+
+use std::time::Instant;
+
+use futures::StreamExt;
+use tracing::field::Empty;
+use tracing::{Instrument, Span};
+
+use crate::providers::base::{MessageStream, ProviderUsage};
+
+/// A span for one model call, `chat {model}`
+pub fn chat_span(provider: &str, model: &str) -> Span {
+    tracing::info_span!(
+        "gen_ai.chat",
+        otel.name = %format!("chat {}", model),
+        otel.kind = "client",
+        otel.status_code = Empty,
+        gen_ai.operation.name = "chat",
+        gen_ai.system = %provider,
+        gen_ai.request.model = %model,
+        gen_ai.response.model = Empty,
+        gen_ai.usage.input_tokens = Empty,
+        gen_ai.usage.output_tokens = Empty,
+        "error.type" = Empty,
+        goose.usage.cached_input_tokens = Empty,
+        goose.cost_usd = Empty,
+        goose.time_to_first_chunk_ms = Empty,
+        goose.latency_ms = Empty,
+        goose.chunks = Empty,
+    )
+}
+
+/// Record the model, token counts and cost of a response on a [`chat_span`]
+pub fn record_usage(span: &Span, usage: &ProviderUsage) {
+    span.record("gen_ai.response.model", usage.model.as_str());
+    if let Some(tokens) = usage.usage.input_tokens {
+        span.record("gen_ai.usage.input_tokens", tokens);
+    }
+    if let Some(tokens) = usage.usage.output_tokens {
+        span.record("gen_ai.usage.output_tokens", tokens);
+    }
+    if let Some(tokens) = usage.usage.cached_input_tokens {
+        span.record("goose.usage.cached_input_tokens", tokens);
+    }
+    if let Some(cost) = usage.cost {
+        span.record("goose.cost_usd", cost);
+    }
+}
+
+/// Record a failed call on a [`chat_span`]
+pub fn record_error(span: &Span, error_type: &str) {
+    span.record("otel.status_code", "ERROR");
+    span.record("error.type", error_type);
+}
+
+/// Keep `span` open until `stream` ends, recording the time to the first chunk, the total
+/// latency, usage, and an event per chunk
+pub fn instrument_stream(mut stream: MessageStream, span: Span) -> MessageStream {
+    let start = Instant::now();
+    Box::pin(async_stream::stream! {
+        let mut chunks: u64 = 0;
+        while let Some(item) = stream.next().instrument(span.clone()).await {
+            match &item {
+                Ok((message, usage)) => {
+                    chunks += 1;
+                    if chunks == 1 {
+                        span.record(
+                            "goose.time_to_first_chunk_ms",
+                            start.elapsed().as_millis() as u64,
+                        );
+                    }
+                    if let Some(usage) = usage.as_ref().filter(|usage| !usage.interim) {
+                        record_usage(&span, usage);
+                    }
+                    tracing::debug!(
+                        parent: &span,
+                        chunk = chunks,
+                        has_message = message.is_some(),
+                        "gen_ai.chunk"
+                    );
+                }
+                Err(e) => record_error(&span, e.telemetry_type()),
+            }
+            yield item;
+        }
+        span.record("goose.chunks", chunks);
+        span.record("goose.latency_ms", start.elapsed().as_millis() as u64);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::message::Message;
+    use crate::providers::base::{stream_from_single_message, Usage};
+    use futures::TryStreamExt;
+
+    #[tokio::test]
+    async fn test_instrument_stream_passes_items_through() {
+        let usage = ProviderUsage::new("model".to_string(), Usage::new(Some(3), Some(4), Some(7)));
+        let stream = stream_from_single_message(Message::assistant().with_text("hi"), usage);
+
+        let items: Vec<_> = instrument_stream(stream, chat_span("openai", "model"))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].0.as_ref().unwrap().as_concat_text(), "hi");
+        assert_eq!(items[0].1.as_ref().unwrap().usage.total_tokens, Some(7));
+    }
+}
@@ -152,7 +152,9 @@ impl BatchManager for LangfuseBatchManager {
     }
 }
 
-pub fn create_langfuse_observer() -> Option<ObservationLayer> {
+/// A batch manager for the Langfuse instance configured with LANGFUSE_PUBLIC_KEY,
+/// LANGFUSE_SECRET_KEY and LANGFUSE_URL, sending in the background. None if the keys aren't set.
+pub(crate) fn configured_batch_manager() -> Option<Arc<Mutex<LangfuseBatchManager>>> {
     let public_key = env::var("LANGFUSE_PUBLIC_KEY")
         .or_else(|_| env::var("LANGFUSE_INIT_PROJECT_PUBLIC_KEY"))
         .unwrap_or_default(); // Use empty string if not found
@@ -176,8 +178,12 @@ pub fn create_langfuse_observer() -> Option<ObservationLayer> {
         LangfuseBatchManager::spawn_sender(batch_manager.clone());
     }
 
+    Some(batch_manager)
+}
+
+pub fn create_langfuse_observer() -> Option<ObservationLayer> {
     Some(ObservationLayer {
-        batch_manager,
+        batch_manager: configured_batch_manager()?,
         span_tracker: Arc::new(Mutex::new(SpanTracker::new())),
     })
 }
@@ -0,0 +1,265 @@
+//! Report each completion to Langfuse as a generation.
+//!
+//! Unlike the span-based [`create_langfuse_observer`](super::create_langfuse_observer), which
+//! exports every tracing span, [`LangfuseMiddleware`] hooks provider calls and sends one trace
+//! and generation per call with its prompt, response, usage, latency and session id. Set
+//! `LANGFUSE_EXPORT=generations` alongside the usual Langfuse keys to use it instead of the
+//! observer.
+//!
+//! Streamed responses are reported when their usage arrives, which providers send with the last
+//! chunk.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+use super::langfuse_layer::{configured_batch_manager, LangfuseBatchManager};
+use super::observation_layer::BatchManager;
+use crate::config::Config;
+use crate::conversation::message::{Message, MessageContent};
+use crate::providers::base::ProviderUsage;
+use crate::providers::errors::ProviderError;
+use crate::providers::middleware::{register_middleware, ProviderMiddleware, ProviderRequest};
+use crate::redaction::redact_json;
+
+pub const LANGFUSE_EXPORT_CONFIG_KEY: &str = "LANGFUSE_EXPORT";
+
+/// Text and tool calls of a response, gathered across streamed chunks
+#[derive(Default)]
+struct Output {
+    text: String,
+    tool_calls: Vec<Value>,
+    first_chunk_at: Option<DateTime<Utc>>,
+}
+
+impl Output {
+    fn add(&mut self, message: &Message) {
+        self.first_chunk_at.get_or_insert_with(Utc::now);
+        self.text.push_str(&message.as_concat_text());
+        for content in &message.content {
+            if let MessageContent::ToolRequest(request) = content {
+                if let Ok(call) = &request.tool_call {
+                    self.tool_calls.push(json!({
+                        "id": request.id,
+                        "name": call.name,
+                        "arguments": call.arguments,
+                    }));
+                }
+            }
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "role": "assistant",
+            "content": self.text,
+            "tool_calls": self.tool_calls,
+        })
+    }
+}
+
+pub struct LangfuseMiddleware {
+    batch_manager: Arc<Mutex<LangfuseBatchManager>>,
+    streams: std::sync::Mutex<HashMap<String, Output>>,
+}
+
+impl LangfuseMiddleware {
+    pub fn new(batch_manager: Arc<Mutex<LangfuseBatchManager>>) -> Self {
+        Self {
+            batch_manager,
+            streams: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Trace and generation events for a finished call
+    fn events(
+        request: &ProviderRequest,
+        output: Option<&Output>,
+        usage: Option<&ProviderUsage>,
+        error: Option<&ProviderError>,
+    ) -> Vec<(&'static str, Value)> {
+        let end_time = Utc::now();
+        let latency_ms = (end_time - request.started_at).num_milliseconds();
+        let model = usage
+            .map(|usage| usage.model.clone())
+            .unwrap_or_else(|| request.model_config.model_name.clone());
+        let level = if error.is_some() { "ERROR" } else { "DEFAULT" };
+
+        let trace = json!({
+            "id": request.id,
+            "name": format!("{} {}", request.provider, model),
+            "sessionId": request.session_id,
+            "timestamp": request.started_at.to_rfc3339(),
+        });
+        let mut generation = json!({
+            "id": request.id,
+            "traceId": request.id,
+            "name": "chat",
+            "startTime": request.started_at.to_rfc3339(),
+            "endTime": end_time.to_rfc3339(),
+            "completionStartTime": output
+                .and_then(|output| output.first_chunk_at)
+                .map(|time| time.to_rfc3339()),
+            "model": model,
+            "input": {
+                "system": request.system,
+                "messages": request.messages,
+            },
+            "output": output.map(Output::to_json),
+            "metadata": {
+                "provider": request.provider,
+                "latency_ms": latency_ms,
+            },
+            "level": level,
+            "statusMessage": error.map(|e| e.to_string()),
+        });
+        if let Some(usage) = usage {
+            generation["usage"] = json!({
+                "input": usage.usage.input_tokens,
+                "output": usage.usage.output_tokens,
+                "total": usage.usage.total_tokens,
+                "unit": "TOKENS",
+            });
+            if let Some(cost) = usage.cost {
+                generation["costDetails"] = json!({ "total": cost });
+            }
+        }
+
+        redact_json(&mut generation);
+        vec![("trace-create", trace), ("generation-create", generation)]
+    }
+
+    async fn report(&self, events: Vec<(&'static str, Value)>) {
+        let mut batch = self.batch_manager.lock().await;
+        for (event_type, body) in events {
+            batch.add_event(event_type, body);
+        }
+    }
+}
+
+#[async_trait]
+impl ProviderMiddleware for LangfuseMiddleware {
+    async fn on_response(
+        &self,
+        request: &ProviderRequest,
+        message: &mut Message,
+        usage: &mut ProviderUsage,
+    ) -> Result<(), ProviderError> {
+        let mut output = Output::default();
+        output.add(message);
+        output.first_chunk_at = None;
+        self.report(Self::events(request, Some(&output), Some(usage), None))
+            .await;
+        Ok(())
+    }
+
+    async fn on_error(&self, request: &ProviderRequest, error: ProviderError) -> ProviderError {
+        let output = self.streams.lock().unwrap().remove(&request.id);
+        self.report(Self::events(request, output.as_ref(), None, Some(&error)))
+            .await;
+        error
+    }
+
+    fn on_stream_chunk(
+        &self,
+        request: &ProviderRequest,
+        message: &mut Option<Message>,
+        usage: &mut Option<ProviderUsage>,
+    ) -> Result<(), ProviderError> {
+        let mut streams = self.streams.lock().unwrap();
+        let output = streams.entry(request.id.clone()).or_default();
+        if let Some(message) = message {
+            output.add(message);
+        }
+        if let Some(usage) = usage.as_ref().filter(|usage| !usage.interim) {
+            let output = streams.remove(&request.id);
+            let events = Self::events(request, output.as_ref(), Some(usage), None);
+            let batch_manager = self.batch_manager.clone();
+            // Chunks are handled synchronously, so hand the events to a task
+            tokio::spawn(async move {
+                let mut batch = batch_manager.lock().await;
+                for (event_type, body) in events {
+                    batch.add_event(event_type, body);
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Register a [`LangfuseMiddleware`] for the configured Langfuse instance when
+/// `LANGFUSE_EXPORT` is `generations`. Returns whether it was registered, in which case the
+/// span-based observer shouldn't be installed as well.
+pub fn register_langfuse_middleware() -> bool {
+    let export: Option<String> = Config::global().get_param(LANGFUSE_EXPORT_CONFIG_KEY).ok();
+    if export.as_deref() != Some("generations") {
+        return false;
+    }
+    match configured_batch_manager() {
+        Some(batch_manager) => {
+            register_middleware(Arc::new(LangfuseMiddleware::new(batch_manager)));
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{Provider, Usage};
+    use crate::providers::middleware::MiddlewareProvider;
+    use crate::providers::mock::{MockProvider, MockResponse};
+
+    #[tokio::test]
+    async fn test_reports_generation_per_completion() {
+        let batch_manager = Arc::new(Mutex::new(LangfuseBatchManager::new(
+            "public".to_string(),
+            "secret".to_string(),
+            "http://langfuse.local".to_string(),
+        )));
+        let mock = MockProvider::new()
+            .with_response(MockResponse::text("hello").with_usage(Usage::new(
+                Some(12),
+                Some(3),
+                Some(15),
+            )))
+            .with_response(MockResponse::error(ProviderError::RequestFailed(
+                "boom".to_string(),
+            )));
+        let provider = MiddlewareProvider::new(
+            Arc::new(mock),
+            vec![Arc::new(LangfuseMiddleware::new(batch_manager.clone()))
+                as Arc<dyn ProviderMiddleware>],
+        );
+
+        crate::session_context::with_session_id(Some("session-1".to_string()), async {
+            let user = [Message::user().with_text("hi")];
+            provider.complete("be brief", &user, &[]).await.unwrap();
+            assert!(provider.complete("be brief", &user, &[]).await.is_err());
+        })
+        .await;
+
+        let batch = batch_manager.lock().await.batch.clone();
+        assert_eq!(batch.len(), 4);
+        assert_eq!(batch[0]["type"], "trace-create");
+        assert_eq!(batch[0]["body"]["sessionId"], "session-1");
+
+        let generation = &batch[1]["body"];
+        assert_eq!(batch[1]["type"], "generation-create");
+        assert_eq!(generation["traceId"], batch[0]["body"]["id"]);
+        assert_eq!(generation["model"], "mock-model");
+        assert_eq!(generation["input"]["system"], "be brief");
+        assert_eq!(generation["output"]["content"], "hello");
+        assert_eq!(generation["usage"]["total"], 15);
+        assert_eq!(generation["level"], "DEFAULT");
+
+        let failed = &batch[3]["body"];
+        assert_eq!(failed["level"], "ERROR");
+        assert!(failed["statusMessage"].as_str().unwrap().contains("boom"));
+    }
+}
@@ -1,5 +1,8 @@
+pub mod genai;
 pub mod langfuse_layer;
+pub mod langfuse_middleware;
 mod observation_layer;
+#[cfg(feature = "otel")]
 pub mod otlp_layer;
 pub mod rate_limiter;
 
@@ -7,6 +10,7 @@ pub use langfuse_layer::{create_langfuse_observer, LangfuseBatchManager};
 pub use observation_layer::{
     flatten_metadata, map_level, BatchManager, ObservationLayer, SpanData, SpanTracker,
 };
+#[cfg(feature = "otel")]
 pub use otlp_layer::{
     create_otlp_metrics_filter, create_otlp_tracing_filter, create_otlp_tracing_layer,
     init_otlp_metrics, init_otlp_tracing, init_otlp_tracing_only, shutdown_otlp, OtlpConfig,
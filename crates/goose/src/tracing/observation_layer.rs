@@ -1,3 +1,4 @@
+use crate::redaction::{is_secret_key, redact_json, REDACTED};
 use chrono::Utc;
 use serde_json::{json, Value};
 use std::collections::HashMap;
@@ -313,7 +314,12 @@ impl JsonVisitor {
         }
     }
 
-    fn insert_value(&mut self, field: &Field, value: Value) {
+    fn insert_value(&mut self, field: &Field, mut value: Value) {
+        if is_secret_key(field.name()) {
+            value = Value::String(REDACTED.to_string());
+        } else {
+            redact_json(&mut value);
+        }
         self.recorded_fields.insert(field.name().to_string(), value);
     }
 }
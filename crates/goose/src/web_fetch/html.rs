@@ -0,0 +1,549 @@
+//! Turning web pages into Markdown.
+//!
+//! Pages are reduced to their main content the way reader modes do: the largest `<article>`,
+//! else `<main>`, else `<body>`, without navigation, headers, footers, forms and scripts. What
+//! remains is written as Markdown, keeping headings, lists, links, emphasis and code.
+
+use url::Url;
+
+/// Elements whose contents are never part of the page's text
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "canvas", "iframe", "object", "head",
+    "title", "nav", "header", "footer", "aside", "form", "button", "select", "dialog",
+];
+/// Elements whose contents are kept as they are, up to their end tag
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "main",
+    "table",
+    "tr",
+    "dl",
+    "dt",
+    "dd",
+    "figure",
+    "figcaption",
+    "details",
+    "summary",
+    "address",
+    "center",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'a> {
+    Text(&'a str),
+    Start { name: String, attributes: &'a str },
+    End(String),
+}
+
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+    while !rest.is_empty() {
+        let Some(open) = rest.find('<') else {
+            tokens.push(Token::Text(rest));
+            break;
+        };
+        let (text, tag_start) = rest.split_at(open);
+        if !text.is_empty() {
+            tokens.push(Token::Text(text));
+        }
+        rest = tag_start;
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.split_once("-->").map_or("", |(_, after)| after);
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.split_once('>').map_or("", |(_, after)| after);
+            continue;
+        }
+
+        let is_end = rest.starts_with("</");
+        let tag = rest
+            .strip_prefix("</")
+            .or_else(|| rest.strip_prefix('<'))
+            .unwrap_or(rest);
+        let name_len = tag
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+            .unwrap_or(tag.len());
+        if name_len == 0 {
+            // A `<` that doesn't start a tag
+            let (less_than, after) = rest.split_at(1);
+            tokens.push(Token::Text(less_than));
+            rest = after;
+            continue;
+        }
+        let (name, after_name) = tag.split_at(name_len);
+        let name = name.to_ascii_lowercase();
+        let Some((attributes, after)) = after_name.split_once('>') else {
+            break;
+        };
+        let attributes = attributes.trim_end_matches('/');
+        rest = after;
+
+        if is_end {
+            tokens.push(Token::End(name));
+            continue;
+        }
+        let raw = RAW_TEXT_ELEMENTS.contains(&name.as_str());
+        tokens.push(Token::Start {
+            name: name.clone(),
+            attributes,
+        });
+        if raw {
+            let end = find_ascii_case_insensitive(rest, &format!("</{}", name));
+            let end = end.unwrap_or(rest.len());
+            let (text, after) = rest.split_at(end);
+            tokens.push(Token::Text(text));
+            tokens.push(Token::End(name));
+            rest = after.split_once('>').map_or("", |(_, after)| after);
+        }
+    }
+    tokens
+}
+
+fn find_ascii_case_insensitive(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// The value of attribute `name` in the attributes of a start tag
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes;
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return None;
+        }
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let (attribute_name, after) = rest.split_at(name_end);
+        rest = after.trim_start();
+        let value = if let Some(after_equals) = rest.strip_prefix('=') {
+            let after_equals = after_equals.trim_start();
+            let (value, remainder) = match after_equals.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let quoted = after_equals.strip_prefix(quote).unwrap_or(after_equals);
+                    quoted.split_once(quote).unwrap_or((quoted, ""))
+                }
+                _ => {
+                    let end = after_equals
+                        .find(char::is_whitespace)
+                        .unwrap_or(after_equals.len());
+                    after_equals.split_at(end)
+                }
+            };
+            rest = remainder;
+            Some(value)
+        } else {
+            None
+        };
+        if attribute_name.eq_ignore_ascii_case(name) {
+            return value.map(decode_entities);
+        }
+    }
+}
+
+pub(crate) fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((before, after)) = rest.split_once('&') {
+        decoded.push_str(before);
+        let entity_end = after
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '#')
+            .unwrap_or(after.len());
+        let (entity, after_entity) = after.split_at(entity_end);
+        let replacement = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            "mdash" => Some('—'),
+            "ndash" => Some('–'),
+            "hellip" => Some('…'),
+            "lsquo" => Some('‘'),
+            "rsquo" => Some('’'),
+            "ldquo" => Some('“'),
+            "rdquo" => Some('”'),
+            "copy" => Some('©'),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match replacement {
+            Some(c) => {
+                decoded.push(c);
+                rest = after_entity.strip_prefix(';').unwrap_or(after_entity);
+            }
+            None => {
+                decoded.push('&');
+                rest = after;
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn text_len(tokens: &[Token]) -> usize {
+    tokens
+        .iter()
+        .map(|token| match token {
+            Token::Text(text) => text.trim().len(),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// The tokens of the element starting at `start`, up to its end tag
+fn element<'a, 'b>(tokens: &'b [Token<'a>], start: usize) -> &'b [Token<'a>] {
+    let Token::Start { name, .. } = &tokens[start] else {
+        return &tokens[start..start];
+    };
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::Start { name: other, .. } if other == name => depth += 1,
+            Token::End(other) if other == name => {
+                depth -= 1;
+                if depth == 0 {
+                    return &tokens[start..=i];
+                }
+            }
+            _ => {}
+        }
+    }
+    &tokens[start..]
+}
+
+/// The part of the page with its main content
+fn main_content<'a, 'b>(tokens: &'b [Token<'a>]) -> &'b [Token<'a>] {
+    for candidate in ["article", "main", "body"] {
+        let largest = tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, token)| matches!(token, Token::Start { name, .. } if name == candidate))
+            .map(|(i, _)| element(tokens, i))
+            .max_by_key(|content| text_len(content));
+        if let Some(content) = largest.filter(|content| text_len(content) > 0) {
+            return content;
+        }
+    }
+    tokens
+}
+
+#[derive(Default)]
+struct MarkdownWriter {
+    output: String,
+    /// Whether the next text starts after a space, so runs of whitespace collapse into one
+    pending_space: bool,
+    /// Item numbers of the lists being written, `None` for unordered ones
+    lists: Vec<Option<usize>>,
+    links: Vec<Option<String>>,
+    in_pre: bool,
+}
+
+impl MarkdownWriter {
+    fn block_break(&mut self) {
+        self.pending_space = false;
+        let trimmed = self.output.trim_end_matches(' ');
+        self.output.truncate(trimmed.len());
+        if self.output.is_empty() {
+            return;
+        }
+        while !self.output.ends_with("\n\n") {
+            self.output.push('\n');
+        }
+    }
+
+    fn line_break(&mut self) {
+        self.pending_space = false;
+        let trimmed = self.output.trim_end_matches(' ');
+        self.output.truncate(trimmed.len());
+        if !self.output.is_empty() && !self.output.ends_with('\n') {
+            self.output.push('\n');
+        }
+    }
+
+    fn push_inline(&mut self, markup: &str) {
+        if self.pending_space && !self.output.ends_with(['\n', ' ']) {
+            self.output.push(' ');
+        }
+        self.pending_space = false;
+        self.output.push_str(markup);
+    }
+
+    fn text(&mut self, text: &str) {
+        let text = decode_entities(text);
+        if self.in_pre {
+            self.output.push_str(&text);
+            return;
+        }
+        if text.starts_with(char::is_whitespace) {
+            self.pending_space = true;
+        }
+        let mut words = text.split_whitespace().peekable();
+        while let Some(word) = words.next() {
+            self.push_inline(word);
+            if words.peek().is_some() {
+                self.pending_space = true;
+            }
+        }
+        if text.ends_with(char::is_whitespace) && !text.trim().is_empty() {
+            self.pending_space = true;
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut markdown = String::new();
+        let mut blank_lines = 0;
+        for line in self.output.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                blank_lines += 1;
+                if blank_lines > 1 {
+                    continue;
+                }
+            } else {
+                blank_lines = 0;
+            }
+            markdown.push_str(line);
+            markdown.push('\n');
+        }
+        markdown.trim().to_string()
+    }
+}
+
+fn resolve(base: Option<&Url>, href: &str) -> Option<String> {
+    if href.is_empty() || href.starts_with('#') || href.starts_with("javascript:") {
+        return None;
+    }
+    match base {
+        Some(base) => base.join(href).ok().map(String::from),
+        None => Some(href.to_string()),
+    }
+}
+
+/// The `<title>` of a page, if it has one
+pub fn title(html: &str) -> Option<String> {
+    let tokens = tokenize(html);
+    let start = tokens
+        .iter()
+        .position(|token| matches!(token, Token::Start { name, .. } if name == "title"))?;
+    match tokens.get(start + 1) {
+        Some(Token::Text(text)) => {
+            let title = decode_entities(text);
+            let words = title.split_whitespace().collect::<Vec<_>>();
+            (!words.is_empty()).then(|| words.join(" "))
+        }
+        _ => None,
+    }
+}
+
+/// The main content of `html` as Markdown, with links resolved against `base`
+pub fn to_markdown(html: &str, base: Option<&Url>) -> String {
+    let tokens = tokenize(html);
+    let mut writer = MarkdownWriter::default();
+    let mut skipping: Option<(String, usize)> = None;
+
+    for token in main_content(&tokens) {
+        if let Some((skipped, depth)) = &mut skipping {
+            match token {
+                Token::Start { name, .. } if name == &*skipped => *depth += 1,
+                Token::End(name) if name == &*skipped => {
+                    *depth -= 1;
+                    if *depth == 0 {
+                        skipping = None;
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match token {
+            Token::Text(text) => writer.text(text),
+            Token::Start { name, attributes } => {
+                let name = name.as_str();
+                if SKIPPED_ELEMENTS.contains(&name) {
+                    if !VOID_ELEMENTS.contains(&name) {
+                        skipping = Some((name.to_string(), 1));
+                    }
+                    continue;
+                }
+                match name {
+                    "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                        writer.block_break();
+                        let level = name
+                            .strip_prefix('h')
+                            .and_then(|level| level.parse().ok())
+                            .unwrap_or(1);
+                        writer.output.push_str(&"#".repeat(level));
+                        writer.output.push(' ');
+                    }
+                    "br" => writer.line_break(),
+                    "hr" => {
+                        writer.block_break();
+                        writer.output.push_str("---");
+                        writer.block_break();
+                    }
+                    "ul" => {
+                        writer.line_break();
+                        writer.lists.push(None);
+                    }
+                    "ol" => {
+                        writer.line_break();
+                        writer.lists.push(Some(0));
+                    }
+                    "li" => {
+                        writer.line_break();
+                        let depth = writer.lists.len().saturating_sub(1);
+                        writer.output.push_str(&"  ".repeat(depth));
+                        let marker = match writer.lists.last_mut() {
+                            Some(Some(number)) => {
+                                *number += 1;
+                                format!("{}. ", number)
+                            }
+                            _ => "- ".to_string(),
+                        };
+                        writer.output.push_str(&marker);
+                    }
+                    "blockquote" => {
+                        writer.block_break();
+                        writer.output.push_str("> ");
+                    }
+                    "pre" => {
+                        writer.block_break();
+                        writer.output.push_str("```\n");
+                        writer.in_pre = true;
+                    }
+                    "code" if !writer.in_pre => writer.push_inline("`"),
+                    "strong" | "b" => writer.push_inline("**"),
+                    "em" | "i" => writer.push_inline("*"),
+                    "a" => {
+                        let href = attribute(attributes, "href")
+                            .and_then(|href| resolve(base, href.trim()));
+                        if href.is_some() {
+                            writer.push_inline("[");
+                        }
+                        writer.links.push(href);
+                    }
+                    "img" => {
+                        let alt = attribute(attributes, "alt").unwrap_or_default();
+                        let src =
+                            attribute(attributes, "src").and_then(|src| resolve(base, src.trim()));
+                        if let (false, Some(src)) = (alt.trim().is_empty(), src) {
+                            writer.push_inline(&format!("![{}]({})", alt.trim(), src));
+                        }
+                    }
+                    "td" | "th" => writer.push_inline("| "),
+                    _ if BLOCK_ELEMENTS.contains(&name) => writer.block_break(),
+                    _ => {}
+                }
+            }
+            Token::End(name) => match name.as_str() {
+                "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote" => writer.block_break(),
+                "ul" | "ol" => {
+                    writer.lists.pop();
+                    if writer.lists.is_empty() {
+                        writer.block_break();
+                    } else {
+                        writer.line_break();
+                    }
+                }
+                "pre" => {
+                    writer.in_pre = false;
+                    writer.line_break();
+                    writer.output.push_str("```");
+                    writer.block_break();
+                }
+                "code" if !writer.in_pre => writer.output.push('`'),
+                "strong" | "b" => writer.output.push_str("**"),
+                "em" | "i" => writer.output.push('*'),
+                "a" => {
+                    if let Some(Some(href)) = writer.links.pop() {
+                        writer.output.push_str(&format!("]({})", href));
+                    }
+                }
+                "td" | "th" => writer.pending_space = true,
+                name if BLOCK_ELEMENTS.contains(&name) => writer.block_break(),
+                _ => {}
+            },
+        }
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_to_markdown_keeps_main_content() {
+        let html = indoc! {r#"
+            <!DOCTYPE html>
+            <html><head><title>Release &amp; notes</title>
+            <script>var x = "<p>not content</p>";</script></head>
+            <body>
+              <nav><a href="/">Home</a> <a href="/docs">Docs</a></nav>
+              <article>
+                <h1>Version 2.0</h1>
+                <p>This release adds <strong>streaming</strong> and
+                   <a href="/changes#api">API changes</a>.</p>
+                <ul><li>Faster builds</li><li>Fewer <em>bugs</em></li></ul>
+                <pre><code>cargo install goose
+            goose --version</code></pre>
+                <!-- <p>hidden</p> -->
+              </article>
+              <footer>Copyright</footer>
+            </body></html>
+        "#};
+        let base = Url::parse("https://example.com/blog/post").unwrap();
+
+        assert_eq!(title(html), Some("Release & notes".to_string()));
+        assert_eq!(
+            to_markdown(html, Some(&base)),
+            indoc! {"
+                # Version 2.0
+
+                This release adds **streaming** and [API changes](https://example.com/changes#api).
+
+                - Faster builds
+                - Fewer *bugs*
+
+                ```
+                cargo install goose
+                goose --version
+                ```"}
+        );
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("a &lt;b&gt; &#169; &#x41; &bogus; &"),
+            "a <b> © A &bogus; &"
+        );
+    }
+}
@@ -0,0 +1,539 @@
+//! Fetching web pages for the model to read.
+//!
+//! Pages are fetched over HTTP(S) and HTML is reduced to its main content as Markdown, so basic
+//! web access works without a separate MCP server. Which sites can be fetched is up to the
+//! user: GOOSE_FETCH_ALLOWED_DOMAINS limits fetches to those domains and their subdomains,
+//! GOOSE_FETCH_BLOCKED_DOMAINS rules domains out, and robots.txt is respected unless
+//! GOOSE_FETCH_RESPECT_ROBOTS is turned off. Responses are cut off at GOOSE_FETCH_MAX_BYTES.
+//!
+//! Addresses on the machine itself or on a private network, which could reach services that
+//! aren't meant to be public, can't be fetched unless GOOSE_FETCH_ALLOW_PRIVATE_ADDRESSES is
+//! turned on. Host names are checked by the addresses they resolve to, for the first request as
+//! well as every redirect, so a public name can't be used to reach a private address.
+
+pub mod html;
+pub mod robots;
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::CONTENT_TYPE;
+use tokio::sync::Mutex;
+use url::{Host, Url};
+
+use crate::config::Config;
+use robots::{RobotsRules, ROBOTS_USER_AGENT};
+
+pub const FETCH_ALLOWED_DOMAINS_CONFIG_KEY: &str = "GOOSE_FETCH_ALLOWED_DOMAINS";
+pub const FETCH_BLOCKED_DOMAINS_CONFIG_KEY: &str = "GOOSE_FETCH_BLOCKED_DOMAINS";
+pub const FETCH_RESPECT_ROBOTS_CONFIG_KEY: &str = "GOOSE_FETCH_RESPECT_ROBOTS";
+pub const FETCH_MAX_BYTES_CONFIG_KEY: &str = "GOOSE_FETCH_MAX_BYTES";
+pub const FETCH_ALLOW_PRIVATE_ADDRESSES_CONFIG_KEY: &str = "GOOSE_FETCH_ALLOW_PRIVATE_ADDRESSES";
+pub const DEFAULT_FETCH_MAX_BYTES: usize = 5 * 1024 * 1024;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_REDIRECTS: usize = 10;
+
+/// Which URLs may be fetched, and how much of them
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchPolicy {
+    /// Domains that may be fetched, along with their subdomains; any if empty
+    pub allowed_domains: Vec<String>,
+    /// Domains that may not be fetched, along with their subdomains
+    pub blocked_domains: Vec<String>,
+    pub respect_robots: bool,
+    pub max_bytes: usize,
+    /// Whether loopback, private, link-local and unique local addresses may be fetched
+    pub allow_private_addresses: bool,
+}
+
+impl Default for FetchPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_domains: Vec::new(),
+            blocked_domains: Vec::new(),
+            respect_robots: true,
+            max_bytes: DEFAULT_FETCH_MAX_BYTES,
+            allow_private_addresses: false,
+        }
+    }
+}
+
+/// Whether `ip` is on the machine itself or on a private network
+fn is_private_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                // Shared address space, used by carrier-grade NAT
+                || (ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_private_address(IpAddr::V4(ip)),
+            None => {
+                ip.is_loopback()
+                    || ip.is_unspecified()
+                    // Unique local, fc00::/7
+                    || ip.segments()[0] & 0xfe00 == 0xfc00
+                    // Link-local, fe80::/10
+                    || ip.segments()[0] & 0xffc0 == 0xfe80
+            }
+        },
+    }
+}
+
+/// Resolves host names for fetches, leaving out private addresses so that neither a request
+/// nor a redirect reaches one by name. Proxies are exempt, as they are the user's choice.
+struct PublicAddressResolver {
+    exempt_hosts: Vec<String>,
+}
+
+impl PublicAddressResolver {
+    fn from_env() -> Self {
+        let exempt_hosts = ["HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY"]
+            .iter()
+            .flat_map(|key| [key.to_string(), key.to_ascii_lowercase()])
+            .filter_map(|key| std::env::var(key).ok())
+            .filter_map(|proxy| {
+                Url::parse(&proxy)
+                    .ok()?
+                    .host_str()
+                    .map(|host| host.to_string())
+            })
+            .collect();
+        Self { exempt_hosts }
+    }
+}
+
+impl Resolve for PublicAddressResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        let is_exempt = self
+            .exempt_hosts
+            .iter()
+            .any(|exempt| exempt.eq_ignore_ascii_case(&host));
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            let allowed: Vec<SocketAddr> = addresses
+                .iter()
+                .copied()
+                .filter(|address| is_exempt || !is_private_address(address.ip()))
+                .collect();
+            if allowed.is_empty() && !addresses.is_empty() {
+                return Err(format!(
+                    "{} resolves to a private address, which the fetch settings don't allow",
+                    host
+                )
+                .into());
+            }
+            Ok(Box::new(allowed.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn in_domain(host: &str, domain: &str) -> bool {
+    let domain = domain.trim().trim_start_matches("*.").trim_end_matches('.');
+    host.eq_ignore_ascii_case(domain)
+        || host
+            .to_ascii_lowercase()
+            .ends_with(&format!(".{}", domain.to_ascii_lowercase()))
+}
+
+impl FetchPolicy {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let default = Self::default();
+        Self {
+            allowed_domains: config
+                .get_param(FETCH_ALLOWED_DOMAINS_CONFIG_KEY)
+                .unwrap_or(default.allowed_domains),
+            blocked_domains: config
+                .get_param(FETCH_BLOCKED_DOMAINS_CONFIG_KEY)
+                .unwrap_or(default.blocked_domains),
+            respect_robots: config
+                .get_param(FETCH_RESPECT_ROBOTS_CONFIG_KEY)
+                .unwrap_or(default.respect_robots),
+            max_bytes: config
+                .get_param(FETCH_MAX_BYTES_CONFIG_KEY)
+                .unwrap_or(default.max_bytes),
+            allow_private_addresses: config
+                .get_param(FETCH_ALLOW_PRIVATE_ADDRESSES_CONFIG_KEY)
+                .unwrap_or(default.allow_private_addresses),
+        }
+    }
+
+    /// Why `url` may not be fetched, if it may not
+    pub fn violation(&self, url: &Url) -> Option<String> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Some(format!(
+                "Only http and https URLs can be fetched, not {}",
+                url
+            ));
+        }
+        let Some(host) = url.host_str() else {
+            return Some(format!("{} has no host", url));
+        };
+        let ip = match url.host() {
+            Some(Host::Ipv4(ip)) => Some(IpAddr::V4(ip)),
+            Some(Host::Ipv6(ip)) => Some(IpAddr::V6(ip)),
+            _ => None,
+        };
+        if let Some(ip) = ip.filter(|ip| !self.allow_private_addresses && is_private_address(*ip)) {
+            return Some(format!(
+                "{} is a private address, which the fetch settings don't allow",
+                ip
+            ));
+        }
+        let host = host.trim_end_matches('.');
+        if let Some(blocked) = self
+            .blocked_domains
+            .iter()
+            .find(|domain| in_domain(host, domain))
+        {
+            return Some(format!("{} is blocked by the fetch settings", blocked));
+        }
+        if !self.allowed_domains.is_empty()
+            && !self
+                .allowed_domains
+                .iter()
+                .any(|domain| in_domain(host, domain))
+        {
+            return Some(format!(
+                "{} isn't in the domains allowed by the fetch settings: {}",
+                host,
+                self.allowed_domains.join(", ")
+            ));
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FetchedPage {
+    /// Where the page was fetched from, after redirects
+    pub url: Url,
+    pub title: Option<String>,
+    /// The page's main content as Markdown if it is HTML and wasn't fetched raw, else its text
+    pub content: String,
+    /// Whether the response was cut off at the policy's size limit
+    pub truncated: bool,
+}
+
+pub struct WebFetcher {
+    client: reqwest::Client,
+    policy: FetchPolicy,
+    /// robots.txt rules by origin, read once per origin
+    robots: Mutex<HashMap<String, RobotsRules>>,
+}
+
+impl WebFetcher {
+    pub fn new(policy: FetchPolicy) -> reqwest::Result<Self> {
+        Self::with_resolver(policy, PublicAddressResolver::from_env())
+    }
+
+    fn with_resolver(
+        policy: FetchPolicy,
+        resolver: PublicAddressResolver,
+    ) -> reqwest::Result<Self> {
+        let redirect_policy = policy.clone();
+        let mut builder = reqwest::Client::builder();
+        if !policy.allow_private_addresses {
+            builder = builder.dns_resolver(Arc::new(resolver));
+        }
+        let client = builder
+            .user_agent(format!(
+                "{}/{} (+https://github.com/block/goose)",
+                ROBOTS_USER_AGENT,
+                env!("CARGO_PKG_VERSION")
+            ))
+            .timeout(FETCH_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() >= MAX_REDIRECTS {
+                    return attempt.error("too many redirects");
+                }
+                match redirect_policy.violation(attempt.url()) {
+                    Some(violation) => attempt.error(violation),
+                    None => attempt.follow(),
+                }
+            }))
+            .build()?;
+        Ok(Self {
+            client,
+            policy,
+            robots: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn from_config() -> reqwest::Result<Self> {
+        Self::new(FetchPolicy::from_config())
+    }
+
+    async fn robots_rules(&self, url: &Url) -> RobotsRules {
+        let origin = url.origin().ascii_serialization();
+        if let Some(rules) = self.robots.lock().await.get(&origin) {
+            return rules.clone();
+        }
+
+        // A missing or unreadable robots.txt puts no limits on fetching
+        let rules = match self
+            .client
+            .get(format!("{}/robots.txt", origin))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+        {
+            Ok(response) => match response.text().await {
+                Ok(robots_txt) => RobotsRules::parse(&robots_txt, ROBOTS_USER_AGENT),
+                Err(_) => RobotsRules::default(),
+            },
+            Err(e) => {
+                tracing::debug!("No robots.txt for {}: {}", origin, e);
+                RobotsRules::default()
+            }
+        };
+        self.robots.lock().await.insert(origin, rules.clone());
+        rules
+    }
+
+    /// Fetch `url`, converting HTML to Markdown unless `raw`
+    pub async fn fetch(&self, url: &str, raw: bool) -> Result<FetchedPage, String> {
+        let url = Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+        if let Some(violation) = self.policy.violation(&url) {
+            return Err(violation);
+        }
+        if self.policy.respect_robots {
+            let path = match url.query() {
+                Some(query) => format!("{}?{}", url.path(), query),
+                None => url.path().to_string(),
+            };
+            if !self.robots_rules(&url).await.is_allowed(&path) {
+                return Err(format!(
+                    "robots.txt of {} doesn't allow fetching {}",
+                    url.origin().ascii_serialization(),
+                    path
+                ));
+            }
+        }
+
+        let mut response = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch {}: {}", url, error_chain(&e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("Fetching {} failed with status {}", url, status));
+        }
+        let final_url = response.url().clone();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("text/html")
+            .to_ascii_lowercase();
+        let is_html = content_type.contains("html");
+        if !is_html
+            && !content_type.starts_with("text/")
+            && !content_type.contains("json")
+            && !content_type.contains("xml")
+        {
+            return Err(format!(
+                "{} is {}, which can't be shown as text",
+                final_url, content_type
+            ));
+        }
+
+        let mut body = Vec::new();
+        let mut truncated = false;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", final_url, e))?
+        {
+            let room = self.policy.max_bytes - body.len();
+            if chunk.len() > room {
+                body.extend_from_slice(&chunk[..room]);
+                truncated = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+        let text = String::from_utf8_lossy(&body);
+
+        let (title, content) = if is_html && !raw {
+            (
+                html::title(&text),
+                html::to_markdown(&text, Some(&final_url)),
+            )
+        } else {
+            (None, text.into_owned())
+        };
+        Ok(FetchedPage {
+            url: final_url,
+            title,
+            content,
+            truncated,
+        })
+    }
+}
+
+/// `error` with the errors that caused it, which say why a connection was refused
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetch_policy_domains() {
+        let policy = FetchPolicy {
+            allowed_domains: vec!["example.com".to_string(), "*.rust-lang.org".to_string()],
+            blocked_domains: vec!["private.example.com".to_string()],
+            ..FetchPolicy::default()
+        };
+        let violation = |url: &str| policy.violation(&Url::parse(url).unwrap());
+
+        assert_eq!(violation("https://example.com/page"), None);
+        assert_eq!(violation("https://docs.Example.com/page"), None);
+        assert_eq!(violation("https://doc.rust-lang.org/std"), None);
+        assert!(violation("https://notexample.com/").is_some());
+        assert!(violation("https://api.private.example.com/").is_some());
+        assert!(violation("file:///etc/passwd").is_some());
+        assert_eq!(
+            FetchPolicy::default().violation(&Url::parse("http://anything.test").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_private_addresses() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(is_private_address(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "93.184.216.34",
+            "100.128.0.1",
+            "2606:4700::1111",
+            "::ffff:8.8.8.8",
+        ] {
+            assert!(!is_private_address(ip.parse().unwrap()), "{}", ip);
+        }
+
+        let policy = FetchPolicy::default();
+        let violation =
+            |policy: &FetchPolicy, url: &str| policy.violation(&Url::parse(url).unwrap());
+        assert!(violation(&policy, "http://127.0.0.1:8080/").is_some());
+        assert!(violation(&policy, "http://169.254.169.254/latest/meta-data").is_some());
+        assert!(violation(&policy, "http://[::1]/").is_some());
+        assert!(violation(&policy, "http://[::ffff:10.0.0.1]/").is_some());
+        assert_eq!(violation(&policy, "http://93.184.216.34/"), None);
+
+        let allowed = FetchPolicy {
+            allow_private_addresses: true,
+            ..FetchPolicy::default()
+        };
+        assert_eq!(violation(&allowed, "http://127.0.0.1:8080/"), None);
+        assert_eq!(violation(&allowed, "http://[::1]/"), None);
+    }
+
+    /// Serves `response` to every connection on a local port
+    async fn serve(response: String) -> u16 {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = [0u8; 4096];
+                let _ = stream.read(&mut request).await;
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+        port
+    }
+
+    fn page(body: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    fn local_policy(allow_private_addresses: bool) -> FetchPolicy {
+        FetchPolicy {
+            respect_robots: false,
+            allow_private_addresses,
+            ..FetchPolicy::default()
+        }
+    }
+
+    fn resolver(exempt_hosts: &[&str]) -> PublicAddressResolver {
+        PublicAddressResolver {
+            exempt_hosts: exempt_hosts.iter().map(|host| host.to_string()).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_names_resolving_to_private_addresses_are_refused() {
+        let port = serve(page("internal")).await;
+        let url = format!("http://localhost:{}/", port);
+
+        let fetcher = WebFetcher::with_resolver(local_policy(false), resolver(&[])).unwrap();
+        let error = fetcher.fetch(&url, true).await.unwrap_err();
+        assert!(error.contains("private address"), "{}", error);
+
+        let fetcher = WebFetcher::with_resolver(local_policy(true), resolver(&[])).unwrap();
+        let page = fetcher.fetch(&url, true).await.unwrap();
+        assert_eq!(page.content, "internal");
+    }
+
+    #[tokio::test]
+    async fn test_redirects_to_private_addresses_are_refused() {
+        let target = serve(page("internal")).await;
+        let port = serve(format!(
+            "HTTP/1.1 302 Found\r\nLocation: http://127.0.0.1:{}/secret\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            target
+        ))
+        .await;
+        let url = format!("http://localhost:{}/", port);
+
+        let fetcher =
+            WebFetcher::with_resolver(local_policy(false), resolver(&["localhost"])).unwrap();
+        let error = fetcher.fetch(&url, true).await.unwrap_err();
+        assert!(error.contains("private address"), "{}", error);
+
+        let fetcher = WebFetcher::with_resolver(local_policy(true), resolver(&[])).unwrap();
+        let page = fetcher.fetch(&url, true).await.unwrap();
+        assert_eq!(page.content, "internal");
+    }
+}
@@ -0,0 +1,139 @@
+//! Reading robots.txt files.
+//!
+//! Rules come from the group naming goose's user agent, else from the `*` group. The rule with
+//! the longest matching path decides, with `Allow` winning ties, and `*` and `$` in paths match
+//! as described in RFC 9309.
+
+/// Product token goose matches robots.txt user agents against
+pub const ROBOTS_USER_AGENT: &str = "goose";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    allow: bool,
+    path: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RobotsRules {
+    rules: Vec<Rule>,
+}
+
+impl RobotsRules {
+    /// The rules of `robots_txt` for `user_agent`
+    pub fn parse(robots_txt: &str, user_agent: &str) -> Self {
+        let user_agent = user_agent.to_ascii_lowercase();
+        let mut specific = Vec::new();
+        let mut general = Vec::new();
+        let mut found_specific = false;
+
+        // User agents of the group being read, and whether its rules have started
+        let mut agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+        for line in robots_txt.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim();
+            match key.as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        agents.clear();
+                        in_rules = false;
+                    }
+                    agents.push(value.to_ascii_lowercase());
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    let for_user_agent = agents.contains(&user_agent);
+                    found_specific |= for_user_agent;
+                    // An empty Disallow allows everything, which is also what no rule does
+                    if value.is_empty() {
+                        continue;
+                    }
+                    let rule = Rule {
+                        allow: key == "allow",
+                        path: value.to_string(),
+                    };
+                    if for_user_agent {
+                        specific.push(rule.clone());
+                    }
+                    if agents.iter().any(|agent| agent == "*") {
+                        general.push(rule);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            rules: if found_specific { specific } else { general },
+        }
+    }
+
+    /// Whether `path`, with its query, may be fetched
+    pub fn is_allowed(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|rule| path_matches(&rule.path, path))
+            .max_by_key(|rule| (rule.path.len(), rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+}
+
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        let is_last = i == parts.len() - 1;
+        if is_last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.split_once(part) {
+            Some((_, after)) => rest = after,
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_robots_rules() {
+        let robots_txt = indoc! {"
+            User-agent: *
+            Disallow: /private/
+            Allow: /private/public-*.html$
+
+            User-agent: goose
+            User-agent: other-bot
+            Disallow: /drafts
+            Disallow:
+        "};
+
+        let general = RobotsRules::parse(robots_txt, "some-bot");
+        assert!(general.is_allowed("/"));
+        assert!(!general.is_allowed("/private/notes.html"));
+        assert!(general.is_allowed("/private/public-page.html"));
+        assert!(!general.is_allowed("/private/public-page.html?print=1"));
+
+        let goose = RobotsRules::parse(robots_txt, ROBOTS_USER_AGENT);
+        assert!(goose.is_allowed("/private/notes.html"));
+        assert!(!goose.is_allowed("/drafts/2024"));
+
+        assert!(RobotsRules::parse("", ROBOTS_USER_AGENT).is_allowed("/anything"));
+    }
+}
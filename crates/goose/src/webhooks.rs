@@ -0,0 +1,216 @@
+//! Telling other systems what runs are doing.
+//!
+//! Webhooks listed under GOOSE_WEBHOOKS get a JSON POST when a scheduled or headless run
+//! starts, finishes or fails, and when a session needs a tool call approved. That way chat
+//! bridges or ticketing systems can follow runs nobody is watching. Each webhook can be limited
+//! to some events. It can also be given a secret to sign payloads with: the X-Goose-Signature
+//! header then holds `sha256=` and the hex HMAC-SHA256 of the body, which receivers should
+//! check before trusting the payload.
+//!
+//! ```yaml
+//! GOOSE_WEBHOOKS:
+//!   - url: https://hooks.example.com/goose
+//!     secret: s3cret
+//!     events: [session_failed, approval_needed]
+//! ```
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+
+use crate::config::Config;
+
+pub const WEBHOOKS_CONFIG_KEY: &str = "GOOSE_WEBHOOKS";
+pub const SIGNATURE_HEADER: &str = "X-Goose-Signature";
+pub const EVENT_HEADER: &str = "X-Goose-Event";
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    SessionStarted,
+    SessionFinished,
+    SessionFailed,
+    /// A tool call is waiting for someone to approve or deny it
+    ApprovalNeeded,
+}
+
+impl WebhookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::SessionStarted => "session_started",
+            WebhookEvent::SessionFinished => "session_finished",
+            WebhookEvent::SessionFailed => "session_failed",
+            WebhookEvent::ApprovalNeeded => "approval_needed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Key to sign payloads with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// Events to send; all of them if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<WebhookEvent>,
+}
+
+impl WebhookConfig {
+    fn wants(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    pub event: WebhookEvent,
+    pub timestamp: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// What else there is to know about the event, such as the error a run failed with
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+}
+
+/// The value of the signature header for `body` signed with `secret`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(body);
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("sha256={}", digest)
+}
+
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    webhooks: Vec<WebhookConfig>,
+}
+
+impl WebhookNotifier {
+    pub fn new(webhooks: Vec<WebhookConfig>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self { client, webhooks }
+    }
+
+    /// The webhooks in the config, if there are any
+    pub fn from_config() -> Option<Self> {
+        let webhooks: Vec<WebhookConfig> = Config::global()
+            .get_param(WEBHOOKS_CONFIG_KEY)
+            .unwrap_or_default();
+        (!webhooks.is_empty()).then(|| Self::new(webhooks))
+    }
+
+    /// Send `payload` to the webhooks that want its event. Failures are logged, not returned,
+    /// as a webhook being down shouldn't stop a run.
+    pub async fn send(&self, payload: &WebhookPayload) {
+        let body = match serde_json::to_vec(payload) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::error!("Failed to serialize a webhook payload: {}", e);
+                return;
+            }
+        };
+
+        for webhook in self
+            .webhooks
+            .iter()
+            .filter(|webhook| webhook.wants(payload.event))
+        {
+            let mut request = self
+                .client
+                .post(&webhook.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, payload.event.as_str())
+                .body(body.clone());
+            if let Some(secret) = &webhook.secret {
+                request = request.header(SIGNATURE_HEADER, sign(secret, &body));
+            }
+            match request.send().await.and_then(|r| r.error_for_status()) {
+                Ok(_) => tracing::debug!("Sent {} to {}", payload.event.as_str(), webhook.url),
+                Err(e) => tracing::warn!(
+                    "Failed to send {} to webhook {}: {}",
+                    payload.event.as_str(),
+                    webhook.url,
+                    e
+                ),
+            }
+        }
+    }
+}
+
+/// Send `event` to the configured webhooks in the background
+pub fn notify(event: WebhookEvent, session_id: Option<&str>, details: Value) {
+    let Some(notifier) = WebhookNotifier::from_config() else {
+        return;
+    };
+    let payload = WebhookPayload {
+        event,
+        timestamp: Utc::now(),
+        session_id: session_id.map(String::from),
+        details,
+    };
+    tokio::spawn(async move { notifier.send(&payload).await });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sign_matches_hmac_sha256() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // RFC 4231, test case 1
+        assert_eq!(
+            sign(&"\x0b".repeat(20), b"Hi There"),
+            "sha256=b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+    }
+
+    #[test]
+    fn test_webhook_config_and_payload() {
+        let webhooks: Vec<WebhookConfig> = serde_yaml::from_str(
+            "- url: https://hooks.example.com/all\n- url: https://hooks.example.com/failures\n  secret: s3cret\n  events: [session_failed]\n",
+        )
+        .unwrap();
+        assert!(webhooks[0].wants(WebhookEvent::ApprovalNeeded));
+        assert!(webhooks[1].wants(WebhookEvent::SessionFailed));
+        assert!(!webhooks[1].wants(WebhookEvent::SessionFinished));
+
+        let payload = WebhookPayload {
+            event: WebhookEvent::SessionFailed,
+            timestamp: DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            session_id: Some("20250101_1".to_string()),
+            details: json!({"error": "provider unavailable"}),
+        };
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            json!({
+                "event": "session_failed",
+                "timestamp": "2025-01-01T00:00:00Z",
+                "session_id": "20250101_1",
+                "details": {"error": "provider unavailable"}
+            })
+        );
+    }
+}
@@ -15,7 +15,7 @@ mod tests {
         use async_trait::async_trait;
         use chrono::{DateTime, Utc};
         use goose::agents::platform_tools::PLATFORM_MANAGE_SCHEDULE_TOOL_NAME;
-        use goose::scheduler::{ScheduledJob, SchedulerError};
+        use goose::scheduler::{ScheduledJob, ScheduledRun, SchedulerError};
         use goose::scheduler_trait::SchedulerTrait;
         use goose::session::Session;
         use std::path::PathBuf;
@@ -110,6 +110,14 @@ mod tests {
             ) -> Result<Option<(String, DateTime<Utc>)>, SchedulerError> {
                 Ok(None)
             }
+
+            async fn run_history(
+                &self,
+                _sched_id: &str,
+                _limit: usize,
+            ) -> Result<Vec<ScheduledRun>, SchedulerError> {
+                Ok(vec![])
+            }
         }
 
         #[tokio::test]
@@ -389,6 +397,14 @@ mod tests {
                 schedule_id: None,
                 max_turns: Some(1),
                 retry_config: None,
+                moderation: None,
+                context_policy: None,
+                budget: None,
+                budget_session_id: None,
+                model_routing: None,
+                lead_worker: None,
+                reflection: None,
+                final_answer_schema: None,
             };
 
             let reply_stream = agent.reply(user_message, session_config, None).await?;
@@ -415,6 +431,11 @@ mod tests {
                     }
                     Ok(AgentEvent::McpNotification(_)) => {}
                     Ok(AgentEvent::ModelChange { .. }) => {}
+                    Ok(AgentEvent::ToolTimeout { .. }) => {}
+                    Ok(AgentEvent::BudgetExceeded(_)) => {}
+                    Ok(AgentEvent::PlanUpdated(_)) => {}
+                    Ok(AgentEvent::FinalAnswer(_)) => {}
+                    Ok(AgentEvent::InterimUsage(_)) => {}
                     Ok(AgentEvent::HistoryReplaced(_updated_conversation)) => {
                         // We should update the conversation here, but we're not reading it
                     }
@@ -444,6 +465,164 @@ mod tests {
         }
     }
 
+    #[cfg(test)]
+    mod cancellation_tests {
+        use super::*;
+        use async_trait::async_trait;
+        use goose::agents::extension::ExtensionConfig;
+        use goose::agents::SessionConfig;
+        use goose::conversation::message::{Message, MessageContent};
+        use goose::model::ModelConfig;
+        use goose::providers::base::{Provider, ProviderMetadata, ProviderUsage, Usage};
+        use goose::providers::errors::ProviderError;
+        use goose::session::session_manager::SessionType;
+        use goose::session::SessionManager;
+        use rmcp::model::{CallToolRequestParam, Tool};
+        use rmcp::object;
+        use std::path::PathBuf;
+        use tokio_util::sync::CancellationToken;
+
+        /// Always asks for the frontend's `pick_file` tool
+        struct FrontendToolProvider;
+
+        #[async_trait]
+        impl Provider for FrontendToolProvider {
+            async fn complete(
+                &self,
+                _system_prompt: &str,
+                _messages: &[Message],
+                _tools: &[Tool],
+            ) -> Result<(Message, ProviderUsage), ProviderError> {
+                let tool_call = CallToolRequestParam {
+                    name: "pick_file".into(),
+                    arguments: Some(object!({})),
+                };
+                let message = Message::assistant()
+                    .with_text("Pick a file")
+                    .with_tool_request("call_pick", Ok(tool_call));
+                let usage = ProviderUsage::new(
+                    "mock-model".to_string(),
+                    Usage::new(Some(10), Some(5), Some(15)),
+                );
+                Ok((message, usage))
+            }
+
+            async fn complete_with_model(
+                &self,
+                _model_config: &ModelConfig,
+                system_prompt: &str,
+                messages: &[Message],
+                tools: &[Tool],
+            ) -> anyhow::Result<(Message, ProviderUsage), ProviderError> {
+                self.complete(system_prompt, messages, tools).await
+            }
+
+            fn get_model_config(&self) -> ModelConfig {
+                ModelConfig::new("mock-model").unwrap()
+            }
+
+            fn metadata() -> ProviderMetadata {
+                ProviderMetadata {
+                    name: "mock".to_string(),
+                    display_name: "Mock Provider".to_string(),
+                    description: "Mock provider for testing".to_string(),
+                    default_model: "mock-model".to_string(),
+                    known_models: vec![],
+                    model_doc_link: "".to_string(),
+                    config_keys: vec![],
+                }
+            }
+
+            fn get_name(&self) -> &str {
+                "mock-frontend-tool"
+            }
+        }
+
+        #[tokio::test]
+        async fn test_cancel_with_tool_request_in_flight() -> Result<()> {
+            let agent = Agent::new();
+            agent
+                .add_extension(ExtensionConfig::Frontend {
+                    name: "frontend".to_string(),
+                    description: "Tools run by the client".to_string(),
+                    tools: vec![Tool::new(
+                        "pick_file",
+                        "Ask the user to pick a file",
+                        object!({"type": "object", "properties": {}}),
+                    )],
+                    instructions: None,
+                    bundled: None,
+                    available_tools: vec![],
+                })
+                .await?;
+
+            let session = SessionManager::create_session(
+                PathBuf::default(),
+                "cancel-in-flight-test".to_string(),
+                SessionType::Hidden,
+            )
+            .await?;
+            agent
+                .update_provider(Arc::new(FrontendToolProvider), &session.id)
+                .await?;
+
+            let session_config = SessionConfig {
+                id: session.id.clone(),
+                schedule_id: None,
+                max_turns: None,
+                retry_config: None,
+                moderation: None,
+                context_policy: None,
+                budget: None,
+                budget_session_id: None,
+                model_routing: None,
+                lead_worker: None,
+                reflection: None,
+                final_answer_schema: None,
+            };
+
+            // The frontend never answers; the user gives up while it waits
+            let cancel_token = CancellationToken::new();
+            let reply_stream = agent
+                .reply(
+                    Message::user().with_text("Open something"),
+                    session_config,
+                    Some(cancel_token.clone()),
+                )
+                .await?;
+            tokio::pin!(reply_stream);
+            while let Some(event) = reply_stream.next().await {
+                if let AgentEvent::Message(message) = event? {
+                    let waiting = message
+                        .content
+                        .iter()
+                        .any(|content| matches!(content, MessageContent::FrontendToolRequest(_)));
+                    if waiting {
+                        cancel_token.cancel();
+                    }
+                }
+            }
+            assert!(cancel_token.is_cancelled());
+
+            let stored = SessionManager::get_session(&session.id, true)
+                .await?
+                .conversation
+                .unwrap_or_default();
+            let requests: Vec<_> = stored
+                .iter()
+                .flat_map(|message| message.get_tool_request_ids())
+                .collect();
+            assert!(
+                requests.is_empty(),
+                "unanswered requests kept: {requests:?}"
+            );
+            assert!(stored
+                .iter()
+                .any(|message| message.as_concat_text() == "Open something"));
+            Ok(())
+        }
+    }
+
     #[cfg(test)]
     mod extension_manager_tests {
         use super::*;
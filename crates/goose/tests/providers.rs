@@ -1,6 +1,7 @@
 use anyhow::Result;
 use dotenvy::dotenv;
 use goose::conversation::message::{Message, MessageContent};
+use goose::model::ModelConfig;
 use goose::providers::anthropic::ANTHROPIC_DEFAULT_MODEL;
 use goose::providers::azure::AZURE_DEFAULT_MODEL;
 use goose::providers::base::Provider;
@@ -12,6 +13,7 @@ use goose::providers::google::GOOGLE_DEFAULT_MODEL;
 use goose::providers::litellm::LITELLM_DEFAULT_MODEL;
 use goose::providers::ollama::OLLAMA_DEFAULT_MODEL;
 use goose::providers::openai::OPEN_AI_DEFAULT_MODEL;
+use goose::providers::recording::{RecordingProvider, ReplayProvider};
 use goose::providers::sagemaker_tgi::SAGEMAKER_TGI_DEFAULT_MODEL;
 use goose::providers::snowflake::SNOWFLAKE_DEFAULT_MODEL;
 use goose::providers::xai::XAI_DEFAULT_MODEL;
@@ -19,6 +21,7 @@ use rmcp::model::{AnnotateAble, Content, RawImageContent};
 use rmcp::model::{CallToolRequestParam, Tool};
 use rmcp::object;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 
@@ -360,6 +363,59 @@ impl ProviderTester {
     }
 }
 
+/// How provider tests talk to providers, set with GOOSE_PROVIDER_TEST_MODE
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RecordingMode {
+    /// Call providers directly
+    Live,
+    /// Call providers and write each interaction under `tests/provider_recordings`
+    Record,
+    /// Serve responses from `tests/provider_recordings` without credentials
+    Replay,
+}
+
+fn recording_mode() -> RecordingMode {
+    match std::env::var("GOOSE_PROVIDER_TEST_MODE").as_deref() {
+        Ok("record") => RecordingMode::Record,
+        Ok("replay") => RecordingMode::Replay,
+        _ => RecordingMode::Live,
+    }
+}
+
+fn recording_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("provider_recordings")
+        .join(format!("{}.json", name.to_lowercase()))
+}
+
+async fn run_replay(name: &str, model_name: &str) -> Result<()> {
+    let path = recording_path(name);
+    if !path.exists() {
+        println!("Skipping {} tests - no recording at {:?}", name, path);
+        TEST_REPORT.record_skip(name);
+        return Ok(());
+    }
+
+    let provider = ReplayProvider::new(&path, ModelConfig::new_or_fail(model_name))?;
+    run_tester(Arc::new(provider), name).await
+}
+
+async fn run_tester(provider: Arc<dyn Provider>, name: &str) -> Result<()> {
+    let tester = ProviderTester::new(provider, name.to_string());
+    match tester.run_test_suite().await {
+        Ok(_) => {
+            TEST_REPORT.record_pass(name);
+            Ok(())
+        }
+        Err(e) => {
+            println!("{} test failed: {}", name, e);
+            TEST_REPORT.record_fail(name);
+            Err(e)
+        }
+    }
+}
+
 fn load_env() {
     if let Ok(path) = dotenv() {
         println!("Loaded environment from {:?}", path);
@@ -374,6 +430,11 @@ async fn test_provider(
 ) -> Result<()> {
     TEST_REPORT.record_fail(name);
 
+    let mode = recording_mode();
+    if mode == RecordingMode::Replay {
+        return run_replay(name, model_name).await;
+    }
+
     let original_env = {
         let _lock = ENV_LOCK.lock().unwrap();
 
@@ -435,18 +496,12 @@ async fn test_provider(
         }
     }
 
-    let tester = ProviderTester::new(provider, name.to_string());
-    match tester.run_test_suite().await {
-        Ok(_) => {
-            TEST_REPORT.record_pass(name);
-            Ok(())
-        }
-        Err(e) => {
-            println!("{} test failed: {}", name, e);
-            TEST_REPORT.record_fail(name);
-            Err(e)
-        }
-    }
+    let provider: Arc<dyn Provider> = if mode == RecordingMode::Record {
+        Arc::new(RecordingProvider::new(provider, recording_path(name)))
+    } else {
+        provider
+    };
+    run_tester(provider, name).await
 }
 
 #[tokio::test]